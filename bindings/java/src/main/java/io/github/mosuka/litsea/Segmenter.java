@@ -0,0 +1,73 @@
+package io.github.mosuka.litsea;
+
+/**
+ * Word segmenter backed by the native litsea library.
+ *
+ * <p>The native library ({@code liblitsea.so}, {@code liblitsea.dylib} or
+ * {@code litsea.dll}) must be on {@code java.library.path}. A segmenter can be shared
+ * between threads for {@link #segment} and {@link #offsets}, but must not be used
+ * after {@link #close}.
+ */
+public final class Segmenter implements AutoCloseable {
+    static {
+        System.loadLibrary("litsea");
+    }
+
+    private long handle;
+
+    /**
+     * Loads a model file.
+     *
+     * @param language  the language of the model: {@code japanese}, {@code chinese} or {@code korean}
+     * @param modelPath the path of the model file
+     * @throws IllegalArgumentException if the language is unknown or the model cannot be loaded
+     */
+    public Segmenter(String language, String modelPath) {
+        this.handle = open(language, modelPath);
+    }
+
+    /**
+     * Segments a text into words.
+     *
+     * @param text the text to segment
+     * @return the words, which concatenate back to {@code text}
+     */
+    public String[] segment(String text) {
+        return segment(checkOpen(), text);
+    }
+
+    /**
+     * Segments a text and returns the offsets of each word in {@code text}.
+     *
+     * @param text the text to segment
+     * @return {@code [start0, end0, start1, end1, ...]} as indices into {@code text},
+     *         suitable for {@code OffsetAttribute} in Lucene tokenizers
+     */
+    public int[] offsets(String text) {
+        return offsets(checkOpen(), text);
+    }
+
+    /** Releases the native segmenter. Calling this more than once has no effect. */
+    @Override
+    public synchronized void close() {
+        if (handle != 0) {
+            free(handle);
+            handle = 0;
+        }
+    }
+
+    private long checkOpen() {
+        if (handle == 0) {
+            throw new IllegalStateException("Segmenter is closed");
+        }
+        return handle;
+    }
+
+    private static native long open(String language, String modelPath);
+
+    private static native String[] segment(long handle, String text);
+
+    private static native int[] offsets(long handle, String text);
+
+    private static native void free(long handle);
+}
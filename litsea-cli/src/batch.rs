@@ -0,0 +1,129 @@
+//! Segmentation of whole files and directory trees for `litsea segment --input` and
+//! `litsea segment-file`.
+//!
+//! A directory is walked recursively and every file under it is segmented into a file
+//! of the same relative path under the output directory, so a corpus keeps its layout.
+//! Compressed files are read and written as their names tell, see
+//! [`litsea::compression`]. A progress bar on standard error counts the bytes read
+//! from disk across all files, unless `--quiet` or `--verbose` is given.
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use litsea::compression;
+
+use crate::logging;
+
+/// A file to segment and the file to write its segmentation to.
+#[derive(Debug)]
+pub struct Job {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    /// The size of the input file on disk.
+    pub len: u64,
+}
+
+/// Lists the files to segment.
+///
+/// # Arguments
+/// * `input` - A file, or a directory whose files are all segmented.
+/// * `output` - The output file for a file, or the output directory for a directory.
+///   The directory is created if needed, and is skipped if it is under `input`.
+///
+/// # Returns
+/// The files in the order of their paths.
+///
+/// # Errors
+/// Returns an error if `input` cannot be read, or it is a directory and `output` is an
+/// existing file.
+pub fn plan(input: &Path, output: &Path) -> io::Result<Vec<Job>> {
+    let metadata = fs::metadata(input)?;
+    if !metadata.is_dir() {
+        return Ok(vec![Job {
+            input: input.to_path_buf(),
+            output: output.to_path_buf(),
+            len: metadata.len(),
+        }]);
+    }
+    if output.is_file() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: the output of a directory must be a directory", output.display()),
+        ));
+    }
+    fs::create_dir_all(output)?;
+    let skip = fs::canonicalize(output)?;
+    let mut jobs = Vec::new();
+    walk(input, output, &skip, &mut jobs)?;
+    jobs.sort_by(|a, b| a.input.cmp(&b.input));
+    Ok(jobs)
+}
+
+/// Adds the files under `dir` to `jobs`, with outputs at the same relative paths under
+/// `output`.
+fn walk(dir: &Path, output: &Path, skip: &Path, jobs: &mut Vec<Job>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = fs::metadata(&path)?;
+        if metadata.is_dir() {
+            if fs::canonicalize(&path)? != skip {
+                walk(&path, &output.join(entry.file_name()), skip, jobs)?;
+            }
+        } else if metadata.is_file() {
+            jobs.push(Job {
+                input: path,
+                output: output.join(entry.file_name()),
+                len: metadata.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Segments files one after another while showing their progress.
+///
+/// # Arguments
+/// * `jobs` - The files returned by [`plan`].
+/// * `segment` - Segments the decompressed content of an input file into an output
+///   writer, which is compressed and finished afterwards.
+///
+/// # Errors
+/// Returns the first error of reading, segmenting or writing a file, with its path.
+pub fn run<F>(jobs: &[Job], mut segment: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(Box<dyn BufRead>, &mut dyn Write) -> Result<(), Box<dyn Error>>,
+{
+    let progress = logging::progress_bar(jobs.iter().map(|job| job.len).sum());
+    progress.set_style(ProgressStyle::with_template(
+        "{bar:40} {bytes}/{total_bytes} ({eta}) {wide_msg}",
+    )?);
+    for job in jobs {
+        progress.set_message(job.input.display().to_string());
+        if let Err(e) = run_job(job, &progress, &mut segment) {
+            progress.abandon();
+            return Err(format!("{}: {}", job.input.display(), e).into());
+        }
+    }
+    progress.finish_and_clear();
+    Ok(())
+}
+
+/// Segments one file, counting the bytes read in the progress bar.
+fn run_job<F>(job: &Job, progress: &ProgressBar, segment: &mut F) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(Box<dyn BufRead>, &mut dyn Write) -> Result<(), Box<dyn Error>>,
+{
+    let reader = compression::decompress(progress.wrap_read(File::open(&job.input)?), &job.input)?;
+    if let Some(parent) = job.output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = compression::create(&job.output)?;
+    segment(reader, &mut writer)?;
+    writer.finish()?;
+    Ok(())
+}
@@ -0,0 +1,248 @@
+//! Editor integration protocol over standard input and output.
+//!
+//! Editor plugins start `litsea editor` as a child process and exchange newline-delimited
+//! JSON-RPC 2.0 messages with it, in the style of a language server. The `segment`
+//! method returns every word of a text with its offsets and the confidence of the
+//! boundary before it, so a plugin can highlight word boundaries, and flag uncertain
+//! ones, while a user writes or corrects an annotated corpus.
+//!
+//! Offsets are counted in UTF-16 code units by default, as in the Language Server
+//! Protocol; `initialize` can switch them to bytes (`utf-8`) or characters (`utf-32`).
+
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use litsea::segmenter::Segmenter;
+
+use crate::rpc::{self, INVALID_PARAMS, METHOD_NOT_FOUND, Request, Response, RpcError};
+
+/// The unit offsets are counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+enum PositionEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[default]
+    #[serde(rename = "utf-16")]
+    Utf16,
+    #[serde(rename = "utf-32")]
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Returns the length of a string in this unit.
+    fn len(self, s: &str) -> usize {
+        match self {
+            PositionEncoding::Utf8 => s.len(),
+            PositionEncoding::Utf16 => s.encode_utf16().count(),
+            PositionEncoding::Utf32 => s.chars().count(),
+        }
+    }
+}
+
+/// Parameters of the `initialize` method.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InitializeParams {
+    position_encoding: Option<PositionEncoding>,
+}
+
+/// Parameters of the `segment` method.
+#[derive(Debug, Deserialize)]
+struct SegmentParams {
+    text: String,
+}
+
+/// A word in the result of the `segment` method.
+#[derive(Debug, Serialize)]
+struct Token {
+    surface: String,
+    start: usize,
+    end: usize,
+    /// Score of the boundary before the word; `null` at the start of a line.
+    score: Option<f64>,
+    confidence: f64,
+}
+
+/// An editor session.
+pub struct Session {
+    segmenter: Segmenter,
+    position_encoding: PositionEncoding,
+    exited: bool,
+}
+
+impl Session {
+    /// Creates a session serving a segmenter.
+    pub fn new(segmenter: Segmenter) -> Self {
+        Session {
+            segmenter,
+            position_encoding: PositionEncoding::default(),
+            exited: false,
+        }
+    }
+
+    /// Reads requests until the input ends or the editor calls `exit`.
+    ///
+    /// # Errors
+    /// Returns an error if reading or writing fails.
+    pub fn run<R: BufRead, W: Write>(&mut self, reader: R, mut writer: W) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let response = match rpc::parse_request(line.trim()) {
+                Err(response) => Some(response),
+                Ok(request) => self.handle_request(request),
+            };
+            if let Some(response) = response {
+                // Serializing our own response types cannot fail.
+                writeln!(writer, "{}", serde_json::to_string(&response).unwrap_or_default())?;
+                writer.flush()?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Option<Response> {
+        let outcome = self.dispatch(&request.method, request.params);
+        request.id.map(|id| Response::new(id, outcome))
+    }
+
+    fn dispatch(&mut self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => {
+                let params: InitializeParams = if params.is_null() {
+                    InitializeParams::default()
+                } else {
+                    parse_params(params)?
+                };
+                if let Some(encoding) = params.position_encoding {
+                    self.position_encoding = encoding;
+                }
+                Ok(json!({
+                    "name": "litsea",
+                    "version": litsea::version(),
+                    "language": self.segmenter.language.to_string(),
+                    "positionEncoding": self.position_encoding,
+                }))
+            }
+            "segment" => {
+                let params: SegmentParams = parse_params(params)?;
+                Ok(json!({ "tokens": self.segment(&params.text) }))
+            }
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exited = true;
+                Ok(Value::Null)
+            }
+            other => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        }
+    }
+
+    /// Segments a text line by line, with offsets into the whole text.
+    fn segment(&self, text: &str) -> Vec<Token> {
+        let encoding = self.position_encoding;
+        let mut tokens = Vec::new();
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            let mut pos = line_start;
+            for word in self.segmenter.segment_with_scores(line.trim_end_matches(['\n', '\r'])) {
+                let len = encoding.len(&word.surface);
+                tokens.push(Token {
+                    start: pos,
+                    end: pos + len,
+                    score: word.score,
+                    confidence: word.confidence(),
+                    surface: word.surface,
+                });
+                pos += len;
+            }
+            line_start += encoding.len(line);
+        }
+        tokens
+    }
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use litsea::adaboost::AdaBoost;
+    use litsea::language::Language;
+
+    /// Returns a session whose model only splits before and after "は".
+    fn session() -> Session {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str("UW4:は\t2\nUW3:は\t2\n-1\n").unwrap();
+        Session::new(Segmenter::new(Language::Japanese, Some(learner)))
+    }
+
+    /// Returns the surface and range of every token of `text`.
+    fn ranges(session: &mut Session, encoding: &str, text: &str) -> Vec<(String, usize, usize)> {
+        let params = json!({ "positionEncoding": encoding });
+        let result = session.dispatch("initialize", params).unwrap();
+        assert_eq!(result["positionEncoding"], encoding);
+        let result = session.dispatch("segment", json!({ "text": text })).unwrap();
+        result["tokens"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|token| {
+                let surface = token["surface"].as_str().unwrap().to_string();
+                let start = token["start"].as_u64().unwrap() as usize;
+                let end = token["end"].as_u64().unwrap() as usize;
+                (surface, start, end)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_segment_position_encodings() {
+        // "𠮷" is outside the BMP: 2 UTF-16 code units and 4 bytes.
+        let text = "これは\n𠮷野はい";
+        let mut session = session();
+        let expected = |ranges: [(usize, usize); 5]| {
+            ["これ", "は", "𠮷野", "は", "い"]
+                .iter()
+                .zip(ranges)
+                .map(|(surface, (start, end))| (surface.to_string(), start, end))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            ranges(&mut session, "utf-16", text),
+            expected([(0, 2), (2, 3), (4, 7), (7, 8), (8, 9)])
+        );
+        assert_eq!(
+            ranges(&mut session, "utf-8", text),
+            expected([(0, 6), (6, 9), (10, 17), (17, 20), (20, 23)])
+        );
+        assert_eq!(
+            ranges(&mut session, "utf-32", text),
+            expected([(0, 2), (2, 3), (4, 6), (6, 7), (7, 8)])
+        );
+
+        // The byte ranges slice the text.
+        for (surface, start, end) in ranges(&mut session, "utf-8", text) {
+            assert_eq!(&text[start..end], surface);
+        }
+    }
+
+    #[test]
+    fn test_default_position_encoding() {
+        let mut session = session();
+        let result = session.dispatch("initialize", Value::Null).unwrap();
+        assert_eq!(result["positionEncoding"], "utf-16");
+        let err = session.dispatch("initialize", json!({ "positionEncoding": "utf-7" }));
+        assert!(err.is_err());
+    }
+}
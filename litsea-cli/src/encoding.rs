@@ -0,0 +1,193 @@
+//! Character encodings for text read from standard input and written to standard output.
+//!
+//! Input is decoded to UTF-8 before segmentation and output is encoded back, so the
+//! segmenter always works on the original characters. For encodings other than UTF-8,
+//! a byte order mark at the start of the input takes precedence over the selected
+//! encoding, as it does in browsers. Characters that Shift_JIS or EUC-JP cannot
+//! represent, such as the U+FFFD that replaces malformed input, are written as HTML
+//! numeric character references (`&#NNNN;`) rather than failing the whole output.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::str::FromStr;
+
+use encoding_rs::{EUC_JP, Encoding, SHIFT_JIS, UTF_16LE};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+/// A character encoding supported by the CLI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    ShiftJis,
+    EucJp,
+    /// UTF-16, little-endian unless the input starts with a big-endian byte order mark.
+    Utf16,
+}
+
+impl FromStr for TextEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf8" | "utf-8" => Ok(TextEncoding::Utf8),
+            "sjis" | "shift_jis" | "shift-jis" | "cp932" => Ok(TextEncoding::ShiftJis),
+            "eucjp" | "euc-jp" => Ok(TextEncoding::EucJp),
+            "utf16" | "utf-16" | "utf16le" | "utf-16le" => Ok(TextEncoding::Utf16),
+            _ => Err(format!("Unsupported encoding: '{}'. Supported: utf8, sjis, eucjp, utf16", s)),
+        }
+    }
+}
+
+impl TextEncoding {
+    fn encoding(self) -> Option<&'static Encoding> {
+        match self {
+            TextEncoding::Utf8 => None,
+            TextEncoding::ShiftJis => Some(SHIFT_JIS),
+            TextEncoding::EucJp => Some(EUC_JP),
+            TextEncoding::Utf16 => Some(UTF_16LE),
+        }
+    }
+
+    /// Wraps a reader so that it yields UTF-8 text.
+    /// UTF-8 input is passed through unchanged, reading from the reader's own buffer;
+    /// input in other encodings is decoded, with malformed sequences replaced by U+FFFD.
+    pub fn decode<'a, R: BufRead + 'a>(self, reader: R) -> Box<dyn BufRead + 'a> {
+        let Some(encoding) = self.encoding() else {
+            return Box::new(reader);
+        };
+        Box::new(BufReader::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(Some(encoding))
+                .bom_override(true)
+                .strip_bom(true)
+                .build(reader),
+        ))
+    }
+
+    /// Wraps a writer so that UTF-8 text written to it is encoded in this encoding.
+    pub fn encoder<W: Write>(self, writer: W) -> Encoder<W> {
+        Encoder {
+            encoding: self,
+            writer,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Writes a string in this encoding.
+    /// Characters that cannot be represented are written as HTML numeric character
+    /// references, as `encoding_rs` does.
+    fn write<W: Write + ?Sized>(self, writer: &mut W, s: &str) -> io::Result<()> {
+        match self {
+            TextEncoding::Utf8 => writer.write_all(s.as_bytes()),
+            // encoding_rs only decodes UTF-16, so it is encoded here.
+            TextEncoding::Utf16 => {
+                let bytes: Vec<u8> = s.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                writer.write_all(&bytes)
+            }
+            TextEncoding::ShiftJis | TextEncoding::EucJp => {
+                let encoding = self.encoding().unwrap_or(encoding_rs::UTF_8);
+                let (bytes, _, _) = encoding.encode(s);
+                writer.write_all(&bytes)
+            }
+        }
+    }
+}
+
+/// A writer that encodes the UTF-8 text written to it, created by [`TextEncoding::encoder`].
+/// A character split across two writes is held back until it is complete.
+pub struct Encoder<W: Write> {
+    encoding: TextEncoding,
+    writer: W,
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.encoding.write(&mut self.writer, text)?;
+        self.pending.drain(..valid);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Read;
+
+    /// Encodes `chunks`, written one at a time, and returns the output.
+    fn encode(encoding: TextEncoding, chunks: &[&[u8]]) -> io::Result<Vec<u8>> {
+        let mut encoder = encoding.encoder(Vec::new());
+        for chunk in chunks {
+            encoder.write_all(chunk)?;
+        }
+        encoder.flush()?;
+        Ok(encoder.writer)
+    }
+
+    fn decode(encoding: TextEncoding, input: &[u8]) -> String {
+        let mut text = String::new();
+        encoding.decode(input).read_to_string(&mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn test_split_character() {
+        // "あ" is E3 81 82 in UTF-8; the first write ends in the middle of it.
+        let chunks: [&[u8]; 2] = [b"a\xE3\x81", b"\x82b"];
+        assert_eq!(encode(TextEncoding::Utf8, &chunks).unwrap(), "aあb".as_bytes());
+        assert_eq!(encode(TextEncoding::ShiftJis, &chunks).unwrap(), b"a\x82\xA0b");
+        assert_eq!(encode(TextEncoding::Utf16, &chunks).unwrap(), b"a\x00\x42\x30b\x00");
+
+        let err = encode(TextEncoding::ShiftJis, &[b"a\xFFb"]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_utf16() {
+        // Output is little-endian without a byte order mark.
+        assert_eq!(
+            encode(TextEncoding::Utf16, &["これ\n".as_bytes()]).unwrap(),
+            b"\x53\x30\x8C\x30\n\x00"
+        );
+        assert_eq!(decode(TextEncoding::Utf16, b"\x53\x30\x8C\x30"), "これ");
+        // A byte order mark is stripped, and a big-endian one overrides the encoding.
+        assert_eq!(decode(TextEncoding::Utf16, b"\xFF\xFE\x53\x30"), "こ");
+        assert_eq!(decode(TextEncoding::Utf16, b"\xFE\xFF\x30\x53"), "こ");
+        assert_eq!(decode(TextEncoding::ShiftJis, b"\xFF\xFE\x53\x30"), "こ");
+    }
+
+    #[test]
+    fn test_japanese_encodings() {
+        assert_eq!(
+            encode(TextEncoding::ShiftJis, &["これ".as_bytes()]).unwrap(),
+            b"\x82\xB1\x82\xEA"
+        );
+        assert_eq!(encode(TextEncoding::EucJp, &["これ".as_bytes()]).unwrap(), b"\xA4\xB3\xA4\xEC");
+        assert_eq!(decode(TextEncoding::ShiftJis, b"\x82\xB1\x82\xEA"), "これ");
+        assert_eq!(decode(TextEncoding::EucJp, b"\xA4\xB3\xA4\xEC"), "これ");
+    }
+
+    #[test]
+    fn test_unmappable_characters() {
+        // Characters without a Shift_JIS or EUC-JP code become numeric character references.
+        let text = "𠮷\u{FFFD}".as_bytes();
+        assert_eq!(encode(TextEncoding::ShiftJis, &[text]).unwrap(), b"&#134071;&#65533;");
+        assert_eq!(encode(TextEncoding::EucJp, &[text]).unwrap(), b"&#134071;&#65533;");
+        // Malformed input decodes to U+FFFD, which is written back as a reference.
+        let text = decode(TextEncoding::ShiftJis, b"\x82");
+        assert_eq!(text, "\u{FFFD}");
+        assert_eq!(encode(TextEncoding::ShiftJis, &[text.as_bytes()]).unwrap(), b"&#65533;");
+    }
+}
@@ -0,0 +1,137 @@
+//! Verbosity of the `litsea` command.
+//!
+//! Status messages, reports and progress bars go to standard error unless `--quiet` is
+//! given. The library writes nothing itself; its `tracing` events are printed by a
+//! minimal subscriber, warnings by default and more with each `--verbose`.
+
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use indicatif::{ProgressBar, ProgressDrawTarget};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Whether `--quiet` was given.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Whether progress bars are drawn: not with `--quiet`, nor with `--verbose`, whose
+/// events would break them.
+static PROGRESS: AtomicBool = AtomicBool::new(true);
+
+/// Prints a status message to standard error, unless `--quiet` is given.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::logging::is_quiet() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+/// Sets the verbosity and installs the subscriber for the events of the library.
+///
+/// # Arguments
+/// * `quiet` - Print only errors.
+/// * `verbose` - The number of `--verbose` flags: 0 prints warnings, 1 informational
+///   events such as the end of training, 2 every boosting iteration, 3 everything.
+///
+/// # Errors
+/// Returns an error if a subscriber is already installed.
+pub fn init(quiet: bool, verbose: u8) -> Result<(), tracing::subscriber::SetGlobalDefaultError> {
+    QUIET.store(quiet, Ordering::Relaxed);
+    PROGRESS.store(!quiet && verbose == 0, Ordering::Relaxed);
+    let max_level = match (quiet, verbose) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    };
+    tracing::subscriber::set_global_default(StderrSubscriber {
+        max_level,
+        next_id: AtomicU64::new(1),
+    })
+}
+
+/// Returns whether `--quiet` was given.
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Returns standard error for reports, or a sink with `--quiet`.
+pub fn stderr() -> Box<dyn Write> {
+    if is_quiet() { Box::new(io::sink()) } else { Box::new(io::stderr()) }
+}
+
+/// Returns a progress bar of `len` steps on standard error, hidden with `--quiet` or
+/// `--verbose`.
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if PROGRESS.load(Ordering::Relaxed) {
+        ProgressBar::new(len)
+    } else {
+        ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::hidden())
+    }
+}
+
+/// Prints events of up to a level to standard error, one line each. Spans are not
+/// tracked.
+struct StderrSubscriber {
+    max_level: Level,
+    next_id: AtomicU64,
+}
+
+impl Subscriber for StderrSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.max_level
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::from_level(self.max_level))
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut line = EventLine::default();
+        event.record(&mut line);
+        let metadata = event.metadata();
+        eprintln!("{} {}: {}{}", metadata.level(), metadata.target(), line.message, line.fields);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// The message of an event and its other fields as ` name=value` pairs.
+#[derive(Default)]
+struct EventLine {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventLine {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
@@ -1,18 +1,64 @@
 use std::error::Error;
-use std::io::{self, BufRead, Write};
-use std::path::PathBuf;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::io::{self, BufRead, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use clap::{Args, Parser, Subcommand};
+use indicatif::ProgressStyle;
 
-use litsea::adaboost::AdaBoost;
-use litsea::extractor::Extractor;
-use litsea::language::Language;
-use litsea::segmenter::Segmenter;
-use litsea::trainer::Trainer;
+use litsea::adaboost::{AdaBoost, Algorithm, Boosting};
+use litsea::analysis;
+use litsea::bench;
+use litsea::cli::{
+    self, ExtractOptions, FeatureFormat, OutputFormat, SegmentOptions, TrainOptions,
+};
+use litsea::compare;
+use litsea::compound::CompoundSplitter;
+use litsea::compression;
+use litsea::control::TrainControl;
+use litsea::corpus::{self, CorpusFormat};
+use litsea::delta;
+use litsea::evaluation;
+use litsea::feature_file::{self, SortOptions};
+use litsea::inspect;
+use litsea::integrity;
+use litsea::kytea;
+use litsea::language::{Language, LatinClasses};
+use litsea::lattice::{LatticeSegmenter, Lexicon};
+use litsea::lemma::LemmaDictionary;
+use litsea::model_card::{self, ModelCard, ModelSummary};
+use litsea::model_info;
+use litsea::model_store::ModelStore;
+use litsea::onnx;
+use litsea::pipeline::PipelineConfig;
+use litsea::progress::{MetricsCsv, ProgressHandler, TrainingProgress};
+use litsea::project::{self, Tasks};
+use litsea::quantize::Quantization;
+use litsea::reading::ReadingDictionary;
+use litsea::registry::{self, Registry};
+use litsea::repair;
+use litsea::script::ScriptRouter;
+use litsea::segmenter::{FeatureTemplates, Segmenter};
+use litsea::self_training::SelfTrainer;
+use litsea::stopwords::StopWords;
+use litsea::tagger::{self, Tagger};
+use litsea::tensorboard::EventWriter;
+use litsea::tinysegmenter::{self, ScriptFlavor, export_js};
+use litsea::unigram::{UnigramModel, UnigramReranker};
+use litsea::vaporetto;
 use litsea::version;
 
+use crate::encoding::TextEncoding;
+
+#[macro_use]
+mod logging;
+
+mod batch;
+mod editor;
+mod encoding;
+mod rpc;
+mod serve;
+
 /// Arguments for the extract command.
 #[derive(Debug, Args)]
 #[command(
@@ -21,13 +67,110 @@ use litsea::version;
     version = version(),
 )]
 struct ExtractArgs {
-    #[arg(short, long, default_value = "japanese")]
-    language: String,
+    /// Language of the corpus (default: japanese, or the language of --config).
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Pipeline configuration (TOML, or JSON with a .json extension) whose language and
+    /// normalization are used for extraction.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Format of the corpus: "plain" (words separated by spaces), "kytea" (words written
+    /// as surface/tag) or "conllu".
+    #[arg(long, default_value = "plain")]
+    corpus_format: String,
+
+    /// Feature templates: litsea or tinysegmenter (default: litsea, or the templates of
+    /// --config).
+    #[arg(long)]
+    feature_templates: Option<String>,
+
+    /// Latin character classes: single, case or case-width (default: single, or the
+    /// classes of --config).
+    #[arg(long)]
+    latin_classes: Option<String>,
+
+    /// Output format: "text" or "parquet".
+    #[arg(short, long, default_value = "text")]
+    format: String,
+
+    /// Feature dictionary for the parquet format (default: FEATURES_FILE with a .dict.parquet extension).
+    #[arg(long)]
+    dictionary_file: Option<PathBuf>,
+
+    /// Maximum number of threads to extract text features on (default: one per available
+    /// core).
+    #[arg(short = 'j', long, default_value = "0", hide_default_value = true)]
+    num_threads: usize,
 
     corpus_file: PathBuf,
     features_file: PathBuf,
 }
 
+/// Arguments for the shrink-features command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Remove rare features from an extracted features file",
+    version = version(),
+)]
+struct ShrinkFeaturesArgs {
+    /// Drop features that occur in fewer than this many instances.
+    #[arg(long)]
+    min_count: u64,
+
+    /// Format of the features: "text" or "parquet".
+    #[arg(short, long, default_value = "text")]
+    format: String,
+
+    /// Feature dictionary of the parquet input (default: FEATURES_FILE with a
+    /// .dict.parquet extension).
+    #[arg(long)]
+    dictionary_file: Option<PathBuf>,
+
+    /// Reduced feature dictionary for the parquet output (default: OUTPUT_FILE with a
+    /// .dict.parquet extension).
+    #[arg(long)]
+    output_dictionary_file: Option<PathBuf>,
+
+    features_file: PathBuf,
+    output_file: PathBuf,
+}
+
+/// Arguments for the sort-features command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Sort, merge and deduplicate features files with bounded memory",
+    version = version(),
+)]
+struct SortFeaturesArgs {
+    /// Merge identical instances into one line with a count column after the label.
+    #[arg(long)]
+    dedup: bool,
+
+    /// The inputs have a count column, e.g. because they were written with --dedup.
+    #[arg(long)]
+    counted: bool,
+
+    /// Megabytes of instances to sort in memory at once; larger inputs are sorted in
+    /// runs in temporary files.
+    #[arg(long, default_value = "256")]
+    memory_limit: usize,
+
+    /// Directory for the temporary files (default: the system temporary directory).
+    #[arg(long)]
+    temp_dir: Option<PathBuf>,
+
+    /// The sorted features file to write.
+    #[arg(short, long)]
+    output: PathBuf,
+
+    #[arg(required = true)]
+    features_files: Vec<PathBuf>,
+}
+
 /// Arguments for the train command.
 #[derive(Debug, Args)]
 #[command(author,
@@ -41,221 +184,2713 @@ struct TrainArgs {
     #[arg(short = 'i', long, default_value = "100")]
     num_iterations: usize,
 
-    #[arg(short = 'm', long)]
-    load_model_uri: Option<String>,
+    #[arg(short = 'm', long)]
+    load_model_uri: Option<String>,
+
+    /// Weak learners: "discrete" (AdaBoost) or "real" (Real AdaBoost with
+    /// confidence-rated predictions, usually converging in far fewer iterations).
+    #[arg(long, default_value = "discrete")]
+    algorithm: String,
+
+    /// Cap instance weights at this multiple of their average after every round, so that
+    /// mislabeled instances (e.g. in automatically segmented corpora) cannot dominate
+    /// training. At least 1; without it, weights are not capped.
+    #[arg(long)]
+    weight_cap: Option<f64>,
+
+    /// Choose the feature of each iteration from a random sample of about this many
+    /// instances, for faster iterations on large features files.
+    #[arg(long)]
+    batch_size: Option<usize>,
+
+    /// With --batch-size, use all instances every this many iterations.
+    #[arg(long, default_value = "10", requires = "batch_size")]
+    correction_interval: usize,
+
+    /// Maximum number of threads to train on (default: one per available core).
+    #[arg(short = 'j', long, default_value = "0", hide_default_value = true)]
+    num_threads: usize,
+
+    /// Pipeline configuration (TOML, or JSON with a .json extension) to embed in the
+    /// model, so that `litsea segment` uses the same settings.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Development features, extracted from a corpus held out from training, to track
+    /// the gap between the training and development accuracy.
+    #[arg(long)]
+    dev_features: Option<PathBuf>,
+
+    /// Warn of overfitting when the train/dev gap has grown this many times without
+    /// shrinking.
+    #[arg(long, requires = "dev_features")]
+    overfitting_patience: Option<usize>,
+
+    /// Stop training when overfitting is detected.
+    #[arg(long, requires = "overfitting_patience")]
+    stop_on_overfitting: bool,
+
+    /// Stop training when the F1 score on the development features has not improved for
+    /// N iterations, and save the model of the best iteration.
+    #[arg(long, value_name = "N", requires = "dev_features")]
+    patience: Option<usize>,
+
+    /// Show the accuracy on the training features in the progress bar every N
+    /// iterations (0 for never). Each update scores all training instances.
+    #[arg(long, value_name = "N", default_value = "100")]
+    accuracy_interval: usize,
+
+    /// Write the training and development accuracy of every iteration to this file as
+    /// JSON Lines.
+    #[arg(long, requires = "dev_features")]
+    log: Option<PathBuf>,
+
+    /// Write the progress of every iteration to this file as CSV while training: the
+    /// alpha and error rate of its weak learner, the training accuracy every
+    /// --accuracy-interval iterations, and with --dev-features the development accuracy
+    /// and F1 score.
+    #[arg(long, value_name = "FILE")]
+    metrics_out: Option<PathBuf>,
+
+    /// Write the progress of every iteration, as for --metrics-out, to a TensorBoard
+    /// event file in this directory while training.
+    #[arg(long)]
+    tensorboard: Option<PathBuf>,
+
+    /// Write the metrics and, with --dev-features, the development metrics and the
+    /// accuracies of every iteration to this file as JSON.
+    #[arg(long)]
+    metrics_json: Option<PathBuf>,
+
+    /// Keep the training instances in this file, memory-mapped, instead of in memory, to
+    /// train on features files larger than memory. The file is overwritten.
+    #[arg(long)]
+    instance_file: Option<PathBuf>,
+
+    /// The features file has a weight column after the label, such as the count column
+    /// written by `sort-features --dedup`. Each instance counts as much as its weight.
+    #[arg(long, conflicts_with = "instance_file")]
+    weighted: bool,
+
+    /// Before training, estimate the accuracy on unseen data with K-fold
+    /// cross-validation: train on K-1 folds of the features and score the held-out one.
+    #[arg(long, value_name = "K", conflicts_with = "instance_file")]
+    cv: Option<usize>,
+
+    /// After training, drop the features whose weight is smaller than WEIGHT in absolute
+    /// value, to make the model smaller.
+    #[arg(long, value_name = "WEIGHT")]
+    prune_below: Option<f64>,
+
+    /// After training, keep only the N features with the largest weights.
+    #[arg(long, value_name = "N")]
+    max_features: Option<usize>,
+
+    /// Save the weights quantized to "f16" (half-precision floats) or "i8" (integers
+    /// times a shared scale), for a smaller model file.
+    #[arg(long, value_name = "TYPE")]
+    quantize: Option<String>,
+
+    /// Train K models on bootstrap samples of the features, each as many instances drawn
+    /// with replacement, and save the model that averages their scores. Reduces the
+    /// variance of models trained on small corpora.
+    #[arg(long, value_name = "K", conflicts_with_all = ["instance_file", "load_model_uri"])]
+    ensemble: Option<usize>,
+
+    /// Seed of the bootstrap sampling of --ensemble.
+    #[arg(long, default_value = "0", requires = "ensemble")]
+    seed: u64,
+
+    features_file: PathBuf,
+    model_file: PathBuf,
+}
+
+/// Arguments for the self-train command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Grow a model from unlabeled text by repeated segmenting and retraining",
+    version = version(),
+)]
+struct SelfTrainArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Minimum confidence of the least certain boundary of a sentence for it to be
+    /// trained on, between 0.5 and 1.
+    #[arg(short, long, default_value = "0.55")]
+    confidence: f64,
+
+    /// Number of rounds of segmenting and retraining.
+    #[arg(short, long, default_value = "3")]
+    rounds: usize,
+
+    /// Labeled corpus (words separated by spaces) added to the training data of every round.
+    #[arg(long)]
+    labeled: Option<PathBuf>,
+
+    #[arg(short, long, default_value = "0.01")]
+    threshold: f64,
+
+    #[arg(short = 'i', long, default_value = "100")]
+    num_iterations: usize,
+
+    /// Cap instance weights at this multiple of their average after every round, so that
+    /// boundary errors of the selected sentences cannot dominate training. At least 1.
+    #[arg(long)]
+    weight_cap: Option<f64>,
+
+    /// Directory for the corpus, features and model of each round (default: a
+    /// directory next to MODEL_FILE with a .rounds extension).
+    #[arg(long)]
+    work_dir: Option<PathBuf>,
+
+    /// Unlabeled corpus, one sentence per line.
+    unlabeled_file: PathBuf,
+
+    /// Initial model (file path or http/https URL).
+    model_uri: String,
+
+    /// Output model, a copy of the model of the last round.
+    model_file: PathBuf,
+}
+
+/// Arguments for the update-model command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Update a model with corrected sentences without retraining it",
+    version = version(),
+)]
+struct UpdateModelArgs {
+    /// Language of the model (default: the language of its embedded configuration).
+    #[arg(short, long)]
+    language: Option<String>,
+
+    #[arg(short, long, default_value = "0.01")]
+    threshold: f64,
+
+    /// Maximum number of boosting iterations. Updating stops as soon as the model
+    /// segments every sentence of CORPUS_FILE correctly.
+    #[arg(short = 'i', long, default_value = "50")]
+    num_iterations: usize,
+
+    /// Factor of the confidence of every weak learner, greater than 0 and at most 1.
+    /// Smaller rates change the model no more than the corrections need.
+    #[arg(long, default_value = "0.02")]
+    learning_rate: f64,
+
+    /// Weak learners: "discrete" (AdaBoost) or "real" (Real AdaBoost).
+    #[arg(long, default_value = "discrete")]
+    algorithm: String,
+
+    /// Write the updated model here instead of overwriting MODEL_FILE.
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    /// Correctly segmented sentences, one per line with words separated by spaces.
+    corpus_file: PathBuf,
+
+    model_file: PathBuf,
+}
+
+/// Arguments for the route command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Segment mixed-language text with a model per script",
+    version = version(),
+)]
+struct RouteArgs {
+    /// Route each run of one script within a line separately, instead of the whole line
+    /// by its detected script.
+    #[arg(long)]
+    spans: bool,
+
+    /// Character encoding of standard input and output: utf8, sjis, eucjp or utf16.
+    /// Characters that sjis or eucjp cannot represent are written as "&#NNNN;".
+    #[arg(short, long, default_value = "utf8")]
+    encoding: String,
+
+    /// File with one "script language model_uri" or "script passthrough" line per script.
+    model_map: PathBuf,
+}
+
+/// Arguments for the vocab command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Build a word-frequency vocabulary from a segmented corpus",
+    version = version(),
+)]
+struct VocabArgs {
+    /// Drop words that occur fewer than this many times.
+    #[arg(long, default_value = "1")]
+    min_count: u64,
+
+    /// Do not count stopwords (the bundled Japanese list, plus any --stopwords).
+    #[arg(long)]
+    drop_stopwords: bool,
+
+    /// File of additional stopwords, one per line, for --drop-stopwords.
+    #[arg(long, requires = "drop_stopwords")]
+    stopwords: Option<PathBuf>,
+
+    corpus_file: PathBuf,
+    vocab_file: PathBuf,
+}
+
+/// Arguments for the train-tagger command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Train a part-of-speech tagger on a tagged corpus",
+    version = version(),
+)]
+struct TrainTaggerArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    #[arg(short, long, default_value = "0.001")]
+    threshold: f64,
+
+    #[arg(short = 'i', long, default_value = "1000")]
+    num_iterations: usize,
+
+    /// Maximum number of threads to train on (default: one per available core).
+    #[arg(short = 'j', long, default_value = "0", hide_default_value = true)]
+    num_threads: usize,
+
+    /// Tagged corpus, one sentence per line with words written as surface/TAG and
+    /// separated by spaces.
+    corpus_file: PathBuf,
+    model_file: PathBuf,
+}
+
+/// Arguments for the tag command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Segment text and tag the words with a tagger model",
+    version = version(),
+)]
+struct TagArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// The input is already segmented, with words separated by spaces. MODEL_URI is
+    /// not loaded.
+    #[arg(long)]
+    segmented: bool,
+
+    /// The segmentation model (file path or http/https URL).
+    model_uri: String,
+    /// The tagger model trained with `litsea train-tagger`.
+    tagger_model_uri: String,
+}
+
+/// Options shared by the segment and segment-file commands.
+#[derive(Debug, Args)]
+struct SegmentOptionsArgs {
+    /// Language of the model (default: japanese, or the language of the configuration).
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Pipeline configuration (TOML, or JSON with a .json extension). Without it, the
+    /// configuration embedded in the model is used, if any. Command line options take
+    /// precedence over both.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Feature templates of the model: litsea or tinysegmenter, for models converted
+    /// from TinySegmenter (default: the templates of the configuration or those the
+    /// model records, or litsea).
+    #[arg(long)]
+    feature_templates: Option<String>,
+
+    /// Latin character classes of the model: single, case or case-width (default:
+    /// single, or the classes of the configuration).
+    #[arg(long)]
+    latin_classes: Option<String>,
+
+    /// Flush output after every line instead of buffering (for interactive pipelines).
+    #[arg(long)]
+    line_buffered: bool,
+
+    /// Character encoding of the input and output text: utf8, sjis, eucjp or utf16.
+    /// Characters that sjis or eucjp cannot represent are written as "&#NNNN;".
+    #[arg(short, long, default_value = "utf8")]
+    encoding: String,
+
+    /// Output format: plain (words separated by spaces), tagged (surface/reading), mecab
+    /// (surface and reading per line, EOS after each sentence), json or jsonl (sentence,
+    /// words, offsets and boundary scores) or tsv (word, offsets and score per line).
+    /// Default: plain.
+    #[arg(short, long)]
+    format: Option<String>,
+
+    /// TSV dictionary of word readings (surface, reading) for the tagged and mecab formats.
+    /// Without it, only kana are read.
+    #[arg(long)]
+    readings: Option<PathBuf>,
+
+    /// TSV lexicon (surface, lemma, comma-separated tags) whose lemmas and tags are
+    /// attached to matching words and shown in the mecab format.
+    #[arg(long)]
+    lemmas: Option<PathBuf>,
+
+    /// Split long kanji and katakana compounds into their components (for search indexing).
+    #[arg(long)]
+    split_compounds: bool,
+
+    /// Word list (one word per line, or the first column of a TSV) that compounds are
+    /// split into with --split-compounds. Without it, compounds are only split where the
+    /// character type changes.
+    #[arg(long, requires = "split_compounds")]
+    compound_words: Option<PathBuf>,
+
+    /// Drop stopwords (the bundled Japanese list, plus any --stopwords) from the output.
+    #[arg(long)]
+    drop_stopwords: bool,
+
+    /// File of additional stopwords, one per line, for --drop-stopwords.
+    #[arg(long, requires = "drop_stopwords")]
+    stopwords: Option<PathBuf>,
+
+    /// Tokens never split inside, whatever the model says: a comma-separated list of
+    /// digits, urls, emails, emoji and latin, or all (default: none, or the grouping
+    /// rules of the configuration).
+    #[arg(long)]
+    group: Option<String>,
+
+    /// Added to the score of every boundary: positive to split more, negative to split
+    /// less. Default: 0, or the bias of the configuration.
+    #[arg(long, value_name = "BIAS", allow_negative_numbers = true)]
+    bias: Option<f64>,
+
+    /// Vocabulary built by `litsea vocab`, used to rerank the N-best segmentations of
+    /// each line by word frequency.
+    #[arg(long)]
+    vocab: Option<PathBuf>,
+
+    /// Weight of the word log likelihood against the classifier margin for --vocab.
+    /// Default: 0.1.
+    #[arg(long)]
+    vocab_weight: Option<f64>,
+
+    /// Number of segmentations reranked with --vocab. Default: 8.
+    #[arg(long)]
+    beam: Option<usize>,
+
+    /// Word list (one word per line, or the first column of a TSV) or MeCab dictionary (a
+    /// UTF-8 .csv file or a directory of them) whose words are kept whole unless the
+    /// model is confident they should be split, by picking the best path through the
+    /// lattice of the words and the boundary scores.
+    #[arg(long, conflicts_with = "vocab")]
+    lexicon: Option<PathBuf>,
+
+    /// Bonus of --lexicon words for every position inside them, against the boundary
+    /// margins. Default: 0.5.
+    #[arg(long)]
+    lexicon_bonus: Option<f64>,
+
+    /// Never split --lexicon words, matched longest first from the start of the line.
+    #[arg(long)]
+    lexicon_strict: bool,
+
+    /// Write the N best segmentations of each line, each preceded by its rank and total
+    /// boundary margin, instead of the best one.
+    #[arg(long, value_name = "N", conflicts_with_all = ["vocab", "lexicon"])]
+    nbest: Option<usize>,
+
+    /// Load MODEL_URI by name from this model store; the language is taken from the store.
+    #[arg(long)]
+    store: Option<PathBuf>,
+
+    /// Load the model even if it does not match the checksum or signature in its header.
+    #[arg(long)]
+    no_verify: bool,
+
+    /// File with the hex-encoded Ed25519 public key the model must be signed with.
+    #[arg(long, conflicts_with = "no_verify")]
+    public_key: Option<PathBuf>,
+
+    /// Cache up to this many decision scores, keyed by the features of the decision.
+    /// Speeds up text that repeats the same contexts, such as logs.
+    #[arg(long)]
+    score_cache: Option<usize>,
+}
+
+/// Arguments for the segment command.
+#[derive(Debug, Args)]
+#[command(author,
+    about = "Segment a sentence",
+    version = version(),
+)]
+struct SegmentArgs {
+    #[command(flatten)]
+    options: SegmentOptionsArgs,
+
+    /// Read from this file instead of standard input, or segment every file under this
+    /// directory into the --output directory.
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Write to this file instead of standard output, or to this directory for a
+    /// directory --input.
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// The model (file path or http/https URL). May be omitted if the configuration
+    /// names one.
+    #[arg(required_unless_present = "config")]
+    model_uri: Option<String>,
+}
+
+/// Arguments for the segment-file command.
+#[derive(Debug, Args)]
+#[command(author,
+    about = "Segment a file, or every file under a directory",
+    version = version(),
+)]
+struct SegmentFileArgs {
+    #[command(flatten)]
+    options: SegmentOptionsArgs,
+
+    /// The model (file path or http/https URL).
+    model_uri: String,
+
+    /// The file to segment, or a directory whose files are all segmented.
+    input: PathBuf,
+
+    /// The output file, or for a directory the output directory, in which each file is
+    /// written at the same relative path.
+    output: PathBuf,
+}
+
+/// Arguments for the editor command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Speak a JSON-RPC protocol over stdio for editor plugins",
+    version = version(),
+)]
+struct EditorArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    model_uri: String,
+}
+
+/// Arguments for the sign command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Sign a model file with an Ed25519 key",
+    version = version(),
+)]
+struct SignArgs {
+    /// File with the hex-encoded 32-byte Ed25519 secret key.
+    #[arg(short, long)]
+    key_file: PathBuf,
+
+    /// Write the signed model here instead of overwriting MODEL_FILE.
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    model_file: PathBuf,
+}
+
+/// Arguments for the store command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Manage named, versioned models in a SQLite model store",
+    version = version(),
+)]
+struct StoreArgs {
+    store_file: PathBuf,
+
+    #[command(subcommand)]
+    command: StoreCommands,
+}
+
+/// Subcommands of the store command.
+#[derive(Debug, Subcommand)]
+enum StoreCommands {
+    /// Add a model as the next version of NAME.
+    Add {
+        #[arg(short, long, default_value = "japanese")]
+        language: String,
+
+        /// Free-form description stored with the model.
+        #[arg(short, long, default_value = "")]
+        description: String,
+
+        name: String,
+        model_uri: String,
+    },
+    /// List the models in the store.
+    List,
+    /// Write a stored model to a file in Litsea's model format.
+    Get {
+        /// Version to write (default: the latest version).
+        #[arg(long)]
+        version: Option<u32>,
+
+        name: String,
+        output_file: PathBuf,
+    },
+    /// Remove a model from the store.
+    Remove {
+        /// Version to remove (default: all versions).
+        #[arg(long)]
+        version: Option<u32>,
+
+        name: String,
+    },
+}
+
+/// Arguments for the fix-model command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Check a model file and repair duplicate features, missing bias and other problems",
+    version = version(),
+)]
+struct FixModelArgs {
+    /// Only report the problems, without writing the model. Fails if any are found.
+    #[arg(long, conflicts_with = "output_file")]
+    check: bool,
+
+    /// Write the repaired model here instead of overwriting MODEL_FILE.
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    model_file: PathBuf,
+}
+
+/// Arguments for the delta command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Create and apply compact patches between two versions of a model",
+    version = version(),
+)]
+struct DeltaArgs {
+    #[command(subcommand)]
+    command: DeltaCommands,
+}
+
+/// Subcommands of the delta command.
+#[derive(Debug, Subcommand)]
+enum DeltaCommands {
+    /// Write the changes from BASE_FILE to TARGET_FILE to DELTA_FILE.
+    Create {
+        base_file: PathBuf,
+        target_file: PathBuf,
+        delta_file: PathBuf,
+    },
+    /// Apply DELTA_FILE to BASE_FILE, reproducing the target model.
+    Apply {
+        /// Write the updated model here instead of overwriting BASE_FILE.
+        #[arg(short, long)]
+        output_file: Option<PathBuf>,
+
+        base_file: PathBuf,
+        delta_file: PathBuf,
+    },
+}
+
+/// Arguments for the split-sentences command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Split text into sentences using Unicode UAX #29 rules",
+    version = version(),
+)]
+struct SplitSentencesArgs {}
+
+/// Arguments for the export-js command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Export a model as a TinySegmenter-compatible JavaScript/TypeScript file",
+    version = version(),
+)]
+struct ExportJsArgs {
+    /// The language, by default the one of the model's configuration or Japanese.
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Feature templates of the model: litsea or tinysegmenter, for models converted
+    /// from TinySegmenter (default: the templates of the model's configuration or those
+    /// the model records, or litsea).
+    #[arg(long)]
+    feature_templates: Option<String>,
+
+    /// Latin character classes of the model: single, case or case-width (default:
+    /// single, or the classes of the model's configuration).
+    #[arg(long)]
+    latin_classes: Option<String>,
+
+    model_uri: String,
+    output_file: PathBuf,
+}
+
+/// Arguments for the export command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Convert a model into another tool's model format (Vaporetto or ONNX)",
+    version = version(),
+)]
+struct ExportArgs {
+    #[arg(short, long)]
+    to: String,
+
+    model_uri: String,
+    output_file: PathBuf,
+}
+
+/// Arguments for the import command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Convert a KyTea, TinySegmenter or Vaporetto model into Litsea's model format",
+    version = version(),
+)]
+struct ImportArgs {
+    #[arg(short, long)]
+    from: String,
+
+    input_file: PathBuf,
+    model_file: PathBuf,
+}
+
+/// Arguments for the import-corpus command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Convert a KyTea or CoNLL-U corpus into a corpus with words separated by spaces",
+    version = version(),
+)]
+struct ImportCorpusArgs {
+    /// Format of the input corpus: "kytea" or "conllu".
+    #[arg(short, long)]
+    from: String,
+
+    /// Also write the raw text of each sentence to this file, one sentence per line. The
+    /// text of a CoNLL-U sentence keeps the spaces between tokens without SpaceAfter=No.
+    #[arg(long)]
+    text_file: Option<PathBuf>,
+
+    input_file: PathBuf,
+    corpus_file: PathBuf,
+}
+
+/// Arguments for the model-card command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Generate a Markdown model card with model details, evaluation and benchmarks",
+    version = version(),
+)]
+struct ModelCardArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Gold corpus (words separated by spaces) to evaluate the model against.
+    #[arg(short, long)]
+    corpus_file: Option<PathBuf>,
+
+    /// Number of passes over the corpus when measuring segmentation speed (0 to skip).
+    #[arg(short, long, default_value = "10")]
+    benchmark_iterations: usize,
+
+    /// Write the model card to this file instead of standard output.
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    model_uri: String,
+}
+
+/// Arguments for the analyze command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "List and categorize the segmentation errors of a model on a gold corpus",
+    version = version(),
+)]
+struct AnalyzeArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Report the errors where most features were unseen in training and the feature
+    /// coverage of each template instead.
+    #[arg(long)]
+    coverage: bool,
+
+    /// Features file the model was trained on, for --coverage. Defaults to the features
+    /// with a weight in the model.
+    #[arg(long, requires = "coverage")]
+    features: Option<PathBuf>,
+
+    /// Write the word boundary metrics of the model on the gold corpus to this file as
+    /// JSON.
+    #[arg(long)]
+    metrics_json: Option<PathBuf>,
+
+    /// Gold corpus, one sentence per line with words separated by spaces.
+    corpus_file: PathBuf,
+    model_uri: String,
+}
+
+/// Arguments for the evaluate command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Report the boundary and word accuracy of a model on a held-out gold corpus",
+    version = version(),
+)]
+struct EvaluateArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Write the metrics to this file as JSON.
+    #[arg(long)]
+    metrics_json: Option<PathBuf>,
+
+    /// Write the most frequent wrongly split and wrongly joined character contexts, with
+    /// counts and example sentences, to this file as tab-separated values.
+    #[arg(long)]
+    error_report: Option<PathBuf>,
+
+    /// Format of the gold corpus: "plain", "kytea" or "conllu".
+    #[arg(long, default_value = "plain")]
+    corpus_format: String,
+
+    model_uri: String,
+    /// Gold corpus, one sentence per line with words separated by spaces unless
+    /// --corpus-format is given.
+    corpus_file: PathBuf,
+}
+
+/// Arguments for the bench command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Measure the segmentation throughput, latency and memory use of a model",
+    version = version(),
+)]
+struct BenchArgs {
+    /// The language, by default the one of the model's configuration or Japanese.
+    #[arg(short, long)]
+    language: Option<String>,
+
+    /// Number of measured passes over the input.
+    #[arg(short, long, default_value = "10")]
+    iterations: usize,
+
+    /// Number of unmeasured passes over the input before measuring.
+    #[arg(long, default_value = "1")]
+    warmup: usize,
+
+    /// The model file.
+    #[arg(short, long)]
+    model: PathBuf,
+
+    /// Text to segment, one sentence per line.
+    #[arg(long)]
+    input: PathBuf,
+}
+
+/// Arguments for the inspect command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Summarize the weights of a model: features, bias, top weights and distribution",
+    version = version(),
+)]
+struct InspectArgs {
+    /// Number of features with the largest weights to list.
+    #[arg(short = 'n', long, default_value = "20")]
+    top: usize,
+
+    /// Number of bins of the weight histogram (0 to skip).
+    #[arg(long, default_value = "10")]
+    bins: usize,
+
+    model_uri: String,
+}
+
+/// Arguments for the diff command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "List the features whose weights changed most between two models",
+    version = version(),
+)]
+struct DiffArgs {
+    /// Number of features with the largest changes to list.
+    #[arg(short = 'n', long, default_value = "20")]
+    top: usize,
+
+    /// The first model, e.g. the one in production.
+    old_model_uri: String,
+    /// The second model, e.g. a retrained one.
+    new_model_uri: String,
+}
+
+/// Arguments for the compare command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Compare the segmentations of a corpus by two models",
+    version = version(),
+)]
+struct CompareArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// The corpus is a gold corpus with words separated by spaces. Also tests whether
+    /// the difference in accuracy between the models is significant.
+    #[arg(long)]
+    gold: bool,
+
+    /// Number of paired bootstrap samples for the significance test; 0 to skip.
+    #[arg(long, default_value = "1000", requires = "gold")]
+    samples: usize,
+
+    /// Seed of the bootstrap resampling.
+    #[arg(long, default_value = "0", requires = "gold")]
+    seed: u64,
+
+    /// Corpus to segment, one sentence per line.
+    corpus_file: PathBuf,
+    /// The first model, e.g. the one in production.
+    left_model_uri: String,
+    /// The second model, e.g. a retrained one.
+    right_model_uri: String,
+}
+
+/// Arguments for the serve command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Serve a model over a Unix domain socket or TCP with newline-delimited JSON-RPC",
+    version = version(),
+)]
+#[command(group = clap::ArgGroup::new("endpoint").required(true))]
+struct ServeArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Path of the Unix domain socket to listen on (Unix only).
+    #[arg(long, group = "endpoint")]
+    unix: Option<PathBuf>,
+
+    /// TCP address to listen on (e.g. 0.0.0.0:7700).
+    #[arg(long, group = "endpoint")]
+    tcp: Option<String>,
+
+    /// PEM file with the TLS certificate chain (TCP only).
+    #[arg(long, requires = "tls_key", conflicts_with = "unix")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM file with the TLS private key (TCP only).
+    #[arg(long, requires = "tls_cert", conflicts_with = "unix")]
+    tls_key: Option<PathBuf>,
+
+    /// File listing accepted API tokens, one per line; clients must authenticate first.
+    #[arg(long)]
+    token_file: Option<PathBuf>,
+
+    /// Model to load when reloading (on SIGHUP or the `reload` method), instead of MODEL_URI.
+    #[arg(long)]
+    reload_uri: Option<String>,
+
+    /// Maximum number of segmentation jobs running at once.
+    #[arg(long, default_value = "4")]
+    max_concurrent: usize,
+
+    /// Maximum size of a request line in bytes; larger requests are rejected.
+    #[arg(long, default_value = "1048576")]
+    max_request_bytes: usize,
+
+    /// Maximum number of small requests segmented together (1 disables batching).
+    #[arg(long, default_value = "32")]
+    batch_size: usize,
+
+    /// Microseconds to wait for more small requests before segmenting a batch.
+    #[arg(long, default_value = "200")]
+    batch_delay_us: u64,
+
+    model_uri: String,
+}
+
+/// Arguments for the fetch command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Download a pretrained model from a model registry into the local cache",
+    version = version(),
+)]
+struct FetchArgs {
+    /// Registry URL (defaults to $LITSEA_REGISTRY or the official registry).
+    #[arg(long)]
+    registry: Option<String>,
+
+    /// Cache directory (defaults to $LITSEA_CACHE_DIR or ~/.cache/litsea).
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// List the models in the registry instead of fetching one.
+    #[arg(long)]
+    list: bool,
+
+    /// Model to fetch, as NAME or NAME@VERSION (latest version if omitted).
+    #[arg(required_unless_present = "list")]
+    model: Option<String>,
+}
+
+/// Arguments for the init command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Create a project with a configuration, sample corpora and build tasks",
+    version = version(),
+)]
+struct InitArgs {
+    #[arg(short, long, default_value = "japanese")]
+    language: String,
+
+    /// Project directory, created if it does not exist.
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+}
+
+/// Arguments for the build command.
+#[derive(Debug, Args)]
+#[command(
+    author,
+    about = "Run the build tasks of a project, skipping those whose outputs are up to date",
+    version = version(),
+)]
+struct BuildArgs {
+    /// Tasks file. Paths in the tasks are relative to its directory.
+    #[arg(short = 'f', long, default_value = project::TASKS_FILE)]
+    tasks_file: PathBuf,
+
+    /// Run the tasks even if their outputs are up to date.
+    #[arg(long)]
+    force: bool,
+
+    /// Print the commands without running them.
+    #[arg(short = 'n', long)]
+    dry_run: bool,
+
+    /// List the tasks and exit.
+    #[arg(long)]
+    list: bool,
+
+    /// Tasks to run, after the tasks they depend on (default: the default tasks).
+    tasks: Vec<String>,
+}
+
+/// Subcommands for litsea CLI.
+#[derive(Debug, Subcommand)]
+enum Commands {
+    Extract(ExtractArgs),
+    ShrinkFeatures(ShrinkFeaturesArgs),
+    SortFeatures(SortFeaturesArgs),
+    Train(TrainArgs),
+    SelfTrain(SelfTrainArgs),
+    UpdateModel(UpdateModelArgs),
+    Segment(SegmentArgs),
+    SegmentFile(SegmentFileArgs),
+    TrainTagger(TrainTaggerArgs),
+    Tag(TagArgs),
+    Vocab(VocabArgs),
+    Route(RouteArgs),
+    SplitSentences(SplitSentencesArgs),
+    ExportJs(ExportJsArgs),
+    Import(ImportArgs),
+    ImportCorpus(ImportCorpusArgs),
+    Export(ExportArgs),
+    ModelCard(ModelCardArgs),
+    Analyze(AnalyzeArgs),
+    Evaluate(EvaluateArgs),
+    Bench(BenchArgs),
+    Compare(CompareArgs),
+    Inspect(InspectArgs),
+    Diff(DiffArgs),
+    Serve(ServeArgs),
+    Store(StoreArgs),
+    Fetch(FetchArgs),
+    Sign(SignArgs),
+    FixModel(FixModelArgs),
+    Delta(DeltaArgs),
+    Editor(EditorArgs),
+    Init(InitArgs),
+    Build(BuildArgs),
+}
+
+/// Arguments for the litsea command.
+#[derive(Debug, Parser)]
+#[command(
+    name = "litsea",
+    author,
+    about = "A morphological analysis command line interface",
+    version = version(),
+)]
+struct CommandArgs {
+    /// Print only errors: no status messages, reports or progress bars.
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print the events of the library: -v for informational ones, -vv for every
+    /// boosting iteration, -vvv for everything.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+/// Extract features from a corpus file and write them to a specified output file.
+/// This function reads sentences from the corpus file, segments them into words,
+/// and writes the extracted features to the output file.
+///
+/// # Arguments
+/// * `args` - The arguments for the extract command [`ExtractArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn extract(args: ExtractArgs) -> Result<(), Box<dyn Error>> {
+    let config = args.config.as_ref().map(PipelineConfig::load).transpose()?.unwrap_or_default();
+    let language: Language = match &args.language {
+        Some(language) => language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.language,
+    };
+    let templates: FeatureTemplates = match &args.feature_templates {
+        Some(templates) => templates.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.feature_templates,
+    };
+    let latin_classes: LatinClasses = match &args.latin_classes {
+        Some(classes) => classes.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.latin_classes,
+    };
+    let corpus_format: CorpusFormat =
+        args.corpus_format.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let format: FeatureFormat =
+        args.format.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+
+    let options = ExtractOptions {
+        language,
+        corpus_format,
+        normalizer: config.normalizer,
+        templates,
+        latin_classes,
+        format,
+        dictionary_file: args.dictionary_file.as_deref(),
+        num_threads: args.num_threads,
+    };
+    let dictionary_file =
+        cli::extract(args.corpus_file.as_path(), args.features_file.as_path(), &options)?;
+    if let Some(dictionary_file) = dictionary_file {
+        status!("Feature dictionary written to {}.", dictionary_file.display());
+    }
+
+    status!("Feature extraction completed successfully.");
+    Ok(())
+}
+
+/// Remove rare features from an extracted features file.
+///
+/// # Arguments
+/// * `args` - The arguments for the shrink-features command [`ShrinkFeaturesArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn shrink_features(args: ShrinkFeaturesArgs) -> Result<(), Box<dyn Error>> {
+    if args.features_file == args.output_file {
+        return Err("The output file must differ from the features file".into());
+    }
+    let format: FeatureFormat =
+        args.format.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let stats = match format {
+        FeatureFormat::Text => {
+            feature_file::shrink_features(&args.features_file, &args.output_file, args.min_count)?
+        }
+        FeatureFormat::Parquet => {
+            let dictionary_file = args
+                .dictionary_file
+                .unwrap_or_else(|| args.features_file.with_extension("dict.parquet"));
+            let output_dictionary_file = args
+                .output_dictionary_file
+                .unwrap_or_else(|| args.output_file.with_extension("dict.parquet"));
+            let stats = feature_file::shrink_features_parquet(
+                &args.features_file,
+                &dictionary_file,
+                &args.output_file,
+                &output_dictionary_file,
+                args.min_count,
+            )?;
+            status!("Feature dictionary written to {}.", output_dictionary_file.display());
+            stats
+        }
+    };
+
+    status!(
+        "Kept {} of {} features ({:.2}% of {} occurrences) in {} instances.",
+        stats.kept_features,
+        stats.features,
+        stats.kept_occurrence_ratio(),
+        stats.occurrences,
+        stats.instances
+    );
+    Ok(())
+}
+
+/// Sort, merge and deduplicate features files.
+///
+/// # Arguments
+/// * `args` - The arguments for the sort-features command [`SortFeaturesArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn sort_features(args: SortFeaturesArgs) -> Result<(), Box<dyn Error>> {
+    if args.features_files.contains(&args.output) {
+        return Err("The output file must differ from the features files".into());
+    }
+    let inputs: Vec<&Path> = args.features_files.iter().map(PathBuf::as_path).collect();
+    let options = SortOptions {
+        dedup: args.dedup,
+        counted_input: args.counted,
+        memory_limit: args.memory_limit.saturating_mul(1024 * 1024),
+        temp_dir: args.temp_dir,
+    };
+    let stats = feature_file::sort_features(&inputs, &args.output, &options)?;
+
+    status!(
+        "Sorted {} instances into {} lines ({} runs) in {}.",
+        stats.instances,
+        stats.lines,
+        stats.runs,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Returns the boosting variant for a `--weight-cap` option.
+///
+/// # Arguments
+/// * `weight_cap` - The cap on instance weights, if given.
+fn boosting(weight_cap: Option<f64>) -> Boosting {
+    weight_cap.map_or(Boosting::AdaBoost, Boosting::Capped)
+}
+
+/// Writes a value to a file as pretty-printed JSON, for `--metrics-json`.
+///
+/// # Arguments
+/// * `path` - The output file.
+/// * `value` - The value to write.
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// The files the progress of training is recorded to while it runs.
+#[derive(Default)]
+struct MetricsRecorders {
+    csv: Option<MetricsCsv<io::BufWriter<std::fs::File>>>,
+    events: Option<EventWriter<io::BufWriter<std::fs::File>>>,
+    /// The first error writing the files; nothing more is written after it.
+    error: Option<io::Error>,
+}
+
+impl MetricsRecorders {
+    /// Records the progress of an iteration.
+    fn record(&mut self, progress: &TrainingProgress) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = self
+            .csv
+            .as_mut()
+            .map_or(Ok(()), |csv| csv.add_progress(progress))
+            .and_then(|()| self.events.as_mut().map_or(Ok(()), |e| e.add_progress(progress)));
+        if let Err(e) = result {
+            self.error = Some(e);
+        }
+    }
+
+    /// Flushes the files, or returns the first error writing them.
+    fn finish(mut self) -> io::Result<()> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if let Some(csv) = &mut self.csv {
+            csv.flush()?;
+        }
+        if let Some(events) = &mut self.events {
+            events.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Stops a training run at the first Ctrl-C, and exits at the second.
+///
+/// # Returns
+/// The control to train with.
+fn stop_on_ctrlc() -> Result<TrainControl, Box<dyn Error>> {
+    let control = TrainControl::new();
+    let handle = control.clone();
+    ctrlc::set_handler(move || {
+        if handle.is_stopped() {
+            std::process::exit(0);
+        }
+        handle.stop();
+    })?;
+    Ok(control)
+}
+
+/// Train a segmenter using the provided arguments.
+/// This function initializes a Trainer with the specified parameters,
+/// loads a model if specified, and trains the model using the features file.
+///
+/// # Arguments
+/// * `args` - The arguments for the train command [`TrainArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn train(args: TrainArgs) -> Result<(), Box<dyn Error>> {
+    let control = stop_on_ctrlc()?;
+
+    let algorithm: Algorithm =
+        args.algorithm.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let quantize: Option<Quantization> = args
+        .quantize
+        .as_deref()
+        .map(str::parse)
+        .transpose()
+        .map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let metadata = match &args.config {
+        Some(path) => {
+            let config = PipelineConfig::load(path)?;
+            vec![
+                config.to_metadata()?,
+                (
+                    model_info::CHAR_TYPES_KEY.to_string(),
+                    model_info::char_type_scheme(
+                        config.language,
+                        config.feature_templates,
+                        config.latin_classes,
+                    ),
+                ),
+                (
+                    model_info::TEMPLATES_KEY.to_string(),
+                    model_info::templates_id(config.feature_templates),
+                ),
+            ]
+        }
+        None => Vec::new(),
+    };
+    let progress = logging::progress_bar(args.num_iterations as u64);
+    progress.set_style(ProgressStyle::with_template(
+        "{bar:40} {pos}/{len} iterations ({eta}) {wide_msg}",
+    )?);
+    let recorders = Arc::new(Mutex::new(MetricsRecorders {
+        csv: match &args.metrics_out {
+            Some(path) => Some(MetricsCsv::new(io::BufWriter::new(std::fs::File::create(path)?))?),
+            None => None,
+        },
+        events: args.tensorboard.as_deref().map(EventWriter::create).transpose()?,
+        error: None,
+    }));
+    let handler: Arc<dyn ProgressHandler> = {
+        let progress = progress.clone();
+        let recorders = Arc::clone(&recorders);
+        // The last training accuracy, kept between the iterations that report none.
+        let train_accuracy = Mutex::new(None);
+        Arc::new(move |iteration: &TrainingProgress| {
+            progress.set_position(iteration.iteration as u64);
+            let mut train_accuracy = train_accuracy.lock().unwrap_or_else(|e| e.into_inner());
+            if iteration.train_accuracy.is_some() {
+                *train_accuracy = iteration.train_accuracy;
+            }
+            let mut message =
+                format!("error {:.4}, alpha {:+.4}", iteration.error_rate, iteration.alpha);
+            if let Some(accuracy) = *train_accuracy {
+                message.push_str(&format!(", train accuracy {:.2}%", accuracy));
+            }
+            if let Some(record) = iteration.record {
+                message.push_str(&format!(", dev accuracy {:.2}%", record.dev_accuracy));
+            }
+            progress.set_message(message);
+            recorders.lock().unwrap_or_else(|e| e.into_inner()).record(iteration);
+        })
+    };
+
+    let result = cli::train(
+        args.features_file.as_path(),
+        args.model_file.as_path(),
+        &TrainOptions {
+            threshold: args.threshold,
+            num_iterations: args.num_iterations,
+            algorithm,
+            boosting: boosting(args.weight_cap),
+            load_model_uri: args.load_model_uri.as_deref(),
+            metadata: &metadata,
+            dev_features_file: args.dev_features.as_deref(),
+            overfitting_patience: args.overfitting_patience,
+            stop_on_overfitting: args.stop_on_overfitting,
+            early_stopping_patience: args.patience,
+            batch_size: args.batch_size,
+            correction_interval: args.correction_interval,
+            num_threads: args.num_threads,
+            instance_file: args.instance_file.as_deref(),
+            weighted: args.weighted,
+            cross_validation_folds: args.cv,
+            prune_below: args.prune_below,
+            max_features: args.max_features,
+            quantize,
+            ensemble: args.ensemble,
+            seed: args.seed,
+            progress: Some(&handler),
+            accuracy_interval: args.accuracy_interval,
+        },
+        control,
+    )
+    .await;
+    progress.finish_and_clear();
+    let report = result?;
+    std::mem::take(&mut *recorders.lock().unwrap_or_else(|e| e.into_inner())).finish()?;
+
+    if let Some(path) = &args.log {
+        let mut log = io::BufWriter::new(std::fs::File::create(path)?);
+        cli::write_training_log(&mut log, &report.log)?;
+        log.flush()?;
+    }
+    if let Some(path) = &args.metrics_json {
+        write_json(path, &report)?;
+    }
+    if let Some(cv) = &report.cross_validation {
+        cli::write_cross_validation(&mut logging::stderr(), cv)?;
+    }
+    if let Some(ensemble) = &report.ensemble {
+        cli::write_ensemble(&mut logging::stderr(), ensemble)?;
+    }
+    cli::write_metrics(&mut logging::stderr(), &report.metrics)?;
+    if let Some(dev_metrics) = &report.dev_metrics {
+        status!("Development set:");
+        cli::write_metrics(&mut logging::stderr(), dev_metrics)?;
+    }
+    if let Some(pruning) = &report.pruning {
+        cli::write_pruning(&mut logging::stderr(), pruning)?;
+    }
+    if let Some(quantization) = &report.quantization {
+        cli::write_quantization(&mut logging::stderr(), quantization)?;
+    }
+    if let Some(iteration) = report.best_iteration {
+        status!(
+            "Early stopping: saved the model of iteration {}, the best on the development set",
+            iteration
+        );
+    }
+    if let Some(record) = report.overfitting() {
+        status!(
+            "Warning: the train/dev accuracy gap kept growing up to iteration {} ({:.2} points); \
+             the model may be overfitting{}",
+            record.iteration,
+            record.gap(),
+            if args.stop_on_overfitting { ", training was stopped" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+/// Grow a model from unlabeled text: each round segments the unlabeled corpus with the
+/// current model and retrains on the confidently segmented sentences.
+///
+/// # Arguments
+/// * `args` - The arguments for the self-train command [`SelfTrainArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn self_train(args: SelfTrainArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let control = stop_on_ctrlc()?;
+
+    let mut learner = AdaBoost::new(args.threshold, args.num_iterations);
+    learner.load_model(&args.model_uri).await?;
+    let mut self_trainer = SelfTrainer::new(language)
+        .with_confidence(args.confidence)
+        .with_rounds(args.rounds)
+        .with_training(args.threshold, args.num_iterations)
+        .with_boosting(boosting(args.weight_cap));
+    if let Some(labeled) = &args.labeled {
+        self_trainer = self_trainer.with_labeled_corpus(labeled);
+    }
+    let work_dir = args.work_dir.unwrap_or_else(|| args.model_file.with_extension("rounds"));
+
+    let reports = self_trainer.run(learner, &args.unlabeled_file, &work_dir, control)?;
+    for report in &reports {
+        status!(
+            "Round {}: trained on {} of {} sentences.",
+            report.round,
+            report.selected,
+            report.sentences
+        );
+        cli::write_metrics(&mut logging::stderr(), &report.metrics)?;
+    }
+    let last = reports.last().ok_or("Self-training was stopped before the first round")?;
+    std::fs::copy(&last.model_file, &args.model_file)?;
+    status!("Model written to {}.", args.model_file.display());
+    Ok(())
+}
+
+/// Update a model with correctly segmented sentences, e.g. corrections of its mistakes,
+/// boosting from its current weights instead of retraining it from scratch.
+///
+/// # Arguments
+/// * `args` - The arguments for the update-model command [`UpdateModelArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn update_model(args: UpdateModelArgs) -> Result<(), Box<dyn Error>> {
+    let algorithm: Algorithm =
+        args.algorithm.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let mut learner = AdaBoost::new(args.threshold, args.num_iterations);
+    learner.set_algorithm(algorithm);
+    learner.set_learning_rate(args.learning_rate)?;
+    learner.load_model(&args.model_file.to_string_lossy()).await?;
+    let config = PipelineConfig::from_model(&learner)?
+        .unwrap_or_else(|| PipelineConfig::for_model(&learner));
+    let language: Language = match &args.language {
+        Some(language) => language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.language,
+    };
+    let mut segmenter = Segmenter::new(language, Some(learner));
+    segmenter.set_feature_templates(config.feature_templates);
+    segmenter.set_latin_classes(config.latin_classes);
+    segmenter.check_model()?;
+
+    let mut sentences = Vec::new();
+    for line in compression::open(&args.corpus_file)?.lines() {
+        let line = line?;
+        let line = config.normalizer.normalize(line.trim());
+        if !line.is_empty() {
+            sentences.push(line.into_owned());
+        }
+    }
+    let num_wrong = |segmenter: &Segmenter| {
+        sentences
+            .iter()
+            .filter(|sentence| {
+                let words: Vec<&str> = sentence.split(' ').filter(|w| !w.is_empty()).collect();
+                segmenter.segment(&words.concat()) != words
+            })
+            .count()
+    };
+    let wrong_before = num_wrong(&segmenter);
+
+    let mut instances = Vec::new();
+    for sentence in &sentences {
+        segmenter.add_corpus_with_writer(sentence, |attributes, label| {
+            instances.push((attributes, label));
+        });
+    }
+    let learned = segmenter.learner_mut().partial_fit(instances);
+    let wrong_after = num_wrong(&segmenter);
+
+    let output_file = args.output_file.unwrap_or(args.model_file);
+    segmenter.learner().save_model(&output_file)?;
+    status!(
+        "Learned {} boundary decisions from {} sentences; {} were segmented wrong before, {} after.",
+        learned,
+        sentences.len(),
+        wrong_before,
+        wrong_after
+    );
+    status!("Updated model written to {}.", output_file.display());
+    Ok(())
+}
+
+/// Loads the lexicon of `litsea segment --lexicon`: a MeCab dictionary if the path is a
+/// directory or a `.csv` file, and a word list otherwise.
+fn load_lexicon<P: AsRef<Path>>(path: P) -> litsea::error::Result<Lexicon> {
+    let path = path.as_ref();
+    if path.is_dir() || path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")) {
+        Lexicon::from_mecab_csv(path)
+    } else {
+        Lexicon::load(path)
+    }
+}
+
+/// Segment a sentence using the trained model.
+/// This function loads the AdaBoost model from the specified file,
+/// reads sentences from standard input or the input file, segments them into words,
+/// and writes the segmented sentences to standard output or the output file.
+/// With both an input and an output, directories are segmented file by file.
+///
+/// # Arguments
+/// * `args` - The arguments for the segment command [`SegmentArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn segment(args: SegmentArgs) -> Result<(), Box<dyn Error>> {
+    let options = &args.options;
+    let encoding: TextEncoding =
+        options.encoding.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let explicit_config = options.config.as_ref().map(PipelineConfig::load).transpose()?;
+    let model_uri = args
+        .model_uri
+        .clone()
+        .or_else(|| explicit_config.as_ref().and_then(|config| config.model.clone()))
+        .ok_or("No model given on the command line or in the configuration")?;
+    let (learner, store_language) = match &options.store {
+        Some(store) => {
+            let segmenter = Segmenter::from_store(store, &model_uri)?;
+            (segmenter.shared_learner(), Some(segmenter.language))
+        }
+        None => {
+            // AdaBoost parameters are not used for prediction; only the loaded model weights matter.
+            let mut learner = AdaBoost::new(0.01, 100);
+            learner.set_verify_integrity(!options.no_verify);
+            if let Some(public_key) = &options.public_key {
+                let key = integrity::verifying_key_from_hex(&std::fs::read_to_string(public_key)?)?;
+                learner.set_verifying_key(Some(key));
+            }
+            learner.load_model(model_uri.as_str()).await?;
+            (Arc::new(learner), None)
+        }
+    };
+    // An explicit configuration wins over the one the model was trained with.
+    let embedded_config = PipelineConfig::from_model(&learner)?;
+    let config = match explicit_config {
+        Some(config) => {
+            if let Some(embedded) = &embedded_config {
+                if embedded.normalizer != config.normalizer {
+                    status!(
+                        "Warning: the normalization of the configuration differs from the one \
+                         the model was trained with; accuracy may drop"
+                    );
+                }
+            }
+            config
+        }
+        None => embedded_config.unwrap_or_else(|| PipelineConfig::for_model(&learner)),
+    };
+    let language: Language = match (&options.language, store_language) {
+        (Some(language), _) => language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        (None, Some(language)) => language,
+        (None, None) => config.language,
+    };
+    let mut segmenter = Segmenter::from_shared(language, learner);
+    segmenter.set_feature_templates(match &options.feature_templates {
+        Some(templates) => templates.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.feature_templates,
+    });
+    segmenter.set_latin_classes(match &options.latin_classes {
+        Some(classes) => classes.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.latin_classes,
+    });
+    segmenter.check_model()?;
+    segmenter.set_grouping_rules(match &options.group {
+        Some(rules) => rules.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.post_rules.grouping,
+    });
+    segmenter.set_boundary_bias(options.bias.unwrap_or(config.post_rules.boundary_bias));
+    if let Some(capacity) = options.score_cache {
+        segmenter.set_score_cache(capacity);
+    }
+    let format: OutputFormat = match &options.format {
+        Some(format) => format.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.output_format,
+    };
+    let dictionaries = &config.dictionaries;
+    let post_rules = &config.post_rules;
+    let readings = options
+        .readings
+        .as_ref()
+        .or(dictionaries.readings.as_ref())
+        .map(ReadingDictionary::load)
+        .transpose()?;
+    let lemmas = options
+        .lemmas
+        .as_ref()
+        .or(dictionaries.lemmas.as_ref())
+        .map(LemmaDictionary::load)
+        .transpose()?;
+    let compounds = if options.split_compounds || post_rules.split_compounds {
+        let splitter = CompoundSplitter::new(segmenter.language);
+        Some(match options.compound_words.as_ref().or(dictionaries.compound_words.as_ref()) {
+            Some(path) => splitter.load_words(path)?,
+            None => splitter,
+        })
+    } else {
+        None
+    };
+    let stopwords = if options.drop_stopwords || post_rules.drop_stopwords {
+        let mut stopwords = StopWords::japanese();
+        if let Some(path) = options.stopwords.as_ref().or(dictionaries.stopwords.as_ref()) {
+            stopwords.load(path)?;
+        }
+        Some(stopwords)
+    } else {
+        None
+    };
+    let vocab_weight = options.vocab_weight.unwrap_or(post_rules.vocab_weight);
+    let beam = options.beam.unwrap_or(post_rules.beam);
+    let reranker = options
+        .vocab
+        .as_ref()
+        .or(dictionaries.vocab.as_ref())
+        .map(UnigramModel::load)
+        .transpose()?
+        .map(|model| UnigramReranker::new(model).with_weight(vocab_weight).with_beam(beam));
+    let lexicon_bonus = options.lexicon_bonus.unwrap_or(post_rules.lexicon_bonus);
+    let lattice = options
+        .lexicon
+        .as_ref()
+        .or(dictionaries.lexicon.as_ref())
+        .map(load_lexicon)
+        .transpose()?
+        .map(|lexicon| {
+            LatticeSegmenter::new(lexicon)
+                .with_bonus(lexicon_bonus)
+                .with_strict(options.lexicon_strict)
+        });
+    let normalizer = (!config.normalizer.is_identity()).then_some(config.normalizer);
+    let segment_options = SegmentOptions {
+        format,
+        line_buffered: options.line_buffered,
+        readings: readings.as_ref(),
+        lemmas: lemmas.as_ref(),
+        compounds: compounds.as_ref(),
+        stopwords: stopwords.as_ref(),
+        reranker: reranker.as_ref(),
+        lattice: lattice.as_ref(),
+        normalizer: normalizer.as_ref(),
+        nbest: options.nbest,
+    };
+    let segment =
+        |reader: Box<dyn BufRead>, writer: &mut dyn Write| -> Result<(), Box<dyn Error>> {
+            cli::segment_lines(
+                &segmenter,
+                encoding.decode(reader),
+                &mut encoding.encoder(writer),
+                &segment_options,
+            )?;
+            Ok(())
+        };
+
+    if let (Some(input), Some(output)) = (&args.input, &args.output) {
+        return batch::run(&batch::plan(input, output)?, segment);
+    }
+    let reader: Box<dyn BufRead> = match &args.input {
+        Some(input) if input.is_dir() => {
+            return Err("--output is required to segment a directory".into());
+        }
+        Some(input) => compression::open(input)?,
+        None => Box::new(io::stdin().lock()),
+    };
+    if let Some(output) = &args.output {
+        let mut writer = compression::create(output)?;
+        segment(reader, &mut writer)?;
+        writer.finish()?;
+        return Ok(());
+    }
+    let stdout = io::stdout();
+    // In line-buffered mode, write straight to stdout and flush after every line so
+    // each result is visible to the reader as soon as it is produced.
+    let mut writer: Box<dyn Write> = if options.line_buffered {
+        Box::new(stdout.lock())
+    } else {
+        Box::new(io::BufWriter::new(stdout.lock()))
+    };
+    segment(reader, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Train a part-of-speech tagger on a tagged corpus and save it.
+///
+/// # Arguments
+/// * `args` - The arguments for the train-tagger command [`TrainTaggerArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn train_tagger(args: TrainTaggerArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let control = stop_on_ctrlc()?;
+
+    let sentences = tagger::read_corpus(compression::open(&args.corpus_file)?)?;
+    let mut learner = AdaBoost::new(args.threshold, args.num_iterations);
+    learner.set_num_threads(args.num_threads);
+    let tagger = Tagger::train(language, &sentences, learner, control)?;
+    tagger.save(&args.model_file)?;
+    status!(
+        "Tags: {}\nAccuracy: {:.2}% on the training corpus",
+        tagger.tags().len(),
+        tagger.accuracy(&sentences)
+    );
+    Ok(())
+}
+
+/// Segment text from standard input, unless it is already segmented, and write each
+/// word with its tag as surface/TAG.
+///
+/// # Arguments
+/// * `args` - The arguments for the tag command [`TagArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn tag(args: TagArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    // The segmentation model is not needed for segmented input.
+    let segmenter = if args.segmented {
+        None
+    } else {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model(args.model_uri.as_str()).await?;
+        Some(Segmenter::new(language, Some(learner)))
+    };
+    let mut tagger_learner = AdaBoost::new(0.01, 100);
+    tagger_learner.load_model(args.tagger_model_uri.as_str()).await?;
+    let tagger = Tagger::new(language, tagger_learner)?;
+
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let words: Vec<String> = match &segmenter {
+            Some(segmenter) => segmenter.segment(line),
+            None => line.split_whitespace().map(str::to_string).collect(),
+        };
+        let tagged: Vec<String> = words
+            .iter()
+            .zip(tagger.tag(&words))
+            .map(|(word, tag)| format!("{}/{}", word, tag))
+            .collect();
+        writeln!(out, "{}", tagged.join(" "))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Segment mixed-language text, sending each line (or each run of one script with
+/// `--spans`) to the model of its script as configured in a model map.
+///
+/// # Arguments
+/// * `args` - The arguments for the route command [`RouteArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn route(args: RouteArgs) -> Result<(), Box<dyn Error>> {
+    let encoding: TextEncoding =
+        args.encoding.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let router = ScriptRouter::load(&args.model_map).await?.with_span_splitting(args.spans);
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut writer = encoding.encoder(io::BufWriter::new(stdout.lock()));
+
+    for line in encoding.decode(stdin.lock()).lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        cli::write_tokens(&mut writer, &router.tokenize(line), OutputFormat::Plain)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Build a word-frequency vocabulary from a segmented corpus, for reranking the
+/// N-best segmentations of the segment command with `--vocab`.
+///
+/// # Arguments
+/// * `args` - The arguments for the vocab command [`VocabArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn vocab(args: VocabArgs) -> Result<(), Box<dyn Error>> {
+    let stopwords = if args.drop_stopwords {
+        let mut stopwords = StopWords::japanese();
+        if let Some(path) = &args.stopwords {
+            stopwords.load(path)?;
+        }
+        Some(stopwords)
+    } else {
+        None
+    };
+    let corpus = compression::open(&args.corpus_file)?;
+    let mut model = UnigramModel::from_corpus(corpus, stopwords.as_ref())?;
+    model.retain_min_count(args.min_count);
+    model.save(&args.vocab_file)?;
+
+    status!(
+        "Vocabulary of {} words ({} occurrences) written to {}.",
+        model.len(),
+        model.total(),
+        args.vocab_file.display()
+    );
+    Ok(())
+}
+
+/// Split text into sentences using ICU4X SentenceSegmenter (Unicode UAX #29).
+/// This function reads text from standard input (one paragraph per line),
+/// splits each line into sentences, and writes one sentence per line to standard output.
+///
+/// # Arguments
+/// * `_args` - The arguments for the split-sentences command [`SplitSentencesArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn split_sentences(_args: SplitSentencesArgs) -> Result<(), Box<dyn Error>> {
+    use icu_segmenter::SentenceSegmenter;
+    use icu_segmenter::options::SentenceBreakInvariantOptions;
+
+    let segmenter = SentenceSegmenter::new(SentenceBreakInvariantOptions::default());
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut breakpoints: Vec<usize> = segmenter.segment_str(line).collect();
+        // Ensure the first breakpoint is 0 so no leading text is lost.
+        if breakpoints.first() != Some(&0) {
+            breakpoints.insert(0, 0);
+        }
+        for window in breakpoints.windows(2) {
+            let sentence = line[window[0]..window[1]].trim();
+            if !sentence.is_empty() {
+                writeln!(writer, "{}", sentence)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Export a model as a self-contained TinySegmenter-compatible script.
+/// The output is TypeScript if the output file ends in `.ts`, JavaScript otherwise.
+///
+/// # Arguments
+/// * `args` - The arguments for the export-js command [`ExportJsArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn export_js_model(args: ExportJsArgs) -> Result<(), Box<dyn Error>> {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+
+    let config = PipelineConfig::from_model(&learner)?
+        .unwrap_or_else(|| PipelineConfig::for_model(&learner));
+    let language: Language = match &args.language {
+        Some(language) => language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.language,
+    };
+    let templates: FeatureTemplates = match &args.feature_templates {
+        Some(templates) => templates.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.feature_templates,
+    };
+    let latin_classes: LatinClasses = match &args.latin_classes {
+        Some(classes) => classes.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.latin_classes,
+    };
+
+    let flavor = ScriptFlavor::from_path(&args.output_file);
+    let mut writer = io::BufWriter::new(std::fs::File::create(&args.output_file)?);
+    export_js(&learner, language, templates, latin_classes, flavor, &mut writer)?;
+    writer.flush()?;
+
+    status!("Model exported to {}.", args.output_file.display());
+    Ok(())
+}
+
+/// Convert a model trained with another tool into Litsea's model format.
+/// Only features whose templates overlap with Litsea's are converted.
+///
+/// # Arguments
+/// * `args` - The arguments for the import command [`ImportArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn import(args: ImportArgs) -> Result<(), Box<dyn Error>> {
+    let input = io::BufReader::new(std::fs::File::open(&args.input_file)?);
+    let learner = match args.from.to_lowercase().as_str() {
+        "tinysegmenter" => tinysegmenter::import_js(input)?,
+        "kytea" => {
+            let (learner, skipped) = kytea::import_text_model(input)?;
+            if skipped > 0 {
+                status!("Skipped {} features without a Litsea counterpart.", skipped);
+            }
+            learner
+        }
+        "vaporetto" => {
+            let (learner, skipped) = vaporetto::import_model(input)?;
+            if skipped > 0 {
+                status!("Skipped {} weights without a Litsea counterpart.", skipped);
+            }
+            learner
+        }
+        other => {
+            return Err(format!(
+                "Unsupported model format: '{}'. Supported: kytea, tinysegmenter, vaporetto",
+                other
+            )
+            .into());
+        }
+    };
+
+    learner.save_model(args.model_file.as_path())?;
+
+    status!("Model imported to {}.", args.model_file.display());
+    Ok(())
+}
+
+/// Convert a KyTea or CoNLL-U corpus into a corpus with words separated by spaces, one
+/// sentence per line, and optionally write the raw text of the sentences.
+///
+/// # Arguments
+/// * `args` - The arguments for the import-corpus command [`ImportCorpusArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn import_corpus(args: ImportCorpusArgs) -> Result<(), Box<dyn Error>> {
+    let format: CorpusFormat = args.from.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let input = compression::open(&args.input_file)?;
+    let mut corpus = compression::create(&args.corpus_file)?;
+    let mut text = args.text_file.as_deref().map(compression::create).transpose()?;
+
+    let mut num_sentences = 0;
+    let mut write = |words: &[String], raw: &str| -> io::Result<()> {
+        if words.is_empty() {
+            return Ok(());
+        }
+        writeln!(corpus, "{}", words.join(" "))?;
+        if let Some(text) = text.as_mut() {
+            writeln!(text, "{}", raw)?;
+        }
+        num_sentences += 1;
+        Ok(())
+    };
+    if format == CorpusFormat::Conllu {
+        for sentence in corpus::conllu_sentences(input) {
+            let sentence = sentence?;
+            write(&sentence.words, &sentence.text)?;
+        }
+    } else {
+        for sentence in corpus::sentences(input, format) {
+            let words: Vec<String> = sentence?.split_whitespace().map(str::to_string).collect();
+            write(&words, &words.concat())?;
+        }
+    }
+    corpus.finish()?;
+    if let Some(text) = text {
+        text.finish()?;
+    }
+
+    status!("Imported {} sentences to {}.", num_sentences, args.corpus_file.display());
+    Ok(())
+}
+
+/// Convert a model into another tool's model format.
+/// Only features whose templates overlap with the target format are converted.
+///
+/// # Arguments
+/// * `args` - The arguments for the export command [`ExportArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn export(args: ExportArgs) -> Result<(), Box<dyn Error>> {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+
+    let mut writer = io::BufWriter::new(std::fs::File::create(&args.output_file)?);
+    let skipped = match args.to.to_lowercase().as_str() {
+        "vaporetto" => vaporetto::export_model(&learner, &mut writer)?,
+        "onnx" => {
+            onnx::export_model(&learner, &mut writer)?;
+            // The graph takes feature indices, so it is only usable with the feature table.
+            let table_file = args.output_file.with_extension("features.txt");
+            let mut table_writer = io::BufWriter::new(std::fs::File::create(&table_file)?);
+            for feature in onnx::feature_table(&learner) {
+                writeln!(table_writer, "{}", feature)?;
+            }
+            table_writer.flush()?;
+            status!("Feature table written to {}.", table_file.display());
+            0
+        }
+        other => {
+            return Err(format!(
+                "Unsupported model format: '{}'. Supported: vaporetto, onnx",
+                other
+            )
+            .into());
+        }
+    };
+    writer.flush()?;
 
-    features_file: PathBuf,
-    model_file: PathBuf,
+    if skipped > 0 {
+        status!("Skipped {} features without a counterpart in the target format.", skipped);
+    }
+    status!("Model exported to {}.", args.output_file.display());
+    Ok(())
 }
 
-/// Arguments for the segment command.
-#[derive(Debug, Args)]
-#[command(author,
-    about = "Segment a sentence",
-    version = version(),
-)]
-struct SegmentArgs {
-    #[arg(short, long, default_value = "japanese")]
-    language: String,
+/// Manage models in a SQLite model store.
+///
+/// # Arguments
+/// * `args` - The arguments for the store command [`StoreArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn store(args: StoreArgs) -> Result<(), Box<dyn Error>> {
+    let mut store = ModelStore::open(&args.store_file)?;
+    match args.command {
+        StoreCommands::Add {
+            language,
+            description,
+            name,
+            model_uri,
+        } => {
+            let language: Language =
+                language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+            let mut learner = AdaBoost::new(0.01, 100);
+            learner.load_model(model_uri.as_str()).await?;
+            let version = store.put(&name, language, &description, &learner)?;
+            status!("Added {} version {} to {}.", name, version, args.store_file.display());
+        }
+        StoreCommands::List => {
+            println!("NAME\tVERSION\tLANGUAGE\tFEATURES\tCREATED\tDESCRIPTION");
+            for model in store.list()? {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}",
+                    model.name,
+                    model.version,
+                    model.language,
+                    model.num_features,
+                    model.created_at,
+                    model.description
+                );
+            }
+        }
+        StoreCommands::Get {
+            version,
+            name,
+            output_file,
+        } => {
+            let (_, learner) = store.load(&name, version)?;
+            learner.save_model(&output_file)?;
+            status!("Model written to {}.", output_file.display());
+        }
+        StoreCommands::Remove { version, name } => {
+            let removed = store.remove(&name, version)?;
+            if removed == 0 {
+                return Err(format!("Model not found in store: {}", name).into());
+            }
+            status!("Removed {} version(s) of {}.", removed, name);
+        }
+    }
+    Ok(())
+}
 
-    model_uri: String,
+/// Fetch a pretrained model from a model registry into the local cache.
+/// The path of the cached model is printed to standard output, so it can be
+/// passed to other commands as a model URI.
+///
+/// # Arguments
+/// * `args` - The arguments for the fetch command [`FetchArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn fetch(args: FetchArgs) -> Result<(), Box<dyn Error>> {
+    let url = args
+        .registry
+        .or_else(|| std::env::var(registry::REGISTRY_ENV).ok())
+        .unwrap_or_else(|| registry::DEFAULT_REGISTRY_URL.to_string());
+    let cache_dir = match args.cache_dir {
+        Some(dir) => dir,
+        None => registry::default_cache_dir()?,
+    };
+    let registry = Registry::new(&url, cache_dir);
+
+    if args.list {
+        println!("NAME\tVERSION\tLANGUAGE");
+        for entry in registry.index().await? {
+            println!("{}\t{}\t{}", entry.name, entry.version, entry.language);
+        }
+        return Ok(());
+    }
+
+    let Some(spec) = args.model else {
+        return Err("No model specified".into());
+    };
+    let fetched = registry.fetch(&spec).await?;
+    if fetched.downloaded {
+        status!(
+            "Downloaded {} version {} from {}.",
+            fetched.entry.name,
+            fetched.entry.version,
+            registry.url()
+        );
+    } else {
+        status!("Using cached {} version {}.", fetched.entry.name, fetched.entry.version);
+    }
+    println!("{}", fetched.path.display());
+    Ok(())
 }
 
-/// Arguments for the split-sentences command.
-#[derive(Debug, Args)]
-#[command(
-    author,
-    about = "Split text into sentences using Unicode UAX #29 rules",
-    version = version(),
-)]
-struct SplitSentencesArgs {}
+/// Serve an editor plugin over standard input and output.
+/// Requests and responses are newline-delimited JSON-RPC messages; see the `editor`
+/// module for the methods.
+///
+/// # Arguments
+/// * `args` - The arguments for the editor command [`EditorArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn editor(args: EditorArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+    let segmenter = Segmenter::new(language, Some(learner));
 
-/// Subcommands for litsea CLI.
-#[derive(Debug, Subcommand)]
-enum Commands {
-    Extract(ExtractArgs),
-    Train(TrainArgs),
-    Segment(SegmentArgs),
-    SplitSentences(SplitSentencesArgs),
+    editor::Session::new(segmenter).run(io::stdin().lock(), io::stdout().lock())?;
+    Ok(())
 }
 
-/// Arguments for the litsea command.
-#[derive(Debug, Parser)]
-#[command(
-    name = "litsea",
-    author,
-    about = "A morphological analysis command line interface",
-    version = version(),
-)]
-struct CommandArgs {
-    #[command(subcommand)]
-    command: Commands,
+/// Sign a model file so that loaders given the matching public key can verify it.
+/// The public key is printed to standard output.
+///
+/// # Arguments
+/// * `args` - The arguments for the sign command [`SignArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn sign(args: SignArgs) -> Result<(), Box<dyn Error>> {
+    let key = integrity::signing_key_from_hex(&std::fs::read_to_string(&args.key_file)?)?;
+    let signed = integrity::sign(&std::fs::read(&args.model_file)?, &key)?;
+    let output_file = args.output_file.unwrap_or(args.model_file);
+    std::fs::write(&output_file, signed)?;
+    status!("Signed model written to {}.", output_file.display());
+    println!("{}", integrity::to_hex(key.verifying_key().as_bytes()));
+    Ok(())
 }
 
-/// Extract features from a corpus file and write them to a specified output file.
-/// This function reads sentences from the corpus file, segments them into words,
-/// and writes the extracted features to the output file.
+/// Segment a gold corpus and write the mis-segmented sentences to standard output,
+/// followed by the errors by kind and character-type context on standard error.
+/// With `--coverage`, write the errors at mostly unseen positions and the feature
+/// coverage of each template instead.
 ///
 /// # Arguments
-/// * `args` - The arguments for the extract command [`ExtractArgs`].
+/// * `args` - The arguments for the analyze command [`AnalyzeArgs`].
 ///
 /// # Returns
 /// Returns a Result indicating success or failure.
-fn extract(args: ExtractArgs) -> Result<(), Box<dyn Error>> {
+async fn analyze(args: AnalyzeArgs) -> Result<(), Box<dyn Error>> {
     let language: Language =
         args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
-    let mut extractor = Extractor::new(language);
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+    let segmenter = Segmenter::new(language, Some(learner));
 
-    extractor.extract(args.corpus_file.as_path(), args.features_file.as_path())?;
+    if let Some(path) = &args.metrics_json {
+        let corpus = compression::read_to_string(&args.corpus_file)?;
+        write_json(path, &model_card::evaluate(&segmenter, corpus.lines()))?;
+    }
+    let corpus = compression::open(&args.corpus_file)?;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    if args.coverage {
+        let seen = match &args.features {
+            Some(path) => {
+                Some(analysis::read_feature_names(io::BufReader::new(std::fs::File::open(path)?))?)
+            }
+            None => None,
+        };
+        let coverage = analysis::analyze_coverage(&segmenter, seen.as_ref(), corpus, &mut out)?;
+        out.flush()?;
+        analysis::write_coverage(&mut logging::stderr(), &coverage)?;
+        return Ok(());
+    }
+    let analysis = analysis::analyze(&segmenter, corpus, &mut out)?;
+    out.flush()?;
+    analysis::write_summary(&mut logging::stderr(), &analysis)?;
+    Ok(())
+}
+
+/// Segment the text of a gold corpus and print the boundary and word metrics of the
+/// model on it.
+///
+/// # Arguments
+/// * `args` - The arguments for the evaluate command [`EvaluateArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn evaluate(args: EvaluateArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+    let segmenter = Segmenter::new(language, Some(learner));
 
-    eprintln!("Feature extraction completed successfully.");
+    let corpus_format: CorpusFormat =
+        args.corpus_format.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let corpus = corpus::sentences(compression::open(&args.corpus_file)?, corpus_format)
+        .collect::<io::Result<Vec<String>>>()?;
+    let evaluation = evaluation::evaluate(&segmenter, corpus.iter().map(String::as_str));
+    if let Some(path) = &args.metrics_json {
+        write_json(path, &evaluation)?;
+    }
+    if let Some(path) = &args.error_report {
+        let errors = analysis::frequent_errors(&segmenter, corpus.iter().map(String::as_str));
+        let mut writer = compression::create(path)?;
+        analysis::write_error_report(&mut writer, &errors)?;
+        writer.finish()?;
+    }
+    evaluation::write_report(&mut io::stdout().lock(), &evaluation)?;
     Ok(())
 }
 
-/// Train a segmenter using the provided arguments.
-/// This function initializes a Trainer with the specified parameters,
-/// loads a model if specified, and trains the model using the features file.
+/// Measure how fast a model loads and segments a text, after warming up, and write the
+/// report to standard output.
 ///
 /// # Arguments
-/// * `args` - The arguments for the train command [`TrainArgs`].
+/// * `args` - The arguments for the bench command [`BenchArgs`].
 ///
 /// # Returns
 /// Returns a Result indicating success or failure.
-async fn train(args: TrainArgs) -> Result<(), Box<dyn Error>> {
-    let running = Arc::new(AtomicBool::new(true));
-    let r = running.clone();
+fn bench(args: BenchArgs) -> Result<(), Box<dyn Error>> {
+    let mut content = Vec::new();
+    compression::open(&args.model)?.read_to_end(&mut content)?;
+    let (load, learner) = bench::bench_model_load(&content, args.iterations)?;
+    let memory = bench::memory_footprint(&learner);
+    let config = PipelineConfig::from_model(&learner)?
+        .unwrap_or_else(|| PipelineConfig::for_model(&learner));
+    let language: Language = match &args.language {
+        Some(language) => language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?,
+        None => config.language,
+    };
+    let mut segmenter = Segmenter::new(language, Some(learner));
+    segmenter.set_feature_templates(config.feature_templates);
+    segmenter.set_latin_classes(config.latin_classes);
+    segmenter.check_model()?;
 
-    ctrlc::set_handler(move || {
-        if r.load(Ordering::SeqCst) {
-            r.store(false, Ordering::SeqCst);
-        } else {
-            std::process::exit(0);
+    let mut sentences = Vec::new();
+    for line in compression::open(&args.input)?.lines() {
+        let line = line?;
+        let line = config.normalizer.normalize(line.trim());
+        if !line.is_empty() {
+            sentences.push(line.into_owned());
         }
-    })?;
+    }
+    if sentences.is_empty() {
+        return Err(format!("No sentences in {}", args.input.display()).into());
+    }
+    status!("Benchmarking {} sentences, {} passes...", sentences.len(), args.iterations);
+
+    bench::warm_up(&segmenter, &sentences, args.warmup);
+    let throughput = bench::benchmark(&segmenter, &sentences, args.iterations);
+    let latency = bench::bench_latency(&segmenter, &sentences, args.iterations);
+    let report = bench::BenchReport {
+        load,
+        memory,
+        throughput,
+        latency,
+        peak_memory: bench::peak_memory(),
+    };
+    bench::write_report(&mut io::stdout().lock(), &report)?;
+    Ok(())
+}
 
-    let mut trainer =
-        Trainer::new(args.threshold, args.num_iterations, args.features_file.as_path())?;
+/// Segment a corpus with two models and write the sentences they segment differently
+/// to standard output, followed by their agreement on standard error. With `--gold`,
+/// also test whether their difference in accuracy on the gold corpus is significant.
+///
+/// # Arguments
+/// * `args` - The arguments for the compare command [`CompareArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn compare(args: CompareArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let mut segmenters = Vec::with_capacity(2);
+    for model_uri in [&args.left_model_uri, &args.right_model_uri] {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model(model_uri.as_str()).await?;
+        segmenters.push(Segmenter::new(language, Some(learner)));
+    }
 
-    if let Some(model_uri) = &args.load_model_uri {
-        trainer.load_model(model_uri).await?;
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    if !args.gold {
+        let corpus = compression::open(&args.corpus_file)?;
+        let comparison = compare::compare(&segmenters[0], &segmenters[1], corpus, &mut out)?;
+        out.flush()?;
+        compare::write_summary(&mut logging::stderr(), &comparison)?;
+        return Ok(());
     }
 
-    let metrics = trainer.train(running, args.model_file.as_path())?;
+    let gold = compression::read_to_string(&args.corpus_file)?;
+    let text: String = gold.lines().map(|line| line.replace(' ', "") + "\n").collect();
+    let comparison = compare::compare(&segmenters[0], &segmenters[1], text.as_bytes(), &mut out)?;
+    out.flush()?;
+    compare::write_summary(&mut logging::stderr(), &comparison)?;
+    let significance = compare::significance(
+        &segmenters[0],
+        &segmenters[1],
+        gold.as_bytes(),
+        args.samples,
+        args.seed,
+    )?;
+    compare::write_significance(&mut logging::stderr(), &significance)?;
+    Ok(())
+}
 
-    eprintln!("Result Metrics:");
-    eprintln!(
-        "  Accuracy: {:.2}% ( {} / {} )",
-        metrics.accuracy,
-        metrics.true_positives + metrics.true_negatives,
-        metrics.num_instances
-    );
-    eprintln!(
-        "  Precision: {:.2}% ( {} / {} )",
-        metrics.precision,
-        metrics.true_positives,
-        metrics.true_positives + metrics.false_positives
-    );
-    eprintln!(
-        "  Recall: {:.2}% ( {} / {} )",
-        metrics.recall,
-        metrics.true_positives,
-        metrics.true_positives + metrics.false_negatives
-    );
-    eprintln!(
-        "  Confusion Matrix:\n    True Positives: {}\n    False Positives: {}\n    False Negatives: {}\n    True Negatives: {}",
-        metrics.true_positives,
-        metrics.false_positives,
-        metrics.false_negatives,
-        metrics.true_negatives
+/// Write the number of features, bias, largest weights and weight distribution of a
+/// model to standard output.
+///
+/// # Arguments
+/// * `args` - The arguments for the inspect command [`InspectArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn inspect_model(args: InspectArgs) -> Result<(), Box<dyn Error>> {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(args.model_uri.as_str()).await?;
+
+    let inspection = inspect::inspect(&learner, args.top, args.bins);
+    inspect::write_inspection(&mut io::stdout().lock(), &inspection)?;
+    Ok(())
+}
+
+/// Write the features whose weights changed most between two models to standard output.
+///
+/// # Arguments
+/// * `args` - The arguments for the diff command [`DiffArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+async fn diff_models(args: DiffArgs) -> Result<(), Box<dyn Error>> {
+    let mut old = AdaBoost::new(0.01, 100);
+    old.load_model(args.old_model_uri.as_str()).await?;
+    let mut new = AdaBoost::new(0.01, 100);
+    new.load_model(args.new_model_uri.as_str()).await?;
+
+    let diff = inspect::diff(&old, &new, args.top);
+    inspect::write_diff(&mut io::stdout().lock(), &diff)?;
+    Ok(())
+}
+
+/// Check a model file and write a repaired, normalized copy of it.
+/// The problems found are reported on standard error.
+///
+/// # Arguments
+/// * `args` - The arguments for the fix-model command [`FixModelArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn fix_model(args: FixModelArgs) -> Result<(), Box<dyn Error>> {
+    let (repaired, report) = repair::repair_model(&std::fs::read(&args.model_file)?)?;
+    for issue in &report.issues {
+        status!("{}: {}", args.model_file.display(), issue);
+    }
+    if args.check {
+        if !report.is_clean() {
+            return Err(format!("Found {} problems in the model.", report.issues.len()).into());
+        }
+        status!("No problems found ({} features).", report.features);
+        return Ok(());
+    }
+    let output_file = args.output_file.unwrap_or(args.model_file);
+    if report.changed || !output_file.exists() {
+        std::fs::write(&output_file, repaired)?;
+    }
+    status!(
+        "Repaired model written to {} ({} problems fixed, {} features).",
+        output_file.display(),
+        report.issues.len(),
+        report.features
     );
+    Ok(())
+}
 
+/// Create or apply a model delta.
+///
+/// # Arguments
+/// * `args` - The arguments for the delta command [`DeltaArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn delta(args: DeltaArgs) -> Result<(), Box<dyn Error>> {
+    match args.command {
+        DeltaCommands::Create {
+            base_file,
+            target_file,
+            delta_file,
+        } => {
+            let base = std::fs::read_to_string(&base_file)?;
+            let target = std::fs::read_to_string(&target_file)?;
+            let patch = delta::diff(&base, &target)?;
+            std::fs::write(&delta_file, &patch)?;
+            status!(
+                "Delta written to {} ({} bytes, model {} bytes).",
+                delta_file.display(),
+                patch.len(),
+                target.len()
+            );
+        }
+        DeltaCommands::Apply {
+            output_file,
+            base_file,
+            delta_file,
+        } => {
+            let base = std::fs::read_to_string(&base_file)?;
+            let patch = std::fs::read_to_string(&delta_file)?;
+            let target = delta::apply_delta(&base, &patch)?;
+            let output_file = output_file.unwrap_or(base_file);
+            std::fs::write(&output_file, target)?;
+            status!("Updated model written to {}.", output_file.display());
+        }
+    }
     Ok(())
 }
 
-/// Segment a sentence using the trained model.
-/// This function loads the AdaBoost model from the specified file,
-/// reads sentences from standard input, segments them into words,
-/// and writes the segmented sentences to standard output.
+/// Generate a Markdown model card for a model.
+/// The card always describes the model itself; if a gold corpus is given,
+/// it also includes evaluation results and segmentation speed on that corpus.
 ///
 /// # Arguments
-/// * `args` - The arguments for the segment command [`SegmentArgs`].
+/// * `args` - The arguments for the model-card command [`ModelCardArgs`].
 ///
 /// # Returns
 /// Returns a Result indicating success or failure.
-async fn segment(args: SegmentArgs) -> Result<(), Box<dyn Error>> {
+async fn model_card(args: ModelCardArgs) -> Result<(), Box<dyn Error>> {
     let language: Language =
         args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
-    // AdaBoost parameters are not used for prediction; only the loaded model weights matter.
     let mut learner = AdaBoost::new(0.01, 100);
     learner.load_model(args.model_uri.as_str()).await?;
 
+    let name = args.model_uri.rsplit('/').next().unwrap_or(&args.model_uri).to_string();
+    let summary = ModelSummary::new(&learner);
     let segmenter = Segmenter::new(language, Some(learner));
-    let stdin = io::stdin();
-    let stdout = io::stdout();
-    let mut writer = io::BufWriter::new(stdout.lock());
 
-    for line in stdin.lock().lines() {
-        let line = line?;
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
+    let (evaluation, benchmark) = match &args.corpus_file {
+        Some(path) => {
+            let corpus = compression::read_to_string(path)?;
+            let gold: Vec<&str> = corpus.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+            let evaluation = model_card::evaluate(&segmenter, gold.iter().copied());
+            let benchmark = (args.benchmark_iterations > 0).then(|| {
+                let sentences: Vec<String> = gold.iter().map(|l| l.replace(' ', "")).collect();
+                model_card::benchmark(&segmenter, &sentences, args.benchmark_iterations)
+            });
+            (Some(evaluation), benchmark)
         }
-        let tokens = segmenter.segment(line);
-        writeln!(writer, "{}", tokens.join(" "))?;
-    }
+        None => (None, None),
+    };
 
+    let card = ModelCard {
+        name,
+        language,
+        summary,
+        evaluation,
+        benchmark,
+    };
+    match &args.output_file {
+        Some(path) => {
+            let mut writer = io::BufWriter::new(std::fs::File::create(path)?);
+            card.write_markdown(&mut writer)?;
+            writer.flush()?;
+            status!("Model card written to {}.", path.display());
+        }
+        None => {
+            let mut writer = io::BufWriter::new(io::stdout().lock());
+            card.write_markdown(&mut writer)?;
+            writer.flush()?;
+        }
+    }
     Ok(())
 }
 
-/// Split text into sentences using ICU4X SentenceSegmenter (Unicode UAX #29).
-/// This function reads text from standard input (one paragraph per line),
-/// splits each line into sentences, and writes one sentence per line to standard output.
+/// Serve a model to clients over a Unix domain socket or TCP.
+/// The model is shared by all connections and reloaded on SIGHUP (Unix only) or with the
+/// `reload` method; see [`serve`](crate::serve) for the protocol.
 ///
 /// # Arguments
-/// * `_args` - The arguments for the split-sentences command [`SplitSentencesArgs`].
+/// * `args` - The arguments for the serve command [`ServeArgs`].
 ///
 /// # Returns
 /// Returns a Result indicating success or failure.
-fn split_sentences(_args: SplitSentencesArgs) -> Result<(), Box<dyn Error>> {
-    use icu_segmenter::SentenceSegmenter;
-    use icu_segmenter::options::SentenceBreakInvariantOptions;
+async fn serve(args: ServeArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    let limits = serve::Limits {
+        max_concurrent: args.max_concurrent,
+        max_request_bytes: args.max_request_bytes,
+        batch_size: args.batch_size,
+        batch_delay: std::time::Duration::from_micros(args.batch_delay_us),
+    };
+    let tokens = match &args.token_file {
+        Some(path) => {
+            let tokens: Vec<String> = std::fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect();
+            if tokens.is_empty() {
+                return Err(format!("No tokens found in {}", path.display()).into());
+            }
+            tokens
+        }
+        None => Vec::new(),
+    };
+    let endpoint = match (args.unix, args.tcp) {
+        (Some(path), _) => serve::Endpoint::Unix(path),
+        (None, Some(addr)) => serve::Endpoint::Tcp {
+            addr,
+            tls: args.tls_cert.zip(args.tls_key).map(|(cert, key)| serve::TlsFiles { cert, key }),
+        },
+        (None, None) => unreachable!("clap requires one of --unix or --tcp"),
+    };
 
-    let segmenter = SentenceSegmenter::new(SentenceBreakInvariantOptions::default());
-    let stdin = io::stdin();
-    let stdout = io::stdout();
-    let mut writer = io::BufWriter::new(stdout.lock());
+    let server = serve::Server::new(language, args.model_uri, args.reload_uri, limits)
+        .await?
+        .with_tokens(tokens);
+    serve::run(server, endpoint).await
+}
 
-    for line in stdin.lock().lines() {
-        let line = line?;
-        let line = line.trim();
-        if line.is_empty() {
+/// Create a project directory for training a model.
+///
+/// # Arguments
+/// * `args` - The arguments for the init command [`InitArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn init(args: InitArgs) -> Result<(), Box<dyn Error>> {
+    let language: Language =
+        args.language.parse().map_err(|e: String| Box::<dyn Error>::from(e))?;
+    for path in project::init(&args.dir, language)? {
+        status!("Created {}", path.display());
+    }
+    status!(
+        "Replace the sample corpora in {} and run `litsea build`.",
+        args.dir.join("corpus").display()
+    );
+    Ok(())
+}
+
+/// Run the build tasks of a project in dependency order. Each task runs this
+/// executable with the arguments of the task, in the directory of the tasks file.
+///
+/// # Arguments
+/// * `args` - The arguments for the build command [`BuildArgs`].
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+fn build(args: BuildArgs) -> Result<(), Box<dyn Error>> {
+    let tasks = Tasks::load(&args.tasks_file)?;
+    if args.list {
+        for (name, task) in &tasks.tasks {
+            let marker = if tasks.default.contains(name) { " (default)" } else { "" };
+            println!("{}{}\t{}", name, marker, task.description.as_deref().unwrap_or(""));
+        }
+        return Ok(());
+    }
+    let dir = match args.tasks_file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let exe = std::env::current_exe()?;
+    for name in tasks.plan(&args.tasks)? {
+        let task = &tasks.tasks[name];
+        if !args.force && task.is_up_to_date(dir) {
+            status!("==> {} (up to date)", name);
             continue;
         }
-
-        let mut breakpoints: Vec<usize> = segmenter.segment_str(line).collect();
-        // Ensure the first breakpoint is 0 so no leading text is lost.
-        if breakpoints.first() != Some(&0) {
-            breakpoints.insert(0, 0);
+        status!("==> {}: litsea {}", name, task.run.join(" "));
+        if args.dry_run {
+            continue;
         }
-        for window in breakpoints.windows(2) {
-            let sentence = line[window[0]..window[1]].trim();
-            if !sentence.is_empty() {
-                writeln!(writer, "{}", sentence)?;
-            }
+        let status = std::process::Command::new(&exe).args(&task.run).current_dir(dir).status()?;
+        if !status.success() {
+            return Err(format!("Task '{}' failed ({}).", name, status).into());
         }
     }
-
     Ok(())
 }
 
 async fn run() -> Result<(), Box<dyn std::error::Error>> {
     let args = CommandArgs::parse();
+    logging::init(args.quiet, args.verbose)?;
 
     match args.command {
         Commands::Extract(args) => extract(args),
+        Commands::ShrinkFeatures(args) => shrink_features(args),
+        Commands::SortFeatures(args) => sort_features(args),
         Commands::Train(args) => train(args).await,
+        Commands::SelfTrain(args) => self_train(args).await,
+        Commands::UpdateModel(args) => update_model(args).await,
         Commands::Segment(args) => segment(args).await,
+        Commands::SegmentFile(args) => {
+            segment(SegmentArgs {
+                options: args.options,
+                input: Some(args.input),
+                output: Some(args.output),
+                model_uri: Some(args.model_uri),
+            })
+            .await
+        }
+        Commands::TrainTagger(args) => train_tagger(args),
+        Commands::Tag(args) => tag(args).await,
+        Commands::Vocab(args) => vocab(args),
+        Commands::Route(args) => route(args).await,
         Commands::SplitSentences(args) => split_sentences(args),
+        Commands::ExportJs(args) => export_js_model(args).await,
+        Commands::Import(args) => import(args),
+        Commands::ImportCorpus(args) => import_corpus(args),
+        Commands::Export(args) => export(args).await,
+        Commands::ModelCard(args) => model_card(args).await,
+        Commands::Analyze(args) => analyze(args).await,
+        Commands::Evaluate(args) => evaluate(args).await,
+        Commands::Bench(args) => bench(args),
+        Commands::Compare(args) => compare(args).await,
+        Commands::Inspect(args) => inspect_model(args).await,
+        Commands::Diff(args) => diff_models(args).await,
+        Commands::Serve(args) => serve(args).await,
+        Commands::Store(args) => store(args).await,
+        Commands::Fetch(args) => fetch(args).await,
+        Commands::Sign(args) => sign(args),
+        Commands::FixModel(args) => fix_model(args),
+        Commands::Delta(args) => delta(args),
+        Commands::Editor(args) => editor(args).await,
+        Commands::Init(args) => init(args),
+        Commands::Build(args) => build(args),
     }
 }
 
@@ -266,3 +2901,27 @@ async fn main() {
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment_args(args: &[&str]) -> Result<SegmentArgs, clap::Error> {
+        let args = CommandArgs::try_parse_from(
+            ["litsea", "segment"].iter().chain(args).chain(&["model.model"]),
+        )?;
+        match args.command {
+            Commands::Segment(args) => Ok(args),
+            command => panic!("unexpected command: {:?}", command),
+        }
+    }
+
+    #[test]
+    fn test_segment_bias() {
+        // Negative biases are values, not flags.
+        let args = segment_args(&["--bias", "-0.5"]).unwrap();
+        assert_eq!(args.options.bias, Some(-0.5));
+        let args = segment_args(&[]).unwrap();
+        assert_eq!(args.options.bias, None);
+    }
+}
@@ -0,0 +1,85 @@
+//! JSON-RPC 2.0 message types shared by the `serve` and `editor` commands.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// A JSON-RPC request. Requests without an `id` are notifications and get no response.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub jsonrpc: Option<String>,
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// A JSON-RPC error object.
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A JSON-RPC response carrying either a result or an error.
+#[derive(Debug, Serialize)]
+pub struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Response {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Parses one line of input as a request.
+///
+/// # Returns
+/// The request, or the error response to send back if the line is not valid JSON, not a
+/// request, or not JSON-RPC 2.0.
+pub fn parse_request(line: &str) -> Result<Request, Response> {
+    let request: Request = serde_json::from_str(line).map_err(|e| {
+        let error = if e.is_syntax() || e.is_eof() {
+            RpcError::new(PARSE_ERROR, format!("Parse error: {}", e))
+        } else {
+            RpcError::new(INVALID_REQUEST, format!("Invalid request: {}", e))
+        };
+        Response::new(Value::Null, Err(error))
+    })?;
+    if request.jsonrpc.as_deref().is_some_and(|v| v != "2.0") {
+        return Err(Response::new(
+            request.id.unwrap_or(Value::Null),
+            Err(RpcError::new(INVALID_REQUEST, "Unsupported jsonrpc version")),
+        ));
+    }
+    Ok(request)
+}
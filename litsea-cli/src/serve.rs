@@ -0,0 +1,743 @@
+//! Server speaking newline-delimited JSON-RPC 2.0 over a Unix domain socket or TCP.
+//!
+//! Each line sent by a client is one request, and each response is written back as one
+//! line on the same connection. A single model is loaded at startup and shared by all
+//! connections. The model can be reloaded without dropping connections by calling the
+//! `reload` method or, on Unix, by sending `SIGHUP` to the process. Unix domain sockets
+//! are only available on Unix; TCP works on every platform.
+//!
+//! Segmentation runs on blocking worker threads, at most [`Limits::max_concurrent`] jobs at
+//! a time. Small `segment` requests from all connections are grouped into micro-batches so
+//! that many tiny requests share one job, while oversized request lines are rejected
+//! without being buffered.
+//!
+//! TCP connections can be encrypted with TLS, and clients can be required to present an
+//! API token with the `authenticate` method before any other call.
+//!
+//! Connections are not closed when idle: clients such as editor integrations keep one
+//! connection open for a whole session and may not send anything for hours, and an idle
+//! connection only holds a task and its read buffer. Only the TLS handshake has a time
+//! limit, since no request can be read before it completes.
+
+use std::error::Error;
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use tokio::signal::unix::{Signal, SignalKind, signal};
+use tokio::sync::{Semaphore, mpsc, oneshot};
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::crypto::aws_lc_rs;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::server::TlsStream;
+
+use litsea::adaboost::AdaBoost;
+use litsea::language::Language;
+use litsea::segmenter::Segmenter;
+use litsea::util::sha256_hex;
+
+use crate::rpc::{
+    self, INTERNAL_ERROR, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, Request, Response,
+    RpcError,
+};
+
+const UNAUTHORIZED: i64 = -32001;
+
+/// Parameters of the `authenticate` method.
+#[derive(Debug, Deserialize)]
+struct AuthenticateParams {
+    token: String,
+}
+
+/// Parameters of the `segment` method.
+#[derive(Debug, Deserialize)]
+struct SegmentParams {
+    text: String,
+}
+
+/// Texts up to this many bytes are segmented in micro-batches.
+const BATCHABLE_TEXT_BYTES: usize = 1024;
+
+/// How long a TLS client may take to complete the handshake before it is disconnected.
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before accepting again after a failed accept, so that a lack of
+/// file descriptors does not make the accept loop spin.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Limits protecting the server from clients sending large or many requests.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Maximum number of segmentation jobs running at once.
+    pub max_concurrent: usize,
+    /// Maximum size of a request line in bytes.
+    pub max_request_bytes: usize,
+    /// Maximum number of small requests segmented together in one job (1 disables batching).
+    pub batch_size: usize,
+    /// How long to wait for more small requests before starting a batch.
+    pub batch_delay: Duration,
+}
+
+/// A small `segment` request waiting to be batched.
+struct Job {
+    loaded: Arc<Loaded>,
+    text: String,
+    reply: oneshot::Sender<Vec<String>>,
+}
+
+/// A line read from a connection.
+enum Line {
+    Text(String),
+    TooLong,
+}
+
+/// A loaded model together with the URI it was loaded from.
+struct Loaded {
+    segmenter: Segmenter,
+    model_uri: String,
+}
+
+/// Shared state of the server.
+pub struct Server {
+    language: Language,
+    reload_uri: String,
+    current: RwLock<Arc<Loaded>>,
+    limits: Limits,
+    permits: Arc<Semaphore>,
+    batches: mpsc::Sender<Job>,
+    /// The SHA-256 digests of the accepted API tokens.
+    tokens: Vec<String>,
+}
+
+impl Server {
+    /// Loads the model and creates a server answering requests with it.
+    ///
+    /// # Arguments
+    /// * `language` - The language for character type classification.
+    /// * `model_uri` - The URI of the model to serve.
+    /// * `reload_uri` - The URI to load on reload. Defaults to `model_uri`.
+    /// * `limits` - The request limits to enforce.
+    ///
+    /// # Errors
+    /// Returns an error if the model cannot be loaded.
+    pub async fn new(
+        language: Language,
+        model_uri: String,
+        reload_uri: Option<String>,
+        limits: Limits,
+    ) -> std::io::Result<Self> {
+        let loaded = load(language, model_uri.clone()).await?;
+        let permits = Arc::new(Semaphore::new(limits.max_concurrent.max(1)));
+        let (batches, jobs) = mpsc::channel(limits.batch_size.max(1) * 4);
+        tokio::spawn(run_batches(jobs, Arc::clone(&permits), limits.clone()));
+        Ok(Server {
+            language,
+            reload_uri: reload_uri.unwrap_or(model_uri),
+            current: RwLock::new(Arc::new(loaded)),
+            limits,
+            permits,
+            batches,
+            tokens: Vec::new(),
+        })
+    }
+
+    /// Requires clients to authenticate with one of the given tokens.
+    /// With no tokens, every connection is trusted.
+    ///
+    /// # Arguments
+    /// * `tokens` - The accepted API tokens.
+    pub fn with_tokens(mut self, tokens: Vec<String>) -> Self {
+        self.tokens = tokens.iter().map(|token| sha256_hex(token.as_bytes())).collect();
+        self
+    }
+
+    /// Checks a token against the accepted ones by comparing the fixed-length digests of
+    /// both without short-circuiting, so response timing reveals neither the length of
+    /// the accepted tokens nor how much of a token matched.
+    fn is_valid_token(&self, token: &str) -> bool {
+        let digest = sha256_hex(token.as_bytes());
+        self.tokens.iter().fold(false, |found, accepted| {
+            let diff = accepted.bytes().zip(digest.bytes()).fold(0u8, |d, (a, b)| d | (a ^ b));
+            found | (diff == 0)
+        })
+    }
+
+    /// Returns the currently served model.
+    fn current(&self) -> Arc<Loaded> {
+        // A poisoned lock still holds a fully constructed model, since it is only ever replaced whole.
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Loads the model from the reload URI and swaps it in.
+    /// Requests already in progress finish with the previous model; if loading fails,
+    /// the previous model stays in service.
+    ///
+    /// # Returns
+    /// Returns the URI of the newly loaded model.
+    async fn reload(&self) -> std::io::Result<String> {
+        let loaded = load(self.language, self.reload_uri.clone()).await?;
+        let model_uri = loaded.model_uri.clone();
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(loaded);
+        Ok(model_uri)
+    }
+
+    /// Reloads the model and logs the outcome, for reloads not triggered by a client.
+    async fn reload_and_log(&self) {
+        match self.reload().await {
+            Ok(uri) => status!("Reloaded model from {}.", uri),
+            Err(e) => eprintln!("Failed to reload model: {}", e),
+        }
+    }
+
+    /// Handles one line of input and returns the line to send back, if any.
+    async fn handle_line(&self, line: &str, authenticated: &mut bool) -> Option<String> {
+        let response = match rpc::parse_request(line) {
+            Err(response) => Some(response),
+            Ok(request) => self.handle_request(request, authenticated).await,
+        };
+        // Serializing our own response types cannot fail.
+        response.map(|r| serde_json::to_string(&r).unwrap_or_default())
+    }
+
+    async fn handle_request(&self, request: Request, authenticated: &mut bool) -> Option<Response> {
+        let outcome = if request.method == "authenticate" {
+            self.authenticate(request.params, authenticated)
+        } else if *authenticated {
+            self.dispatch(&request.method, request.params).await
+        } else {
+            Err(RpcError::new(UNAUTHORIZED, "Unauthorized: call authenticate first"))
+        };
+        request.id.map(|id| Response::new(id, outcome))
+    }
+
+    fn authenticate(&self, params: Value, authenticated: &mut bool) -> Result<Value, RpcError> {
+        let params: AuthenticateParams = serde_json::from_value(params)
+            .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))?;
+        if self.tokens.is_empty() || self.is_valid_token(&params.token) {
+            *authenticated = true;
+            Ok(json!({ "authenticated": true }))
+        } else {
+            Err(RpcError::new(UNAUTHORIZED, "Unauthorized: invalid token"))
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        match method {
+            "segment" => {
+                let params: SegmentParams = serde_json::from_value(params)
+                    .map_err(|e| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", e)))?;
+                Ok(json!({ "tokens": self.segment(params.text).await? }))
+            }
+            "info" => Ok(json!({
+                "language": self.language.to_string(),
+                "model": self.current().model_uri,
+                "version": litsea::version(),
+            })),
+            "reload" => {
+                let uri = self.reload().await.map_err(|e| {
+                    RpcError::new(INTERNAL_ERROR, format!("Failed to reload model: {}", e))
+                })?;
+                status!("Reloaded model from {}.", uri);
+                Ok(json!({ "model": uri }))
+            }
+            other => Err(RpcError::new(METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+        }
+    }
+
+    /// Segments a text on a worker thread, batching it with other small texts.
+    async fn segment(&self, text: String) -> Result<Vec<String>, RpcError> {
+        let loaded = self.current();
+        let tokens = if self.limits.batch_size > 1 && text.len() <= BATCHABLE_TEXT_BYTES {
+            let (reply, tokens) = oneshot::channel();
+            let job = Job {
+                loaded,
+                text,
+                reply,
+            };
+            // The batcher only stops when the server is dropped.
+            self.batches.send(job).await.ok();
+            tokens.await.ok()
+        } else {
+            let permit = Arc::clone(&self.permits).acquire_owned().await.ok();
+            let tokens = tokio::task::spawn_blocking(move || {
+                let _permit = permit;
+                loaded.segmenter.segment(&text)
+            });
+            tokens.await.ok()
+        };
+        tokens.ok_or_else(|| RpcError::new(INTERNAL_ERROR, "Segmentation failed"))
+    }
+
+    /// Serves one client connection until it is closed.
+    async fn handle_connection<S>(&self, stream: S) -> std::io::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (reader, mut writer) = tokio::io::split(stream);
+        let mut reader = BufReader::new(reader);
+        let mut authenticated = self.tokens.is_empty();
+
+        while let Some(line) = read_line(&mut reader, self.limits.max_request_bytes).await? {
+            let response = match line {
+                Line::Text(line) if line.trim().is_empty() => continue,
+                Line::Text(line) => self.handle_line(line.trim(), &mut authenticated).await,
+                Line::TooLong => {
+                    let error = RpcError::new(
+                        INVALID_REQUEST,
+                        format!(
+                            "Request exceeds the limit of {} bytes",
+                            self.limits.max_request_bytes
+                        ),
+                    );
+                    serde_json::to_string(&Response::new(Value::Null, Err(error))).ok()
+                }
+            };
+            if let Some(mut response) = response {
+                response.push('\n');
+                writer.write_all(response.as_bytes()).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads one line, discarding it without buffering if it exceeds `limit` bytes.
+///
+/// # Returns
+/// Returns `None` at the end of the stream.
+async fn read_line<R>(reader: &mut R, limit: usize) -> std::io::Result<Option<Line>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = Vec::new();
+    let mut too_long = false;
+    loop {
+        let buf = reader.fill_buf().await?;
+        if buf.is_empty() {
+            if line.is_empty() && !too_long {
+                return Ok(None);
+            }
+            break;
+        }
+        let (chunk, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (&buf[..i], Some(i + 1)),
+            None => (buf, None),
+        };
+        if !too_long && line.len() + chunk.len() > limit {
+            too_long = true;
+            line = Vec::new();
+        }
+        if !too_long {
+            line.extend_from_slice(chunk);
+        }
+        let consumed = done.unwrap_or(buf.len());
+        reader.consume(consumed);
+        if done.is_some() {
+            break;
+        }
+    }
+    Ok(Some(if too_long {
+        Line::TooLong
+    } else {
+        Line::Text(String::from_utf8_lossy(&line).into_owned())
+    }))
+}
+
+/// Collects small segmentation jobs into batches and runs each batch as one worker job.
+async fn run_batches(mut jobs: mpsc::Receiver<Job>, permits: Arc<Semaphore>, limits: Limits) {
+    while let Some(first) = jobs.recv().await {
+        let mut batch = vec![first];
+        let deadline = tokio::time::sleep(limits.batch_delay);
+        tokio::pin!(deadline);
+        while batch.len() < limits.batch_size {
+            tokio::select! {
+                job = jobs.recv() => match job {
+                    Some(job) => batch.push(job),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            }
+        }
+
+        let Ok(permit) = Arc::clone(&permits).acquire_owned().await else {
+            return;
+        };
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            for job in batch {
+                // The client may have disconnected while waiting.
+                job.reply.send(job.loaded.segmenter.segment(&job.text)).ok();
+            }
+        });
+    }
+}
+
+/// Loads a model and builds a segmenter for it.
+async fn load(language: Language, model_uri: String) -> std::io::Result<Loaded> {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model(&model_uri).await?;
+    Ok(Loaded {
+        segmenter: Segmenter::new(language, Some(learner)),
+        model_uri,
+    })
+}
+
+/// Where the server listens for connections.
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    /// A Unix domain socket at the given path. Binding it fails on platforms other than Unix.
+    Unix(PathBuf),
+    /// A TCP address, optionally with TLS.
+    Tcp { addr: String, tls: Option<TlsFiles> },
+}
+
+/// PEM files holding the TLS certificate chain and private key.
+#[derive(Debug, Clone)]
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// A bound listener ready to accept connections.
+enum Listener {
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+    Tcp(TcpListener, Option<TlsAcceptor>),
+}
+
+/// Binds the socket, removing a stale socket file left behind by a previous run.
+#[cfg(unix)]
+async fn bind_unix(path: &Path) -> Result<UnixListener, Box<dyn Error>> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(format!("Socket {} is already in use", path.display()).into());
+        }
+        std::fs::remove_file(path)?;
+    }
+    Ok(UnixListener::bind(path)?)
+}
+
+/// Builds a TLS acceptor from PEM certificate and key files.
+fn tls_acceptor(files: &TlsFiles) -> Result<TlsAcceptor, Box<dyn Error>> {
+    let certs = CertificateDer::pem_file_iter(&files.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to read certificate {}: {}", files.cert.display(), e))?;
+    let key = PrivateKeyDer::from_pem_file(&files.key)
+        .map_err(|e| format!("Failed to read private key {}: {}", files.key.display(), e))?;
+    let config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+async fn bind(endpoint: Endpoint) -> Result<Listener, Box<dyn Error>> {
+    match endpoint {
+        #[cfg(unix)]
+        Endpoint::Unix(path) => Ok(Listener::Unix(bind_unix(&path).await?, path)),
+        #[cfg(not(unix))]
+        Endpoint::Unix(path) => Err(format!(
+            "Cannot listen on {}: Unix domain sockets are not supported on this platform; \
+             use --tcp instead",
+            path.display()
+        )
+        .into()),
+        Endpoint::Tcp { addr, tls } => {
+            let acceptor = tls.as_ref().map(tls_acceptor).transpose()?;
+            Ok(Listener::Tcp(TcpListener::bind(&addr).await?, acceptor))
+        }
+    }
+}
+
+/// Notifies the server of `SIGHUP`, which asks it to reload the model.
+///
+/// Platforms other than Unix have no such signal, and [`Hangup::recv`] never completes there.
+struct Hangup {
+    #[cfg(unix)]
+    signal: Signal,
+}
+
+impl Hangup {
+    #[cfg(unix)]
+    fn new() -> std::io::Result<Self> {
+        Ok(Hangup {
+            signal: signal(SignalKind::hangup())?,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn new() -> std::io::Result<Self> {
+        Ok(Hangup {})
+    }
+
+    /// Waits for the next `SIGHUP`.
+    async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+/// Spawns a task serving one connection.
+fn spawn_connection<S>(server: &Arc<Server>, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let server = Arc::clone(server);
+    tokio::spawn(async move {
+        match server.handle_connection(stream).await {
+            // TLS clients often close the socket without a close_notify alert.
+            Err(e) if e.kind() != std::io::ErrorKind::UnexpectedEof => {
+                eprintln!("Connection error: {}", e)
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Runs the server until interrupted with Ctrl-C. On Unix, `SIGHUP` reloads the model.
+///
+/// # Arguments
+/// * `server` - The server state shared by all connections.
+/// * `endpoint` - Where to listen for connections.
+///
+/// # Errors
+/// Returns an error if the listener cannot be bound or TLS cannot be set up, or if a Unix
+/// domain socket is requested on a platform other than Unix.
+pub async fn run(server: Server, endpoint: Endpoint) -> Result<(), Box<dyn Error>> {
+    let listener = bind(endpoint).await?;
+    if matches!(listener, Listener::Tcp(..)) && server.tokens.is_empty() {
+        eprintln!("Warning: listening on TCP without authentication; any client can connect.");
+    }
+    let server = Arc::new(server);
+    let mut hangup = Hangup::new()?;
+
+    match &listener {
+        #[cfg(unix)]
+        Listener::Unix(_, path) => status!("Listening on {}.", path.display()),
+        Listener::Tcp(l, tls) => status!(
+            "Listening on {}{}.",
+            l.local_addr()?,
+            if tls.is_some() { " (TLS)" } else { "" }
+        ),
+    }
+
+    loop {
+        tokio::select! {
+            accepted = accept(&listener, &server) => {
+                // Failures such as running out of file descriptors or a client aborting
+                // the connection only concern that connection.
+                if let Err(e) = accepted {
+                    eprintln!("Failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                }
+            }
+            _ = hangup.recv() => {
+                let server = Arc::clone(&server);
+                tokio::spawn(async move { server.reload_and_log().await });
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    #[cfg(unix)]
+    if let Listener::Unix(_, path) = &listener {
+        std::fs::remove_file(path)?;
+    }
+    status!("Server stopped.");
+    Ok(())
+}
+
+/// Completes the TLS handshake of an accepted connection, giving up on clients that do
+/// not complete it within `timeout`.
+///
+/// # Errors
+/// Returns a [`std::io::ErrorKind::TimedOut`] error if the handshake times out, or the
+/// error of the handshake if it fails.
+async fn handshake<S>(
+    acceptor: &TlsAcceptor,
+    stream: S,
+    timeout: Duration,
+) -> std::io::Result<TlsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    tokio::time::timeout(timeout, acceptor.accept(stream))
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "TLS handshake timed out"))?
+}
+
+/// Accepts one connection and spawns a task serving it.
+async fn accept(listener: &Listener, server: &Arc<Server>) -> std::io::Result<()> {
+    match listener {
+        #[cfg(unix)]
+        Listener::Unix(l, _) => {
+            let (stream, _) = l.accept().await?;
+            spawn_connection(server, stream);
+        }
+        Listener::Tcp(l, None) => {
+            let (stream, _) = l.accept().await?;
+            spawn_connection(server, stream);
+        }
+        Listener::Tcp(l, Some(acceptor)) => {
+            let (stream, peer) = l.accept().await?;
+            let acceptor = acceptor.clone();
+            let server = Arc::clone(server);
+            // Handshake in the connection task so a slow client cannot block accepting.
+            tokio::spawn(async move {
+                match handshake(&acceptor, stream, TLS_HANDSHAKE_TIMEOUT).await {
+                    Ok(stream) => spawn_connection(&server, stream),
+                    Err(e) => eprintln!("TLS handshake with {} failed: {}", peer, e),
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+    use tokio_rustls::rustls::sign::CertifiedKey;
+
+    /// Reads all lines of `input` with [`read_line`], as `Some(text)` or `None` for
+    /// [`Line::TooLong`].
+    async fn read_lines(input: &[u8], limit: usize) -> Vec<Option<String>> {
+        let mut reader = BufReader::new(input);
+        let mut lines = Vec::new();
+        while let Some(line) = read_line(&mut reader, limit).await.unwrap() {
+            lines.push(match line {
+                Line::Text(text) => Some(text),
+                Line::TooLong => None,
+            });
+        }
+        lines
+    }
+
+    #[tokio::test]
+    async fn test_read_line_limit() {
+        // Under and at the limit, the newline not counting.
+        assert_eq!(
+            read_lines(b"abc\nabcd\n", 4).await,
+            [Some("abc".to_string()), Some("abcd".to_string())]
+        );
+        // Over the limit, the rest of the line is discarded and the next one is read.
+        assert_eq!(read_lines(b"abcde\nab\n", 4).await, [None, Some("ab".to_string())]);
+        // A last line without a newline is still checked against the limit.
+        assert_eq!(
+            read_lines(b"ab\nabcd", 4).await,
+            [Some("ab".to_string()), Some("abcd".to_string())]
+        );
+        assert_eq!(read_lines(b"ab\nabcde", 4).await, [Some("ab".to_string()), None]);
+        assert_eq!(read_lines(b"", 4).await, []);
+    }
+
+    #[tokio::test]
+    async fn test_read_line_split_reads() {
+        // A line over the limit is detected across reads of a small buffer.
+        let mut reader = BufReader::with_capacity(2, &b"abcdef\nxy\n"[..]);
+        assert!(matches!(read_line(&mut reader, 4).await.unwrap(), Some(Line::TooLong)));
+        assert!(
+            matches!(read_line(&mut reader, 4).await.unwrap(), Some(Line::Text(t)) if t == "xy")
+        );
+        assert!(read_line(&mut reader, 4).await.unwrap().is_none());
+    }
+
+    /// A certificate resolver without certificates, for handshakes that never get as far
+    /// as needing one.
+    #[derive(Debug)]
+    struct NoCertificate;
+
+    impl ResolvesServerCert for NoCertificate {
+        fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+            None
+        }
+    }
+
+    /// Returns a server for the bundled RWCP model accepting the token `secret-token`.
+    async fn server() -> Server {
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model");
+        let limits = Limits {
+            max_concurrent: 1,
+            max_request_bytes: 1024,
+            batch_size: 1,
+            batch_delay: Duration::ZERO,
+        };
+        Server::new(Language::Japanese, model.to_string(), None, limits)
+            .await
+            .unwrap()
+            .with_tokens(vec!["secret-token".to_string()])
+    }
+
+    /// Sends a request and returns the response.
+    async fn call(server: &Server, method: &str, params: Value, authenticated: &mut bool) -> Value {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let response = server.handle_line(&request.to_string(), authenticated).await.unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_token_authentication() {
+        let server = server().await;
+        assert!(server.is_valid_token("secret-token"));
+        assert!(!server.is_valid_token("secret-tokeN"));
+        assert!(!server.is_valid_token("secret"));
+        assert!(!server.is_valid_token("secret-token-and-more"));
+        assert!(!server.is_valid_token(""));
+
+        // Without a token, nothing but authenticate is answered.
+        let mut authenticated = false;
+        let segment = json!({ "text": "これはテストです。" });
+        let response = call(&server, "segment", segment.clone(), &mut authenticated).await;
+        assert_eq!(response["error"]["code"], UNAUTHORIZED);
+        let response = call(&server, "authenticate", json!({}), &mut authenticated).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+        assert!(!authenticated);
+
+        // A wrong token, and one of the same length differing only in its last byte.
+        for token in ["wrong", "secret-tokeN"] {
+            let params = json!({ "token": token });
+            let response = call(&server, "authenticate", params, &mut authenticated).await;
+            assert_eq!(response["error"]["code"], UNAUTHORIZED);
+            assert!(!authenticated);
+            let response = call(&server, "segment", segment.clone(), &mut authenticated).await;
+            assert_eq!(response["error"]["code"], UNAUTHORIZED);
+        }
+
+        let params = json!({ "token": "secret-token" });
+        let response = call(&server, "authenticate", params, &mut authenticated).await;
+        assert_eq!(response["result"]["authenticated"], true);
+        assert!(authenticated);
+        let response = call(&server, "segment", segment, &mut authenticated).await;
+        assert!(response["result"]["tokens"].is_array(), "{}", response);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_handshake_timeout() {
+        let config = ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(NoCertificate));
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        // The client end stays open but never sends a ClientHello.
+        let (_client, server) = tokio::io::duplex(1024);
+        let err = handshake(&acceptor, server, TLS_HANDSHAKE_TIMEOUT).await.err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
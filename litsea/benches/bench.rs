@@ -79,7 +79,7 @@ fn bench_add_corpus(c: &mut Criterion) {
     });
 }
 
-/// Benchmarks `char_type_patterns()` which includes regex compilation cost on every call.
+/// Benchmarks `char_type_patterns()`, which builds the code point tables on every call.
 /// This measures the full cost of creating patterns, not just matching.
 fn bench_char_type_patterns(c: &mut Criterion) {
     c.bench_function("char_type_patterns_japanese", |b| {
@@ -109,7 +109,7 @@ fn bench_predict(c: &mut Criterion) {
     let attrs = segmenter.get_attributes(4, &tags, &chars, &types);
 
     c.bench_function("predict", |b| {
-        b.iter(|| segmenter.learner.predict(black_box(attrs.clone())));
+        b.iter(|| segmenter.learner().predict(black_box(attrs.clone())));
     });
 }
 
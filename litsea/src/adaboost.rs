@@ -1,17 +1,434 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
-use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "train")]
+use core::ops::Range;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::io::{BufRead, Write};
+#[cfg(feature = "std")]
 use std::path::Path;
-use std::str::FromStr;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
 
+#[cfg(feature = "std")]
+use crate::compression;
+#[cfg(feature = "train")]
+use crate::control::TrainControl;
+use crate::error::LitseaError;
+#[cfg(feature = "mmap")]
+use crate::instance_file::{self, InstanceFileWriter, MappedInstances};
+use crate::integrity;
+use crate::model_info;
+#[cfg(feature = "train")]
+use crate::progress::{ProgressHandler, TrainingProgress};
+#[cfg(feature = "std")]
+use crate::quantize::QuantizationError;
+use crate::quantize::{self, Quantization};
+#[cfg(feature = "train")]
+use crate::sparse::SparseMatrix;
+#[cfg(feature = "std")]
 use crate::util::ModelScheme;
+#[cfg(feature = "train")]
+use crate::util::{SplitMix64, for_each_chunk_mut, map_chunks_limited};
 
-type Label = i8;
+/// Position of a feature in the model. The training instances hold one per feature
+/// occurrence, in a `SparseMatrix`, so they take half the memory of `usize` indices on
+/// 64-bit targets.
+type FeatureId = u32;
+
+/// Index of each feature in the model. `HashMap` needs `std`, so `no_std` builds use a
+/// `BTreeMap` instead.
+#[cfg(feature = "std")]
+type FeatureIndex = std::collections::HashMap<Box<str>, FeatureId>;
+#[cfg(not(feature = "std"))]
+type FeatureIndex = BTreeMap<Box<str>, FeatureId>;
+
+/// Feature names stored back to back in a single string, so that the names of a model
+/// take two allocations instead of one per feature.
+#[derive(Debug, Default, Clone)]
+struct FeatureTable {
+    text: String,
+    /// End offset of each name in `text`.
+    ends: Vec<usize>,
+}
+
+impl FeatureTable {
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    #[cfg(test)]
+    fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the name of the feature at `id`.
+    fn get(&self, id: usize) -> &str {
+        let start = if id == 0 { 0 } else { self.ends[id - 1] };
+        &self.text[start..self.ends[id]]
+    }
+
+    /// Appends a feature name and returns its position.
+    ///
+    /// # Panics
+    /// Panics if the table already holds `u32::MAX + 1` features.
+    fn push(&mut self, feature: &str) -> FeatureId {
+        let id = FeatureId::try_from(self.ends.len()).expect("too many features for u32 indices");
+        self.text.push_str(feature);
+        self.ends.push(self.text.len());
+        id
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &str> {
+        (0..self.len()).map(|id| self.get(id))
+    }
+
+    /// Returns the heap memory held by the table, in bytes.
+    #[cfg(feature = "std")]
+    fn heap_size(&self) -> usize {
+        self.text.capacity() + self.ends.capacity() * core::mem::size_of::<usize>()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for FeatureTable {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut table = FeatureTable::default();
+        for feature in iter {
+            table.push(feature);
+        }
+        table
+    }
+}
+
+/// Checks the number of folds of a cross-validation and the fold to hold out.
+#[cfg(feature = "train")]
+fn check_fold(num_folds: usize, fold: usize) -> Result<(), LitseaError> {
+    if num_folds < 2 || fold >= num_folds {
+        return Err(LitseaError::InvalidInput(format!(
+            "Invalid fold {} of {}: the number of folds must be at least 2",
+            fold, num_folds
+        )));
+    }
+    Ok(())
+}
+
+/// Builds the index of the features of a table.
+fn index_of(features: &FeatureTable) -> FeatureIndex {
+    features.iter().zip(0..).map(|(f, i)| (Box::from(f), i)).collect()
+}
+
+#[cfg(feature = "train")]
+type BinaryLabel = i8;
+
+/// How [`AdaBoost::train`] reweights the training instances after each round.
+#[cfg(feature = "train")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Boosting {
+    /// Standard AdaBoost. The weight of an instance grows exponentially with every round
+    /// that misclassifies it, so mislabeled instances end up dominating the training.
+    #[default]
+    AdaBoost,
+    /// AdaBoost with the instance weights capped at the given multiple of their average
+    /// after each round, as MadaBoost caps weights at their initial value.
+    /// Mislabeled instances, such as the boundary errors of automatically segmented
+    /// corpora, then cannot outweigh the rest of the data. The cap must be at least 1.
+    Capped(f64),
+}
+
+/// The weak learners combined by [`AdaBoost::train`].
+///
+/// Each weak learner looks at one feature, and splits the instances into those that have
+/// it and those that do not.
+#[cfg(feature = "train")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Discrete AdaBoost: the weak learner predicts a boundary if the feature is present
+    /// and no boundary otherwise, and its weight depends only on its error rate.
+    #[default]
+    Discrete,
+    /// Real AdaBoost with confidence-rated predictions (Schapire & Singer, 1999): the
+    /// weak learner outputs a separate real-valued confidence for the instances with and
+    /// without the feature, from the weights of the boundaries and non-boundaries among
+    /// them. It usually reaches the same accuracy in far fewer iterations.
+    Real,
+}
+
+#[cfg(feature = "train")]
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "discrete" => Ok(Algorithm::Discrete),
+            "real" => Ok(Algorithm::Real),
+            _ => Err(format!("Unsupported algorithm: '{}'. Supported: discrete, real", s)),
+        }
+    }
+}
+
+/// The feature indices of training or development instances, held in memory or, with
+/// the `mmap` feature, in a memory-mapped instance file.
+///
+/// Cloning a store shares its instances, which are copied only when an instance is
+/// added to a shared store.
+#[cfg(feature = "train")]
+#[derive(Debug, Default, Clone)]
+struct InstanceStore {
+    matrix: Arc<SparseMatrix>,
+    /// The rows of `matrix` of the instances of the store, in order, for a store that
+    /// selects some instances of a shared one; `None` for all of them.
+    rows: Option<Vec<usize>>,
+    #[cfg(feature = "mmap")]
+    mapped: Option<Arc<MappedInstances>>,
+}
+
+#[cfg(feature = "train")]
+impl InstanceStore {
+    /// Appends an instance held in memory.
+    fn push<I: IntoIterator<Item = FeatureId>>(&mut self, features: I) {
+        if self.rows.is_some() {
+            *self = self.select(0..self.len());
+        }
+        // Sort feature indices so that binary_search in train() works correctly.
+        Arc::make_mut(&mut self.matrix).push_row(features).sort_unstable();
+    }
+
+    /// Returns the number of instances.
+    fn len(&self) -> usize {
+        #[cfg(feature = "mmap")]
+        if let Some(mapped) = &self.mapped {
+            return mapped.len();
+        }
+        self.rows.as_ref().map_or(self.matrix.num_rows(), Vec::len)
+    }
+
+    /// Returns a store of the instances at the given positions, in order, which shares
+    /// the instances held in memory. Mapped instances are copied into memory.
+    fn select<I: IntoIterator<Item = usize>>(&self, positions: I) -> InstanceStore {
+        #[cfg(feature = "mmap")]
+        if self.mapped.is_some() {
+            let mut keep = vec![false; self.len()];
+            for i in positions {
+                keep[i] = true;
+            }
+            let mut selected = InstanceStore::default();
+            self.for_each(0..keep.len(), |i, hs| {
+                if keep[i] {
+                    selected.push(hs.iter().copied());
+                }
+            });
+            return selected;
+        }
+        let rows = match &self.rows {
+            Some(rows) => positions.into_iter().map(|i| rows[i]).collect(),
+            None => positions.into_iter().collect(),
+        };
+        InstanceStore {
+            matrix: Arc::clone(&self.matrix),
+            rows: Some(rows),
+            #[cfg(feature = "mmap")]
+            mapped: None,
+        }
+    }
+
+    /// Calls `f` with the index and the sorted feature indices of each instance in `range`.
+    fn for_each<F: FnMut(usize, &[FeatureId])>(&self, range: Range<usize>, mut f: F) {
+        #[cfg(feature = "mmap")]
+        if let Some(mapped) = &self.mapped {
+            mapped.for_each(range, f);
+            return;
+        }
+        match &self.rows {
+            Some(rows) => range.for_each(|i| f(i, self.matrix.row(rows[i]))),
+            None => range.for_each(|i| f(i, self.matrix.row(i))),
+        }
+    }
+
+    /// Returns the heap memory held by the in-memory instances, in bytes.
+    fn heap_size(&self) -> usize {
+        self.matrix.heap_size()
+            + self
+                .rows
+                .as_ref()
+                .map_or(0, |rows| rows.capacity() * core::mem::size_of::<usize>())
+    }
+}
+
+/// The features and the instances of a features file, read once to initialize several
+/// learners with [`AdaBoost::set_dataset`], e.g. for the folds of a cross-validation,
+/// the models of an [`Ensemble`](crate::ensemble::Ensemble) or a search over the
+/// training settings, instead of reading and indexing the file for every learner.
+///
+/// Cloning a dataset or initializing a learner from it shares the instances and the
+/// feature names; a learner copies only the labels and the weights of the instances.
+#[cfg(feature = "train")]
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    features: Arc<FeatureTable>,
+    feature_index: Arc<FeatureIndex>,
+    labels: Arc<[BinaryLabel]>,
+    /// The weights of the instances, 1 unless read from a weighted features file.
+    weights: Arc<[f64]>,
+    instances: InstanceStore,
+}
+
+#[cfg(feature = "train")]
+impl Dataset {
+    /// Reads a features file, decompressing files ending in `.gz` or `.zst` like
+    /// [`AdaBoost::initialize`].
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    ///
+    /// # Returns: The dataset.
+    ///
+    /// # Errors: Returns an error if the file cannot be opened or read, a label is invalid,
+    /// or the file has no features.
+    pub fn load(filename: &Path) -> Result<Self, LitseaError> {
+        Ok(AdaBoost::new(0.0, 0).read_dataset(filename)?)
+    }
+
+    /// Reads a features file with a weight column after the label, see
+    /// [`AdaBoost::set_weighted`].
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the weighted features file.
+    ///
+    /// # Returns: The dataset.
+    ///
+    /// # Errors: Returns an error if the file cannot be opened or read, a label is invalid,
+    /// a weight is missing or not a positive number, or the file has no features.
+    pub fn load_weighted(filename: &Path) -> Result<Self, LitseaError> {
+        let mut learner = AdaBoost::new(0.0, 0);
+        learner.set_weighted(true);
+        Ok(learner.read_dataset(filename)?)
+    }
+
+    /// Returns the number of instances.
+    #[must_use]
+    pub fn num_instances(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns the number of distinct features, without the bias term.
+    #[must_use]
+    pub fn num_features(&self) -> usize {
+        self.features.len().saturating_sub(1)
+    }
+}
+
+/// Held-out instances scored during training to monitor overfitting.
+#[cfg(feature = "train")]
+#[derive(Debug, Default, Clone)]
+struct DevSet {
+    labels: Vec<BinaryLabel>,
+    instances: InstanceStore,
+}
+
+/// Accuracies on the training and development sets after a boosting iteration.
+#[cfg(feature = "train")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct TrainingRecord {
+    /// The iteration, starting from 1.
+    pub iteration: usize,
+    /// Accuracy on the training set in percentage (%).
+    pub train_accuracy: f64,
+    /// Accuracy on the development set in percentage (%).
+    pub dev_accuracy: f64,
+    /// F1 score of the boundaries of the development set in percentage (%).
+    pub dev_f1: f64,
+    /// Whether the gap between the two has grown for the configured number of
+    /// iterations at this iteration.
+    pub overfitting: bool,
+}
+
+#[cfg(feature = "train")]
+impl TrainingRecord {
+    /// Returns the training accuracy minus the development accuracy, in points.
+    #[must_use]
+    pub fn gap(&self) -> f64 {
+        self.train_accuracy - self.dev_accuracy
+    }
+}
+
+/// Minimum number of instances scored per thread by [`AdaBoost::get_metrics`]; smaller
+/// sets are not worth spawning threads for.
+#[cfg(feature = "train")]
+const METRICS_CHUNK_LEN: usize = 16_384;
+
+/// Minimum number of instances per thread in an iteration of [`AdaBoost::train`].
+#[cfg(feature = "train")]
+const TRAIN_CHUNK_LEN: usize = 16_384;
+
+/// Seed of the batch sampling of [`AdaBoost::set_mini_batch`].
+#[cfg(feature = "train")]
+const MINI_BATCH_SEED: u64 = 0x6C69_7473_6561;
+
+/// Counts of a confusion matrix of boundary decisions, summed across threads.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Confusion {
+    true_positives: usize,
+    false_positives: usize,
+    false_negatives: usize,
+    true_negatives: usize,
+}
+
+#[cfg(feature = "std")]
+impl Confusion {
+    /// Counts a decision given whether it is a boundary and whether one was predicted.
+    pub(crate) fn add(&mut self, actual: bool, predicted: bool) {
+        match (actual, predicted) {
+            (true, true) => self.true_positives += 1,
+            (false, true) => self.false_positives += 1,
+            (true, false) => self.false_negatives += 1,
+            (false, false) => self.true_negatives += 1,
+        }
+    }
+
+    /// Adds up the counts of two parts of a data set.
+    pub(crate) fn merge(self, other: Confusion) -> Confusion {
+        Confusion {
+            true_positives: self.true_positives + other.true_positives,
+            false_positives: self.false_positives + other.false_positives,
+            false_negatives: self.false_negatives + other.false_negatives,
+            true_negatives: self.true_negatives + other.true_negatives,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Confusion> for Metrics {
+    fn from(c: Confusion) -> Self {
+        let num_instances =
+            c.true_positives + c.false_positives + c.false_negatives + c.true_negatives;
+        Metrics {
+            accuracy: (c.true_positives + c.true_negatives) as f64 / num_instances.max(1) as f64
+                * 100.0,
+            precision: c.true_positives as f64
+                / (c.true_positives + c.false_positives).max(1) as f64
+                * 100.0,
+            recall: c.true_positives as f64 / (c.true_positives + c.false_negatives).max(1) as f64
+                * 100.0,
+            num_instances,
+            true_positives: c.true_positives,
+            false_positives: c.false_positives,
+            false_negatives: c.false_negatives,
+            true_negatives: c.true_negatives,
+        }
+    }
+}
 
 /// Structure to hold evaluation metrics.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
 pub struct Metrics {
     /// Accuracy in percentage (%)
     pub accuracy: f64,
@@ -32,21 +449,76 @@ pub struct Metrics {
 }
 
 /// AdaBoost implementation for binary classification
-/// This implementation uses a simple feature extraction method
-/// and is designed for educational purposes.
-/// It is not optimized for performance or large datasets.
-#[derive(Debug)]
+/// This implementation uses a simple feature extraction method.
+/// Features are looked up through a hash index built when the model is loaded, and the
+/// bias is kept up to date whenever the weights change, so prediction time does not grow
+/// with the size of the model.
+#[derive(Debug, Clone)]
 pub struct AdaBoost {
     pub threshold: f64,
     pub num_iterations: usize,
+    #[cfg(feature = "train")]
     instance_weights: Vec<f64>,
     model: Vec<f64>,
-    features: Vec<String>,
-    feature_index: HashMap<String, usize>,
-    labels: Vec<Label>,
-    instances_buf: Vec<usize>,
-    instances: Vec<(usize, usize)>, // (start, end) index in instances_buf
+    /// The bias term, [`get_bias`](Self::get_bias) of the current weights, which
+    /// [`update_bias`](Self::update_bias) recomputes whenever they change.
+    bias: f64,
+    /// The feature names and their index, shared with a [`Dataset`] the learner was
+    /// initialized from.
+    features: Arc<FeatureTable>,
+    feature_index: Arc<FeatureIndex>,
+    #[cfg(feature = "train")]
+    labels: Vec<BinaryLabel>,
+    #[cfg(feature = "train")]
+    instances: InstanceStore,
+    #[cfg(feature = "train")]
     num_instances: usize,
+    #[cfg(feature = "train")]
+    algorithm: Algorithm,
+    #[cfg(feature = "train")]
+    boosting: Boosting,
+    #[cfg(feature = "train")]
+    dev_set: Option<DevSet>,
+    /// Number of growths of the train/dev gap that signal overfitting, and whether to stop.
+    #[cfg(feature = "train")]
+    overfitting_patience: Option<(usize, bool)>,
+    /// Number of iterations without a better development F1 after which training stops.
+    #[cfg(feature = "train")]
+    early_stopping_patience: Option<usize>,
+    /// Iteration of the model kept by early stopping in the last training.
+    #[cfg(feature = "train")]
+    best_iteration: Option<usize>,
+    /// Number of instances sampled per iteration, and the interval of full iterations.
+    #[cfg(feature = "train")]
+    mini_batch: Option<(usize, usize)>,
+    /// Maximum number of training threads; 0 uses the available parallelism.
+    #[cfg(feature = "train")]
+    num_threads: usize,
+    /// Factor of the confidences of the weak learners.
+    #[cfg(feature = "train")]
+    learning_rate: f64,
+    /// Whether training stops once every training instance is classified correctly.
+    #[cfg(feature = "train")]
+    until_correct: bool,
+    /// Whether features files have a weight column after the label.
+    #[cfg(feature = "train")]
+    weighted: bool,
+    #[cfg(feature = "train")]
+    progress: Option<Arc<dyn ProgressHandler>>,
+    /// Number of iterations between the training accuracies reported to the progress
+    /// handler; 0 reports none.
+    #[cfg(feature = "train")]
+    accuracy_interval: usize,
+    #[cfg(feature = "train")]
+    training_log: Vec<TrainingRecord>,
+    metadata: BTreeMap<String, String>,
+    /// How the weights are written by [`save_model`](Self::save_model), set by
+    /// [`quantize`](Self::quantize), with the scale of i8 weights.
+    #[cfg(feature = "std")]
+    quantization: Option<(Quantization, f64)>,
+    verify_integrity: bool,
+    #[cfg(feature = "signing")]
+    verifying_key: Option<integrity::VerifyingKey>,
 }
 
 impl AdaBoost {
@@ -63,17 +535,354 @@ impl AdaBoost {
         AdaBoost {
             threshold,
             num_iterations,
+            #[cfg(feature = "train")]
             instance_weights: vec![],
             model: vec![],
-            features: vec![],
-            feature_index: HashMap::new(),
+            bias: 0.0,
+            features: Arc::default(),
+            feature_index: Arc::default(),
+            #[cfg(feature = "train")]
             labels: vec![],
-            instances_buf: vec![],
-            instances: vec![],
+            #[cfg(feature = "train")]
+            instances: InstanceStore::default(),
+            #[cfg(feature = "train")]
             num_instances: 0,
+            #[cfg(feature = "train")]
+            algorithm: Algorithm::default(),
+            #[cfg(feature = "train")]
+            boosting: Boosting::default(),
+            #[cfg(feature = "train")]
+            dev_set: None,
+            #[cfg(feature = "train")]
+            overfitting_patience: None,
+            #[cfg(feature = "train")]
+            early_stopping_patience: None,
+            #[cfg(feature = "train")]
+            best_iteration: None,
+            #[cfg(feature = "train")]
+            mini_batch: None,
+            #[cfg(feature = "train")]
+            num_threads: 0,
+            #[cfg(feature = "train")]
+            learning_rate: 1.0,
+            #[cfg(feature = "train")]
+            until_correct: false,
+            #[cfg(feature = "train")]
+            weighted: false,
+            #[cfg(feature = "train")]
+            progress: None,
+            #[cfg(feature = "train")]
+            accuracy_interval: 0,
+            #[cfg(feature = "train")]
+            training_log: vec![],
+            metadata: BTreeMap::new(),
+            #[cfg(feature = "std")]
+            quantization: None,
+            verify_integrity: true,
+            #[cfg(feature = "signing")]
+            verifying_key: None,
+        }
+    }
+
+    /// Returns a metadata entry of the model.
+    ///
+    /// Metadata are `#<key> <value>` lines at the start of the model body, covered by its
+    /// checksum and signature, e.g. the pipeline configuration the model was trained with.
+    ///
+    /// # Arguments
+    /// * `key`: The key of the entry.
+    ///
+    /// # Returns: The value of the entry, or `None` if the model has no such entry.
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    /// Returns all metadata entries of the model, sorted by key.
+    ///
+    /// # Returns: An iterator over `(key, value)` pairs.
+    pub fn metadata_entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.metadata.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Sets a metadata entry, written with the model by [`save_model`](Self::save_model).
+    ///
+    /// # Arguments
+    /// * `key`: The key of the entry: a non-empty word without whitespace.
+    /// * `value`: The value of the entry, on a single line.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if the key is empty or contains
+    /// whitespace, or the value contains a line break.
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> Result<(), LitseaError> {
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(LitseaError::InvalidInput(format!("Invalid metadata key: '{}'", key)));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(LitseaError::InvalidInput(format!(
+                "Metadata value of '{}' must be a single line",
+                key
+            )));
+        }
+        self.metadata.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// Sets the weak learners [`train`](Self::train) combines.
+    ///
+    /// # Arguments
+    /// * `algorithm`: The boosting algorithm; see [`Algorithm`].
+    #[cfg(feature = "train")]
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// Sets the learning rate, the factor [`train`](Self::train) multiplies the confidence
+    /// of every weak learner by. Rates below 1 take smaller steps, which needs more
+    /// iterations but keeps a few instances from moving the model far, e.g. in
+    /// [`partial_fit`](Self::partial_fit).
+    ///
+    /// # Arguments
+    /// * `rate`: The learning rate, greater than 0 and at most 1 (the default).
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if `rate` is out of range.
+    #[cfg(feature = "train")]
+    pub fn set_learning_rate(&mut self, rate: f64) -> Result<(), LitseaError> {
+        if !(rate > 0.0 && rate <= 1.0) {
+            return Err(LitseaError::InvalidInput(format!(
+                "Learning rate must be in (0, 1], got {}",
+                rate
+            )));
+        }
+        self.learning_rate = rate;
+        Ok(())
+    }
+
+    /// Sets the handler [`train`](Self::train) reports its progress to after every
+    /// iteration, e.g. to draw a progress bar. Training itself writes nothing to standard
+    /// error.
+    ///
+    /// # Arguments
+    /// * `handler`: The handler, or `None` to report nothing (the default).
+    #[cfg(feature = "train")]
+    pub fn set_progress_handler(&mut self, handler: Option<Arc<dyn ProgressHandler>>) {
+        self.progress = handler;
+    }
+
+    /// Sets how often [`train`](Self::train) reports the accuracy on the training
+    /// instances to the progress handler. Each report scores all training instances, so
+    /// it costs about as much as an iteration. With a development set, the accuracy is
+    /// reported after every iteration anyway.
+    ///
+    /// # Arguments
+    /// * `interval`: The number of iterations between reports, or 0 for none (the
+    ///   default).
+    #[cfg(feature = "train")]
+    pub fn set_accuracy_interval(&mut self, interval: usize) {
+        self.accuracy_interval = interval;
+    }
+
+    /// Sets how [`train`](Self::train) reweights the training instances.
+    ///
+    /// # Arguments
+    /// * `boosting`: The boosting variant; see [`Boosting`].
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if the cap of [`Boosting::Capped`]
+    /// is less than 1.
+    #[cfg(feature = "train")]
+    pub fn set_boosting(&mut self, boosting: Boosting) -> Result<(), LitseaError> {
+        if let Boosting::Capped(cap) = boosting {
+            if !(cap >= 1.0 && cap.is_finite()) {
+                return Err(LitseaError::InvalidInput(format!(
+                    "Weight cap must be a number of at least 1, got {}",
+                    cap
+                )));
+            }
+        }
+        self.boosting = boosting;
+        Ok(())
+    }
+
+    /// Sets when [`train`](Self::train) reports overfitting: when the gap between the
+    /// training and development accuracy has grown `patience` times without shrinking in
+    /// between. Requires a development set, see
+    /// [`initialize_dev_instances`](Self::initialize_dev_instances).
+    ///
+    /// # Arguments
+    /// * `patience`: The number of growths of the gap that signal overfitting.
+    /// * `stop`: Whether to stop training when overfitting is detected, instead of only
+    ///   flagging it in the [`training_log`](Self::training_log).
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if `patience` is 0.
+    #[cfg(feature = "train")]
+    pub fn set_overfitting_patience(
+        &mut self,
+        patience: usize,
+        stop: bool,
+    ) -> Result<(), LitseaError> {
+        if patience == 0 {
+            return Err(LitseaError::InvalidInput(
+                "Overfitting patience must be at least 1".to_string(),
+            ));
+        }
+        self.overfitting_patience = Some((patience, stop));
+        Ok(())
+    }
+
+    /// Sets [`train`](Self::train) to stop when the F1 score on the development set has
+    /// not improved for `patience` iterations, and to keep the weights of the iteration
+    /// with the best score instead of those of the last one. Requires a development set,
+    /// see [`initialize_dev_instances`](Self::initialize_dev_instances).
+    ///
+    /// # Arguments
+    /// * `patience`: The number of iterations without improvement before stopping.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if `patience` is 0.
+    #[cfg(feature = "train")]
+    pub fn set_early_stopping(&mut self, patience: usize) -> Result<(), LitseaError> {
+        if patience == 0 {
+            return Err(LitseaError::InvalidInput(
+                "Early stopping patience must be at least 1".to_string(),
+            ));
         }
+        self.early_stopping_patience = Some(patience);
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Returns the number of training instances, read from a features file or added with
+    /// [`add_instance`](Self::add_instance).
+    #[must_use]
+    pub fn num_instances(&self) -> usize {
+        self.num_instances
     }
 
+    #[cfg(feature = "train")]
+    /// Returns the iteration whose weights early stopping kept in the last call to
+    /// [`train`](Self::train), 0 if no iteration improved on the initial model.
+    ///
+    /// # Returns: The iteration, or `None` without early stopping or a development set.
+    #[must_use]
+    pub fn best_iteration(&self) -> Option<usize> {
+        self.best_iteration
+    }
+
+    /// Sets [`train`](Self::train) to choose the hypothesis of each iteration from a
+    /// random sample of about `batch_size` instances instead of all of them, which makes
+    /// iterations on large training sets much cheaper. Every `correction_interval`-th
+    /// iteration still uses all instances, so the errors of the sampled iterations do not
+    /// accumulate. The instance weights are always updated on all instances.
+    ///
+    /// Batches are sampled with a fixed seed, so training is reproducible.
+    ///
+    /// # Arguments
+    /// * `batch_size`: The expected number of instances per sampled iteration.
+    /// * `correction_interval`: The number of iterations between full iterations; 1 makes
+    ///   every iteration a full one.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::InvalidInput`] if `batch_size` or
+    /// `correction_interval` is 0.
+    #[cfg(feature = "train")]
+    pub fn set_mini_batch(
+        &mut self,
+        batch_size: usize,
+        correction_interval: usize,
+    ) -> Result<(), LitseaError> {
+        if batch_size == 0 {
+            return Err(LitseaError::InvalidInput("Batch size must be at least 1".to_string()));
+        }
+        if correction_interval == 0 {
+            return Err(LitseaError::InvalidInput(
+                "Correction interval must be at least 1".to_string(),
+            ));
+        }
+        self.mini_batch = Some((batch_size, correction_interval));
+        Ok(())
+    }
+
+    /// Sets the maximum number of threads [`train`](Self::train) and
+    /// [`get_metrics`](Self::get_metrics) split the instances across. Training sets
+    /// smaller than a few ten thousand instances are processed on the calling thread.
+    ///
+    /// The weights are summed in a different order with a different number of threads, so
+    /// models trained with different numbers of threads may differ in the last digits.
+    ///
+    /// # Arguments
+    /// * `num_threads`: The maximum number of threads, or 0 for one per available core,
+    ///   the default.
+    #[cfg(feature = "train")]
+    pub fn set_num_threads(&mut self, num_threads: usize) {
+        self.num_threads = num_threads;
+    }
+
+    /// Sets whether features files have a weight column after the label, such as the
+    /// count column of the files [`sort_features`](crate::feature_file::sort_features)
+    /// deduplicates. The initial weight of each instance is multiplied by its weight, so
+    /// frequent sentences or confident annotations count more during boosting. Must be
+    /// set before a features file is read.
+    ///
+    /// Instance files cannot hold weights, so
+    /// [`initialize_mapped`](Self::initialize_mapped) and
+    /// [`write_instance_file`](Self::write_instance_file) fail on weighted features files.
+    ///
+    /// # Arguments
+    /// * `weighted`: Whether the second column of each line is a positive weight.
+    #[cfg(feature = "train")]
+    pub fn set_weighted(&mut self, weighted: bool) {
+        self.weighted = weighted;
+    }
+
+    /// Sets whether loaded models are checked against the checksum in their header.
+    /// Checking is enabled by default; disabling it allows loading a model whose
+    /// header no longer matches its content, e.g. after editing weights by hand.
+    /// See [`integrity`](crate::integrity).
+    ///
+    /// # Arguments
+    /// * `verify`: Whether to check the checksum (and signature) of loaded models.
+    pub fn set_verify_integrity(&mut self, verify: bool) {
+        self.verify_integrity = verify;
+    }
+
+    /// Sets the public key that loaded models must be signed with.
+    /// With a key set, unsigned models and models with an invalid signature fail to load,
+    /// unless integrity checking is disabled with [`set_verify_integrity`](Self::set_verify_integrity).
+    ///
+    /// # Arguments
+    /// * `key`: The public key, or `None` to accept unsigned models.
+    #[cfg(feature = "signing")]
+    pub fn set_verifying_key(&mut self, key: Option<integrity::VerifyingKey>) {
+        self.verifying_key = key;
+    }
+
+    /// Creates a model from a TinySegmenter JavaScript file.
+    /// Each `this.<TEMPLATE>__` table becomes a set of `<TEMPLATE>:<key>` features and
+    /// `this.BIAS__` becomes the bias. The word trigram tables (`TW1`-`TW4`) exist only in
+    /// the TinySegmenter templates, so a model from a script that scores them records
+    /// those templates and their character types in its metadata. See
+    /// [`import_js`](crate::tinysegmenter::import_js).
+    ///
+    /// # Arguments
+    /// * `reader`: A reader over the JavaScript source, e.g. `tiny_segmenter.js`.
+    ///
+    /// # Returns: A new instance of [`AdaBoost`] holding the imported weights.
+    ///
+    /// # Errors: Returns an error if the source cannot be read or contains no score tables.
+    #[cfg(feature = "std")]
+    pub fn from_tinysegmenter_js<R: std::io::Read>(reader: R) -> Result<Self, LitseaError> {
+        crate::tinysegmenter::import_js(reader)
+    }
+
+    #[cfg(feature = "train")]
     /// Initializes the features from a file.
     /// The file should contain lines with a label followed by space-separated features.
     ///
@@ -92,21 +901,22 @@ impl AdaBoost {
     /// The last feature is an empty string, which is used as a bias term.
     /// The model is initialized with zeros for each feature.
     /// The number of instances is counted to ensure that the model can handle the data efficiently.
-    pub fn initialize_features(&mut self, filename: &Path) -> std::io::Result<()> {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
+    pub fn initialize_features(&mut self, filename: &Path) -> Result<(), LitseaError> {
+        trace_span!(DEBUG, "initialize_features", path = %filename.display());
+        let reader = compression::open(filename)?;
         let mut map = BTreeMap::new(); // preserve order
 
         let mut buf_size = 0;
         self.num_instances = 0;
 
-        for line in reader.lines() {
+        for (line_num, line) in reader.lines().enumerate() {
             let line = line?;
             let mut parts = line.split_whitespace();
             // Skip empty lines (no label token).
             let Some(_label) = parts.next() else {
                 continue;
             };
+            self.instance_weight(&mut parts, line_num)?;
 
             for h in parts {
                 map.entry(h.to_string()).or_insert(0.0);
@@ -121,25 +931,34 @@ impl AdaBoost {
 
         // A map with only the bias term means no actual features were extracted.
         if map.len() == 1 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "No features found in the training data (only bias term present)",
+            return Err(LitseaError::InvalidInput(
+                "No features found in the training data (only bias term present)".to_string(),
             ));
         }
 
-        self.features = map.keys().cloned().collect();
+        if map.len() > FeatureId::MAX as usize {
+            return Err(LitseaError::InvalidInput(format!("Too many features: {}", map.len())));
+        }
+
+        self.features = Arc::new(map.keys().map(String::as_str).collect());
         self.model = map.values().cloned().collect();
-        self.feature_index =
-            self.features.iter().enumerate().map(|(i, f)| (f.clone(), i)).collect();
+        self.update_bias();
+        self.index_features();
 
         self.instance_weights.reserve(self.num_instances);
         self.labels.reserve(self.num_instances);
-        self.instances.reserve(self.num_instances);
-        self.instances_buf.reserve(buf_size);
+        Arc::make_mut(&mut self.instances.matrix).reserve(self.num_instances, buf_size);
 
+        trace_event!(
+            debug,
+            num_features = self.features.len(),
+            num_instances = self.num_instances,
+            "initialized features"
+        );
         Ok(())
     }
 
+    #[cfg(feature = "train")]
     /// Initializes the instances from a file.
     /// The file should contain lines with a label followed by space-separated features.
     ///
@@ -157,137 +976,863 @@ impl AdaBoost {
     /// and initializes the instances with their corresponding weights.
     /// It calculates the score for each instance based on the features and updates the model accordingly.
     /// The instance weights are initialized based on the label and score.
-    pub fn initialize_instances(&mut self, filename: &Path) -> std::io::Result<()> {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
+    pub fn initialize_instances(&mut self, filename: &Path) -> Result<(), LitseaError> {
+        trace_span!(DEBUG, "initialize_instances", path = %filename.display());
+        let reader = compression::open(filename)?;
         let bias = self.get_bias();
 
-        for line in reader.lines() {
+        for (line_num, line) in reader.lines().enumerate() {
             let line = line?;
             let mut parts = line.split_whitespace();
-            let label: Label = parts
-                .next()
-                .ok_or_else(|| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        "Missing label in instance line",
-                    )
-                })?
-                .parse()
-                .map_err(|e| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        format!("Invalid label: {}", e),
-                    )
-                })?;
+            let label = parse_label(parts.next(), line_num)?;
+            let weight = self.instance_weight(&mut parts, line_num)?;
             self.labels.push(label);
 
-            let start = self.instances_buf.len();
             let mut score = bias;
+            self.instances.push(parts.filter_map(|h| {
+                let pos = *self.feature_index.get(h)?;
+                score += self.model[pos as usize];
+                Some(pos)
+            }));
+            self.instance_weights.push(weight * (-2.0 * label as f64 * score).exp());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a features file in a single pass,
+    /// with the same result as [`initialize_features`](Self::initialize_features)
+    /// followed by [`initialize_instances`](Self::initialize_instances), which read the
+    /// file twice. Like them, it decompresses files ending in `.gz` or `.zst`.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if the file cannot be opened or read, a label is invalid,
+    /// or the file has no features.
+    pub fn initialize(&mut self, filename: &Path) -> Result<(), LitseaError> {
+        let dataset = self.read_dataset(filename)?;
+        self.set_dataset(&dataset);
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Reads the features and the instances of a features file into a [`Dataset`], with
+    /// the weight column if features files are [weighted](Self::set_weighted).
+    fn read_dataset(&self, filename: &Path) -> std::io::Result<Dataset> {
+        trace_span!(DEBUG, "initialize", path = %filename.display());
+        let mut labels = Vec::new();
+        let mut weights = Vec::new();
+        let mut matrix = SparseMatrix::default();
+        let (features, remap) = self.scan_features(filename, |label, weight, features| {
+            labels.push(label);
+            weights.push(weight);
+            // Indices are sorted once they are renumbered.
+            matrix.push_row(features.iter().copied());
+            Ok(())
+        })?;
+        matrix.remap(&remap);
 
+        Ok(Dataset {
+            feature_index: Arc::new(index_of(&features)),
+            features: Arc::new(features),
+            labels: labels.into(),
+            weights: weights.into(),
+            instances: InstanceStore {
+                matrix: Arc::new(matrix),
+                ..InstanceStore::default()
+            },
+        })
+    }
+
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a dataset, in place of
+    /// [`initialize`](Self::initialize), which reads the features file every time. The
+    /// learner shares the instances and the feature names of the dataset, so initializing
+    /// several learners from one dataset holds them in memory once.
+    ///
+    /// Replaces the model and any instances added before with an untrained model of the
+    /// features of the dataset.
+    ///
+    /// # Arguments
+    /// * `dataset`: The dataset, e.g. read with [`Dataset::load`].
+    pub fn set_dataset(&mut self, dataset: &Dataset) {
+        self.features = Arc::clone(&dataset.features);
+        self.feature_index = Arc::clone(&dataset.feature_index);
+        self.model = vec![0.0; self.features.len()];
+        self.update_bias();
+        self.labels = dataset.labels.to_vec();
+        self.instance_weights = dataset.weights.to_vec();
+        self.instances = dataset.instances.clone();
+        self.num_instances = self.labels.len();
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Returns the features and the instances of the learner as a [`Dataset`] that
+    /// shares them, with the current instance weights as the weights of the instances.
+    pub(crate) fn dataset(&self) -> Dataset {
+        Dataset {
+            features: Arc::clone(&self.features),
+            feature_index: Arc::clone(&self.feature_index),
+            labels: self.labels.as_slice().into(),
+            weights: self.instance_weights.as_slice().into(),
+            instances: self.instances.clone(),
+        }
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Initializes the features from a features file and writes its instances to a
+    /// memory-mapped instance file in a single pass, with the same result as
+    /// [`initialize_features`](Self::initialize_features),
+    /// [`write_instance_file`](Self::write_instance_file) and
+    /// [`map_instances`](Self::map_instances), which read the features file twice.
+    /// Only the feature names are held in memory while the file is read; each instance is
+    /// written to the instance file as soon as it is read. A feature repeated within an
+    /// instance is written once, since instance files hold distinct indices.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    /// * `instance_file`: The path of the instance file to write.
+    ///
+    /// # Returns: The number of instances.
+    ///
+    /// # Errors: Returns an error if a file cannot be read, written or mapped, a label is
+    /// invalid, or the features file has no features.
+    pub fn initialize_mapped(
+        &mut self,
+        filename: &Path,
+        instance_file: &Path,
+    ) -> Result<usize, LitseaError> {
+        trace_span!(DEBUG, "initialize_mapped", path = %instance_file.display());
+        self.check_unweighted()?;
+        // The number of features is written when the indices are renumbered.
+        let mut writer = InstanceFileWriter::create(instance_file, 0)?;
+        let mut distinct = Vec::new();
+        let (features, remap) = self.scan_features(filename, |label, _, features| {
+            distinct.clear();
+            distinct.extend_from_slice(features);
+            distinct.sort_unstable();
+            distinct.dedup();
+            writer.push(label, &distinct)
+        })?;
+        self.features = Arc::new(features);
+        self.model = vec![0.0; self.features.len()];
+        self.update_bias();
+        self.index_features();
+        writer.finish()?;
+        instance_file::remap_features(instance_file, &remap)?;
+        self.map_instances(instance_file)?;
+        Ok(self.num_instances)
+    }
+
+    #[cfg(feature = "train")]
+    /// Reads a features file once, numbering the features in order of appearance and
+    /// passing the label, the weight and the feature indices of each instance to `sink`.
+    ///
+    /// # Returns: The features in the sorted order that
+    /// [`initialize_features`](Self::initialize_features) uses, and the new index of each
+    /// index passed to `sink`.
+    fn scan_features<F>(
+        &self,
+        filename: &Path,
+        mut sink: F,
+    ) -> std::io::Result<(FeatureTable, Vec<FeatureId>)>
+    where
+        F: FnMut(BinaryLabel, f64, &[FeatureId]) -> std::io::Result<()>,
+    {
+        let reader = compression::open(filename)?;
+        // The bias term (empty string key) is always present.
+        let mut table = FeatureTable::default();
+        let mut index = FeatureIndex::new();
+        index.insert(Box::from(""), table.push(""));
+        let mut features = Vec::new();
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            // Skip empty lines, as initialize_features does.
+            let Some(label) = parts.next() else {
+                continue;
+            };
+            let label = parse_label(Some(label), line_num)?;
+            let weight = self.instance_weight(&mut parts, line_num)?;
+            features.clear();
             for h in parts {
-                if let Some(&pos) = self.feature_index.get(h) {
-                    self.instances_buf.push(pos);
-                    score += self.model[pos];
-                }
+                let id = match index.get(h) {
+                    Some(&id) => id,
+                    None if table.len() > FeatureId::MAX as usize => {
+                        return Err(LitseaError::InvalidInput(format!(
+                            "Too many features: {}",
+                            table.len() + 1
+                        ))
+                        .into());
+                    }
+                    None => {
+                        let id = table.push(h);
+                        index.insert(Box::from(h), id);
+                        id
+                    }
+                };
+                features.push(id);
             }
+            sink(label, weight, &features)?;
+        }
+        drop(index);
+
+        // A table with only the bias term means no actual features were extracted.
+        if table.len() == 1 {
+            return Err(LitseaError::InvalidInput(
+                "No features found in the training data (only bias term present)".to_string(),
+            )
+            .into());
+        }
+
+        let mut order: Vec<FeatureId> = (0..table.len() as FeatureId).collect();
+        order.sort_unstable_by(|&a, &b| table.get(a as usize).cmp(table.get(b as usize)));
+        let mut remap = vec![0; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            remap[old as usize] = new as FeatureId;
+        }
+        let features: FeatureTable = order.iter().map(|&old| table.get(old as usize)).collect();
+        trace_event!(debug, num_features = features.len(), "initialized features");
+        Ok((features, remap))
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Writes the instances of a features file to an instance file, to train from it
+    /// with [`map_instances`](Self::map_instances) instead of
+    /// [`initialize_instances`](Self::initialize_instances) when the instances do not fit
+    /// in memory. The file is read line by line, so it can be much larger than memory.
+    ///
+    /// Must be called after [`initialize_features`](Self::initialize_features) on the same
+    /// file, since the instance file holds the indices of the features in the model.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    /// * `instance_file`: The path of the instance file to write. See
+    ///   [`instance_file`](crate::instance_file) for its format.
+    ///
+    /// # Returns: The number of instances written.
+    ///
+    /// # Errors: Returns an error if a file cannot be read or written, or a label is invalid.
+    pub fn write_instance_file(
+        &self,
+        filename: &Path,
+        instance_file: &Path,
+    ) -> Result<usize, LitseaError> {
+        trace_span!(DEBUG, "write_instance_file", path = %instance_file.display());
+        self.check_unweighted()?;
+        let reader = compression::open(filename)?;
+        let mut writer = InstanceFileWriter::create(instance_file, self.features.len())?;
+        let mut features = Vec::new();
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            let label = parse_label(parts.next(), line_num)?;
+            features.clear();
+            features.extend(parts.filter_map(|h| self.feature_index.get(h).copied()));
+            features.sort_unstable();
+            writer.push(label, &features)?;
+        }
+        Ok(writer.finish()?)
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Memory-maps an instance file written by
+    /// [`write_instance_file`](Self::write_instance_file) and trains from it, in place of
+    /// [`initialize_instances`](Self::initialize_instances). Every boosting round reads
+    /// the file sequentially; only the labels and weights of the instances are kept in
+    /// memory.
+    ///
+    /// Replaces any instances added before. Instances cannot be added with
+    /// [`add_instance`](Self::add_instance) afterwards.
+    ///
+    /// # Arguments
+    /// * `instance_file`: The path to the instance file.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if the file cannot be mapped, or was not written for the
+    /// features of this model.
+    pub fn map_instances(&mut self, instance_file: &Path) -> Result<(), LitseaError> {
+        trace_span!(DEBUG, "map_instances", path = %instance_file.display());
+        let (mapped, labels) = MappedInstances::open(instance_file, self.features.len())?;
+        let bias = self.get_bias();
+        let mut instance_weights = Vec::with_capacity(labels.len());
+        mapped.for_each(0..labels.len(), |i, hs| {
+            let score = self.instance_score(bias, hs);
+            instance_weights.push((-2.0 * labels[i] as f64 * score).exp());
+        });
+        trace_event!(debug, num_instances = mapped.len(), "mapped instances");
+        self.num_instances = mapped.len();
+        self.labels = labels;
+        self.instance_weights = instance_weights;
+        self.instances = InstanceStore {
+            mapped: Some(Arc::new(mapped)),
+            ..InstanceStore::default()
+        };
+        Ok(())
+    }
 
-            let end = self.instances_buf.len();
-            // Sort feature indices so that binary_search in train() works correctly.
-            self.instances_buf[start..end].sort_unstable();
-            self.instances.push((start, end));
-            self.instance_weights.push((-2.0 * label as f64 * score).exp());
+    #[cfg(feature = "train")]
+    /// Initializes the development set from a features file held out from training.
+    /// During [`train`](Self::train), the accuracy on it is recorded after every
+    /// iteration in the [`training_log`](Self::training_log), together with the training
+    /// accuracy, so that overfitting shows as a growing gap between the two.
+    ///
+    /// Call it after [`initialize_features`](Self::initialize_features) and after loading
+    /// a model to resume from, since features that are not in the model are ignored.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the development features file.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if the file cannot be opened or read, or a label is invalid.
+    pub fn initialize_dev_instances(&mut self, filename: &Path) -> Result<(), LitseaError> {
+        let reader = compression::open(filename)?;
+        let mut dev_set = DevSet::default();
+        for (line_num, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut parts = line.split_whitespace();
+            dev_set.labels.push(parse_label(parts.next(), line_num)?);
+            // The development set is scored unweighted.
+            self.instance_weight(&mut parts, line_num)?;
+            dev_set.instances.push(parts.filter_map(|h| self.feature_index.get(h).copied()));
         }
+        self.dev_set = Some(dev_set);
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a features file like
+    /// [`initialize`](Self::initialize), and holds out the `fold`-th of `num_folds`
+    /// contiguous blocks of instances as the development set, for k-fold
+    /// cross-validation. Contiguous blocks keep the characters of a sentence together,
+    /// so the development set is not scored on the neighbours of training instances.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    /// * `num_folds`: The number of folds, at least 2.
+    /// * `fold`: The fold to hold out, from 0 to `num_folds - 1`.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if `num_folds` is less than 2, `fold` is out of range,
+    /// or the features file cannot be read.
+    pub fn initialize_fold(
+        &mut self,
+        filename: &Path,
+        num_folds: usize,
+        fold: usize,
+    ) -> Result<(), LitseaError> {
+        check_fold(num_folds, fold)?;
+        let dataset = self.read_dataset(filename)?;
+        self.set_dataset_fold(&dataset, num_folds, fold)
+    }
+
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a dataset like
+    /// [`set_dataset`](Self::set_dataset), and holds out a fold as the development set
+    /// like [`initialize_fold`](Self::initialize_fold). The folds share the instances of
+    /// the dataset.
+    ///
+    /// # Arguments
+    /// * `dataset`: The dataset.
+    /// * `num_folds`: The number of folds, at least 2.
+    /// * `fold`: The fold to hold out, from 0 to `num_folds - 1`.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if `num_folds` is less than 2 or `fold` is out of range.
+    pub fn set_dataset_fold(
+        &mut self,
+        dataset: &Dataset,
+        num_folds: usize,
+        fold: usize,
+    ) -> Result<(), LitseaError> {
+        check_fold(num_folds, fold)?;
+        self.set_dataset(dataset);
+
+        let n = self.num_instances;
+        let held_out = n * fold / num_folds..n * (fold + 1) / num_folds;
+        let draws: Vec<u32> = (0..n).map(|i| u32::from(!held_out.contains(&i))).collect();
+        self.hold_out(&draws);
+        Ok(())
+    }
 
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a features file like
+    /// [`initialize`](Self::initialize), and keeps a bootstrap sample of the instances:
+    /// as many as the file has, drawn with replacement. An instance drawn several times
+    /// is kept once with its weight multiplied by the number of draws, which trains the
+    /// same model as repeating it. The instances never drawn, about a third of them,
+    /// become the development set, so [`dev_metrics`](Self::dev_metrics) returns the
+    /// out-of-bag metrics.
+    ///
+    /// # Arguments
+    /// * `filename`: The path to the features file.
+    /// * `seed`: The seed of the sampling; the same seed draws the same sample.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error if the features file cannot be read.
+    pub fn initialize_bootstrap(&mut self, filename: &Path, seed: u64) -> Result<(), LitseaError> {
+        let dataset = self.read_dataset(filename)?;
+        self.set_dataset_bootstrap(&dataset, seed);
         Ok(())
     }
 
+    #[cfg(feature = "train")]
+    /// Initializes the features and the instances from a dataset like
+    /// [`set_dataset`](Self::set_dataset), and keeps a bootstrap sample of the instances
+    /// like [`initialize_bootstrap`](Self::initialize_bootstrap). The sample shares the
+    /// instances of the dataset.
+    ///
+    /// # Arguments
+    /// * `dataset`: The dataset.
+    /// * `seed`: The seed of the sampling; the same seed draws the same sample.
+    pub fn set_dataset_bootstrap(&mut self, dataset: &Dataset, seed: u64) {
+        self.set_dataset(dataset);
+
+        let n = self.num_instances;
+        let mut draws = vec![0u32; n];
+        let mut rng = SplitMix64(seed);
+        for _ in 0..n {
+            draws[rng.below(n)] += 1;
+        }
+        self.hold_out(&draws);
+    }
+
+    #[cfg(feature = "train")]
+    /// Keeps the instances drawn at least once for training, with their weights
+    /// multiplied by the number of draws, and makes the others the development set.
+    ///
+    /// # Arguments
+    /// * `draws`: The number of draws of each instance.
+    fn hold_out(&mut self, draws: &[u32]) {
+        let (kept, held_out): (Vec<usize>, Vec<usize>) =
+            (0..self.num_instances).partition(|&i| draws[i] > 0);
+        self.dev_set = Some(DevSet {
+            labels: held_out.iter().map(|&i| self.labels[i]).collect(),
+            instances: self.instances.select(held_out),
+        });
+        self.instance_weights =
+            kept.iter().map(|&i| self.instance_weights[i] * f64::from(draws[i])).collect();
+        self.labels = kept.iter().map(|&i| self.labels[i]).collect();
+        self.num_instances = kept.len();
+        self.instances = self.instances.select(kept);
+    }
+
+    #[cfg(feature = "train")]
+    /// Replaces the weights and the bias with their means over `models`, so that the
+    /// score of every instance is the mean of its scores by the models. Features of the
+    /// models missing from this learner are ignored; models initialized from the same
+    /// features file as this learner have the same features.
+    ///
+    /// Records the largest number of iterations of the models and the date, and the
+    /// number of models as the [`ENSEMBLE_KEY`](model_info::ENSEMBLE_KEY) entry.
+    ///
+    /// # Arguments
+    /// * `models`: The models to average. Does nothing if there are none.
+    pub(crate) fn average(&mut self, models: &[AdaBoost]) {
+        if models.is_empty() {
+            return;
+        }
+        self.quantization = None;
+        self.model.iter_mut().for_each(|w| *w = 0.0);
+        let k = models.len() as f64;
+        for model in models {
+            // The bias bucket is averaged with the weights, which averages the bias.
+            for (h, w) in model.features.iter().zip(&model.model) {
+                if let Some(&i) = self.feature_index.get(h) {
+                    self.model[i as usize] += w / k;
+                }
+            }
+        }
+        self.update_bias();
+        let iterations = models
+            .iter()
+            .filter_map(|model| model.metadata(model_info::ITERATIONS_KEY)?.parse::<usize>().ok())
+            .max()
+            .unwrap_or(0);
+        self.metadata.remove(model_info::ITERATIONS_KEY);
+        self.record_training(iterations);
+        self.metadata
+            .insert(model_info::ENSEMBLE_KEY.to_string(), models.len().to_string());
+    }
+
+    #[cfg(feature = "train")]
+    /// Returns a learner without features or instances that trains with the same
+    /// settings, such as the threshold, the number of iterations, the algorithm, the
+    /// boosting variant, mini-batches, the number of threads and the learning rate.
+    pub(crate) fn untrained_copy(&self) -> AdaBoost {
+        let mut learner = AdaBoost::new(self.threshold, self.num_iterations);
+        learner.algorithm = self.algorithm;
+        learner.boosting = self.boosting;
+        learner.mini_batch = self.mini_batch;
+        learner.num_threads = self.num_threads;
+        learner.learning_rate = self.learning_rate;
+        learner.weighted = self.weighted;
+        learner
+    }
+
+    #[cfg(feature = "train")]
+    /// Returns the accuracies recorded after each iteration of the last call to
+    /// [`train`](Self::train). Empty without a development set.
+    ///
+    /// # Returns: The records in iteration order.
+    #[must_use]
+    pub fn training_log(&self) -> &[TrainingRecord] {
+        &self.training_log
+    }
+
+    #[cfg(feature = "train")]
     /// Trains the AdaBoost model.
     /// This method iteratively updates the model based on the training data.
     ///
     /// # Arguments
-    /// * `running`: An `Arc<AtomicBool>` to control the running state of the training process.
+    /// * `control`: A [`TrainControl`] to stop, pause or resume the training from another
+    ///   thread, or an `Arc<AtomicBool>` cleared to stop it.
     ///
     /// # Returns: This method does not return a value.
     ///
-    /// # Errors: This method does not return an error, but it will stop training if `running` is set to false.
+    /// # Errors: This method does not return an error. When it is stopped, the iteration
+    /// in progress is discarded and the model keeps the completed ones.
     ///
     /// This method performs the following steps:
     /// 1. Initializes the error vector and sums of weights.
     /// 2. Iterates through the training data for a specified number of iterations.
-    /// 3. For each instance, calculates the error based on the current model.
-    /// 4. Finds the best hypothesis based on the error rates.
-    /// 5. Updates the model with the best hypothesis and calculates the alpha value.
+    /// 3. For each instance, or each instance of a random batch with
+    ///    [`set_mini_batch`](Self::set_mini_batch), calculates the error based on the
+    ///    current model, splitting the instances across threads (see
+    ///    [`set_num_threads`](Self::set_num_threads)).
+    /// 4. Finds the best hypothesis based on the error rates, or with [`Algorithm::Real`]
+    ///    the one with the smallest normalization factor Z.
+    /// 5. Updates the model with the best hypothesis and calculates the alpha value, or
+    ///    the confidences for the instances with and without the feature.
     /// 6. Updates the instance weights based on the predictions.
-    /// 7. Normalizes the instance weights to ensure they sum to 1.
-    pub fn train(&mut self, running: Arc<AtomicBool>) {
+    /// 7. Normalizes the instance weights to ensure they sum to 1, and caps them with
+    ///    [`Boosting::Capped`].
+    ///
+    /// With [`Algorithm::Real`], the threshold applies to the edge the normalization factor
+    /// corresponds to, so that training stops at the same quality of weak learner as
+    /// with [`Algorithm::Discrete`].
+    pub fn train(&mut self, control: impl Into<TrainControl>) {
+        let control = control.into();
+        self.quantization = None;
         let num_features = self.features.len();
+        trace_span!(
+            INFO,
+            "train",
+            num_features,
+            num_instances = self.num_instances,
+            num_iterations = self.num_iterations
+        );
+        // Weights of instances resumed from a model start from their margins, which may
+        // already exceed the cap.
+        self.cap_instance_weights();
+
+        // Scores of the training and development instances, updated after every iteration
+        // to monitor the gap between their accuracies.
+        self.training_log.clear();
+        let mut scores: Option<(Vec<f64>, Vec<f64>)> = match &self.dev_set {
+            Some(dev_set) => {
+                let bias = self.get_bias();
+                let mut train = Vec::with_capacity(self.num_instances);
+                self.instances.for_each(0..self.num_instances, |_, hs| {
+                    train.push(self.instance_score(bias, hs));
+                });
+                let mut dev = Vec::with_capacity(dev_set.labels.len());
+                dev_set.instances.for_each(0..dev_set.labels.len(), |_, hs| {
+                    dev.push(self.instance_score(bias, hs));
+                });
+                Some((train, dev))
+            }
+            None => None,
+        };
+        let mut previous_gap = match (&scores, &self.dev_set) {
+            (Some((train_scores, dev_scores)), Some(dev_set)) => {
+                accuracy(&self.labels, train_scores) - accuracy(&dev_set.labels, dev_scores)
+            }
+            _ => 0.0,
+        };
+        let mut growths = 0;
+
+        // With early stopping, the best development F1 so far, the number of iterations
+        // since it, and the previous weights of the features updated since then, to
+        // restore the best model at the end.
+        self.best_iteration = None;
+        let mut best = match (self.early_stopping_patience, &scores, &self.dev_set) {
+            (Some(_), Some((_, dev_scores)), Some(dev_set)) => {
+                self.best_iteration = Some(0);
+                Some((f1_score(&dev_set.labels, dev_scores), 0))
+            }
+            _ => None,
+        };
+        let mut undo: Vec<(usize, f64)> = Vec::new();
 
+        // Scores of the training instances for partial_fit, which stops once all of them
+        // are classified correctly.
+        let mut fit_scores: Option<Vec<f64>> = self.until_correct.then(|| {
+            let bias = self.get_bias();
+            let mut fit_scores = Vec::with_capacity(self.num_instances);
+            self.instances.for_each(0..self.num_instances, |_, hs| {
+                fit_scores.push(self.instance_score(bias, hs));
+            });
+            fit_scores
+        });
+
+        // The number of iterations that updated the model, recorded in the metadata.
+        let mut applied = 0;
         for _t in 0..self.num_iterations {
-            if !running.load(Ordering::SeqCst) {
+            if !control.checkpoint() {
+                trace_event!(info, iteration = _t, "training interrupted");
+                break;
+            }
+            if fit_scores.as_ref().is_some_and(|s| accuracy(&self.labels, s) >= 100.0) {
+                trace_event!(info, iteration = _t, "all instances classified correctly");
                 break;
             }
 
-            let mut errors = vec![0.0f64; num_features];
-            let mut instance_weight_sum = 0.0;
-            let mut positive_weight_sum = 0.0;
-
-            // Calculate errors and sum of weights
-            for i in 0..self.num_instances {
-                let d = self.instance_weights[i];
-                let label = self.labels[i];
-                instance_weight_sum += d;
-                if label > 0 {
-                    positive_weight_sum += d;
-                }
-                let delta = d * label as f64;
-                let (start, end) = self.instances[i];
-                for &h in &self.instances_buf[start..end] {
-                    errors[h] -= delta;
+            // With mini-batches, each instance is sampled with the probability that gives
+            // the batch size, scaled to the range of u64, except in correction iterations.
+            let sample_threshold = match self.mini_batch {
+                Some((batch_size, correction_interval))
+                    if batch_size < self.num_instances && (_t + 1) % correction_interval != 0 =>
+                {
+                    Some((batch_size as f64 / self.num_instances as f64 * u64::MAX as f64) as u64)
                 }
+                _ => None,
+            };
+
+            // Calculate errors and sum of weights, each thread over its own range of
+            // instances, checking the control between chunks of it. Real AdaBoost also
+            // needs the weight of the instances with each feature.
+            let (errors, coverage, instance_weight_sum, positive_weight_sum) = map_chunks_limited(
+                self.num_instances,
+                TRAIN_CHUNK_LEN,
+                self.num_threads,
+                |range| {
+                    let mut errors = vec![0.0f64; num_features];
+                    let mut coverage = match self.algorithm {
+                        Algorithm::Discrete => Vec::new(),
+                        Algorithm::Real => vec![0.0f64; num_features],
+                    };
+                    let mut instance_weight_sum = 0.0;
+                    let mut positive_weight_sum = 0.0;
+                    for start in range.clone().step_by(TRAIN_CHUNK_LEN) {
+                        if !control.checkpoint() {
+                            break;
+                        }
+                        let chunk = start..(start + TRAIN_CHUNK_LEN).min(range.end);
+                        self.instances.for_each(chunk, |i, hs| {
+                            if sample_threshold.is_some_and(|threshold| {
+                                SplitMix64::nth(
+                                    MINI_BATCH_SEED,
+                                    (self.num_instances * _t + i) as u64,
+                                ) > threshold
+                            }) {
+                                return;
+                            }
+                            let d = self.instance_weights[i];
+                            let label = self.labels[i];
+                            instance_weight_sum += d;
+                            if label > 0 {
+                                positive_weight_sum += d;
+                            }
+                            let delta = d * label as f64;
+                            for &h in hs {
+                                errors[h as usize] -= delta;
+                            }
+                            if !coverage.is_empty() {
+                                for &h in hs {
+                                    coverage[h as usize] += d;
+                                }
+                            }
+                        });
+                    }
+                    (errors, coverage, instance_weight_sum, positive_weight_sum)
+                },
+            )
+            .into_iter()
+            .reduce(
+                |(mut errors, mut coverage, w, p), (other, other_coverage, other_w, other_p)| {
+                    for (e, o) in errors.iter_mut().zip(other) {
+                        *e += o;
+                    }
+                    for (c, o) in coverage.iter_mut().zip(other_coverage) {
+                        *c += o;
+                    }
+                    (errors, coverage, w + other_w, p + other_p)
+                },
+            )
+            .unwrap_or_default();
+            if control.is_stopped() {
+                // The sums of a stopped iteration may miss chunks of instances.
+                trace_event!(info, iteration = _t, "training interrupted");
+                break;
+            }
+
+            // The best hypothesis and its confidences, in the units of the instance
+            // margins, for the instances with and without the feature.
+            let hypothesis = match self.algorithm {
+                Algorithm::Discrete => discrete_hypothesis(
+                    &errors,
+                    instance_weight_sum,
+                    positive_weight_sum,
+                    self.threshold,
+                ),
+                Algorithm::Real => real_hypothesis(
+                    &errors,
+                    &coverage,
+                    instance_weight_sum,
+                    positive_weight_sum,
+                    self.num_instances,
+                    self.threshold,
+                ),
+            };
+            let Some((h_best, present, absent, error_rate)) = hypothesis else {
+                trace_event!(info, iteration = _t, "training converged");
+                break;
+            };
+            applied += 1;
+            let (present, absent) = (present * self.learning_rate, absent * self.learning_rate);
+
+            // A confidence c for the instances with the feature and c' for the others
+            // moves their scores by c / 2 and c' / 2. Adding (c - c') / 2 to the weight of
+            // the feature moves them by (c - c') / 4 and -(c - c') / 4, and the bias term
+            // of index 0, which no instance has, shifts all scores by the rest.
+            if best.is_some() {
+                undo.push((h_best, self.model[h_best]));
+                undo.push((0, self.model[0]));
+            }
+            let previous_weight = self.model[h_best];
+            self.model[h_best] += (present - absent) / 2.0;
+            self.model[0] -= (present + absent) / 2.0;
+            trace_event!(
+                debug,
+                iteration = _t,
+                feature = %self.features.get(h_best),
+                present,
+                absent,
+                sampled = sample_threshold.is_some(),
+                "boosting iteration"
+            );
+
+            // Update model
+            let (instances, labels) = (&self.instances, &self.labels);
+            let (present_exp, absent_exp) = (present.abs().exp(), absent.abs().exp());
+            for_each_chunk_mut(
+                &mut self.instance_weights[..self.num_instances],
+                TRAIN_CHUNK_LEN,
+                self.num_threads,
+                |offset, weights| {
+                    instances.for_each(offset..offset + weights.len(), |i, hs| {
+                        // The weight is multiplied by exp(-label * confidence).
+                        let (confidence, confidence_exp) =
+                            if hs.binary_search(&(h_best as FeatureId)).is_ok() {
+                                (present, present_exp)
+                            } else {
+                                (absent, absent_exp)
+                            };
+                        if labels[i] as f64 * confidence < 0.0 {
+                            weights[i - offset] *= confidence_exp;
+                        } else {
+                            weights[i - offset] /= confidence_exp;
+                        }
+                    });
+                },
+            );
+
+            if let Some(fit_scores) = &mut fit_scores {
+                update_scores(fit_scores, &self.instances, h_best as FeatureId, present, absent);
             }
+            let mut stop = false;
+            if let (Some((train_scores, dev_scores)), Some(dev_set)) = (&mut scores, &self.dev_set)
+            {
+                update_scores(train_scores, &self.instances, h_best as FeatureId, present, absent);
+                update_scores(dev_scores, &dev_set.instances, h_best as FeatureId, present, absent);
 
-            // Find the best hypothesis.
-            // Initialize h_best to 0 (the bias bucket, i.e., the empty-string feature "").
-            // The initial best_error_rate corresponds to a hypothetical weak learner that
-            // predicts all instances as negative (label = -1), whose error rate equals the
-            // fraction of positive instances.  Any real feature (index >= 1) must beat this
-            // baseline to be selected.  If none does, h_best stays 0 and the bias bucket
-            // is updated, which is equivalent to adding a constant "all-negative" weak learner.
-            let mut h_best = 0;
-            let mut best_error_rate = positive_weight_sum / instance_weight_sum;
-            for (h, _) in errors.iter().enumerate().skip(1) {
-                let mut e = errors[h] + positive_weight_sum;
-                e /= instance_weight_sum;
-                if (0.5 - e).abs() > (0.5 - best_error_rate).abs() {
-                    h_best = h;
-                    best_error_rate = e;
+                let mut record = TrainingRecord {
+                    iteration: _t + 1,
+                    train_accuracy: accuracy(&self.labels, train_scores),
+                    dev_accuracy: accuracy(&dev_set.labels, dev_scores),
+                    dev_f1: f1_score(&dev_set.labels, dev_scores),
+                    overfitting: false,
+                };
+                let gap = record.gap();
+                if gap > previous_gap {
+                    growths += 1;
+                } else if gap < previous_gap {
+                    growths = 0;
+                }
+                previous_gap = gap;
+                if let Some((patience, stop_on_overfitting)) = self.overfitting_patience {
+                    if growths >= patience {
+                        trace_event!(
+                            warn,
+                            iteration = _t + 1,
+                            gap,
+                            growths,
+                            "train/dev gap keeps growing; the model may be overfitting"
+                        );
+                        record.overfitting = true;
+                        growths = 0;
+                        stop = stop_on_overfitting;
+                    }
                 }
+                if let (Some((best_f1, since_best)), Some(patience)) =
+                    (&mut best, self.early_stopping_patience)
+                {
+                    let f1 = record.dev_f1;
+                    if f1 > *best_f1 {
+                        *best_f1 = f1;
+                        *since_best = 0;
+                        self.best_iteration = Some(_t + 1);
+                        undo.clear();
+                    } else {
+                        *since_best += 1;
+                        if *since_best >= patience {
+                            trace_event!(
+                                info,
+                                iteration = _t + 1,
+                                best_iteration = self.best_iteration,
+                                "development F1 stopped improving"
+                            );
+                            stop = true;
+                        }
+                    }
+                }
+                self.training_log.push(record);
+            }
+            if let Some(progress) = &self.progress {
+                let record = scores.as_ref().and(self.training_log.last());
+                let train_accuracy = match record {
+                    Some(record) => Some(record.train_accuracy),
+                    None if self.accuracy_interval > 0
+                        && (_t + 1) % self.accuracy_interval == 0 =>
+                    {
+                        Some(self.get_metrics().accuracy)
+                    }
+                    None => None,
+                };
+                progress.on_iteration(&TrainingProgress {
+                    iteration: _t + 1,
+                    num_iterations: self.num_iterations,
+                    feature: self.features.get(h_best),
+                    alpha: self.model[h_best] - previous_weight,
+                    error_rate,
+                    train_accuracy,
+                    record,
+                });
             }
-
-            if (0.5 - best_error_rate).abs() < self.threshold {
+            if stop {
                 break;
             }
 
-            // Calculate alpha (weight for the weak learner)
-            let alpha =
-                0.5 * ((1.0 - best_error_rate).max(1e-10) / best_error_rate.max(1e-10)).ln();
-            let alpha_exp = alpha.exp();
-            self.model[h_best] += alpha;
-
-            // Update model
-            for i in 0..self.num_instances {
-                let label = self.labels[i];
-                let (start, end) = self.instances[i];
-                let hs = &self.instances_buf[start..end];
-                let prediction = if hs.binary_search(&h_best).is_ok() { 1 } else { -1 };
-                if label * prediction < 0 {
-                    self.instance_weights[i] *= alpha_exp;
-                } else {
-                    self.instance_weights[i] /= alpha_exp;
-                }
-            }
-
             // Normalize instance weights (guard against zero sum to prevent NaN).
             let sum_w: f64 = self.instance_weights.iter().sum();
             if sum_w > 0.0 {
@@ -295,6 +1840,94 @@ impl AdaBoost {
                     *d /= sum_w;
                 }
             }
+            self.cap_instance_weights();
+        }
+
+        // Restore the weights of the best iteration.
+        for &(h, weight) in undo.iter().rev() {
+            self.model[h] = weight;
+        }
+        self.update_bias();
+        if best.is_some() {
+            applied = self.best_iteration.unwrap_or(applied);
+        }
+        self.record_training(applied);
+    }
+
+    #[cfg(feature = "train")]
+    /// Adds the iterations of a training run to the total in the metadata and records
+    /// the date.
+    fn record_training(&mut self, iterations: usize) {
+        let total = self
+            .metadata(model_info::ITERATIONS_KEY)
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0)
+            + iterations;
+        self.metadata.insert(model_info::ITERATIONS_KEY.to_string(), total.to_string());
+        self.metadata.insert(model_info::DATE_KEY.to_string(), model_info::today());
+    }
+
+    #[cfg(feature = "train")]
+    /// Reads the weight of an instance from the column after its label if features files
+    /// are [weighted](Self::set_weighted).
+    ///
+    /// # Returns: The weight, 1 for unweighted files.
+    ///
+    /// # Errors: Returns an error if the weight is missing or not a positive number.
+    fn instance_weight(
+        &self,
+        parts: &mut core::str::SplitWhitespace<'_>,
+        line_num: usize,
+    ) -> std::io::Result<f64> {
+        if !self.weighted {
+            return Ok(1.0);
+        }
+        let reason = match parts.next().map(str::parse::<f64>) {
+            Some(Ok(weight)) if weight.is_finite() && weight > 0.0 => return Ok(weight),
+            Some(Ok(weight)) => format!("Weight must be positive: {}", weight),
+            Some(Err(e)) => format!("Invalid weight: {}", e),
+            None => "Missing weight in instance line".to_string(),
+        };
+        Err(LitseaError::Parse {
+            line: line_num + 1,
+            reason,
+        }
+        .into())
+    }
+
+    #[cfg(feature = "mmap")]
+    /// Fails if features files are [weighted](Self::set_weighted), since instance files
+    /// have no weights.
+    fn check_unweighted(&self) -> std::io::Result<()> {
+        if self.weighted {
+            return Err(LitseaError::InvalidInput(
+                "Instance files cannot hold the weights of a weighted features file".to_string(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Returns the score of an instance given as its feature indices.
+    fn instance_score(&self, bias: f64, features: &[FeatureId]) -> f64 {
+        bias + features.iter().map(|&h| self.model[h as usize]).sum::<f64>()
+    }
+
+    #[cfg(feature = "train")]
+    /// Caps the instance weights at the multiple of their average set by
+    /// [`Boosting::Capped`]. Does nothing for [`Boosting::AdaBoost`].
+    fn cap_instance_weights(&mut self) {
+        let Boosting::Capped(cap) = self.boosting else {
+            return;
+        };
+        if self.num_instances == 0 {
+            return;
+        }
+        let sum_w: f64 = self.instance_weights.iter().sum();
+        let max_w = cap * sum_w / self.num_instances as f64;
+        for d in &mut self.instance_weights {
+            *d = d.min(max_w);
         }
     }
 
@@ -312,29 +1945,77 @@ impl AdaBoost {
     /// This method writes the model to a file in a tab-separated format,
     /// where each line contains a feature and its corresponding weight.
     /// The last line contains the bias term, which is calculated as the negative sum of the model weights divided by 2.
-    pub fn save_model(&self, filename: &Path) -> std::io::Result<()> {
+    /// A file name ending in `.gz` or `.zst` is compressed, see [`compression`](crate::compression).
+    #[cfg(feature = "std")]
+    pub fn save_model(&self, filename: &Path) -> Result<(), LitseaError> {
+        if self.model.is_empty() {
+            return Err(LitseaError::InvalidInput("Cannot save an empty model".to_string()));
+        }
+        let mut writer = compression::create(filename)?;
+        self.write_model(&mut writer)?;
+        Ok(writer.finish()?)
+    }
+
+    /// Writes the model in Litsea's model format, preceded by a header with its checksum.
+    ///
+    /// # Arguments
+    /// * `writer`: The destination of the model.
+    ///
+    /// # Errors: Returns an error if the model is empty or writing fails.
+    #[cfg(feature = "std")]
+    pub(crate) fn write_model<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
         if self.model.is_empty() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 "Cannot save an empty model",
             ));
         }
-        let mut file = File::create(filename)?;
-        let mut bias = -self.model[0];
+        let mut body = Vec::new();
+        // Only i8 weights need the newest format, so other models still load in earlier
+        // versions.
+        let format = match self.quantization {
+            Some((Quantization::I8, _)) => model_info::FORMAT_VERSION,
+            _ => 1,
+        };
+        writeln!(body, "#{} {}", model_info::FORMAT_KEY, format)?;
+        match self.quantization {
+            Some((Quantization::I8, scale)) => {
+                writeln!(body, "#{} {} {}", model_info::QUANTIZATION_KEY, Quantization::I8, scale)?
+            }
+            Some((quantization, _)) => {
+                writeln!(body, "#{} {}", model_info::QUANTIZATION_KEY, quantization)?
+            }
+            None => {}
+        }
+        for (key, value) in &self.metadata {
+            if key != model_info::FORMAT_KEY && key != model_info::QUANTIZATION_KEY {
+                writeln!(body, "#{} {}", key, value)?;
+            }
+        }
         for (h, &w) in self.features.iter().zip(self.model.iter()).skip(1) {
-            if w != 0.0 {
-                writeln!(file, "{}\t{}", h, w)?;
-                bias -= w;
+            match self.quantization {
+                _ if w == 0.0 => {}
+                None => writeln!(body, "{}\t{}", h, w)?,
+                Some((Quantization::F16, _)) => match quantize::round_f16(w) {
+                    0.0 => {}
+                    rounded => writeln!(body, "{}\t{}", h, quantize::format_f16(rounded))?,
+                },
+                Some((Quantization::I8, scale)) => match quantize::to_i8(w, scale) {
+                    0 => {}
+                    q => writeln!(body, "{}\t{}", h, q)?,
+                },
             }
         }
-        writeln!(file, "{}", bias / 2.0)?;
-        Ok(())
+        // The bias is kept in full precision; loading subtracts the weights as written.
+        writeln!(body, "{}", self.get_bias())?;
+        writer.write_all(&integrity::add_header(&body))
     }
 
     /// Loads a model from a URI.
     /// The URI can be a file path or a URL (http, https or file).
     /// The model should contain lines with a feature and its weight,
-    /// with the last line containing the bias term.
+    /// with the last line containing the bias term. Local files ending in `.gz` or
+    /// `.zst` are decompressed, see [`compression`](crate::compression).
     ///
     /// # Arguments
     /// * `uri`: The URI of the file containing the model.
@@ -342,61 +2023,63 @@ impl AdaBoost {
     /// # Returns: A result indicating success or failure.
     ///
     /// # Errors: Returns an error if the URI is invalid or the file cannot be read.
-    pub async fn load_model(&mut self, uri: &str) -> std::io::Result<()> {
+    #[cfg(feature = "std")]
+    pub async fn load_model(&mut self, uri: &str) -> Result<(), LitseaError> {
         if uri.contains("://") {
             let parts: Vec<&str> = uri.splitn(2, "://").collect();
             if parts.len() != 2 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidInput,
-                    format!("Invalid URI: {}", uri),
-                ));
+                return Err(LitseaError::InvalidInput(format!("Invalid URI: {}", uri)));
             }
-            let scheme = ModelScheme::from_str(parts[0]).map_err(|e| {
-                std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string())
-            })?;
+            let scheme = ModelScheme::from_str(parts[0]).map_err(LitseaError::InvalidInput)?;
             match scheme {
                 ModelScheme::Http | ModelScheme::Https => {
                     #[cfg(not(feature = "remote_model"))]
                     {
-                        return Err(std::io::Error::new(
+                        Err(LitseaError::Io(std::io::Error::new(
                             std::io::ErrorKind::Unsupported,
                             "http:// and https:// scheme is not supported in this build. Use file:// URLs.",
-                        ));
+                        )))
                     }
                     #[cfg(feature = "remote_model")]
                     {
+                        // Errors about the content of the model are kept as they are.
                         self.load_model_from_url(uri).await.map_err(|e| {
-                            std::io::Error::other(format!("Failed to load model from URL: {}", e))
+                            match LitseaError::from(e) {
+                                LitseaError::Io(e) => LitseaError::Io(std::io::Error::other(
+                                    format!("Failed to load model from URL: {}", e),
+                                )),
+                                e => e,
+                            }
                         })
                     }
                 }
                 ModelScheme::File => {
                     #[cfg(target_arch = "wasm32")]
                     {
-                        return Err(std::io::Error::new(
+                        return Err(LitseaError::Io(std::io::Error::new(
                             std::io::ErrorKind::Unsupported,
                             "file:// scheme is not supported in WASM environment. Use http:// or https:// URLs.",
-                        ));
+                        )));
                     }
                     #[cfg(not(target_arch = "wasm32"))]
                     {
                         let path = Path::new(parts[1]);
-                        self.load_model_from_file(path)
+                        Ok(self.load_model_from_file(path)?)
                     }
                 }
             }
         } else {
             #[cfg(target_arch = "wasm32")]
             {
-                return Err(std::io::Error::new(
+                return Err(LitseaError::Io(std::io::Error::new(
                     std::io::ErrorKind::Unsupported,
                     "Local file paths are not supported in WASM environment. Use http:// or https:// URLs.",
-                ));
+                )));
             }
             #[cfg(not(target_arch = "wasm32"))]
             {
                 let path = Path::new(uri);
-                self.load_model_from_file(path)
+                Ok(self.load_model_from_file(path)?)
             }
         }
     }
@@ -413,91 +2096,181 @@ impl AdaBoost {
     /// # Errors: Returns an error if the URL cannot be accessed or the file cannot be read.
     #[cfg(feature = "remote_model")]
     async fn load_model_from_url(&mut self, url: &str) -> std::io::Result<()> {
-        use reqwest::Client;
-
-        // Create HTTP client with a custom user agent
-        let client = Client::builder()
-            .user_agent(format!("Litsea/{}", env!("CARGO_PKG_VERSION")))
-            .build()
-            .map_err(|e| std::io::Error::other(format!("Failed to create HTTP client: {}", e)))?;
-
-        // Send GET request to the URL
-        let resp = client
-            .get(url)
-            .send()
-            .await
-            .map_err(|e| std::io::Error::other(format!("Failed to download model: {}", e)))?;
-
-        // Check if the response status is successful
-        if !resp.status().is_success() {
-            return Err(std::io::Error::other(format!(
-                "Failed to download model: HTTP {}",
-                resp.status()
-            )));
-        }
+        trace_event!(debug, url, "downloading model");
+        let content = crate::util::download(url).await?;
+        self.load_model_bytes(&content)
+    }
+
+    /// Loads a model from the content of a model file, e.g. one embedded with
+    /// `include_str!`. This is how models are loaded without the `std` feature.
+    /// Unless integrity checking is disabled, the checksum in the header (and the
+    /// signature, if a public key is set) must match the content.
+    ///
+    /// # Arguments
+    /// * `content`: The content of the model file, including its header.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::ModelFormat`] if verification fails, or
+    /// [`LitseaError::Parse`] with the line that cannot be parsed.
+    pub fn load_model_str(&mut self, content: &str) -> Result<(), LitseaError> {
+        trace_span!(DEBUG, "load_model", bytes = content.len());
+        let body = self.verified_body(content.as_bytes())?;
+        // The header is a whole line, so the body is still valid UTF-8.
+        let body =
+            core::str::from_utf8(body).map_err(|e| LitseaError::ModelFormat(e.to_string()))?;
+        self.parse_model_str(body)
+    }
 
-        // Read the response body
-        let content = resp
-            .bytes()
-            .await
-            .map_err(|e| std::io::Error::other(format!("Failed to read model content: {}", e)))?;
+    /// Verifies and parses the content of a model file.
+    /// See [`load_model_str`](Self::load_model_str).
+    ///
+    /// # Arguments
+    /// * `content`: The bytes of the model file, including its header.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns an error carrying a [`LitseaError`] if verification fails or the
+    /// content cannot be parsed.
+    #[cfg(feature = "std")]
+    pub(crate) fn load_model_bytes(&mut self, content: &[u8]) -> std::io::Result<()> {
+        trace_span!(DEBUG, "load_model", bytes = content.len());
+        let body = self.verified_body(content)?;
+        self.parse_model_content(body)
+    }
 
-        let reader = BufReader::new(content.as_ref());
-        self.parse_model_content(reader)
+    /// Checks the header of a model file, unless integrity checking is disabled.
+    ///
+    /// # Arguments
+    /// * `content`: The bytes of the model file, including its header.
+    ///
+    /// # Returns: The model content after the header.
+    ///
+    /// # Errors: Returns [`LitseaError::ModelFormat`] if the checksum or the signature does
+    /// not match.
+    fn verified_body<'a>(&self, content: &'a [u8]) -> Result<&'a [u8], LitseaError> {
+        if !self.verify_integrity {
+            return Ok(integrity::split_header(content).1);
+        }
+        #[cfg(feature = "signing")]
+        if let Some(key) = &self.verifying_key {
+            return integrity::verify_signed(content, key)
+                .map_err(|e| LitseaError::ModelFormat(e.to_string()));
+        }
+        integrity::check_checksum(content)
+            .map(|(_, body)| body)
+            .map_err(LitseaError::ModelFormat)
     }
 
-    /// Parses model content from a buffered reader.
-    /// This is a helper method used by both `load_model_from_file` and `load_model_from_url`.
+    /// Parses the content of a model file after its header.
     ///
     /// # Arguments
     /// * `reader`: A buffered reader containing the model data.
     ///
     /// # Returns: A result indicating success or failure.
     ///
-    /// # Errors: Returns an error if the content cannot be parsed.
-    pub(crate) fn parse_model_content<R: BufRead>(&mut self, reader: R) -> std::io::Result<()> {
-        let mut m: HashMap<String, f64> = HashMap::new();
+    /// # Errors: Returns an error if the content cannot be read or parsed.
+    #[cfg(feature = "std")]
+    pub(crate) fn parse_model_content<R: BufRead>(&mut self, mut reader: R) -> std::io::Result<()> {
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        Ok(self.parse_model_str(&content)?)
+    }
+
+    /// Parses the content of a model file after its header.
+    /// Each line holds a feature and its weight, and the last line the bias term. The
+    /// weights of a [quantized](crate::quantize) model are read as they were rounded:
+    /// f16 weights are rounded again, and i8 weights multiplied by the scale.
+    ///
+    /// # Arguments
+    /// * `content`: The model data.
+    ///
+    /// # Returns: A result indicating success or failure.
+    ///
+    /// # Errors: Returns [`LitseaError::Parse`] with the first line that cannot be parsed,
+    /// or [`LitseaError::ModelFormat`] if the metadata is invalid.
+    fn parse_model_str(&mut self, content: &str) -> Result<(), LitseaError> {
+        let mut sorted: BTreeMap<String, f64> = BTreeMap::new();
         let mut bias = 0.0;
+        let mut quantization = None;
+        self.metadata.clear();
 
-        for (line_num, line) in reader.lines().enumerate() {
-            let line = line?;
+        for (line_num, line) in content.lines().enumerate() {
+            if let Some(entry) = line.strip_prefix('#') {
+                let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+                let value = value.trim_end_matches('\r');
+                if key == model_info::QUANTIZATION_KEY {
+                    quantization = Some(quantize::parse_entry(value)?);
+                }
+                self.metadata.insert(key.to_string(), value.to_string());
+                continue;
+            }
             let mut parts = line.split_whitespace();
 
-            let h = parts.next().ok_or_else(|| {
-                std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Empty line at line {}", line_num + 1),
-                )
-            })?;
+            let parse_error = |reason| LitseaError::Parse {
+                line: line_num + 1,
+                reason,
+            };
+            let h = parts.next().ok_or_else(|| parse_error("Empty line".to_string()))?;
 
             if let Some(v) = parts.next() {
-                let value: f64 = v.parse().map_err(|e| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        format!("Invalid value at line {}: {}", line_num + 1, e),
-                    )
-                })?;
-                m.insert(h.to_string(), value);
+                let value: f64 =
+                    v.parse().map_err(|e| parse_error(format!("Invalid value: {}", e)))?;
+                let value = match quantization {
+                    Some((Quantization::F16, _)) => quantize::round_f16(value),
+                    Some((Quantization::I8, scale)) => value * scale,
+                    None => value,
+                };
+                sorted.insert(h.to_string(), value);
                 bias += value;
             } else {
-                let b: f64 = h.parse().map_err(|e| {
-                    std::io::Error::new(
-                        std::io::ErrorKind::InvalidData,
-                        format!("Invalid bias at line {}: {}", line_num + 1, e),
-                    )
-                })?;
-                m.insert("".to_string(), -b * 2.0 - bias);
+                let b: f64 = h.parse().map_err(|e| parse_error(format!("Invalid bias: {}", e)))?;
+                sorted.insert("".to_string(), -b * 2.0 - bias);
             }
         }
+        model_info::validate(
+            self.metadata(model_info::FORMAT_KEY),
+            self.metadata(model_info::ITERATIONS_KEY),
+        )?;
 
-        let sorted: BTreeMap<_, _> = m.into_iter().collect();
-        self.features = sorted.keys().cloned().collect();
+        self.features = Arc::new(sorted.keys().map(String::as_str).collect());
         self.model = sorted.values().cloned().collect();
-        self.feature_index =
-            self.features.iter().enumerate().map(|(i, f)| (f.clone(), i)).collect();
+        self.update_bias();
+        self.index_features();
+        trace_event!(debug, num_features = self.features.len(), "loaded model");
         Ok(())
     }
 
+    /// Replaces the model with the given feature weights and bias.
+    /// This is used by the model importers to build a model from foreign formats.
+    ///
+    /// # Arguments
+    /// * `weights`: The feature weights. Later duplicates are added to earlier ones.
+    /// * `bias`: The bias term, as written on the last line of a model file.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_weights<I>(&mut self, weights: I, bias: f64)
+    where
+        I: IntoIterator<Item = (String, f64)>,
+    {
+        let mut sorted: BTreeMap<String, f64> = BTreeMap::new();
+        for (h, w) in weights {
+            *sorted.entry(h).or_insert(0.0) += w;
+        }
+        let sum: f64 = sorted.values().sum();
+        // The bias bucket is stored so that get_bias() returns `bias`.
+        sorted.insert("".to_string(), -bias * 2.0 - sum);
+
+        self.features = Arc::new(sorted.keys().map(String::as_str).collect());
+        self.model = sorted.values().cloned().collect();
+        self.update_bias();
+        self.index_features();
+    }
+
+    /// Rebuilds the feature index from the feature table.
+    fn index_features(&mut self) {
+        self.feature_index = Arc::new(index_of(&self.features));
+    }
+
     /// Loads a model from a file.
     /// The file should contain lines with a feature and its weight,
     /// with the last line containing the bias term.
@@ -508,14 +2281,14 @@ impl AdaBoost {
     /// # Returns: A result indicating success or failure.
     ///
     /// # Errors: Returns an error if the file cannot be read.
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
     fn load_model_from_file(&mut self, filename: &Path) -> std::io::Result<()> {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
-        self.parse_model_content(reader)
+        let mut content = Vec::new();
+        std::io::Read::read_to_end(&mut compression::open(filename)?, &mut content)?;
+        self.load_model_bytes(&content)
     }
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(all(feature = "std", target_arch = "wasm32"))]
     fn load_model_from_file(&mut self, _filename: &Path) -> std::io::Result<()> {
         Err(std::io::Error::new(
             std::io::ErrorKind::Unsupported,
@@ -523,122 +2296,789 @@ impl AdaBoost {
         ))
     }
 
+    #[cfg(feature = "train")]
     /// Adds a new instance to the model.
     /// The instance is represented by a set of attributes and a label.
+    /// Instances added this way can be trained on with [`train`](Self::train).
     ///
     /// # Arguments
     /// * `attributes`: A `HashSet<String>` containing the attributes of the instance.
     /// * `label`: The label of the instance, represented as an `i8`.
     pub fn add_instance(&mut self, attributes: HashSet<String>, label: i8) {
-        let start = self.instances_buf.len();
-        let attrs: Vec<String> = attributes.into_iter().collect();
-        for attr in attrs.iter() {
-            let idx = if let Some(&pos) = self.feature_index.get(attr) {
+        #[cfg(feature = "mmap")]
+        assert!(self.instances.mapped.is_none(), "cannot add instances to mapped instances");
+        // Training expects the bias bucket at position 0, as initialize_features puts it.
+        if self.model.is_empty() {
+            Arc::make_mut(&mut self.features).push("");
+            self.model.push(0.0);
+            Arc::make_mut(&mut self.feature_index).insert(Box::from(""), 0);
+        }
+        let mut features = Vec::with_capacity(attributes.len());
+        for attr in attributes {
+            let idx = if let Some(&pos) = self.feature_index.get(attr.as_str()) {
                 pos
             } else {
-                let pos = self.features.len();
-                self.features.push(attr.clone());
+                let pos = Arc::make_mut(&mut self.features).push(&attr);
                 self.model.push(0.0);
-                self.feature_index.insert(attr.clone(), pos);
+                Arc::make_mut(&mut self.feature_index).insert(attr.into_boxed_str(), pos);
                 pos
             };
-            self.instances_buf.push(idx);
+            features.push(idx);
         }
-        let end = self.instances_buf.len();
-        // Sort feature indices numerically so that binary_search in train() works correctly.
-        self.instances_buf[start..end].sort_unstable();
-        self.instances.push((start, end));
+        // Training looks features up in each instance by binary search.
+        features.sort_unstable();
+        self.instances.push(features);
         self.labels.push(label);
         self.instance_weights.push(1.0);
         self.num_instances += 1;
     }
 
+    #[cfg(feature = "train")]
+    /// Updates the model with new instances without retraining from scratch.
+    ///
+    /// The instances are added as with [`add_instance`](Self::add_instance), and boosting
+    /// resumes from the current weights over all the instances of the learner until it
+    /// classifies every one of them correctly, for at most
+    /// [`num_iterations`](Self::num_iterations) rounds. Like training resumed from a model
+    /// file, every instance starts from the weight its margin under the current model
+    /// gives, so the instances the model gets wrong weigh the most.
+    ///
+    /// A learner loaded from a model file holds no other instances, so a weak learner
+    /// that separates a handful of corrections gets a large confidence. Set a small
+    /// [learning rate](Self::set_learning_rate), e.g. 0.02, to change the model only as
+    /// much as the corrections need.
+    ///
+    /// # Arguments
+    /// * `instances`: The attributes and the label of each new instance.
+    ///
+    /// # Returns: The number of instances added.
+    pub fn partial_fit<I>(&mut self, instances: I) -> usize
+    where
+        I: IntoIterator<Item = (HashSet<String>, i8)>,
+    {
+        let start = self.num_instances;
+        for (attributes, label) in instances {
+            self.add_instance(attributes, label);
+        }
+        if self.num_instances == 0 {
+            return 0;
+        }
+
+        let bias = self.get_bias();
+        let mut weights = Vec::with_capacity(self.num_instances);
+        self.instances.for_each(0..self.num_instances, |i, hs| {
+            let score = self.instance_score(bias, hs);
+            weights.push((-2.0 * self.labels[i] as f64 * score).exp());
+        });
+        self.instance_weights = weights;
+        trace_event!(
+            debug,
+            num_added = self.num_instances - start,
+            num_instances = self.num_instances,
+            "partial fit"
+        );
+        self.until_correct = true;
+        self.train(TrainControl::new());
+        self.until_correct = false;
+        self.num_instances - start
+    }
+
     /// Predicts the label for a given set of attributes.
+    /// Each attribute is looked up in the feature index in constant time,
+    /// and attributes unknown to the model are ignored.
     ///
     /// # Arguments
     /// * `attributes`: A `HashSet<String>` containing the attributes to predict.
     ///
     /// # Returns: The predicted label as an `i8`, where 1 indicates a positive prediction and -1 indicates a negative prediction.
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn predict(&self, attributes: HashSet<String>) -> i8 {
-        let mut score = self.get_bias();
-        for attr in &attributes {
-            if let Some(&idx) = self.feature_index.get(attr) {
-                score += self.model[idx];
+        if self.score(&attributes) >= 0.0 { 1 } else { -1 }
+    }
+
+    /// Computes the classification score for a given set of attributes.
+    /// The score is the bias plus the weights of all attributes known to the model;
+    /// a non-negative score is a positive prediction, and its magnitude is the margin.
+    ///
+    /// # Arguments
+    /// * `attributes`: The attributes to score.
+    ///
+    /// # Returns: The score as an `f64`.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn score(&self, attributes: &HashSet<String>) -> f64 {
+        self.score_attributes(attributes)
+    }
+
+    /// Computes the classification score for attributes given in any collection.
+    /// Unlike [`score`](Self::score), this is available without the `std` feature.
+    /// Each attribute must occur only once.
+    ///
+    /// # Arguments
+    /// * `attributes`: The attributes to score.
+    ///
+    /// # Returns: The score as an `f64`.
+    #[must_use]
+    pub fn score_attributes<I>(&self, attributes: I) -> f64
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut score = self.bias;
+        for attr in attributes {
+            if let Some(&idx) = self.feature_index.get(attr.as_ref()) {
+                score += self.model[idx as usize];
             }
         }
-        if score >= 0.0 { 1 } else { -1 }
+        score
+    }
+
+    /// Estimates the heap memory held by the model, in bytes.
+    ///
+    /// Counts the capacity of the weight vector, the feature names and the feature index.
+    /// Hash table overhead is approximated by the size of its entries, and training
+    /// buffers are included when present.
+    ///
+    /// # Returns: The estimated number of heap bytes.
+    #[cfg(feature = "std")]
+    pub(crate) fn heap_size(&self) -> usize {
+        use std::mem::size_of;
+
+        let names: usize = self.features.iter().map(str::len).sum();
+        #[cfg(feature = "train")]
+        let training = self.instance_weights.capacity() * size_of::<f64>()
+            + self.labels.capacity() * size_of::<BinaryLabel>()
+            + self.instances.heap_size();
+        #[cfg(not(feature = "train"))]
+        let training = 0;
+
+        self.model.capacity() * size_of::<f64>()
+            + self.features.heap_size()
+            // The index holds its own copy of every feature name.
+            + self.feature_index.capacity() * (size_of::<Box<str>>() + size_of::<FeatureId>())
+            + names
+            + training
+    }
+
+    /// Returns the non-zero feature weights of the model, excluding the bias bucket.
+    ///
+    /// Features are yielded in the model's internal order, which is sorted by feature name
+    /// for models loaded from a file or initialized from a features file.
+    ///
+    /// # Returns: An iterator over `(feature, weight)` pairs.
+    pub fn weights(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.features
+            .iter()
+            .zip(self.model.iter())
+            .filter(|(h, w)| !h.is_empty() && **w != 0.0)
+            .map(|(h, w)| (h, *w))
     }
 
     /// Gets the bias term of the model.
     /// The bias is calculated as the negative sum of the model weights divided by 2.
     ///
-    /// # Returns: The bias term as a `f64`.
+    /// # Returns: The bias term as a `f64`.
+    #[must_use]
+    pub fn get_bias(&self) -> f64 {
+        -self.model.iter().sum::<f64>() / 2.0
+    }
+
+    /// Recomputes the bias read by [`score_attributes`](Self::score_attributes), which
+    /// would otherwise sum all the weights for every score. Called whenever the weights
+    /// change.
+    fn update_bias(&mut self) {
+        self.bias = self.get_bias();
+    }
+
+    /// Drops the features whose weight is smaller than `threshold` in absolute value, to
+    /// make the model smaller. The bias is kept, so only the scores of the instances that
+    /// contain a dropped feature change. Dropped features are not written by
+    /// [`save_model`](Self::save_model).
+    ///
+    /// # Arguments
+    /// * `threshold` - The smallest absolute weight to keep.
+    ///
+    /// # Returns: The number of features dropped.
+    pub fn prune(&mut self, threshold: f64) -> usize {
+        let mut dropped = 0;
+        for h in 1..self.model.len() {
+            let w = self.model[h];
+            if w != 0.0 && w.abs() < threshold {
+                self.drop_feature(h);
+                dropped += 1;
+            }
+        }
+        self.update_bias();
+        dropped
+    }
+
+    /// Keeps only the `max_features` features with the largest weights in absolute value,
+    /// dropping the others as [`prune`](Self::prune) does. Ties are broken by the
+    /// model's internal order.
+    ///
+    /// # Arguments
+    /// * `max_features` - The number of features to keep.
+    ///
+    /// # Returns: The number of features dropped.
+    pub fn prune_to(&mut self, max_features: usize) -> usize {
+        let mut kept: Vec<usize> =
+            (1..self.model.len()).filter(|&h| self.model[h] != 0.0).collect();
+        if kept.len() <= max_features {
+            return 0;
+        }
+        kept.select_nth_unstable_by(max_features, |&a, &b| {
+            self.model[b].abs().total_cmp(&self.model[a].abs()).then(a.cmp(&b))
+        });
+        let dropped = kept.split_off(max_features);
+        for &h in &dropped {
+            self.drop_feature(h);
+        }
+        self.update_bias();
+        dropped.len()
+    }
+
+    /// Rounds the weights to `quantization`, so that [`save_model`](Self::save_model)
+    /// writes them with fewer digits, see the [`quantize`](crate::quantize) module. The
+    /// bias is kept, and features whose weight rounds to 0 are dropped. Training the
+    /// model again clears the quantization.
+    ///
+    /// # Arguments
+    /// * `quantization` - How to round the weights.
+    ///
+    /// # Returns: How much the weights changed.
+    #[cfg(feature = "std")]
+    pub fn quantize(&mut self, quantization: Quantization) -> QuantizationError {
+        let scale = match quantization {
+            Quantization::F16 => 1.0,
+            Quantization::I8 => {
+                quantize::i8_scale(self.weights().fold(0.0, |max, (_, w)| w.abs().max(max)))
+            }
+        };
+        self.set_quantization(quantization, scale)
+    }
+
+    /// Rounds the weights to `quantization` with the given scale of i8 weights, e.g. to
+    /// keep the scale of a quantized model being repaired. See [`quantize`](Self::quantize).
+    #[cfg(feature = "std")]
+    pub(crate) fn set_quantization(
+        &mut self,
+        quantization: Quantization,
+        scale: f64,
+    ) -> QuantizationError {
+        let round = |w| match quantization {
+            Quantization::F16 => quantize::round_f16(w),
+            Quantization::I8 => quantize::round_i8(w, scale),
+        };
+        let mut error = QuantizationError::default();
+        let mut num_weights = 0;
+        for h in 1..self.model.len() {
+            let w = self.model[h];
+            if w == 0.0 {
+                continue;
+            }
+            let rounded = round(w);
+            let change = (rounded - w).abs();
+            num_weights += 1;
+            error.max_error = error.max_error.max(change);
+            error.mean_error += change;
+            if rounded == 0.0 {
+                self.drop_feature(h);
+                error.features_zeroed += 1;
+            } else {
+                self.model[0] += w - rounded;
+                self.model[h] = rounded;
+            }
+        }
+        if num_weights > 0 {
+            error.mean_error /= num_weights as f64;
+        }
+        self.update_bias();
+        self.quantization = Some((quantization, scale));
+        error
+    }
+
+    /// Moves the weight of a feature to the bias bucket, which keeps the bias unchanged.
+    fn drop_feature(&mut self, h: usize) {
+        self.model[0] += self.model[h];
+        self.model[h] = 0.0;
+    }
+
+    #[cfg(feature = "train")]
+    /// Calculates the performance metrics of the model on the development set.
+    ///
+    /// # Returns: The metrics, or `None` without a development set.
+    #[must_use]
+    pub fn dev_metrics(&self) -> Option<Metrics> {
+        let dev_set = self.dev_set.as_ref()?;
+        Some(self.metrics(&dev_set.labels, &dev_set.instances))
+    }
+
+    #[cfg(feature = "train")]
+    /// Calculates and returns the performance metrics of the model on the training data.
+    /// Instances are scored in parallel on large training sets.
+    #[must_use]
+    pub fn get_metrics(&self) -> Metrics {
+        let n = self.num_instances.min(self.labels.len());
+        self.metrics(&self.labels[..n], &self.instances)
+    }
+
+    #[cfg(feature = "train")]
+    /// Scores instances across threads and counts the confusion matrix of their predictions.
+    fn metrics(&self, labels: &[BinaryLabel], instances: &InstanceStore) -> Metrics {
+        let bias = self.get_bias();
+        map_chunks_limited(labels.len(), METRICS_CHUNK_LEN, self.num_threads, |range| {
+            let mut confusion = Confusion::default();
+            instances.for_each(range, |i, hs| {
+                let score = self.instance_score(bias, hs);
+                confusion.add(labels[i] > 0, score >= 0.0);
+            });
+            confusion
+        })
+        .into_iter()
+        .fold(Confusion::default(), Confusion::merge)
+        .into()
+    }
+}
+
+#[cfg(feature = "train")]
+/// Parses the label at the start of an instance line of a features file.
+///
+/// # Errors: Returns an error carrying [`LitseaError::Parse`] with `line_num + 1` if the
+/// label is missing or invalid.
+fn parse_label(token: Option<&str>, line_num: usize) -> std::io::Result<BinaryLabel> {
+    let reason = match token.map(str::parse) {
+        Some(Ok(label)) => return Ok(label),
+        Some(Err(e)) => format!("Invalid label: {}", e),
+        None => "Missing label in instance line".to_string(),
+    };
+    Err(LitseaError::Parse {
+        line: line_num + 1,
+        reason,
+    }
+    .into())
+}
+
+#[cfg(feature = "train")]
+/// Chooses the weak learner of an iteration of discrete AdaBoost: the feature whose
+/// error rate is the farthest from 0.5.
+///
+/// # Returns: The index of the feature, the confidences `alpha` and `-alpha` for the
+/// instances with and without it and its weighted error rate, or `None` if the edge of
+/// the best feature is below the threshold.
+fn discrete_hypothesis(
+    errors: &[f64],
+    instance_weight_sum: f64,
+    positive_weight_sum: f64,
+    threshold: f64,
+) -> Option<(usize, f64, f64, f64)> {
+    // Initialize h_best to 0 (the bias bucket, i.e., the empty-string feature "").
+    // The initial best_error_rate corresponds to a hypothetical weak learner that
+    // predicts all instances as negative (label = -1), whose error rate equals the
+    // fraction of positive instances.  Any real feature (index >= 1) must beat this
+    // baseline to be selected.  If none does, h_best stays 0 and the bias bucket
+    // is updated, which is equivalent to adding a constant "all-negative" weak learner.
+    let mut h_best = 0;
+    let mut best_error_rate = positive_weight_sum / instance_weight_sum;
+    for (h, _) in errors.iter().enumerate().skip(1) {
+        let mut e = errors[h] + positive_weight_sum;
+        e /= instance_weight_sum;
+        if (0.5 - e).abs() > (0.5 - best_error_rate).abs() {
+            h_best = h;
+            best_error_rate = e;
+        }
+    }
+
+    if (0.5 - best_error_rate).abs() < threshold {
+        trace_event!(debug, error_rate = best_error_rate, "best error rate");
+        return None;
+    }
+
+    // Calculate alpha (weight for the weak learner)
+    let alpha = 0.5 * ((1.0 - best_error_rate).max(1e-10) / best_error_rate.max(1e-10)).ln();
+    trace_event!(debug, error_rate = best_error_rate, alpha, "discrete hypothesis");
+    // A feature with an error rate above 0.5 predicts the opposite label.
+    Some((h_best, alpha, -alpha, best_error_rate.min(1.0 - best_error_rate)))
+}
+
+#[cfg(feature = "train")]
+/// Chooses the weak learner of an iteration of Real AdaBoost: the feature that minimizes
+/// the normalization factor Z = 2 (sqrt(W+ W-) over the instances with the feature +
+/// sqrt(W+ W-) over the others), where W+ and W- are the weights of the boundaries and
+/// the non-boundaries. The bias term of index 0 stands for the constant weak learner.
+///
+/// # Returns: The index of the feature, the confidences `ln(W+ / W-) / 2` for the
+/// instances with and without it, smoothed by a weight of `1 / (2 n)` of the total, and
+/// its weighted error rate, or `None` if the edge of the best feature is below the
+/// threshold.
+fn real_hypothesis(
+    errors: &[f64],
+    coverage: &[f64],
+    instance_weight_sum: f64,
+    positive_weight_sum: f64,
+    num_instances: usize,
+    threshold: f64,
+) -> Option<(usize, f64, f64, f64)> {
+    let negative_weight_sum = instance_weight_sum - positive_weight_sum;
+    let smoothing = instance_weight_sum / (2.0 * num_instances.max(1) as f64);
+    let confidence = |positive: f64, negative: f64| {
+        0.5 * ((positive.max(0.0) + smoothing) / (negative.max(0.0) + smoothing)).ln()
+    };
+
+    let mut h_best = 0;
+    let mut best_z = 2.0 * (positive_weight_sum * negative_weight_sum).sqrt();
+    let mut best_split = (0.0, 0.0);
+    for (h, (&error, &covered)) in errors.iter().zip(coverage).enumerate().skip(1) {
+        // errors[h] is the weight of the non-boundaries with the feature minus the
+        // weight of the boundaries with it.
+        let positive = ((covered - error) / 2.0).max(0.0);
+        let negative = ((covered + error) / 2.0).max(0.0);
+        let rest_positive = (positive_weight_sum - positive).max(0.0);
+        let rest_negative = (negative_weight_sum - negative).max(0.0);
+        let z = 2.0 * ((positive * negative).sqrt() + (rest_positive * rest_negative).sqrt());
+        if z < best_z {
+            h_best = h;
+            best_z = z;
+            best_split = (positive, negative);
+        }
+    }
+
+    // A discrete weak learner with error rate e has Z = 2 sqrt(e (1 - e)) of the total
+    // weight, so an edge |0.5 - e| below the threshold means Z above this bound.
+    let bound = (1.0 - 4.0 * threshold * threshold).max(0.0).sqrt();
+    if best_z.is_nan() || best_z >= bound * instance_weight_sum {
+        trace_event!(debug, z = best_z / instance_weight_sum, "best normalization factor");
+        return None;
+    }
+
+    let (positive, negative) = best_split;
+    let (rest_positive, rest_negative) =
+        (positive_weight_sum - positive, negative_weight_sum - negative);
+    let absent = confidence(rest_positive, rest_negative);
+    // No instance has the bias term, so its confidence for present instances is unused.
+    let present = if h_best == 0 { absent } else { confidence(positive, negative) };
+    trace_event!(debug, z = best_z / instance_weight_sum, "real hypothesis");
+    // The weight of the instances whose label disagrees with the sign of the confidence.
+    let misclassified = if present >= 0.0 { negative } else { positive }
+        + if absent >= 0.0 { rest_negative } else { rest_positive };
+    Some((h_best, present, absent, misclassified / instance_weight_sum))
+}
+
+#[cfg(feature = "train")]
+/// Moves the scores of instances by half the confidence of a weak learner on feature
+/// `h`: `present / 2` for the instances with the feature and `absent / 2` for the others.
+fn update_scores(
+    scores: &mut [f64],
+    instances: &InstanceStore,
+    h: FeatureId,
+    present: f64,
+    absent: f64,
+) {
+    instances.for_each(0..scores.len(), |i, hs| {
+        if hs.binary_search(&h).is_ok() {
+            scores[i] += present / 2.0;
+        } else {
+            scores[i] += absent / 2.0;
+        }
+    });
+}
+
+#[cfg(feature = "train")]
+/// Returns the F1 score in percentage of the positive predictions of instances.
+fn f1_score(labels: &[BinaryLabel], scores: &[f64]) -> f64 {
+    let mut confusion = Confusion::default();
+    for (&label, &score) in labels.iter().zip(scores) {
+        confusion.add(label > 0, score >= 0.0);
+    }
+    let metrics: Metrics = confusion.into();
+    crate::evaluation::f1(metrics.precision, metrics.recall)
+}
+
+#[cfg(feature = "train")]
+/// Returns the percentage of instances whose score has the sign of their label.
+fn accuracy(labels: &[BinaryLabel], scores: &[f64]) -> f64 {
+    let correct = labels
+        .iter()
+        .zip(scores)
+        .filter(|&(&label, &score)| (score >= 0.0) == (label > 0))
+        .count();
+    correct as f64 / labels.len().max(1) as f64 * 100.0
+}
+
+/// Separator of a label and an attribute in the features of a [`MultiClassAdaBoost`].
+/// Labels must not contain it.
+pub const LABEL_SEPARATOR: char = '|';
+
+/// A class of a [`MultiClassAdaBoost`], such as a part-of-speech tag.
+///
+/// Labels are written into the features and the metadata of a model in their `Display`
+/// form and read back from the metadata with `FromStr`, so that form must be non-empty
+/// and contain neither whitespace nor [`LABEL_SEPARATOR`]. Every type with these traits
+/// is a label, e.g. `String`, integers, or an enum of tags.
+pub trait Label: Clone + Ord + fmt::Display + FromStr {}
+
+impl<T: Clone + Ord + fmt::Display + FromStr> Label for T {}
+
+/// A multiclass classifier trained with AdaBoost.MH.
+///
+/// AdaBoost.MH reduces a problem of `k` classes to a binary one over pairs of an instance
+/// and a class. Every attribute is conjoined with each class as `LABEL|attribute`, the
+/// pair with the gold class is a positive instance and the other pairs are negative ones,
+/// and a single binary [`AdaBoost`] learns them all, so a weak hypothesis is an attribute
+/// voting for or against one class. The predicted class is the one whose conjoined
+/// attributes score highest. Training takes about `k` times as long as a binary problem
+/// over the same instances.
+///
+/// The bias of the binary learner is shared by all classes; add a constant attribute to
+/// every instance to learn a prior for each class.
+///
+/// # Example
+/// ```
+/// use litsea::adaboost::{AdaBoost, MultiClassAdaBoost};
+/// use litsea::control::TrainControl;
+///
+/// let mut classifier = MultiClassAdaBoost::new(AdaBoost::new(0.001, 50), [1u8, 2, 3]).unwrap();
+/// classifier.add_instance(&["PRIOR", "w:one"], &1).unwrap();
+/// classifier.add_instance(&["PRIOR", "w:two"], &2).unwrap();
+/// classifier.add_instance(&["PRIOR", "w:three"], &3).unwrap();
+/// classifier.train(TrainControl::new());
+/// assert_eq!(classifier.predict(&["PRIOR", "w:two"]), &2);
+/// ```
+#[derive(Debug)]
+pub struct MultiClassAdaBoost<L: Label> {
+    learner: AdaBoost,
+    /// The classes in sorted order.
+    labels: Vec<L>,
+    /// The `Display` form of each class, followed by [`LABEL_SEPARATOR`].
+    prefixes: Vec<String>,
+}
+
+impl<L: Label> MultiClassAdaBoost<L> {
+    /// Creates a multiclass classifier choosing among the given classes.
+    ///
+    /// # Arguments
+    /// * `learner` - The binary learner, either new with the training settings or loaded
+    ///   from a model trained for the same classes.
+    /// * `labels` - The classes; duplicates are ignored.
+    ///
+    /// # Returns
+    /// A new [`MultiClassAdaBoost`].
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if there are no classes, or a class is empty
+    /// or contains whitespace or [`LABEL_SEPARATOR`] in its `Display` form.
+    pub fn new<I>(learner: AdaBoost, labels: I) -> Result<Self, LitseaError>
+    where
+        I: IntoIterator<Item = L>,
+    {
+        let mut labels: Vec<L> = labels.into_iter().collect();
+        labels.sort();
+        labels.dedup();
+        if labels.is_empty() {
+            return Err(LitseaError::InvalidInput(
+                "A multiclass classifier needs at least one label".to_string(),
+            ));
+        }
+        let mut prefixes = Vec::with_capacity(labels.len());
+        for label in &labels {
+            let name = label.to_string();
+            if name.is_empty()
+                || name.contains(LABEL_SEPARATOR)
+                || name.chars().any(char::is_whitespace)
+            {
+                return Err(LitseaError::InvalidInput(format!(
+                    "Label '{}' must be non-empty without whitespace or '{}'",
+                    name, LABEL_SEPARATOR
+                )));
+            }
+            prefixes.push(format!("{}{}", name, LABEL_SEPARATOR));
+        }
+        Ok(MultiClassAdaBoost {
+            learner,
+            labels,
+            prefixes,
+        })
+    }
+
+    /// Creates a multiclass classifier from a loaded model whose classes are listed in
+    /// a metadata entry, as [`store_labels`](Self::store_labels) writes them.
+    ///
+    /// # Arguments
+    /// * `learner` - The loaded model.
+    /// * `key` - The metadata key of the space-separated classes.
+    ///
+    /// # Returns
+    /// A new [`MultiClassAdaBoost`].
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::ModelFormat`] if the entry is missing or empty, or a class
+    /// cannot be parsed.
+    pub fn from_metadata(learner: AdaBoost, key: &str) -> Result<Self, LitseaError> {
+        let labels = learner
+            .metadata(key)
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(|name| {
+                name.parse::<L>().map_err(|_| {
+                    LitseaError::ModelFormat(format!("Invalid label '{}' in '{}'", name, key))
+                })
+            })
+            .collect::<Result<Vec<L>, _>>()?;
+        if labels.is_empty() {
+            return Err(LitseaError::ModelFormat(format!(
+                "The model has no labels in its '{}' metadata",
+                key
+            )));
+        }
+        MultiClassAdaBoost::new(learner, labels).map_err(|e| match e {
+            LitseaError::InvalidInput(reason) => LitseaError::ModelFormat(reason),
+            e => e,
+        })
+    }
+
+    /// Lists the classes in a metadata entry of the model, so that
+    /// [`from_metadata`](Self::from_metadata) can restore them after loading.
+    ///
+    /// # Arguments
+    /// * `key` - The metadata key.
+    ///
+    /// # Errors
+    /// Returns an error if the key is not a valid metadata key.
+    pub fn store_labels(&mut self, key: &str) -> Result<(), LitseaError> {
+        let names: Vec<String> = self.labels.iter().map(ToString::to_string).collect();
+        self.learner.set_metadata(key, &names.join(" "))
+    }
+
+    /// Returns the classes in sorted order.
+    #[must_use]
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    /// Returns the binary learner.
+    #[must_use]
+    pub fn learner(&self) -> &AdaBoost {
+        &self.learner
+    }
+
+    /// Returns the binary learner, e.g. to change its training settings.
+    pub fn learner_mut(&mut self) -> &mut AdaBoost {
+        &mut self.learner
+    }
+
+    /// Returns the binary learner, e.g. to save the model.
+    #[must_use]
+    pub fn into_learner(self) -> AdaBoost {
+        self.learner
+    }
+
+    #[cfg(feature = "train")]
+    /// Adds an instance of a class, as one binary instance per class.
+    ///
+    /// # Arguments
+    /// * `attributes` - The attributes of the instance, each once.
+    /// * `label` - The gold class.
+    ///
+    /// # Errors
+    /// Returns an error if the class is not one of [`labels`](Self::labels).
+    pub fn add_instance<S: AsRef<str>>(
+        &mut self,
+        attributes: &[S],
+        label: &L,
+    ) -> Result<(), LitseaError> {
+        let gold = self
+            .labels
+            .binary_search(label)
+            .map_err(|_| LitseaError::InvalidInput(format!("Unknown label '{}'", label)))?;
+        for (i, prefix) in self.prefixes.iter().enumerate() {
+            let conjoined = conjoin(prefix, attributes).collect();
+            self.learner.add_instance(conjoined, if i == gold { 1 } else { -1 });
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    /// Trains the binary learner on the instances added so far, see [`AdaBoost::train`].
+    ///
+    /// # Arguments
+    /// * `control` - Stops, pauses or resumes training.
+    pub fn train(&mut self, control: impl Into<TrainControl>) {
+        self.learner.train(control);
+    }
+
+    /// Scores every class for an instance.
+    ///
+    /// # Arguments
+    /// * `attributes` - The attributes of the instance, each once.
+    ///
+    /// # Returns
+    /// Each class with its score, in the order of [`labels`](Self::labels).
     #[must_use]
-    pub fn get_bias(&self) -> f64 {
-        -self.model.iter().sum::<f64>() / 2.0
+    pub fn scores<S: AsRef<str>>(&self, attributes: &[S]) -> Vec<(&L, f64)> {
+        self.labels
+            .iter()
+            .zip(&self.prefixes)
+            .map(|(label, prefix)| {
+                (label, self.learner.score_attributes(conjoin(prefix, attributes)))
+            })
+            .collect()
     }
 
-    /// Calculates and returns the performance metrics of the model on the training data.
+    /// Predicts the class of an instance.
+    ///
+    /// # Arguments
+    /// * `attributes` - The attributes of the instance, each once.
+    ///
+    /// # Returns
+    /// The class with the highest score; the first in sorted order among equal scores.
     #[must_use]
-    pub fn get_metrics(&self) -> Metrics {
-        let bias = self.get_bias();
-        let mut true_positives = 0; // true positives
-        let mut false_positives = 0; // false positives
-        let mut false_negatives = 0; // false negatives
-        let mut true_negatives = 0; // true negatives
-
-        for i in 0..self.num_instances {
-            let label = self.labels[i];
-            let (start, end) = self.instances[i];
-            let mut score = bias;
-            for &h in &self.instances_buf[start..end] {
-                score += self.model[h];
-            }
-            if score >= 0.0 {
-                if label > 0 {
-                    true_positives += 1;
-                } else {
-                    false_positives += 1;
-                }
-            } else if label > 0 {
-                false_negatives += 1;
-            } else {
-                true_negatives += 1;
+    pub fn predict<S: AsRef<str>>(&self, attributes: &[S]) -> &L {
+        let mut best = (&self.labels[0], f64::NEG_INFINITY);
+        for (label, score) in self.scores(attributes) {
+            if score > best.1 {
+                best = (label, score);
             }
         }
-
-        let accuracy =
-            (true_positives + true_negatives) as f64 / self.num_instances.max(1) as f64 * 100.0;
-        let precision =
-            true_positives as f64 / (true_positives + false_positives).max(1) as f64 * 100.0;
-        let recall =
-            true_positives as f64 / (true_positives + false_negatives).max(1) as f64 * 100.0;
-
-        Metrics {
-            accuracy,
-            precision,
-            recall,
-            num_instances: self.num_instances,
-            true_positives,
-            false_positives,
-            false_negatives,
-            true_negatives,
-        }
+        best.0
     }
 }
 
+/// Conjoins attributes with a class prefix `LABEL|`.
+fn conjoin<'a, S: AsRef<str>>(
+    prefix: &'a str,
+    attributes: &'a [S],
+) -> impl Iterator<Item = String> + 'a {
+    attributes.iter().map(move |attr| format!("{}{}", prefix, attr.as_ref()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use std::collections::HashSet;
+    #[cfg(feature = "std")]
+    use std::collections::{HashMap, HashSet};
+    #[cfg(feature = "train")]
     use std::io::Write;
+    #[cfg(feature = "train")]
     use std::sync::Arc;
+    #[cfg(feature = "train")]
     use std::sync::atomic::AtomicBool;
 
+    #[cfg(feature = "std")]
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_feature_table() {
+        let mut table: FeatureTable = ["", "UW4:は", "BC1:IK"].into_iter().collect();
+        assert_eq!(table.len(), 3);
+        assert!(table.get(0).is_empty());
+        assert_eq!(table.get(1), "UW4:は");
+        assert_eq!(table.push("TW2:ですか"), 3);
+        assert_eq!(table.iter().collect::<Vec<_>>(), vec!["", "UW4:は", "BC1:IK", "TW2:ですか"]);
+        assert!(FeatureTable::default().is_empty());
+    }
+
+    #[cfg(feature = "train")]
     #[test]
     fn test_initialize_features() -> std::io::Result<()> {
         // Create a dummy features file
@@ -651,13 +3091,14 @@ mod tests {
         learner.initialize_features(features_file.path())?;
 
         // Features is an ordered set that should contain ""(empty string), "feat1", "feat2", "feat3"
-        assert!(learner.features.contains(&"".to_string()));
-        assert!(learner.features.contains(&"feat1".to_string()));
-        assert!(learner.features.contains(&"feat2".to_string()));
-        assert!(learner.features.contains(&"feat3".to_string()));
+        assert!(learner.features.iter().any(str::is_empty));
+        assert!(learner.features.iter().any(|f| f == "feat1"));
+        assert!(learner.features.iter().any(|f| f == "feat2"));
+        assert!(learner.features.iter().any(|f| f == "feat3"));
         Ok(())
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_initialize_instances() -> std::io::Result<()> {
         // First, initialize features in the feature file.
@@ -680,11 +3121,52 @@ mod tests {
         assert_eq!(learner.num_instances, 1);
         assert_eq!(learner.labels.len(), 1);
         assert_eq!(learner.instance_weights.len(), 1);
-        assert_eq!(learner.instances.len(), 1);
+        assert_eq!(learner.instances.matrix.num_rows(), 1);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_initialize_single_pass() -> std::io::Result<()> {
+        // Features appear out of order.
+        let mut features_file = NamedTempFile::new()?;
+        writeln!(features_file, "1 d b a\n-1 c b\n1 a\n-1 e d\n1 a c")?;
+
+        let train = |initialize: &dyn Fn(&mut AdaBoost) -> Result<(), LitseaError>| {
+            let mut learner = AdaBoost::new(0.0, 5);
+            initialize(&mut learner)?;
+            learner.train(Arc::new(AtomicBool::new(true)));
+            let mut model = Vec::new();
+            learner.write_model(&mut model)?;
+            Ok::<_, LitseaError>((learner.features.iter().collect::<Vec<_>>().join(" "), model))
+        };
+        let two_passes = train(&|learner| {
+            learner.initialize_features(features_file.path())?;
+            learner.initialize_instances(features_file.path())
+        })?;
+        assert_eq!(two_passes.0, " a b c d e");
+        assert_eq!(train(&|learner| learner.initialize(features_file.path()))?, two_passes);
+
+        #[cfg(feature = "mmap")]
+        {
+            let instance_file = NamedTempFile::new()?;
+            let mapped = train(&|learner| {
+                assert_eq!(
+                    learner.initialize_mapped(features_file.path(), instance_file.path())?,
+                    5
+                );
+                Ok(())
+            })?;
+            assert_eq!(mapped, two_passes);
+        }
 
+        let empty = NamedTempFile::new()?;
+        assert!(AdaBoost::new(0.0, 5).initialize(empty.path()).is_err());
         Ok(())
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_train_immediate_stop() -> std::io::Result<()> {
         // Initialize features using a features file.
@@ -721,13 +3203,396 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_control() -> std::io::Result<()> {
+        // Noisy instances, so that every iteration finds a hypothesis.
+        let mut features_file = NamedTempFile::new()?;
+        for i in 0..40 {
+            writeln!(features_file, "{} a c{}", if i % 3 == 0 { -1 } else { 1 }, i % 7)?;
+            writeln!(features_file, "{} b c{}", if i % 5 == 0 { 1 } else { -1 }, i % 4)?;
+        }
+
+        // Counts the iterations, and calls `action` with the control after each of them.
+        let train = |action: fn(&TrainControl, usize)| -> std::io::Result<usize> {
+            let control = TrainControl::new();
+            let handle = control.clone();
+            let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let counter = Arc::clone(&iterations);
+            let mut learner = AdaBoost::new(0.0, 10);
+            learner.initialize(features_file.path())?;
+            learner.set_progress_handler(Some(Arc::new(move |_: &TrainingProgress| {
+                let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                action(&handle, n);
+            })));
+            learner.train(control);
+            Ok(iterations.load(std::sync::atomic::Ordering::SeqCst))
+        };
+
+        assert_eq!(train(|_, _| {})?, 10);
+        // A stop takes effect before the next iteration.
+        assert_eq!(
+            train(|control, n| {
+                if n == 2 {
+                    control.stop();
+                }
+            })?,
+            2
+        );
+        // A paused run continues once another thread resumes it.
+        assert_eq!(
+            train(|control, n| {
+                if n == 1 {
+                    control.pause();
+                    let control = control.clone();
+                    std::thread::spawn(move || {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                        control.resume();
+                    });
+                }
+            })?,
+            10
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_capped() -> std::io::Result<()> {
+        // "a" marks a boundary, except in two mislabeled instances.
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..18 {
+            writeln!(features_file, "1 a\n-1 b")?;
+        }
+        writeln!(features_file, "-1 a\n-1 a")?;
+
+        let train = |boosting: Boosting| -> std::io::Result<AdaBoost> {
+            let mut learner = AdaBoost::new(0.0, 5);
+            learner.set_boosting(boosting).unwrap();
+            learner.initialize_features(features_file.path())?;
+            learner.initialize_instances(features_file.path())?;
+            learner.train(Arc::new(AtomicBool::new(true)));
+            Ok(learner)
+        };
+        // Weights are normalized to sum to 1 before they are capped.
+        let max_weight =
+            |learner: &AdaBoost| learner.instance_weights.iter().fold(0.0f64, |m, w| m.max(*w));
+
+        assert!(AdaBoost::new(0.0, 5).set_boosting(Boosting::Capped(0.5)).is_err());
+        let vanilla = train(Boosting::AdaBoost)?;
+        let capped = train(Boosting::Capped(2.0))?;
+        let average = 1.0 / capped.num_instances as f64;
+        assert!(max_weight(&capped) <= 2.0 * average + 1e-9);
+        assert!(max_weight(&vanilla) > 2.0 * average);
+        // Both still learn that "a" marks a boundary.
+        for learner in [&vanilla, &capped] {
+            assert!(learner.score_attributes(["a"]) > 0.0);
+            assert!(learner.score_attributes(["b"]) < 0.0);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_mini_batch() -> std::io::Result<()> {
+        // "a" marks a boundary and "b" does not; "c" is noise on both.
+        let mut features_file = NamedTempFile::new()?;
+        for i in 0..500 {
+            if i % 3 == 0 {
+                writeln!(features_file, "1 a c\n-1 b c")?;
+            } else {
+                writeln!(features_file, "1 a\n-1 b")?;
+            }
+        }
+
+        let train = |mini_batch: Option<(usize, usize)>| -> std::io::Result<AdaBoost> {
+            let mut learner = AdaBoost::new(0.0, 20);
+            if let Some((batch_size, correction_interval)) = mini_batch {
+                learner.set_mini_batch(batch_size, correction_interval).unwrap();
+            }
+            learner.initialize_features(features_file.path())?;
+            learner.initialize_instances(features_file.path())?;
+            learner.train(Arc::new(AtomicBool::new(true)));
+            Ok(learner)
+        };
+
+        assert!(AdaBoost::new(0.0, 5).set_mini_batch(0, 5).is_err());
+        assert!(AdaBoost::new(0.0, 5).set_mini_batch(10, 0).is_err());
+        let full = train(None)?;
+        let batched = train(Some((50, 5)))?;
+        assert_eq!(batched.get_metrics().accuracy, full.get_metrics().accuracy);
+        assert!(batched.score_attributes(["a"]) > 0.0);
+        assert!(batched.score_attributes(["b"]) < 0.0);
+
+        // Sampling is seeded, and a correction interval of 1 samples nothing.
+        let model = |learner: &AdaBoost| learner.model.clone();
+        assert_eq!(model(&train(Some((50, 5)))?), model(&batched));
+        assert_eq!(model(&train(Some((50, 1)))?), model(&full));
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_threads() -> std::io::Result<()> {
+        // Enough instances for several threads, with features of distinct strengths so
+        // that no two hypotheses tie.
+        let mut features_file = NamedTempFile::new()?;
+        let mut rng = SplitMix64(1);
+        for _ in 0..3 * TRAIN_CHUNK_LEN {
+            let label = if rng.below(3) == 0 { 1 } else { -1 };
+            write!(features_file, "{}", label)?;
+            for (f, agreement) in [("a", 90), ("b", 75), ("c", 60), ("d", 55)] {
+                if (rng.below(100) < agreement) == (label > 0) {
+                    write!(features_file, " {}", f)?;
+                }
+            }
+            writeln!(features_file)?;
+        }
+
+        let train = |num_threads: usize| -> std::io::Result<AdaBoost> {
+            let mut learner = AdaBoost::new(0.0, 10);
+            learner.set_num_threads(num_threads);
+            learner.initialize_features(features_file.path())?;
+            learner.initialize_instances(features_file.path())?;
+            learner.train(Arc::new(AtomicBool::new(true)));
+            Ok(learner)
+        };
+        let single = train(1)?;
+        let parallel = train(3)?;
+        assert_eq!(parallel.get_metrics().accuracy, single.get_metrics().accuracy);
+        for (a, b) in single.model.iter().zip(&parallel.model) {
+            assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+        }
+        for (a, b) in single.instance_weights.iter().zip(&parallel.instance_weights) {
+            assert!((a - b).abs() < 1e-12);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_with_dev_set() -> std::io::Result<()> {
+        // "a" marks a boundary; the training set has mislabeled instances that are fitted
+        // with "c", which the development set contradicts.
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..8 {
+            writeln!(features_file, "1 a\n-1 b")?;
+        }
+        writeln!(features_file, "-1 a c\n-1 a c\n-1 a c")?;
+        let mut dev_file = NamedTempFile::new()?;
+        writeln!(dev_file, "1 a\n-1 b\n1 a c")?;
+
+        let train = |patience: Option<(usize, bool)>| -> std::io::Result<AdaBoost> {
+            let mut learner = AdaBoost::new(0.0, 20);
+            learner.initialize_features(features_file.path())?;
+            learner.initialize_instances(features_file.path())?;
+            learner.initialize_dev_instances(dev_file.path())?;
+            if let Some((patience, stop)) = patience {
+                learner.set_overfitting_patience(patience, stop).unwrap();
+            }
+            learner.train(Arc::new(AtomicBool::new(true)));
+            Ok(learner)
+        };
+
+        assert!(AdaBoost::new(0.0, 5).set_overfitting_patience(0, false).is_err());
+        let learner = train(None)?;
+        let log = learner.training_log();
+        assert!(!log.is_empty());
+        assert_eq!(log[0].iteration, 1);
+        assert!(log.iter().all(|record| !record.overfitting));
+        // The accuracies tracked during training match those of the final model.
+        let last = log.last().unwrap();
+        assert!((last.train_accuracy - learner.get_metrics().accuracy).abs() < 1e-9);
+        let dev_metrics = learner.dev_metrics().unwrap();
+        assert_eq!(dev_metrics.num_instances, 3);
+        assert!((last.dev_accuracy - dev_metrics.accuracy).abs() < 1e-9);
+
+        // Fitting the mislabeled instances widens the gap, which stops the training.
+        let stopped = train(Some((1, true)))?;
+        let log = stopped.training_log();
+        assert!(log.last().unwrap().overfitting);
+        assert!(log.len() < learner.training_log().len());
+        let flagged = train(Some((1, false)))?;
+        assert_eq!(flagged.training_log().len(), learner.training_log().len());
+        assert!(flagged.training_log().iter().any(|record| record.overfitting));
+
+        assert!(AdaBoost::new(0.0, 5).dev_metrics().is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_early_stopping() -> std::io::Result<()> {
+        // As in test_train_with_dev_set, fitting the mislabeled instances with "c" hurts
+        // the development set.
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..8 {
+            writeln!(features_file, "1 a\n-1 b")?;
+        }
+        writeln!(features_file, "-1 a c\n-1 a c\n-1 a c")?;
+        let mut dev_file = NamedTempFile::new()?;
+        writeln!(dev_file, "1 a\n-1 b\n1 a c")?;
+
+        let mut learner = AdaBoost::new(0.0, 20);
+        learner.initialize(features_file.path())?;
+        learner.initialize_dev_instances(dev_file.path())?;
+        learner.set_early_stopping(2).unwrap();
+        learner.train(Arc::new(AtomicBool::new(true)));
+
+        // The development set is classified perfectly before "c" is learned, and training
+        // stops two iterations after the best one with its weights restored.
+        let best = learner.best_iteration().unwrap();
+        assert!(best >= 1);
+        assert_eq!(learner.training_log().len(), best + 2);
+        assert_eq!(learner.dev_metrics().unwrap().accuracy, 100.0);
+        assert!(learner.weights().all(|(feature, _)| feature != "c"));
+        // The model records the iterations it kept, added to those of earlier runs.
+        assert_eq!(learner.metadata("iterations"), Some(best.to_string().as_str()));
+        assert_eq!(learner.metadata("date").map(str::len), Some(10));
+        learner.set_metadata("iterations", "100").unwrap();
+        learner.num_iterations = 0;
+        learner.train(Arc::new(AtomicBool::new(true)));
+        assert_eq!(learner.metadata("iterations"), Some("100"));
+
+        assert!(AdaBoost::new(0.0, 5).set_early_stopping(0).is_err());
+        // Without a development set, nothing is kept.
+        let mut learner = AdaBoost::new(0.0, 5);
+        learner.initialize(features_file.path())?;
+        learner.set_early_stopping(2).unwrap();
+        learner.train(Arc::new(AtomicBool::new(true)));
+        assert!(learner.best_iteration().is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_real() -> std::io::Result<()> {
+        let mut features_file = NamedTempFile::new()?;
+        writeln!(features_file, "{}", ["1 a"; 6].join("\n"))?;
+        writeln!(features_file, "-1 a\n-1 a")?;
+        writeln!(features_file, "{}", ["-1 b"; 8].join("\n"))?;
+
+        let mut learner = AdaBoost::new(0.0, 1);
+        learner.set_algorithm("real".parse().unwrap());
+        learner.initialize(features_file.path())?;
+        let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = Arc::clone(&reported);
+        learner.set_progress_handler(Some(Arc::new(move |progress: &TrainingProgress| {
+            let stats = (progress.alpha, progress.error_rate, progress.train_accuracy);
+            sink.lock().unwrap().push(stats);
+        })));
+        learner.set_accuracy_interval(1);
+        learner.train(Arc::new(AtomicBool::new(true)));
+
+        // One weak learner splits the instances by "a", with a confidence for each side
+        // smoothed by 1 / (2 * 16) of the total weight of 16.
+        let present = 0.5 * (6.5f64 / 2.5).ln();
+        let absent = 0.5 * (0.5f64 / 8.5).ln();
+        let bias = learner.get_bias();
+        let weight_a = learner.weights().find(|(feature, _)| *feature == "a").unwrap().1;
+        assert!((bias + weight_a - present / 2.0).abs() < 1e-9);
+        assert!((bias - absent / 2.0).abs() < 1e-9);
+        assert_eq!(learner.get_metrics().accuracy, 87.5);
+
+        // The two negative instances with "a" are misclassified.
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        let (alpha, error_rate, train_accuracy) = reported[0];
+        assert!((alpha - weight_a).abs() < 1e-9);
+        assert!((error_rate - 0.125).abs() < 1e-9);
+        assert_eq!(train_accuracy, Some(87.5));
+
+        assert_eq!("Discrete".parse(), Ok(Algorithm::Discrete));
+        assert!("gentle".parse::<Algorithm>().is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_initialize_fold() -> std::io::Result<()> {
+        let mut features_file = NamedTempFile::new()?;
+        for i in 0..10 {
+            writeln!(features_file, "{} f{}", if i % 2 == 0 { 1 } else { -1 }, i)?;
+        }
+
+        let mut learner = AdaBoost::new(0.0, 5);
+        learner.initialize_fold(features_file.path(), 3, 1)?;
+        // Instances 3 to 5 are held out; all features are known to the model.
+        assert_eq!(learner.num_instances, 7);
+        assert_eq!(learner.labels, [1, -1, 1, 1, -1, 1, -1]);
+        assert_eq!(learner.instance_weights.len(), 7);
+        let dev_set = learner.dev_set.as_ref().unwrap();
+        assert_eq!(dev_set.labels, [-1, 1, -1]);
+        assert_eq!(learner.features.len(), 11);
+        learner.train(Arc::new(AtomicBool::new(true)));
+        assert_eq!(learner.dev_metrics().unwrap().num_instances, 3);
+
+        assert!(AdaBoost::new(0.0, 5).initialize_fold(features_file.path(), 1, 0).is_err());
+        assert!(AdaBoost::new(0.0, 5).initialize_fold(features_file.path(), 3, 3).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_dataset() -> std::io::Result<()> {
+        let mut features_file = NamedTempFile::new()?;
+        writeln!(features_file, "1 d b a\n-1 c b\n1 a\n-1 e d\n1 a c")?;
+        let dataset = Dataset::load(features_file.path())?;
+        assert_eq!((dataset.num_instances(), dataset.num_features()), (5, 5));
+
+        let train = |learner: &mut AdaBoost| -> std::io::Result<Vec<u8>> {
+            learner.train(Arc::new(AtomicBool::new(true)));
+            let mut model = Vec::new();
+            learner.write_model(&mut model)?;
+            Ok(model)
+        };
+        let mut read = AdaBoost::new(0.0, 5);
+        read.initialize(features_file.path())?;
+        let mut first = AdaBoost::new(0.0, 5);
+        first.set_dataset(&dataset);
+        let mut second = AdaBoost::new(0.0, 5);
+        second.set_dataset(&dataset.clone());
+        // The learners share the instances and train the same model as one that read the
+        // file.
+        assert!(Arc::ptr_eq(&first.instances.matrix, &second.instances.matrix));
+        assert!(Arc::ptr_eq(&first.feature_index, &dataset.feature_index));
+        let model = train(&mut read)?;
+        assert_eq!(train(&mut first)?, model);
+        assert_eq!(train(&mut second)?, model);
+
+        // Adding an instance copies the shared instances.
+        first.add_instance(["a".to_string()].into(), 1);
+        assert_eq!(first.instances.len(), 6);
+        assert_eq!(dataset.instances.len(), 5);
+
+        let mut fold = AdaBoost::new(0.0, 5);
+        fold.set_dataset_fold(&dataset, 5, 0)?;
+        assert!(Arc::ptr_eq(&fold.instances.matrix, &dataset.instances.matrix));
+        assert_eq!(fold.labels, [-1, 1, -1, 1]);
+        let mut first_features = Vec::new();
+        fold.dev_set.as_ref().unwrap().instances.for_each(0..1, |_, hs| {
+            first_features.extend(hs.iter().map(|&h| fold.features.get(h as usize)));
+        });
+        assert_eq!(first_features, ["a", "b", "d"]);
+        assert!(AdaBoost::new(0.0, 5).set_dataset_fold(&dataset, 5, 5).is_err());
+
+        let mut sample = AdaBoost::new(0.0, 5);
+        sample.set_dataset_bootstrap(&dataset, 1);
+        let held_out = sample.dev_set.as_ref().unwrap().labels.len();
+        assert_eq!(sample.num_instances + held_out, 5);
+        // The weights of the kept instances add up to the number of draws.
+        assert_eq!(sample.instance_weights.iter().sum::<f64>(), 5.0);
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
     #[tokio::test]
     async fn test_save_and_load_model() -> std::io::Result<()> {
         // Prepare a dummy learner.
         let mut learner = AdaBoost::new(0.01, 10);
 
         // Set the features and weights in advance.
-        learner.features = vec!["feat1".to_string(), "feat2".to_string()];
+        learner.features = Arc::new(["feat1", "feat2"].into_iter().collect());
         learner.model = vec![0.5, -0.3];
 
         // Save the model to a temporary file.
@@ -745,6 +3610,306 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_load_model_checksum_mismatch() -> std::io::Result<()> {
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.set_weights(vec![("feat1".to_string(), 0.5)], -0.25);
+        let temp_model = NamedTempFile::new()?;
+        learner.save_model(temp_model.path())?;
+
+        // Tamper with the weight after the header was written.
+        let content = std::fs::read_to_string(temp_model.path())?;
+        assert!(content.starts_with(crate::integrity::HEADER_PREFIX));
+        std::fs::write(temp_model.path(), content.replace("feat1\t0.5", "feat1\t5"))?;
+        let uri = temp_model.path().to_str().unwrap();
+
+        let mut learner2 = AdaBoost::new(0.01, 10);
+        let err = learner2.load_model(uri).await.unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)), "{err:?}");
+
+        // The check can be overridden.
+        learner2.set_verify_integrity(false);
+        learner2.load_model(uri).await?;
+        assert_eq!(learner2.weights().collect::<Vec<_>>(), vec![("feat1", 5.0)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_model_str() {
+        let model = "#litsea-model sha256=9b1f5c1c55e5c0b1ba4bde0a4a4b3f1b0f4cb1e2ef8c5bd2f9d2d4dcd6b5b3ad\nfeat1\t0.5\n-0.25\n";
+        let mut learner = AdaBoost::new(0.01, 10);
+        let err = learner.load_model_str(model).unwrap_err();
+        assert!(matches!(&err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        let body = "feat1\t0.5\n-0.25\n";
+        let model =
+            format!("#litsea-model sha256={}\n{}", crate::util::sha256_hex(body.as_bytes()), body);
+        learner.load_model_str(&model).unwrap();
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("feat1", 0.5)]);
+        assert!((learner.get_bias() + 0.25).abs() < 1e-9);
+        assert!((learner.score_attributes(["feat1"]) - 0.25).abs() < 1e-9);
+
+        // Models without a header still load.
+        learner.load_model_str(body).unwrap();
+        assert!(learner.load_model_str("feat1\tnot_a_number").is_err());
+    }
+
+    #[test]
+    fn test_score_uses_index_of_loaded_model() {
+        let body: String = (0..1000).map(|i| format!("f{}\t{}\n", i, i)).collect();
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.load_model_str(&format!("{}0\n", body)).unwrap();
+
+        for i in [0, 1, 499, 999] {
+            let expected = i as f64 + learner.get_bias();
+            let name = format!("f{}", i);
+            assert!((learner.score_attributes([name.as_str()]) - expected).abs() < 1e-9);
+        }
+        assert_eq!(learner.score_attributes(["f1000"]), learner.get_bias());
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.load_model_str("a\t0.5\nb\t-0.01\nc\t0.02\nd\t-2\n0.3\n").unwrap();
+        let bias = learner.get_bias();
+        let score_a = learner.score_attributes(["a", "d"]);
+
+        assert_eq!(learner.prune(0.05), 2);
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("a", 0.5), ("d", -2.0)]);
+        // The bias and the scores of instances without a dropped feature are unchanged.
+        assert!((learner.get_bias() - bias).abs() < 1e-9);
+        assert!((learner.score_attributes(["a", "d"]) - score_a).abs() < 1e-9);
+        assert!((learner.score_attributes(["b", "c"]) - bias).abs() < 1e-9);
+        assert_eq!(learner.prune(0.05), 0);
+
+        assert_eq!(learner.prune_to(1), 1);
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("d", -2.0)]);
+        assert!((learner.get_bias() - bias).abs() < 1e-9);
+        assert_eq!(learner.prune_to(5), 0);
+        assert_eq!(learner.prune_to(0), 1);
+        assert_eq!(learner.weights().count(), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_quantize() {
+        let model = "a\t0.5\nb\t-0.01\nc\t0.02\nd\t-2\ne\t0.005\n0.3\n";
+        let save = |learner: &AdaBoost| {
+            let mut saved = Vec::new();
+            learner.write_model(&mut saved).unwrap();
+            String::from_utf8(saved).unwrap()
+        };
+        let load = |content: &str| {
+            let mut learner = AdaBoost::new(0.01, 10);
+            learner.load_model_str(content).unwrap();
+            learner
+        };
+        let mut learner = load(model);
+        let bias = learner.get_bias();
+
+        // The largest weight maps to 127, and "e" rounds to 0.
+        let scale = 2.0 / 127.0;
+        let error = learner.quantize(Quantization::I8);
+        assert_eq!(error.features_zeroed, 1);
+        assert!(error.max_error <= scale / 2.0);
+        assert!((learner.get_bias() - bias).abs() < 1e-9);
+        assert_eq!(learner.weights().count(), 4);
+
+        let saved = save(&learner);
+        let lines: Vec<&str> = saved.lines().collect();
+        assert_eq!(lines[1], "#format 2");
+        assert_eq!(lines[2], format!("#quantization i8 {}", scale));
+        assert!(saved.contains("\na\t32\n"), "{saved}");
+        assert!(saved.contains("\nd\t-127\n"), "{saved}");
+        let loaded = load(&saved);
+        assert!((loaded.get_bias() - bias).abs() < 1e-9);
+        let weights: Vec<(&str, f64)> = learner.weights().collect();
+        let loaded_weights: Vec<(&str, f64)> = loaded.weights().collect();
+        assert_eq!(weights.len(), loaded_weights.len());
+        for ((f, w), (g, v)) in weights.iter().zip(&loaded_weights) {
+            assert_eq!(f, g);
+            assert!((w - v).abs() < 1e-12);
+        }
+        // A loaded model is saved in full precision unless quantized again.
+        let resaved = save(&loaded);
+        assert!(resaved.contains("#format 1\n"), "{resaved}");
+        assert!(!resaved.contains("#quantization"), "{resaved}");
+
+        let mut learner = load(model);
+        learner.quantize(Quantization::F16);
+        let saved = save(&learner);
+        assert!(saved.contains("#format 1\n#quantization f16\n"), "{saved}");
+        assert!(saved.contains("\nb\t-0.01\n"), "{saved}");
+        let loaded = load(&saved);
+        assert_eq!(loaded.weights().collect::<Vec<_>>(), learner.weights().collect::<Vec<_>>());
+        assert!((loaded.get_bias() - bias).abs() < 1e-9);
+
+        // The scale of i8 weights must be valid.
+        let mut learner = AdaBoost::new(0.01, 10);
+        for invalid in ["#quantization i8\na\t1\n0\n", "#quantization i4 1\na\t1\n0\n"] {
+            let result = learner.load_model_str(invalid);
+            assert!(matches!(result, Err(LitseaError::ModelFormat(_))), "{result:?}");
+        }
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_cached_bias() {
+        let mut learner = AdaBoost::new(0.01, 10);
+        assert_eq!(learner.bias, learner.get_bias());
+
+        learner.load_model_str("a\t0.5\nb\t-0.01\nc\t0.02\nd\t-2\n0.3\n").unwrap();
+        assert_eq!(learner.bias, learner.get_bias());
+        assert_eq!(learner.score_attributes(["a"]), learner.get_bias() + 0.5);
+
+        learner.prune(0.015);
+        assert_eq!(learner.bias, learner.get_bias());
+        learner.prune_to(2);
+        assert_eq!(learner.bias, learner.get_bias());
+        learner.quantize(Quantization::I8);
+        assert_eq!(learner.bias, learner.get_bias());
+
+        learner.set_weights(vec![("a".to_string(), 1.0), ("e".to_string(), -0.25)], 0.75);
+        assert_eq!(learner.bias, learner.get_bias());
+        assert_eq!(learner.score_attributes(["a", "e"]), 0.75 + 1.0 - 0.25);
+
+        let instances = [("a", 1), ("e", -1), ("f", 1), ("f", 1)]
+            .map(|(attr, label)| (HashSet::from([attr.to_string()]), label));
+        learner.partial_fit(instances.clone());
+        assert_eq!(learner.bias, learner.get_bias());
+        for (attrs, label) in instances.clone() {
+            learner.add_instance(attrs, label);
+        }
+        learner.train(TrainControl::new());
+        assert_eq!(learner.bias, learner.get_bias());
+
+        let mut averaged = learner.clone();
+        let mut other = learner.clone();
+        other.partial_fit(instances);
+        averaged.average(&[learner, other]);
+        assert_eq!(averaged.bias, averaged.get_bias());
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_partial_fit() {
+        let instance = |features: &[&str], label| {
+            (features.iter().map(|f| f.to_string()).collect::<HashSet<_>>(), label)
+        };
+        let mut learner = AdaBoost::new(0.001, 100);
+        assert!(learner.set_learning_rate(0.0).is_err());
+        assert!(learner.set_learning_rate(1.5).is_err());
+        learner.set_learning_rate(0.1).unwrap();
+        learner.load_model_str("a\t0.5\nb\t-0.2\n0.1\n").unwrap();
+        assert_eq!(learner.predict(["b", "c"].iter().map(|f| f.to_string()).collect()), -1);
+
+        let added = learner.partial_fit(vec![instance(&["b", "c"], 1), instance(&["a", "b"], 1)]);
+        assert_eq!(added, 2);
+        // Boosting stops as soon as both instances are classified correctly.
+        assert!(learner.score_attributes(["b", "c"]) >= 0.0);
+        assert!(learner.score_attributes(["b", "c"]) < 0.5);
+        assert!(learner.score_attributes(["a", "b"]) >= 0.0);
+        assert_eq!(learner.get_metrics().accuracy, 100.0);
+
+        assert_eq!(learner.partial_fit(Vec::new()), 0);
+        assert_eq!(AdaBoost::new(0.01, 10).partial_fit(Vec::new()), 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_metadata_roundtrip() -> std::io::Result<()> {
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.set_weights(vec![("feat1".to_string(), 0.5)], -0.25);
+        learner.set_metadata("pipeline", "{\"language\":\"japanese\"}").unwrap();
+        learner.set_metadata("note", "").unwrap();
+        assert!(learner.set_metadata("two words", "x").is_err());
+        assert!(learner.set_metadata("note", "line\nbreak").is_err());
+
+        let temp_model = NamedTempFile::new()?;
+        learner.save_model(temp_model.path())?;
+        let content = std::fs::read_to_string(temp_model.path())?;
+        assert_eq!(content.lines().nth(1), Some("#format 1"));
+        assert!(content.lines().nth(2).unwrap().starts_with("#note"));
+
+        let mut learner2 = AdaBoost::new(0.01, 10);
+        learner2.load_model(temp_model.path().to_str().unwrap()).await?;
+        assert_eq!(learner2.metadata("pipeline"), Some("{\"language\":\"japanese\"}"));
+        assert_eq!(learner2.metadata("format"), Some("1"));
+        assert_eq!(learner2.metadata_entries().count(), 3);
+        assert_eq!(learner2.weights().collect::<Vec<_>>(), vec![("feat1", 0.5)]);
+
+        // Loading another model replaces the metadata.
+        learner2.load_model_str("feat1\t0.5\n-0.25\n").unwrap();
+        assert_eq!(learner2.metadata("pipeline"), None);
+
+        // Models of a newer format, or with a malformed iteration count, are rejected.
+        let newer = learner2.load_model_str("#format 3\nfeat1\t0.5\n-0.25\n");
+        assert!(matches!(newer, Err(LitseaError::ModelFormat(_))));
+        let malformed = learner2.load_model_str("#iterations many\nfeat1\t0.5\n-0.25\n");
+        assert!(matches!(malformed, Err(LitseaError::ModelFormat(_))));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_load_model_str_traces() {
+        use std::sync::Mutex;
+
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Records the names of created spans and events.
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<&'static str>>);
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                self.0.lock().unwrap().push(span.metadata().name());
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                self.0.lock().unwrap().push(event.metadata().name());
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let recorder = std::sync::Arc::new(Recorder::default());
+        tracing::subscriber::with_default(recorder.clone(), || {
+            AdaBoost::new(0.01, 10).load_model_str("feat1\t0.5\n-0.25\n").unwrap();
+        });
+        let names = recorder.0.lock().unwrap();
+        assert_eq!(names[0], "load_model");
+        assert!(names[1].starts_with("event "));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_from_tinysegmenter_js() {
+        let source = "this.BIAS__ = -332;\nthis.UW4__ = {\"は\":1200,\"の\":-50};\n";
+        let learner = AdaBoost::from_tinysegmenter_js(source.as_bytes()).unwrap();
+
+        let weights: HashMap<&str, f64> = learner.weights().collect();
+        assert_eq!(weights.len(), 2);
+        assert_eq!(weights["UW4:は"], 1200.0);
+        assert!((learner.get_bias() + 332.0).abs() < 1e-9);
+
+        let attrs: HashSet<String> = ["UW4:は".to_string()].into_iter().collect();
+        assert_eq!(learner.predict(attrs), 1);
+        assert!(AdaBoost::from_tinysegmenter_js("var x = 1;".as_bytes()).is_err());
+    }
+
+    #[cfg(feature = "train")]
     #[test]
     fn test_add_instance_and_predict() {
         let mut learner = AdaBoost::new(0.01, 10);
@@ -759,6 +3924,24 @@ mod tests {
         assert_eq!(prediction, 1);
     }
 
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_add_instance_and_train() {
+        let mut learner = AdaBoost::new(0.001, 10);
+        for (attrs, label) in [(["a", "x"], 1), (["b", "x"], -1), (["a", "y"], 1), (["b", "y"], -1)]
+        {
+            learner.add_instance(attrs.iter().map(|a| a.to_string()).collect(), label);
+        }
+        assert_eq!(learner.features.get(0), "");
+        learner.train(Arc::new(AtomicBool::new(true)));
+
+        let predict =
+            |attrs: &[&str]| learner.predict(attrs.iter().map(|a| a.to_string()).collect());
+        assert_eq!(predict(&["a", "y"]), 1);
+        assert_eq!(predict(&["b", "x"]), -1);
+        assert_eq!(learner.get_metrics().accuracy, 100.0);
+    }
+
     #[test]
     fn test_get_bias() {
         let mut learner = AdaBoost::new(0.01, 10);
@@ -770,15 +3953,15 @@ mod tests {
         assert!((learner.get_bias() + 0.2).abs() < 1e-6);
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_get_metrics() {
         let mut learner = AdaBoost::new(0.01, 10);
 
         // Set features and model for prediction
-        learner.features = vec!["A".to_string(), "B".to_string()];
+        learner.features = Arc::new(["A", "B"].into_iter().collect());
         learner.model = vec![0.5, -1.0];
-        learner.feature_index =
-            learner.features.iter().enumerate().map(|(i, f)| (f.clone(), i)).collect();
+        learner.index_features();
 
         // Instance 1: Attribute "A" → score = 0.25 + 0.5 = 0.75 (positive example)
         let mut attrs1 = HashSet::new();
@@ -801,6 +3984,7 @@ mod tests {
         assert!((metrics.accuracy - 100.0).abs() < 1e-6);
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_get_metrics_zero_instances() {
         // An empty AdaBoost with no instances should return zeroed metrics
@@ -818,14 +4002,14 @@ mod tests {
         assert!((metrics.recall - 0.0).abs() < f64::EPSILON);
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_get_metrics_all_positive() {
         // All-positive instances: precision=100%, recall=100%, no false negatives.
         // Verifies the .max(1) guard handles zero denominators correctly.
         let mut learner = AdaBoost::new(0.01, 10);
-        learner.features = vec!["".to_string(), "A".to_string()];
-        learner.feature_index.insert("".to_string(), 0);
-        learner.feature_index.insert("A".to_string(), 1);
+        learner.features = Arc::new(["", "A"].into_iter().collect());
+        learner.feature_index = Arc::new(index_of(&learner.features));
         // model: weight for "" (bias bucket) = 0, weight for "A" = 1.0
         // bias = -(0.0 + 1.0) / 2.0 = -0.5
         // score for instance with "A": -0.5 + 1.0 = 0.5 >= 0 → positive prediction
@@ -847,6 +4031,7 @@ mod tests {
         assert!((metrics.recall - 100.0).abs() < f64::EPSILON);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_parse_model_content_empty_input() {
         let mut learner = AdaBoost::new(0.01, 10);
@@ -856,6 +4041,7 @@ mod tests {
         assert!(learner.features.is_empty());
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_parse_model_content_invalid_bias() {
         let mut learner = AdaBoost::new(0.01, 10);
@@ -865,6 +4051,7 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_parse_model_content_invalid_weight() {
         let mut learner = AdaBoost::new(0.01, 10);
@@ -874,12 +4061,162 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_load_model_str_parse_error() {
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.set_verify_integrity(false);
+        let err = learner.load_model_str("#key value\nfeat1\t0.5\nfeat2\tx\n-0.25\n").unwrap_err();
+        match err {
+            LitseaError::Parse { line, reason } => {
+                assert_eq!(line, 3);
+                assert!(reason.starts_with("Invalid value"));
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_initialize_weighted() -> std::io::Result<()> {
+        // A weight counts as many copies of the instance.
+        let mut weighted = NamedTempFile::new()?;
+        writeln!(weighted, "1\t3\ta b\n-1\t1\tc b\n-1\t2.5\tc\n1\t1\ta c")?;
+        let mut expanded = NamedTempFile::new()?;
+        writeln!(expanded, "1 a b\n1 a b\n1 a b\n-1 c b\n-1 c\n-1 c\n1 a c")?;
+
+        let train = |path: &Path, weighted: bool, copies: f64| {
+            let mut learner = AdaBoost::new(0.0, 5);
+            learner.set_weighted(weighted);
+            learner.initialize(path)?;
+            // Half a copy of "-1 c" cannot be expanded, so scale its weight instead.
+            if !weighted {
+                learner.instance_weights[5] *= copies;
+            }
+            learner.train(Arc::new(AtomicBool::new(true)));
+            Ok::<_, std::io::Error>(learner)
+        };
+        let from_weights = train(weighted.path(), true, 1.0)?;
+        let from_copies = train(expanded.path(), false, 0.5)?;
+        assert_eq!(from_weights.num_instances, 4);
+        for ((h, a), (_, b)) in from_weights.weights().zip(from_copies.weights()) {
+            assert!((a - b).abs() < 1e-9, "{}: {} != {}", h, a, b);
+        }
+
+        // The dev set and the two-pass initialization skip the weight column.
+        let mut learner = AdaBoost::new(0.0, 5);
+        learner.set_weighted(true);
+        learner.initialize_features(weighted.path())?;
+        learner.initialize_instances(weighted.path())?;
+        learner.initialize_dev_instances(weighted.path())?;
+        assert!(!learner.features.iter().any(|h| h == "3"));
+        assert_eq!(learner.instance_weights, [3.0, 1.0, 2.5, 1.0]);
+
+        for line in ["1 a b", "1 0 a b", "1 -2 a", "1 x a"] {
+            let mut invalid = NamedTempFile::new()?;
+            writeln!(invalid, "{}", line)?;
+            let mut learner = AdaBoost::new(0.0, 5);
+            learner.set_weighted(true);
+            let err = learner.initialize(invalid.path()).unwrap_err();
+            assert!(matches!(err, LitseaError::Parse { line: 1, .. }));
+        }
+
+        #[cfg(feature = "mmap")]
+        {
+            let instance_file = NamedTempFile::new()?;
+            let mut learner = AdaBoost::new(0.0, 5);
+            learner.set_weighted(true);
+            assert!(learner.initialize_mapped(weighted.path(), instance_file.path()).is_err());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_initialize_invalid_label() -> std::io::Result<()> {
+        let mut features_file = NamedTempFile::new()?;
+        writeln!(features_file, "1 feat1\n-1 feat2\nx feat1")?;
+        features_file.as_file().sync_all()?;
+
+        let mut learner = AdaBoost::new(0.01, 10);
+        match learner.initialize(features_file.path()).unwrap_err() {
+            LitseaError::Parse { line, reason } => {
+                assert_eq!(line, 3);
+                assert!(reason.starts_with("Invalid label"));
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
     #[test]
     fn test_save_model_empty() {
         let learner = AdaBoost::new(0.01, 10);
         let temp = NamedTempFile::new().unwrap();
         let result = learner.save_model(temp.path());
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert!(matches!(result, Err(LitseaError::InvalidInput(_))));
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_multiclass_train_and_predict() {
+        let mut classifier = MultiClassAdaBoost::new(
+            AdaBoost::new(0.001, 100),
+            ["noun", "verb", "particle"].map(String::from),
+        )
+        .unwrap();
+        assert_eq!(classifier.labels(), ["noun", "particle", "verb"]);
+        let corpus = [
+            (["B", "w:cat", "s:t"], "noun"),
+            (["B", "w:dog", "s:g"], "noun"),
+            (["B", "w:run", "s:n"], "verb"),
+            (["B", "w:see", "s:e"], "verb"),
+            (["B", "w:ga", "s:a"], "particle"),
+            (["B", "w:wo", "s:o"], "particle"),
+        ];
+        for (attributes, label) in &corpus {
+            classifier.add_instance(attributes, &label.to_string()).unwrap();
+        }
+        assert!(classifier.add_instance(&["B"], &"adjective".to_string()).is_err());
+        // One binary instance per class.
+        assert_eq!(classifier.learner().num_instances, corpus.len() * 3);
+        classifier.train(Arc::new(AtomicBool::new(true)));
+
+        for (attributes, label) in &corpus {
+            assert_eq!(classifier.predict(attributes), label);
+        }
+        let scores = classifier.scores(&["B", "w:cat", "s:t"]);
+        assert_eq!(scores.len(), 3);
+        assert_eq!(scores[0].0, "noun");
+        assert!(scores[0].1 > scores[1].1 && scores[0].1 > scores[2].1);
+        // The features are conjoined with the classes.
+        assert!(classifier.learner().weights().any(|(f, _)| f.starts_with("noun|")));
+    }
+
+    #[test]
+    fn test_multiclass_labels() {
+        assert!(MultiClassAdaBoost::<u8>::new(AdaBoost::new(0.01, 10), []).is_err());
+        assert!(MultiClassAdaBoost::new(AdaBoost::new(0.01, 10), ["a|b".to_string()]).is_err());
+        assert!(MultiClassAdaBoost::new(AdaBoost::new(0.01, 10), ["a b".to_string()]).is_err());
+        assert!(MultiClassAdaBoost::new(AdaBoost::new(0.01, 10), ["".to_string()]).is_err());
+
+        let mut learner = AdaBoost::new(0.01, 10);
+        learner.load_model_str("1|x\t0.5\n2|x\t-0.5\n0.0\n").unwrap();
+        let mut classifier = MultiClassAdaBoost::new(learner, [2u8, 1, 2]).unwrap();
+        assert_eq!(classifier.labels(), [1, 2]);
+        assert_eq!(classifier.predict(&["x"]), &1);
+        // Equal scores go to the first class.
+        assert_eq!(classifier.predict(&["y"]), &1);
+
+        classifier.store_labels("classes").unwrap();
+        let learner = classifier.into_learner();
+        assert_eq!(learner.metadata("classes"), Some("1 2"));
+        let restored = MultiClassAdaBoost::<u8>::from_metadata(learner, "classes").unwrap();
+        assert_eq!(restored.labels(), [1, 2]);
+        assert!(matches!(
+            MultiClassAdaBoost::<u8>::from_metadata(restored.into_learner(), "tags"),
+            Err(LitseaError::ModelFormat(_))
+        ));
     }
 }
@@ -0,0 +1,741 @@
+//! Error analysis of a segmenter against a gold corpus.
+//!
+//! Accuracy alone does not say what to fix. [`analyze`] segments every sentence of a
+//! gold corpus, lists the mis-segmented ones with the gold and system words aligned,
+//! classifies each error and counts the wrong boundary decisions by the character types
+//! on either side, e.g. how often a boundary between kanji and hiragana is missed:
+//!
+//! - **over-split**: the system splits a gold word.
+//! - **under-split**: the system joins gold words.
+//! - **boundary shift**: the system places a boundary elsewhere within the span, so it
+//!   both splits and joins.
+//!
+//! [`frequent_errors`] aggregates the wrong decisions by the characters around them
+//! instead, so the most frequent wrongly split and wrongly joined contexts, such as
+//! `で|す` split between で and す, can be fixed in the corpus or the features.
+//!
+//! [`analyze_coverage`] tells whether errors come from a lack of data or from the
+//! features: it checks which features of every boundary decision were seen in training,
+//! reports the errors made where most lexical features were unseen, and summarizes the
+//! coverage of each feature template.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::compare::{differing_spans, mark_spans, split, starts};
+use crate::segmenter::Segmenter;
+
+/// The kind of a segmentation error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ErrorKind {
+    /// The system places boundaries inside a gold word.
+    OverSplit,
+    /// The system misses boundaries between gold words.
+    UnderSplit,
+    /// The system both misses gold boundaries and places others within the span.
+    BoundaryShift,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::OverSplit => write!(f, "over-split"),
+            ErrorKind::UnderSplit => write!(f, "under-split"),
+            ErrorKind::BoundaryShift => write!(f, "boundary-shift"),
+        }
+    }
+}
+
+/// A span of a sentence that the system segments differently from the gold corpus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentationError {
+    /// Byte offset of the start of the span in the unsegmented sentence.
+    pub start: usize,
+    /// Byte offset of the end of the span in the unsegmented sentence.
+    pub end: usize,
+    /// The gold words of the span.
+    pub gold: Vec<String>,
+    /// The words of the span according to the system.
+    pub system: Vec<String>,
+    /// The kind of the error.
+    pub kind: ErrorKind,
+}
+
+/// Wrong boundary decisions between two character types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextErrors {
+    /// Boundaries the system places but the gold corpus does not have.
+    pub false_positives: usize,
+    /// Gold boundaries the system misses.
+    pub false_negatives: usize,
+}
+
+impl ContextErrors {
+    /// Returns the number of wrong decisions.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.false_positives + self.false_negatives
+    }
+}
+
+/// The errors of a segmenter over a gold corpus.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorAnalysis {
+    /// Number of sentences analyzed.
+    pub sentences: usize,
+    /// Number of sentences with at least one error.
+    pub erroneous_sentences: usize,
+    /// Number of errors of each kind.
+    pub errors: BTreeMap<ErrorKind, usize>,
+    /// Wrong boundary decisions by the character types before and after the decision,
+    /// written as `<before>/<after>`, e.g. `H/I` for kanji followed by hiragana.
+    pub contexts: BTreeMap<String, ContextErrors>,
+}
+
+/// The errors in one sentence, with the segmentations they were found in.
+struct SentenceAnalysis {
+    gold: Vec<String>,
+    system: Vec<String>,
+    errors: Vec<SegmentationError>,
+    /// Wrong decisions as (character index, context, is false positive).
+    decisions: Vec<(usize, String, bool)>,
+}
+
+/// Segments the text of a gold sentence and compares the result with the gold words.
+fn analyze_gold(segmenter: &Segmenter, gold: Vec<String>) -> SentenceAnalysis {
+    let text = gold.concat();
+    let chars: Vec<char> = text.chars().collect();
+    let offsets: Vec<usize> =
+        text.char_indices().map(|(i, _)| i).chain(std::iter::once(text.len())).collect();
+    let system = segmenter.segment(&text);
+    let gold_starts = starts(&gold, chars.len());
+    let system_starts = starts(&system, chars.len());
+
+    let errors = differing_spans(&gold_starts, &system_starts)
+        .into_iter()
+        .map(|(start, end)| {
+            let inner = start + 1..end;
+            let extra = inner.clone().any(|i| system_starts[i] && !gold_starts[i]);
+            let missed = inner.clone().any(|i| gold_starts[i] && !system_starts[i]);
+            let kind = match (extra, missed) {
+                (true, false) => ErrorKind::OverSplit,
+                (false, true) => ErrorKind::UnderSplit,
+                _ => ErrorKind::BoundaryShift,
+            };
+            SegmentationError {
+                start: offsets[start],
+                end: offsets[end],
+                gold: split(&chars, &gold_starts, start, end),
+                system: split(&chars, &system_starts, start, end),
+                kind,
+            }
+        })
+        .collect();
+
+    let decisions = (1..chars.len())
+        .filter(|&i| gold_starts[i] != system_starts[i])
+        .map(|i| {
+            let before = segmenter.get_type(&chars[i - 1].to_string());
+            let after = segmenter.get_type(&chars[i].to_string());
+            (i, format!("{}/{}", before, after), system_starts[i])
+        })
+        .collect();
+
+    SentenceAnalysis {
+        gold,
+        system,
+        errors,
+        decisions,
+    }
+}
+
+/// Splits a gold sentence into its words.
+fn gold_words(sentence: &str) -> Vec<String> {
+    sentence.split(' ').filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+/// Segments the text of a gold sentence and returns the spans segmented wrongly.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to analyze.
+/// * `sentence` - The gold sentence, with words separated by spaces.
+///
+/// # Returns
+/// The errors in order, empty if the sentence is segmented correctly.
+#[must_use]
+pub fn analyze_sentence(segmenter: &Segmenter, sentence: &str) -> Vec<SegmentationError> {
+    let gold = gold_words(sentence);
+    if gold.is_empty() {
+        return Vec::new();
+    }
+    analyze_gold(segmenter, gold).errors
+}
+
+/// Segments every sentence of a gold corpus and writes the mis-segmented ones, as
+/// tab-separated lines: the line number, `gold` or `system`, and the words with the
+/// wrong spans in brackets, followed by a line with the line number, `errors`, and the
+/// kind of each error with the gold and system words of its span.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to analyze.
+/// * `corpus` - The gold corpus, one sentence per line with words separated by spaces.
+/// * `writer` - The destination of the report.
+///
+/// # Returns
+/// The errors aggregated over the corpus.
+///
+/// # Errors
+/// Returns an error if the corpus cannot be read or the report cannot be written.
+pub fn analyze<R: BufRead, W: Write>(
+    segmenter: &Segmenter,
+    corpus: R,
+    writer: &mut W,
+) -> io::Result<ErrorAnalysis> {
+    let mut analysis = ErrorAnalysis::default();
+    for (line_num, line) in corpus.lines().enumerate() {
+        let gold = gold_words(&line?);
+        if gold.is_empty() {
+            continue;
+        }
+        let result = analyze_gold(segmenter, gold);
+        analysis.sentences += 1;
+        for (_, context, false_positive) in result.decisions {
+            let errors = analysis.contexts.entry(context).or_default();
+            if false_positive {
+                errors.false_positives += 1;
+            } else {
+                errors.false_negatives += 1;
+            }
+        }
+        if result.errors.is_empty() {
+            continue;
+        }
+        analysis.erroneous_sentences += 1;
+
+        let spans = result.errors.iter().map(|e| (e.start, e.end));
+        writeln!(writer, "{}\tgold\t{}", line_num + 1, mark_spans(&result.gold, spans.clone()))?;
+        writeln!(writer, "{}\tsystem\t{}", line_num + 1, mark_spans(&result.system, spans))?;
+        let descriptions: Vec<String> = result
+            .errors
+            .iter()
+            .map(|e| format!("{} {} -> {}", e.kind, e.gold.join(" "), e.system.join(" ")))
+            .collect();
+        writeln!(writer, "{}\terrors\t{}", line_num + 1, descriptions.join("; "))?;
+        for error in &result.errors {
+            *analysis.errors.entry(error.kind).or_insert(0) += 1;
+        }
+    }
+    Ok(analysis)
+}
+
+/// Writes the errors aggregated by kind and by character-type context, the most
+/// frequent contexts first.
+///
+/// # Arguments
+/// * `writer` - The destination, e.g. standard error.
+/// * `analysis` - The analysis returned by [`analyze`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_summary<W: Write>(writer: &mut W, analysis: &ErrorAnalysis) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Sentences: {} ({} with errors)",
+        analysis.sentences, analysis.erroneous_sentences
+    )?;
+    for (kind, count) in &analysis.errors {
+        writeln!(writer, "{}: {}", kind, count)?;
+    }
+    let mut contexts: Vec<(&String, &ContextErrors)> = analysis.contexts.iter().collect();
+    contexts.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(b.0)));
+    if !contexts.is_empty() {
+        writeln!(writer, "CONTEXT\tERRORS\tEXTRA\tMISSED")?;
+    }
+    for (context, errors) in contexts {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}",
+            context,
+            errors.total(),
+            errors.false_positives,
+            errors.false_negatives
+        )?;
+    }
+    Ok(())
+}
+
+/// Number of characters on either side of a wrong decision in the context of a
+/// [`FrequentError`]. One character keys the errors by the pair of characters the
+/// decision is between, so that errors in different words add up.
+const CONTEXT_CHARS: usize = 1;
+
+/// A wrong boundary decision between the same characters, with how often it is made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrequentError {
+    /// `true` if the system splits where the gold corpus has no boundary, `false` if it
+    /// joins across a gold boundary.
+    pub split: bool,
+    /// The characters around the decision, with `|` at the decision, e.g. `で|す`.
+    pub context: String,
+    /// The character types before and after the decision, e.g. `I/I`.
+    pub types: String,
+    /// The number of times the decision is made wrongly in the corpus.
+    pub count: usize,
+    /// The number of the first sentence with the error, counting from 1.
+    pub sentence: usize,
+    /// The first gold sentence with the error.
+    pub example: String,
+}
+
+/// Segments every sentence of a gold corpus and aggregates the wrong boundary decisions
+/// by the characters around them.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to analyze.
+/// * `sentences` - The gold sentences, with words separated by spaces. Empty sentences
+///   are skipped, but counted in [`FrequentError::sentence`].
+///
+/// # Returns
+/// The wrong decisions, the most frequent first.
+#[must_use]
+pub fn frequent_errors<'a, I>(segmenter: &Segmenter, sentences: I) -> Vec<FrequentError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut errors: HashMap<(bool, String), FrequentError> = HashMap::new();
+    for (sentence_num, sentence) in sentences.into_iter().enumerate() {
+        let gold = gold_words(sentence);
+        if gold.is_empty() {
+            continue;
+        }
+        let chars: Vec<char> = gold.concat().chars().collect();
+        for (i, types, split) in analyze_gold(segmenter, gold).decisions {
+            let before: String = chars[i.saturating_sub(CONTEXT_CHARS)..i].iter().collect();
+            let after: String = chars[i..(i + CONTEXT_CHARS).min(chars.len())].iter().collect();
+            let context = format!("{}|{}", before, after);
+            errors
+                .entry((split, context.clone()))
+                .or_insert_with(|| FrequentError {
+                    split,
+                    context,
+                    types,
+                    count: 0,
+                    sentence: sentence_num + 1,
+                    example: sentence.trim().to_string(),
+                })
+                .count += 1;
+        }
+    }
+    let mut errors: Vec<FrequentError> = errors.into_values().collect();
+    errors.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.sentence.cmp(&b.sentence))
+            .then_with(|| (a.split, &a.context).cmp(&(b.split, &b.context)))
+    });
+    errors
+}
+
+/// Writes frequent errors as tab-separated values with a header: the kind (`split` or
+/// `join`), the context, the character types, the count, and the number and text of
+/// the first sentence with the error.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `errors` - The errors returned by [`frequent_errors`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_error_report<W: Write + ?Sized>(
+    writer: &mut W,
+    errors: &[FrequentError],
+) -> io::Result<()> {
+    writeln!(writer, "KIND\tCONTEXT\tTYPES\tCOUNT\tSENTENCE\tEXAMPLE")?;
+    for error in errors {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            if error.split { "split" } else { "join" },
+            error.context,
+            error.types,
+            error.count,
+            error.sentence,
+            error.example
+        )?;
+    }
+    Ok(())
+}
+
+/// Templates whose features contain characters of the text, rather than only character
+/// types or previous decisions. Only these can be unseen because of new words.
+const LEXICAL_TEMPLATES: [&str; 4] = ["UW", "BW", "TW", "WC"];
+
+/// Returns the template of a feature, e.g. `UW4` for `UW4:は`.
+fn template(feature: &str) -> &str {
+    feature.split_once(':').map_or(feature, |(t, _)| t)
+}
+
+/// Returns `true` if a feature contains characters of the text.
+fn is_lexical(feature: &str) -> bool {
+    LEXICAL_TEMPLATES.iter().any(|prefix| feature.starts_with(prefix))
+}
+
+/// How many active features of a template were seen in training.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TemplateCoverage {
+    /// Number of active features of the template over all decisions.
+    pub active: usize,
+    /// Number of those seen in training.
+    pub seen: usize,
+}
+
+impl TemplateCoverage {
+    /// Returns the percentage of active features seen in training.
+    #[must_use]
+    pub fn coverage(&self) -> f64 {
+        percentage(self.seen, self.active)
+    }
+}
+
+/// Feature coverage of a model over a gold corpus, and the errors by coverage.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageAnalysis {
+    /// Number of boundary decisions.
+    pub decisions: usize,
+    /// Number of wrong decisions.
+    pub errors: usize,
+    /// Number of decisions where most lexical features were unseen.
+    pub unseen_decisions: usize,
+    /// Number of wrong decisions where most lexical features were unseen.
+    pub unseen_errors: usize,
+    /// Coverage of each template, e.g. `UW4`.
+    pub templates: BTreeMap<String, TemplateCoverage>,
+}
+
+impl CoverageAnalysis {
+    /// Returns the error rate in percent of decisions where most lexical features were
+    /// unseen.
+    #[must_use]
+    pub fn unseen_error_rate(&self) -> f64 {
+        percentage(self.unseen_errors, self.unseen_decisions)
+    }
+
+    /// Returns the error rate in percent of the other decisions.
+    #[must_use]
+    pub fn seen_error_rate(&self) -> f64 {
+        percentage(self.errors - self.unseen_errors, self.decisions - self.unseen_decisions)
+    }
+}
+
+/// Returns `part` as a percentage of `total`, or 0 if `total` is 0.
+fn percentage(part: usize, total: usize) -> f64 {
+    part as f64 / total.max(1) as f64 * 100.0
+}
+
+/// Reads the names of the features in a features file written by `litsea extract`.
+///
+/// # Arguments
+/// * `reader` - The features file, one instance per line: a label followed by
+///   tab-separated features.
+///
+/// # Returns
+/// The set of feature names.
+///
+/// # Errors
+/// Returns an error if the file cannot be read.
+pub fn read_feature_names<R: BufRead>(reader: R) -> io::Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    for line in reader.lines() {
+        let line = line?;
+        for feature in line.split('\t').skip(1) {
+            if !names.contains(feature) {
+                names.insert(feature.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Segments every sentence of a gold corpus, checks which features of each boundary
+/// decision were seen in training, and writes the wrong decisions where most lexical
+/// features (those of the `UW`, `BW`, `TW` and `WC` templates) were unseen, as
+/// tab-separated lines: the line number, the three characters on either side of the
+/// decision with `|` at the decision, the share of unseen lexical features, and `extra`
+/// for a wrong boundary or `missed` for a missed one.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to analyze.
+/// * `seen` - The features seen in training, e.g. read from the training features with
+///   [`read_feature_names`]. If `None`, the features with a weight in the model are
+///   used, which also counts features seen in training but never selected as unseen.
+/// * `corpus` - The gold corpus, one sentence per line with words separated by spaces.
+/// * `writer` - The destination of the report.
+///
+/// # Returns
+/// The coverage and the errors by coverage over the corpus.
+///
+/// # Errors
+/// Returns an error if the corpus cannot be read or the report cannot be written.
+pub fn analyze_coverage<R: BufRead, W: Write>(
+    segmenter: &Segmenter,
+    seen: Option<&HashSet<String>>,
+    corpus: R,
+    writer: &mut W,
+) -> io::Result<CoverageAnalysis> {
+    let weighted: HashSet<String>;
+    let seen = match seen {
+        Some(seen) => seen,
+        None => {
+            weighted = segmenter.learner().weights().map(|(h, _)| h.to_string()).collect();
+            &weighted
+        }
+    };
+
+    let mut analysis = CoverageAnalysis::default();
+    for (line_num, line) in corpus.lines().enumerate() {
+        let gold = gold_words(&line?);
+        if gold.is_empty() {
+            continue;
+        }
+        let text = gold.concat();
+        let chars: Vec<char> = text.chars().collect();
+        let gold_starts = starts(&gold, chars.len());
+        let system_starts = starts(&segmenter.segment(&text), chars.len());
+
+        for (k, features) in segmenter.decision_features(&text).iter().enumerate() {
+            // The decision before character k + 1.
+            let position = k + 1;
+            let mut lexical = 0;
+            let mut unseen = 0;
+            for feature in features {
+                let coverage = analysis.templates.entry(template(feature).to_string()).or_default();
+                coverage.active += 1;
+                let is_seen = seen.contains(feature);
+                if is_seen {
+                    coverage.seen += 1;
+                }
+                if is_lexical(feature) {
+                    lexical += 1;
+                    if !is_seen {
+                        unseen += 1;
+                    }
+                }
+            }
+            let mostly_unseen = unseen * 2 > lexical;
+            let wrong = gold_starts[position] != system_starts[position];
+            analysis.decisions += 1;
+            analysis.errors += usize::from(wrong);
+            analysis.unseen_decisions += usize::from(mostly_unseen);
+            if wrong && mostly_unseen {
+                analysis.unseen_errors += 1;
+                let before: String = chars[position.saturating_sub(3)..position].iter().collect();
+                let after: String =
+                    chars[position..(position + 3).min(chars.len())].iter().collect();
+                writeln!(
+                    writer,
+                    "{}\t{}|{}\t{:.2}\t{}",
+                    line_num + 1,
+                    before,
+                    after,
+                    unseen as f64 / lexical as f64,
+                    if system_starts[position] { "extra" } else { "missed" }
+                )?;
+            }
+        }
+    }
+    Ok(analysis)
+}
+
+/// Writes the errors by coverage and the coverage of each template.
+///
+/// # Arguments
+/// * `writer` - The destination, e.g. standard error.
+/// * `analysis` - The analysis returned by [`analyze_coverage`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_coverage<W: Write>(writer: &mut W, analysis: &CoverageAnalysis) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Decisions: {} ({} wrong, {:.2}%)",
+        analysis.decisions,
+        analysis.errors,
+        percentage(analysis.errors, analysis.decisions)
+    )?;
+    writeln!(
+        writer,
+        "Mostly unseen: {} decisions, {} wrong ({:.2}%)",
+        analysis.unseen_decisions,
+        analysis.unseen_errors,
+        analysis.unseen_error_rate()
+    )?;
+    writeln!(
+        writer,
+        "Mostly seen: {} decisions, {} wrong ({:.2}%)",
+        analysis.decisions - analysis.unseen_decisions,
+        analysis.errors - analysis.unseen_errors,
+        analysis.seen_error_rate()
+    )?;
+    writeln!(writer, "TEMPLATE\tACTIVE\tSEEN\tCOVERAGE")?;
+    for (template, coverage) in &analysis.templates {
+        writeln!(
+            writer,
+            "{}\t{}\t{}\t{:.2}%",
+            template,
+            coverage.active,
+            coverage.seen,
+            coverage.coverage()
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::japanese_segmenter;
+
+    #[test]
+    fn test_analyze_sentence() {
+        let segmenter = japanese_segmenter();
+        // The model segments this as "これ は テスト で す 。".
+        let errors = analyze_sentence(&segmenter, "これ は テスト です 。");
+        assert_eq!(
+            errors,
+            vec![SegmentationError {
+                start: 18,
+                end: 24,
+                gold: vec!["です".to_string()],
+                system: vec!["で".to_string(), "す".to_string()],
+                kind: ErrorKind::OverSplit,
+            }]
+        );
+
+        let errors = analyze_sentence(&segmenter, "これ は テ スト で す 。");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ErrorKind::UnderSplit);
+
+        let errors = analyze_sentence(&segmenter, "これ は テス トで す 。");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, ErrorKind::BoundaryShift);
+        assert_eq!(errors[0].gold, vec!["テス", "トで"]);
+        assert_eq!(errors[0].system, vec!["テスト", "で"]);
+
+        assert!(analyze_sentence(&segmenter, "これ は テスト で す 。").is_empty());
+        assert!(analyze_sentence(&segmenter, " ").is_empty());
+    }
+
+    #[test]
+    fn test_analyze() {
+        let segmenter = japanese_segmenter();
+        let corpus = "これ は テスト です 。\n\nこれ は テスト で す 。\n";
+        let mut report = Vec::new();
+        let analysis = analyze(&segmenter, corpus.as_bytes(), &mut report).unwrap();
+        assert_eq!(analysis.sentences, 2);
+        assert_eq!(analysis.erroneous_sentences, 1);
+        assert_eq!(analysis.errors.get(&ErrorKind::OverSplit), Some(&1));
+        // で and す are both hiragana.
+        assert_eq!(
+            analysis.contexts.get("I/I"),
+            Some(&ContextErrors {
+                false_positives: 1,
+                false_negatives: 0,
+            })
+        );
+
+        let report = String::from_utf8(report).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "1\tgold\tこれ は テスト [です] 。",
+                "1\tsystem\tこれ は テスト [で す] 。",
+                "1\terrors\tover-split です -> で す",
+            ]
+        );
+
+        let mut summary = Vec::new();
+        write_summary(&mut summary, &analysis).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.contains("over-split: 1\n"));
+        assert!(summary.contains("I/I\t1\t1\t0\n"));
+    }
+
+    #[test]
+    fn test_frequent_errors() {
+        let segmenter = japanese_segmenter();
+        let corpus = [
+            "これ は テスト です 。",
+            "",
+            "それ も テスト です 。",
+            "これ は テ スト で す 。",
+        ];
+        let errors = frequent_errors(&segmenter, corpus);
+
+        // です is split in the first two sentences, テ スト joined in the last.
+        assert_eq!(
+            errors[0],
+            FrequentError {
+                split: true,
+                context: "で|す".to_string(),
+                types: "I/I".to_string(),
+                count: 2,
+                sentence: 1,
+                example: "これ は テスト です 。".to_string(),
+            }
+        );
+        assert_eq!(errors.len(), 2);
+        assert!(!errors[1].split);
+        assert_eq!(errors[1].context, "テ|ス");
+        assert_eq!((errors[1].count, errors[1].sentence), (1, 4));
+
+        let mut report = Vec::new();
+        write_error_report(&mut report, &errors).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines[0], "KIND\tCONTEXT\tTYPES\tCOUNT\tSENTENCE\tEXAMPLE");
+        assert_eq!(lines[1], "split\tで|す\tI/I\t2\t1\tこれ は テスト です 。");
+        assert!(lines[2].starts_with("join\tテ|ス\tK/K\t1\t4\t"));
+    }
+
+    #[test]
+    fn test_read_feature_names() {
+        let features = "1\tUW4:は\tUC4:I\n-1\tUW4:は\tUC4:K\n";
+        let names = read_feature_names(features.as_bytes()).unwrap();
+        assert_eq!(names.len(), 3);
+        assert!(names.contains("UC4:K"));
+    }
+
+    #[test]
+    fn test_analyze_coverage() {
+        let segmenter = japanese_segmenter();
+        let corpus = "これ は テスト です 。\n";
+        let mut report = Vec::new();
+        let analysis = analyze_coverage(&segmenter, None, corpus.as_bytes(), &mut report).unwrap();
+        assert_eq!(analysis.decisions, 8);
+        assert_eq!(analysis.errors, 1);
+        let uw4 = analysis.templates["UW4"];
+        assert_eq!(uw4.active, 8);
+        assert!(uw4.seen <= uw4.active);
+
+        // Nothing is seen: every decision is mostly unseen and every error is reported.
+        let seen = HashSet::new();
+        let mut report = Vec::new();
+        let analysis =
+            analyze_coverage(&segmenter, Some(&seen), corpus.as_bytes(), &mut report).unwrap();
+        assert_eq!(analysis.unseen_decisions, 8);
+        assert_eq!(analysis.unseen_errors, 1);
+        assert_eq!(analysis.unseen_error_rate(), 12.5);
+        assert_eq!(analysis.seen_error_rate(), 0.0);
+        assert_eq!(analysis.templates["UW4"].coverage(), 0.0);
+        assert_eq!(String::from_utf8(report).unwrap(), "1\tストで|す。\t1.00\textra\n");
+
+        let mut summary = Vec::new();
+        write_coverage(&mut summary, &analysis).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.contains("Mostly unseen: 8 decisions, 1 wrong (12.50%)"));
+        assert!(summary.contains("UW4\t8\t0\t0.00%"));
+    }
+}
@@ -0,0 +1,183 @@
+//! Asynchronous segmentation on Tokio, enabled by the `tokio` feature.
+//!
+//! Segmentation is CPU-bound, so running it directly in an async task blocks the
+//! executor thread. [`AsyncSegmenter`] moves the work to Tokio's blocking thread pool,
+//! limited to a fixed number of concurrent jobs, and can segment a whole stream of
+//! lines from an [`AsyncBufRead`] with [`segment_stream`](AsyncSegmenter::segment_stream).
+
+use std::io;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use futures_util::{Stream, StreamExt, stream};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio::sync::Semaphore;
+
+use crate::segmenter::{ScoredWord, Segmenter};
+
+/// A [`Segmenter`] that segments on Tokio's blocking thread pool.
+///
+/// Cloning an `AsyncSegmenter` is cheap; clones share the segmenter and the limit on
+/// concurrent jobs.
+#[derive(Clone)]
+pub struct AsyncSegmenter {
+    segmenter: Arc<Segmenter>,
+    permits: Arc<Semaphore>,
+    max_jobs: usize,
+}
+
+impl AsyncSegmenter {
+    /// Creates a new instance of [`AsyncSegmenter`] that runs as many jobs at once as
+    /// there are CPUs.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter to run.
+    ///
+    /// # Returns
+    /// A new AsyncSegmenter.
+    pub fn new(segmenter: Segmenter) -> Self {
+        let max_jobs = std::thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        Self::with_max_jobs(segmenter, max_jobs)
+    }
+
+    /// Creates a new instance of [`AsyncSegmenter`] with a limit on concurrent jobs.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter to run.
+    /// * `max_jobs` - The maximum number of sentences segmented at once; at least 1.
+    ///
+    /// # Returns
+    /// A new AsyncSegmenter.
+    pub fn with_max_jobs(segmenter: Segmenter, max_jobs: usize) -> Self {
+        let max_jobs = max_jobs.max(1);
+        AsyncSegmenter {
+            segmenter: Arc::new(segmenter),
+            permits: Arc::new(Semaphore::new(max_jobs)),
+            max_jobs,
+        }
+    }
+
+    /// Returns the underlying segmenter.
+    #[must_use]
+    pub fn segmenter(&self) -> &Segmenter {
+        &self.segmenter
+    }
+
+    /// Segments a sentence on the blocking thread pool.
+    ///
+    /// # Arguments
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The words of the sentence, as returned by [`Segmenter::segment_with_scores`].
+    ///
+    /// # Errors
+    /// Returns an error if the blocking task panics or the runtime is shutting down.
+    pub async fn segment(&self, sentence: String) -> io::Result<Vec<ScoredWord>> {
+        // The semaphore is never closed, so acquiring a permit cannot fail.
+        let _permit = self.permits.acquire().await.map_err(io::Error::other)?;
+        let segmenter = Arc::clone(&self.segmenter);
+        tokio::task::spawn_blocking(move || segmenter.segment_with_scores(&sentence))
+            .await
+            .map_err(io::Error::other)
+    }
+
+    /// Segments each line read from `reader`.
+    ///
+    /// Up to the job limit of lines are segmented concurrently, but the results are
+    /// yielded in input order. Line endings are not part of the segmented text. The
+    /// stream ends after the last line or the first read error.
+    ///
+    /// # Arguments
+    /// * `reader` - The source of the lines.
+    ///
+    /// # Returns
+    /// A stream of the words of each line.
+    ///
+    /// # Example
+    /// ```
+    /// use futures_util::StreamExt;
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::async_segmenter::AsyncSegmenter;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// # tokio_test::block_on(async {
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    /// let segmenter = AsyncSegmenter::new(Segmenter::new(Language::Japanese, Some(learner)));
+    ///
+    /// let mut lines = std::pin::pin!(segmenter.segment_stream("これはテストです。\n".as_bytes()));
+    /// let words = lines.next().await.unwrap().unwrap();
+    /// assert_eq!(words[2].surface, "テスト");
+    /// # });
+    /// ```
+    pub fn segment_stream<R>(
+        &self,
+        reader: R,
+    ) -> impl Stream<Item = io::Result<Vec<ScoredWord>>> + Send + 'static
+    where
+        R: AsyncBufRead + Unpin + Send + 'static,
+    {
+        let lines = stream::unfold(Some(reader.lines()), |lines| async move {
+            let mut lines = lines?;
+            match lines.next_line().await {
+                Ok(Some(line)) => Some((Ok(line), Some(lines))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        });
+        let this = self.clone();
+        lines
+            .map(move |line| {
+                let this = this.clone();
+                async move { this.segment(line?).await }
+            })
+            .buffered(self.max_jobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::rwcp_segmenter;
+
+    fn test_segmenter(max_jobs: usize) -> AsyncSegmenter {
+        AsyncSegmenter::with_max_jobs(rwcp_segmenter(), max_jobs)
+    }
+
+    fn surfaces(words: Vec<ScoredWord>) -> Vec<String> {
+        words.into_iter().map(|w| w.surface).collect()
+    }
+
+    #[tokio::test]
+    async fn test_segment() {
+        let segmenter = test_segmenter(2);
+        let words = segmenter.segment("これはテストです。".to_string()).await.unwrap();
+        assert_eq!(surfaces(words), vec!["これ", "は", "テスト", "です", "。"]);
+    }
+
+    #[tokio::test]
+    async fn test_segment_stream_keeps_order() {
+        let segmenter = test_segmenter(4);
+        let input = "これはテストです。\r\n\n今日は晴れです。\n".repeat(20);
+        let results: Vec<_> = segmenter.segment_stream(std::io::Cursor::new(input)).collect().await;
+        assert_eq!(results.len(), 60);
+        for chunk in results.chunks(3) {
+            let chunk: Vec<_> =
+                chunk.iter().map(|r| surfaces(r.as_ref().unwrap().clone())).collect();
+            assert_eq!(chunk[0], vec!["これ", "は", "テスト", "です", "。"]);
+            assert!(chunk[1].is_empty());
+            assert_eq!(chunk[2].concat(), "今日は晴れです。");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_segment_stream_read_error() {
+        let segmenter = test_segmenter(1);
+        let results: Vec<_> = segmenter.segment_stream(&b"\xff\xfe\n"[..]).collect().await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,428 @@
+//! Benchmarking helpers measuring segmentation throughput and latency, model load time
+//! and memory footprint.
+//!
+//! The functions return structured results rather than printing them, so they can be
+//! used by the CLI, by library tests, and by the CI of downstream crates to catch
+//! performance regressions of a given model on their own text.
+//!
+//! ```
+//! use litsea::bench;
+//! use litsea::language::Language;
+//!
+//! let model = include_bytes!("../../resources/RWCP.model");
+//! let sentences = vec!["これはテストです。".to_string()];
+//! let report = bench::run(model, Language::Japanese, &sentences, 10).unwrap();
+//!
+//! assert!(report.memory.num_features > 0);
+//! assert!(report.throughput.chars_per_second() > 0.0);
+//! ```
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crate::adaboost::AdaBoost;
+use crate::language::Language;
+use crate::segmenter::Segmenter;
+
+/// Segmentation speed measured by [`benchmark`].
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    /// Number of sentences segmented per pass.
+    pub num_sentences: usize,
+    /// Number of characters segmented per pass.
+    pub num_chars: usize,
+    /// Number of passes over the sentences.
+    pub iterations: usize,
+    /// Total time spent segmenting.
+    pub elapsed: Duration,
+}
+
+impl Benchmark {
+    /// Mean time to segment one sentence, in microseconds.
+    #[must_use]
+    pub fn mean_latency_micros(&self) -> f64 {
+        let runs = (self.num_sentences * self.iterations).max(1);
+        self.elapsed.as_secs_f64() * 1_000_000.0 / runs as f64
+    }
+
+    /// Segmentation throughput, in sentences per second.
+    #[must_use]
+    pub fn sentences_per_second(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 {
+            (self.num_sentences * self.iterations) as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Segmentation throughput, in characters per second.
+    #[must_use]
+    pub fn chars_per_second(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0.0 { (self.num_chars * self.iterations) as f64 / secs } else { 0.0 }
+    }
+}
+
+/// Measures how fast a segmenter segments the given sentences.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to measure.
+/// * `sentences` - The sentences to segment, without word separators.
+/// * `iterations` - The number of passes over the sentences.
+///
+/// # Returns
+/// The measured [`Benchmark`].
+pub fn benchmark(segmenter: &Segmenter, sentences: &[String], iterations: usize) -> Benchmark {
+    let num_chars = sentences.iter().map(|s| s.chars().count()).sum();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        for sentence in sentences {
+            std::hint::black_box(segmenter.segment(sentence));
+        }
+    }
+    Benchmark {
+        num_sentences: sentences.len(),
+        num_chars,
+        iterations,
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Segments the sentences without measuring, so that caches and the allocator are warm
+/// before [`benchmark`] or [`bench_latency`] runs.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to warm up.
+/// * `sentences` - The sentences to segment, without word separators.
+/// * `passes` - The number of passes over the sentences.
+pub fn warm_up(segmenter: &Segmenter, sentences: &[String], passes: usize) {
+    for _ in 0..passes {
+        for sentence in sentences {
+            std::hint::black_box(segmenter.segment(sentence));
+        }
+    }
+}
+
+/// Distribution of the time to segment one sentence, measured by [`bench_latency`].
+#[derive(Debug, Clone)]
+pub struct Latency {
+    /// Number of sentences timed.
+    pub num_samples: usize,
+    /// Median time to segment a sentence.
+    pub p50: Duration,
+    /// 99th percentile of the time to segment a sentence.
+    pub p99: Duration,
+    /// Slowest sentence.
+    pub max: Duration,
+}
+
+/// Times every sentence separately and reports the percentiles of the latency.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to measure.
+/// * `sentences` - The sentences to segment, without word separators.
+/// * `iterations` - The number of passes over the sentences.
+///
+/// # Returns
+/// The measured [`Latency`], all zero if there is nothing to segment.
+pub fn bench_latency(segmenter: &Segmenter, sentences: &[String], iterations: usize) -> Latency {
+    let mut samples = Vec::with_capacity(sentences.len() * iterations);
+    for _ in 0..iterations {
+        for sentence in sentences {
+            let start = Instant::now();
+            std::hint::black_box(segmenter.segment(sentence));
+            samples.push(start.elapsed());
+        }
+    }
+    samples.sort_unstable();
+    Latency {
+        num_samples: samples.len(),
+        p50: percentile(&samples, 50.0),
+        p99: percentile(&samples, 99.0),
+        max: samples.last().copied().unwrap_or_default(),
+    }
+}
+
+/// Returns the nearest-rank percentile of sorted samples, or zero if there are none.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Returns the peak resident memory of the process so far, in bytes.
+///
+/// # Returns
+/// The high water mark of the resident set size, or `None` where it is unavailable.
+/// It is read from `/proc/self/status` and so only known on Linux.
+#[must_use]
+pub fn peak_memory() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: usize = line["VmHWM:".len()..].trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Model loading time measured by [`bench_model_load`].
+#[derive(Debug, Clone)]
+pub struct LoadBenchmark {
+    /// Size of the model file, in bytes.
+    pub model_bytes: usize,
+    /// Number of times the model was loaded.
+    pub iterations: usize,
+    /// Total time spent loading.
+    pub elapsed: Duration,
+    /// Fastest single load.
+    pub min: Duration,
+}
+
+impl LoadBenchmark {
+    /// Mean time to load the model once.
+    #[must_use]
+    pub fn mean(&self) -> Duration {
+        self.elapsed / self.iterations.max(1) as u32
+    }
+}
+
+/// Measures how long it takes to parse and verify a model.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file, including its header.
+/// * `iterations` - The number of times to load the model. At least one load is done.
+///
+/// # Returns
+/// The measured [`LoadBenchmark`] and the model from the last load.
+///
+/// # Errors
+/// Returns an error if the model fails verification or cannot be parsed.
+pub fn bench_model_load(
+    content: &[u8],
+    iterations: usize,
+) -> io::Result<(LoadBenchmark, AdaBoost)> {
+    let iterations = iterations.max(1);
+    let mut elapsed = Duration::ZERO;
+    let mut min = Duration::MAX;
+    let mut learner = AdaBoost::new(0.01, 100);
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let mut loaded = AdaBoost::new(0.01, 100);
+        loaded.load_model_bytes(content)?;
+        let took = start.elapsed();
+        elapsed += took;
+        min = min.min(took);
+        learner = std::hint::black_box(loaded);
+    }
+    let bench = LoadBenchmark {
+        model_bytes: content.len(),
+        iterations,
+        elapsed,
+        min,
+    };
+    Ok((bench, learner))
+}
+
+/// Memory held by a loaded model, as estimated by [`memory_footprint`].
+#[derive(Debug, Clone)]
+pub struct MemoryFootprint {
+    /// Number of features with a non-zero weight.
+    pub num_features: usize,
+    /// Estimated heap memory held by the model, in bytes.
+    pub heap_bytes: usize,
+}
+
+/// Estimates the memory footprint of a model.
+///
+/// The estimate counts the heap allocations of the weights, the feature names and the
+/// feature index. It does not account for allocator overhead, so it is a lower bound
+/// that is stable across runs and suited for comparing models.
+///
+/// # Arguments
+/// * `learner` - The model to measure.
+///
+/// # Returns
+/// The estimated [`MemoryFootprint`].
+#[must_use]
+pub fn memory_footprint(learner: &AdaBoost) -> MemoryFootprint {
+    MemoryFootprint {
+        num_features: learner.weights().count(),
+        heap_bytes: learner.heap_size(),
+    }
+}
+
+/// Results of [`run`].
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    /// Model loading time.
+    pub load: LoadBenchmark,
+    /// Memory held by the loaded model.
+    pub memory: MemoryFootprint,
+    /// Segmentation speed on the given sentences.
+    pub throughput: Benchmark,
+    /// Per-sentence latency on the given sentences.
+    pub latency: Latency,
+    /// Peak resident memory of the process, see [`peak_memory`].
+    pub peak_memory: Option<usize>,
+}
+
+/// Loads a model and measures its load time, memory footprint, segmentation speed and
+/// latency.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file, including its header.
+/// * `language` - The language the model is used with.
+/// * `sentences` - The sentences to segment, without word separators.
+/// * `iterations` - The number of model loads and of passes over the sentences.
+///
+/// # Returns
+/// A [`BenchReport`] with all measurements.
+///
+/// # Errors
+/// Returns an error if the model fails verification or cannot be parsed.
+pub fn run(
+    content: &[u8],
+    language: Language,
+    sentences: &[String],
+    iterations: usize,
+) -> io::Result<BenchReport> {
+    let (load, learner) = bench_model_load(content, iterations)?;
+    let memory = memory_footprint(&learner);
+    let segmenter = Segmenter::new(language, Some(learner));
+    let throughput = benchmark(&segmenter, sentences, iterations);
+    let latency = bench_latency(&segmenter, sentences, iterations);
+    Ok(BenchReport {
+        load,
+        memory,
+        throughput,
+        latency,
+        peak_memory: peak_memory(),
+    })
+}
+
+/// Writes a report in the format printed by `litsea bench`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `report` - The measurements, see [`run`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_report<W: Write + ?Sized>(writer: &mut W, report: &BenchReport) -> io::Result<()> {
+    let micros = |d: Duration| d.as_secs_f64() * 1_000_000.0;
+    writeln!(writer, "Model:")?;
+    writeln!(writer, "  Size: {} bytes", report.load.model_bytes)?;
+    writeln!(writer, "  Features: {}", report.memory.num_features)?;
+    writeln!(writer, "  Heap: {} bytes", report.memory.heap_bytes)?;
+    writeln!(
+        writer,
+        "  Load time: {:.2} ms (min {:.2} ms)",
+        report.load.mean().as_secs_f64() * 1000.0,
+        report.load.min.as_secs_f64() * 1000.0
+    )?;
+    let t = &report.throughput;
+    writeln!(writer, "Throughput:")?;
+    writeln!(
+        writer,
+        "  Input: {} sentences, {} characters, {} passes",
+        t.num_sentences, t.num_chars, t.iterations
+    )?;
+    writeln!(writer, "  Sentences/sec: {:.1}", t.sentences_per_second())?;
+    writeln!(writer, "  Characters/sec: {:.1}", t.chars_per_second())?;
+    writeln!(writer, "Latency:")?;
+    writeln!(writer, "  Mean: {:.2} us", t.mean_latency_micros())?;
+    writeln!(writer, "  p50: {:.2} us", micros(report.latency.p50))?;
+    writeln!(writer, "  p99: {:.2} us", micros(report.latency.p99))?;
+    writeln!(writer, "  Max: {:.2} us", micros(report.latency.max))?;
+    match report.peak_memory {
+        Some(bytes) => writeln!(writer, "Peak memory: {} bytes", bytes)?,
+        None => writeln!(writer, "Peak memory: unavailable")?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &[u8] = include_bytes!("../../resources/RWCP.model");
+
+    #[test]
+    fn test_benchmark_rates() {
+        let bench = Benchmark {
+            num_sentences: 2,
+            num_chars: 10,
+            iterations: 5,
+            elapsed: Duration::from_secs(2),
+        };
+
+        assert!((bench.sentences_per_second() - 5.0).abs() < 1e-9);
+        assert!((bench.chars_per_second() - 25.0).abs() < 1e-9);
+        assert!((bench.mean_latency_micros() - 200_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bench_model_load() {
+        let (bench, learner) = bench_model_load(MODEL, 3).unwrap();
+
+        assert_eq!(bench.model_bytes, MODEL.len());
+        assert_eq!(bench.iterations, 3);
+        assert!(bench.min <= bench.mean());
+        assert!(learner.weights().count() > 0);
+    }
+
+    #[test]
+    fn test_bench_model_load_invalid() {
+        assert!(bench_model_load(b"UW4:a\tnot-a-number\n", 1).is_err());
+    }
+
+    #[test]
+    fn test_memory_footprint() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let footprint = memory_footprint(&learner);
+
+        let names: usize = learner.weights().map(|(h, _)| h.len()).sum();
+        assert_eq!(footprint.num_features, learner.weights().count());
+        // At least the weights and two copies of every feature name.
+        assert!(footprint.heap_bytes >= footprint.num_features * 8 + 2 * names);
+    }
+
+    #[test]
+    fn test_run() {
+        let sentences = vec!["これはテストです。".to_string(), "今日はいい天気".to_string()];
+        let report = run(MODEL, Language::Japanese, &sentences, 2).unwrap();
+
+        assert_eq!(report.load.iterations, 2);
+        assert_eq!(report.throughput.num_sentences, 2);
+        assert_eq!(report.throughput.num_chars, 16);
+        assert_eq!(report.throughput.iterations, 2);
+        assert!(report.memory.num_features > 0);
+        assert_eq!(report.latency.num_samples, 4);
+        assert!(report.latency.p50 <= report.latency.p99);
+        assert!(report.latency.p99 <= report.latency.max);
+
+        let mut out = Vec::new();
+        write_report(&mut out, &report).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Sentences/sec: "));
+        assert!(out.contains("p99: "));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_micros).collect();
+        assert_eq!(percentile(&samples, 50.0), Duration::from_micros(50));
+        assert_eq!(percentile(&samples, 99.0), Duration::from_micros(99));
+        assert_eq!(percentile(&samples, 100.0), Duration::from_micros(100));
+        assert_eq!(percentile(&samples[..1], 50.0), Duration::from_micros(1));
+        assert_eq!(percentile(&[], 99.0), Duration::ZERO);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_peak_memory() {
+        assert!(peak_memory().unwrap() > 0);
+    }
+}
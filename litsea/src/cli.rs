@@ -0,0 +1,1321 @@
+//! The `extract`, `train` and `segment` subcommands of the `litsea` command line tool as
+//! library functions, so other tools can embed them without spawning the binary.
+//!
+//! The functions take plain arguments instead of parsed command line options, and leave
+//! reporting (progress messages, exit codes) to the caller. `extract` and `train` need
+//! the `train` feature.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+#[cfg(feature = "train")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+#[cfg(feature = "train")]
+use std::sync::Arc;
+
+#[cfg(feature = "train")]
+use crate::adaboost::{Algorithm, Boosting, Metrics, TrainingRecord};
+use crate::compound::CompoundSplitter;
+#[cfg(feature = "train")]
+use crate::control::TrainControl;
+#[cfg(feature = "train")]
+use crate::corpus::CorpusFormat;
+#[cfg(feature = "train")]
+use crate::extractor::Extractor;
+#[cfg(feature = "train")]
+use crate::language::{Language, LatinClasses};
+use crate::lattice::LatticeSegmenter;
+use crate::lemma::LemmaDictionary;
+use crate::normalize::Normalizer;
+#[cfg(feature = "train")]
+use crate::progress::ProgressHandler;
+#[cfg(feature = "train")]
+use crate::quantize::Quantization;
+use crate::reading::ReadingDictionary;
+#[cfg(feature = "train")]
+use crate::segmenter::FeatureTemplates;
+use crate::segmenter::Segmenter;
+use crate::stopwords::StopWords;
+use crate::token::{Token, TokenFilter, set_char_offsets};
+#[cfg(feature = "train")]
+use crate::trainer::{CrossValidation, EnsembleReport, PruneReport, QuantizationReport, Trainer};
+use crate::unigram::UnigramReranker;
+
+#[cfg(feature = "train")]
+/// Output format of [`extract`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureFormat {
+    /// One instance per line: the label followed by its features, separated by tabs.
+    Text,
+    /// Deduplicated instances and a feature dictionary as Parquet files.
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[cfg(feature = "train")]
+impl FromStr for FeatureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(FeatureFormat::Text),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(FeatureFormat::Parquet),
+            #[cfg(feature = "parquet")]
+            _ => Err(format!("Unsupported output format: '{}'. Supported: text, parquet", s)),
+            #[cfg(not(feature = "parquet"))]
+            _ => Err(format!("Unsupported output format: '{}'. Supported: text", s)),
+        }
+    }
+}
+
+#[cfg(feature = "train")]
+/// Options of [`extract`], with the defaults of `litsea extract`.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractOptions<'a> {
+    /// The language of the corpus.
+    pub language: Language,
+    /// The format of the corpus.
+    pub corpus_format: CorpusFormat,
+    /// The normalization applied to the corpus before extraction.
+    pub normalizer: Normalizer,
+    /// The feature templates to extract.
+    pub templates: FeatureTemplates,
+    /// How Latin letters are divided into character types.
+    pub latin_classes: LatinClasses,
+    /// The output format.
+    pub format: FeatureFormat,
+    /// The output feature dictionary for [`FeatureFormat::Parquet`]. Defaults to the
+    /// features file with the `dict.parquet` extension. Ignored for text output.
+    pub dictionary_file: Option<&'a Path>,
+    /// The maximum number of threads to extract text features on, or 0 for one per
+    /// available core.
+    pub num_threads: usize,
+}
+
+#[cfg(feature = "train")]
+impl Default for ExtractOptions<'_> {
+    fn default() -> Self {
+        ExtractOptions {
+            language: Language::default(),
+            corpus_format: CorpusFormat::default(),
+            normalizer: Normalizer::default(),
+            templates: FeatureTemplates::default(),
+            latin_classes: LatinClasses::default(),
+            format: FeatureFormat::Text,
+            dictionary_file: None,
+            num_threads: 0,
+        }
+    }
+}
+
+#[cfg(feature = "train")]
+/// Extracts features from a corpus file, as `litsea extract` does.
+///
+/// # Arguments
+/// * `corpus_file` - The corpus, in the format of [`ExtractOptions::corpus_format`].
+/// * `features_file` - The output features file.
+/// * `options` - The language, corpus format, normalization, feature templates and
+///   output format.
+///
+/// # Returns
+/// The path of the feature dictionary if one was written.
+///
+/// # Errors
+/// Returns an error if the corpus cannot be read or the output cannot be written.
+pub fn extract(
+    corpus_file: &Path,
+    features_file: &Path,
+    options: &ExtractOptions<'_>,
+) -> crate::error::Result<Option<PathBuf>> {
+    let mut extractor = Extractor::new(options.language);
+    extractor.set_corpus_format(options.corpus_format);
+    extractor.set_normalizer(options.normalizer);
+    extractor.set_feature_templates(options.templates);
+    extractor.set_latin_classes(options.latin_classes);
+    extractor.set_num_threads(options.num_threads);
+    match options.format {
+        FeatureFormat::Text => {
+            // Text output has no dictionary.
+            extractor.extract(corpus_file, features_file)?;
+            Ok(None)
+        }
+        #[cfg(feature = "parquet")]
+        FeatureFormat::Parquet => {
+            let dictionary_file = options
+                .dictionary_file
+                .map_or_else(|| features_file.with_extension("dict.parquet"), Path::to_path_buf);
+            extractor.extract_parquet(corpus_file, features_file, &dictionary_file)?;
+            Ok(Some(dictionary_file))
+        }
+    }
+}
+
+#[cfg(feature = "train")]
+/// Options of [`train`], with the defaults of `litsea train`.
+#[derive(Debug, Clone, Copy)]
+pub struct TrainOptions<'a> {
+    /// The threshold for stopping the training.
+    pub threshold: f64,
+    /// The maximum number of iterations.
+    pub num_iterations: usize,
+    /// The weak learners to combine: discrete or Real AdaBoost.
+    pub algorithm: Algorithm,
+    /// How instances are reweighted during training.
+    pub boosting: Boosting,
+    /// A model to resume training from (file path or URL).
+    pub load_model_uri: Option<&'a str>,
+    /// Metadata entries to write to the model, e.g. an embedded pipeline configuration.
+    pub metadata: &'a [(String, String)],
+    /// A development features file held out from training, to monitor overfitting.
+    pub dev_features_file: Option<&'a Path>,
+    /// With a development set, the number of growths of the train/dev accuracy gap that
+    /// signal overfitting.
+    pub overfitting_patience: Option<usize>,
+    /// Whether to stop training when overfitting is detected.
+    pub stop_on_overfitting: bool,
+    /// With a development set, stop when its F1 score has not improved for this many
+    /// iterations and keep the model of the best iteration.
+    pub early_stopping_patience: Option<usize>,
+    /// The number of instances sampled per iteration in mini-batch training, see
+    /// [`AdaBoost::set_mini_batch`](crate::adaboost::AdaBoost::set_mini_batch).
+    pub batch_size: Option<usize>,
+    /// With a batch size, the number of iterations between iterations on all instances.
+    pub correction_interval: usize,
+    /// The maximum number of threads to train on, 0 for one per available core.
+    pub num_threads: usize,
+    /// A file to keep the training instances in, memory-mapped, instead of in memory.
+    #[cfg(feature = "mmap")]
+    pub instance_file: Option<&'a Path>,
+    /// Whether the features file has a weight column after the label, see
+    /// [`Trainer::with_weights`].
+    pub weighted: bool,
+    /// The number of folds of a cross-validation run before the model is trained, see
+    /// [`Trainer::cross_validate`].
+    pub cross_validation_folds: Option<usize>,
+    /// Drop the features whose weight is smaller than this in absolute value from the
+    /// trained model, see [`Trainer::set_prune_below`].
+    pub prune_below: Option<f64>,
+    /// Keep only this many features with the largest weights in the trained model.
+    pub max_features: Option<usize>,
+    /// Quantize the weights of the trained model, see [`Trainer::set_quantization`].
+    pub quantize: Option<Quantization>,
+    /// Train this many models on bootstrap samples of the features and average them, see
+    /// [`Trainer::set_ensemble`].
+    pub ensemble: Option<usize>,
+    /// The seed of the bootstrap sampling of an ensemble.
+    pub seed: u64,
+    /// The handler the training reports its progress to after every iteration.
+    pub progress: Option<&'a Arc<dyn ProgressHandler>>,
+    /// The number of iterations between the training accuracies reported to `progress`,
+    /// 0 for none.
+    pub accuracy_interval: usize,
+}
+
+#[cfg(feature = "train")]
+impl Default for TrainOptions<'_> {
+    fn default() -> Self {
+        TrainOptions {
+            threshold: 0.01,
+            num_iterations: 100,
+            algorithm: Algorithm::default(),
+            boosting: Boosting::default(),
+            load_model_uri: None,
+            metadata: &[],
+            dev_features_file: None,
+            overfitting_patience: None,
+            stop_on_overfitting: false,
+            early_stopping_patience: None,
+            batch_size: None,
+            correction_interval: 10,
+            num_threads: 0,
+            #[cfg(feature = "mmap")]
+            instance_file: None,
+            weighted: false,
+            cross_validation_folds: None,
+            prune_below: None,
+            max_features: None,
+            quantize: None,
+            ensemble: None,
+            seed: 0,
+            progress: None,
+            accuracy_interval: 0,
+        }
+    }
+}
+
+#[cfg(feature = "train")]
+/// The result of [`train`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct TrainReport {
+    /// The metrics of the trained model on the training data.
+    pub metrics: Metrics,
+    /// The metrics on the development set, if one was given.
+    pub dev_metrics: Option<Metrics>,
+    /// The training and development accuracy after each iteration, empty without a
+    /// development set.
+    pub log: Vec<TrainingRecord>,
+    /// The result of the cross-validation, if one was requested.
+    pub cross_validation: Option<CrossValidation>,
+    /// With early stopping, the iteration of the saved model.
+    pub best_iteration: Option<usize>,
+    /// The result of pruning the model, if it was pruned.
+    pub pruning: Option<PruneReport>,
+    /// The result of quantizing the model, if it was quantized.
+    pub quantization: Option<QuantizationReport>,
+    /// The out-of-bag metrics of the models of an ensemble, if one was trained.
+    pub ensemble: Option<EnsembleReport>,
+}
+
+#[cfg(feature = "train")]
+impl TrainReport {
+    /// Returns the first iteration at which overfitting was detected.
+    #[must_use]
+    pub fn overfitting(&self) -> Option<&TrainingRecord> {
+        self.log.iter().find(|record| record.overfitting)
+    }
+}
+
+#[cfg(feature = "train")]
+/// Trains a model from a features file and saves it, as `litsea train` does.
+///
+/// # Arguments
+/// * `features_file` - The features file written by [`extract`].
+/// * `model_file` - The output model file.
+/// * `options` - The training parameters and the model to resume from.
+/// * `control` - Stops, pauses or resumes the training; after a stop the model trained so far
+///   is saved.
+///
+/// # Returns
+/// The metrics of the trained model on the training data and, with a development set,
+/// on the development set along with the accuracies after each iteration.
+///
+/// # Errors
+/// Returns an error if the features or the model cannot be read, or the model cannot be saved.
+pub async fn train(
+    features_file: &Path,
+    model_file: &Path,
+    options: &TrainOptions<'_>,
+    control: impl Into<TrainControl>,
+) -> crate::error::Result<TrainReport> {
+    let control = control.into();
+    #[cfg(feature = "mmap")]
+    let mut trainer = match options.instance_file {
+        Some(_) if options.weighted => {
+            return Err(crate::error::LitseaError::InvalidInput(
+                "Instance files cannot hold the weights of a weighted features file".to_string(),
+            ));
+        }
+        Some(instance_file) => Trainer::with_instance_file(
+            options.threshold,
+            options.num_iterations,
+            features_file,
+            instance_file,
+        )?,
+        None if options.weighted => {
+            Trainer::with_weights(options.threshold, options.num_iterations, features_file)?
+        }
+        None => Trainer::new(options.threshold, options.num_iterations, features_file)?,
+    };
+    #[cfg(not(feature = "mmap"))]
+    let mut trainer = if options.weighted {
+        Trainer::with_weights(options.threshold, options.num_iterations, features_file)?
+    } else {
+        Trainer::new(options.threshold, options.num_iterations, features_file)?
+    };
+    trainer.set_algorithm(options.algorithm);
+    trainer.set_boosting(options.boosting)?;
+    if let Some(model_uri) = options.load_model_uri {
+        trainer.load_model(model_uri).await?;
+    }
+    for (key, value) in options.metadata {
+        trainer.set_metadata(key, value)?;
+    }
+    if let Some(dev_features_file) = options.dev_features_file {
+        trainer.set_dev_set(dev_features_file)?;
+    }
+    if let Some(patience) = options.overfitting_patience {
+        trainer.set_overfitting_patience(patience, options.stop_on_overfitting)?;
+    }
+    if let Some(patience) = options.early_stopping_patience {
+        trainer.set_early_stopping(patience)?;
+    }
+    if let Some(batch_size) = options.batch_size {
+        trainer.set_mini_batch(batch_size, options.correction_interval)?;
+    }
+    trainer.set_num_threads(options.num_threads);
+    if let Some(threshold) = options.prune_below {
+        trainer.set_prune_below(threshold)?;
+    }
+    if let Some(max_features) = options.max_features {
+        trainer.set_max_features(max_features);
+    }
+    trainer.set_quantization(options.quantize);
+    if let Some(size) = options.ensemble {
+        trainer.set_ensemble(size, options.seed)?;
+    }
+    trainer.set_progress_handler(options.progress.cloned());
+    trainer.set_accuracy_interval(options.accuracy_interval);
+    let cross_validation = match options.cross_validation_folds {
+        Some(num_folds) => Some(trainer.cross_validate(num_folds, control.clone())?),
+        None => None,
+    };
+    let metrics = trainer.train(control, model_file)?;
+    Ok(TrainReport {
+        metrics,
+        dev_metrics: trainer.dev_metrics(),
+        log: trainer.training_log().to_vec(),
+        cross_validation,
+        best_iteration: trainer.best_iteration(),
+        pruning: trainer.prune_report().cloned(),
+        quantization: trainer.quantization_report().cloned(),
+        ensemble: trainer.ensemble_report().cloned(),
+    })
+}
+
+#[cfg(feature = "train")]
+/// Writes the training log as JSON Lines, one object per iteration with the
+/// `iteration`, `train_accuracy`, `dev_accuracy`, `gap` and `overfitting` fields.
+///
+/// # Arguments
+/// * `writer` - The destination, e.g. a log file.
+/// * `log` - The records of [`TrainReport::log`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_training_log<W: Write + ?Sized>(
+    writer: &mut W,
+    log: &[TrainingRecord],
+) -> io::Result<()> {
+    for record in log {
+        writeln!(
+            writer,
+            "{{\"iteration\":{},\"train_accuracy\":{},\"dev_accuracy\":{},\"dev_f1\":{},\"gap\":{},\"overfitting\":{}}}",
+            record.iteration,
+            record.train_accuracy,
+            record.dev_accuracy,
+            record.dev_f1,
+            record.gap(),
+            record.overfitting
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "train")]
+/// Writes training metrics in the format printed by `litsea train`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `metrics` - The metrics returned by [`train`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_metrics<W: Write + ?Sized>(writer: &mut W, metrics: &Metrics) -> io::Result<()> {
+    writeln!(writer, "Result Metrics:")?;
+    writeln!(
+        writer,
+        "  Accuracy: {:.2}% ( {} / {} )",
+        metrics.accuracy,
+        metrics.true_positives + metrics.true_negatives,
+        metrics.num_instances
+    )?;
+    writeln!(
+        writer,
+        "  Precision: {:.2}% ( {} / {} )",
+        metrics.precision,
+        metrics.true_positives,
+        metrics.true_positives + metrics.false_positives
+    )?;
+    writeln!(
+        writer,
+        "  Recall: {:.2}% ( {} / {} )",
+        metrics.recall,
+        metrics.true_positives,
+        metrics.true_positives + metrics.false_negatives
+    )?;
+    writeln!(
+        writer,
+        "  Confusion Matrix:\n    True Positives: {}\n    False Positives: {}\n    False Negatives: {}\n    True Negatives: {}",
+        metrics.true_positives,
+        metrics.false_positives,
+        metrics.false_negatives,
+        metrics.true_negatives
+    )
+}
+
+#[cfg(feature = "train")]
+/// Writes the result of a cross-validation in the format printed by `litsea train --cv`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `cv` - The result of [`Trainer::cross_validate`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_cross_validation<W: Write + ?Sized>(
+    writer: &mut W,
+    cv: &CrossValidation,
+) -> io::Result<()> {
+    writeln!(writer, "Cross-Validation ({} folds):", cv.folds.len())?;
+    for (i, metrics) in cv.folds.iter().enumerate() {
+        writeln!(
+            writer,
+            "  Fold {}: Precision {:.2}% Recall {:.2}% ( {} instances )",
+            i + 1,
+            metrics.precision,
+            metrics.recall,
+            metrics.num_instances
+        )?;
+    }
+    for (name, statistic) in [("Precision", cv.precision), ("Recall", cv.recall), ("F1", cv.f1)] {
+        writeln!(writer, "  {}: {:.2}% ± {:.2}", name, statistic.mean, statistic.stddev)?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "train")]
+/// Writes the out-of-bag metrics of an ensemble in the format printed by
+/// `litsea train --ensemble`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `report` - The result of [`Trainer::ensemble_report`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_ensemble<W: Write + ?Sized>(
+    writer: &mut W,
+    report: &EnsembleReport,
+) -> io::Result<()> {
+    writeln!(writer, "Ensemble ({} models, out-of-bag):", report.out_of_bag.len())?;
+    for (i, metrics) in report.out_of_bag.iter().enumerate() {
+        writeln!(
+            writer,
+            "  Model {}: Precision {:.2}% Recall {:.2}% ( {} instances )",
+            i + 1,
+            metrics.precision,
+            metrics.recall,
+            metrics.num_instances
+        )?;
+    }
+    writeln!(writer, "  F1: {:.2}% ± {:.2}", report.f1.mean, report.f1.stddev)
+}
+
+#[cfg(feature = "train")]
+/// Writes the result of pruning in the format printed by `litsea train --prune-below` and
+/// `--max-features`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `report` - The result of [`Trainer::prune_report`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_pruning<W: Write + ?Sized>(writer: &mut W, report: &PruneReport) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Pruning: kept {} of {} features",
+        report.features_after, report.features_before
+    )?;
+    writeln!(
+        writer,
+        "  Accuracy: {:.2}% -> {:.2}%",
+        report.metrics_before.accuracy, report.metrics_after.accuracy
+    )?;
+    if let (Some(before), Some(after)) = (&report.dev_metrics_before, &report.dev_metrics_after) {
+        writeln!(
+            writer,
+            "  Development Accuracy: {:.2}% -> {:.2}%",
+            before.accuracy, after.accuracy
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "train")]
+/// Writes the result of quantization in the format printed by `litsea train --quantize`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `report` - The result of [`Trainer::quantization_report`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_quantization<W: Write + ?Sized>(
+    writer: &mut W,
+    report: &QuantizationReport,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Quantization ({}): max weight error {:.6}, mean {:.6}, {} features rounded to 0",
+        report.quantization,
+        report.error.max_error,
+        report.error.mean_error,
+        report.error.features_zeroed
+    )?;
+    writeln!(
+        writer,
+        "  Accuracy: {:.2}% -> {:.2}%",
+        report.metrics_before.accuracy, report.metrics_after.accuracy
+    )?;
+    if let (Some(before), Some(after)) = (&report.dev_metrics_before, &report.dev_metrics_after) {
+        writeln!(
+            writer,
+            "  Development Accuracy: {:.2}% -> {:.2}%",
+            before.accuracy, after.accuracy
+        )?;
+    }
+    Ok(())
+}
+
+/// Output format of [`segment_lines`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Words separated by spaces, one sentence per line.
+    #[default]
+    Plain,
+    /// `surface/reading` pairs separated by spaces, one sentence per line.
+    Tagged,
+    /// One `surface<TAB>tags,lemma,reading` line per word, each sentence followed by `EOS`,
+    /// as MeCab does.
+    Mecab,
+    /// A JSON array of one object per sentence, with the sentence, its words and their
+    /// character offsets, and the scores of the boundary decisions.
+    Json,
+    /// The objects of [`OutputFormat::Json`], one per line (JSON Lines).
+    Jsonl,
+    /// One `surface<TAB>start<TAB>end<TAB>score` line per word, with character offsets and
+    /// the score of the boundary before the word, each sentence followed by an empty line.
+    Tsv,
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputFormat::Plain => write!(f, "plain"),
+            OutputFormat::Tagged => write!(f, "tagged"),
+            OutputFormat::Mecab => write!(f, "mecab"),
+            OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Jsonl => write!(f, "jsonl"),
+            OutputFormat::Tsv => write!(f, "tsv"),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" | "text" => Ok(OutputFormat::Plain),
+            "tagged" => Ok(OutputFormat::Tagged),
+            "mecab" => Ok(OutputFormat::Mecab),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" | "ndjson" => Ok(OutputFormat::Jsonl),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!(
+                "Unsupported output format: '{}'. Supported: plain, tagged, mecab, json, jsonl, tsv",
+                s
+            )),
+        }
+    }
+}
+
+/// Options of [`segment_lines`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SegmentOptions<'a> {
+    /// How the segmented sentences are written.
+    pub format: OutputFormat,
+    /// Whether to flush the writer after every line.
+    pub line_buffered: bool,
+    /// Dictionary used to attach readings to the words. Without one, the tagged and
+    /// MeCab formats use the character-level fallback of [`ReadingDictionary`] only, and
+    /// the JSON formats have no readings.
+    pub readings: Option<&'a ReadingDictionary>,
+    /// Lexicon used to attach lemmas and tags to the words, shown in the MeCab format.
+    pub lemmas: Option<&'a LemmaDictionary>,
+    /// Splitter applied to the words before annotation, e.g. for search indexing.
+    pub compounds: Option<&'a CompoundSplitter>,
+    /// Words dropped from the output, after annotation so lemmas can match.
+    pub stopwords: Option<&'a StopWords>,
+    /// Word frequencies used to pick the best of the N-best segmentations of each line.
+    pub reranker: Option<&'a UnigramReranker>,
+    /// Lexicon segmentation used instead of the segmenter, and instead of the reranker
+    /// if both are given.
+    pub lattice: Option<&'a LatticeSegmenter>,
+    /// Normalization applied to each line before segmentation.
+    pub normalizer: Option<&'a Normalizer>,
+    /// Write up to this many segmentations of each line, from
+    /// [`Segmenter::segment_nbest`], instead of the best one. The reranker and the
+    /// lattice are not used.
+    pub nbest: Option<usize>,
+}
+
+/// Segments text line by line, as `litsea segment` does.
+///
+/// Each non-empty line is trimmed, segmented, and written in the selected
+/// [`OutputFormat`]. Empty lines are skipped.
+///
+/// With [`SegmentOptions::nbest`], every candidate of a line is written in turn. In the
+/// plain and tagged formats each is preceded by its rank and its total margin
+/// ([`Segmentation::score`](crate::segmenter::Segmentation::score)), separated by tabs;
+/// the JSON formats write them as the `rank` and `score` fields of each object; in the
+/// MeCab and TSV formats the candidates simply follow each other, as MeCab writes them.
+///
+/// The JSON objects hold the `sentence`, its `tokens` with their `surface`, `start` and
+/// `end` offsets in characters and the `score` of the boundary before them, and the
+/// `boundary_scores` of [`Segmenter::boundary_scores`], one for each pair of adjacent
+/// characters.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to use.
+/// * `reader` - The input text.
+/// * `writer` - The destination of the segmented lines.
+/// * `options` - The output format and annotations.
+///
+/// # Errors
+/// Returns an error if reading or writing fails.
+pub fn segment_lines<R: BufRead, W: Write + ?Sized>(
+    segmenter: &Segmenter,
+    reader: R,
+    writer: &mut W,
+    options: &SegmentOptions,
+) -> io::Result<()> {
+    let fallback = ReadingDictionary::new();
+    let readings = options.readings.unwrap_or(&fallback);
+    let attach_readings = match options.format {
+        OutputFormat::Plain => false,
+        OutputFormat::Tagged | OutputFormat::Mecab => true,
+        OutputFormat::Json | OutputFormat::Jsonl | OutputFormat::Tsv => options.readings.is_some(),
+    };
+    let mut num_written = 0;
+    if options.format == OutputFormat::Json {
+        write!(writer, "[")?;
+    }
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let line = match options.normalizer {
+            Some(normalizer) => normalizer.normalize(line),
+            None => Cow::Borrowed(line),
+        };
+        let annotate = |mut tokens: Vec<Token>| {
+            if let Some(compounds) = options.compounds {
+                compounds.apply(&mut tokens);
+            }
+            if attach_readings {
+                readings.apply(&mut tokens);
+            }
+            if let Some(lemmas) = options.lemmas {
+                lemmas.apply(&mut tokens);
+            }
+            if let Some(stopwords) = options.stopwords {
+                stopwords.apply(&mut tokens);
+            }
+            tokens
+        };
+        let boundary_scores = match options.format {
+            OutputFormat::Json | OutputFormat::Jsonl => segmenter.boundary_scores(&line),
+            _ => Vec::new(),
+        };
+        let mut sentence = Sentence {
+            text: &line,
+            boundary_scores: &boundary_scores,
+            candidate: None,
+        };
+        if let Some(n) = options.nbest {
+            for (rank, candidate) in segmenter.segment_nbest(&line, n).into_iter().enumerate() {
+                sentence.candidate = Some((rank + 1, candidate.score));
+                let mut tokens: Vec<Token> = candidate.words.into_iter().map(Token::from).collect();
+                set_char_offsets(&mut tokens, &line);
+                sentence.write(writer, &annotate(tokens), options.format, num_written)?;
+                num_written += 1;
+            }
+        } else {
+            let tokens = match (options.lattice, options.reranker) {
+                (Some(lattice), _) => lattice.tokenize(segmenter, &line),
+                (None, Some(reranker)) => reranker.tokenize(segmenter, &line),
+                (None, None) => segmenter.tokenize(&line),
+            };
+            sentence.write(writer, &annotate(tokens), options.format, num_written)?;
+            num_written += 1;
+        }
+        if options.line_buffered {
+            writer.flush()?;
+        }
+    }
+    if options.format == OutputFormat::Json {
+        writeln!(writer, "\n]")?;
+    }
+    writer.flush()
+}
+
+/// A sentence written by [`segment_lines`], with what the structured formats add to its
+/// tokens.
+struct Sentence<'a> {
+    text: &'a str,
+    boundary_scores: &'a [f64],
+    /// The rank and total margin of an N-best candidate.
+    candidate: Option<(usize, f64)>,
+}
+
+impl Sentence<'_> {
+    /// Writes the sentence, after `index` sentences or candidates written before it.
+    fn write<W: Write + ?Sized>(
+        &self,
+        writer: &mut W,
+        tokens: &[Token],
+        format: OutputFormat,
+        index: usize,
+    ) -> io::Result<()> {
+        match format {
+            OutputFormat::Json | OutputFormat::Jsonl => {
+                if format == OutputFormat::Json {
+                    writeln!(writer, "{}", if index == 0 { "" } else { "," })?;
+                }
+                write!(writer, "{{\"sentence\":")?;
+                write_json_string(writer, self.text)?;
+                if let Some((rank, score)) = self.candidate {
+                    write!(writer, ",\"rank\":{},\"score\":{}", rank, score)?;
+                }
+                write!(writer, ",")?;
+                write_json_tokens(writer, tokens)?;
+                let scores: Vec<String> =
+                    self.boundary_scores.iter().map(|score| score.to_string()).collect();
+                write!(writer, ",\"boundary_scores\":[{}]}}", scores.join(","))?;
+                if format == OutputFormat::Jsonl {
+                    writeln!(writer)?;
+                }
+                Ok(())
+            }
+            OutputFormat::Plain | OutputFormat::Tagged => {
+                if let Some((rank, score)) = self.candidate {
+                    write!(writer, "{}\t{:.4}\t", rank, score)?;
+                }
+                write_tokens(writer, tokens, format)
+            }
+            OutputFormat::Mecab | OutputFormat::Tsv => write_tokens(writer, tokens, format),
+        }
+    }
+}
+
+/// Writes the tokens of one sentence in the given format.
+///
+/// # Arguments
+/// * `writer` - The destination of the output.
+/// * `tokens` - The tokens of the sentence.
+/// * `format` - The output format. Missing readings, lemmas and tags are written as `*`
+///   in the tagged and MeCab formats. The JSON formats write an object with only the
+///   `tokens` of the sentence, followed by a line break; [`segment_lines`] adds the
+///   sentence and its boundary scores.
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_tokens<W: Write + ?Sized>(
+    writer: &mut W,
+    tokens: &[Token],
+    format: OutputFormat,
+) -> io::Result<()> {
+    let reading = |token: &Token| token.reading.clone().unwrap_or_else(|| "*".to_string());
+    match format {
+        OutputFormat::Plain => {
+            let words: Vec<&str> = tokens.iter().map(|t| t.surface.as_str()).collect();
+            writeln!(writer, "{}", words.join(" "))
+        }
+        OutputFormat::Tagged => {
+            let words: Vec<String> =
+                tokens.iter().map(|t| format!("{}/{}", t.surface, reading(t))).collect();
+            writeln!(writer, "{}", words.join(" "))
+        }
+        OutputFormat::Mecab => {
+            for token in tokens {
+                let tags =
+                    if token.tags.is_empty() { "*".to_string() } else { token.tags.join(",") };
+                let lemma = token.lemma.as_deref().unwrap_or("*");
+                writeln!(writer, "{}\t{},{},{}", token.surface, tags, lemma, reading(token))?;
+            }
+            writeln!(writer, "EOS")
+        }
+        OutputFormat::Json | OutputFormat::Jsonl => {
+            write!(writer, "{{")?;
+            write_json_tokens(writer, tokens)?;
+            writeln!(writer, "}}")
+        }
+        OutputFormat::Tsv => {
+            for token in tokens {
+                let surface = token.surface.replace('\\', "\\\\").replace('\t', "\\t");
+                let score = token.score.map(|score| score.to_string()).unwrap_or_default();
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}",
+                    surface, token.char_start, token.char_end, score
+                )?;
+            }
+            writeln!(writer)
+        }
+    }
+}
+
+/// Writes the `"tokens"` member of a JSON object: the words with their character
+/// offsets, the score of the boundary before them, and the annotations they have.
+fn write_json_tokens<W: Write + ?Sized>(writer: &mut W, tokens: &[Token]) -> io::Result<()> {
+    write!(writer, "\"tokens\":[")?;
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "{{\"surface\":")?;
+        write_json_string(writer, &token.surface)?;
+        write!(writer, ",\"start\":{},\"end\":{},\"score\":", token.char_start, token.char_end)?;
+        match token.score {
+            Some(score) => write!(writer, "{}", score)?,
+            None => write!(writer, "null")?,
+        }
+        if let Some(reading) = &token.reading {
+            write!(writer, ",\"reading\":")?;
+            write_json_string(writer, reading)?;
+        }
+        if let Some(lemma) = &token.lemma {
+            write!(writer, ",\"lemma\":")?;
+            write_json_string(writer, lemma)?;
+        }
+        if !token.tags.is_empty() {
+            write!(writer, ",\"tags\":[")?;
+            for (j, tag) in token.tags.iter().enumerate() {
+                if j > 0 {
+                    write!(writer, ",")?;
+                }
+                write_json_string(writer, tag)?;
+            }
+            write!(writer, "]")?;
+        }
+        write!(writer, "}}")?;
+    }
+    write!(writer, "]")
+}
+
+/// Writes a string as a JSON string literal.
+fn write_json_string<W: Write + ?Sized>(writer: &mut W, s: &str) -> io::Result<()> {
+    write!(writer, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(writer, "\\\"")?,
+            '\\' => write!(writer, "\\\\")?,
+            '\n' => write!(writer, "\\n")?,
+            '\r' => write!(writer, "\\r")?,
+            '\t' => write!(writer, "\\t")?,
+            c if c < ' ' => write!(writer, "\\u{:04x}", c as u32)?,
+            c => write!(writer, "{}", c)?,
+        }
+    }
+    write!(writer, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "train")]
+    use std::fs;
+    #[cfg(feature = "train")]
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[cfg(feature = "train")]
+    use tempfile::tempdir;
+
+    #[cfg(not(feature = "train"))]
+    use crate::language::Language;
+    #[cfg(feature = "train")]
+    use crate::progress::TrainingProgress;
+    use crate::test_util::rwcp_segmenter;
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_feature_format_from_str() {
+        assert_eq!("TEXT".parse::<FeatureFormat>(), Ok(FeatureFormat::Text));
+        #[cfg(feature = "parquet")]
+        assert_eq!("parquet".parse::<FeatureFormat>(), Ok(FeatureFormat::Parquet));
+        assert!("csv".parse::<FeatureFormat>().is_err());
+    }
+
+    #[cfg(feature = "train")]
+    #[tokio::test]
+    async fn test_extract_and_train() {
+        let dir = tempdir().unwrap();
+        let corpus_file = dir.path().join("corpus.txt");
+        let features_file = dir.path().join("features.txt");
+        let model_file = dir.path().join("model.txt");
+        fs::write(&corpus_file, "これ は テスト です 。\nテスト です\n").unwrap();
+
+        let dictionary = extract(&corpus_file, &features_file, &ExtractOptions::default()).unwrap();
+        assert!(dictionary.is_none());
+        assert!(fs::metadata(&features_file).unwrap().len() > 0);
+
+        let control = TrainControl::new();
+        let iterations = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&iterations);
+        let progress: Arc<dyn ProgressHandler> = Arc::new(move |progress: &TrainingProgress| {
+            assert_eq!(progress.num_iterations, 10);
+            assert!(progress.record.is_none());
+            assert!((0.0..=0.5).contains(&progress.error_rate), "{}", progress.error_rate);
+            assert_eq!(progress.train_accuracy.is_some(), progress.iteration.is_multiple_of(5));
+            counter.store(progress.iteration, Ordering::Relaxed);
+        });
+        let options = TrainOptions {
+            num_iterations: 10,
+            progress: Some(&progress),
+            accuracy_interval: 5,
+            ..TrainOptions::default()
+        };
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        let metrics = result.metrics;
+        assert!(iterations.load(Ordering::Relaxed) > 0);
+        assert!(metrics.num_instances > 0);
+        assert!(model_file.exists());
+        assert!(result.dev_metrics.is_none());
+        assert!(result.log.is_empty());
+
+        let mut report = Vec::new();
+        write_metrics(&mut report, &metrics).unwrap();
+        assert!(String::from_utf8(report).unwrap().starts_with("Result Metrics:\n  Accuracy: "));
+
+        // The corpus doubles as a development set.
+        let options = TrainOptions {
+            num_iterations: 10,
+            dev_features_file: Some(&features_file),
+            overfitting_patience: Some(3),
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        let dev_metrics = result.dev_metrics.as_ref().unwrap();
+        assert_eq!(dev_metrics.accuracy, result.metrics.accuracy);
+        assert!(!result.log.is_empty());
+        // Without a gap, nothing is flagged.
+        assert!(result.overfitting().is_none());
+        assert!(result.best_iteration.is_none());
+
+        let mut log = Vec::new();
+        write_training_log(&mut log, &result.log).unwrap();
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(log.lines().count(), result.log.len());
+        assert!(log.starts_with("{\"iteration\":1,\"train_accuracy\":"));
+        assert!(log.contains("\"gap\":0,\"overfitting\":false}"));
+
+        #[cfg(feature = "config")]
+        {
+            let json: serde_json::Value = serde_json::to_value(&result).unwrap();
+            assert_eq!(json["metrics"]["num_instances"], result.metrics.num_instances);
+            assert_eq!(json["dev_metrics"]["accuracy"], dev_metrics.accuracy);
+            assert_eq!(json["log"][0]["iteration"], 1);
+            assert_eq!(json["log"].as_array().unwrap().len(), result.log.len());
+        }
+
+        let options = TrainOptions {
+            num_iterations: 10,
+            dev_features_file: Some(&features_file),
+            early_stopping_patience: Some(100),
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        assert!(result.best_iteration.unwrap() <= result.log.len());
+
+        let options = TrainOptions {
+            num_iterations: 10,
+            cross_validation_folds: Some(2),
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        let cv = result.cross_validation.unwrap();
+        assert_eq!(cv.folds.len(), 2);
+        let mut report = Vec::new();
+        write_cross_validation(&mut report, &cv).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("Cross-Validation (2 folds):\n  Fold 1: Precision "));
+        assert!(report.contains("\n  F1: "));
+
+        let options = TrainOptions {
+            num_iterations: 10,
+            ensemble: Some(2),
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        let ensemble = result.ensemble.unwrap();
+        assert_eq!(ensemble.out_of_bag.len(), 2);
+        let mut report = Vec::new();
+        write_ensemble(&mut report, &ensemble).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with("Ensemble (2 models, out-of-bag):\n  Model 1: Precision "));
+        assert!(report.contains("\n  F1: "));
+
+        let options = TrainOptions {
+            num_iterations: 10,
+            dev_features_file: Some(&features_file),
+            max_features: Some(1),
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&features_file, &model_file, &options, control).await.unwrap();
+        let pruning = result.pruning.unwrap();
+        assert_eq!(pruning.features_after, 1);
+        assert_eq!(pruning.metrics_after.accuracy, result.metrics.accuracy);
+        let mut report = Vec::new();
+        write_pruning(&mut report, &pruning).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(report.starts_with(&format!(
+            "Pruning: kept 1 of {} features\n  Accuracy: ",
+            pruning.features_before
+        )));
+        assert!(report.contains("\n  Development Accuracy: "));
+    }
+
+    #[cfg(feature = "train")]
+    #[tokio::test]
+    async fn test_train_weighted() {
+        let dir = tempdir().unwrap();
+        let corpus_file = dir.path().join("corpus.txt");
+        let features_file = dir.path().join("features.txt");
+        let counted_file = dir.path().join("features.counted");
+        let model_file = dir.path().join("model.txt");
+        fs::write(&corpus_file, "これ は テスト です 。\n".repeat(3) + "テスト です\n").unwrap();
+        extract(&corpus_file, &features_file, &ExtractOptions::default()).unwrap();
+        let sort_options = crate::feature_file::SortOptions {
+            dedup: true,
+            ..Default::default()
+        };
+        let stats =
+            crate::feature_file::sort_features(&[&features_file], &counted_file, &sort_options)
+                .unwrap();
+        assert!(stats.lines < stats.instances);
+
+        let options = TrainOptions {
+            num_iterations: 10,
+            weighted: true,
+            ..TrainOptions::default()
+        };
+        let control = TrainControl::new();
+        let result = train(&counted_file, &model_file, &options, control).await.unwrap();
+        assert_eq!(result.metrics.num_instances, stats.lines as usize);
+        assert!(result.metrics.accuracy > 90.0);
+    }
+
+    fn segment(input: &str, options: &SegmentOptions) -> String {
+        let mut out = Vec::new();
+        segment_lines(&rwcp_segmenter(), input.as_bytes(), &mut out, options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_segment_lines() {
+        let options = SegmentOptions {
+            line_buffered: true,
+            ..SegmentOptions::default()
+        };
+        assert_eq!(
+            segment("これはテストです。\n\n  テスト  \n", &options),
+            "これ は テスト です 。\nテスト\n"
+        );
+    }
+
+    #[test]
+    fn test_segment_lines_json() {
+        let options = SegmentOptions {
+            format: OutputFormat::Jsonl,
+            ..SegmentOptions::default()
+        };
+        let out = segment("テスト\n\"A\"です\n", &options);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(
+            "{\"sentence\":\"テスト\",\"tokens\":[{\"surface\":\"テスト\",\"start\":0,\"end\":3,\"score\":null}],\"boundary_scores\":["
+        ));
+        assert!(lines[1].starts_with("{\"sentence\":\"\\\"A\\\"です\","));
+
+        let options = SegmentOptions {
+            format: OutputFormat::Json,
+            ..SegmentOptions::default()
+        };
+        let out = segment("テスト\nテスト\n", &options);
+        assert!(out.starts_with("[\n{\"sentence\":\"テスト\","));
+        assert!(out.contains("]},\n{\"sentence\""));
+        assert!(out.ends_with("]}\n]\n"));
+        assert_eq!(segment("", &options), "[\n]\n");
+
+        #[cfg(feature = "config")]
+        {
+            let options = SegmentOptions {
+                format: OutputFormat::Json,
+                nbest: Some(2),
+                ..SegmentOptions::default()
+            };
+            let json: serde_json::Value =
+                serde_json::from_str(&segment("これはテストです。\n", &options)).unwrap();
+            let candidates = json.as_array().unwrap();
+            assert_eq!(candidates.len(), 2);
+            assert_eq!(candidates[1]["rank"], 2);
+            let tokens = candidates[0]["tokens"].as_array().unwrap();
+            assert_eq!(tokens[1]["surface"], "は");
+            assert_eq!(tokens[1]["start"], 2);
+            assert!(tokens[1]["score"].as_f64().unwrap() >= 0.0);
+            // One score per pair of adjacent characters.
+            assert_eq!(candidates[0]["boundary_scores"].as_array().unwrap().len(), 8);
+        }
+    }
+
+    #[test]
+    fn test_segment_lines_tsv() {
+        let options = SegmentOptions {
+            format: OutputFormat::Tsv,
+            ..SegmentOptions::default()
+        };
+        let out = segment("これはテストです。\n", &options);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "これ\t0\t2\t");
+        assert!(lines[1].starts_with("は\t2\t3\t0."));
+        assert_eq!(lines.last(), Some(&""));
+    }
+
+    #[test]
+    fn test_segment_lines_nbest() {
+        let options = SegmentOptions {
+            nbest: Some(2),
+            ..SegmentOptions::default()
+        };
+        let out = segment("これはテストです。\n", &options);
+        let lines: Vec<Vec<&str>> = out.lines().map(|l| l.split('\t').collect()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0][0], "1");
+        assert_eq!(lines[0][2], "これ は テスト です 。");
+        assert_eq!(lines[1][0], "2");
+        assert_ne!(lines[1][2], lines[0][2]);
+        let score = |line: &[&str]| line[1].parse::<f64>().unwrap();
+        assert!(score(&lines[0]) >= score(&lines[1]));
+
+        let mecab = SegmentOptions {
+            format: OutputFormat::Mecab,
+            nbest: Some(2),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("これはテスト", &mecab).matches("EOS\n").count(), 2);
+    }
+
+    #[test]
+    fn test_segment_lines_with_readings() {
+        let readings = ReadingDictionary::from_tsv("今日\tきょう\n".as_bytes()).unwrap();
+        let tagged = SegmentOptions {
+            format: OutputFormat::Tagged,
+            readings: Some(&readings),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(
+            segment("今日はテストです。", &tagged),
+            "今日/キョウ は/ハ テスト/テスト です/デス 。/。\n"
+        );
+
+        let mecab = SegmentOptions {
+            format: OutputFormat::Mecab,
+            ..SegmentOptions::default()
+        };
+        assert_eq!(
+            segment("これはテスト", &mecab),
+            "これ\t*,*,コレ\nは\t*,*,ハ\nテスト\t*,*,テスト\nEOS\n"
+        );
+    }
+
+    #[test]
+    fn test_segment_lines_with_lemmas() {
+        let lemmas = LemmaDictionary::from_tsv("です\tです\t助動詞\n".as_bytes()).unwrap();
+        let options = SegmentOptions {
+            format: OutputFormat::Mecab,
+            lemmas: Some(&lemmas),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(
+            segment("テストです", &options),
+            "テスト\t*,*,テスト\nです\t助動詞,です,デス\nEOS\n"
+        );
+    }
+
+    #[test]
+    fn test_segment_lines_with_compounds() {
+        let compounds =
+            CompoundSplitter::new(Language::Japanese).with_words(["自然", "言語", "処理"]);
+        let options = SegmentOptions {
+            compounds: Some(&compounds),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("自然言語処理", &SegmentOptions::default()), "自然言語処理\n");
+        assert_eq!(segment("自然言語処理", &options), "自然 言語 処理\n");
+    }
+
+    #[test]
+    fn test_segment_lines_with_stopwords() {
+        let stopwords = StopWords::japanese();
+        let options = SegmentOptions {
+            stopwords: Some(&stopwords),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("これはテストです。", &options), "テスト 。\n");
+    }
+
+    #[test]
+    fn test_segment_lines_with_reranker() {
+        let corpus = "自然 言語 処理 の 研究\n言語 処理\n";
+        let reranker = UnigramReranker::new(
+            crate::unigram::UnigramModel::from_corpus(corpus.as_bytes(), None).unwrap(),
+        );
+        let options = SegmentOptions {
+            reranker: Some(&reranker),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("自然言語処理", &SegmentOptions::default()), "自然言語処理\n");
+        assert_eq!(segment("自然言語処理", &options), "自然 言語 処理\n");
+    }
+
+    #[test]
+    fn test_segment_lines_with_lattice() {
+        let lattice =
+            LatticeSegmenter::new(crate::lattice::Lexicon::from_tsv("処理\n".as_bytes()).unwrap());
+        let options = SegmentOptions {
+            lattice: Some(&lattice),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("自然言語処理", &SegmentOptions::default()), "自然言語処理\n");
+        assert_eq!(segment("自然言語処理", &options), "自然言語 処理\n");
+    }
+
+    #[test]
+    fn test_segment_lines_with_normalizer() {
+        let normalizer = Normalizer {
+            fullwidth_ascii: true,
+            ..Normalizer::default()
+        };
+        let options = SegmentOptions {
+            normalizer: Some(&normalizer),
+            ..SegmentOptions::default()
+        };
+        assert_eq!(segment("ＲＷＣＰ", &options), "RWCP\n");
+    }
+
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!(
+            OutputFormat::Mecab.to_string().parse::<OutputFormat>(),
+            Ok(OutputFormat::Mecab)
+        );
+        assert_eq!("plain".parse::<OutputFormat>(), Ok(OutputFormat::Plain));
+        assert_eq!("MeCab".parse::<OutputFormat>(), Ok(OutputFormat::Mecab));
+        assert_eq!("jsonl".parse::<OutputFormat>(), Ok(OutputFormat::Jsonl));
+        assert_eq!(OutputFormat::Tsv.to_string().parse::<OutputFormat>(), Ok(OutputFormat::Tsv));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+}
@@ -0,0 +1,606 @@
+//! Segmentation differences between two models.
+//!
+//! Before rolling out a retrained model, it helps to know where it segments text
+//! differently from the model in production. [`compare`] segments a corpus with both
+//! models and reports every sentence where they disagree, with the disagreeing spans
+//! marked in both segmentations and the confidence of each model in its own choice
+//! (the columns are separated by tabs):
+//!
+//! ```text
+//! 1   left    0.509   [坊っ ちゃ ん]
+//! 1   right   0.815   [坊っちゃん]
+//! ```
+//!
+//! The [`Comparison`] it returns summarizes how often the models agree, per sentence
+//! and per boundary decision.
+//!
+//! Given a gold corpus, [`significance`] tells whether the difference in boundary
+//! accuracy between the models is larger than chance would explain on a corpus of that
+//! size, with McNemar's test over the boundary decisions and a paired bootstrap over the
+//! sentences.
+
+use std::io::{self, BufRead, Write};
+
+use crate::segmenter::Segmenter;
+use crate::util::SplitMix64;
+
+/// A span of a sentence that two models segment differently.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    /// Byte offset of the start of the span in the sentence.
+    pub start: usize,
+    /// Byte offset of the end of the span in the sentence.
+    pub end: usize,
+    /// The words of the span according to the left model.
+    pub left: Vec<String>,
+    /// The words of the span according to the right model.
+    pub right: Vec<String>,
+    /// Confidence of the left model in its least certain decision within the span.
+    pub left_confidence: f64,
+    /// Confidence of the right model in its least certain decision within the span.
+    pub right_confidence: f64,
+}
+
+/// Agreement between two models over a corpus.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Comparison {
+    /// Number of sentences compared.
+    pub sentences: usize,
+    /// Number of sentences segmented differently.
+    pub differing_sentences: usize,
+    /// Number of boundary decisions, one per pair of adjacent characters.
+    pub decisions: usize,
+    /// Number of boundary decisions on which the models disagree.
+    pub differing_decisions: usize,
+}
+
+impl Comparison {
+    /// Returns the percentage of sentences both models segment identically.
+    #[must_use]
+    pub fn sentence_agreement(&self) -> f64 {
+        agreement(self.differing_sentences, self.sentences)
+    }
+
+    /// Returns the percentage of boundary decisions both models agree on.
+    #[must_use]
+    pub fn decision_agreement(&self) -> f64 {
+        agreement(self.differing_decisions, self.decisions)
+    }
+}
+
+/// Returns the percentage of `total` that does not differ; 100 if there is nothing.
+fn agreement(differing: usize, total: usize) -> f64 {
+    if total == 0 {
+        return 100.0;
+    }
+    (total - differing) as f64 / total as f64 * 100.0
+}
+
+/// Converts a decision score to the probability that the decision is correct, as
+/// [`ScoredWord::confidence`](crate::segmenter::ScoredWord::confidence) does.
+fn confidence(score: f64) -> f64 {
+    1.0 / (1.0 + (-2.0 * score.abs()).exp())
+}
+
+/// Returns, for every character position of a sentence with `num_chars` characters,
+/// whether a word starts there.
+pub(crate) fn starts(words: &[String], num_chars: usize) -> Vec<bool> {
+    let mut starts = vec![false; num_chars + 1];
+    let mut offset = 0;
+    for word in words {
+        starts[offset] = true;
+        offset += word.chars().count();
+    }
+    starts[num_chars] = true;
+    starts
+}
+
+/// Splits the characters `chars[start..end]` at the word starts of a segmentation.
+pub(crate) fn split(chars: &[char], starts: &[bool], start: usize, end: usize) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    for i in start..end {
+        if starts[i] && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(chars[i]);
+    }
+    words.push(word);
+    words
+}
+
+/// Returns the spans of character positions `(start, end)` that two segmentations split
+/// differently, given the word starts of each. Spans run between the boundaries both
+/// segmentations agree on.
+pub(crate) fn differing_spans(left: &[bool], right: &[bool]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut differs = false;
+    for i in 1..left.len() {
+        if left[i] != right[i] {
+            differs = true;
+        } else if left[i] {
+            if differs {
+                spans.push((start, i));
+            }
+            start = i;
+            differs = false;
+        }
+    }
+    spans
+}
+
+/// The segmentations of a sentence by two models and their differences.
+struct SentenceComparison {
+    left: Vec<String>,
+    right: Vec<String>,
+    differences: Vec<Difference>,
+    differing_decisions: usize,
+}
+
+/// Segments a sentence with two models and returns the spans they segment differently.
+///
+/// Spans run between the word boundaries both models agree on, so each span holds whole
+/// words of both segmentations.
+///
+/// # Arguments
+/// * `left` - The first segmenter, e.g. with the model in production.
+/// * `right` - The second segmenter, e.g. with a retrained model.
+/// * `sentence` - The sentence to segment.
+///
+/// # Returns
+/// The differing spans in order, empty if both models segment the sentence identically.
+#[must_use]
+pub fn compare_sentence(left: &Segmenter, right: &Segmenter, sentence: &str) -> Vec<Difference> {
+    compare_segmentations(left, right, sentence).differences
+}
+
+/// Segments a sentence with two models and compares the segmentations.
+fn compare_segmentations(
+    left: &Segmenter,
+    right: &Segmenter,
+    sentence: &str,
+) -> SentenceComparison {
+    let chars: Vec<char> = sentence.chars().collect();
+    let offsets: Vec<usize> = sentence
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(sentence.len()))
+        .collect();
+    let left_words = left.segment(sentence);
+    let right_words = right.segment(sentence);
+    let left_starts = starts(&left_words, chars.len());
+    let right_starts = starts(&right_words, chars.len());
+    let left_scores = left.boundary_scores(sentence);
+    let right_scores = right.boundary_scores(sentence);
+
+    let differences = differing_spans(&left_starts, &right_starts)
+        .into_iter()
+        .map(|(start, end)| {
+            // The score of the decision before character k is at index k - 1.
+            let min_confidence = |scores: &[f64]| {
+                scores[start..end - 1].iter().map(|&s| confidence(s)).fold(1.0, f64::min)
+            };
+            Difference {
+                start: offsets[start],
+                end: offsets[end],
+                left: split(&chars, &left_starts, start, end),
+                right: split(&chars, &right_starts, start, end),
+                left_confidence: min_confidence(&left_scores),
+                right_confidence: min_confidence(&right_scores),
+            }
+        })
+        .collect();
+    let differing_decisions = left_starts.iter().zip(&right_starts).filter(|(l, r)| l != r).count();
+    SentenceComparison {
+        left: left_words,
+        right: right_words,
+        differences,
+        differing_decisions,
+    }
+}
+
+/// Returns a segmentation with the spans given as byte offsets `(start, end)` in brackets.
+pub(crate) fn mark_spans<I>(words: &[String], spans: I) -> String
+where
+    I: IntoIterator<Item = (usize, usize)> + Clone,
+{
+    let mut line = String::new();
+    let mut offset = 0;
+    for word in words {
+        if offset > 0 {
+            line.push(' ');
+        }
+        let end = offset + word.len();
+        if spans.clone().into_iter().any(|(start, _)| start == offset) {
+            line.push('[');
+        }
+        line.push_str(word);
+        if spans.clone().into_iter().any(|(_, span_end)| span_end == end) {
+            line.push(']');
+        }
+        offset = end;
+    }
+    line
+}
+
+/// Segments every line of a corpus with two models and writes the lines they segment
+/// differently, as two tab-separated lines each: the line number, `left` or `right`,
+/// the model's confidence in its least certain disputed decision, and its segmentation
+/// with the differing spans in brackets.
+///
+/// # Arguments
+/// * `left` - The first segmenter, e.g. with the model in production.
+/// * `right` - The second segmenter, e.g. with a retrained model.
+/// * `corpus` - The corpus, one sentence per line.
+/// * `writer` - The destination of the report.
+///
+/// # Returns
+/// The agreement between the models over the corpus.
+///
+/// # Errors
+/// Returns an error if the corpus cannot be read or the report cannot be written.
+pub fn compare<R: BufRead, W: Write>(
+    left: &Segmenter,
+    right: &Segmenter,
+    corpus: R,
+    writer: &mut W,
+) -> io::Result<Comparison> {
+    let mut comparison = Comparison::default();
+    for (line_num, line) in corpus.lines().enumerate() {
+        let line = line?;
+        let sentence = line.trim();
+        if sentence.is_empty() {
+            continue;
+        }
+        let result = compare_segmentations(left, right, sentence);
+        comparison.sentences += 1;
+        comparison.decisions += sentence.chars().count() - 1;
+        comparison.differing_decisions += result.differing_decisions;
+        let differences = &result.differences;
+        if differences.is_empty() {
+            continue;
+        }
+        comparison.differing_sentences += 1;
+
+        let left_confidence = differences.iter().map(|d| d.left_confidence).fold(1.0, f64::min);
+        let right_confidence = differences.iter().map(|d| d.right_confidence).fold(1.0, f64::min);
+        let spans = differences.iter().map(|d| (d.start, d.end));
+        writeln!(
+            writer,
+            "{}\tleft\t{:.3}\t{}",
+            line_num + 1,
+            left_confidence,
+            mark_spans(&result.left, spans.clone())
+        )?;
+        writeln!(
+            writer,
+            "{}\tright\t{:.3}\t{}",
+            line_num + 1,
+            right_confidence,
+            mark_spans(&result.right, spans)
+        )?;
+    }
+    Ok(comparison)
+}
+
+/// Writes the agreement between two models in a human-readable format.
+///
+/// # Arguments
+/// * `writer` - The destination, e.g. standard error.
+/// * `comparison` - The agreement returned by [`compare`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_summary<W: Write>(writer: &mut W, comparison: &Comparison) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Sentences: {} ({} differ, {:.2}% agreement)",
+        comparison.sentences,
+        comparison.differing_sentences,
+        comparison.sentence_agreement()
+    )?;
+    writeln!(
+        writer,
+        "Boundary decisions: {} ({} differ, {:.2}% agreement)",
+        comparison.decisions,
+        comparison.differing_decisions,
+        comparison.decision_agreement()
+    )
+}
+
+/// A paired significance test of the difference in boundary accuracy between two models
+/// on the same gold corpus.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Significance {
+    /// Number of boundary decisions in the gold corpus.
+    pub decisions: usize,
+    /// Number of decisions the left model gets right.
+    pub left_correct: usize,
+    /// Number of decisions the right model gets right.
+    pub right_correct: usize,
+    /// Number of decisions only the left model gets right.
+    pub left_only: usize,
+    /// Number of decisions only the right model gets right.
+    pub right_only: usize,
+    /// Two-sided p-value of McNemar's test over the boundary decisions.
+    pub mcnemar_p_value: f64,
+    /// Number of bootstrap samples drawn.
+    pub bootstrap_samples: usize,
+    /// Two-sided p-value of the paired bootstrap over the sentences.
+    pub bootstrap_p_value: f64,
+}
+
+impl Significance {
+    /// Returns the boundary accuracy of the left model in percent.
+    #[must_use]
+    pub fn left_accuracy(&self) -> f64 {
+        self.left_correct as f64 / self.decisions.max(1) as f64 * 100.0
+    }
+
+    /// Returns the boundary accuracy of the right model in percent.
+    #[must_use]
+    pub fn right_accuracy(&self) -> f64 {
+        self.right_correct as f64 / self.decisions.max(1) as f64 * 100.0
+    }
+
+    /// Returns the accuracy of the right model minus that of the left, in points.
+    #[must_use]
+    pub fn difference(&self) -> f64 {
+        self.right_accuracy() - self.left_accuracy()
+    }
+}
+
+/// Complementary error function, with a fractional error below 1.2e-7 (Numerical
+/// Recipes, `erfcc`).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 { r } else { 2.0 - r }
+}
+
+/// Returns the two-sided p-value of McNemar's test given the decisions only one model
+/// gets right: exact binomial for fewer than 25 discordant decisions, the chi-squared
+/// approximation with continuity correction otherwise.
+fn mcnemar(left_only: usize, right_only: usize) -> f64 {
+    let n = left_only + right_only;
+    if n == 0 {
+        return 1.0;
+    }
+    if n < 25 {
+        let k = left_only.min(right_only);
+        let mut term = 0.5f64.powi(n as i32);
+        let mut tail = 0.0;
+        for i in 0..=k {
+            tail += term;
+            term *= (n - i) as f64 / (i + 1) as f64;
+        }
+        return (2.0 * tail).min(1.0);
+    }
+    let diff = (left_only as f64 - right_only as f64).abs() - 1.0;
+    let chi2 = diff.max(0.0).powi(2) / n as f64;
+    erfc((chi2 / 2.0).sqrt())
+}
+
+/// Tests whether two models differ in boundary accuracy on a gold corpus.
+///
+/// Both models segment the text of every gold sentence. McNemar's test compares the
+/// decisions only one of them gets right. The paired bootstrap resamples the sentences
+/// `samples` times and counts how often the difference in accuracy moves at least as far
+/// from the observed one as the observed one is from zero.
+///
+/// # Arguments
+/// * `left` - The first segmenter, e.g. with the model in production.
+/// * `right` - The second segmenter, e.g. with a retrained model.
+/// * `corpus` - The gold corpus, one sentence per line with words separated by spaces.
+/// * `samples` - The number of bootstrap samples, e.g. 1000. 0 skips the bootstrap.
+/// * `seed` - The seed of the bootstrap resampling.
+///
+/// # Returns
+/// The accuracies of both models and the p-values of both tests.
+///
+/// # Errors
+/// Returns an error if the corpus cannot be read.
+pub fn significance<R: BufRead>(
+    left: &Segmenter,
+    right: &Segmenter,
+    corpus: R,
+    samples: usize,
+    seed: u64,
+) -> io::Result<Significance> {
+    let mut result = Significance::default();
+    // Per sentence: (decisions, left correct, right correct).
+    let mut sentences = Vec::new();
+    for line in corpus.lines() {
+        let line = line?;
+        let gold: Vec<String> =
+            line.split(' ').filter(|w| !w.is_empty()).map(str::to_string).collect();
+        if gold.is_empty() {
+            continue;
+        }
+        let text = gold.concat();
+        let num_chars = text.chars().count();
+        let gold_starts = starts(&gold, num_chars);
+        let left_starts = starts(&left.segment(&text), num_chars);
+        let right_starts = starts(&right.segment(&text), num_chars);
+
+        let mut counts = (num_chars - 1, 0, 0);
+        for i in 1..num_chars {
+            let left_right = left_starts[i] == gold_starts[i];
+            let right_right = right_starts[i] == gold_starts[i];
+            counts.1 += usize::from(left_right);
+            counts.2 += usize::from(right_right);
+            result.left_only += usize::from(left_right && !right_right);
+            result.right_only += usize::from(right_right && !left_right);
+        }
+        result.decisions += counts.0;
+        result.left_correct += counts.1;
+        result.right_correct += counts.2;
+        sentences.push(counts);
+    }
+    result.mcnemar_p_value = mcnemar(result.left_only, result.right_only);
+
+    result.bootstrap_samples = samples;
+    result.bootstrap_p_value = 1.0;
+    let observed = result.difference();
+    if samples > 0 && !sentences.is_empty() && observed != 0.0 {
+        let mut rng = SplitMix64(seed);
+        let mut extreme = 0;
+        for _ in 0..samples {
+            let (mut decisions, mut left_correct, mut right_correct) = (0, 0, 0);
+            for _ in 0..sentences.len() {
+                let (d, l, r) = sentences[rng.below(sentences.len())];
+                decisions += d;
+                left_correct += l;
+                right_correct += r;
+            }
+            let difference =
+                (right_correct as f64 - left_correct as f64) / decisions.max(1) as f64 * 100.0;
+            if (difference - observed).abs() >= observed.abs() {
+                extreme += 1;
+            }
+        }
+        result.bootstrap_p_value = extreme as f64 / samples as f64;
+    }
+    Ok(result)
+}
+
+/// Writes the result of a significance test in a human-readable format.
+///
+/// # Arguments
+/// * `writer` - The destination, e.g. standard error.
+/// * `significance` - The result returned by [`significance`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_significance<W: Write>(writer: &mut W, significance: &Significance) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Boundary accuracy: left {:.2}%, right {:.2}% ({:+.2} points)",
+        significance.left_accuracy(),
+        significance.right_accuracy(),
+        significance.difference()
+    )?;
+    writeln!(
+        writer,
+        "McNemar: {} decisions only left correct, {} only right correct, p = {:.4}",
+        significance.left_only, significance.right_only, significance.mcnemar_p_value
+    )?;
+    if significance.bootstrap_samples > 0 {
+        writeln!(
+            writer,
+            "Paired bootstrap: {} samples, p = {:.4}",
+            significance.bootstrap_samples, significance.bootstrap_p_value
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::{japanese_segmenter, rwcp_segmenter};
+
+    #[test]
+    fn test_compare_sentence() {
+        let left = rwcp_segmenter();
+        let right = japanese_segmenter();
+        let sentence = "これはテストです。";
+        let differences = compare_sentence(&left, &right, sentence);
+        // RWCP: これ は テスト です 。 / japanese: これ は テスト で す 。
+        assert_eq!(differences.len(), 1);
+        let difference = &differences[0];
+        assert_eq!(&sentence[difference.start..difference.end], "です");
+        assert_eq!(difference.left, vec!["です"]);
+        assert_eq!(difference.right, vec!["で", "す"]);
+        assert!(difference.left_confidence > 0.5 && difference.left_confidence <= 1.0);
+        assert!(difference.right_confidence > 0.5 && difference.right_confidence <= 1.0);
+
+        assert!(compare_sentence(&left, &left, sentence).is_empty());
+        assert!(compare_sentence(&left, &right, "").is_empty());
+    }
+
+    #[test]
+    fn test_compare() {
+        let left = rwcp_segmenter();
+        let right = japanese_segmenter();
+        let corpus = "これはテストです。\n\nこれ\n";
+        let mut report = Vec::new();
+        let comparison = compare(&left, &right, corpus.as_bytes(), &mut report).unwrap();
+        assert_eq!(comparison.sentences, 2);
+        assert_eq!(comparison.differing_sentences, 1);
+        assert_eq!(comparison.decisions, 9);
+        assert_eq!(comparison.differing_decisions, 1);
+        assert_eq!(comparison.sentence_agreement(), 50.0);
+
+        let report = String::from_utf8(report).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("1\tleft\t"));
+        assert!(lines[0].ends_with("\tこれ は テスト [です] 。"));
+        assert!(lines[1].starts_with("1\tright\t"));
+        assert!(lines[1].ends_with("\tこれ は テスト [で す] 。"));
+
+        let mut summary = Vec::new();
+        write_summary(&mut summary, &comparison).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.contains("Sentences: 2 (1 differ, 50.00% agreement)"));
+    }
+
+    #[test]
+    fn test_mcnemar() {
+        assert_eq!(mcnemar(0, 0), 1.0);
+        assert_eq!(mcnemar(5, 5), 1.0);
+        // Exact: 2 * P(X <= 0) with X ~ B(10, 0.5).
+        assert!((mcnemar(0, 10) - 2.0 / 1024.0).abs() < 1e-12);
+        // Chi-squared: (|40 - 60| - 1)^2 / 100 = 3.61, p = 0.0574.
+        assert!((mcnemar(40, 60) - 0.0574).abs() < 1e-4);
+        assert!((erfc(0.0) - 1.0).abs() < 1e-7);
+        assert!((erfc(-1.0) - 1.842_700_79).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_significance() {
+        let left = rwcp_segmenter();
+        let right = japanese_segmenter();
+        let gold = "これ は テスト です 。\n\nこれ は テスト です 。\n";
+        let result = significance(&left, &right, gold.as_bytes(), 100, 42).unwrap();
+        assert_eq!(result.decisions, 16);
+        assert_eq!(result.left_correct, 16);
+        assert_eq!(result.right_correct, 14);
+        assert_eq!((result.left_only, result.right_only), (2, 0));
+        assert_eq!(result.mcnemar_p_value, 0.5);
+        assert!(result.difference() < 0.0);
+        // Every resample has the same difference, never further from it than zero is.
+        assert_eq!(result.bootstrap_p_value, 0.0);
+
+        let same = significance(&left, &left, gold.as_bytes(), 100, 42).unwrap();
+        assert_eq!(same.mcnemar_p_value, 1.0);
+        assert_eq!(same.bootstrap_p_value, 1.0);
+
+        let mut summary = Vec::new();
+        write_significance(&mut summary, &result).unwrap();
+        let summary = String::from_utf8(summary).unwrap();
+        assert!(summary.contains("left 100.00%, right 87.50% (-12.50 points)"));
+        assert!(summary.contains("2 decisions only left correct, 0 only right correct"));
+        assert!(summary.contains("Paired bootstrap: 100 samples, p = 0.0000"));
+    }
+
+    #[test]
+    fn test_empty_corpus() {
+        let left = rwcp_segmenter();
+        let comparison = compare(&left, &left, "".as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(comparison, Comparison::default());
+        assert_eq!(comparison.decision_agreement(), 100.0);
+    }
+}
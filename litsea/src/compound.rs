@@ -0,0 +1,328 @@
+//! Compound splitting, an optional second pass that splits long noun compounds into
+//! their components.
+//!
+//! Search indexing usually wants `自然言語処理` as `自然 / 言語 / 処理`, while display
+//! wants it whole, so the pass is applied per call to the tokens of
+//! [`Segmenter::tokenize`](crate::segmenter::Segmenter::tokenize). A token is a compound
+//! candidate when it has at least [`min_chars`](CompoundSplitter::with_min_chars)
+//! characters, all of noun-like character types (kanji and katakana by default). A
+//! candidate is first split where the character type changes (`データ処理` becomes
+//! `データ / 処理`), then each run is split into the fewest dictionary words that cover it.
+//! Runs that the dictionary cannot cover are kept whole.
+//!
+//! ```
+//! use litsea::compound::CompoundSplitter;
+//! use litsea::language::Language;
+//!
+//! let splitter = CompoundSplitter::new(Language::Japanese)
+//!     .with_words(["自然", "言語", "処理"]);
+//! assert_eq!(splitter.split("自然言語処理"), vec!["自然", "言語", "処理"]);
+//! assert_eq!(splitter.split("データ処理"), vec!["データ", "処理"]);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::language::{CharTypePatterns, Language};
+use crate::token::{Token, TokenFilter};
+
+/// Splits noun compounds into components using character types and a word list.
+#[derive(Debug)]
+pub struct CompoundSplitter {
+    char_types: CharTypePatterns,
+    /// Noun-like character types, mapped to the group a run of them belongs to.
+    groups: HashMap<String, usize>,
+    words: HashSet<String>,
+    /// Length in characters of the longest word, bounding the dictionary search.
+    max_chars: usize,
+    min_chars: usize,
+}
+
+impl CompoundSplitter {
+    /// Creates a splitter without a dictionary, which only splits where the character
+    /// type changes. Kanji (`H`, with kanji numerals `M`) and katakana (`K`) are noun-like,
+    /// and compounds need at least 4 characters.
+    ///
+    /// # Arguments
+    /// * `language` - The language whose character types are used.
+    ///
+    /// # Returns
+    /// A new [`CompoundSplitter`].
+    #[must_use]
+    pub fn new(language: Language) -> Self {
+        CompoundSplitter {
+            char_types: language.char_type_patterns(),
+            groups: HashMap::new(),
+            words: HashSet::new(),
+            max_chars: 0,
+            min_chars: 4,
+        }
+        .with_type_groups(&[&["H", "M"], &["K"]])
+    }
+
+    /// Sets the noun-like character types. Types in the same group form one run, so
+    /// a compound is only split between types of different groups.
+    ///
+    /// # Arguments
+    /// * `groups` - Groups of character type codes, e.g. `&[&["H", "M"], &["K"]]`.
+    ///
+    /// # Returns
+    /// The splitter with the new types.
+    #[must_use]
+    pub fn with_type_groups(mut self, groups: &[&[&str]]) -> Self {
+        self.groups = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(i, types)| types.iter().map(move |t| (t.to_string(), i)))
+            .collect();
+        self
+    }
+
+    /// Sets the minimum length in characters of a compound; shorter tokens are never split.
+    ///
+    /// # Arguments
+    /// * `min_chars` - The minimum length.
+    ///
+    /// # Returns
+    /// The splitter with the new minimum length.
+    #[must_use]
+    pub fn with_min_chars(mut self, min_chars: usize) -> Self {
+        self.min_chars = min_chars;
+        self
+    }
+
+    /// Adds words that compounds can be split into.
+    ///
+    /// # Arguments
+    /// * `words` - The component words.
+    ///
+    /// # Returns
+    /// The splitter with the words added.
+    #[must_use]
+    pub fn with_words<I>(mut self, words: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        for word in words {
+            let word = word.as_ref();
+            if !word.is_empty() {
+                self.max_chars = self.max_chars.max(word.chars().count());
+                self.words.insert(word.to_string());
+            }
+        }
+        self
+    }
+
+    /// Adds the words of a word list, one per line. Only the first tab-separated column
+    /// is used, so lexicons such as those of [`LemmaDictionary`](crate::lemma::LemmaDictionary)
+    /// can be used as is. Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the word list.
+    ///
+    /// # Returns
+    /// The splitter with the words added.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`](crate::error::LitseaError::Io) if the file cannot be
+    /// read.
+    pub fn load_words<P: AsRef<Path>>(self, path: P) -> crate::error::Result<Self> {
+        let mut words = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let word = line.split('\t').next().unwrap_or_default().trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                words.push(word.to_string());
+            }
+        }
+        Ok(self.with_words(words))
+    }
+
+    /// Splits a word into its components.
+    ///
+    /// # Arguments
+    /// * `surface` - The word to split.
+    ///
+    /// # Returns
+    /// The components in order, or the whole word if it is not a compound.
+    #[must_use]
+    pub fn split<'a>(&self, surface: &'a str) -> Vec<&'a str> {
+        let mut parts = Vec::new();
+        let mut run_start = 0;
+        let mut run_group = None;
+        let mut num_chars = 0;
+        for (pos, ch) in surface.char_indices() {
+            let mut buf = [0; 4];
+            let Some(&group) = self.groups.get(self.char_types.get_type(ch.encode_utf8(&mut buf)))
+            else {
+                return vec![surface];
+            };
+            if run_group.is_some_and(|g| g != group) {
+                parts.push(&surface[run_start..pos]);
+                run_start = pos;
+            }
+            run_group = Some(group);
+            num_chars += 1;
+        }
+        if num_chars < self.min_chars.max(2) {
+            return vec![surface];
+        }
+        parts.push(&surface[run_start..]);
+        parts.into_iter().flat_map(|run| self.split_run(run)).collect()
+    }
+
+    /// Splits tokens that are compounds, leaving the others as they are. Components get
+    /// the offsets of their text in the sentence, and no annotations.
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens of a sentence.
+    ///
+    /// # Returns
+    /// The tokens with compounds replaced by their components.
+    #[must_use]
+    pub fn split_tokens(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut out = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let parts = self.split(&token.surface);
+            if parts.len() == 1 {
+                out.push(token);
+                continue;
+            }
+            let mut start = token.start;
+            let mut char_start = token.char_start;
+            for part in parts {
+                let char_end = char_start + part.chars().count();
+                out.push(Token {
+                    surface: part.to_string(),
+                    start,
+                    end: start + part.len(),
+                    char_start,
+                    char_end,
+                    // The boundary before the first part is the one the segmenter placed.
+                    score: if start == token.start { token.score } else { None },
+                    ..Token::default()
+                });
+                start += part.len();
+                char_start = char_end;
+            }
+        }
+        out
+    }
+
+    /// Splits a run of one character type group into the fewest dictionary words, or
+    /// keeps it whole if the words cannot cover it.
+    fn split_run<'a>(&self, run: &'a str) -> Vec<&'a str> {
+        let bounds: Vec<usize> =
+            run.char_indices().map(|(pos, _)| pos).chain([run.len()]).collect();
+        let n = bounds.len() - 1;
+        // best[j] = (number of words covering the first j characters, start of the last word)
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; n + 1];
+        best[0] = Some((0, 0));
+        for j in 1..=n {
+            for i in j.saturating_sub(self.max_chars)..j {
+                let Some((count, _)) = best[i] else { continue };
+                if !self.words.contains(&run[bounds[i]..bounds[j]]) {
+                    continue;
+                }
+                if best[j].is_none_or(|(c, _)| count + 1 < c) {
+                    best[j] = Some((count + 1, i));
+                }
+            }
+        }
+        if best[n].is_none() {
+            return vec![run];
+        }
+        let mut parts = Vec::new();
+        let mut j = n;
+        while j > 0 {
+            let (_, i) = best[j].unwrap_or((0, 0));
+            parts.push(&run[bounds[i]..bounds[j]]);
+            j = i;
+        }
+        parts.reverse();
+        parts
+    }
+}
+
+impl TokenFilter for CompoundSplitter {
+    fn apply(&self, tokens: &mut Vec<Token>) {
+        *tokens = self.split_tokens(std::mem::take(tokens));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn splitter() -> CompoundSplitter {
+        CompoundSplitter::new(Language::Japanese).with_words([
+            "自然",
+            "言語",
+            "処理",
+            "言語処理",
+            "自然言語",
+        ])
+    }
+
+    #[test]
+    fn test_split_dictionary() {
+        // The fewest words win; ties prefer the longest last word.
+        assert_eq!(splitter().split("自然言語処理"), vec!["自然", "言語処理"]);
+        let splitter =
+            CompoundSplitter::new(Language::Japanese).with_words(["自然", "言語", "処理"]);
+        assert_eq!(splitter.split("自然言語処理"), vec!["自然", "言語", "処理"]);
+    }
+
+    #[test]
+    fn test_split_char_type_runs() {
+        let splitter = CompoundSplitter::new(Language::Japanese);
+        assert_eq!(splitter.split("データ処理"), vec!["データ", "処理"]);
+        // Kanji numerals belong to the kanji run.
+        assert_eq!(splitter.split("第三者機関"), vec!["第三者機関"]);
+    }
+
+    #[test]
+    fn test_split_not_compound() {
+        let splitter = splitter();
+        // Too short, not all noun-like, or not covered by the dictionary.
+        assert_eq!(splitter.split("言語"), vec!["言語"]);
+        assert_eq!(splitter.split("自然な言語"), vec!["自然な言語"]);
+        assert_eq!(splitter.split("機械翻訳"), vec!["機械翻訳"]);
+        assert_eq!(splitter.with_min_chars(8).split("データ処理"), vec!["データ処理"]);
+    }
+
+    #[test]
+    fn test_split_tokens() {
+        let tokens = vec![
+            Token {
+                surface: "は".to_string(),
+                start: 0,
+                end: 3,
+                ..Token::default()
+            },
+            Token {
+                surface: "自然言語処理".to_string(),
+                start: 3,
+                end: 21,
+                char_start: 1,
+                char_end: 7,
+                reading: Some("シゼンゲンゴショリ".to_string()),
+                ..Token::default()
+            },
+        ];
+        let splitter =
+            CompoundSplitter::new(Language::Japanese).with_words(["自然", "言語", "処理"]);
+        let tokens = splitter.split_tokens(tokens);
+
+        let spans: Vec<(&str, usize, usize)> =
+            tokens.iter().map(|t| (t.surface.as_str(), t.start, t.end)).collect();
+        assert_eq!(spans, vec![("は", 0, 3), ("自然", 3, 9), ("言語", 9, 15), ("処理", 15, 21)]);
+        let char_spans: Vec<(usize, usize)> =
+            tokens[1..].iter().map(|t| (t.char_start, t.char_end)).collect();
+        assert_eq!(char_spans, vec![(1, 3), (3, 5), (5, 7)]);
+        assert!(tokens[1].reading.is_none());
+    }
+}
@@ -0,0 +1,296 @@
+//! Transparent compression of corpus, features and model files.
+//!
+//! Files whose name ends in `.gz` are read and written with gzip, and files ending in
+//! `.zst` with Zstandard; other files are read and written as they are. Reading and
+//! writing compressed files needs the `compression` feature: without it, opening such a
+//! file fails with [`ErrorKind::Unsupported`](io::ErrorKind::Unsupported).
+//!
+//! ```no_run
+//! use std::io::{BufRead, Write};
+//! use std::path::Path;
+//!
+//! use litsea::compression;
+//!
+//! let mut writer = compression::create(Path::new("corpus.txt.zst"))?;
+//! writeln!(writer, "これ は テスト です 。")?;
+//! writer.finish()?;
+//!
+//! for line in compression::open(Path::new("corpus.txt.zst"))?.lines() {
+//!     println!("{}", line?);
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The compression of a file, told by the extension of its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Not compressed.
+    None,
+    /// gzip, for names ending in `.gz`.
+    Gzip,
+    /// Zstandard, for names ending in `.zst`.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression of a file from the extension of its name.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    /// [`Compression::Gzip`] for `.gz`, [`Compression::Zstd`] for `.zst`, and
+    /// [`Compression::None`] otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Returns an error for a compressed file in a build without the `compression`
+    /// feature.
+    #[cfg(not(feature = "compression"))]
+    fn unsupported(self, path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{}: compressed files are not supported in this build; enable the `compression` feature",
+                path.display()
+            ),
+        )
+    }
+}
+
+/// Opens a file for reading, decompressing it according to [`Compression::from_path`].
+/// A gzip file may consist of several concatenated members, as written by `pigz`.
+///
+/// # Arguments
+/// * `path` - The path to the file.
+///
+/// # Returns
+/// A buffered reader of the decompressed content.
+///
+/// # Errors
+/// Returns an error if the file cannot be opened, or it is compressed and the
+/// `compression` feature is disabled. Errors in the compressed data are returned when
+/// reading.
+pub fn open(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    decompress(File::open(path)?, path)
+}
+
+/// Decompresses the content of a file read from `reader`, according to
+/// [`Compression::from_path`] of its path. Unlike [`open`], the file can be read through
+/// another reader, e.g. one that counts the bytes read.
+///
+/// # Arguments
+/// * `reader` - The reader of the file content.
+/// * `path` - The path of the file, which tells its compression.
+///
+/// # Returns
+/// A buffered reader of the decompressed content.
+///
+/// # Errors
+/// Returns an error if the file is compressed and the `compression` feature is disabled.
+pub fn decompress<R: Read + 'static>(reader: R, path: &Path) -> io::Result<Box<dyn BufRead>> {
+    match Compression::from_path(path) {
+        Compression::None => Ok(Box::new(BufReader::new(reader))),
+        #[cfg(feature = "compression")]
+        Compression::Gzip => Ok(Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+            BufReader::new(reader),
+        )))),
+        #[cfg(feature = "compression")]
+        Compression::Zstd => Ok(Box::new(BufReader::new(zstd::Decoder::new(reader)?))),
+        #[cfg(not(feature = "compression"))]
+        compression => Err(compression.unsupported(path)),
+    }
+}
+
+/// Reads a whole file into a string, decompressing it like [`open`].
+///
+/// # Arguments
+/// * `path` - The path to the file.
+///
+/// # Returns
+/// The decompressed content.
+///
+/// # Errors
+/// Returns an error if the file cannot be read or decompressed, or it is not UTF-8.
+pub fn read_to_string(path: &Path) -> io::Result<String> {
+    let mut content = String::new();
+    open(path)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Creates a file for writing, compressing it according to [`Compression::from_path`].
+///
+/// # Arguments
+/// * `path` - The path to the file, which is truncated if it exists.
+///
+/// # Returns
+/// A buffered writer, which must be closed with [`FileWriter::finish`] to complete the
+/// compressed stream.
+///
+/// # Errors
+/// Returns an error if the file cannot be created, or it is compressed and the
+/// `compression` feature is disabled.
+pub fn create(path: &Path) -> io::Result<FileWriter> {
+    let compression = Compression::from_path(path);
+    #[cfg(not(feature = "compression"))]
+    if compression != Compression::None {
+        return Err(compression.unsupported(path));
+    }
+    let file = BufWriter::new(File::create(path)?);
+    let inner = match compression {
+        #[cfg(feature = "compression")]
+        Compression::Gzip => {
+            Inner::Gzip(flate2::write::GzEncoder::new(file, flate2::Compression::default()))
+        }
+        #[cfg(feature = "compression")]
+        Compression::Zstd => {
+            Inner::Zstd(zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)?)
+        }
+        _ => Inner::Plain(file),
+    };
+    Ok(FileWriter { inner })
+}
+
+/// A writer returned by [`create`].
+pub struct FileWriter {
+    inner: Inner,
+}
+
+enum Inner {
+    Plain(BufWriter<File>),
+    #[cfg(feature = "compression")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    #[cfg(feature = "compression")]
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl FileWriter {
+    /// Completes the compressed stream and flushes the file. Dropping the writer
+    /// without calling it may leave a compressed file truncated.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    #[cfg_attr(
+        not(feature = "compression"),
+        allow(clippy::infallible_destructuring_match)
+    )]
+    pub fn finish(self) -> io::Result<()> {
+        let mut file = match self.inner {
+            Inner::Plain(file) => file,
+            #[cfg(feature = "compression")]
+            Inner::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "compression")]
+            Inner::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Inner::Plain(file) => file.write(buf),
+            #[cfg(feature = "compression")]
+            Inner::Gzip(encoder) => encoder.write(buf),
+            #[cfg(feature = "compression")]
+            Inner::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Inner::Plain(file) => file.flush(),
+            #[cfg(feature = "compression")]
+            Inner::Gzip(encoder) => encoder.flush(),
+            #[cfg(feature = "compression")]
+            Inner::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Compression::from_path(Path::new("corpus.txt.gz")), Compression::Gzip);
+        assert_eq!(Compression::from_path(Path::new("features.zst")), Compression::Zstd);
+        assert_eq!(Compression::from_path(Path::new("model.txt")), Compression::None);
+        assert_eq!(Compression::from_path(Path::new("gz")), Compression::None);
+    }
+
+    #[test]
+    fn test_round_trip_plain() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("corpus.txt");
+        let mut writer = create(&path)?;
+        writer.write_all("これ は テスト です 。\n".as_bytes())?;
+        writer.finish()?;
+
+        assert_eq!(std::fs::read_to_string(&path)?, "これ は テスト です 。\n");
+        assert_eq!(read_to_string(&path)?, "これ は テスト です 。\n");
+        Ok(())
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_round_trip_compressed() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        for name in ["corpus.txt.gz", "corpus.txt.zst"] {
+            let path = dir.path().join(name);
+            let mut writer = create(&path)?;
+            for _ in 0..100 {
+                writer.write_all("これ は テスト です 。\n".as_bytes())?;
+            }
+            writer.finish()?;
+
+            let compressed = std::fs::read(&path)?;
+            assert!(compressed.len() < 100 * "これ は テスト です 。\n".len(), "{}", name);
+            let lines: Vec<String> = open(&path)?.lines().collect::<io::Result<_>>()?;
+            assert_eq!(lines.len(), 100, "{}", name);
+            assert!(lines.iter().all(|line| line == "これ は テスト です 。"));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_open_concatenated_gzip() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("corpus.txt.gz");
+        let mut content = Vec::new();
+        for line in ["一 行 目\n", "二 行 目\n"] {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(line.as_bytes())?;
+            content.extend(encoder.finish()?);
+        }
+        std::fs::write(&path, content)?;
+
+        let mut text = String::new();
+        open(&path)?.read_to_string(&mut text)?;
+        assert_eq!(text, "一 行 目\n二 行 目\n");
+        Ok(())
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_compressed_unsupported() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("corpus.txt.gz");
+        std::fs::write(&path, b"")?;
+        assert_eq!(open(&path).err().map(|e| e.kind()), Some(io::ErrorKind::Unsupported));
+        assert_eq!(create(&path).err().map(|e| e.kind()), Some(io::ErrorKind::Unsupported));
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Cooperative control of training runs.
+//!
+//! A [`TrainControl`] is a handle shared between a training run and the code that
+//! drives it, such as a GUI or a service. Cloning it shares the same state, so one clone
+//! is given to the run and the others stop, pause or resume it from any thread:
+//!
+//! ```
+//! use std::thread;
+//!
+//! use litsea::adaboost::AdaBoost;
+//! use litsea::control::TrainControl;
+//!
+//! let control = TrainControl::new();
+//! let handle = control.clone();
+//! let training = thread::spawn(move || {
+//!     let mut learner = AdaBoost::new(0.01, 100);
+//!     learner.add_instance(["UW4:は".to_string()].into(), 1);
+//!     learner.add_instance(["UW4:が".to_string()].into(), -1);
+//!     learner.train(handle);
+//!     learner
+//! });
+//! control.stop();
+//! let learner = training.join().unwrap();
+//! ```
+//!
+//! Training checks the handle before every iteration and while it scores the
+//! instances, once per chunk of them, so a stop takes effect within a fraction of an
+//! iteration, and a paused run holds its threads until it is resumed or stopped. An
+//! iteration interrupted by a stop is discarded; the model keeps the iterations
+//! completed before it.
+//!
+//! Every function that takes a `TrainControl` also accepts the `Arc<AtomicBool>` of
+//! earlier versions, cleared to stop.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// How long a paused run waits before it checks the running flag again, which may be
+/// cleared without notifying it when it was given as an `Arc<AtomicBool>`.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A handle that stops, pauses and resumes a training run.
+#[derive(Debug, Clone)]
+pub struct TrainControl {
+    running: Arc<AtomicBool>,
+    pause: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Default for TrainControl {
+    fn default() -> Self {
+        TrainControl::new()
+    }
+}
+
+impl From<Arc<AtomicBool>> for TrainControl {
+    /// Wraps a running flag: clearing it stops the run like [`TrainControl::stop`].
+    fn from(running: Arc<AtomicBool>) -> Self {
+        TrainControl {
+            running,
+            pause: Arc::new((Mutex::new(false), Condvar::new())),
+        }
+    }
+}
+
+impl TrainControl {
+    /// Creates a handle for a run that is neither stopped nor paused.
+    #[must_use]
+    pub fn new() -> Self {
+        Arc::new(AtomicBool::new(true)).into()
+    }
+
+    /// Stops the run as soon as it checks the handle, also if it is paused. A stopped
+    /// run cannot be resumed.
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+        let (_, resumed) = &*self.pause;
+        // Taking the lock orders the store before the wake-up of a run that is about to
+        // wait.
+        let _paused = self.lock();
+        resumed.notify_all();
+    }
+
+    /// Returns `true` once the run has been stopped.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        !self.running.load(Ordering::SeqCst)
+    }
+
+    /// Pauses the run the next time it checks the handle, until it is resumed or
+    /// stopped.
+    pub fn pause(&self) {
+        *self.lock() = true;
+    }
+
+    /// Resumes a paused run.
+    pub fn resume(&self) {
+        *self.lock() = false;
+        self.pause.1.notify_all();
+    }
+
+    /// Returns `true` while the run is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        *self.lock()
+    }
+
+    /// Waits while the run is paused, then returns `true` if it may continue and
+    /// `false` if it has been stopped.
+    pub(crate) fn checkpoint(&self) -> bool {
+        let (_, resumed) = &*self.pause;
+        let mut paused = self.lock();
+        while *paused && !self.is_stopped() {
+            paused = match resumed.wait_timeout(paused, PAUSE_POLL_INTERVAL) {
+                Ok((paused, _)) => paused,
+                Err(e) => e.into_inner().0,
+            };
+        }
+        !self.is_stopped()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.pause.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn test_stop() {
+        let control = TrainControl::new();
+        assert!(control.checkpoint());
+        control.clone().stop();
+        assert!(control.is_stopped());
+        assert!(!control.checkpoint());
+
+        let running = Arc::new(AtomicBool::new(true));
+        let control = TrainControl::from(Arc::clone(&running));
+        running.store(false, Ordering::SeqCst);
+        assert!(control.is_stopped());
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let control = TrainControl::new();
+        control.pause();
+        assert!(control.is_paused());
+
+        let handle = control.clone();
+        let waiter = thread::spawn(move || handle.checkpoint());
+        thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+        control.resume();
+        assert!(waiter.join().unwrap());
+        assert!(!control.is_paused());
+
+        // A stop wakes a paused run, which then ends.
+        control.pause();
+        let handle = control.clone();
+        let waiter = thread::spawn(move || handle.checkpoint());
+        control.stop();
+        assert!(!waiter.join().unwrap());
+    }
+
+    #[test]
+    fn test_pause_polls_running_flag() {
+        let running = Arc::new(AtomicBool::new(true));
+        let control = TrainControl::from(Arc::clone(&running));
+        control.pause();
+        let waiter = thread::spawn(move || control.checkpoint());
+        running.store(false, Ordering::SeqCst);
+        assert!(!waiter.join().unwrap());
+    }
+}
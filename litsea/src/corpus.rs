@@ -0,0 +1,299 @@
+//! Readers of segmented corpora in the formats of other tools.
+//!
+//! Training reads sentences with words separated by spaces, one sentence per line.
+//! [`sentences`] converts corpora annotated for KyTea or in CoNLL-U, the format of the
+//! Universal Dependencies treebanks, into such sentences, dropping their tags.
+//! [`conllu_sentences`] also reconstructs the raw text of each CoNLL-U sentence, to
+//! segment or evaluate on.
+
+use std::fmt;
+use std::io::{self, BufRead, Lines};
+use std::str::FromStr;
+
+use crate::error::LitseaError;
+
+/// The format of a segmented corpus.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CorpusFormat {
+    /// Words separated by spaces, one sentence per line.
+    #[default]
+    Plain,
+    /// KyTea full annotation: words written as `surface/tag/...` and separated by
+    /// spaces, one sentence per line. Tags are optional, and `\` escapes a `/`, a space
+    /// or a `\` in a surface.
+    Kytea,
+    /// CoNLL-U: one token per line with the surface in the second of the tab-separated
+    /// columns, and sentences ended by empty lines.
+    Conllu,
+}
+
+impl fmt::Display for CorpusFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorpusFormat::Plain => write!(f, "plain"),
+            CorpusFormat::Kytea => write!(f, "kytea"),
+            CorpusFormat::Conllu => write!(f, "conllu"),
+        }
+    }
+}
+
+impl FromStr for CorpusFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(CorpusFormat::Plain),
+            "kytea" => Ok(CorpusFormat::Kytea),
+            "conllu" | "conll-u" => Ok(CorpusFormat::Conllu),
+            _ => {
+                Err(format!("Unsupported corpus format: '{}'. Supported: plain, kytea, conllu", s))
+            }
+        }
+    }
+}
+
+/// A sentence of a CoNLL-U corpus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConlluSentence {
+    /// The surfaces of the tokens.
+    pub words: Vec<String>,
+    /// The raw text: the surfaces, each followed by a space unless its `MISC` column has
+    /// `SpaceAfter=No`, without the space after the last one.
+    pub text: String,
+}
+
+/// Iterator over the sentences of a corpus, returned by [`sentences`].
+pub struct Sentences<R> {
+    lines: Lines<R>,
+    format: CorpusFormat,
+    line_num: usize,
+}
+
+/// Reads the sentences of a corpus as words separated by spaces.
+///
+/// Plain and KyTea corpora yield one sentence per line, including empty ones. CoNLL-U
+/// corpora yield one sentence per block of token lines; comment lines are skipped, a
+/// multiword token yields its surface instead of the words it is split into, and empty
+/// nodes are skipped.
+///
+/// # Arguments
+/// * `reader` - The corpus.
+/// * `format` - The format of the corpus.
+///
+/// # Returns
+/// An iterator over the sentences.
+///
+/// # Errors
+/// The iterator returns an error if reading fails, or an [`io::ErrorKind::InvalidData`]
+/// error carrying [`LitseaError::Parse`] if a CoNLL-U token line has no surface.
+pub fn sentences<R: BufRead>(reader: R, format: CorpusFormat) -> Sentences<R> {
+    Sentences {
+        lines: reader.lines(),
+        format,
+        line_num: 0,
+    }
+}
+
+/// Iterator over the sentences of a CoNLL-U corpus, returned by [`conllu_sentences`].
+pub struct ConlluSentences<R>(Sentences<R>);
+
+/// Reads the sentences of a CoNLL-U corpus with their raw text.
+///
+/// A multiword token yields its surface instead of the words it is split into, and
+/// comment lines and empty nodes are skipped, as in [`sentences`].
+///
+/// # Arguments
+/// * `reader` - The corpus.
+///
+/// # Returns
+/// An iterator over the sentences.
+///
+/// # Errors
+/// The iterator returns an error if reading fails, or an [`io::ErrorKind::InvalidData`]
+/// error carrying [`LitseaError::Parse`] if a token line has no surface.
+pub fn conllu_sentences<R: BufRead>(reader: R) -> ConlluSentences<R> {
+    ConlluSentences(sentences(reader, CorpusFormat::Conllu))
+}
+
+impl<R: BufRead> Iterator for ConlluSentences<R> {
+    type Item = io::Result<ConlluSentence>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_conllu()
+    }
+}
+
+impl<R: BufRead> Sentences<R> {
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        let line = self.lines.next()?;
+        self.line_num += 1;
+        Some(line)
+    }
+
+    fn next_conllu(&mut self) -> Option<io::Result<ConlluSentence>> {
+        let mut words: Vec<String> = Vec::new();
+        let mut text = String::new();
+        // The last word ID covered by the multiword token read last.
+        let mut covered = 0;
+        loop {
+            let line = match self.next_line() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => return Some(Err(e)),
+                None if words.is_empty() => return None,
+                None => break,
+            };
+            let line = line.trim_end();
+            if line.is_empty() {
+                if words.is_empty() {
+                    continue;
+                }
+                break;
+            }
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let id = columns.next().unwrap_or_default();
+            let form = match columns.next() {
+                Some(form) if !form.is_empty() => form,
+                _ => {
+                    return Some(Err(LitseaError::Parse {
+                        line: self.line_num,
+                        reason: format!("Token '{}' has no FORM column", id),
+                    }
+                    .into()));
+                }
+            };
+            if id.contains('.') {
+                continue;
+            }
+            if let Some((_, last)) = id.split_once('-') {
+                covered = last.parse().unwrap_or(0);
+            } else if id.parse::<usize>().is_ok_and(|id| id <= covered) {
+                continue;
+            }
+            words.push(form.to_string());
+            text.push_str(form);
+            if !columns.nth(7).is_some_and(|misc| misc.split('|').any(|m| m == "SpaceAfter=No")) {
+                text.push(' ');
+            }
+        }
+        text.truncate(text.trim_end().len());
+        Some(Ok(ConlluSentence { words, text }))
+    }
+}
+
+impl<R: BufRead> Iterator for Sentences<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.format {
+            CorpusFormat::Plain => self.next_line(),
+            CorpusFormat::Kytea => self.next_line().map(|line| line.map(|line| kytea_words(&line))),
+            CorpusFormat::Conllu => self
+                .next_conllu()
+                .map(|sentence| sentence.map(|sentence| sentence.words.join(" "))),
+        }
+    }
+}
+
+/// Converts a sentence in KyTea full annotation into words separated by spaces, keeping
+/// the surface of each word and unescaping it.
+fn kytea_words(line: &str) -> String {
+    let mut sentence = String::with_capacity(line.len());
+    let mut in_word = false;
+    let mut in_tag = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                // An escaped space is dropped, as it would split the word in the output.
+                if let Some(next) = chars.next().filter(|next| !next.is_whitespace()) {
+                    if !in_tag {
+                        if !in_word && !sentence.is_empty() {
+                            sentence.push(' ');
+                        }
+                        sentence.push(next);
+                        in_word = true;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                in_word = false;
+                in_tag = false;
+            }
+            '/' => in_tag = true,
+            c if !in_tag => {
+                if !in_word && !sentence.is_empty() {
+                    sentence.push(' ');
+                }
+                sentence.push(c);
+                in_word = true;
+            }
+            _ => {}
+        }
+    }
+    sentence
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(corpus: &str, format: CorpusFormat) -> Vec<String> {
+        sentences(corpus.as_bytes(), format).collect::<io::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        for format in [CorpusFormat::Plain, CorpusFormat::Kytea, CorpusFormat::Conllu] {
+            assert_eq!(format.to_string().parse::<CorpusFormat>().unwrap(), format);
+        }
+        assert_eq!("CoNLL-U".parse::<CorpusFormat>().unwrap(), CorpusFormat::Conllu);
+        assert!("xml".parse::<CorpusFormat>().is_err());
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(read("これ は\n\nテスト\n", CorpusFormat::Plain), ["これ は", "", "テスト"]);
+    }
+
+    #[test]
+    fn test_kytea() {
+        let corpus = "\
+これ/代名詞/これ は/助詞/は テスト/名詞/てすと 。/補助記号/。
+1\\/2/名詞 と\\\\/助詞 タグ なし
+";
+        assert_eq!(read(corpus, CorpusFormat::Kytea), ["これ は テスト 。", "1/2 と\\ タグ なし"]);
+    }
+
+    #[test]
+    fn test_conllu() {
+        let corpus = "\
+# sent_id = 1
+# text = これはテストです。
+1\tこれ\tこれ\tPRON\t_\t_\t3\tnsubj\t_\tSpaceAfter=No
+2\tは\tは\tADP\t_\t_\t1\tcase\t_\tSpaceAfter=No
+3\tテスト\tテスト\tNOUN\t_\t_\t0\troot\t_\tSpaceAfter=No
+3.1\tだ\tだ\tAUX\t_\t_\t_\t_\t3:cop\tSpaceAfter=No
+4\tです\tです\tAUX\t_\t_\t3\tcop\t_\tSpaceAfter=No
+5\t。\t。\tPUNCT\t_\t_\t3\tpunct\t_\tSpaceAfter=No
+
+
+1-2\tdel\t_\t_\t_\t_\t_\t_\t_\t_
+1\tde\tde\tADP\t_\t_\t3\tcase\t_\t_
+2\tel\tel\tDET\t_\t_\t3\tdet\t_\t_
+3\tmar\tmar\tNOUN\t_\t_\t0\troot\t_\t_
+";
+        assert_eq!(read(corpus, CorpusFormat::Conllu), ["これ は テスト です 。", "del mar"]);
+
+        let texts: Vec<String> = conllu_sentences(corpus.as_bytes())
+            .map(|sentence| sentence.unwrap().text)
+            .collect();
+        assert_eq!(texts, ["これはテストです。", "del mar"]);
+
+        let error = sentences("1\n".as_bytes(), CorpusFormat::Conllu).next().unwrap().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().starts_with("line 1:"), "{}", error);
+    }
+}
@@ -0,0 +1,347 @@
+//! Compact patches between two versions of a model.
+//!
+//! A model delta records the features added, changed and removed between a base model
+//! and a target model, along with changes to the bias and the metadata. Retraining on a
+//! slightly larger corpus changes a small part of the weights, so shipping the delta
+//! instead of the full model keeps regular updates to edge devices small:
+//!
+//! ```text
+//! #litsea-delta sha256=<hex>
+//! base <hex>
+//! target <hex>
+//! header #litsea-model sha256=<hex>
+//! meta <key> <value>
+//! unmeta <key>
+//! set <feature> <weight>
+//! del <feature>
+//! bias <value>
+//! ```
+//!
+//! Fields are separated by tabs. The first line holds the SHA-256 checksum of the rest
+//! of the delta. `base` and `target` are the checksums of the two model bodies (the
+//! content after the integrity header), so a delta is only applied to the model it was
+//! made from and reproduces the target byte for byte, including its header and
+//! signature. Weights are copied as written, so no precision is lost.
+//!
+//! Deltas are plain text and need only `alloc`, so they can also be applied on devices
+//! built without the `std` feature.
+//!
+//! ```
+//! use litsea::delta;
+//!
+//! let base = "UW4:は\t0.5\nUW4:が\t0.25\n-0.125\n";
+//! let target = "UW4:の\t0.3\nUW4:は\t0.75\n-0.5\n";
+//!
+//! let patch = delta::diff(base, target).unwrap();
+//! assert_eq!(delta::apply_delta(base, &patch).unwrap(), target);
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::error::{LitseaError, Result};
+use crate::integrity;
+use crate::util::sha256_hex;
+
+/// Prefix of the header line of a delta file.
+pub const HEADER_PREFIX: &str = "#litsea-delta";
+
+/// The content of a model file after its integrity header, with the lines kept as written.
+#[derive(Debug, Default, PartialEq)]
+struct ModelBody {
+    metadata: BTreeMap<String, String>,
+    weights: BTreeMap<String, String>,
+    bias: Option<String>,
+}
+
+impl ModelBody {
+    /// Parses the content of a model file after its header, as
+    /// [`AdaBoost::load_model_str`](crate::adaboost::AdaBoost::load_model_str) does.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Parse`] with the first line that cannot be parsed.
+    fn parse(body: &str) -> Result<Self> {
+        let mut model = ModelBody::default();
+        for (line_num, line) in body.lines().enumerate() {
+            if let Some(entry) = line.strip_prefix('#') {
+                let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+                model.metadata.insert(key.to_string(), value.trim_end_matches('\r').to_string());
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let h = parts.next().ok_or_else(|| LitseaError::Parse {
+                line: line_num + 1,
+                reason: "Empty line".to_string(),
+            })?;
+            match parts.next() {
+                Some(w) => {
+                    model.weights.insert(h.to_string(), w.to_string());
+                }
+                None => model.bias = Some(h.to_string()),
+            }
+        }
+        Ok(model)
+    }
+
+    /// Writes the model body in the order of
+    /// [`AdaBoost::save_model`](crate::adaboost::AdaBoost::save_model): metadata sorted by
+    /// key, weights sorted by feature, then the bias.
+    fn render(&self) -> String {
+        let mut body = String::new();
+        for (key, value) in &self.metadata {
+            body.push_str(&format!("#{} {}\n", key, value));
+        }
+        for (feature, weight) in &self.weights {
+            body.push_str(&format!("{}\t{}\n", feature, weight));
+        }
+        if let Some(bias) = &self.bias {
+            body.push_str(bias);
+            body.push('\n');
+        }
+        body
+    }
+}
+
+/// Splits a model file into its header line, if any, and its verified body.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the checksum in the header does not match the
+/// body.
+fn split_model(content: &str) -> Result<(Option<&str>, &str)> {
+    let (header, _) =
+        integrity::check_checksum(content.as_bytes()).map_err(LitseaError::ModelFormat)?;
+    match header {
+        Some(_) => {
+            let (line, body) = content.split_once('\n').unwrap_or((content, ""));
+            Ok((Some(line), body))
+        }
+        None => Ok((None, content)),
+    }
+}
+
+/// Computes the delta that turns `base` into `target`.
+///
+/// # Arguments
+/// * `base` - The content of the base model file, e.g. the model on the devices.
+/// * `target` - The content of the new model file.
+///
+/// # Returns
+/// The content of the delta file.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if either model fails its checksum or the target
+/// is not in the order written by
+/// [`AdaBoost::save_model`](crate::adaboost::AdaBoost::save_model), which the delta
+/// could not reproduce, or [`LitseaError::Parse`] if a model cannot be parsed.
+pub fn diff(base: &str, target: &str) -> Result<String> {
+    let (_, base_body) = split_model(base)?;
+    let (target_header, target_body) = split_model(target)?;
+    let from = ModelBody::parse(base_body)?;
+    let to = ModelBody::parse(target_body)?;
+    if to.render() != target_body {
+        return Err(LitseaError::ModelFormat(
+            "Target model is not in the order written by litsea; load and save it with \
+             litsea before computing a delta"
+                .to_string(),
+        ));
+    }
+
+    let mut delta = String::new();
+    delta.push_str(&format!("base\t{}\n", sha256_hex(base_body.as_bytes())));
+    delta.push_str(&format!("target\t{}\n", sha256_hex(target_body.as_bytes())));
+    if let Some(header) = target_header {
+        delta.push_str(&format!("header\t{}\n", header));
+    }
+    for (key, value) in &to.metadata {
+        if from.metadata.get(key) != Some(value) {
+            delta.push_str(&format!("meta\t{}\t{}\n", key, value));
+        }
+    }
+    for key in from.metadata.keys().filter(|key| !to.metadata.contains_key(*key)) {
+        delta.push_str(&format!("unmeta\t{}\n", key));
+    }
+    for (feature, weight) in &to.weights {
+        if from.weights.get(feature) != Some(weight) {
+            delta.push_str(&format!("set\t{}\t{}\n", feature, weight));
+        }
+    }
+    for feature in from.weights.keys().filter(|feature| !to.weights.contains_key(*feature)) {
+        delta.push_str(&format!("del\t{}\n", feature));
+    }
+    if from.bias != to.bias {
+        // A target without a bias line is written as an empty bias.
+        delta.push_str(&format!("bias\t{}\n", to.bias.as_deref().unwrap_or("")));
+    }
+
+    Ok(format!("{} sha256={}\n{}", HEADER_PREFIX, sha256_hex(delta.as_bytes()), delta))
+}
+
+/// Applies a delta made by [`diff`] to the model it was made from.
+///
+/// # Arguments
+/// * `base` - The content of the base model file.
+/// * `delta` - The content of the delta file.
+///
+/// # Returns
+/// The content of the target model file, identical to the one the delta was made from.
+///
+/// # Errors
+/// Returns [`LitseaError::Parse`] with the line, counted after the header of the delta,
+/// of an entry that cannot be parsed, or [`LitseaError::ModelFormat`] if the delta is
+/// corrupted, was made from a different model, or does not reproduce the target.
+pub fn apply_delta(base: &str, delta: &str) -> Result<String> {
+    let (header, ops) = delta.split_once('\n').unwrap_or((delta, ""));
+    let checksum = header
+        .strip_prefix(HEADER_PREFIX)
+        .and_then(|fields| fields.trim().strip_prefix("sha256="))
+        .ok_or_else(|| LitseaError::ModelFormat("Not a litsea delta file".to_string()))?;
+    if checksum != sha256_hex(ops.as_bytes()) {
+        return Err(LitseaError::ModelFormat(
+            "Delta checksum mismatch; the file is corrupted or has been modified".to_string(),
+        ));
+    }
+
+    let (_, base_body) = split_model(base)?;
+    let mut model = ModelBody::parse(base_body)?;
+    let mut expected_base = None;
+    let mut expected_target = None;
+    let mut target_header = None;
+    for (line_num, line) in ops.lines().enumerate() {
+        let fields: Vec<&str> = line.splitn(3, '\t').collect();
+        match fields.as_slice() {
+            ["base", hash] => expected_base = Some(*hash),
+            ["target", hash] => expected_target = Some(*hash),
+            ["header", header] => target_header = Some(*header),
+            ["meta", key, value] => {
+                model.metadata.insert(key.to_string(), value.to_string());
+            }
+            ["unmeta", key] => {
+                model.metadata.remove(*key);
+            }
+            ["set", feature, weight] => {
+                model.weights.insert(feature.to_string(), weight.to_string());
+            }
+            ["del", feature] => {
+                model.weights.remove(*feature);
+            }
+            ["bias", bias] => {
+                model.bias = (!bias.is_empty()).then(|| bias.to_string());
+            }
+            _ => {
+                return Err(LitseaError::Parse {
+                    line: line_num + 1,
+                    reason: format!("Invalid delta entry '{}'", line),
+                });
+            }
+        }
+    }
+
+    let actual = sha256_hex(base_body.as_bytes());
+    if expected_base != Some(actual.as_str()) {
+        return Err(LitseaError::ModelFormat(format!(
+            "Delta was made for model {}, not for this model ({})",
+            expected_base.unwrap_or("(unknown)"),
+            actual
+        )));
+    }
+    let body = model.render();
+    if expected_target != Some(sha256_hex(body.as_bytes()).as_str()) {
+        return Err(LitseaError::ModelFormat(
+            "Delta does not reproduce the target model".to_string(),
+        ));
+    }
+    Ok(match target_header {
+        Some(header) => format!("{}\n{}", header, body),
+        None => body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::adaboost::AdaBoost;
+
+    const BASE: &str = "#language japanese\nUW4:は\t0.5\nUW4:が\t0.25\nBC1:IK\t-0.125\n-0.125\n";
+    const TARGET: &str = "#source wiki\nBC1:IK\t-0.125\nUW4:の\t0.3\nUW4:は\t0.75\n-0.5\n";
+
+    fn with_header(body: &str) -> String {
+        String::from_utf8(integrity::add_header(body.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn test_diff_and_apply() {
+        let target = with_header(TARGET);
+        let patch = diff(BASE, &target).unwrap();
+        assert!(patch.starts_with(HEADER_PREFIX));
+        assert!(patch.contains("set\tUW4:の\t0.3\n"));
+        assert!(patch.contains("set\tUW4:は\t0.75\n"));
+        assert!(patch.contains("del\tUW4:が\n"));
+        assert!(patch.contains("meta\tsource\twiki\n"));
+        assert!(patch.contains("unmeta\tlanguage\n"));
+        assert!(patch.contains("bias\t-0.5\n"));
+        // Unchanged weights are not repeated.
+        assert!(!patch.contains("BC1:IK"));
+
+        let patched = apply_delta(BASE, &patch).unwrap();
+        assert_eq!(patched, target);
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(&patched).unwrap();
+        assert_eq!(learner.metadata("source"), Some("wiki"));
+    }
+
+    #[test]
+    fn test_identical_models() {
+        let patch = diff(TARGET, TARGET).unwrap();
+        assert_eq!(patch.lines().count(), 3);
+        assert_eq!(apply_delta(TARGET, &patch).unwrap(), TARGET);
+    }
+
+    #[test]
+    fn test_wrong_base() {
+        let patch = diff(BASE, TARGET).unwrap();
+        let err = apply_delta(TARGET, &patch).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("Delta was made for model"));
+    }
+
+    #[test]
+    fn test_corrupted_delta() {
+        let patch = diff(BASE, TARGET).unwrap().replace("0.75", "0.76");
+        let err = apply_delta(BASE, &patch).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("checksum mismatch"));
+        assert!(matches!(apply_delta(BASE, "UW4:は\t0.5\n"), Err(LitseaError::ModelFormat(_))));
+    }
+
+    #[test]
+    fn test_malformed_entry() {
+        let ops = format!("base\t{}\nset\tUW4:は\n", sha256_hex(BASE.as_bytes()));
+        let patch = format!("{} sha256={}\n{}", HEADER_PREFIX, sha256_hex(ops.as_bytes()), ops);
+        match apply_delta(BASE, &patch) {
+            Err(LitseaError::Parse { line, reason }) => {
+                assert_eq!(line, 2);
+                assert!(reason.contains("set\tUW4:は"));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_target_not_canonical() {
+        let err = diff(BASE, "UW4:は\t0.5\nBC1:IK\t0.25\n0.0\n").unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("not in the order written by litsea"));
+    }
+
+    #[test]
+    fn test_corrupted_model() {
+        let mut base = with_header(BASE);
+        base.push_str("UW4:で\t1.0\n");
+        let err = diff(&base, TARGET).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+}
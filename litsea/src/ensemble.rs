@@ -0,0 +1,237 @@
+//! Bagging of AdaBoost models, to reduce the variance of models trained on small corpora.
+//!
+//! An [`Ensemble`] trains several models, each on a bootstrap sample of the instances of
+//! a [`Dataset`], see [`AdaBoost::set_dataset_bootstrap`], and scores an instance with
+//! the mean of their scores. Each model is scored on the instances left out of its
+//! sample, which estimates the accuracy on unseen data without a development set.
+//!
+//! The score of a model is its bias plus the weights of the features of an instance, so
+//! the mean of the scores of the models is the score of a single model whose weights and
+//! bias are the means of theirs. [`Ensemble::average_into`] writes that model, which is
+//! saved, loaded and segments like any other; its `ensemble` metadata entry records the
+//! number of models:
+//!
+//! ```text
+//! #litsea-model sha256=<hex>
+//! #format 1
+//! #ensemble 5
+//! ...
+//! ```
+//!
+//! [`Trainer::set_ensemble`](crate::trainer::Trainer::set_ensemble) trains an ensemble
+//! in place of a single model, as `litsea train --ensemble` does.
+
+use crate::adaboost::{AdaBoost, Dataset, Metrics};
+use crate::control::TrainControl;
+use crate::error::LitseaError;
+use crate::util::SplitMix64;
+
+/// Models trained on bootstrap samples of the same features file.
+#[derive(Debug)]
+pub struct Ensemble {
+    members: Vec<AdaBoost>,
+}
+
+impl Ensemble {
+    /// Trains `size` models on bootstrap samples of a dataset, one after another. The
+    /// samples share the instances of the dataset.
+    ///
+    /// The models are trained from scratch with the settings of `learner`, such as the
+    /// threshold, the number of iterations and the boosting variant, like the folds of
+    /// [`Trainer::cross_validate`](crate::trainer::Trainer::cross_validate). Each is
+    /// sampled with a seed derived from `seed`, so the same seed trains the same models.
+    ///
+    /// # Arguments
+    /// * `learner` - The learner whose settings the models are trained with.
+    /// * `dataset` - The training instances, e.g. read with [`Dataset::load`].
+    /// * `size` - The number of models, at least 2.
+    /// * `seed` - The seed of the bootstrap sampling.
+    /// * `control` - Stops, pauses or resumes the training. A stop discards the model in
+    ///   progress and keeps the models trained before it.
+    ///
+    /// # Returns
+    /// The trained models.
+    ///
+    /// # Errors
+    /// Returns an error if `size` is less than 2.
+    pub fn train(
+        learner: &AdaBoost,
+        dataset: &Dataset,
+        size: usize,
+        seed: u64,
+        control: impl Into<TrainControl>,
+    ) -> crate::error::Result<Self> {
+        let control = control.into();
+        if size < 2 {
+            return Err(LitseaError::InvalidInput(format!(
+                "Invalid ensemble size {}: an ensemble needs at least 2 models",
+                size
+            )));
+        }
+        let mut members = Vec::with_capacity(size);
+        for i in 0..size {
+            let mut member = learner.untrained_copy();
+            member.set_dataset_bootstrap(dataset, SplitMix64::nth(seed, i as u64));
+            member.train(control.clone());
+            if control.is_stopped() {
+                break;
+            }
+            #[cfg(feature = "tracing")]
+            if let Some(metrics) = member.dev_metrics() {
+                trace_event!(
+                    info,
+                    member = i,
+                    precision = metrics.precision,
+                    recall = metrics.recall,
+                    "ensemble model"
+                );
+            }
+            members.push(member);
+        }
+        Ok(Ensemble { members })
+    }
+
+    /// Returns the models of the ensemble, in training order.
+    #[must_use]
+    pub fn members(&self) -> &[AdaBoost] {
+        &self.members
+    }
+
+    /// Returns the number of models.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns `true` if no model was trained, e.g. because training was stopped.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the metrics of each model on the instances left out of its sample.
+    ///
+    /// # Returns
+    /// The out-of-bag metrics, in training order.
+    #[must_use]
+    pub fn out_of_bag_metrics(&self) -> Vec<Metrics> {
+        self.members.iter().filter_map(AdaBoost::dev_metrics).collect()
+    }
+
+    /// Computes the mean of the scores of the models for the given attributes, as
+    /// [`AdaBoost::score_attributes`] does for one model.
+    ///
+    /// # Arguments
+    /// * `attributes` - The attributes to score.
+    ///
+    /// # Returns
+    /// The mean score, or 0 for an empty ensemble.
+    #[must_use]
+    pub fn score_attributes<I>(&self, attributes: I) -> f64
+    where
+        I: IntoIterator + Clone,
+        I::Item: AsRef<str>,
+    {
+        if self.members.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .members
+            .iter()
+            .map(|member| member.score_attributes(attributes.clone()))
+            .sum();
+        sum / self.members.len() as f64
+    }
+
+    /// Replaces the weights and the bias of a learner with the means of those of the
+    /// models, so that it scores every instance with the mean of their scores.
+    ///
+    /// The learner must have been initialized from the same dataset as the models, as
+    /// the learner of a [`Trainer`](crate::trainer::Trainer) is; features it lacks are
+    /// ignored. It records the number of models in its metadata, and is left
+    /// unchanged if the ensemble is empty.
+    ///
+    /// # Arguments
+    /// * `learner` - The learner to write the averaged model to.
+    pub fn average_into(&self, learner: &mut AdaBoost) {
+        learner.average(&self.members);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use crate::model_info::{self, ModelInfo};
+
+    fn features_file() -> std::io::Result<NamedTempFile> {
+        let mut file = NamedTempFile::new()?;
+        for i in 0..40 {
+            // "c" is noise, on half of the positive and half of the negative instances.
+            match i % 4 {
+                0 => writeln!(file, "1 a c")?,
+                1 => writeln!(file, "1 a")?,
+                2 => writeln!(file, "-1 b c")?,
+                _ => writeln!(file, "-1 b")?,
+            }
+        }
+        Ok(file)
+    }
+
+    #[test]
+    fn test_train_and_average() -> std::io::Result<()> {
+        let dataset = Dataset::load(features_file()?.path())?;
+        let template = AdaBoost::new(0.0, 10);
+        let ensemble = Ensemble::train(&template, &dataset, 3, 7, TrainControl::new())?;
+        assert_eq!(ensemble.len(), 3);
+        assert_eq!(ensemble.out_of_bag_metrics().len(), 3);
+        for metrics in ensemble.out_of_bag_metrics() {
+            assert_eq!(metrics.accuracy, 100.0);
+        }
+
+        // The same seed draws the same samples.
+        let again = Ensemble::train(&template, &dataset, 3, 7, TrainControl::new())?;
+        for (a, b) in ensemble.members().iter().zip(again.members()) {
+            assert_eq!(a.weights().collect::<Vec<_>>(), b.weights().collect::<Vec<_>>());
+        }
+
+        let mut learner = AdaBoost::new(0.0, 10);
+        learner.set_dataset(&dataset);
+        ensemble.average_into(&mut learner);
+        for attributes in [vec!["a"], vec!["b", "c"], vec!["a", "c"], vec![]] {
+            let mean = ensemble.score_attributes(attributes.iter());
+            assert!((learner.score_attributes(attributes.iter()) - mean).abs() < 1e-9);
+        }
+        assert!(learner.score_attributes(["a"]) > 0.0);
+        assert_eq!(learner.get_metrics().accuracy, 100.0);
+
+        let info = ModelInfo::from_model(&learner).unwrap();
+        assert_eq!(info.ensemble, Some(3));
+        assert_eq!(info.iterations, Some(10));
+        assert!(learner.metadata(model_info::DATE_KEY).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_size_and_stop() -> std::io::Result<()> {
+        let dataset = Dataset::load(features_file()?.path())?;
+        let template = AdaBoost::new(0.0, 10);
+        assert!(Ensemble::train(&template, &dataset, 1, 0, TrainControl::new()).is_err());
+
+        let control = TrainControl::new();
+        control.stop();
+        let ensemble = Ensemble::train(&template, &dataset, 3, 0, control)?;
+        assert!(ensemble.is_empty());
+        assert_eq!(ensemble.score_attributes(["a"]), 0.0);
+
+        let mut learner = AdaBoost::new(0.0, 10);
+        learner.set_dataset(&dataset);
+        ensemble.average_into(&mut learner);
+        assert!(learner.metadata(model_info::ENSEMBLE_KEY).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//! The error type of the library.
+//!
+//! The functions that load models, dictionaries, configurations and training data, such
+//! as [`AdaBoost::initialize`](crate::adaboost::AdaBoost::initialize),
+//! [`AdaBoost::load_model`](crate::adaboost::AdaBoost::load_model) and the constructors
+//! of [`Trainer`](crate::trainer::Trainer), return a [`Result`] with a [`LitseaError`],
+//! which tells malformed content apart from I/O failures, which are
+//! [`LitseaError::Io`]:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use litsea::adaboost::AdaBoost;
+//! use litsea::error::LitseaError;
+//!
+//! let mut learner = AdaBoost::new(0.01, 100);
+//! match learner.initialize(Path::new("features.txt")) {
+//!     Ok(()) => {}
+//!     Err(LitseaError::Parse { line, reason }) => eprintln!("line {}: {}", line, reason),
+//!     Err(e) => eprintln!("{}", e),
+//! }
+//! ```
+//!
+//! Errors of the Parquet and Arrow libraries, with the `parquet` feature, are wrapped in
+//! [`LitseaError::Io`].
+//!
+//! Functions that only write reports to an [`std::io::Write`] return
+//! [`std::io::Result`]. A [`LitseaError`] converts into an [`std::io::Error`] that
+//! carries it, so `?` also works in functions returning `std::io::Result`, and
+//! `LitseaError::from` recovers it.
+
+use alloc::string::String;
+use core::fmt;
+
+/// The cause of a failure in the library.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LitseaError {
+    /// Reading or writing a file failed.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// A line of a model, features file, dictionary or other input could not be parsed.
+    Parse {
+        /// The line number, starting at 1, counted after the model header.
+        line: usize,
+        /// What is wrong with the line.
+        reason: String,
+    },
+    /// A model is not a valid model, e.g. its checksum or signature does not match.
+    ModelFormat(String),
+    /// An argument or setting is out of range, or a configuration is invalid.
+    InvalidInput(String),
+}
+
+/// A result with a [`LitseaError`].
+pub type Result<T> = core::result::Result<T, LitseaError>;
+
+impl fmt::Display for LitseaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LitseaError::Io(e) => e.fmt(f),
+            LitseaError::Parse { line, reason } => write!(f, "line {}: {}", line, reason),
+            LitseaError::ModelFormat(reason) | LitseaError::InvalidInput(reason) => {
+                f.write_str(reason)
+            }
+        }
+    }
+}
+
+impl core::error::Error for LitseaError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            LitseaError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for LitseaError {
+    /// Recovers the [`LitseaError`] carried by an I/O error, or wraps the I/O error.
+    fn from(e: std::io::Error) -> Self {
+        match e.downcast::<LitseaError>() {
+            Ok(e) => e,
+            Err(e) => LitseaError::Io(e),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<parquet::errors::ParquetError> for LitseaError {
+    /// Wraps a Parquet error as an I/O error of the kind
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData).
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        LitseaError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<arrow_schema::ArrowError> for LitseaError {
+    /// Recovers the I/O error carried by an Arrow error, or wraps the Arrow error as an
+    /// I/O error of the kind [`InvalidData`](std::io::ErrorKind::InvalidData).
+    fn from(e: arrow_schema::ArrowError) -> Self {
+        match e {
+            arrow_schema::ArrowError::IoError(_, e) => LitseaError::Io(e),
+            e => LitseaError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<LitseaError> for std::io::Error {
+    /// Converts into an I/O error carrying the [`LitseaError`], with the kind
+    /// [`InvalidData`](std::io::ErrorKind::InvalidData) for malformed content and
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) for invalid arguments.
+    fn from(e: LitseaError) -> Self {
+        let kind = match e {
+            LitseaError::Io(e) => return e,
+            LitseaError::Parse { .. } | LitseaError::ModelFormat(_) => {
+                std::io::ErrorKind::InvalidData
+            }
+            LitseaError::InvalidInput(_) => std::io::ErrorKind::InvalidInput,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    use std::io;
+
+    #[test]
+    fn test_io_round_trip() {
+        let e = io::Error::from(LitseaError::Parse {
+            line: 3,
+            reason: "Invalid label".into(),
+        });
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "line 3: Invalid label");
+        match LitseaError::from(e) {
+            LitseaError::Parse { line, reason } => {
+                assert_eq!(line, 3);
+                assert_eq!(reason, "Invalid label");
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let e = io::Error::from(LitseaError::InvalidInput("bad".into()));
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_from_plain_io_error() {
+        let e = LitseaError::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(matches!(&e, LitseaError::Io(inner) if inner.kind() == io::ErrorKind::NotFound));
+        assert!(core::error::Error::source(&e).is_some());
+        // Converting back gives the original error.
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,287 @@
+//! Evaluation of a model on a held-out gold corpus.
+//!
+//! The metrics reported by training are measured on the training instances, so they
+//! cannot show overfitting. [`evaluate`] segments the text of a gold corpus the model has
+//! not seen and scores the result at two levels:
+//!
+//! - **boundaries**: whether each position between two characters is a word boundary,
+//!   as in training.
+//! - **words**: whether each word spans exactly the same characters as a gold word, the
+//!   usual measure of word segmentation, where one wrong boundary costs two words.
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::adaboost::{Confusion, Metrics};
+use crate::segmenter::Segmenter;
+use crate::util::map_chunks;
+
+/// Minimum number of sentences segmented per thread by [`evaluate`].
+const EVALUATE_CHUNK_LEN: usize = 64;
+
+/// Segmentation accuracy on a gold corpus, returned by [`evaluate`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct Evaluation {
+    /// Metrics of the boundary decisions between the characters of each sentence.
+    pub boundaries: Metrics,
+    /// Harmonic mean of the boundary precision and recall in percentage (%).
+    pub boundary_f1: f64,
+    /// Number of words in the gold corpus.
+    pub gold_words: usize,
+    /// Number of words in the segmentation.
+    pub system_words: usize,
+    /// Number of words of the segmentation that match a gold word exactly.
+    pub correct_words: usize,
+    /// Share of the segmented words that are correct in percentage (%).
+    pub word_precision: f64,
+    /// Share of the gold words that were found in percentage (%).
+    pub word_recall: f64,
+    /// Harmonic mean of the word precision and recall in percentage (%).
+    pub word_f1: f64,
+    /// Number of non-empty sentences.
+    pub num_sentences: usize,
+    /// Share of the sentences segmented without any error in percentage (%).
+    pub sentence_accuracy: f64,
+}
+
+/// Counts of one part of the corpus, summed across threads.
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    confusion: Confusion,
+    gold_words: usize,
+    system_words: usize,
+    correct_words: usize,
+    num_sentences: usize,
+    correct_sentences: usize,
+}
+
+impl Counts {
+    fn merge(self, other: Counts) -> Counts {
+        Counts {
+            confusion: self.confusion.merge(other.confusion),
+            gold_words: self.gold_words + other.gold_words,
+            system_words: self.system_words + other.system_words,
+            correct_words: self.correct_words + other.correct_words,
+            num_sentences: self.num_sentences + other.num_sentences,
+            correct_sentences: self.correct_sentences + other.correct_sentences,
+        }
+    }
+}
+
+/// Returns `part / whole` in percentage, or 0 if `whole` is 0.
+fn percent(part: usize, whole: usize) -> f64 {
+    part as f64 / whole.max(1) as f64 * 100.0
+}
+
+/// Returns the harmonic mean of a precision and a recall, or 0 if both are 0.
+pub(crate) fn f1(precision: f64, recall: f64) -> f64 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+impl From<Counts> for Evaluation {
+    fn from(c: Counts) -> Self {
+        let boundaries: Metrics = c.confusion.into();
+        let word_precision = percent(c.correct_words, c.system_words);
+        let word_recall = percent(c.correct_words, c.gold_words);
+        Evaluation {
+            boundary_f1: f1(boundaries.precision, boundaries.recall),
+            boundaries,
+            gold_words: c.gold_words,
+            system_words: c.system_words,
+            correct_words: c.correct_words,
+            word_precision,
+            word_recall,
+            word_f1: f1(word_precision, word_recall),
+            num_sentences: c.num_sentences,
+            sentence_accuracy: percent(c.correct_sentences, c.num_sentences),
+        }
+    }
+}
+
+/// Evaluates a segmenter against a gold corpus.
+///
+/// Each sentence of the corpus is written with words separated by spaces, as in the
+/// corpus used for training. The spaces are removed, the text is segmented, and the
+/// result is compared with the gold words. Large corpora are split across threads.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to evaluate.
+/// * `sentences` - The gold sentences, with words separated by spaces. Empty sentences
+///   are skipped.
+///
+/// # Returns
+/// The boundary and word metrics over all sentences.
+pub fn evaluate<'a, I>(segmenter: &Segmenter, sentences: I) -> Evaluation
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let sentences: Vec<&str> = sentences.into_iter().collect();
+    map_chunks(sentences.len(), EVALUATE_CHUNK_LEN, |range| {
+        let mut counts = Counts::default();
+        for sentence in &sentences[range] {
+            let gold: Vec<&str> = sentence.split(' ').filter(|w| !w.is_empty()).collect();
+            if gold.is_empty() {
+                continue;
+            }
+            let text = gold.concat();
+            let predicted = segmenter.segment(&text);
+
+            let gold_boundaries = boundaries(gold.iter().copied());
+            let predicted_boundaries = boundaries(predicted.iter().map(String::as_str));
+            let num_chars = text.chars().count();
+
+            // The position before the first character is always a boundary and is not
+            // scored.
+            for i in 1..num_chars {
+                counts
+                    .confusion
+                    .add(gold_boundaries.contains(&i), predicted_boundaries.contains(&i));
+            }
+
+            let gold_spans = spans(gold.iter().copied());
+            let predicted_spans = spans(predicted.iter().map(String::as_str));
+            counts.gold_words += gold_spans.len();
+            counts.system_words += predicted_spans.len();
+            counts.correct_words += predicted_spans.intersection(&gold_spans).count();
+            counts.num_sentences += 1;
+            if gold_boundaries == predicted_boundaries {
+                counts.correct_sentences += 1;
+            }
+        }
+        counts
+    })
+    .into_iter()
+    .fold(Counts::default(), Counts::merge)
+    .into()
+}
+
+/// Returns the character offsets at which each word after the first one starts.
+fn boundaries<'a, I>(words: I) -> HashSet<usize>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut offset = 0;
+    let mut out = HashSet::new();
+    for word in words {
+        if offset > 0 {
+            out.insert(offset);
+        }
+        offset += word.chars().count();
+    }
+    out
+}
+
+/// Returns the start and end character offsets of each word.
+fn spans<'a, I>(words: I) -> HashSet<(usize, usize)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut offset = 0;
+    words
+        .into_iter()
+        .map(|word| {
+            let start = offset;
+            offset += word.chars().count();
+            (start, offset)
+        })
+        .collect()
+}
+
+/// Writes an evaluation in the format printed by `litsea evaluate`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `evaluation` - The result of [`evaluate`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_report<W: Write + ?Sized>(writer: &mut W, evaluation: &Evaluation) -> io::Result<()> {
+    let b = &evaluation.boundaries;
+    writeln!(writer, "Boundaries:")?;
+    writeln!(
+        writer,
+        "  Precision: {:.2}% ( {} / {} )",
+        b.precision,
+        b.true_positives,
+        b.true_positives + b.false_positives
+    )?;
+    writeln!(
+        writer,
+        "  Recall: {:.2}% ( {} / {} )",
+        b.recall,
+        b.true_positives,
+        b.true_positives + b.false_negatives
+    )?;
+    writeln!(writer, "  F1: {:.2}%", evaluation.boundary_f1)?;
+    writeln!(writer, "Words:")?;
+    writeln!(
+        writer,
+        "  Precision: {:.2}% ( {} / {} )",
+        evaluation.word_precision, evaluation.correct_words, evaluation.system_words
+    )?;
+    writeln!(
+        writer,
+        "  Recall: {:.2}% ( {} / {} )",
+        evaluation.word_recall, evaluation.correct_words, evaluation.gold_words
+    )?;
+    writeln!(writer, "  F1: {:.2}%", evaluation.word_f1)?;
+    writeln!(
+        writer,
+        "Sentences: {:.2}% without errors ( {} )",
+        evaluation.sentence_accuracy, evaluation.num_sentences
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::ha_segmenter;
+
+    #[test]
+    fn test_evaluate() {
+        // The model splits before and after "は" only, so "ペンです" is one word.
+        let evaluation = evaluate(&ha_segmenter(), ["これ は ペン", "", "これ は ペン です"]);
+
+        assert_eq!(evaluation.boundaries.true_positives, 4);
+        assert_eq!(evaluation.boundaries.false_negatives, 1);
+        assert!((evaluation.boundary_f1 - 800.0 / 9.0).abs() < 1e-9);
+
+        assert_eq!(evaluation.gold_words, 7);
+        assert_eq!(evaluation.system_words, 6);
+        assert_eq!(evaluation.correct_words, 5);
+        assert!((evaluation.word_precision - 500.0 / 6.0).abs() < 1e-9);
+        assert!((evaluation.word_recall - 500.0 / 7.0).abs() < 1e-9);
+        assert!((evaluation.word_f1 - 1000.0 / 13.0).abs() < 1e-9);
+
+        assert_eq!(evaluation.num_sentences, 2);
+        assert!((evaluation.sentence_accuracy - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_evaluate_empty() {
+        let evaluation = evaluate(&ha_segmenter(), []);
+        assert_eq!(evaluation.num_sentences, 0);
+        assert_eq!(evaluation.word_f1, 0.0);
+        assert_eq!(evaluation.boundary_f1, 0.0);
+    }
+
+    #[test]
+    fn test_write_report() {
+        let evaluation = evaluate(&ha_segmenter(), ["これ は ペン です"]);
+        let mut out = Vec::new();
+        write_report(&mut out, &evaluation).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.contains("Boundaries:\n  Precision: 100.00% ( 2 / 2 )\n"));
+        assert!(
+            out.contains("Words:\n  Precision: 66.67% ( 2 / 3 )\n  Recall: 50.00% ( 2 / 4 )\n")
+        );
+        assert!(out.ends_with("Sentences: 0.00% without errors ( 1 )\n"));
+    }
+}
@@ -1,18 +1,54 @@
-use std::cell::RefCell;
+#[cfg(feature = "parquet")]
+use std::collections::HashMap;
 use std::collections::HashSet;
-use std::error::Error;
+#[cfg(feature = "parquet")]
 use std::fs::File;
-use std::io::{self, BufRead, Write};
+use std::io::Write;
 use std::path::Path;
 
-use crate::language::Language;
-use crate::segmenter::Segmenter;
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+#[cfg(feature = "parquet")]
+use arrow_array::builder::{ListBuilder, UInt32Builder};
+#[cfg(feature = "parquet")]
+use arrow_array::{ArrayRef, Int8Array, RecordBatch, StringArray, UInt32Array, UInt64Array};
+#[cfg(feature = "parquet")]
+use arrow_schema::{DataType, Field, Schema};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+#[cfg(feature = "parquet")]
+use parquet::basic::Compression;
+#[cfg(feature = "parquet")]
+use parquet::file::properties::WriterProperties;
+
+use crate::compression;
+use crate::corpus::{self, CorpusFormat};
+use crate::error;
+use crate::language::{Language, LatinClasses};
+use crate::normalize::Normalizer;
+use crate::segmenter::{FeatureTemplates, Segmenter};
+use crate::util::map_chunks_limited;
+
+/// Number of corpus lines read at a time by [`Extractor::extract`] and split across
+/// threads.
+const EXTRACT_BATCH_LEN: usize = 16384;
+
+/// Minimum number of corpus lines processed per thread by [`Extractor::extract`].
+const EXTRACT_CHUNK_LEN: usize = 256;
+
+/// Number of rows per record batch when writing Parquet files.
+#[cfg(feature = "parquet")]
+const PARQUET_BATCH_SIZE: usize = 65536;
 
 /// Extractor struct for processing text data and extracting features.
 /// It reads sentences from a corpus file, segments them into words,
 /// and writes the extracted features to a specified output file.
 pub struct Extractor {
     segmenter: Segmenter,
+    normalizer: Normalizer,
+    corpus_format: CorpusFormat,
+    num_threads: usize,
 }
 
 impl Default for Extractor {
@@ -36,10 +72,94 @@ impl Extractor {
     pub fn new(language: Language) -> Self {
         Extractor {
             segmenter: Segmenter::new(language, None),
+            normalizer: Normalizer::default(),
+            corpus_format: CorpusFormat::default(),
+            num_threads: 0,
         }
     }
 
+    /// Sets the normalization applied to each sentence of the corpus before extraction.
+    /// Text segmented with the trained model must be normalized the same way.
+    ///
+    /// # Arguments
+    /// * `normalizer` - The normalization settings.
+    pub fn set_normalizer(&mut self, normalizer: Normalizer) {
+        self.normalizer = normalizer;
+    }
+
+    /// Sets the format of the corpus files read by [`extract`](Self::extract) and
+    /// [`extract_parquet`](Self::extract_parquet), plain by default.
+    ///
+    /// # Arguments
+    /// * `format` - The corpus format.
+    pub fn set_corpus_format(&mut self, format: CorpusFormat) {
+        self.corpus_format = format;
+    }
+
+    /// Sets the feature templates to extract. Models trained on the features must be
+    /// used with a segmenter set to the same templates.
+    ///
+    /// # Arguments
+    /// * `templates` - The feature templates.
+    pub fn set_feature_templates(&mut self, templates: FeatureTemplates) {
+        self.segmenter.set_feature_templates(templates);
+    }
+
+    /// Sets how Latin letters are divided into character types. Models trained on the
+    /// features must be used with a segmenter set to the same classes.
+    ///
+    /// # Arguments
+    /// * `classes` - The Latin classes.
+    pub fn set_latin_classes(&mut self, classes: LatinClasses) {
+        self.segmenter.set_latin_classes(classes);
+    }
+
+    /// Sets the maximum number of threads [`extract`](Self::extract) splits the lines of
+    /// the corpus across. The features are written in the order of the corpus whatever
+    /// the number of threads, so the output does not depend on it.
+    ///
+    /// # Arguments
+    /// * `num_threads` - The maximum number of threads, or 0 for one per available core,
+    ///   the default.
+    pub fn set_num_threads(&mut self, num_threads: usize) {
+        self.num_threads = num_threads;
+    }
+
+    /// Extracts the training instances of sentences in memory, normalizing each sentence
+    /// and skipping empty ones as [`extract`](Self::extract) does.
+    ///
+    /// # Arguments
+    /// * `lines` - Sentences with words separated by spaces.
+    ///
+    /// # Returns
+    /// The attributes and the label of each instance, in the order of the sentences.
+    pub fn extract_instances<I, S>(&self, lines: I) -> Vec<(HashSet<String>, i8)>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let sentences: Vec<String> = lines
+            .into_iter()
+            .map(|line| self.normalizer.normalize(line.as_ref().trim()).into_owned())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let chunks =
+            map_chunks_limited(sentences.len(), EXTRACT_CHUNK_LEN, self.num_threads, |range| {
+                let mut instances = Vec::new();
+                for sentence in &sentences[range] {
+                    self.segmenter.add_corpus_with_writer(sentence, |attributes, label| {
+                        instances.push((attributes, label));
+                    });
+                }
+                instances
+            });
+        chunks.into_iter().flatten().collect()
+    }
+
     /// Extracts features from a corpus file and writes them to a specified output file.
+    /// Files whose names end in `.gz` or `.zst` are decompressed and compressed, see
+    /// [`compression`](crate::compression). The corpus is read in the format set with
+    /// [`set_corpus_format`](Self::set_corpus_format).
     ///
     /// # Arguments
     /// * `corpus_path` - The path to the input corpus file containing sentences.
@@ -47,56 +167,223 @@ impl Extractor {
     ///
     /// # Returns
     /// Returns a Result indicating success or failure.
-    pub fn extract(
-        &mut self,
-        corpus_path: &Path,
-        features_path: &Path,
-    ) -> Result<(), Box<dyn Error>> {
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`](crate::error::LitseaError::Io) if a file cannot be read
+    /// or written, or [`LitseaError::Parse`](crate::error::LitseaError::Parse) if the
+    /// corpus is not in the set format.
+    pub fn extract(&mut self, corpus_path: &Path, features_path: &Path) -> error::Result<()> {
+        trace_span!(INFO, "extract", corpus = %corpus_path.display());
         // Read sentences from the corpus file.
-        // Each line is treated as a separate sentence.
-        let corpus_file = File::open(corpus_path)?;
-        let corpus = io::BufReader::new(corpus_file);
+        let corpus = compression::open(corpus_path)?;
 
         // Create a file to write the features
-        let features_file = File::create(features_path)?;
-        let mut features = io::BufWriter::new(features_file);
+        let mut features = compression::create(features_path)?;
 
-        // Capture write errors from the closure via RefCell
-        let write_error: RefCell<Option<io::Error>> = RefCell::new(None);
+        // Read the corpus in batches, extract the features of each batch into per-thread
+        // buffers and write the buffers in the order of the lines.
+        let mut lines = corpus::sentences(corpus, self.corpus_format);
+        let mut batch: Vec<String> = Vec::with_capacity(EXTRACT_BATCH_LEN);
+        loop {
+            batch.clear();
+            let mut num_read = 0;
+            for line in lines.by_ref().take(EXTRACT_BATCH_LEN) {
+                num_read += 1;
+                let line = line?;
+                let line = self.normalizer.normalize(line.trim());
+                if !line.is_empty() {
+                    batch.push(line.into_owned());
+                }
+            }
+            if num_read == 0 {
+                break;
+            }
 
-        // Learner function to write features
-        // It takes a set of attributes and a label, and writes them to the output file
-        let mut learner = |attributes: HashSet<String>, label: i8| {
-            if write_error.borrow().is_some() {
-                return;
+            let buffers =
+                map_chunks_limited(batch.len(), EXTRACT_CHUNK_LEN, self.num_threads, |range| {
+                    let mut buffer = String::new();
+                    for line in &batch[range] {
+                        self.segmenter.add_corpus_with_writer(line, |attributes, label| {
+                            write_instance(&mut buffer, attributes, label);
+                        });
+                    }
+                    buffer
+                });
+            for buffer in buffers {
+                features.write_all(buffer.as_bytes())?;
             }
-            let mut attrs: Vec<String> = attributes.into_iter().collect();
-            attrs.sort();
-            let mut line = vec![label.to_string()];
-            line.extend(attrs);
-            if let Err(e) = writeln!(features, "{}", line.join("\t")) {
-                *write_error.borrow_mut() = Some(e);
+        }
+        features.finish()?;
+
+        Ok(())
+    }
+
+    /// Extracts features from a corpus file and writes them as Parquet files.
+    ///
+    /// Identical instances are written once, so the output is usually much smaller than
+    /// the text format. The instances file has the columns `label` (`int8`, 1 for a
+    /// boundary and -1 otherwise), `features` (`list<uint32>`, sorted feature IDs) and
+    /// `count` (`uint32`, the number of occurrences of the instance). The dictionary file
+    /// has the columns `id` (`uint32`), `feature` (`utf8`) and `count` (`uint64`, the
+    /// number of instances containing the feature). Feature IDs are assigned in the order
+    /// of the feature names.
+    ///
+    /// # Arguments
+    /// * `corpus_path` - The path to the input corpus file containing sentences.
+    /// * `instances_path` - The path to the output Parquet file of instances.
+    /// * `dictionary_path` - The path to the output Parquet file of feature names.
+    ///
+    /// # Returns
+    /// Returns a Result indicating success or failure.
+    #[cfg(feature = "parquet")]
+    pub fn extract_parquet(
+        &mut self,
+        corpus_path: &Path,
+        instances_path: &Path,
+        dictionary_path: &Path,
+    ) -> error::Result<()> {
+        trace_span!(INFO, "extract_parquet", corpus = %corpus_path.display());
+        let corpus = compression::open(corpus_path)?;
+
+        let mut feature_ids: HashMap<String, u32> = HashMap::new();
+        let mut instance_rows: HashMap<(i8, Vec<u32>), usize> = HashMap::new();
+        let mut instances: Vec<(i8, Vec<u32>, u32)> = Vec::new();
+
+        let mut learner = |attributes: HashSet<String>, label: i8| {
+            let mut ids: Vec<u32> = attributes
+                .into_iter()
+                .map(|attr| {
+                    let next = feature_ids.len() as u32;
+                    *feature_ids.entry(attr).or_insert(next)
+                })
+                .collect();
+            ids.sort_unstable();
+            let key = (label, ids);
+            match instance_rows.get(&key) {
+                Some(&row) => instances[row].2 += 1,
+                None => {
+                    instances.push((label, key.1.clone(), 1));
+                    instance_rows.insert(key, instances.len() - 1);
+                }
             }
         };
 
-        for line in corpus.lines() {
+        for line in corpus::sentences(corpus, self.corpus_format) {
             let line = line?;
-            let line = line.trim();
+            let line = self.normalizer.normalize(line.trim());
             if !line.is_empty() {
-                self.segmenter.add_corpus_with_writer(line, &mut learner);
+                self.segmenter.add_corpus_with_writer(&line, &mut learner);
             }
-            // Stop processing further lines if a write error occurred.
-            if write_error.borrow().is_some() {
-                break;
+        }
+        drop(instance_rows);
+        trace_event!(
+            debug,
+            num_features = feature_ids.len(),
+            num_instances = instances.len(),
+            "collected instances"
+        );
+
+        // Renumber features by name so that IDs do not depend on the corpus order.
+        let mut features: Vec<(String, u32)> = feature_ids.into_iter().collect();
+        features.sort_unstable();
+        let mut remap = vec![0u32; features.len()];
+        for (new_id, (_, old_id)) in features.iter().enumerate() {
+            remap[*old_id as usize] = new_id as u32;
+        }
+        let mut feature_counts = vec![0u64; features.len()];
+        for (_, ids, count) in &mut instances {
+            for id in ids.iter_mut() {
+                *id = remap[*id as usize];
+                feature_counts[*id as usize] += u64::from(*count);
             }
+            ids.sort_unstable();
         }
+        let names: Vec<String> = features.into_iter().map(|(name, _)| name).collect();
+
+        write_parquet(&instances, &names, &feature_counts, instances_path, dictionary_path)
+    }
+}
+
+/// Appends an instance to `buffer` as a line of the text features format: the label and
+/// the sorted attributes, separated by tabs.
+fn write_instance(buffer: &mut String, attributes: HashSet<String>, label: i8) {
+    let mut attrs: Vec<String> = attributes.into_iter().collect();
+    attrs.sort();
+    buffer.push_str(&label.to_string());
+    for attr in attrs {
+        buffer.push('\t');
+        buffer.push_str(&attr);
+    }
+    buffer.push('\n');
+}
 
-        if let Some(e) = write_error.into_inner() {
-            return Err(Box::new(e));
+/// Writes deduplicated instances and their feature dictionary as Parquet files, in the
+/// format of [`Extractor::extract_parquet`].
+///
+/// # Arguments
+/// * `instances` - The label, sorted feature IDs and number of occurrences of each instance.
+/// * `names` - The feature names, indexed by ID.
+/// * `feature_counts` - The number of instances containing each feature, indexed by ID.
+/// * `instances_path` - The path to the output Parquet file of instances.
+/// * `dictionary_path` - The path to the output Parquet file of feature names.
+///
+/// # Errors
+/// Returns an error if a file cannot be written.
+#[cfg(feature = "parquet")]
+pub(crate) fn write_parquet(
+    instances: &[(i8, Vec<u32>, u32)],
+    names: &[String],
+    feature_counts: &[u64],
+    instances_path: &Path,
+    dictionary_path: &Path,
+) -> error::Result<()> {
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+
+    let item = Arc::new(Field::new("item", DataType::UInt32, false));
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("label", DataType::Int8, false),
+        Field::new("features", DataType::List(item.clone()), false),
+        Field::new("count", DataType::UInt32, false),
+    ]));
+    let mut writer = ArrowWriter::try_new(
+        File::create(instances_path)?,
+        schema.clone(),
+        Some(properties.clone()),
+    )?;
+    for chunk in instances.chunks(PARQUET_BATCH_SIZE) {
+        let labels = Int8Array::from_iter_values(chunk.iter().map(|(label, _, _)| *label));
+        let mut ids = ListBuilder::new(UInt32Builder::new()).with_field(item.clone());
+        for (_, row, _) in chunk {
+            ids.values().append_slice(row);
+            ids.append(true);
         }
+        let counts = UInt32Array::from_iter_values(chunk.iter().map(|(_, _, count)| *count));
+        let columns: Vec<ArrayRef> =
+            vec![Arc::new(labels), Arc::new(ids.finish()), Arc::new(counts)];
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    }
+    writer.close()?;
 
-        Ok(())
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt32, false),
+        Field::new("feature", DataType::Utf8, false),
+        Field::new("count", DataType::UInt64, false),
+    ]));
+    let mut writer =
+        ArrowWriter::try_new(File::create(dictionary_path)?, schema.clone(), Some(properties))?;
+    for (start, chunk) in (0..names.len())
+        .step_by(PARQUET_BATCH_SIZE)
+        .zip(names.chunks(PARQUET_BATCH_SIZE))
+    {
+        let ids = UInt32Array::from_iter_values((start..start + chunk.len()).map(|i| i as u32));
+        let names = StringArray::from_iter_values(chunk);
+        let counts = UInt64Array::from(feature_counts[start..start + chunk.len()].to_vec());
+        let columns: Vec<ArrayRef> = vec![Arc::new(ids), Arc::new(names), Arc::new(counts)];
+        writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
     }
+    writer.close()?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -146,4 +433,126 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_extract_num_threads() -> Result<(), Box<dyn std::error::Error>> {
+        // Enough lines for several threads, with an empty line that must be skipped.
+        let mut corpus_file = NamedTempFile::new()?;
+        for i in 0..1000 {
+            writeln!(corpus_file, "これ は テスト {} です 。", i)?;
+            if i == 500 {
+                writeln!(corpus_file)?;
+            }
+        }
+        corpus_file.as_file().sync_all()?;
+
+        let mut outputs = Vec::new();
+        for num_threads in [1, 4] {
+            let features_file = NamedTempFile::new()?;
+            let mut extractor = Extractor::default();
+            extractor.set_num_threads(num_threads);
+            extractor.extract(corpus_file.path(), features_file.path())?;
+            let mut output = String::new();
+            File::open(features_file.path())?.read_to_string(&mut output)?;
+            outputs.push(output);
+        }
+
+        assert!(!outputs[0].is_empty());
+        assert_eq!(outputs[0], outputs[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_corpus_format() -> Result<(), Box<dyn std::error::Error>> {
+        let corpora = [
+            (CorpusFormat::Plain, "これ は テスト です 。\n"),
+            (CorpusFormat::Kytea, "これ/代名詞 は/助詞 テスト/名詞 です/助動詞 。/補助記号\n"),
+            (
+                CorpusFormat::Conllu,
+                "# text = これはテストです。\n1\tこれ\n2\tは\n3\tテスト\n4\tです\n5\t。\n\n",
+            ),
+        ];
+        let mut outputs = Vec::new();
+        for (format, corpus) in corpora {
+            let mut corpus_file = NamedTempFile::new()?;
+            corpus_file.write_all(corpus.as_bytes())?;
+            corpus_file.as_file().sync_all()?;
+            let features_file = NamedTempFile::new()?;
+            let mut extractor = Extractor::default();
+            extractor.set_corpus_format(format);
+            extractor.extract(corpus_file.path(), features_file.path())?;
+            let mut output = String::new();
+            File::open(features_file.path())?.read_to_string(&mut output)?;
+            outputs.push(output);
+        }
+
+        assert!(!outputs[0].is_empty());
+        assert_eq!(outputs[0], outputs[1]);
+        assert_eq!(outputs[0], outputs[2]);
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_extract_parquet() -> Result<(), Box<dyn std::error::Error>> {
+        use arrow_array::cast::AsArray;
+        use arrow_array::types::{Int8Type, UInt32Type, UInt64Type};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut corpus_file = NamedTempFile::new()?;
+        writeln!(corpus_file, "これ は テスト です 。")?;
+        writeln!(corpus_file, "これ は テスト です 。")?;
+        corpus_file.as_file().sync_all()?;
+        let instances_file = NamedTempFile::new()?;
+        let dictionary_file = NamedTempFile::new()?;
+
+        let mut extractor = Extractor::default();
+        extractor.extract_parquet(
+            corpus_file.path(),
+            instances_file.path(),
+            dictionary_file.path(),
+        )?;
+
+        let mut ids = Vec::new();
+        let mut features = Vec::new();
+        let mut feature_counts = Vec::new();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(dictionary_file.path())?)?
+            .build()?;
+        for batch in reader {
+            let batch = batch?;
+            ids.extend(batch.column(0).as_primitive::<UInt32Type>().values().iter().copied());
+            features
+                .extend(batch.column(1).as_string::<i32>().iter().map(|f| f.unwrap().to_string()));
+            feature_counts
+                .extend(batch.column(2).as_primitive::<UInt64Type>().values().iter().copied());
+        }
+        assert_eq!(ids, (0..features.len() as u32).collect::<Vec<_>>());
+        assert!(features.windows(2).all(|w| w[0] < w[1]), "Features should be sorted");
+        assert!(features.iter().any(|f| f == "UW4:テ"));
+
+        let mut total = 0;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(instances_file.path())?)?
+            .build()?;
+        for batch in reader {
+            let batch = batch?;
+            let labels = batch.column(0).as_primitive::<Int8Type>();
+            let lists = batch.column(1).as_list::<i32>();
+            let counts = batch.column(2).as_primitive::<UInt32Type>();
+            for row in 0..batch.num_rows() {
+                assert!(labels.value(row) == 1 || labels.value(row) == -1);
+                let ids = lists.value(row);
+                let ids = ids.as_primitive::<UInt32Type>();
+                assert!(ids.values().windows(2).all(|w| w[0] < w[1]));
+                assert!(ids.values().iter().all(|id| (*id as usize) < features.len()));
+                // Both sentences are identical, so every instance occurs twice.
+                assert_eq!(counts.value(row), 2);
+                total += counts.value(row);
+            }
+        }
+        // One instance per character after the first, for each sentence.
+        assert_eq!(total, 2 * 8);
+        assert!(feature_counts.iter().all(|c| *c >= 2));
+
+        Ok(())
+    }
 }
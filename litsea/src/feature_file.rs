@@ -0,0 +1,690 @@
+//! Utilities for features files that have already been extracted.
+//!
+//! Extracting features from a large corpus takes hours, while pruning rare features
+//! from the result only takes a pass or two over the file. [`shrink_features`] keeps the
+//! features that occur in at least a given number of instances, so a minimum count can
+//! be tried without extracting again:
+//!
+//! ```no_run
+//! use std::path::Path;
+//!
+//! use litsea::feature_file::shrink_features;
+//!
+//! let stats = shrink_features(Path::new("wiki.features"), Path::new("wiki.min5.features"), 5)?;
+//! println!("kept {} of {} features", stats.kept_features, stats.features);
+//! # Ok::<(), litsea::error::LitseaError>(())
+//! ```
+//!
+//! [`sort_features`] sorts and merges several features files, e.g. the extractions of
+//! the parts of a corpus, with bounded memory. With deduplication, identical instances
+//! are written once with their number of occurrences in a count column after the label,
+//! separated by tabs like the features:
+//!
+//! ```text
+//! 1 3 BC1:HI UW4:は
+//! ```
+//!
+//! Training reads the count as the weight of the instance with
+//! [`AdaBoost::set_weighted`](crate::adaboost::AdaBoost::set_weighted).
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "parquet")]
+use arrow_array::cast::AsArray;
+#[cfg(feature = "parquet")]
+use arrow_array::types::{Int8Type, UInt32Type, UInt64Type};
+#[cfg(feature = "parquet")]
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::compression::{self, FileWriter};
+use crate::error::LitseaError;
+#[cfg(feature = "parquet")]
+use crate::extractor::write_parquet;
+
+/// What [`shrink_features`] kept of a features file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShrinkStats {
+    /// Number of instances, counting duplicates.
+    pub instances: u64,
+    /// Number of distinct features before shrinking.
+    pub features: usize,
+    /// Number of distinct features kept.
+    pub kept_features: usize,
+    /// Number of feature occurrences before shrinking.
+    pub occurrences: u64,
+    /// Number of feature occurrences kept.
+    pub kept_occurrences: u64,
+}
+
+impl ShrinkStats {
+    /// Returns the percentage of feature occurrences kept, which is roughly the size of
+    /// the output relative to the input.
+    #[must_use]
+    pub fn kept_occurrence_ratio(&self) -> f64 {
+        self.kept_occurrences as f64 / self.occurrences.max(1) as f64 * 100.0
+    }
+}
+
+/// Removes the features that occur in fewer than `min_count` instances from a text
+/// features file. Instances left without features are kept, since their labels still
+/// count towards the bias.
+///
+/// The input is read twice, once to count the features and once to rewrite it, so only
+/// the distinct features are held in memory.
+///
+/// # Arguments
+/// * `input` - The features file written by `litsea extract`.
+/// * `output` - The features file to write. It must not be the input.
+/// * `min_count` - The minimum number of instances a feature must occur in.
+///
+/// # Returns
+/// The number of features and occurrences before and after shrinking.
+///
+/// # Errors
+/// Returns an error if the input cannot be read or the output cannot be written.
+pub fn shrink_features(
+    input: &Path,
+    output: &Path,
+    min_count: u64,
+) -> crate::error::Result<ShrinkStats> {
+    trace_span!(INFO, "shrink_features", path = %input.display(), min_count);
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    let mut instances = 0;
+    for line in compression::open(input)?.lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        if parts.next().is_none() {
+            continue;
+        }
+        instances += 1;
+        for h in parts {
+            match counts.get_mut(h) {
+                Some(count) => *count += 1,
+                None => {
+                    counts.insert(h.to_string(), 1);
+                }
+            }
+        }
+    }
+
+    let mut writer = compression::create(output)?;
+    for line in compression::open(input)?.lines() {
+        let line = line?;
+        let mut parts = line.split_whitespace();
+        let Some(label) = parts.next() else {
+            continue;
+        };
+        writer.write_all(label.as_bytes())?;
+        for h in parts.filter(|h| counts.get(*h).is_some_and(|&count| count >= min_count)) {
+            write!(writer, "\t{}", h)?;
+        }
+        writeln!(writer)?;
+    }
+    writer.finish()?;
+
+    let kept = counts.values().filter(|&&count| count >= min_count);
+    Ok(ShrinkStats {
+        instances,
+        features: counts.len(),
+        kept_features: kept.clone().count(),
+        occurrences: counts.values().sum(),
+        kept_occurrences: kept.sum(),
+    })
+}
+
+/// Removes the features that occur in fewer than `min_count` instances from features
+/// written as Parquet by `litsea extract --format parquet`. The kept features are
+/// renumbered into a reduced dictionary, and instances that become identical are merged.
+///
+/// # Arguments
+/// * `instances_input` - The Parquet file of instances.
+/// * `dictionary_input` - The Parquet file of feature names.
+/// * `instances_output` - The Parquet file of instances to write.
+/// * `dictionary_output` - The Parquet file of the reduced dictionary to write.
+/// * `min_count` - The minimum number of instances a feature must occur in.
+///
+/// # Returns
+/// The number of features and occurrences before and after shrinking.
+///
+/// # Errors
+/// Returns an error if an input cannot be read, refers to an unknown feature, or an
+/// output cannot be written.
+#[cfg(feature = "parquet")]
+pub fn shrink_features_parquet(
+    instances_input: &Path,
+    dictionary_input: &Path,
+    instances_output: &Path,
+    dictionary_output: &Path,
+    min_count: u64,
+) -> crate::error::Result<ShrinkStats> {
+    trace_span!(INFO, "shrink_features_parquet", path = %instances_input.display(), min_count);
+    // New ID of each old feature ID, if the feature is kept.
+    let mut remap: Vec<Option<u32>> = Vec::new();
+    let mut names = Vec::new();
+    let mut feature_counts = Vec::new();
+    let reader =
+        ParquetRecordBatchReaderBuilder::try_new(File::open(dictionary_input)?)?.build()?;
+    for batch in reader {
+        let batch = batch?;
+        let ids = batch.column(0).as_primitive::<UInt32Type>();
+        let features = batch.column(1).as_string::<i32>();
+        let counts = batch.column(2).as_primitive::<UInt64Type>();
+        for row in 0..batch.num_rows() {
+            let id = ids.value(row) as usize;
+            if remap.len() <= id {
+                remap.resize(id + 1, None);
+            }
+            if counts.value(row) >= min_count {
+                remap[id] = Some(names.len() as u32);
+                names.push(features.value(row).to_string());
+                feature_counts.push(counts.value(row));
+            }
+        }
+    }
+
+    let mut stats = ShrinkStats {
+        instances: 0,
+        features: remap.len(),
+        kept_features: names.len(),
+        occurrences: 0,
+        kept_occurrences: 0,
+    };
+    let mut instance_rows: HashMap<(i8, Vec<u32>), usize> = HashMap::new();
+    let mut instances: Vec<(i8, Vec<u32>, u32)> = Vec::new();
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(instances_input)?)?.build()?;
+    for batch in reader {
+        let batch = batch?;
+        let labels = batch.column(0).as_primitive::<Int8Type>();
+        let lists = batch.column(1).as_list::<i32>();
+        let counts = batch.column(2).as_primitive::<UInt32Type>();
+        for row in 0..batch.num_rows() {
+            let count = counts.value(row);
+            let ids = lists.value(row);
+            let ids = ids.as_primitive::<UInt32Type>().values();
+            let mut kept = Vec::with_capacity(ids.len());
+            for &id in ids {
+                let new_id = remap.get(id as usize).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown feature ID {} in the instances", id),
+                    )
+                })?;
+                kept.extend(*new_id);
+            }
+            stats.instances += u64::from(count);
+            stats.occurrences += ids.len() as u64 * u64::from(count);
+            stats.kept_occurrences += kept.len() as u64 * u64::from(count);
+
+            // New IDs keep the order of the old ones, so `kept` is still sorted.
+            let key = (labels.value(row), kept);
+            match instance_rows.get(&key) {
+                Some(&row) => instances[row].2 += count,
+                None => {
+                    instances.push((key.0, key.1.clone(), count));
+                    instance_rows.insert(key, instances.len() - 1);
+                }
+            }
+        }
+    }
+    drop(instance_rows);
+
+    write_parquet(&instances, &names, &feature_counts, instances_output, dictionary_output)?;
+    Ok(stats)
+}
+
+/// Options of [`sort_features`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SortOptions {
+    /// Merge identical instances into one line with their number of occurrences in a
+    /// count column.
+    pub dedup: bool,
+    /// Whether the inputs have a count column, e.g. because they were deduplicated before.
+    pub counted_input: bool,
+    /// Approximate number of bytes of instances to sort in memory at once. Larger inputs
+    /// are sorted in runs written to temporary files, which are then merged.
+    pub memory_limit: usize,
+    /// Directory for the temporary files (default: the system temporary directory).
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        SortOptions {
+            dedup: false,
+            counted_input: false,
+            memory_limit: 256 * 1024 * 1024,
+            temp_dir: None,
+        }
+    }
+}
+
+/// What [`sort_features`] read and wrote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortStats {
+    /// Number of instances read, counting the occurrences in a count column.
+    pub instances: u64,
+    /// Number of lines written.
+    pub lines: u64,
+    /// Number of sorted runs written to temporary files; 0 if the input fit in memory.
+    pub runs: usize,
+}
+
+/// Bytes counted for each buffered instance on top of its text.
+const SORT_ENTRY_OVERHEAD: usize = 48;
+
+/// Distinguishes the temporary files of concurrent sorts in the same process.
+static NEXT_SORT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Parses a line of a features file into its canonical form, the label followed by the
+/// features in byte order, and its count. `line_number` starts at 1 and is only used in
+/// errors.
+fn canonical_instance(
+    line: &str,
+    line_number: usize,
+    counted: bool,
+) -> crate::error::Result<Option<(String, u64)>> {
+    let mut parts = line.split_whitespace();
+    let Some(label) = parts.next() else {
+        return Ok(None);
+    };
+    let count = if counted {
+        let count = parts.next().unwrap_or("");
+        count.parse().map_err(|e| LitseaError::Parse {
+            line: line_number,
+            reason: format!("Invalid count '{}': {}", count, e),
+        })?
+    } else {
+        1
+    };
+    let mut features: Vec<&str> = parts.collect();
+    features.sort_unstable();
+    let mut instance = String::with_capacity(line.len());
+    instance.push_str(label);
+    for h in features {
+        instance.push('\t');
+        instance.push_str(h);
+    }
+    Ok(Some((instance, count)))
+}
+
+/// Splits a line of a sorted run into its count and instance.
+fn parse_run_line(line: &str) -> io::Result<(u64, String)> {
+    line.split_once('\t')
+        .and_then(|(count, instance)| Some((count.parse().ok()?, instance.to_string())))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Corrupted sort run"))
+}
+
+/// Writes sorted instances to a features file, merging identical neighbours if asked to.
+struct SortedWriter {
+    writer: FileWriter,
+    dedup: bool,
+    counted: bool,
+    pending: Option<(String, u64)>,
+    lines: u64,
+}
+
+impl SortedWriter {
+    fn push(&mut self, instance: String, count: u64) -> io::Result<()> {
+        if self.dedup {
+            if let Some((pending, pending_count)) = &mut self.pending {
+                if *pending == instance {
+                    *pending_count += count;
+                    return Ok(());
+                }
+            }
+            if let Some((pending, pending_count)) = self.pending.replace((instance, count)) {
+                self.write(&pending, pending_count)?;
+            }
+            Ok(())
+        } else {
+            self.write(&instance, count)
+        }
+    }
+
+    fn write(&mut self, instance: &str, count: u64) -> io::Result<()> {
+        self.lines += 1;
+        if !self.counted {
+            return writeln!(self.writer, "{}", instance);
+        }
+        // The count goes after the label.
+        let (label, features) = instance.split_once('\t').unwrap_or((instance, ""));
+        if features.is_empty() {
+            writeln!(self.writer, "{}\t{}", label, count)
+        } else {
+            writeln!(self.writer, "{}\t{}\t{}", label, count, features)
+        }
+    }
+
+    fn finish(mut self) -> io::Result<u64> {
+        if let Some((pending, count)) = self.pending.take() {
+            self.write(&pending, count)?;
+        }
+        self.writer.finish()?;
+        Ok(self.lines)
+    }
+}
+
+/// Sorts, merges and optionally deduplicates features files with bounded memory.
+///
+/// Each instance is written as its label followed by its features in byte order, and
+/// the instances are sorted by that text, so the byte order of UTF-8 feature strings is
+/// kept regardless of the locale. Instances that do not fit in
+/// [`SortOptions::memory_limit`] are sorted in runs in temporary files, which are merged
+/// and removed afterwards.
+///
+/// With [`SortOptions::dedup`] or [`SortOptions::counted_input`], the output has a count
+/// column after the label: `<label>\t<count>\t<features>`.
+///
+/// # Arguments
+/// * `inputs` - The features files to sort and merge.
+/// * `output` - The features file to write. It must not be one of the inputs.
+/// * `options` - Whether to deduplicate, the memory limit and the temporary directory.
+///
+/// # Returns
+/// The number of instances read, lines written and runs used.
+///
+/// # Errors
+/// Returns [`LitseaError::Parse`] if a count is invalid, or [`LitseaError::Io`] if an
+/// input cannot be read or the output or a temporary file cannot be written.
+pub fn sort_features(
+    inputs: &[&Path],
+    output: &Path,
+    options: &SortOptions,
+) -> crate::error::Result<SortStats> {
+    trace_span!(INFO, "sort_features", inputs = inputs.len(), dedup = options.dedup);
+    let temp_dir = options.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+    let run_prefix = format!(
+        "litsea-sort-{}-{}",
+        std::process::id(),
+        NEXT_SORT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let run_prefix = temp_dir.join(run_prefix);
+    let mut runs = Vec::new();
+    let result = sort_runs(inputs, output, options, &run_prefix, &mut runs);
+    for run in &runs {
+        let _ = std::fs::remove_file(run);
+    }
+    result
+}
+
+fn sort_runs(
+    inputs: &[&Path],
+    output: &Path,
+    options: &SortOptions,
+    run_prefix: &Path,
+    runs: &mut Vec<PathBuf>,
+) -> crate::error::Result<SortStats> {
+    let mut instances = 0;
+    let mut buffer: Vec<(String, u64)> = Vec::new();
+    let mut buffered_bytes = 0;
+    for input in inputs {
+        for (i, line) in compression::open(input)?.lines().enumerate() {
+            let Some((instance, count)) = canonical_instance(&line?, i + 1, options.counted_input)?
+            else {
+                continue;
+            };
+            instances += count;
+            buffered_bytes += instance.len() + SORT_ENTRY_OVERHEAD;
+            buffer.push((instance, count));
+            if buffered_bytes >= options.memory_limit {
+                runs.push(write_run(&mut buffer, run_prefix, runs.len())?);
+                buffered_bytes = 0;
+            }
+        }
+    }
+
+    let mut writer = SortedWriter {
+        writer: compression::create(output)?,
+        dedup: options.dedup,
+        counted: options.dedup || options.counted_input,
+        pending: None,
+        lines: 0,
+    };
+    if runs.is_empty() {
+        buffer.sort_unstable();
+        for (instance, count) in buffer {
+            writer.push(instance, count)?;
+        }
+        return Ok(SortStats {
+            instances,
+            lines: writer.finish()?,
+            runs: 0,
+        });
+    }
+    if !buffer.is_empty() {
+        runs.push(write_run(&mut buffer, run_prefix, runs.len())?);
+    }
+
+    // Merge the runs, taking the smallest head line each time.
+    let mut readers = runs
+        .iter()
+        .map(|run| File::open(run).map(|file| BufReader::new(file).lines()))
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut heads = BinaryHeap::new();
+    for (i, reader) in readers.iter_mut().enumerate() {
+        if let Some(line) = reader.next() {
+            let (count, instance) = parse_run_line(&line?)?;
+            heads.push(Reverse((instance, i, count)));
+        }
+    }
+    while let Some(Reverse((instance, i, count))) = heads.pop() {
+        writer.push(instance, count)?;
+        if let Some(line) = readers[i].next() {
+            let (count, instance) = parse_run_line(&line?)?;
+            heads.push(Reverse((instance, i, count)));
+        }
+    }
+    Ok(SortStats {
+        instances,
+        lines: writer.finish()?,
+        runs: runs.len(),
+    })
+}
+
+/// Sorts the buffered instances and writes them to a temporary run file as
+/// `<count>\t<instance>` lines, emptying the buffer.
+fn write_run(
+    buffer: &mut Vec<(String, u64)>,
+    run_prefix: &Path,
+    index: usize,
+) -> io::Result<PathBuf> {
+    buffer.sort_unstable();
+    let path = run_prefix.with_extension(format!("{}.run", index));
+    let mut writer = BufWriter::new(File::create(&path)?);
+    for (instance, count) in buffer.drain(..) {
+        writeln!(writer, "{}\t{}", count, instance)?;
+    }
+    writer.flush()?;
+    trace_event!(debug, path = %path.display(), "wrote sort run");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_shrink_features() -> io::Result<()> {
+        let input = NamedTempFile::new()?;
+        std::fs::write(
+            input.path(),
+            "1\tUW4:は\tBW1:はテ\n-1\tUW4:テ\tBW1:テス\n-1\tUW4:は\tUW5:テ\n\n1\tBW1:テス\n",
+        )?;
+        let output = NamedTempFile::new()?;
+
+        let stats = shrink_features(input.path(), output.path(), 2)?;
+        assert_eq!(
+            std::fs::read_to_string(output.path())?,
+            "1\tUW4:は\n-1\tBW1:テス\n-1\tUW4:は\n1\tBW1:テス\n"
+        );
+        assert_eq!(
+            stats,
+            ShrinkStats {
+                instances: 4,
+                features: 5,
+                kept_features: 2,
+                occurrences: 7,
+                kept_occurrences: 4,
+            }
+        );
+        assert!((stats.kept_occurrence_ratio() - 400.0 / 7.0).abs() < 1e-9);
+
+        // Nothing is removed with a minimum count of 1.
+        let stats = shrink_features(input.path(), output.path(), 1)?;
+        assert_eq!(stats.kept_features, stats.features);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_features() -> io::Result<()> {
+        let first = NamedTempFile::new()?;
+        std::fs::write(first.path(), "1\tUW4:は\tBW1:はテ\n-1\tUW4:テ\n\n1\tBW1:はテ\tUW4:は\n")?;
+        let second = NamedTempFile::new()?;
+        std::fs::write(second.path(), "-1\tUW4:テ\n1\tUW4:が\n-1\n")?;
+        let inputs = [first.path(), second.path()];
+        let output = NamedTempFile::new()?;
+
+        let stats = sort_features(&inputs, output.path(), &SortOptions::default())?;
+        assert_eq!(
+            stats,
+            SortStats {
+                instances: 6,
+                lines: 6,
+                runs: 0
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(output.path())?,
+            "-1\n-1\tUW4:テ\n-1\tUW4:テ\n1\tBW1:はテ\tUW4:は\n1\tBW1:はテ\tUW4:は\n1\tUW4:が\n"
+        );
+
+        let dedup = SortOptions {
+            dedup: true,
+            ..SortOptions::default()
+        };
+        let stats = sort_features(&inputs, output.path(), &dedup)?;
+        assert_eq!(
+            stats,
+            SortStats {
+                instances: 6,
+                lines: 4,
+                runs: 0
+            }
+        );
+        let expected = "-1\t1\n-1\t2\tUW4:テ\n1\t2\tBW1:はテ\tUW4:は\n1\t1\tUW4:が\n";
+        assert_eq!(std::fs::read_to_string(output.path())?, expected);
+
+        // A tiny memory limit sorts every instance in its own run, with the same result.
+        let temp_dir = tempfile::tempdir()?;
+        let external = SortOptions {
+            memory_limit: 1,
+            temp_dir: Some(temp_dir.path().to_path_buf()),
+            ..dedup.clone()
+        };
+        let stats = sort_features(&inputs, output.path(), &external)?;
+        assert_eq!(
+            stats,
+            SortStats {
+                instances: 6,
+                lines: 4,
+                runs: 6
+            }
+        );
+        assert_eq!(std::fs::read_to_string(output.path())?, expected);
+        assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 0, "runs should be removed");
+
+        // Counted files are merged by adding up their counts.
+        let counted = NamedTempFile::new()?;
+        std::fs::copy(output.path(), counted.path())?;
+        let merged = SortOptions {
+            counted_input: true,
+            ..external
+        };
+        let stats = sort_features(&[counted.path(), counted.path()], output.path(), &merged)?;
+        assert_eq!(
+            stats,
+            SortStats {
+                instances: 12,
+                lines: 4,
+                runs: 8
+            }
+        );
+        assert_eq!(
+            std::fs::read_to_string(output.path())?,
+            "-1\t2\n-1\t4\tUW4:テ\n1\t4\tBW1:はテ\tUW4:は\n1\t2\tUW4:が\n"
+        );
+
+        std::fs::write(counted.path(), "1\tUW4:は\n")?;
+        let err = sort_features(&[counted.path()], output.path(), &merged).unwrap_err();
+        assert!(
+            matches!(&err, LitseaError::Parse { line: 1, reason } if reason.contains("Invalid count 'UW4:は'")),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_shrink_features_parquet() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::extractor::Extractor;
+
+        let corpus = NamedTempFile::new()?;
+        std::fs::write(corpus.path(), "これ は テスト です 。\nこれ は 本 です 。\n")?;
+        let instances = NamedTempFile::new()?;
+        let dictionary = NamedTempFile::new()?;
+        Extractor::default().extract_parquet(corpus.path(), instances.path(), dictionary.path())?;
+
+        let shrunk_instances = NamedTempFile::new()?;
+        let shrunk_dictionary = NamedTempFile::new()?;
+        let stats = shrink_features_parquet(
+            instances.path(),
+            dictionary.path(),
+            shrunk_instances.path(),
+            shrunk_dictionary.path(),
+            2,
+        )?;
+        assert_eq!(stats.instances, 8 + 6);
+        assert!(stats.kept_features < stats.features);
+
+        // The reduced dictionary is numbered from 0 and only has features in both sentences.
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(shrunk_dictionary.path())?)?
+                .build()?;
+        let mut ids = Vec::new();
+        for batch in reader {
+            let batch = batch?;
+            ids.extend(batch.column(0).as_primitive::<UInt32Type>().values().iter().copied());
+            assert!(batch.column(2).as_primitive::<UInt64Type>().values().iter().all(|c| *c >= 2));
+        }
+        assert_eq!(ids, (0..stats.kept_features as u32).collect::<Vec<_>>());
+
+        let mut total = 0;
+        let mut occurrences = 0;
+        let reader =
+            ParquetRecordBatchReaderBuilder::try_new(File::open(shrunk_instances.path())?)?
+                .build()?;
+        for batch in reader {
+            let batch = batch?;
+            let lists = batch.column(1).as_list::<i32>();
+            let counts = batch.column(2).as_primitive::<UInt32Type>();
+            for row in 0..batch.num_rows() {
+                let ids = lists.value(row);
+                let ids = ids.as_primitive::<UInt32Type>();
+                assert!(ids.values().iter().all(|id| *id < stats.kept_features as u32));
+                total += u64::from(counts.value(row));
+                occurrences += ids.len() as u64 * u64::from(counts.value(row));
+            }
+        }
+        assert_eq!(total, stats.instances);
+        assert_eq!(occurrences, stats.kept_occurrences);
+        Ok(())
+    }
+}
@@ -0,0 +1,355 @@
+//! Rules that keep tokens such as numbers, URLs and emoji in one word.
+//!
+//! The classifier decides every boundary on its own, so where it is uncertain it may
+//! split a token that is never split in practice, such as `2024年` into `2 024 年`.
+//! [`GroupingRules`] forbid boundaries inside such tokens, whatever their score; the
+//! boundaries around them are still decided by the model:
+//!
+//! ```
+//! use litsea::grouping::GroupingRules;
+//! use litsea::language::Language;
+//! use litsea::segmenter::Segmenter;
+//!
+//! let mut segmenter = Segmenter::new(Language::Japanese, None);
+//! segmenter.set_grouping_rules("digits,latin".parse().unwrap());
+//! assert_eq!(segmenter.segment("2024年"), ["2024", "年"]);
+//! assert_eq!(
+//!     segmenter.grouping_rules(),
+//!     GroupingRules { digits: true, latin: true, ..Default::default() }
+//! );
+//! ```
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+/// The kinds of tokens that are never split. All rules are off by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct GroupingRules {
+    /// Runs of ASCII and full-width digits, with the decimal and thousands separators
+    /// between them, as in `3.14` and `1,000`.
+    pub digits: bool,
+    /// `http://`, `https://` and `www.` URLs, up to the first character that cannot be
+    /// part of a URL and without trailing punctuation.
+    pub urls: bool,
+    /// Email addresses, as in `user@example.com`.
+    pub emails: bool,
+    /// Emoji sequences: zero width joiner sequences, variation selectors, skin tone
+    /// modifiers, keycaps, tag sequences and flag pairs.
+    pub emoji: bool,
+    /// Runs of Latin letters, including accented and full-width ones.
+    pub latin: bool,
+}
+
+/// The names of the rules, in the order they are written.
+const RULE_NAMES: [&str; 5] = ["digits", "urls", "emails", "emoji", "latin"];
+
+impl GroupingRules {
+    /// Returns the rules with every rule enabled.
+    #[must_use]
+    pub fn all() -> Self {
+        GroupingRules {
+            digits: true,
+            urls: true,
+            emails: true,
+            emoji: true,
+            latin: true,
+        }
+    }
+
+    /// Returns `true` if no rule is enabled.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == GroupingRules::default()
+    }
+
+    /// Finds the characters that must stay in the same word as the character before
+    /// them.
+    ///
+    /// # Arguments
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// One flag per character of the sentence, `true` where no boundary may be placed
+    /// before the character. Empty if no rule is enabled.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::grouping::GroupingRules;
+    ///
+    /// let rules = GroupingRules { digits: true, ..Default::default() };
+    /// assert_eq!(rules.joined("12年"), [false, true, false]);
+    /// ```
+    #[must_use]
+    pub fn joined(&self, sentence: &str) -> Vec<bool> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let chars: Vec<char> = sentence.chars().collect();
+        let mut joined = vec![false; chars.len()];
+        for k in 1..chars.len() {
+            let (prev, ch) = (chars[k - 1], chars[k]);
+            joined[k] = (self.digits && is_digit(prev) && is_digit(ch))
+                || (self.latin && is_latin_letter(prev) && is_latin_letter(ch))
+                || (self.emoji && joins_emoji(&chars, k));
+        }
+        if self.digits {
+            // A separator joins the digits on both sides of it.
+            for k in 1..chars.len().saturating_sub(1) {
+                if is_digit_separator(chars[k]) && is_digit(chars[k - 1]) && is_digit(chars[k + 1])
+                {
+                    joined[k] = true;
+                    joined[k + 1] = true;
+                }
+            }
+        }
+        if self.urls {
+            for (start, end) in url_spans(&chars) {
+                joined[start + 1..end].fill(true);
+            }
+        }
+        if self.emails {
+            for (start, end) in email_spans(&chars) {
+                joined[start + 1..end].fill(true);
+            }
+        }
+        joined
+    }
+}
+
+impl fmt::Display for GroupingRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let enabled = [self.digits, self.urls, self.emails, self.emoji, self.latin];
+        let names: Vec<&str> = RULE_NAMES
+            .iter()
+            .zip(enabled)
+            .filter_map(|(name, on)| on.then_some(*name))
+            .collect();
+        if names.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&names.join(","))
+        }
+    }
+}
+
+impl FromStr for GroupingRules {
+    type Err = String;
+
+    /// Parses a comma-separated list of rules, such as `digits,urls`, or `all` or `none`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rules = GroupingRules::default();
+        for name in s.split(',').map(str::trim) {
+            match name.to_ascii_lowercase().as_str() {
+                "none" | "" => {}
+                "all" => rules = GroupingRules::all(),
+                "digits" => rules.digits = true,
+                "urls" => rules.urls = true,
+                "emails" => rules.emails = true,
+                "emoji" => rules.emoji = true,
+                "latin" => rules.latin = true,
+                _ => return Err(format!("Unsupported grouping rule: {}", name)),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// Returns `true` for ASCII and full-width digits.
+fn is_digit(ch: char) -> bool {
+    ch.is_ascii_digit() || ('０'..='９').contains(&ch)
+}
+
+/// Returns `true` for the decimal and thousands separators of numbers.
+fn is_digit_separator(ch: char) -> bool {
+    matches!(ch, '.' | ',' | '．' | '，')
+}
+
+/// Returns `true` for the letters of the ASCII, Latin-1, Latin Extended-A and -B and
+/// full-width Latin blocks.
+fn is_latin_letter(ch: char) -> bool {
+    ch.is_ascii_alphabetic()
+        || (('\u{00C0}'..='\u{024F}').contains(&ch) && ch != '×' && ch != '÷')
+        || ('Ａ'..='Ｚ').contains(&ch)
+        || ('ａ'..='ｚ').contains(&ch)
+}
+
+/// Returns `true` for the regional indicator symbols that make up flags in pairs.
+fn is_regional_indicator(ch: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&ch)
+}
+
+/// Returns `true` if the character at `k` continues the emoji sequence before it.
+fn joins_emoji(chars: &[char], k: usize) -> bool {
+    let ch = chars[k];
+    if chars[k - 1] == '\u{200D}'
+        || matches!(ch, '\u{200D}' | '\u{FE0E}' | '\u{FE0F}' | '\u{20E3}')
+        || ('\u{1F3FB}'..='\u{1F3FF}').contains(&ch)
+        || ('\u{E0020}'..='\u{E007F}').contains(&ch)
+    {
+        return true;
+    }
+    // A flag is a pair of regional indicators, so the second of each pair joins.
+    if is_regional_indicator(ch) {
+        let run = chars[..k].iter().rev().take_while(|c| is_regional_indicator(**c)).count();
+        return run % 2 == 1;
+    }
+    false
+}
+
+/// Returns `true` for the ASCII characters that may appear in a URL.
+fn is_url_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(ch)
+}
+
+/// Returns `true` if the characters at `k` start with `prefix`, ignoring ASCII case.
+fn starts_with(chars: &[char], k: usize, prefix: &str) -> bool {
+    let mut rest = chars[k..].iter();
+    prefix.chars().all(|p| rest.next().is_some_and(|c| c.eq_ignore_ascii_case(&p)))
+}
+
+/// Finds the URLs in the characters, as half-open character ranges.
+fn url_spans(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut k = 0;
+    while k < chars.len() {
+        let starts_url = (k == 0 || !chars[k - 1].is_ascii_alphanumeric())
+            && ["http://", "https://", "www."].iter().any(|p| starts_with(chars, k, p));
+        if !starts_url {
+            k += 1;
+            continue;
+        }
+        let mut end = k;
+        while end < chars.len() && is_url_char(chars[end]) {
+            end += 1;
+        }
+        while end > k && matches!(chars[end - 1], '.' | ',' | ':' | ';' | '!' | '?' | ')' | '\'') {
+            end -= 1;
+        }
+        spans.push((k, end));
+        k = end.max(k + 1);
+    }
+    spans
+}
+
+/// Finds the email addresses in the characters, as half-open character ranges.
+fn email_spans(chars: &[char]) -> Vec<(usize, usize)> {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+    let mut spans = Vec::new();
+    for (at, _) in chars.iter().enumerate().filter(|(_, c)| **c == '@') {
+        let start = at - chars[..at].iter().rev().take_while(|c| is_local(**c)).count();
+        let mut end = at + 1 + chars[at + 1..].iter().take_while(|c| is_domain(**c)).count();
+        while end > at + 1 && matches!(chars[end - 1], '.' | '-') {
+            end -= 1;
+        }
+        let domain = &chars[at + 1..end];
+        if start < at && domain.contains(&'.') && domain[0] != '.' {
+            spans.push((start, end));
+        }
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the sentence split before every character that is not joined.
+    fn groups(rules: GroupingRules, sentence: &str) -> Vec<String> {
+        let joined = rules.joined(sentence);
+        let mut groups: Vec<String> = Vec::new();
+        for (ch, joined) in sentence.chars().zip(joined) {
+            match groups.last_mut() {
+                Some(group) if joined => group.push(ch),
+                _ => groups.push(ch.into()),
+            }
+        }
+        groups
+    }
+
+    #[test]
+    fn test_digits() {
+        let rules = GroupingRules {
+            digits: true,
+            ..Default::default()
+        };
+        assert_eq!(groups(rules, "2024年"), ["2024", "年"]);
+        assert_eq!(groups(rules, "３．１４と1,000"), ["３．１４", "と", "1,000"]);
+        assert_eq!(groups(rules, "1.x"), ["1", ".", "x"]);
+    }
+
+    #[test]
+    fn test_latin() {
+        let rules = GroupingRules {
+            latin: true,
+            ..Default::default()
+        };
+        assert_eq!(groups(rules, "Café×ｎｅｗ"), ["Café", "×", "ｎｅｗ"]);
+    }
+
+    #[test]
+    fn test_urls() {
+        let rules = GroupingRules {
+            urls: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            groups(rules, "詳細はhttps://example.com/a?b=1。"),
+            ["詳", "細", "は", "https://example.com/a?b=1", "。"]
+        );
+        assert_eq!(groups(rules, "(www.example.com)"), ["(", "www.example.com", ")"]);
+    }
+
+    #[test]
+    fn test_emails() {
+        let rules = GroupingRules {
+            emails: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            groups(rules, "宛先user.name@example.co.jpへ"),
+            ["宛", "先", "user.name@example.co.jp", "へ"]
+        );
+        assert_eq!(
+            groups(rules, "@example.com"),
+            ["@", "e", "x", "a", "m", "p", "l", "e", ".", "c", "o", "m"]
+        );
+    }
+
+    #[test]
+    fn test_emoji() {
+        let rules = GroupingRules {
+            emoji: true,
+            ..Default::default()
+        };
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let thumbs = "\u{1F44D}\u{1F3FD}";
+        let flags = "\u{1F1EF}\u{1F1F5}\u{1F1FA}\u{1F1F8}";
+        assert_eq!(groups(rules, family), [family]);
+        assert_eq!(groups(rules, &format!("{}です", thumbs)), [thumbs, "で", "す"]);
+        assert_eq!(groups(rules, "\u{2764}\u{FE0F}"), ["\u{2764}\u{FE0F}"]);
+        assert_eq!(groups(rules, flags), ["\u{1F1EF}\u{1F1F5}", "\u{1F1FA}\u{1F1F8}"]);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("none".parse::<GroupingRules>().unwrap(), GroupingRules::default());
+        assert_eq!("all".parse::<GroupingRules>().unwrap(), GroupingRules::all());
+        let rules: GroupingRules = "digits, URLs".parse().unwrap();
+        assert!(rules.digits && rules.urls && !rules.latin);
+        assert_eq!(rules.to_string(), "digits,urls");
+        assert_eq!(
+            GroupingRules::all().to_string().parse::<GroupingRules>().unwrap(),
+            GroupingRules::all()
+        );
+        assert_eq!(GroupingRules::default().to_string(), "none");
+        assert!("numbers".parse::<GroupingRules>().is_err());
+        assert!(GroupingRules::default().joined("2024").is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+//! Inspection of the weights of a model, and of how they changed between two models.
+//!
+//! When a retrained model regresses, the first question is which features it weighs
+//! differently. [`inspect`] summarizes one model: its features per template, its bias,
+//! the features with the largest weights and a histogram of the weights. [`diff`]
+//! lists the features whose weights changed most between two models, counting a feature
+//! missing from a model as a weight of 0.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::adaboost::AdaBoost;
+use crate::model_card::ModelSummary;
+
+/// Width in characters of the longest bar of a histogram written by [`write_inspection`].
+const HISTOGRAM_WIDTH: usize = 40;
+
+/// A range of weights and the number of features whose weight falls in it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bin {
+    /// The lower bound of the range, inclusive.
+    pub lower: f64,
+    /// The upper bound of the range, exclusive except for the last bin.
+    pub upper: f64,
+    /// The number of features with a weight in the range.
+    pub count: usize,
+}
+
+/// A summary of the weights of a model, returned by [`inspect`].
+#[derive(Debug, Clone)]
+pub struct Inspection {
+    /// The number of features, bias and features per template.
+    pub summary: ModelSummary,
+    /// The features with the largest weights in absolute value, largest first.
+    pub top: Vec<(String, f64)>,
+    /// The distribution of the weights, in bins of equal width from the smallest to the
+    /// largest weight.
+    pub histogram: Vec<Bin>,
+}
+
+/// Summarizes the weights of a model.
+///
+/// # Arguments
+/// * `learner` - The model to inspect.
+/// * `top_n` - The number of features with the largest weights to list.
+/// * `num_bins` - The number of bins of the weight histogram.
+///
+/// # Returns
+/// The [`Inspection`] of the model.
+#[must_use]
+pub fn inspect(learner: &AdaBoost, top_n: usize, num_bins: usize) -> Inspection {
+    let mut weights: Vec<(&str, f64)> = learner.weights().collect();
+    weights.sort_by(|(a, x), (b, y)| y.abs().total_cmp(&x.abs()).then_with(|| a.cmp(b)));
+    let histogram = histogram(weights.iter().map(|(_, w)| *w), num_bins);
+    let top = weights.into_iter().take(top_n).map(|(f, w)| (f.to_string(), w)).collect();
+
+    Inspection {
+        summary: ModelSummary::new(learner),
+        top,
+        histogram,
+    }
+}
+
+/// Counts weights in `num_bins` bins of equal width from the smallest to the largest.
+fn histogram<I>(weights: I, num_bins: usize) -> Vec<Bin>
+where
+    I: Iterator<Item = f64> + Clone,
+{
+    let (min, max) = weights
+        .clone()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), w| (lo.min(w), hi.max(w)));
+    if num_bins == 0 || min > max {
+        return Vec::new();
+    }
+    let width = (max - min) / num_bins as f64;
+    let mut bins: Vec<Bin> = (0..num_bins)
+        .map(|i| Bin {
+            lower: min + width * i as f64,
+            upper: if i + 1 == num_bins { max } else { min + width * (i + 1) as f64 },
+            count: 0,
+        })
+        .collect();
+    for w in weights {
+        let i = if width > 0.0 { ((w - min) / width) as usize } else { 0 };
+        bins[i.min(num_bins - 1)].count += 1;
+    }
+    bins
+}
+
+/// Writes an inspection in the format printed by `litsea inspect`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `inspection` - The result of [`inspect`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_inspection<W: Write + ?Sized>(
+    writer: &mut W,
+    inspection: &Inspection,
+) -> io::Result<()> {
+    let summary = &inspection.summary;
+    writeln!(writer, "Features: {}", summary.num_features)?;
+    writeln!(writer, "Bias: {:.6}", summary.bias)?;
+    writeln!(writer, "Features per template:")?;
+    for (template, count) in &summary.templates {
+        writeln!(writer, "  {}: {}", template, count)?;
+    }
+
+    if !inspection.top.is_empty() {
+        writeln!(writer, "Top {} weights:", inspection.top.len())?;
+        for (feature, weight) in &inspection.top {
+            writeln!(writer, "  {:>12.6}  {}", weight, feature)?;
+        }
+    }
+
+    if !inspection.histogram.is_empty() {
+        writeln!(writer, "Weight distribution:")?;
+        let max_count = inspection.histogram.iter().map(|bin| bin.count).max().unwrap_or(0);
+        let last = inspection.histogram.len() - 1;
+        for (i, bin) in inspection.histogram.iter().enumerate() {
+            let bar = (bin.count * HISTOGRAM_WIDTH).checked_div(max_count).unwrap_or(0);
+            let line = format!(
+                "  [{:>12.6}, {:>12.6}{} {:>8}  {}",
+                bin.lower,
+                bin.upper,
+                if i == last { ']' } else { ')' },
+                bin.count,
+                "#".repeat(bar)
+            );
+            writeln!(writer, "{}", line.trim_end())?;
+        }
+    }
+    Ok(())
+}
+
+/// The weight of a feature in two models.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightChange {
+    /// The feature name, e.g. `UW4:は`.
+    pub feature: String,
+    /// The weight in the first model, 0 if the feature is missing from it.
+    pub old: f64,
+    /// The weight in the second model, 0 if the feature is missing from it.
+    pub new: f64,
+}
+
+impl WeightChange {
+    /// Returns the change of the weight from the first to the second model.
+    #[must_use]
+    pub fn delta(&self) -> f64 {
+        self.new - self.old
+    }
+}
+
+/// The differences between the weights of two models, returned by [`diff`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDiff {
+    /// The bias of the first model.
+    pub old_bias: f64,
+    /// The bias of the second model.
+    pub new_bias: f64,
+    /// The number of features only in the second model.
+    pub added: usize,
+    /// The number of features only in the first model.
+    pub removed: usize,
+    /// The number of features in both models with different weights.
+    pub changed: usize,
+    /// The features whose weights changed most in absolute value, largest first.
+    pub changes: Vec<WeightChange>,
+}
+
+/// Compares the weights of two models.
+///
+/// # Arguments
+/// * `old` - The first model, e.g. the one in production.
+/// * `new` - The second model, e.g. a retrained one.
+/// * `top_n` - The number of features with the largest changes to list.
+///
+/// # Returns
+/// The [`ModelDiff`] of the models.
+#[must_use]
+pub fn diff(old: &AdaBoost, new: &AdaBoost, top_n: usize) -> ModelDiff {
+    let mut weights: BTreeMap<&str, (f64, f64)> =
+        old.weights().map(|(feature, w)| (feature, (w, 0.0))).collect();
+    let num_old = weights.len();
+    let (mut added, mut common, mut changed) = (0, 0, 0);
+    for (feature, w) in new.weights() {
+        match weights.get_mut(feature) {
+            Some(entry) => {
+                common += 1;
+                if entry.0 != w {
+                    changed += 1;
+                }
+                entry.1 = w;
+            }
+            None => {
+                added += 1;
+                weights.insert(feature, (0.0, w));
+            }
+        }
+    }
+    let removed = num_old - common;
+
+    let mut changes: Vec<WeightChange> = weights
+        .into_iter()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(feature, (old, new))| WeightChange {
+            feature: feature.to_string(),
+            old,
+            new,
+        })
+        .collect();
+    changes.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+    changes.truncate(top_n);
+
+    ModelDiff {
+        old_bias: old.get_bias(),
+        new_bias: new.get_bias(),
+        added,
+        removed,
+        changed,
+        changes,
+    }
+}
+
+/// Writes the differences between two models in the format printed by `litsea diff`.
+///
+/// # Arguments
+/// * `writer` - The destination of the report.
+/// * `diff` - The result of [`diff`].
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn write_diff<W: Write + ?Sized>(writer: &mut W, diff: &ModelDiff) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Bias: {:.6} -> {:.6} ({:+.6})",
+        diff.old_bias,
+        diff.new_bias,
+        diff.new_bias - diff.old_bias
+    )?;
+    writeln!(
+        writer,
+        "Features: {} added, {} removed, {} changed",
+        diff.added, diff.removed, diff.changed
+    )?;
+    if !diff.changes.is_empty() {
+        writeln!(writer, "Largest changes:")?;
+        for change in &diff.changes {
+            writeln!(
+                writer,
+                "  {:>+12.6}  {:>12.6} -> {:>12.6}  {}",
+                change.delta(),
+                change.old,
+                change.new,
+                change.feature
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learner(model: &str) -> AdaBoost {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(model).unwrap();
+        learner
+    }
+
+    #[test]
+    fn test_inspect() {
+        let learner = learner("UW4:は\t3\nUW4:が\t-1\nBC1:HI\t0.5\nUP1:U\t-2\n0.25\n");
+        let inspection = inspect(&learner, 2, 5);
+
+        assert_eq!(inspection.summary.num_features, 4);
+        assert_eq!(inspection.summary.templates["UW4"], 2);
+        assert_eq!(inspection.top, vec![("UW4:は".to_string(), 3.0), ("UP1:U".to_string(), -2.0)]);
+        assert_eq!(inspection.histogram.len(), 5);
+        assert_eq!(inspection.histogram[0].lower, -2.0);
+        assert_eq!(inspection.histogram[4].upper, 3.0);
+        // -2 | -1 | 0.5 | nothing | 3
+        let counts: Vec<usize> = inspection.histogram.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts, vec![1, 1, 1, 0, 1]);
+
+        let mut out = Vec::new();
+        write_inspection(&mut out, &inspection).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("Features: 4\n"), "{out}");
+        assert!(out.contains("Top 2 weights:\n"), "{out}");
+        assert!(out.contains("UW4:は\n"), "{out}");
+        assert!(out.contains("Weight distribution:\n"), "{out}");
+    }
+
+    #[test]
+    fn test_histogram_edge_cases() {
+        assert!(histogram([].into_iter(), 10).is_empty());
+        assert!(histogram([1.0].into_iter(), 0).is_empty());
+        // All weights equal: everything goes to the first bin.
+        let bins = histogram([2.0, 2.0].into_iter(), 3);
+        assert_eq!(bins.iter().map(|bin| bin.count).collect::<Vec<_>>(), vec![2, 0, 0]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = learner("UW4:は\t3\nUW4:が\t-1\nBC1:HI\t0.5\n0\n");
+        let new = learner("UW4:は\t1\nUW4:が\t-1\nUP1:U\t0.25\n0\n");
+        let diff = diff(&old, &new, 10);
+
+        assert_eq!((diff.added, diff.removed, diff.changed), (1, 1, 1));
+        let features: Vec<&str> = diff.changes.iter().map(|c| c.feature.as_str()).collect();
+        assert_eq!(features, vec!["UW4:は", "BC1:HI", "UP1:U"]);
+        assert_eq!(diff.changes[0].delta(), -2.0);
+        assert_eq!(diff.changes[1].new, 0.0);
+        assert_eq!(diff.changes[2].old, 0.0);
+
+        let mut out = Vec::new();
+        write_diff(&mut out, &diff).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("Features: 1 added, 1 removed, 1 changed\n"), "{out}");
+
+        let same = super::diff(&old, &old, 10);
+        assert_eq!((same.added, same.removed, same.changed), (0, 0, 0));
+        assert!(same.changes.is_empty());
+    }
+}
@@ -0,0 +1,349 @@
+//! On-disk instance buffers for training on data larger than memory.
+//!
+//! [`AdaBoost::write_instance_file`](crate::adaboost::AdaBoost::write_instance_file)
+//! converts a features file into a binary instance file, and
+//! [`AdaBoost::map_instances`](crate::adaboost::AdaBoost::map_instances) memory-maps it
+//! for training. Every boosting round then reads the file from start to end, so the
+//! operating system only needs to keep the pages currently being read, and only the
+//! labels and weights of the instances (9 bytes each) stay in memory.
+//!
+//! All integers are little-endian:
+//!
+//! ```text
+//! magic          8 bytes  "LITSEAIF"
+//! num_features   u64      number of features in the model the indices refer to
+//! num_instances  u64
+//! records        num_instances times:
+//!   label        i8       1 or -1
+//!   len          u32      number of features of the instance
+//!   features     len × u32 feature indices in ascending order
+//! ```
+//!
+//! The indices refer to the features of the model the file was written for, so an
+//! instance file is only valid with the features file it was written from.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+/// The first bytes of an instance file.
+pub const MAGIC: &[u8; 8] = b"LITSEAIF";
+
+const HEADER_LEN: usize = 24;
+
+/// Number of instances between the offsets kept to start reading in the middle of the file.
+const CHECKPOINT_INTERVAL: usize = 4096;
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Writes instances to an instance file, filling in the instance count on
+/// [`finish`](Self::finish).
+pub(crate) struct InstanceFileWriter {
+    writer: BufWriter<File>,
+    num_instances: u64,
+}
+
+impl InstanceFileWriter {
+    /// Creates the instance file and writes its header.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be created or written.
+    pub(crate) fn create(path: &Path, num_features: usize) -> std::io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(num_features as u64).to_le_bytes())?;
+        writer.write_all(&0u64.to_le_bytes())?;
+        Ok(InstanceFileWriter {
+            writer,
+            num_instances: 0,
+        })
+    }
+
+    /// Appends an instance. The features must be sorted in ascending order.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub(crate) fn push(&mut self, label: i8, features: &[u32]) -> std::io::Result<()> {
+        self.writer.write_all(&label.to_le_bytes())?;
+        self.writer.write_all(&(features.len() as u32).to_le_bytes())?;
+        for h in features {
+            self.writer.write_all(&h.to_le_bytes())?;
+        }
+        self.num_instances += 1;
+        Ok(())
+    }
+
+    /// Writes the instance count to the header and flushes the file.
+    ///
+    /// # Returns
+    /// The number of instances written.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub(crate) fn finish(self) -> std::io::Result<usize> {
+        let mut file = self.writer.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(16))?;
+        file.write_all(&self.num_instances.to_le_bytes())?;
+        file.sync_all()?;
+        Ok(self.num_instances as usize)
+    }
+}
+
+/// A memory-mapped instance file.
+#[derive(Debug)]
+pub(crate) struct MappedInstances {
+    map: Mmap,
+    num_instances: usize,
+    /// Offset of every `CHECKPOINT_INTERVAL`-th record.
+    checkpoints: Vec<usize>,
+}
+
+impl MappedInstances {
+    /// Maps an instance file and checks every record.
+    ///
+    /// # Arguments
+    /// * `path` - The instance file.
+    /// * `num_features` - The number of features of the model, which the file must match.
+    ///
+    /// # Returns
+    /// The mapped instances and their labels.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be mapped, is not an instance file, was written
+    /// for a different number of features, or is truncated.
+    pub(crate) fn open(path: &Path, num_features: usize) -> std::io::Result<(Self, Vec<i8>)> {
+        let file = File::open(path)?;
+        // SAFETY: The mapping is read-only and the file is written only by
+        // `InstanceFileWriter` before training; modifying it while it is mapped is not
+        // supported.
+        let map = unsafe { Mmap::map(&file)? };
+        #[cfg(unix)]
+        map.advise(memmap2::Advice::Sequential)?;
+
+        if map.len() < HEADER_LEN || &map[..8] != MAGIC {
+            return Err(invalid_data(format!("{} is not an instance file", path.display())));
+        }
+        let expected = read_u64(&map, 8) as usize;
+        if expected != num_features {
+            return Err(invalid_data(format!(
+                "Instance file was written for {} features, but the model has {}",
+                expected, num_features
+            )));
+        }
+        let num_instances = read_u64(&map, 16) as usize;
+
+        let mut labels = Vec::with_capacity(num_instances);
+        let mut checkpoints = Vec::with_capacity(num_instances / CHECKPOINT_INTERVAL + 1);
+        let mut pos = HEADER_LEN;
+        for i in 0..num_instances {
+            if i % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push(pos);
+            }
+            let truncated =
+                || invalid_data(format!("Instance file is truncated at instance {}", i));
+            if pos + 5 > map.len() {
+                return Err(truncated());
+            }
+            let label = map[pos] as i8;
+            let len = read_u32(&map, pos + 1) as usize;
+            let end = pos + 5 + len * 4;
+            if end > map.len() {
+                return Err(truncated());
+            }
+            let mut previous = None;
+            for h in map[pos + 5..end].chunks_exact(4) {
+                let h = u32::from_le_bytes([h[0], h[1], h[2], h[3]]);
+                if h as usize >= num_features || previous.is_some_and(|p| p >= h) {
+                    return Err(invalid_data(format!(
+                        "Invalid feature index {} at instance {}",
+                        h, i
+                    )));
+                }
+                previous = Some(h);
+            }
+            labels.push(label);
+            pos = end;
+        }
+
+        Ok((
+            MappedInstances {
+                map,
+                num_instances,
+                checkpoints,
+            },
+            labels,
+        ))
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.num_instances
+    }
+
+    /// Calls `f` with the index and the features of each instance in `range`, reading
+    /// the file sequentially.
+    pub(crate) fn for_each<F: FnMut(usize, &[u32])>(&self, range: Range<usize>, mut f: F) {
+        if range.is_empty() {
+            return;
+        }
+        let first = range.start / CHECKPOINT_INTERVAL * CHECKPOINT_INTERVAL;
+        let mut pos = self.checkpoints[range.start / CHECKPOINT_INTERVAL];
+        for _ in first..range.start {
+            pos += 5 + read_u32(&self.map, pos + 1) as usize * 4;
+        }
+        let mut features = Vec::new();
+        for i in range {
+            let end = pos + 5 + read_u32(&self.map, pos + 1) as usize * 4;
+            features.clear();
+            features.extend(
+                self.map[pos + 5..end]
+                    .chunks_exact(4)
+                    .map(|h| u32::from_le_bytes([h[0], h[1], h[2], h[3]])),
+            );
+            f(i, &features);
+            pos = end;
+        }
+    }
+}
+
+/// Replaces every feature index `h` of an instance file with `remap[h]`, sorting the
+/// indices of each instance again, and sets the number of features in the header.
+/// The file is rewritten in place in one sequential pass, so an instance file can be
+/// written while the features are still being numbered and renumbered once all are known.
+///
+/// # Arguments
+/// * `path` - The instance file, written by [`InstanceFileWriter`].
+/// * `remap` - The new index of each feature index in the file; its length is the new
+///   number of features.
+///
+/// # Errors
+/// Returns an error if the file cannot be mapped, is not an instance file, or contains
+/// an index outside `remap`.
+pub(crate) fn remap_features(path: &Path, remap: &[u32]) -> std::io::Result<()> {
+    let file = OpenOptions::new().read(true).write(true).open(path)?;
+    // SAFETY: The file was just written by `InstanceFileWriter` and is not mapped or
+    // modified elsewhere while it is remapped.
+    let mut map = unsafe { MmapMut::map_mut(&file)? };
+    if map.len() < HEADER_LEN || &map[..8] != MAGIC {
+        return Err(invalid_data(format!("{} is not an instance file", path.display())));
+    }
+    map[8..16].copy_from_slice(&(remap.len() as u64).to_le_bytes());
+    let num_instances = read_u64(&map, 16) as usize;
+    let mut pos = HEADER_LEN;
+    let mut features = Vec::new();
+    for i in 0..num_instances {
+        if pos + 5 > map.len() {
+            return Err(invalid_data(format!("Instance file is truncated at instance {}", i)));
+        }
+        let len = read_u32(&map, pos + 1) as usize;
+        let end = pos + 5 + len * 4;
+        if end > map.len() {
+            return Err(invalid_data(format!("Instance file is truncated at instance {}", i)));
+        }
+        features.clear();
+        for h in map[pos + 5..end].chunks_exact(4) {
+            let h = u32::from_le_bytes([h[0], h[1], h[2], h[3]]);
+            let new = remap.get(h as usize).ok_or_else(|| {
+                invalid_data(format!("Invalid feature index {} at instance {}", h, i))
+            })?;
+            features.push(*new);
+        }
+        features.sort_unstable();
+        for (bytes, h) in map[pos + 5..end].chunks_exact_mut(4).zip(&features) {
+            bytes.copy_from_slice(&h.to_le_bytes());
+        }
+        pos = end;
+    }
+    map.flush()
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+fn read_u64(bytes: &[u8], pos: usize) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[pos..pos + 8]);
+    u64::from_le_bytes(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::NamedTempFile;
+
+    fn write(instances: &[(i8, &[u32])], num_features: usize) -> NamedTempFile {
+        let file = NamedTempFile::new().unwrap();
+        let mut writer = InstanceFileWriter::create(file.path(), num_features).unwrap();
+        for (label, features) in instances {
+            writer.push(*label, features).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), instances.len());
+        file
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let instances: Vec<(i8, Vec<u32>)> = (0..10_000u32)
+            .map(|i| (if i % 3 == 0 { 1 } else { -1 }, (0..i % 5).map(|h| h * 2 + i % 2).collect()))
+            .collect();
+        let borrowed: Vec<(i8, &[u32])> = instances
+            .iter()
+            .map(|(label, features)| (*label, features.as_slice()))
+            .collect();
+        let file = write(&borrowed, 10);
+
+        let (mapped, labels) = MappedInstances::open(file.path(), 10).unwrap();
+        assert_eq!(mapped.len(), instances.len());
+        assert_eq!(labels, instances.iter().map(|(label, _)| *label).collect::<Vec<_>>());
+
+        // Start in the middle of a checkpoint interval.
+        let mut seen = Vec::new();
+        mapped.for_each(5000..5010, |i, features| seen.push((i, features.to_vec())));
+        assert_eq!(seen.len(), 10);
+        for (i, features) in seen {
+            assert_eq!(features, instances[i].1);
+        }
+    }
+
+    #[test]
+    fn test_remap_features() {
+        let file = write(&[(1, &[0, 1, 2]), (-1, &[2])], 0);
+        remap_features(file.path(), &[3, 0, 1, 2]).unwrap();
+        let (mapped, labels) = MappedInstances::open(file.path(), 4).unwrap();
+        assert_eq!(labels, [1, -1]);
+        let mut seen = Vec::new();
+        mapped.for_each(0..2, |_, features| seen.push(features.to_vec()));
+        assert_eq!(seen, [vec![0, 1, 3], vec![1]]);
+
+        let file = write(&[(1, &[0, 5])], 0);
+        let err = remap_features(file.path(), &[0, 1]).unwrap_err();
+        assert!(err.to_string().contains("Invalid feature index 5"));
+    }
+
+    #[test]
+    fn test_open_errors() {
+        let file = write(&[(1, &[0, 2])], 3);
+        let err = MappedInstances::open(file.path(), 4).unwrap_err();
+        assert!(err.to_string().contains("written for 3 features"));
+
+        let file = write(&[(1, &[0, 3])], 3);
+        let err = MappedInstances::open(file.path(), 3).unwrap_err();
+        assert!(err.to_string().contains("Invalid feature index 3"));
+
+        let file = write(&[(1, &[0, 2])], 3);
+        let len = std::fs::metadata(file.path()).unwrap().len();
+        file.as_file().set_len(len - 2).unwrap();
+        let err = MappedInstances::open(file.path(), 3).unwrap_err();
+        assert!(err.to_string().contains("truncated at instance 0"));
+
+        let file = NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "1 UW4:は\n").unwrap();
+        assert!(MappedInstances::open(file.path(), 3).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+//! Integrity checks for model files.
+//!
+//! A model file may start with a header line that records the SHA-256 checksum of the
+//! rest of the file and, optionally, an Ed25519 signature over it:
+//!
+//! ```text
+//! #litsea-model sha256=<hex> [ed25519=<hex>]
+//! ```
+//!
+//! [`AdaBoost::save_model`](crate::adaboost::AdaBoost::save_model) always writes the
+//! checksum, and [`AdaBoost::load_model`](crate::adaboost::AdaBoost::load_model) refuses
+//! a model whose content does not match it, so a truncated or edited file fails to load
+//! instead of producing wrong segmentations. Models without a header, such as those
+//! written by earlier versions, are still accepted.
+//!
+//! The checksum is also checked without the `std` feature, by
+//! [`AdaBoost::load_model_str`](crate::adaboost::AdaBoost::load_model_str).
+//! Signatures are created and checked with the `signing` feature. A loader only checks
+//! a signature when it has been given a public key, and then also rejects unsigned
+//! models.
+
+#[cfg(feature = "std")]
+use crate::error::LitseaError;
+use crate::util::sha256_hex;
+use alloc::format;
+use alloc::string::{String, ToString};
+#[cfg(any(feature = "std", test))]
+use alloc::vec::Vec;
+
+#[cfg(feature = "signing")]
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Prefix of the header line of a model file.
+pub const HEADER_PREFIX: &str = "#litsea-model";
+
+#[cfg(feature = "std")]
+fn invalid_model(message: String) -> LitseaError {
+    LitseaError::ModelFormat(message)
+}
+
+/// The integrity header of a model file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelHeader {
+    /// SHA-256 checksum of the model content after the header, in lowercase hexadecimal.
+    pub sha256: Option<String>,
+    /// Ed25519 signature of the model content after the header, in lowercase hexadecimal.
+    pub signature: Option<String>,
+}
+
+impl ModelHeader {
+    fn parse(line: &str) -> Self {
+        let mut header = ModelHeader::default();
+        // Unknown fields are skipped so that later versions can add more.
+        for field in line.split_whitespace().skip(1) {
+            match field.split_once('=') {
+                Some(("sha256", value)) => header.sha256 = Some(value.to_ascii_lowercase()),
+                Some(("ed25519", value)) => header.signature = Some(value.to_ascii_lowercase()),
+                _ => {}
+            }
+        }
+        header
+    }
+
+    #[cfg(any(feature = "std", test))]
+    fn to_line(&self) -> String {
+        let mut line = HEADER_PREFIX.to_string();
+        if let Some(sha256) = &self.sha256 {
+            line.push_str(" sha256=");
+            line.push_str(sha256);
+        }
+        if let Some(signature) = &self.signature {
+            line.push_str(" ed25519=");
+            line.push_str(signature);
+        }
+        line.push('\n');
+        line
+    }
+}
+
+/// Splits a model file into its header, if any, and the model content.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file.
+///
+/// # Returns
+/// The parsed header and the content after it.
+#[must_use]
+pub fn split_header(content: &[u8]) -> (Option<ModelHeader>, &[u8]) {
+    if !content.starts_with(HEADER_PREFIX.as_bytes()) {
+        return (None, content);
+    }
+    let end = content.iter().position(|&b| b == b'\n').map_or(content.len(), |i| i + 1);
+    let line = String::from_utf8_lossy(&content[..end]);
+    (Some(ModelHeader::parse(&line)), &content[end..])
+}
+
+/// Prepends a header with the checksum of `body` to it.
+///
+/// # Arguments
+/// * `body` - The model content.
+///
+/// # Returns
+/// The model file content, including the header.
+#[cfg(any(feature = "std", test))]
+pub(crate) fn add_header(body: &[u8]) -> Vec<u8> {
+    let header = ModelHeader {
+        sha256: Some(sha256_hex(body)),
+        signature: None,
+    };
+    let mut content = header.to_line().into_bytes();
+    content.extend_from_slice(body);
+    content
+}
+
+/// Checks the checksum in the header of a model file.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file.
+///
+/// # Returns
+/// The header, if any, and the verified model content after it.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the header has no checksum or the checksum
+/// does not match the content.
+#[cfg(feature = "std")]
+pub fn verify(content: &[u8]) -> crate::error::Result<(Option<ModelHeader>, &[u8])> {
+    check_checksum(content).map_err(invalid_model)
+}
+
+/// Checks the checksum in the header of a model file, without the `std` feature.
+/// See [`verify`].
+///
+/// # Errors
+/// Returns a message if the header has no checksum or the checksum does not match the
+/// content.
+pub(crate) fn check_checksum(content: &[u8]) -> Result<(Option<ModelHeader>, &[u8]), String> {
+    let (header, body) = split_header(content);
+    if let Some(header) = &header {
+        let Some(expected) = &header.sha256 else {
+            return Err("Model header has no checksum".to_string());
+        };
+        let actual = sha256_hex(body);
+        if *expected != actual {
+            return Err(format!(
+                "Model checksum mismatch: expected {}, found {}; the file is corrupted or has been modified",
+                expected, actual
+            ));
+        }
+    }
+    Ok((header, body))
+}
+
+/// Decodes a hexadecimal string.
+#[cfg(feature = "signing")]
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Parses a 32-byte Ed25519 secret key written in hexadecimal.
+///
+/// # Errors
+/// Returns [`LitseaError::InvalidInput`] if the string is not 64 hexadecimal digits.
+#[cfg(feature = "signing")]
+pub fn signing_key_from_hex(s: &str) -> crate::error::Result<SigningKey> {
+    let bytes: [u8; 32] = decode_hex(s).and_then(|b| b.try_into().ok()).ok_or_else(|| {
+        LitseaError::InvalidInput("Invalid secret key: expected 64 hex digits".to_string())
+    })?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Parses a 32-byte Ed25519 public key written in hexadecimal.
+///
+/// # Errors
+/// Returns [`LitseaError::InvalidInput`] if the string is not 64 hexadecimal digits or
+/// not a valid key.
+#[cfg(feature = "signing")]
+pub fn verifying_key_from_hex(s: &str) -> crate::error::Result<VerifyingKey> {
+    let bytes: [u8; 32] = decode_hex(s).and_then(|b| b.try_into().ok()).ok_or_else(|| {
+        LitseaError::InvalidInput("Invalid public key: expected 64 hex digits".to_string())
+    })?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|e| LitseaError::InvalidInput(format!("Invalid public key: {}", e)))
+}
+
+/// Formats a key or signature as lowercase hexadecimal.
+#[cfg(feature = "signing")]
+#[must_use]
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Signs a model file, replacing any existing header.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file.
+/// * `key` - The secret key to sign with.
+///
+/// # Returns
+/// The signed model file content.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the existing checksum does not match, since
+/// signing a damaged model would make the damage look authentic.
+#[cfg(feature = "signing")]
+pub fn sign(content: &[u8], key: &SigningKey) -> crate::error::Result<Vec<u8>> {
+    use ed25519_dalek::Signer;
+
+    let (_, body) = verify(content)?;
+    let header = ModelHeader {
+        sha256: Some(sha256_hex(body)),
+        signature: Some(to_hex(&key.sign(body).to_bytes())),
+    };
+    let mut signed = header.to_line().into_bytes();
+    signed.extend_from_slice(body);
+    Ok(signed)
+}
+
+/// Checks the checksum and the signature of a model file.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file.
+/// * `key` - The public key the model must be signed with.
+///
+/// # Returns
+/// The verified model content after the header.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the checksum does not match, or if the model
+/// is unsigned or its signature is not valid for `key`.
+#[cfg(feature = "signing")]
+pub fn verify_signed<'a>(content: &'a [u8], key: &VerifyingKey) -> crate::error::Result<&'a [u8]> {
+    let (header, body) = verify(content)?;
+    let signature = header
+        .and_then(|h| h.signature)
+        .ok_or_else(|| invalid_model("Model is not signed".to_string()))?;
+    let signature: [u8; 64] = decode_hex(&signature)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| invalid_model("Invalid model signature".to_string()))?;
+    key.verify_strict(body, &ed25519_dalek::Signature::from_bytes(&signature))
+        .map_err(|_| invalid_model("Model signature does not match the public key".to_string()))?;
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &[u8] = b"UW4:\xe3\x81\xaf\t0.5\n-0.25\n";
+
+    #[test]
+    fn test_split_header() {
+        let (header, body) = split_header(BODY);
+        assert!(header.is_none());
+        assert_eq!(body, BODY);
+
+        let content = add_header(BODY);
+        let (header, body) = split_header(&content);
+        assert_eq!(header.unwrap().sha256.unwrap(), sha256_hex(BODY));
+        assert_eq!(body, BODY);
+
+        let (header, _) = split_header(b"#litsea-model sha256=AB future=1\n");
+        assert_eq!(header.unwrap().sha256.as_deref(), Some("ab"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify() {
+        let content = add_header(BODY);
+        assert_eq!(verify(&content).unwrap().1, BODY);
+        assert_eq!(verify(BODY).unwrap().1, BODY);
+
+        let mut tampered = content.clone();
+        *tampered.last_mut().unwrap() = b'9';
+        let err = verify(&tampered).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        assert!(err.to_string().contains("checksum mismatch"));
+
+        assert!(verify(b"#litsea-model\n-0.25\n").is_err());
+    }
+
+    #[cfg(feature = "signing")]
+    #[test]
+    fn test_sign_and_verify() {
+        let key = signing_key_from_hex(&"07".repeat(32)).unwrap();
+        let public_key = verifying_key_from_hex(&to_hex(key.verifying_key().as_bytes())).unwrap();
+        let signed = sign(&add_header(BODY), &key).unwrap();
+        assert_eq!(verify_signed(&signed, &public_key).unwrap(), BODY);
+        // Signed models still load without a key.
+        assert_eq!(verify(&signed).unwrap().1, BODY);
+
+        let other = signing_key_from_hex(&"08".repeat(32)).unwrap();
+        assert!(verify_signed(&signed, &other.verifying_key()).is_err());
+        assert!(verify_signed(&add_header(BODY), &public_key).is_err());
+
+        let mut tampered = signed.clone();
+        *tampered.last_mut().unwrap() = b'9';
+        assert!(verify_signed(&tampered, &public_key).is_err());
+
+        assert!(matches!(signing_key_from_hex("abc"), Err(LitseaError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,185 @@
+//! JNI bindings for the JVM, enabled by the `jni` feature.
+//!
+//! The functions in this module back the native methods of the Java class
+//! `io.github.mosuka.litsea.Segmenter` (see `bindings/java`). A segmenter is created
+//! with `open`, which returns an opaque handle that must be released with `free`.
+//!
+//! Build the shared library with:
+//!
+//! ```sh
+//! cargo rustc -p litsea --lib --release --features jni --crate-type cdylib
+//! ```
+
+use std::fs;
+
+use jni::JNIEnv;
+use jni::objects::{JClass, JObject, JString};
+use jni::sys::{jintArray, jlong, jobjectArray};
+
+use crate::adaboost::AdaBoost;
+use crate::language::Language;
+use crate::segmenter::Segmenter;
+
+/// Computes the UTF-16 start and end offsets of each word, as used by Java strings.
+///
+/// # Returns
+/// A flat vector of `[start0, end0, start1, end1, ...]`.
+fn utf16_offsets<S: AsRef<str>>(words: &[S]) -> Vec<i32> {
+    let mut offsets = Vec::with_capacity(words.len() * 2);
+    let mut pos = 0i32;
+    for word in words {
+        let len = word.as_ref().encode_utf16().count() as i32;
+        offsets.push(pos);
+        offsets.push(pos + len);
+        pos += len;
+    }
+    offsets
+}
+
+/// Loads a model file synchronously and builds a segmenter for it.
+fn open_segmenter(language: &str, model_path: &str) -> Result<Segmenter, String> {
+    let language: Language = language.parse()?;
+    let content =
+        fs::read(model_path).map_err(|e| format!("Failed to open model {}: {}", model_path, e))?;
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner
+        .load_model_bytes(&content)
+        .map_err(|e| format!("Failed to load model {}: {}", model_path, e))?;
+    Ok(Segmenter::new(language, Some(learner)))
+}
+
+/// Throws a Java exception unless one is already pending.
+fn throw(env: &mut JNIEnv, class: &str, message: &str) {
+    if !env.exception_check().unwrap_or(true) {
+        // If throwing fails there is nothing more we can report to the JVM.
+        env.throw_new(class, message).ok();
+    }
+}
+
+/// Converts a handle returned by `open` back into a segmenter reference.
+///
+/// # Safety
+/// `handle` must have been returned by `open` and not yet passed to `free`.
+unsafe fn segmenter<'a>(handle: jlong) -> Option<&'a Segmenter> {
+    unsafe { (handle as *const Segmenter).as_ref() }
+}
+
+/// `static native long open(String language, String modelPath)`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_io_github_mosuka_litsea_Segmenter_open<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    language: JString<'local>,
+    model_path: JString<'local>,
+) -> jlong {
+    let args = env
+        .get_string(&language)
+        .map(String::from)
+        .and_then(|language| Ok((language, String::from(env.get_string(&model_path)?))));
+    let Ok((language, model_path)) = args else {
+        throw(&mut env, "java/lang/IllegalArgumentException", "Invalid arguments");
+        return 0;
+    };
+    match open_segmenter(&language, &model_path) {
+        Ok(segmenter) => Box::into_raw(Box::new(segmenter)) as jlong,
+        Err(message) => {
+            throw(&mut env, "java/lang/IllegalArgumentException", &message);
+            0
+        }
+    }
+}
+
+/// `static native String[] segment(long handle, String text)`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_io_github_mosuka_litsea_Segmenter_segment<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    text: JString<'local>,
+) -> jobjectArray {
+    // SAFETY: the Java class only passes handles it obtained from `open` and has not freed.
+    let Some(segmenter) = (unsafe { segmenter(handle) }) else {
+        throw(&mut env, "java/lang/IllegalStateException", "Segmenter is closed");
+        return JObject::null().into_raw();
+    };
+    let result = (|| -> jni::errors::Result<jobjectArray> {
+        let text: String = env.get_string(&text)?.into();
+        let words = segmenter.segment(&text);
+        let array =
+            env.new_object_array(words.len() as i32, "java/lang/String", JObject::null())?;
+        for (i, word) in words.iter().enumerate() {
+            let word = env.new_string(word)?;
+            env.set_object_array_element(&array, i as i32, word)?;
+        }
+        Ok(array.into_raw())
+    })();
+    result.unwrap_or_else(|e| {
+        throw(&mut env, "java/lang/RuntimeException", &e.to_string());
+        JObject::null().into_raw()
+    })
+}
+
+/// `static native int[] offsets(long handle, String text)`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_io_github_mosuka_litsea_Segmenter_offsets<'local>(
+    mut env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+    text: JString<'local>,
+) -> jintArray {
+    // SAFETY: the Java class only passes handles it obtained from `open` and has not freed.
+    let Some(segmenter) = (unsafe { segmenter(handle) }) else {
+        throw(&mut env, "java/lang/IllegalStateException", "Segmenter is closed");
+        return JObject::null().into_raw();
+    };
+    let result = (|| -> jni::errors::Result<jintArray> {
+        let text: String = env.get_string(&text)?.into();
+        let offsets = utf16_offsets(&segmenter.segment(&text));
+        let array = env.new_int_array(offsets.len() as i32)?;
+        env.set_int_array_region(&array, 0, &offsets)?;
+        Ok(array.into_raw())
+    })();
+    result.unwrap_or_else(|e| {
+        throw(&mut env, "java/lang/RuntimeException", &e.to_string());
+        JObject::null().into_raw()
+    })
+}
+
+/// `static native void free(long handle)`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_io_github_mosuka_litsea_Segmenter_free<'local>(
+    _env: JNIEnv<'local>,
+    _class: JClass<'local>,
+    handle: jlong,
+) {
+    if handle != 0 {
+        // SAFETY: the Java class calls `free` at most once per handle returned by `open`.
+        drop(unsafe { Box::from_raw(handle as *mut Segmenter) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_offsets() {
+        // "𠮷" is outside the BMP and takes two UTF-16 code units.
+        let words = ["これ", "は", "𠮷野家", "です"];
+        assert_eq!(utf16_offsets(&words), vec![0, 2, 2, 3, 3, 7, 7, 9]);
+        assert!(utf16_offsets::<&str>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_open_segmenter() {
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model");
+        let segmenter = open_segmenter("japanese", model).unwrap();
+        assert_eq!(
+            segmenter.segment("これはテストです。"),
+            vec!["これ", "は", "テスト", "です", "。"]
+        );
+
+        assert!(open_segmenter("klingon", model).is_err());
+        assert!(open_segmenter("japanese", "/nonexistent.model").is_err());
+    }
+}
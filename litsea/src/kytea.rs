@@ -0,0 +1,215 @@
+use std::io::BufRead;
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+
+/// Maps a KyTea character type to the Litsea Japanese type codes it covers.
+///
+/// KyTea classifies kanji numerals as plain kanji (`K`), while Litsea gives them their
+/// own type (`M`), so KyTea's `K` expands to both `H` and `M`.
+fn map_type(kytea: char) -> Option<&'static [&'static str]> {
+    match kytea {
+        'K' => Some(&["H", "M"]),
+        'T' => Some(&["K"]),
+        'H' => Some(&["I"]),
+        'R' => Some(&["A"]),
+        'D' => Some(&["N"]),
+        'O' => Some(&["O"]),
+        _ => None,
+    }
+}
+
+/// Expands a KyTea type n-gram (e.g. `KH`) into all matching Litsea type n-grams.
+pub(crate) fn expand_types(ngram: &str) -> Option<Vec<String>> {
+    let mut out = vec![String::new()];
+    for ch in ngram.chars() {
+        let mapped = map_type(ch)?;
+        out = out
+            .iter()
+            .flat_map(|prefix| mapped.iter().map(move |t| format!("{}{}", prefix, t)))
+            .collect();
+    }
+    Some(out)
+}
+
+/// Maps an n-gram feature at `offset` to the Litsea template covering the same window.
+///
+/// `offset` is the position of the n-gram's first character relative to the character
+/// right after the boundary, so `-3..=2` covers Litsea's six-character window.
+pub(crate) fn template(kind: char, offset: i32, len: usize) -> Option<String> {
+    // (template prefix, offset of the first slot, number of slots)
+    let (prefix, first, slots) = match (kind, len) {
+        ('X', 1) => ("UW", -3, 6),
+        ('X', 2) => ("BW", -2, 3),
+        ('T', 1) => ("UC", -3, 6),
+        ('T', 2) => ("BC", -2, 3),
+        ('T', 3) => ("TC", -3, 4),
+        _ => return None,
+    };
+    let index = offset - first;
+    (0..slots).contains(&index).then(|| format!("{}{}", prefix, index + 1))
+}
+
+/// Parses a KyTea feature name such as `X-1の` or `T0KH` into its kind, offset and n-gram.
+fn parse_feature(name: &str) -> Option<(char, i32, &str)> {
+    let mut chars = name.char_indices();
+    let (_, kind) = chars.next()?;
+    if kind != 'X' && kind != 'T' {
+        return None;
+    }
+    let rest = &name[1..];
+    let (sign, rest) = match rest.strip_prefix('-') {
+        Some(r) => (-1, r),
+        None => (1, rest),
+    };
+    // KyTea windows are at most 9 characters wide, so offsets are a single digit.
+    let digit = rest.chars().next()?.to_digit(10)? as i32;
+    let ngram = &rest[1..];
+    (!ngram.is_empty()).then_some((kind, sign * digit, ngram))
+}
+
+/// Imports the word segmentation weights of a KyTea model dumped as feature weights.
+///
+/// KyTea's own model files are not read directly. The input is a plain listing of the
+/// word boundary classifier, extracted from a KyTea text model (`train-kytea -modtext`):
+///
+/// - An optional first line starting with `KyTea`, e.g. `KyTea 0.4.7 T UTF8`, which is
+///   ignored.
+/// - `<feature> <weight>` -- one feature of the classifier and its weight, where positive
+///   weights favor a boundary. Features are `X<offset><chars>` for character n-grams and
+///   `T<offset><types>` for character type n-grams (KyTea types `K T H R D O`).
+/// - `BIAS <weight>` -- the classifier bias.
+/// - Blank lines, which are ignored.
+///
+/// `<offset>` is the position of the n-gram's first character relative to the character
+/// right after the boundary. Only features that fit Litsea's templates are converted:
+/// character unigrams/bigrams (`UW`, `BW`) and type unigrams/bigrams/trigrams
+/// (`UC`, `BC`, `TC`) within the six-character window. Dictionary features, character
+/// trigrams and anything else are skipped. Type codes are mapped to the Japanese
+/// character types, so the result should be used with [`Language::Japanese`](crate::language::Language::Japanese).
+///
+/// # Arguments
+/// * `reader` - A buffered reader over the model text.
+///
+/// # Returns
+/// Returns the imported model and the number of skipped feature lines.
+///
+/// # Errors
+/// Returns [`LitseaError::Parse`] for a line that is not a feature name followed by a
+/// numeric weight, or an error if the model cannot be read or no convertible features
+/// are found.
+pub fn import_text_model<R: BufRead>(reader: R) -> crate::error::Result<(AdaBoost, usize)> {
+    let mut weights = Vec::new();
+    let mut bias = 0.0;
+    let mut skipped = 0;
+
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || (line_num == 0 && line.starts_with("KyTea")) {
+            continue;
+        }
+        let parse_error = |reason| LitseaError::Parse {
+            line: line_num + 1,
+            reason,
+        };
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(value), None) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(parse_error("Expected a feature name and a weight".to_string()));
+        };
+        let weight: f64 =
+            value.parse().map_err(|e| parse_error(format!("Invalid weight: {}", e)))?;
+        if name == "BIAS" {
+            bias = weight;
+            continue;
+        }
+        let converted = parse_feature(name).and_then(|(kind, offset, ngram)| {
+            let template = template(kind, offset, ngram.chars().count())?;
+            let keys = if kind == 'T' { expand_types(ngram)? } else { vec![ngram.to_string()] };
+            Some(keys.into_iter().map(move |k| (format!("{}:{}", template, k), weight)))
+        });
+        match converted {
+            Some(features) => weights.extend(features),
+            None => skipped += 1,
+        }
+    }
+
+    if weights.is_empty() {
+        return Err(LitseaError::ModelFormat(
+            "No convertible features found in KyTea model".to_string(),
+        ));
+    }
+
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.set_weights(weights, bias);
+    Ok((learner, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_template() {
+        assert_eq!(template('X', -3, 1).as_deref(), Some("UW1"));
+        assert_eq!(template('X', 2, 1).as_deref(), Some("UW6"));
+        assert_eq!(template('X', 3, 1), None);
+        assert_eq!(template('X', -2, 2).as_deref(), Some("BW1"));
+        assert_eq!(template('X', 0, 2).as_deref(), Some("BW3"));
+        assert_eq!(template('X', 1, 2), None);
+        assert_eq!(template('T', -3, 3).as_deref(), Some("TC1"));
+        assert_eq!(template('T', 0, 3).as_deref(), Some("TC4"));
+        assert_eq!(template('X', 0, 3), None);
+    }
+
+    #[test]
+    fn test_parse_feature() {
+        assert_eq!(parse_feature("X-1の"), Some(('X', -1, "の")));
+        assert_eq!(parse_feature("T0KH"), Some(('T', 0, "KH")));
+        assert_eq!(parse_feature("D0"), None);
+        assert_eq!(parse_feature("X1"), None);
+    }
+
+    #[test]
+    fn test_import_text_model() {
+        let model = "KyTea 0.3.2 T UTF8\n\
+                     BIAS -0.5\n\
+                     X-1の 1.25\n\
+                     X0国語 -0.75\n\
+                     T-1KH 0.5\n\
+                     D0I -2.0\n\
+                     X-2日本語 3.0\n";
+        let (learner, skipped) = import_text_model(model.as_bytes()).unwrap();
+        let weights: BTreeMap<&str, f64> = learner.weights().collect();
+
+        assert_eq!(weights["UW3:の"], 1.25);
+        assert_eq!(weights["BW3:国語"], -0.75);
+        // KyTea's kanji type covers both Litsea kanji and kanji numerals.
+        assert_eq!(weights["BC2:HI"], 0.5);
+        assert_eq!(weights["BC2:MI"], 0.5);
+        assert_eq!(weights.len(), 4);
+        // The dictionary feature and the character trigram are skipped.
+        assert_eq!(skipped, 2);
+        assert!((learner.get_bias() + 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_import_text_model_malformed() {
+        let err =
+            import_text_model("KyTea 0.4.7 T UTF8\nX-1の 1.0\nX0国\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 3, .. }));
+
+        let err = import_text_model("X-1の one\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 1, .. }));
+
+        // The header is only recognized on the first line.
+        let err = import_text_model("X-1の 1.0\nKyTea 0.4.7 T UTF8\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_import_text_model_empty() {
+        assert!(import_text_model("BIAS 1.0\n".as_bytes()).is_err());
+    }
+}
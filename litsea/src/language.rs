@@ -1,6 +1,11 @@
-use std::fmt;
-use std::str::FromStr;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
 
+#[cfg(feature = "regex")]
 use regex::Regex;
 
 /// Supported languages for word segmentation.
@@ -44,9 +49,8 @@ impl FromStr for Language {
 impl Language {
     /// Creates the character type patterns for this language.
     ///
-    /// Note: This method compiles Regex patterns on each call.
-    /// For performance-sensitive code, cache the returned `CharTypePatterns` instance
-    /// (as `Segmenter::new` already does).
+    /// The built-in patterns are static code point tables, so this is cheap; they do not
+    /// need the `regex` feature.
     pub fn char_type_patterns(&self) -> CharTypePatterns {
         match self {
             Language::Japanese => japanese_patterns(),
@@ -56,33 +60,262 @@ impl Language {
     }
 }
 
-/// A character matcher that can be either a regex or a custom closure.
+/// How Latin letters are divided into character types.
+///
+/// Case is a strong boundary signal for romaji acronyms and CamelCase product names
+/// (`iPhone`, `GitHub`), which the single "A" type does not see. The classes change the
+/// values of the character type features, so a model must be used with the classes its
+/// features were extracted with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "kebab-case"))]
+pub enum LatinClasses {
+    /// One type, "A", for all ASCII and full-width Latin letters.
+    #[default]
+    Single,
+    /// "AU" for uppercase and "AL" for lowercase letters, ASCII or full-width.
+    Case,
+    /// "AU" and "AL" for ASCII letters, "ZU" and "ZL" for full-width letters.
+    CaseWidth,
+}
+
+impl fmt::Display for LatinClasses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatinClasses::Single => write!(f, "single"),
+            LatinClasses::Case => write!(f, "case"),
+            LatinClasses::CaseWidth => write!(f, "case-width"),
+        }
+    }
+}
+
+impl FromStr for LatinClasses {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "single" => Ok(LatinClasses::Single),
+            "case" => Ok(LatinClasses::Case),
+            "case-width" => Ok(LatinClasses::CaseWidth),
+            _ => Err(format!(
+                "Unsupported Latin classes: '{}'. Supported: single, case, case-width",
+                s
+            )),
+        }
+    }
+}
+
+/// A character class of the built-in character type patterns.
+///
+/// The features of a model refer to the classes by their type codes, which
+/// [`CharType::code`] returns and [`str::parse`] reads. Each language uses some of the
+/// classes, see [`Language::char_type_patterns`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum CharType {
+    /// "M": a kanji numeral such as 三 or 億 (Japanese).
+    KanjiNumeral,
+    /// "H": a kanji (Japanese) or hanja (Korean).
+    Ideograph,
+    /// "I": hiragana (Japanese).
+    Hiragana,
+    /// "K": katakana (Japanese).
+    Katakana,
+    /// "P": CJK symbols and full-width punctuation.
+    Punctuation,
+    /// "A": an ASCII or full-width Latin letter.
+    Latin,
+    /// "AU": an uppercase Latin letter, see [`LatinClasses`].
+    LatinUpper,
+    /// "AL": a lowercase Latin letter, see [`LatinClasses`].
+    LatinLower,
+    /// "ZU": a full-width uppercase Latin letter, see [`LatinClasses::CaseWidth`].
+    FullwidthLatinUpper,
+    /// "ZL": a full-width lowercase Latin letter, see [`LatinClasses::CaseWidth`].
+    FullwidthLatinLower,
+    /// "N": an ASCII or full-width digit.
+    Digit,
+    /// "F": a high-frequency function word such as 的 or 了 (Chinese).
+    FunctionWord,
+    /// "C": a CJK Unified Ideograph (Chinese).
+    Hanzi,
+    /// "X": an ideograph of the CJK extensions or a compatibility ideograph (Chinese).
+    HanziExtension,
+    /// "R": a CJK or Kangxi radical (Chinese).
+    Radical,
+    /// "B": bopomofo (Chinese).
+    Bopomofo,
+    /// "E": a high-frequency particle or ending such as 은 or 를 (Korean).
+    Particle,
+    /// "SN": a Hangul syllable without a final consonant (Korean).
+    OpenSyllable,
+    /// "SF": a Hangul syllable with a final consonant (Korean).
+    ClosedSyllable,
+    /// "J": Hangul Jamo (Korean).
+    Jamo,
+    /// "G": Hangul Compatibility Jamo (Korean).
+    CompatibilityJamo,
+    /// "O": any other character.
+    Other,
+}
+
+impl CharType {
+    /// All classes, in the order of their declaration.
+    pub const ALL: [CharType; 22] = [
+        CharType::KanjiNumeral,
+        CharType::Ideograph,
+        CharType::Hiragana,
+        CharType::Katakana,
+        CharType::Punctuation,
+        CharType::Latin,
+        CharType::LatinUpper,
+        CharType::LatinLower,
+        CharType::FullwidthLatinUpper,
+        CharType::FullwidthLatinLower,
+        CharType::Digit,
+        CharType::FunctionWord,
+        CharType::Hanzi,
+        CharType::HanziExtension,
+        CharType::Radical,
+        CharType::Bopomofo,
+        CharType::Particle,
+        CharType::OpenSyllable,
+        CharType::ClosedSyllable,
+        CharType::Jamo,
+        CharType::CompatibilityJamo,
+        CharType::Other,
+    ];
+
+    /// Returns the type code of the class used in features.
+    #[must_use]
+    pub const fn code(self) -> &'static str {
+        match self {
+            CharType::KanjiNumeral => "M",
+            CharType::Ideograph => "H",
+            CharType::Hiragana => "I",
+            CharType::Katakana => "K",
+            CharType::Punctuation => "P",
+            CharType::Latin => "A",
+            CharType::LatinUpper => "AU",
+            CharType::LatinLower => "AL",
+            CharType::FullwidthLatinUpper => "ZU",
+            CharType::FullwidthLatinLower => "ZL",
+            CharType::Digit => "N",
+            CharType::FunctionWord => "F",
+            CharType::Hanzi => "C",
+            CharType::HanziExtension => "X",
+            CharType::Radical => "R",
+            CharType::Bopomofo => "B",
+            CharType::Particle => "E",
+            CharType::OpenSyllable => "SN",
+            CharType::ClosedSyllable => "SF",
+            CharType::Jamo => "J",
+            CharType::CompatibilityJamo => "G",
+            CharType::Other => "O",
+        }
+    }
+}
+
+impl fmt::Display for CharType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for CharType {
+    type Err = String;
+
+    /// Parses a type code, such as "K" or "SN".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        CharType::ALL
+            .into_iter()
+            .find(|char_type| char_type.code() == s)
+            .ok_or_else(|| format!("Unknown character type code: '{}'", s))
+    }
+}
+
+/// A table of inclusive code point ranges.
+pub type CharRanges = &'static [(char, char)];
+
+/// A character matcher: a code point table, a regex, or a built-in codepoint rule.
 enum CharMatcher {
+    /// Membership in a table of code point ranges.
+    Ranges(CharRanges),
     /// Pattern-based matching using a compiled regex.
+    #[cfg(feature = "regex")]
     Regex(Regex),
-    /// Custom matching logic using a closure.
-    Closure(Box<dyn Fn(&str) -> bool + Send + Sync>),
+    /// Hangul syllable (U+AC00..U+D7AF), split by whether it has a final consonant (받침).
+    HangulSyllable {
+        /// Whether the syllable must have a final consonant.
+        final_consonant: bool,
+    },
 }
 
 impl fmt::Debug for CharMatcher {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            CharMatcher::Ranges(ranges) => f.debug_tuple("Ranges").field(ranges).finish(),
+            #[cfg(feature = "regex")]
             CharMatcher::Regex(re) => f.debug_tuple("Regex").field(&re.as_str()).finish(),
-            CharMatcher::Closure(_) => f.debug_tuple("Closure").field(&"<fn>").finish(),
+            CharMatcher::HangulSyllable { final_consonant } => f
+                .debug_struct("HangulSyllable")
+                .field("final_consonant", final_consonant)
+                .finish(),
         }
     }
 }
 
 impl CharMatcher {
     /// Returns true if the given character matches this matcher.
-    fn is_match(&self, ch: &str) -> bool {
+    fn is_match(&self, ch: char) -> bool {
+        match self {
+            CharMatcher::Ranges(ranges) => ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&ch)),
+            #[cfg(feature = "regex")]
+            CharMatcher::Regex(re) => re.is_match(ch.encode_utf8(&mut [0; 4])),
+            CharMatcher::HangulSyllable { final_consonant } => {
+                let code = ch as u32;
+                (0xAC00..=0xD7AF).contains(&code)
+                    && (code - 0xAC00).is_multiple_of(28) != *final_consonant
+            }
+        }
+    }
+
+    /// Renders this matcher as a JavaScript predicate `function (c) { ... }`.
+    #[cfg(feature = "std")]
+    fn to_js(&self) -> String {
         match self {
-            CharMatcher::Regex(re) => re.is_match(ch),
-            CharMatcher::Closure(f) => f(ch),
+            CharMatcher::Ranges(ranges) => {
+                let class: String = ranges
+                    .iter()
+                    .map(|&(lo, hi)| {
+                        if lo == hi {
+                            format!("\\u{{{:x}}}", lo as u32)
+                        } else {
+                            format!("\\u{{{:x}}}-\\u{{{:x}}}", lo as u32, hi as u32)
+                        }
+                    })
+                    .collect();
+                format!("function (c) {{ return /[{}]/u.test(c); }}", class)
+            }
+            #[cfg(feature = "regex")]
+            CharMatcher::Regex(re) => {
+                format!("function (c) {{ return /{}/u.test(c); }}", re.as_str().replace('/', "\\/"))
+            }
+            CharMatcher::HangulSyllable { final_consonant } => format!(
+                "function (c) {{ var code = c.codePointAt(0); return code >= 0xAC00 && code <= 0xD7AF && ((code - 0xAC00) % 28 === 0) !== {}; }}",
+                final_consonant
+            ),
         }
     }
 }
 
+/// Returns a table matching each of the given characters.
+macro_rules! chars {
+    ($($c:literal),* $(,)?) => {
+        &[$(($c, $c)),*]
+    };
+}
+
 /// Character type classification patterns for a specific language.
 /// Each pattern maps a matcher to a type code string.
 #[derive(Debug)]
@@ -92,6 +325,8 @@ pub struct CharTypePatterns {
 
 impl CharTypePatterns {
     /// Creates a new instance of [`CharTypePatterns`] from regex patterns.
+    /// Requires the `regex` feature.
+    #[cfg(feature = "regex")]
     pub fn new(patterns: Vec<(Regex, &'static str)>) -> Self {
         CharTypePatterns {
             patterns: patterns
@@ -101,6 +336,83 @@ impl CharTypePatterns {
         }
     }
 
+    /// Creates a new instance of [`CharTypePatterns`] from code point tables.
+    ///
+    /// # Arguments
+    /// * `patterns` - Pairs of a table of inclusive code point ranges and the type code of
+    ///   the characters in it, in matching order.
+    pub fn from_ranges(patterns: Vec<(CharRanges, &'static str)>) -> Self {
+        CharTypePatterns {
+            patterns: patterns
+                .into_iter()
+                .map(|(ranges, label)| (CharMatcher::Ranges(ranges), label))
+                .collect(),
+        }
+    }
+
+    /// Creates the character type patterns of the original TinySegmenter, for models
+    /// published for TinySegmenter and its ports.
+    ///
+    /// They differ from the Japanese patterns in having no "P" type for punctuation,
+    /// which is "O", and in the half-width katakana they count as "K".
+    ///
+    /// # Returns
+    /// The patterns "M", "H", "I", "K", "A" and "N" of TinySegmenter.
+    pub fn tinysegmenter() -> Self {
+        CharTypePatterns::from_ranges(vec![
+            (
+                chars![
+                    '一', '二', '三', '四', '五', '六', '七', '八', '九', '十', '百', '千', '万',
+                    '億', '兆'
+                ],
+                "M",
+            ),
+            (&[('一', '龠'), ('々', '〆'), ('ヵ', 'ヶ')], "H"),
+            (&[('ぁ', 'ん')], "I"),
+            (&[('ァ', 'ヴ'), ('ー', 'ー'), ('ｱ', 'ﾝ'), ('ﾞ', 'ﾞ'), ('ｰ', 'ｰ')], "K"),
+            (LATIN, "A"),
+            (DIGITS, "N"),
+        ])
+    }
+
+    /// Replaces the "A" type of Latin letters with the given classes, in its place in
+    /// the matching order. [`LatinClasses::Single`] leaves the patterns unchanged.
+    ///
+    /// # Arguments
+    /// * `classes` - How Latin letters are divided into types.
+    ///
+    /// # Returns
+    /// The patterns with the Latin classes.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::{Language, LatinClasses};
+    ///
+    /// let patterns = Language::Japanese.char_type_patterns().with_latin_classes(LatinClasses::Case);
+    /// assert_eq!(patterns.get_type("G"), "AU");
+    /// assert_eq!(patterns.get_type("ｉ"), "AL");
+    /// ```
+    #[must_use]
+    pub fn with_latin_classes(mut self, classes: LatinClasses) -> Self {
+        let split: &[(CharRanges, &'static str)] = match classes {
+            LatinClasses::Single => return self,
+            LatinClasses::Case => &[(LATIN_UPPER, "AU"), (LATIN_LOWER, "AL")],
+            LatinClasses::CaseWidth => &[
+                (&[('A', 'Z')], "AU"),
+                (&[('a', 'z')], "AL"),
+                (&[('Ａ', 'Ｚ')], "ZU"),
+                (&[('ａ', 'ｚ')], "ZL"),
+            ],
+        };
+        if let Some(i) = self.patterns.iter().position(|(_, label)| *label == "A") {
+            self.patterns.splice(
+                i..=i,
+                split.iter().map(|&(ranges, label)| (CharMatcher::Ranges(ranges), label)),
+            );
+        }
+        self
+    }
+
     /// Creates a new instance of [`CharTypePatterns`] from heterogeneous matchers.
     fn from_matchers(patterns: Vec<(CharMatcher, &'static str)>) -> Self {
         CharTypePatterns { patterns }
@@ -115,6 +427,21 @@ impl CharTypePatterns {
     /// A string slice representing the type code of the character.
     /// Returns "O" (Other) if the character does not match any pattern.
     pub fn get_type(&self, ch: &str) -> &str {
+        match ch.chars().next() {
+            Some(ch) => self.get_char_type(ch),
+            None => "O",
+        }
+    }
+
+    /// Gets the type of a character, like [`CharTypePatterns::get_type`] without the
+    /// string slice.
+    ///
+    /// # Arguments
+    /// * `ch` - The character.
+    ///
+    /// # Returns
+    /// The type code of the character, or "O" (Other) if it matches no pattern.
+    pub fn get_char_type(&self, ch: char) -> &str {
         for (matcher, label) in &self.patterns {
             if matcher.is_match(ch) {
                 return label;
@@ -122,35 +449,128 @@ impl CharTypePatterns {
         }
         "O" // Other
     }
+
+    /// Gets the class of a character as a [`CharType`].
+    ///
+    /// # Arguments
+    /// * `ch` - The character.
+    ///
+    /// # Returns
+    /// The class of the character, or `None` if it has a type code of custom patterns
+    /// that is not a [`CharType`].
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::{CharType, Language};
+    ///
+    /// let patterns = Language::Japanese.char_type_patterns();
+    /// assert_eq!(patterns.char_type('ㇰ'), Some(CharType::Katakana));
+    /// assert_eq!(patterns.char_type('𠮷'), Some(CharType::Ideograph));
+    /// ```
+    #[must_use]
+    pub fn char_type(&self, ch: char) -> Option<CharType> {
+        self.get_char_type(ch).parse().ok()
+    }
+
+    /// Renders the patterns as JavaScript `(predicate, type code)` pairs in matching order.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_js(&self) -> Vec<(String, &'static str)> {
+        self.patterns.iter().map(|(matcher, label)| (matcher.to_js(), *label)).collect()
+    }
 }
 
+/// CJK Symbols and Punctuation + full-width punctuation.
+const CJK_PUNCTUATION: CharRanges = &[
+    ('\u{3000}', '\u{303F}'),
+    ('\u{FF01}', '\u{FF0F}'),
+    ('\u{FF1A}', '\u{FF20}'),
+    ('\u{FF3B}', '\u{FF40}'),
+    ('\u{FF5B}', '\u{FF65}'),
+];
+
+/// ASCII + full-width Latin letters.
+const LATIN: CharRanges = &[('a', 'z'), ('A', 'Z'), ('ａ', 'ｚ'), ('Ａ', 'Ｚ')];
+
+/// ASCII + full-width uppercase Latin letters.
+const LATIN_UPPER: CharRanges = &[('A', 'Z'), ('Ａ', 'Ｚ')];
+
+/// ASCII + full-width lowercase Latin letters.
+const LATIN_LOWER: CharRanges = &[('a', 'z'), ('ａ', 'ｚ')];
+
+/// ASCII + full-width digits.
+const DIGITS: CharRanges = &[('0', '9'), ('０', '９')];
+
+/// CJK Unified Ideographs.
+const CJK_UNIFIED: CharRanges = &[('\u{4E00}', '\u{9FFF}')];
+
+/// CJK Unified Ideographs Extension A, the CJK Compatibility Ideographs and the
+/// Supplementary and Tertiary Ideographic Planes (Extensions B to I and the
+/// Compatibility Ideographs Supplement).
+const CJK_EXTENSIONS: CharRanges = &[
+    ('\u{3400}', '\u{4DBF}'),
+    ('\u{F900}', '\u{FAFF}'),
+    ('\u{20000}', '\u{2FA1F}'),
+    ('\u{30000}', '\u{323AF}'),
+];
+
+/// Kanji of the Japanese patterns: the CJK Unified Ideographs up to 龠 and the
+/// extensions, with 々〆 and the small katakana ヵヶ used as counters.
+const KANJI: CharRanges = &[
+    ('一', '龠'),
+    ('々', '〆'),
+    ('ヵ', 'ヶ'),
+    ('\u{3400}', '\u{4DBF}'),
+    ('\u{F900}', '\u{FAFF}'),
+    ('\u{20000}', '\u{2FA1F}'),
+    ('\u{30000}', '\u{323AF}'),
+];
+
+/// Katakana, the prolonged sound mark, the Katakana Phonetic Extensions (small ㇰ to ㇿ
+/// of Ainu) and half-width katakana with their sound marks.
+const KATAKANA: CharRanges = &[
+    ('ァ', 'ヴ'),
+    ('ヷ', 'ヺ'),
+    ('ー', 'ー'),
+    ('\u{31F0}', '\u{31FF}'),
+    ('ｱ', 'ﾝ'),
+    ('ﾞ', 'ﾟ'),
+];
+
+/// Hanja of the Korean patterns: the CJK Unified Ideographs and the extensions.
+const HANJA: CharRanges = &[
+    ('\u{4E00}', '\u{9FFF}'),
+    ('\u{3400}', '\u{4DBF}'),
+    ('\u{F900}', '\u{FAFF}'),
+    ('\u{20000}', '\u{2FA1F}'),
+    ('\u{30000}', '\u{323AF}'),
+];
+
 /// Creates character type patterns for Japanese.
 ///
 /// Type codes:
 /// - "M": Kanji numbers (一二三四五六七八九十百千万億兆)
-/// - "H": Kanji (CJK ideographs)
+/// - "H": Kanji (CJK ideographs, including Extensions A to I and compatibility ideographs)
 /// - "I": Hiragana
-/// - "K": Katakana
+/// - "K": Katakana (including the Katakana Phonetic Extensions and half-width katakana)
 /// - "P": Punctuation (CJK symbols and full-width punctuation)
 /// - "A": ASCII and full-width Latin characters
 /// - "N": Digits (ASCII and full-width)
 /// - "O": Other (fallback)
 fn japanese_patterns() -> CharTypePatterns {
-    CharTypePatterns::new(vec![
-        (Regex::new(r"[一二三四五六七八九十百千万億兆]").expect("hardcoded regex pattern is valid"), "M"),
-        (Regex::new(r"[一-龠々〆ヵヶ]").expect("hardcoded regex pattern is valid"), "H"),
-        (Regex::new(r"[ぁ-ん]").expect("hardcoded regex pattern is valid"), "I"),
-        (Regex::new(r"[ァ-ヴーｱ-ﾝﾞﾟ]").expect("hardcoded regex pattern is valid"), "K"),
-        // CJK Symbols and Punctuation + full-width punctuation
+    CharTypePatterns::from_ranges(vec![
         (
-            Regex::new(
-                r"[\u{3000}-\u{303F}\u{FF01}-\u{FF0F}\u{FF1A}-\u{FF20}\u{FF3B}-\u{FF40}\u{FF5B}-\u{FF65}]",
-            )
-            .expect("hardcoded regex pattern is valid"),
-            "P",
+            chars![
+                '一', '二', '三', '四', '五', '六', '七', '八', '九', '十', '百', '千', '万', '億',
+                '兆'
+            ],
+            "M",
         ),
-        (Regex::new(r"[a-zA-Zａ-ｚＡ-Ｚ]").expect("hardcoded regex pattern is valid"), "A"),
-        (Regex::new(r"[0-9０-９]").expect("hardcoded regex pattern is valid"), "N"),
+        (KANJI, "H"),
+        (&[('ぁ', 'ん')], "I"),
+        (KATAKANA, "K"),
+        (CJK_PUNCTUATION, "P"),
+        (LATIN, "A"),
+        (DIGITS, "N"),
     ])
 }
 
@@ -159,7 +579,7 @@ fn japanese_patterns() -> CharTypePatterns {
 /// Type codes:
 /// - "F": High-frequency function words (虚词: 的了在是和不也 etc.)
 /// - "C": CJK Unified Ideographs (U+4E00..U+9FFF)
-/// - "X": CJK Extension A (U+3400..U+4DBF)
+/// - "X": CJK Extensions A to I and compatibility ideographs
 /// - "R": CJK Radicals and Kangxi Radicals (U+2E80..U+2FDF)
 /// - "P": Chinese punctuation and CJK symbols
 /// - "B": Bopomofo (Zhuyin)
@@ -167,39 +587,32 @@ fn japanese_patterns() -> CharTypePatterns {
 /// - "N": Digits (ASCII and full-width)
 /// - "O": Other (fallback)
 fn chinese_patterns() -> CharTypePatterns {
-    CharTypePatterns::from_matchers(vec![
+    CharTypePatterns::from_ranges(vec![
         // High-frequency function words (虚词)
         // Includes structural particles, aspect/modal particles, conjunctions,
         // prepositions, and common grammatical verbs/adverbs
         (
-            CharMatcher::Regex(
-                Regex::new(r"[的地得了着过吗呢吧啊嘛和与或但而且及在从到把被对向给是有不也都就要会能可]")
-                    .expect("hardcoded regex pattern is valid"),
-            ),
+            chars![
+                '的', '地', '得', '了', '着', '过', '吗', '呢', '吧', '啊', '嘛', '和', '与', '或',
+                '但', '而', '且', '及', '在', '从', '到', '把', '被', '对', '向', '给', '是', '有',
+                '不', '也', '都', '就', '要', '会', '能', '可',
+            ],
             "F",
         ),
         // CJK Unified Ideographs (remaining)
-        (CharMatcher::Regex(Regex::new(r"[\u{4E00}-\u{9FFF}]").expect("hardcoded regex pattern is valid")), "C"),
-        // CJK Extension A
-        (CharMatcher::Regex(Regex::new(r"[\u{3400}-\u{4DBF}]").expect("hardcoded regex pattern is valid")), "X"),
+        (CJK_UNIFIED, "C"),
+        // CJK Extensions and compatibility ideographs
+        (CJK_EXTENSIONS, "X"),
         // CJK Radicals Supplement + Kangxi Radicals
-        (CharMatcher::Regex(Regex::new(r"[\u{2E80}-\u{2FDF}]").expect("hardcoded regex pattern is valid")), "R"),
+        (&[('\u{2E80}', '\u{2FDF}')], "R"),
         // Chinese punctuation: CJK Symbols and Punctuation + full-width punctuation
-        (
-            CharMatcher::Regex(
-                Regex::new(
-                    r"[\u{3000}-\u{303F}\u{FF01}-\u{FF0F}\u{FF1A}-\u{FF20}\u{FF3B}-\u{FF40}\u{FF5B}-\u{FF65}]",
-                )
-                .expect("hardcoded regex pattern is valid"),
-            ),
-            "P",
-        ),
+        (CJK_PUNCTUATION, "P"),
         // Bopomofo + Bopomofo Extended
-        (CharMatcher::Regex(Regex::new(r"[\u{3100}-\u{312F}\u{31A0}-\u{31BF}]").expect("hardcoded regex pattern is valid")), "B"),
+        (&[('\u{3100}', '\u{312F}'), ('\u{31A0}', '\u{31BF}')], "B"),
         // ASCII + Full-width Latin
-        (CharMatcher::Regex(Regex::new(r"[a-zA-Zａ-ｚＡ-Ｚ]").expect("hardcoded regex pattern is valid")), "A"),
+        (LATIN, "A"),
         // Numbers
-        (CharMatcher::Regex(Regex::new(r"[0-9０-９]").expect("hardcoded regex pattern is valid")), "N"),
+        (DIGITS, "N"),
     ])
 }
 
@@ -211,7 +624,7 @@ fn chinese_patterns() -> CharTypePatterns {
 /// - "SF": Hangul Syllable with 받침 (e.g., 한, 글, 각)
 /// - "J": Hangul Jamo (U+1100..U+11FF)
 /// - "G": Hangul Compatibility Jamo (U+3130..U+318F)
-/// - "H": Hanja / CJK Ideographs (U+4E00..U+9FFF)
+/// - "H": Hanja / CJK Ideographs, including the extensions
 /// - "P": Korean punctuation and CJK symbols
 /// - "A": ASCII and full-width Latin characters
 /// - "N": Digits (ASCII and full-width)
@@ -221,83 +634,35 @@ fn korean_patterns() -> CharTypePatterns {
         // High-frequency particles/endings (조사/어미)
         // These characters are overwhelmingly used as grammatical particles:
         // 은/는 (topic), 을/를 (object), 의 (possessive), 에 (locative)
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[은는을를의에]").expect("hardcoded regex pattern is valid"),
-            ),
-            "E",
-        ),
+        (CharMatcher::Ranges(chars!['은', '는', '을', '를', '의', '에']), "E"),
         // Hangul Syllable without 받침 (final consonant)
         // (codepoint - 0xAC00) % 28 == 0
         (
-            CharMatcher::Closure(Box::new(|ch: &str| {
-                if let Some(cp) = ch.chars().next() {
-                    let code = cp as u32;
-                    (0xAC00..=0xD7AF).contains(&code) && (code - 0xAC00).is_multiple_of(28)
-                } else {
-                    false
-                }
-            })),
+            CharMatcher::HangulSyllable {
+                final_consonant: false,
+            },
             "SN",
         ),
         // Hangul Syllable with 받침 (final consonant)
         // (codepoint - 0xAC00) % 28 != 0
         (
-            CharMatcher::Closure(Box::new(|ch: &str| {
-                if let Some(cp) = ch.chars().next() {
-                    let code = cp as u32;
-                    (0xAC00..=0xD7AF).contains(&code) && !(code - 0xAC00).is_multiple_of(28)
-                } else {
-                    false
-                }
-            })),
+            CharMatcher::HangulSyllable {
+                final_consonant: true,
+            },
             "SF",
         ),
         // Hangul Jamo
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[\u{1100}-\u{11FF}]").expect("hardcoded regex pattern is valid"),
-            ),
-            "J",
-        ),
+        (CharMatcher::Ranges(&[('\u{1100}', '\u{11FF}')]), "J"),
         // Hangul Compatibility Jamo
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[\u{3130}-\u{318F}]").expect("hardcoded regex pattern is valid"),
-            ),
-            "G",
-        ),
-        // Hanja (CJK Unified Ideographs)
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[\u{4E00}-\u{9FFF}]").expect("hardcoded regex pattern is valid"),
-            ),
-            "H",
-        ),
+        (CharMatcher::Ranges(&[('\u{3130}', '\u{318F}')]), "G"),
+        // Hanja (CJK Unified Ideographs and extensions)
+        (CharMatcher::Ranges(HANJA), "H"),
         // Korean punctuation: CJK Symbols and Punctuation + full-width punctuation
-        (
-            CharMatcher::Regex(
-                Regex::new(
-                    r"[\u{3000}-\u{303F}\u{FF01}-\u{FF0F}\u{FF1A}-\u{FF20}\u{FF3B}-\u{FF40}\u{FF5B}-\u{FF65}]",
-                )
-                .expect("hardcoded regex pattern is valid"),
-            ),
-            "P",
-        ),
+        (CharMatcher::Ranges(CJK_PUNCTUATION), "P"),
         // ASCII + Full-width Latin
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[a-zA-Zａ-ｚＡ-Ｚ]").expect("hardcoded regex pattern is valid"),
-            ),
-            "A",
-        ),
+        (CharMatcher::Ranges(LATIN), "A"),
         // Numbers
-        (
-            CharMatcher::Regex(
-                Regex::new(r"[0-9０-９]").expect("hardcoded regex pattern is valid"),
-            ),
-            "N",
-        ),
+        (CharMatcher::Ranges(DIGITS), "N"),
     ])
 }
 
@@ -418,4 +783,96 @@ mod tests {
         assert_eq!(p.get_type("5"), "N"); // Digit
         assert_eq!(p.get_type("@"), "O"); // Other
     }
+
+    // --- Extension block tests ---
+
+    #[test]
+    fn test_extension_blocks() {
+        let jp = Language::Japanese.char_type_patterns();
+        assert_eq!(jp.get_type("㐂"), "H"); // CJK Extension A (U+3402)
+        assert_eq!(jp.get_type("𠮷"), "H"); // CJK Extension B (U+20BB7)
+        assert_eq!(jp.get_type("\u{2B740}"), "H"); // CJK Extension D
+        assert_eq!(jp.get_type("\u{30000}"), "H"); // CJK Extension G
+        assert_eq!(jp.get_type("\u{F9DC}"), "H"); // CJK Compatibility Ideograph
+        assert_eq!(jp.get_type("ㇰ"), "K"); // Katakana Phonetic Extensions (U+31F0)
+        assert_eq!(jp.get_type("ㇿ"), "K"); // Katakana Phonetic Extensions (U+31FF)
+        assert_eq!(jp.get_type("ヷ"), "K"); // Katakana VA (U+30F7)
+
+        let cn = Language::Chinese.char_type_patterns();
+        assert_eq!(cn.get_type("㐀"), "X"); // CJK Extension A
+        assert_eq!(cn.get_type("𠀀"), "X"); // CJK Extension B (U+20000)
+
+        let kr = Language::Korean.char_type_patterns();
+        assert_eq!(kr.get_type("𠮷"), "H"); // CJK Extension B
+        assert_eq!(kr.get_type("\u{F900}"), "H"); // CJK Compatibility Ideograph
+
+        // The TinySegmenter patterns stay as they were.
+        assert_eq!(CharTypePatterns::tinysegmenter().get_type("𠮷"), "O");
+    }
+
+    // --- CharType tests ---
+
+    #[test]
+    fn test_char_type() {
+        for char_type in CharType::ALL {
+            assert_eq!(char_type.code().parse::<CharType>().unwrap(), char_type);
+        }
+        assert!("Z".parse::<CharType>().is_err());
+
+        let jp = Language::Japanese.char_type_patterns();
+        assert_eq!(jp.char_type('億'), Some(CharType::KanjiNumeral));
+        assert_eq!(jp.char_type('ｶ'), Some(CharType::Katakana));
+        assert_eq!(jp.char_type('@'), Some(CharType::Other));
+        assert_eq!(jp.get_char_type('あ'), "I");
+
+        let kr = Language::Korean
+            .char_type_patterns()
+            .with_latin_classes(LatinClasses::CaseWidth);
+        assert_eq!(kr.char_type('한'), Some(CharType::ClosedSyllable));
+        assert_eq!(kr.char_type('Ｚ'), Some(CharType::FullwidthLatinUpper));
+
+        // Every type code of the built-in patterns is a CharType.
+        for language in [Language::Japanese, Language::Chinese, Language::Korean] {
+            for (_, label) in &language.char_type_patterns().patterns {
+                assert!(label.parse::<CharType>().is_ok(), "{}", label);
+            }
+        }
+    }
+
+    // --- Latin class tests ---
+
+    #[test]
+    fn test_latin_classes_from_str() {
+        for classes in [LatinClasses::Single, LatinClasses::Case, LatinClasses::CaseWidth] {
+            assert_eq!(classes.to_string().parse::<LatinClasses>().unwrap(), classes);
+        }
+        assert_eq!("Case".parse::<LatinClasses>().unwrap(), LatinClasses::Case);
+        assert!("width".parse::<LatinClasses>().is_err());
+    }
+
+    #[test]
+    fn test_latin_classes() {
+        for language in [Language::Japanese, Language::Chinese, Language::Korean] {
+            let p = language.char_type_patterns().with_latin_classes(LatinClasses::Single);
+            assert_eq!(p.get_type("A"), "A");
+
+            let p = language.char_type_patterns().with_latin_classes(LatinClasses::Case);
+            assert_eq!(p.get_type("A"), "AU");
+            assert_eq!(p.get_type("z"), "AL");
+            assert_eq!(p.get_type("Ｂ"), "AU");
+            assert_eq!(p.get_type("ａ"), "AL");
+            assert_eq!(p.get_type("5"), "N");
+
+            let p = language.char_type_patterns().with_latin_classes(LatinClasses::CaseWidth);
+            assert_eq!(p.get_type("A"), "AU");
+            assert_eq!(p.get_type("z"), "AL");
+            assert_eq!(p.get_type("Ｂ"), "ZU");
+            assert_eq!(p.get_type("ａ"), "ZL");
+            assert_eq!(p.get_type("@"), "O");
+        }
+        // The other types keep their order.
+        let p = Language::Japanese.char_type_patterns().with_latin_classes(LatinClasses::Case);
+        assert_eq!(p.get_type("。"), "P");
+        assert_eq!(p.get_type("億"), "M");
+    }
 }
@@ -0,0 +1,758 @@
+//! Lattice segmentation, combining a lexicon with the boundary classifier.
+//!
+//! The classifier decides each boundary from the few characters around it, so it often
+//! splits long words it has rarely seen, such as names and technical terms, even when
+//! they are listed in a dictionary. A [`LatticeSegmenter`] builds the lattice of all the
+//! ways to split a sentence into words, and picks the best path with the Viterbi
+//! algorithm. A path scores the margins of the classifier shifted by the
+//! [boundary bias](Segmenter::set_boundary_bias), as
+//! [`Segmentation::score`](crate::segmenter::Segmentation::score) does: plus the margin
+//! of every boundary it places and minus that of every boundary it does not. Each word
+//! found in the [`Lexicon`] earns a bonus for every position inside it, which the
+//! classifier must outweigh to split it. The lexicon is a word list, or a dictionary in
+//! the CSV format of MeCab such as IPADIC, read by [`Lexicon::from_mecab_csv`]. With
+//! [`LatticeSegmenter::with_strict`], lexicon words are never split at all.
+//!
+//! Without lexicon words, the best path is the segmentation of [`Segmenter::segment`].
+//! The margins are those of the decisions of the segmenter, so they see the boundaries
+//! it placed before them, not those of the path. Boundaries the
+//! [grouping rules](Segmenter::set_grouping_rules) forbid are never placed.
+//!
+//! ```
+//! use litsea::adaboost::AdaBoost;
+//! use litsea::language::Language;
+//! use litsea::lattice::{LatticeSegmenter, Lexicon};
+//! use litsea::segmenter::Segmenter;
+//!
+//! let mut learner = AdaBoost::new(0.01, 100);
+//! learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+//! let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+//! assert_eq!(segmenter.segment("おもてなしの心"), ["おもて", "なしの", "心"]);
+//!
+//! let lexicon = Lexicon::from_tsv("おもてなし\n".as_bytes()).unwrap();
+//! let lattice = LatticeSegmenter::new(lexicon);
+//! let words = lattice.segment(&segmenter, "おもてなしの心");
+//! let surfaces: Vec<&str> = words.iter().map(|w| w.surface.as_str()).collect();
+//! assert_eq!(surfaces, ["おもてなし", "の", "心"]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::OnceLock;
+
+use fst::Set;
+
+use crate::error::LitseaError;
+use crate::segmenter::{ScoredWord, Segmenter};
+use crate::token::{Token, set_char_offsets};
+
+/// The number of part-of-speech columns of a MeCab dictionary entry, after the cost.
+const MECAB_POS_COLUMNS: usize = 4;
+
+/// What a dictionary records about a word of a [`Lexicon`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LexiconEntry {
+    /// The cost of the word in a MeCab dictionary, lower for more frequent words, or
+    /// `None` for words of a word list.
+    pub cost: Option<i32>,
+    /// The part of speech from coarse to fine, without the unset levels written as `*`.
+    pub pos: Vec<String>,
+}
+
+/// A set of known words, looked up at every position of a sentence, read from a word list
+/// or a MeCab dictionary.
+///
+/// The words are searched in a finite state transducer built from them the first time
+/// they are searched after a change, so a search walks the text once whatever the number
+/// and length of the words.
+#[derive(Debug, Clone, Default)]
+pub struct Lexicon {
+    words: BTreeMap<String, LexiconEntry>,
+    /// The words as an FST, or unset if they changed since it was built.
+    index: OnceLock<Set<Vec<u8>>>,
+}
+
+impl Lexicon {
+    /// Creates an empty lexicon.
+    ///
+    /// # Returns
+    /// A new [`Lexicon`] without words.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a lexicon with one word per line, or from the first column of a TSV file,
+    /// such as a [lemma dictionary](crate::lemma::LemmaDictionary). Empty lines and lines
+    /// starting with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `reader` - The word list.
+    ///
+    /// # Returns
+    /// The lexicon.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if reading fails.
+    pub fn from_tsv<R: BufRead>(reader: R) -> crate::error::Result<Self> {
+        let mut lexicon = Self::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            lexicon.insert(line.split('\t').next().unwrap_or_default().trim());
+        }
+        Ok(lexicon)
+    }
+
+    /// Loads a lexicon from a file. See [`from_tsv`](Self::from_tsv).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the word list.
+    ///
+    /// # Returns
+    /// The loaded lexicon.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if the file cannot be read.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        Self::from_tsv(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads the entries of a dictionary in the CSV format of MeCab, as used by IPADIC
+    /// and UniDic: the surface, the left and right context IDs, the cost and the part of
+    /// speech in the first columns of each line. Fields may be quoted with `"`, which
+    /// MeCab requires for surfaces with a comma. Empty lines are ignored.
+    ///
+    /// # Arguments
+    /// * `reader` - The CSV content, in UTF-8.
+    ///
+    /// # Returns
+    /// The lexicon. A surface listed more than once, e.g. as different parts of speech,
+    /// keeps the entry with the lowest cost.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if reading fails, or [`LitseaError::Parse`] if a line
+    /// has no surface or no valid cost.
+    pub fn read_mecab_csv<R: BufRead>(reader: R) -> crate::error::Result<Self> {
+        let mut lexicon = Self::new();
+        lexicon.add_mecab_csv(reader)?;
+        Ok(lexicon)
+    }
+
+    /// Loads a dictionary in the CSV format of MeCab. See
+    /// [`read_mecab_csv`](Self::read_mecab_csv).
+    ///
+    /// # Arguments
+    /// * `path` - A CSV file, or a dictionary directory whose `.csv` files are all read,
+    ///   such as the source directory of IPADIC. The files must be in UTF-8; the EUC-JP
+    ///   files of IPADIC can be converted with `iconv -f EUC-JP -t UTF-8`.
+    ///
+    /// # Returns
+    /// The loaded lexicon.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if a file cannot be read, or [`LitseaError::Parse`] if
+    /// it is malformed. Errors in a file of a directory name the file.
+    pub fn from_mecab_csv<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        let path = path.as_ref();
+        if !path.is_dir() {
+            return Self::read_mecab_csv(BufReader::new(File::open(path)?));
+        }
+        let mut files = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv")) {
+                files.push(file);
+            }
+        }
+        files.sort();
+        let mut lexicon = Self::new();
+        for file in files {
+            lexicon.add_mecab_csv(BufReader::new(File::open(&file)?)).map_err(|e| match e {
+                LitseaError::Parse { line, reason } => LitseaError::Parse {
+                    line,
+                    reason: format!("{}: {}", file.display(), reason),
+                },
+                LitseaError::Io(e) => {
+                    LitseaError::Io(io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))
+                }
+                e => e,
+            })?;
+        }
+        Ok(lexicon)
+    }
+
+    fn add_mecab_csv<R: BufRead>(&mut self, reader: R) -> crate::error::Result<()> {
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = csv_fields(line);
+            let invalid = |reason: String| LitseaError::Parse {
+                line: i + 1,
+                reason,
+            };
+            let surface = fields.first().map(String::as_str).unwrap_or_default();
+            if surface.is_empty() {
+                return Err(invalid(format!("Dictionary entry has no surface: '{}'", line)));
+            }
+            let cost = match fields.get(3).map(|cost| cost.trim().parse::<i32>()) {
+                Some(Ok(cost)) => cost,
+                _ => {
+                    return Err(invalid(format!("Dictionary entry has no valid cost: '{}'", line)));
+                }
+            };
+            let pos = fields
+                .iter()
+                .skip(4)
+                .take(MECAB_POS_COLUMNS)
+                .filter(|pos| !pos.is_empty() && *pos != "*")
+                .cloned()
+                .collect();
+            self.insert_entry(
+                surface,
+                LexiconEntry {
+                    cost: Some(cost),
+                    pos,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds a word without dictionary information. Empty words are ignored, and words
+    /// already in the lexicon keep their entry.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    pub fn insert(&mut self, word: &str) {
+        if !self.words.contains_key(word) {
+            self.insert_entry(word, LexiconEntry::default());
+        }
+    }
+
+    /// Adds a word with its dictionary entry. Empty words are ignored. If the word is
+    /// already in the lexicon with a cost, the entry with the lower cost is kept.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    /// * `entry` - What the dictionary records about it.
+    pub fn insert_entry(&mut self, word: &str, entry: LexiconEntry) {
+        if word.is_empty() {
+            return;
+        }
+        if let Some(old) = self.words.get(word) {
+            if old.cost.is_some_and(|old| entry.cost.is_none_or(|new| new >= old)) {
+                return;
+            }
+        }
+        self.words.insert(word.to_string(), entry);
+        self.index.take();
+    }
+
+    /// Returns `true` if the lexicon has the word.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    #[must_use]
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains_key(word)
+    }
+
+    /// Returns the dictionary entry of a word.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    ///
+    /// # Returns
+    /// The entry, or `None` if the word is not in the lexicon.
+    #[must_use]
+    pub fn get(&self, word: &str) -> Option<&LexiconEntry> {
+        self.words.get(word)
+    }
+
+    /// Returns the number of words.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if the lexicon has no words.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Finds the words that `text` starts with.
+    ///
+    /// # Arguments
+    /// * `text` - The text, e.g. the rest of a sentence.
+    ///
+    /// # Returns
+    /// The lengths in bytes of the words, shortest first.
+    #[must_use]
+    pub fn common_prefixes(&self, text: &str) -> Vec<usize> {
+        let fst = self.index().as_fst();
+        let mut node = fst.root();
+        let mut ends = Vec::new();
+        for (i, b) in text.bytes().enumerate() {
+            let Some(transition) = node.find_input(b) else {
+                break;
+            };
+            node = fst.node(node.transition(transition).addr);
+            // Words are whole characters, so a final state is at a character boundary.
+            if node.is_final() {
+                ends.push(i + 1);
+            }
+        }
+        ends
+    }
+
+    /// Finds the positions of a sentence inside lexicon words, where a boundary would
+    /// split one, in the form of [`GroupingRules::joined`](crate::grouping::GroupingRules::joined).
+    /// The words are matched leftmost-longest: from the start of the sentence, the
+    /// longest word at each position is taken, and the search continues after it.
+    ///
+    /// # Arguments
+    /// * `sentence` - The sentence.
+    ///
+    /// # Returns
+    /// One flag per character of the sentence, `true` if the character continues a
+    /// lexicon word; empty if the lexicon is empty.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::lattice::Lexicon;
+    ///
+    /// let lexicon = Lexicon::from_tsv("外国\n外国人\n".as_bytes()).unwrap();
+    /// assert_eq!(lexicon.joined("外国人"), [false, true, true]);
+    /// ```
+    #[must_use]
+    pub fn joined(&self, sentence: &str) -> Vec<bool> {
+        if self.is_empty() {
+            return Vec::new();
+        }
+        let offsets: Vec<usize> = sentence.char_indices().map(|(i, _)| i).collect();
+        let mut joined = vec![false; offsets.len()];
+        let mut k = 0;
+        while k < offsets.len() {
+            let start = offsets[k];
+            match self.common_prefixes(&sentence[start..]).last() {
+                Some(&len) => {
+                    let end = offsets.partition_point(|&offset| offset < start + len);
+                    joined[k + 1..end].fill(true);
+                    k = end;
+                }
+                None => k += 1,
+            }
+        }
+        joined
+    }
+
+    /// Returns the FST of the words, built if they changed since it was last built.
+    fn index(&self) -> &Set<Vec<u8>> {
+        self.index.get_or_init(|| {
+            Set::from_iter(self.words.keys()).expect("the keys of a BTreeMap are sorted")
+        })
+    }
+}
+
+/// Splits a CSV line into its fields, removing the quotes of quoted fields and
+/// unescaping the doubled quotes in them.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted || field.is_empty() => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Segments sentences with the Viterbi path through a lattice of the words of a
+/// [`Lexicon`] and the decisions of a [`Segmenter`].
+#[derive(Debug, Clone)]
+pub struct LatticeSegmenter {
+    lexicon: Lexicon,
+    bonus: f64,
+    strict: bool,
+}
+
+/// The best path to a position of the lattice: its score, and the start of its last word.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    score: f64,
+    start: usize,
+}
+
+impl LatticeSegmenter {
+    /// Creates a lattice segmenter that gives lexicon words a bonus of 0.5 for every
+    /// position inside them.
+    ///
+    /// # Arguments
+    /// * `lexicon` - The known words.
+    ///
+    /// # Returns
+    /// A new [`LatticeSegmenter`].
+    #[must_use]
+    pub fn new(lexicon: Lexicon) -> Self {
+        LatticeSegmenter {
+            lexicon,
+            bonus: 0.5,
+            strict: false,
+        }
+    }
+
+    /// Sets the bonus of lexicon words for every position inside them. Boundary margins
+    /// are usually below 1, so a bonus around 1 keeps almost every listed word whole, and
+    /// 0 leaves the segmentation to the classifier.
+    ///
+    /// # Arguments
+    /// * `bonus` - The bonus per position inside a lexicon word.
+    ///
+    /// # Returns
+    /// The lattice segmenter with the new bonus.
+    #[must_use]
+    pub fn with_bonus(mut self, bonus: f64) -> Self {
+        self.bonus = bonus;
+        self
+    }
+
+    /// Sets whether lexicon words are never split. The words are then matched as
+    /// [`Lexicon::joined`] does, and no boundary is placed inside them, whatever the
+    /// classifier says; the other boundaries are still chosen by the lattice.
+    ///
+    /// # Arguments
+    /// * `strict` - `true` to never split lexicon words.
+    ///
+    /// # Returns
+    /// The lattice segmenter with the new setting.
+    #[must_use]
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Returns the known words.
+    #[must_use]
+    pub fn lexicon(&self) -> &Lexicon {
+        &self.lexicon
+    }
+
+    /// Segments a sentence into the words of the best path through the lattice.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter whose boundary scores the paths are scored with.
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The words of the best path, as [`ScoredWord`]s with the score of the boundary
+    /// before them. Empty if the sentence is empty.
+    #[must_use]
+    pub fn segment(&self, segmenter: &Segmenter, sentence: &str) -> Vec<ScoredWord> {
+        if sentence.is_empty() {
+            return Vec::new();
+        }
+        trace_span!(TRACE, "lattice_segment", bytes = sentence.len());
+        let offsets: Vec<usize> =
+            sentence.char_indices().map(|(i, _)| i).chain([sentence.len()]).collect();
+        let n = offsets.len() - 1;
+        let scores = segmenter.boundary_scores(sentence);
+        let mut joined = segmenter.grouping_rules().joined(sentence);
+        if self.strict {
+            let words = self.lexicon.joined(sentence);
+            if joined.is_empty() {
+                joined = words;
+            } else {
+                joined.iter_mut().zip(words).for_each(|(joined, word)| *joined |= word);
+            }
+        }
+        let bias = segmenter.boundary_bias();
+
+        // margins[k] is the margin of the boundary before character k, and sums[k] the
+        // sum of the margins up to it.
+        let mut margins = vec![0.0; n + 1];
+        let mut sums = vec![0.0; n + 1];
+        for k in 1..n {
+            margins[k] = scores[k - 1] + bias;
+            sums[k] = sums[k - 1] + margins[k];
+        }
+        // A word from i to j places no boundary inside it, which loses the margins from
+        // i + 1 to j - 1, and places one at j unless j is the end of the sentence.
+        let inside = |i: usize, j: usize| sums[j - 1] - sums[i];
+
+        let mut best: Vec<Option<Node>> = vec![None; n + 1];
+        best[0] = Some(Node {
+            score: 0.0,
+            start: 0,
+        });
+        // Lexicon words ending at each position, found from the positions before it.
+        let mut known: Vec<Option<Node>> = vec![None; n + 1];
+        // The start of the best word of any kind ending at the next position, and the
+        // score of its path plus the margins up to its start.
+        let mut open = (0.0, 0);
+        self.relax(sentence, &offsets, 0, 0.0, &inside, &mut known);
+        for j in 1..=n {
+            let mut node = Node {
+                score: open.0 - sums[j - 1],
+                start: open.1,
+            };
+            // On ties the word ending here is the shorter one, that is, the boundary is
+            // placed, as `segment` does.
+            if let Some(word) = known[j].filter(|word| word.score > node.score) {
+                node = word;
+            }
+            if j < n {
+                if joined.get(j).copied().unwrap_or(false) {
+                    continue;
+                }
+                node.score += margins[j];
+            }
+            best[j] = Some(node);
+            if node.score + sums[j] >= open.0 {
+                open = (node.score + sums[j], j);
+            }
+            self.relax(sentence, &offsets, j, node.score, &inside, &mut known);
+        }
+
+        let mut bounds = vec![n];
+        let mut j = n;
+        while j > 0 {
+            j = best[j].map_or(0, |node| node.start);
+            bounds.push(j);
+        }
+        bounds.reverse();
+        bounds
+            .windows(2)
+            .map(|word| ScoredWord {
+                surface: sentence[offsets[word[0]]..offsets[word[1]]].to_string(),
+                start: offsets[word[0]],
+                end: offsets[word[1]],
+                score: (word[0] > 0).then(|| scores[word[0] - 1]),
+            })
+            .collect()
+    }
+
+    /// Records the lexicon words starting at character `i`, reached with a path of
+    /// `score`, as candidates for the positions where they end.
+    fn relax<F>(
+        &self,
+        sentence: &str,
+        offsets: &[usize],
+        i: usize,
+        score: f64,
+        inside: &F,
+        known: &mut [Option<Node>],
+    ) where
+        F: Fn(usize, usize) -> f64,
+    {
+        let start = offsets[i];
+        for len in self.lexicon.common_prefixes(&sentence[start..]) {
+            let j = offsets.partition_point(|&offset| offset < start + len);
+            let candidate = Node {
+                score: score - inside(i, j) + self.bonus * (j - i - 1) as f64,
+                start: i,
+            };
+            if known[j].is_none_or(|word| candidate.score > word.score) {
+                known[j] = Some(candidate);
+            }
+        }
+    }
+
+    /// Segments a sentence into [`Token`]s, as [`segment`](Self::segment) does.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter whose boundary scores the paths are scored with.
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The tokens of the best path, with their byte and character offsets and without
+    /// annotations.
+    #[must_use]
+    pub fn tokenize(&self, segmenter: &Segmenter, sentence: &str) -> Vec<Token> {
+        let mut tokens: Vec<Token> =
+            self.segment(segmenter, sentence).into_iter().map(Token::from).collect();
+        set_char_offsets(&mut tokens, sentence);
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::{NamedTempFile, TempDir};
+
+    use crate::grouping::GroupingRules;
+    use crate::test_util::rwcp_segmenter;
+
+    fn surfaces(words: &[ScoredWord]) -> Vec<&str> {
+        words.iter().map(|w| w.surface.as_str()).collect()
+    }
+
+    #[test]
+    fn test_lexicon() {
+        let lexicon =
+            Lexicon::from_tsv("# words\n外国\n外国人\tがいこくじん\n\n参政権\r\n".as_bytes())
+                .unwrap();
+        assert_eq!(lexicon.len(), 3);
+        assert!(lexicon.contains("外国人"));
+        assert!(lexicon.contains("参政権"));
+        assert_eq!(lexicon.common_prefixes("外国人参政権"), ["外国".len(), "外国人".len()]);
+        assert!(lexicon.common_prefixes("人参").is_empty());
+        assert!(lexicon.common_prefixes("").is_empty());
+
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), "外国人\n").unwrap();
+        assert!(Lexicon::load(file.path()).unwrap().contains("外国人"));
+    }
+
+    #[test]
+    fn test_mecab_csv() {
+        let csv = "\
+外国人,1285,1285,5785,名詞,一般,*,*,*,*,外国人,ガイコクジン,ガイコクジン
+外国人,1285,1285,9000,名詞,固有名詞,*,*,*,*,外国人,ガイコクジン,ガイコクジン
+\"\"\"\",5,5,100,記号,括弧開,*,*,*,*,\",\",*,*
+
+\"1,000\",1,1,0,名詞,数,*,*,*,*,*
+";
+        let lexicon = Lexicon::read_mecab_csv(csv.as_bytes()).unwrap();
+        assert_eq!(lexicon.len(), 3);
+        let entry = lexicon.get("外国人").unwrap();
+        assert_eq!(entry.cost, Some(5785));
+        assert_eq!(entry.pos, ["名詞", "一般"]);
+        assert_eq!(lexicon.get("\"").unwrap().pos, ["記号", "括弧開"]);
+        assert!(lexicon.contains("1,000"));
+
+        // Words of a word list do not replace dictionary entries.
+        let mut lexicon = lexicon;
+        lexicon.insert("外国人");
+        assert_eq!(lexicon.get("外国人").unwrap().cost, Some(5785));
+
+        let error = Lexicon::read_mecab_csv("外国人,1,1\n".as_bytes()).unwrap_err();
+        assert!(matches!(error, LitseaError::Parse { line: 1, .. }), "{}", error);
+        assert!(Lexicon::read_mecab_csv(",1,1,0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_from_mecab_csv() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("Noun.csv"), "外国人,1,1,10,名詞,一般,*,*\n").unwrap();
+        fs::write(dir.path().join("Verb.csv"), "書く,2,2,20,動詞,自立,*,*\n").unwrap();
+        fs::write(dir.path().join("matrix.def"), "1 1\n").unwrap();
+        let lexicon = Lexicon::from_mecab_csv(dir.path()).unwrap();
+        assert_eq!(lexicon.len(), 2);
+        assert_eq!(lexicon.get("書く").unwrap().pos, ["動詞", "自立"]);
+
+        let file = dir.path().join("Noun.csv");
+        assert!(Lexicon::from_mecab_csv(&file).unwrap().contains("外国人"));
+
+        fs::write(dir.path().join("Bad.csv"), "壊れた,1,1,x\n").unwrap();
+        let error = Lexicon::from_mecab_csv(dir.path()).unwrap_err();
+        assert!(
+            matches!(&error, LitseaError::Parse { line: 1, reason } if reason.contains("Bad.csv: ")),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_segment() {
+        let segmenter = rwcp_segmenter();
+        let sentence = "おもてなしの心";
+        let lexicon = Lexicon::from_tsv("おもてなし\n".as_bytes()).unwrap();
+        let lattice = LatticeSegmenter::new(lexicon);
+        let words = lattice.segment(&segmenter, sentence);
+        assert_eq!(surfaces(&words), ["おもてなし", "の", "心"]);
+        assert_eq!(words[1].start, "おもてなし".len());
+        let scores = segmenter.boundary_scores(sentence);
+        assert_eq!(words[0].score, None);
+        assert_eq!(words[1].score, Some(scores[4]));
+
+        // Without a bonus, the classifier decides.
+        let words = lattice.with_bonus(0.0).segment(&segmenter, sentence);
+        assert_eq!(surfaces(&words), segmenter.segment(sentence));
+        assert_ne!(surfaces(&words), ["おもてなし", "の", "心"]);
+        assert!(LatticeSegmenter::new(Lexicon::new()).segment(&segmenter, "").is_empty());
+    }
+
+    #[test]
+    fn test_segment_without_lexicon_words() {
+        let segmenter = rwcp_segmenter();
+        let lattice = LatticeSegmenter::new(Lexicon::new());
+        for sentence in ["これはテストです。", "今日は良い天気ですね", "あ"] {
+            let words = lattice.segment(&segmenter, sentence);
+            assert_eq!(surfaces(&words), segmenter.segment(sentence));
+        }
+        let sentence = "これはテストです。";
+        assert_eq!(lattice.tokenize(&segmenter, sentence), segmenter.tokenize(sentence));
+    }
+
+    #[test]
+    fn test_common_prefixes() {
+        let mut lexicon = Lexicon::from_tsv("外\n外国\n外国人参政権\n".as_bytes()).unwrap();
+        assert_eq!(
+            lexicon.common_prefixes("外国人参政権"),
+            ["外".len(), "外国".len(), "外国人参政権".len()]
+        );
+        assert_eq!(lexicon.common_prefixes("外国人"), ["外".len(), "外国".len()]);
+        // The index is rebuilt after a change.
+        lexicon.insert("外国人");
+        assert_eq!(lexicon.common_prefixes("外国人"), ["外".len(), "外国".len(), "外国人".len()]);
+        assert!(Lexicon::new().common_prefixes("外国人").is_empty());
+    }
+
+    #[test]
+    fn test_joined() {
+        let lexicon = Lexicon::from_tsv("外国人\n人参\n参政権\n".as_bytes()).unwrap();
+        // 外国人 is taken first, so 人参 is not matched.
+        assert_eq!(lexicon.joined("外国人参政権"), [false, true, true, false, true, true]);
+        assert_eq!(lexicon.joined("人参"), [false, true]);
+        assert!(Lexicon::new().joined("人参").is_empty());
+    }
+
+    #[test]
+    fn test_strict() {
+        let segmenter = rwcp_segmenter();
+        let sentence = "おもてなしの心";
+        let lexicon = Lexicon::from_tsv("おもてなし\n".as_bytes()).unwrap();
+        // Without a bonus only the strict mode keeps the word whole.
+        let lattice = LatticeSegmenter::new(lexicon).with_bonus(0.0);
+        assert_ne!(surfaces(&lattice.segment(&segmenter, sentence))[0], "おもてなし");
+        // Boundaries around the word are still left to the classifier.
+        let words = lattice.with_strict(true).segment(&segmenter, sentence);
+        assert_eq!(surfaces(&words), ["おもてなしの", "心"]);
+    }
+
+    #[test]
+    fn test_grouping_rules() {
+        let mut segmenter = rwcp_segmenter();
+        segmenter.set_grouping_rules(GroupingRules {
+            digits: true,
+            ..GroupingRules::default()
+        });
+        // The lexicon word would end between two digits.
+        let lexicon = Lexicon::from_tsv("20\n".as_bytes()).unwrap();
+        let lattice = LatticeSegmenter::new(lexicon).with_bonus(10.0);
+        let words = lattice.segment(&segmenter, "2024年");
+        assert_eq!(surfaces(&words), segmenter.segment("2024年"));
+        assert!(surfaces(&words)[0].starts_with("2024"));
+    }
+}
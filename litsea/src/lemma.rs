@@ -0,0 +1,259 @@
+//! Lemma lookup for segmented tokens.
+//!
+//! A [`LemmaDictionary`] is a user-provided lexicon of `surface<TAB>lemma<TAB>tags` entries,
+//! where `tags` is an optional comma-separated list (e.g. a part of speech). Tokens found in
+//! the lexicon get its lemma and tags. Inflected forms that are not listed are matched by
+//! their longest prefix that is the stem of an entry ending in hiragana, as long as the
+//! rest of the token is hiragana too: with `食べる` in the lexicon, `食べ` and `食べた` both
+//! get the lemma `食べる`.
+//!
+//! ```
+//! use litsea::lemma::LemmaDictionary;
+//!
+//! let lexicon = LemmaDictionary::from_tsv("食べる\t食べる\t動詞\n".as_bytes()).unwrap();
+//! let entry = lexicon.lookup("食べた").unwrap();
+//! assert_eq!(entry.lemma, "食べる");
+//! assert_eq!(entry.tags, vec!["動詞"]);
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::LitseaError;
+use crate::token::{Token, TokenFilter};
+
+/// The lemma and tags of a lexicon entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LemmaEntry {
+    /// The normalized form of the word.
+    pub lemma: String,
+    /// The tags of the word, such as its part of speech.
+    pub tags: Vec<String>,
+}
+
+/// A lexicon mapping surface forms to lemmas and tags.
+#[derive(Debug, Clone, Default)]
+pub struct LemmaDictionary {
+    entries: HashMap<String, LemmaEntry>,
+    /// Entries ending in hiragana, keyed by their surface without the final character.
+    stems: HashMap<String, String>,
+}
+
+impl LemmaDictionary {
+    /// Creates an empty lexicon.
+    ///
+    /// # Returns
+    /// A new, empty [`LemmaDictionary`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a lexicon from TSV lines of `surface<TAB>lemma<TAB>tags`.
+    /// Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `reader` - The TSV content.
+    ///
+    /// # Returns
+    /// The lexicon. Later entries for the same surface replace earlier ones.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if reading fails, or [`LitseaError::Parse`] if a line
+    /// has no lemma.
+    pub fn from_tsv<R: BufRead>(reader: R) -> crate::error::Result<Self> {
+        let mut dictionary = Self::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let surface = columns.next().unwrap_or_default();
+            let lemma = columns.next().unwrap_or_default();
+            if surface.is_empty() || lemma.is_empty() {
+                return Err(LitseaError::Parse {
+                    line: i + 1,
+                    reason: format!("Invalid lexicon entry: '{}'", line),
+                });
+            }
+            let tags = columns
+                .next()
+                .map(|tags| tags.split(',').filter(|t| !t.is_empty()).map(str::to_string).collect())
+                .unwrap_or_default();
+            dictionary.insert(surface, lemma, tags);
+        }
+        Ok(dictionary)
+    }
+
+    /// Loads a lexicon from a TSV file. See [`from_tsv`](Self::from_tsv).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the TSV file.
+    ///
+    /// # Returns
+    /// The loaded lexicon.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        Self::from_tsv(BufReader::new(File::open(path)?))
+    }
+
+    /// Adds an entry.
+    ///
+    /// # Arguments
+    /// * `surface` - The written form of the word.
+    /// * `lemma` - Its normalized form.
+    /// * `tags` - Its tags.
+    pub fn insert(&mut self, surface: &str, lemma: &str, tags: Vec<String>) {
+        if let Some((pos, last)) = surface.char_indices().next_back() {
+            if pos > 0 && is_hiragana(last) {
+                self.stems.insert(surface[..pos].to_string(), surface.to_string());
+            }
+        }
+        let entry = LemmaEntry {
+            lemma: lemma.to_string(),
+            tags,
+        };
+        self.entries.insert(surface.to_string(), entry);
+    }
+
+    /// Returns the number of entries in the lexicon.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the lexicon has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Looks up a word, falling back to the longest matching stem for inflected forms.
+    ///
+    /// # Arguments
+    /// * `surface` - The word to look up.
+    ///
+    /// # Returns
+    /// The matching entry, or `None` if the word is neither listed nor an inflected form
+    /// of a listed word.
+    #[must_use]
+    pub fn lookup(&self, surface: &str) -> Option<&LemmaEntry> {
+        if let Some(entry) = self.entries.get(surface) {
+            return Some(entry);
+        }
+        // The stem may only be followed by hiragana (the inflected ending), so it ends
+        // within the trailing run of hiragana. Longest stem first.
+        let ending = surface
+            .char_indices()
+            .rev()
+            .take_while(|&(_, ch)| is_hiragana(ch))
+            .last()
+            .map_or(surface.len(), |(pos, _)| pos);
+        (ending..=surface.len())
+            .rev()
+            .filter(|&end| end > 0 && surface.is_char_boundary(end))
+            .find_map(|end| self.stems.get(&surface[..end]).and_then(|full| self.entries.get(full)))
+    }
+
+    /// Attaches the lemma and tags of every token found in the lexicon.
+    /// Tokens that are not found are left unchanged.
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to annotate.
+    pub fn annotate(&self, tokens: &mut [Token]) {
+        for token in tokens {
+            if let Some(entry) = self.lookup(&token.surface) {
+                token.lemma = Some(entry.lemma.clone());
+                token.tags = entry.tags.clone();
+            }
+        }
+    }
+}
+
+impl TokenFilter for LemmaDictionary {
+    fn apply(&self, tokens: &mut Vec<Token>) {
+        self.annotate(tokens);
+    }
+}
+
+/// Returns `true` for hiragana, which carry the inflection of Japanese words.
+fn is_hiragana(ch: char) -> bool {
+    ('\u{3041}'..='\u{309F}').contains(&ch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon() -> LemmaDictionary {
+        let tsv = "# surface\tlemma\ttags\n食べる\t食べる\t動詞,一段\n書く\t書く\t動詞\n\
+                   東京\t東京\t名詞,固有名詞\nおいしい\tおいしい\t形容詞\nです\tです\n";
+        LemmaDictionary::from_tsv(tsv.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_from_tsv() {
+        let lexicon = lexicon();
+        assert_eq!(lexicon.len(), 5);
+        assert_eq!(lexicon.lookup("です").unwrap().tags, Vec::<String>::new());
+        assert!(LemmaDictionary::new().is_empty());
+    }
+
+    #[test]
+    fn test_from_tsv_invalid() {
+        let err = LemmaDictionary::from_tsv("食べる\t食べる\n書く\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 2, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_lookup_exact() {
+        let lexicon = lexicon();
+        let entry = lexicon.lookup("東京").unwrap();
+        assert_eq!(entry.lemma, "東京");
+        assert_eq!(entry.tags, vec!["名詞", "固有名詞"]);
+    }
+
+    #[test]
+    fn test_lookup_inflected() {
+        let lexicon = lexicon();
+        assert_eq!(lexicon.lookup("食べ").unwrap().lemma, "食べる");
+        assert_eq!(lexicon.lookup("食べた").unwrap().lemma, "食べる");
+        assert_eq!(lexicon.lookup("書いて").unwrap().lemma, "書く");
+        assert_eq!(lexicon.lookup("おいしかった").unwrap().lemma, "おいしい");
+    }
+
+    #[test]
+    fn test_lookup_no_match() {
+        let lexicon = lexicon();
+        // Only entries ending in hiragana inflect, and endings must be hiragana.
+        assert!(lexicon.lookup("東大").is_none());
+        assert!(lexicon.lookup("東京都").is_none());
+        assert!(lexicon.lookup("書道").is_none());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut tokens = vec![
+            Token {
+                surface: "食べた".to_string(),
+                ..Token::default()
+            },
+            Token {
+                surface: "パン".to_string(),
+                ..Token::default()
+            },
+        ];
+        lexicon().annotate(&mut tokens);
+
+        assert_eq!(tokens[0].lemma.as_deref(), Some("食べる"));
+        assert_eq!(tokens[0].tags, vec!["動詞", "一段"]);
+        assert_eq!(tokens[1].lemma, None);
+        assert!(tokens[1].tags.is_empty());
+    }
+}
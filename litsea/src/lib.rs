@@ -7,13 +7,165 @@
 //! - Japanese
 //! - Chinese (Simplified and Traditional)
 //! - Korean
+//!
+//! # Cargo Features
+//! - `std` (default): everything that needs the standard library, such as loading
+//!   models from files and the model format converters. Without it the crate is
+//!   `no_std` and needs only `alloc`: [`language`], [`segmenter::Segmenter`] and
+//!   [`adaboost::AdaBoost`] can still segment text with a model loaded by
+//!   [`adaboost::AdaBoost::load_model_str`]. Features other than `normalization`,
+//!   `tracing` and `wasm` imply `std`.
+//! - `remote_model` (default): load models from `http://` and `https://` URLs.
+//! - `train` (default): training with [`adaboost::AdaBoost::train`], the `control`,
+//!   `extractor`, `progress`, `self_training`, `tensorboard`, `trainer` and `training` modules with
+//!   [`Pipeline`], the training functions of `cli`, and corpus methods of
+//!   [`segmenter::Segmenter`].
+//! - `regex` (default): custom character type patterns from regexes with
+//!   [`language::CharTypePatterns::new`]. The built-in patterns do not need it.
+//! - `normalization` (default): NFC and NFKC Unicode normalization in
+//!   [`normalize::Normalizer`]. Works without `std`.
+//! - `compression`: reading and writing gzip (`.gz`) and Zstandard (`.zst`) corpus,
+//!   features and model files, see the [`compression`] module.
+//! - `parquet`: Parquet output for feature extraction; implies `train`.
+//! - `config`: the serializable pipeline configuration of the `pipeline` module, in TOML
+//!   or JSON.
+//! - `signing`: Ed25519 signatures for model files.
+//! - `sqlite`: the SQLite-backed model store.
+//! - `tracing`: spans and events for feature extraction, training, model loading and
+//!   segmentation, for any `tracing` subscriber. Works without `std`.
+//! - `tokio`: the [`async_segmenter`] module, segmenting on Tokio's blocking thread pool.
+//! - `jni`, `lindera`: JVM bindings and the Lindera adapter.
+//! - `python`: Python bindings with PyO3 in the `python` module; implies `train`.
+//! - `wasm`: `wasm-bindgen` bindings for JavaScript in the [`wasm`] module. Works
+//!   without `std`.
+//!
+//! Inference-only builds, e.g. for WASM or serverless binaries, can use
+//! `default-features = false, features = ["std"]`, and embedded targets
+//! `default-features = false`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+/// Emits a `tracing` event when the `tracing` feature is enabled, and nothing otherwise.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+/// Enters a `tracing` span until the end of the enclosing block when the `tracing`
+/// feature is enabled, and does nothing otherwise. Must not be used in async functions,
+/// since the entered span would be held across `.await`.
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::span!(tracing::Level::$level, $($arg)+).entered();
+    };
+}
 
 pub mod adaboost;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "tokio")]
+pub mod async_segmenter;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod compound;
+#[cfg(feature = "std")]
+pub mod compression;
+#[cfg(feature = "train")]
+pub mod control;
+#[cfg(feature = "std")]
+pub mod corpus;
+pub mod delta;
+#[cfg(feature = "train")]
+pub mod ensemble;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod evaluation;
+#[cfg(feature = "train")]
 pub mod extractor;
+#[cfg(feature = "train")]
+pub mod feature_file;
+pub mod grouping;
+#[cfg(feature = "std")]
+pub mod inspect;
+#[cfg(feature = "mmap")]
+pub mod instance_file;
+pub mod integrity;
+#[cfg(feature = "jni")]
+pub mod jni;
+#[cfg(feature = "std")]
+pub mod kytea;
 pub mod language;
+#[cfg(feature = "std")]
+pub mod lattice;
+#[cfg(feature = "std")]
+pub mod lemma;
+#[cfg(feature = "lindera")]
+pub mod lindera;
+#[cfg(feature = "std")]
+pub mod model_card;
+pub mod model_info;
+#[cfg(feature = "sqlite")]
+pub mod model_store;
+pub mod normalize;
+#[cfg(feature = "std")]
+pub mod onnx;
+#[cfg(feature = "config")]
+pub mod pipeline;
+#[cfg(feature = "train")]
+pub mod progress;
+#[cfg(feature = "config")]
+pub mod project;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod quantize;
+#[cfg(feature = "std")]
+pub mod reading;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod registry;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "std")]
+pub mod script;
 pub mod segmenter;
+#[cfg(feature = "train")]
+pub mod self_training;
+#[cfg(feature = "train")]
+mod sparse;
+#[cfg(feature = "std")]
+pub mod stopwords;
+#[cfg(feature = "std")]
+pub mod tagger;
+#[cfg(feature = "train")]
+pub mod tensorboard;
+#[cfg(all(test, feature = "std"))]
+pub(crate) mod test_util;
+#[cfg(feature = "std")]
+pub mod tinysegmenter;
+pub mod token;
+#[cfg(feature = "train")]
 pub mod trainer;
+#[cfg(feature = "train")]
+pub mod training;
+#[cfg(feature = "std")]
+pub mod unigram;
 pub mod util;
+#[cfg(feature = "std")]
+pub mod vaporetto;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "train")]
+pub use training::{Pipeline, PipelineBuilder};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -0,0 +1,181 @@
+//! Adapter exposing a Litsea segmenter through Lindera's tokenizer interface, enabled by
+//! the `lindera` feature.
+//!
+//! [`LinderaSegmenter`] produces the same [`Token`] values as Lindera's own
+//! [`lindera::segmenter::Segmenter`], so token filters and other code built on Lindera
+//! keep working. Word boundaries come from the Litsea model; the Lindera dictionary is
+//! only used to attach details (such as part of speech) to words it knows. Words that
+//! are not in the dictionary are unknown words whose details start with `UNK`.
+
+use std::borrow::Cow;
+
+use lindera::LinderaResult;
+use lindera::dictionary::Dictionary;
+use lindera::token::Token;
+use lindera_dictionary::viterbi::{LexType, WordId};
+
+use crate::segmenter::Segmenter;
+
+/// A Lindera-compatible segmenter backed by a Litsea model.
+pub struct LinderaSegmenter {
+    segmenter: Segmenter,
+    dictionary: Dictionary,
+    keep_whitespace: bool,
+}
+
+impl LinderaSegmenter {
+    /// Creates a new adapter.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The Litsea segmenter that decides word boundaries.
+    /// * `dictionary` - The Lindera dictionary used to look up word details.
+    ///
+    /// # Returns
+    /// A new [`LinderaSegmenter`] that drops whitespace tokens, like Lindera does by default.
+    #[must_use]
+    pub fn new(segmenter: Segmenter, dictionary: Dictionary) -> Self {
+        LinderaSegmenter {
+            segmenter,
+            dictionary,
+            keep_whitespace: false,
+        }
+    }
+
+    /// Sets whether whitespace-only tokens are kept in the output.
+    ///
+    /// # Arguments
+    /// * `keep_whitespace` - If true, whitespace tokens are returned as unknown words.
+    #[must_use]
+    pub fn keep_whitespace(mut self, keep_whitespace: bool) -> Self {
+        self.keep_whitespace = keep_whitespace;
+        self
+    }
+
+    /// Looks up the word ID of a surface form in the system dictionary.
+    ///
+    /// If the dictionary has several entries for the surface, the one with the lowest
+    /// word cost is used, which is the entry Lindera itself prefers in isolation.
+    fn word_id(&self, surface: &str) -> WordId {
+        self.dictionary
+            .prefix_dictionary
+            .find_surface_iter(surface)
+            .min_by_key(|entry| entry.word_cost())
+            .map(|entry| entry.word_id())
+            .unwrap_or_else(|| WordId::new(LexType::Unknown, u32::MAX))
+    }
+
+    /// Segments text into Lindera tokens.
+    ///
+    /// # Arguments
+    /// * `text` - The text to segment.
+    ///
+    /// # Returns
+    /// The tokens with byte offsets into `text` and positions counted over the
+    /// returned tokens.
+    ///
+    /// # Errors
+    /// This method does not fail; it returns a [`LinderaResult`] to match
+    /// [`lindera::segmenter::Segmenter::segment`].
+    pub fn segment<'a>(&'a self, text: Cow<'a, str>) -> LinderaResult<Vec<Token<'a>>> {
+        let mut tokens = Vec::new();
+        let mut byte_start = 0;
+        for word in self.segmenter.segment(&text) {
+            let byte_end = byte_start + word.len();
+            if self.keep_whitespace || !word.chars().all(char::is_whitespace) {
+                let word_id = self.word_id(&word);
+                tokens.push(Token::new(
+                    Cow::Owned(word),
+                    byte_start,
+                    byte_end,
+                    tokens.len(),
+                    word_id,
+                    &self.dictionary,
+                    None,
+                ));
+            }
+            byte_start = byte_end;
+        }
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use lindera::dictionary::load_fs_dictionary;
+    use lindera_dictionary::builder::DictionaryBuilder;
+    use lindera_dictionary::dictionary::metadata::Metadata;
+
+    use super::*;
+    use crate::adaboost::AdaBoost;
+    use crate::language::Language;
+
+    /// Builds a tiny Lindera dictionary that knows "テスト" but not "これ".
+    fn build_dictionary() -> tempfile::TempDir {
+        let source = tempfile::tempdir().unwrap();
+        fs::write(source.path().join("char.def"), "DEFAULT 0 1 0\n").unwrap();
+        fs::write(source.path().join("unk.def"), "DEFAULT,0,0,10000,名詞,一般,*,*,*,*,*,*,*\n")
+            .unwrap();
+        fs::write(
+            source.path().join("lex.csv"),
+            "テスト,0,0,0,名詞,サ変接続,*,*,*,*,テスト,テスト,テスト\n",
+        )
+        .unwrap();
+        fs::write(source.path().join("matrix.def"), "1 1\n0 0 0\n").unwrap();
+
+        let output = tempfile::tempdir().unwrap();
+        DictionaryBuilder::new(Metadata::default())
+            .build_dictionary(source.path(), output.path())
+            .unwrap();
+        output
+    }
+
+    fn test_segmenter() -> Segmenter {
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model");
+        let mut learner = AdaBoost::new(0.01, 100);
+        tokio_test::block_on(learner.load_model(model)).unwrap();
+        Segmenter::new(Language::Japanese, Some(learner))
+    }
+
+    #[test]
+    fn test_segment() {
+        let dir = build_dictionary();
+        let dictionary = load_fs_dictionary(dir.path()).unwrap();
+        let adapter = LinderaSegmenter::new(test_segmenter(), dictionary);
+
+        let text = "これはテストです。";
+        let mut tokens = adapter.segment(Cow::Borrowed(text)).unwrap();
+        let surfaces: Vec<&str> = tokens.iter().map(|t| t.surface.as_ref()).collect();
+        assert_eq!(surfaces, vec!["これ", "は", "テスト", "です", "。"]);
+        for (i, token) in tokens.iter().enumerate() {
+            assert_eq!(&text[token.byte_start..token.byte_end], token.surface);
+            assert_eq!(token.position, i);
+        }
+
+        assert!(tokens[0].word_id.is_unknown());
+        assert_eq!(tokens[0].get_detail(0), Some("UNK"));
+        assert!(!tokens[2].word_id.is_unknown());
+        assert_eq!(tokens[2].get_detail(1), Some("サ変接続"));
+    }
+
+    #[test]
+    fn test_keep_whitespace() {
+        let dir = build_dictionary();
+        let text = "テスト です";
+
+        let adapter =
+            LinderaSegmenter::new(test_segmenter(), load_fs_dictionary(dir.path()).unwrap());
+        let tokens = adapter.segment(Cow::Borrowed(text)).unwrap();
+        assert!(tokens.iter().all(|t| !t.surface.trim().is_empty()));
+        let last = tokens.last().unwrap();
+        assert_eq!(&text[last.byte_start..last.byte_end], last.surface);
+
+        let adapter =
+            LinderaSegmenter::new(test_segmenter(), load_fs_dictionary(dir.path()).unwrap())
+                .keep_whitespace(true);
+        let tokens = adapter.segment(Cow::Borrowed(text)).unwrap();
+        let joined: String = tokens.iter().map(|t| t.surface.as_ref()).collect();
+        assert_eq!(joined, text);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use crate::adaboost::{AdaBoost, Metrics};
+pub use crate::bench::{Benchmark, benchmark};
+use crate::evaluation;
+use crate::language::Language;
+use crate::segmenter::Segmenter;
+
+/// Metadata describing a model, derived from the model weights themselves.
+#[derive(Debug, Clone)]
+pub struct ModelSummary {
+    /// Number of features with a non-zero weight.
+    pub num_features: usize,
+    /// Bias term of the model.
+    pub bias: f64,
+    /// Size in bytes of the model when saved in Litsea's model format.
+    pub size_bytes: usize,
+    /// Number of features per template (e.g. `UW4`, `BC2`).
+    pub templates: BTreeMap<String, usize>,
+}
+
+impl ModelSummary {
+    /// Summarizes the weights of a model.
+    ///
+    /// # Arguments
+    /// * `learner` - The model to summarize.
+    ///
+    /// # Returns
+    /// A new [`ModelSummary`].
+    #[must_use]
+    pub fn new(learner: &AdaBoost) -> Self {
+        let bias = learner.get_bias();
+        let mut templates: BTreeMap<String, usize> = BTreeMap::new();
+        let mut num_features = 0;
+        // Account for the bias line written at the end of the model file.
+        let mut size_bytes = format!("{}\n", bias).len();
+
+        for (h, w) in learner.weights() {
+            let template = h.split_once(':').map_or(h, |(t, _)| t);
+            *templates.entry(template.to_string()).or_insert(0) += 1;
+            num_features += 1;
+            size_bytes += format!("{}\t{}\n", h, w).len();
+        }
+
+        ModelSummary {
+            num_features,
+            bias,
+            size_bytes,
+            templates,
+        }
+    }
+}
+
+/// Evaluates a segmenter against a gold corpus.
+///
+/// Each sentence of the corpus is written with words separated by spaces, as in the
+/// corpus used for training. The spaces are removed, the text is segmented, and the
+/// predicted word boundaries are compared with the gold ones at every character position.
+/// See [`evaluation::evaluate`] for word-level metrics as well.
+///
+/// # Arguments
+/// * `segmenter` - The segmenter to evaluate.
+/// * `sentences` - The gold sentences, with words separated by spaces.
+///
+/// # Returns
+/// The boundary classification [`Metrics`] over all sentences.
+pub fn evaluate<'a, I>(segmenter: &Segmenter, sentences: I) -> Metrics
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    evaluation::evaluate(segmenter, sentences).boundaries
+}
+
+/// A Markdown model card combining model metadata, evaluation results, and benchmarks.
+#[derive(Debug, Clone)]
+pub struct ModelCard {
+    /// Name of the model, usually its file name.
+    pub name: String,
+    /// Language the model is used with.
+    pub language: Language,
+    /// Metadata derived from the model weights.
+    pub summary: ModelSummary,
+    /// Evaluation results against a gold corpus, if one was provided.
+    pub evaluation: Option<Metrics>,
+    /// Speed measurements, if a benchmark was run.
+    pub benchmark: Option<Benchmark>,
+}
+
+impl ModelCard {
+    /// Writes the model card as Markdown.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination of the Markdown output.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_markdown<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "# Model Card: {}", self.name)?;
+        writeln!(writer)?;
+        writeln!(writer, "## Model Details")?;
+        writeln!(writer)?;
+        writeln!(writer, "| Property | Value |")?;
+        writeln!(writer, "|----------|-------|")?;
+        writeln!(writer, "| Language | {} |", self.language)?;
+        writeln!(writer, "| Algorithm | AdaBoost binary classification |")?;
+        writeln!(writer, "| Features | {} |", self.summary.num_features)?;
+        writeln!(writer, "| Bias | {:.6} |", self.summary.bias)?;
+        writeln!(writer, "| Size | {} bytes |", self.summary.size_bytes)?;
+        writeln!(writer, "| Litsea version | {} |", crate::version())?;
+        writeln!(writer)?;
+
+        writeln!(writer, "### Features per Template")?;
+        writeln!(writer)?;
+        writeln!(writer, "| Template | Features |")?;
+        writeln!(writer, "|----------|----------|")?;
+        for (template, count) in &self.summary.templates {
+            writeln!(writer, "| {} | {} |", template, count)?;
+        }
+        writeln!(writer)?;
+
+        writeln!(writer, "## Evaluation")?;
+        writeln!(writer)?;
+        match &self.evaluation {
+            Some(m) => {
+                let f1 = if m.precision + m.recall > 0.0 {
+                    2.0 * m.precision * m.recall / (m.precision + m.recall)
+                } else {
+                    0.0
+                };
+                writeln!(writer, "Word boundary classification on the gold corpus.")?;
+                writeln!(writer)?;
+                writeln!(writer, "| Metric | Value |")?;
+                writeln!(writer, "|--------|-------|")?;
+                writeln!(writer, "| Accuracy | {:.2}% |", m.accuracy)?;
+                writeln!(writer, "| Precision | {:.2}% |", m.precision)?;
+                writeln!(writer, "| Recall | {:.2}% |", m.recall)?;
+                writeln!(writer, "| F1 | {:.2}% |", f1)?;
+                writeln!(writer, "| Instances | {} |", m.num_instances)?;
+                writeln!(writer, "| True Positives | {} |", m.true_positives)?;
+                writeln!(writer, "| False Positives | {} |", m.false_positives)?;
+                writeln!(writer, "| False Negatives | {} |", m.false_negatives)?;
+                writeln!(writer, "| True Negatives | {} |", m.true_negatives)?;
+            }
+            None => writeln!(writer, "No gold corpus was provided.")?,
+        }
+        writeln!(writer)?;
+
+        writeln!(writer, "## Performance")?;
+        writeln!(writer)?;
+        match &self.benchmark {
+            Some(b) => {
+                writeln!(writer, "| Metric | Value |")?;
+                writeln!(writer, "|--------|-------|")?;
+                writeln!(writer, "| Sentences | {} |", b.num_sentences)?;
+                writeln!(writer, "| Characters | {} |", b.num_chars)?;
+                writeln!(writer, "| Iterations | {} |", b.iterations)?;
+                writeln!(writer, "| Mean latency | {:.2} µs/sentence |", b.mean_latency_micros())?;
+                writeln!(writer, "| Throughput | {:.0} chars/s |", b.chars_per_second())?;
+            }
+            None => writeln!(writer, "No benchmark was run.")?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::ha_segmenter;
+
+    #[test]
+    fn test_model_summary() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.set_weights(
+            vec![
+                ("UW4:は".to_string(), 0.5),
+                ("UW4:が".to_string(), 0.25),
+                ("BC2:II".to_string(), -1.0),
+            ],
+            -0.5,
+        );
+        let summary = ModelSummary::new(&learner);
+
+        assert_eq!(summary.num_features, 3);
+        assert!((summary.bias + 0.5).abs() < 1e-9);
+        assert_eq!(summary.templates["UW4"], 2);
+        assert_eq!(summary.templates["BC2"], 1);
+        assert_eq!(summary.size_bytes, "BC2:II\t-1\nUW4:が\t0.25\nUW4:は\t0.5\n-0.5\n".len());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // The model splits before and after "は" only.
+        let segmenter = ha_segmenter();
+        let metrics = evaluate(&segmenter, ["これ は ペン", "これ は ペン です"]);
+
+        // 4 + 6 scored positions; "です" is a missed boundary.
+        assert_eq!(metrics.num_instances, 10);
+        assert_eq!(metrics.true_positives, 4);
+        assert_eq!(metrics.false_positives, 0);
+        assert_eq!(metrics.false_negatives, 1);
+        assert_eq!(metrics.true_negatives, 5);
+        assert!((metrics.precision - 100.0).abs() < 1e-9);
+        assert!((metrics.recall - 80.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_benchmark() {
+        let segmenter = ha_segmenter();
+        let sentences = vec!["これはペン".to_string()];
+        let bench = benchmark(&segmenter, &sentences, 3);
+
+        assert_eq!(bench.num_sentences, 1);
+        assert_eq!(bench.num_chars, 5);
+        assert_eq!(bench.iterations, 3);
+        assert!(bench.mean_latency_micros() >= 0.0);
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let segmenter = ha_segmenter();
+        let card = ModelCard {
+            name: "test.model".to_string(),
+            language: Language::Japanese,
+            summary: ModelSummary::new(segmenter.learner()),
+            evaluation: Some(evaluate(&segmenter, ["これ は ペン"])),
+            benchmark: None,
+        };
+        let mut out = Vec::new();
+        card.write_markdown(&mut out).unwrap();
+        let md = String::from_utf8(out).unwrap();
+
+        assert!(md.starts_with("# Model Card: test.model\n"));
+        assert!(md.contains("| Language | japanese |"));
+        assert!(md.contains("| Features | 2 |"));
+        assert!(md.contains("| UW3 | 1 |"));
+        assert!(md.contains("| Precision | 100.00% |"));
+        assert!(md.contains("No benchmark was run."));
+    }
+}
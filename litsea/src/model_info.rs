@@ -0,0 +1,280 @@
+//! Provenance of a model, kept in the metadata entries of its file.
+//!
+//! [`AdaBoost::save_model`] writes the version of the model format with every model, and
+//! loading rejects a model of a newer format. Training records how the model was made:
+//! [`AdaBoost::train`] the number of boosting iterations and the date, and
+//! [`Trainer`](crate::trainer::Trainer) the name of the features file. `litsea train
+//! --config` also records the character type scheme and the feature templates, which
+//! [`Segmenter::check_model`](crate::segmenter::Segmenter::check_model) compares with
+//! its own, since a model segments well only with the character types and templates of
+//! its features:
+//!
+//! ```text
+//! #litsea-model sha256=<hex>
+//! #format 1
+//! #char_types japanese-2
+//! #corpus wiki.features
+//! #date 2026-10-16
+//! #iterations 10000
+//! #templates litsea-1
+//! ```
+//!
+//! Models saved before these entries existed have none of them and are not checked.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::language::{Language, LatinClasses};
+use crate::segmenter::FeatureTemplates;
+
+/// The newest version of the model format, read and written by this version of Litsea.
+/// Version 2 added i8 weights, see the [`quantize`](crate::quantize) module; other
+/// models are still written as version 1.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Metadata key of the version of the model format.
+pub const FORMAT_KEY: &str = "format";
+
+/// Metadata key of the character type scheme of the features.
+pub const CHAR_TYPES_KEY: &str = "char_types";
+
+/// Metadata key of the feature templates.
+pub const TEMPLATES_KEY: &str = "templates";
+
+/// Metadata key of the name of the training data.
+pub const CORPUS_KEY: &str = "corpus";
+
+/// Metadata key of the date of the last training, as `YYYY-MM-DD` in UTC.
+pub const DATE_KEY: &str = "date";
+
+/// Metadata key of the total number of boosting iterations.
+pub const ITERATIONS_KEY: &str = "iterations";
+
+/// Metadata key of the quantization of the weights, with the scale of i8 weights.
+pub const QUANTIZATION_KEY: &str = "quantization";
+
+/// Metadata key of the number of bootstrap models averaged into the model, see the
+/// [`ensemble`](crate::ensemble) module.
+pub const ENSEMBLE_KEY: &str = "ensemble";
+
+/// The version of the character types of the built-in languages, raised whenever a type
+/// covers different characters. Version 2 added the CJK extension blocks and the
+/// Katakana Phonetic Extensions.
+pub const CHAR_TYPES_VERSION: u32 = 2;
+
+/// The version of the feature templates, raised whenever a template changes.
+pub const TEMPLATES_VERSION: u32 = 1;
+
+/// The provenance entries of a model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// The version of the model format, `None` for models saved before it was recorded.
+    pub format: Option<u32>,
+    /// The character type scheme, see [`char_type_scheme`].
+    pub char_types: Option<String>,
+    /// The feature templates, see [`templates_id`].
+    pub templates: Option<String>,
+    /// The name of the training data.
+    pub corpus: Option<String>,
+    /// The date of the last training, as `YYYY-MM-DD` in UTC.
+    pub date: Option<String>,
+    /// The total number of boosting iterations, including resumed training.
+    pub iterations: Option<usize>,
+    /// The number of bootstrap models averaged into the model, `None` for a single model.
+    pub ensemble: Option<usize>,
+}
+
+impl ModelInfo {
+    /// Reads the provenance entries of a model.
+    ///
+    /// # Arguments
+    /// * `learner` - The model.
+    ///
+    /// # Returns
+    /// The entries the model has.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::ModelFormat`] if the format version, the number of
+    /// iterations or the number of ensemble models is not a number.
+    pub fn from_model(learner: &AdaBoost) -> Result<Self, LitseaError> {
+        let entry = |key: &str| learner.metadata(key).map(str::to_string);
+        Ok(ModelInfo {
+            format: parse_number(learner.metadata(FORMAT_KEY), FORMAT_KEY)?,
+            char_types: entry(CHAR_TYPES_KEY),
+            templates: entry(TEMPLATES_KEY),
+            corpus: entry(CORPUS_KEY),
+            date: entry(DATE_KEY),
+            iterations: parse_number(learner.metadata(ITERATIONS_KEY), ITERATIONS_KEY)?,
+            ensemble: parse_number(learner.metadata(ENSEMBLE_KEY), ENSEMBLE_KEY)?,
+        })
+    }
+}
+
+/// Checks the entries of a model being loaded.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the model has a newer format version than
+/// [`FORMAT_VERSION`], or an entry that must be a number is not.
+pub(crate) fn validate(format: Option<&str>, iterations: Option<&str>) -> Result<(), LitseaError> {
+    if let Some(version) = parse_number::<u32>(format, FORMAT_KEY)? {
+        if version > FORMAT_VERSION {
+            return Err(LitseaError::ModelFormat(format!(
+                "The model has format version {}, but this version of Litsea reads up to {}",
+                version, FORMAT_VERSION
+            )));
+        }
+    }
+    parse_number::<usize>(iterations, ITERATIONS_KEY)?;
+    Ok(())
+}
+
+/// Parses a numeric metadata entry, if present.
+fn parse_number<T: core::str::FromStr>(
+    value: Option<&str>,
+    key: &str,
+) -> Result<Option<T>, LitseaError> {
+    value
+        .map(|value| {
+            value.parse().map_err(|_| {
+                LitseaError::ModelFormat(format!("Invalid '{}' entry: '{}'", key, value))
+            })
+        })
+        .transpose()
+}
+
+/// Returns the ID of the character types that features are extracted with, such as
+/// `japanese-2` or `korean-2-case`: the language, the version of its types and the Latin
+/// classes unless they are the single "A" type. The TinySegmenter templates have their
+/// own fixed types, `tinysegmenter-1`.
+///
+/// # Arguments
+/// * `language` - The language of the character types.
+/// * `templates` - The feature templates.
+/// * `latin_classes` - The Latin classes.
+///
+/// # Returns
+/// The scheme ID.
+#[must_use]
+pub fn char_type_scheme(
+    language: Language,
+    templates: FeatureTemplates,
+    latin_classes: LatinClasses,
+) -> String {
+    match (templates, latin_classes) {
+        (FeatureTemplates::TinySegmenter, _) => "tinysegmenter-1".to_string(),
+        (_, LatinClasses::Single) => format!("{}-{}", language, CHAR_TYPES_VERSION),
+        (_, classes) => format!("{}-{}-{}", language, CHAR_TYPES_VERSION, classes),
+    }
+}
+
+/// Returns the ID of feature templates with their version, such as `litsea-1`.
+///
+/// # Arguments
+/// * `templates` - The feature templates.
+///
+/// # Returns
+/// The templates ID.
+#[must_use]
+pub fn templates_id(templates: FeatureTemplates) -> String {
+    format!("{}-{}", templates, TEMPLATES_VERSION)
+}
+
+/// Returns the feature templates a model records, if it records templates of this
+/// version of Litsea, the inverse of [`templates_id`].
+///
+/// # Arguments
+/// * `learner` - The model.
+///
+/// # Returns
+/// The recorded templates, or `None` if the model records none or unknown ones.
+#[must_use]
+pub fn recorded_templates(learner: &AdaBoost) -> Option<FeatureTemplates> {
+    let (name, version) = learner.metadata(TEMPLATES_KEY)?.rsplit_once('-')?;
+    (version.parse() == Ok(TEMPLATES_VERSION)).then(|| name.parse().ok()).flatten()
+}
+
+/// Returns today's date in UTC as `YYYY-MM-DD`.
+#[cfg(feature = "train")]
+pub(crate) fn today() -> String {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date.
+#[cfg(feature = "train")]
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_type_scheme() {
+        assert_eq!(
+            char_type_scheme(Language::Japanese, FeatureTemplates::Litsea, LatinClasses::Single),
+            "japanese-2"
+        );
+        assert_eq!(
+            char_type_scheme(Language::Korean, FeatureTemplates::Litsea, LatinClasses::CaseWidth),
+            "korean-2-case-width"
+        );
+        assert_eq!(
+            char_type_scheme(
+                Language::Japanese,
+                FeatureTemplates::TinySegmenter,
+                LatinClasses::Case
+            ),
+            "tinysegmenter-1"
+        );
+        assert_eq!(templates_id(FeatureTemplates::TinySegmenter), "tinysegmenter-1");
+    }
+
+    #[test]
+    fn test_recorded_templates() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        assert_eq!(recorded_templates(&learner), None);
+        learner.set_metadata(TEMPLATES_KEY, "tinysegmenter-1").unwrap();
+        assert_eq!(recorded_templates(&learner), Some(FeatureTemplates::TinySegmenter));
+        learner.set_metadata(TEMPLATES_KEY, "litsea-1").unwrap();
+        assert_eq!(recorded_templates(&learner), Some(FeatureTemplates::Litsea));
+        learner.set_metadata(TEMPLATES_KEY, "litsea-9").unwrap();
+        assert_eq!(recorded_templates(&learner), None);
+        learner.set_metadata(TEMPLATES_KEY, "unknown-1").unwrap();
+        assert_eq!(recorded_templates(&learner), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(None, None).is_ok());
+        assert!(validate(Some("1"), Some("100")).is_ok());
+        assert!(validate(Some("2"), None).is_ok());
+        assert!(matches!(validate(Some("3"), None), Err(LitseaError::ModelFormat(_))));
+        assert!(matches!(validate(Some("x"), None), Err(LitseaError::ModelFormat(_))));
+        assert!(matches!(validate(None, Some("-1")), Err(LitseaError::ModelFormat(_))));
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(20_742), (2026, 10, 16));
+        assert_eq!(today().len(), 10);
+    }
+}
@@ -0,0 +1,288 @@
+//! SQLite-backed storage for multiple models, enabled by the `sqlite` feature.
+//!
+//! A store is a single SQLite file holding any number of named models. Adding a model
+//! under an existing name creates a new version, so earlier versions remain loadable
+//! until they are removed. Each version records the model's language, a free-form
+//! description and the time it was added, so a store can be deployed as one file and
+//! the right model picked by name at run time.
+
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::language::Language;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS models (
+    name TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    language TEXT NOT NULL,
+    description TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    num_features INTEGER NOT NULL,
+    model BLOB NOT NULL,
+    PRIMARY KEY (name, version)
+)";
+
+/// Wraps a SQLite error as an I/O failure.
+fn sqlite_error(e: rusqlite::Error) -> LitseaError {
+    LitseaError::Io(io::Error::other(e))
+}
+
+/// Metadata of one model version in a [`ModelStore`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelInfo {
+    /// Name of the model.
+    pub name: String,
+    /// Version of the model, starting at 1 for each name.
+    pub version: u32,
+    /// Language the model was trained for.
+    pub language: Language,
+    /// Free-form description given when the model was added.
+    pub description: String,
+    /// Time the model was added, in seconds since the Unix epoch.
+    pub created_at: u64,
+    /// Number of features with a non-zero weight.
+    pub num_features: usize,
+}
+
+/// A collection of named, versioned models in a single SQLite file.
+pub struct ModelStore {
+    conn: Connection,
+}
+
+impl ModelStore {
+    /// Opens a model store, creating the file if it does not exist.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SQLite file.
+    ///
+    /// # Returns
+    /// The opened [`ModelStore`].
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be opened or is not a model store.
+    pub fn open<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        let conn = Connection::open(path).map_err(sqlite_error)?;
+        conn.execute(SCHEMA, []).map_err(sqlite_error)?;
+        Ok(ModelStore { conn })
+    }
+
+    /// Adds a model as the next version of `name`.
+    ///
+    /// # Arguments
+    /// * `name` - The name to store the model under.
+    /// * `language` - The language the model was trained for.
+    /// * `description` - A free-form description of the model.
+    /// * `learner` - The model to store.
+    ///
+    /// # Returns
+    /// The version assigned to the model.
+    ///
+    /// # Errors
+    /// Returns an error if the model is empty or the store cannot be written.
+    pub fn put(
+        &mut self,
+        name: &str,
+        language: Language,
+        description: &str,
+        learner: &AdaBoost,
+    ) -> crate::error::Result<u32> {
+        let mut model = Vec::new();
+        learner.write_model(&mut model)?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+
+        let tx = self.conn.transaction().map_err(sqlite_error)?;
+        let version: u32 = tx
+            .query_row(
+                "SELECT COALESCE(MAX(version), 0) + 1 FROM models WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .map_err(sqlite_error)?;
+        tx.execute(
+            "INSERT INTO models (name, version, language, description, created_at, num_features, model)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                name,
+                version,
+                language.to_string(),
+                description,
+                created_at as i64,
+                learner.weights().count() as i64,
+                model
+            ],
+        )
+        .map_err(sqlite_error)?;
+        tx.commit().map_err(sqlite_error)?;
+        Ok(version)
+    }
+
+    /// Loads a model from the store.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the model.
+    /// * `version` - The version to load, or `None` for the latest version.
+    ///
+    /// # Returns
+    /// The language the model was trained for and the model itself.
+    ///
+    /// # Errors
+    /// Returns a [`LitseaError::Io`] error of kind [`io::ErrorKind::NotFound`] if there is
+    /// no such model, [`LitseaError::ModelFormat`] if the stored model or its language is
+    /// invalid, or another error if the store cannot be read.
+    pub fn load(
+        &self,
+        name: &str,
+        version: Option<u32>,
+    ) -> crate::error::Result<(Language, AdaBoost)> {
+        let row: Option<(String, Vec<u8>)> = self
+            .conn
+            .query_row(
+                "SELECT language, model FROM models
+                 WHERE name = ?1 AND (?2 IS NULL OR version = ?2)
+                 ORDER BY version DESC LIMIT 1",
+                params![name, version],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(sqlite_error)?;
+        let Some((language, model)) = row else {
+            let message = match version {
+                Some(version) => {
+                    format!("Model not found in store: {} (version {})", name, version)
+                }
+                None => format!("Model not found in store: {}", name),
+            };
+            return Err(LitseaError::Io(io::Error::new(io::ErrorKind::NotFound, message)));
+        };
+
+        let language: Language = language.parse().map_err(LitseaError::ModelFormat)?;
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_bytes(&model)?;
+        Ok((language, learner))
+    }
+
+    /// Lists the models in the store, ordered by name and version.
+    ///
+    /// # Errors
+    /// Returns an error if the store cannot be read.
+    pub fn list(&self) -> crate::error::Result<Vec<ModelInfo>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT name, version, language, description, created_at, num_features
+                 FROM models ORDER BY name, version",
+            )
+            .map_err(sqlite_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                let language: String = row.get(2)?;
+                Ok(ModelInfo {
+                    name: row.get(0)?,
+                    version: row.get(1)?,
+                    language: language.parse().unwrap_or_default(),
+                    description: row.get(3)?,
+                    created_at: row.get::<_, i64>(4)? as u64,
+                    num_features: row.get::<_, i64>(5)? as usize,
+                })
+            })
+            .map_err(sqlite_error)?;
+        rows.collect::<Result<_, _>>().map_err(sqlite_error)
+    }
+
+    /// Removes a model from the store.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the model.
+    /// * `version` - The version to remove, or `None` to remove all versions.
+    ///
+    /// # Returns
+    /// The number of removed versions.
+    ///
+    /// # Errors
+    /// Returns an error if the store cannot be written.
+    pub fn remove(&mut self, name: &str, version: Option<u32>) -> crate::error::Result<usize> {
+        self.conn
+            .execute(
+                "DELETE FROM models WHERE name = ?1 AND (?2 IS NULL OR version = ?2)",
+                params![name, version],
+            )
+            .map_err(sqlite_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_learner(weight: f64) -> AdaBoost {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.set_weights(vec![("UW4:は".to_string(), weight)], -0.5);
+        learner
+    }
+
+    #[test]
+    fn test_put_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.db");
+        let mut store = ModelStore::open(&path).unwrap();
+
+        assert_eq!(store.put("news", Language::Japanese, "first", &test_learner(1.0)).unwrap(), 1);
+        assert_eq!(store.put("news", Language::Japanese, "second", &test_learner(2.0)).unwrap(), 2);
+        assert_eq!(store.put("wiki", Language::Chinese, "", &test_learner(3.0)).unwrap(), 1);
+        drop(store);
+
+        // The store persists across connections.
+        let store = ModelStore::open(&path).unwrap();
+        let (language, learner) = store.load("news", None).unwrap();
+        assert_eq!(language, Language::Japanese);
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("UW4:は", 2.0)]);
+        assert!((learner.get_bias() + 0.5).abs() < 1e-9);
+
+        let (_, learner) = store.load("news", Some(1)).unwrap();
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("UW4:は", 1.0)]);
+
+        let (language, _) = store.load("wiki", None).unwrap();
+        assert_eq!(language, Language::Chinese);
+
+        for err in [store.load("news", Some(3)).err(), store.load("sports", None).err()] {
+            assert!(matches!(err, Some(LitseaError::Io(e)) if e.kind() == io::ErrorKind::NotFound));
+        }
+    }
+
+    #[test]
+    fn test_list_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ModelStore::open(dir.path().join("models.db")).unwrap();
+        store.put("news", Language::Japanese, "first", &test_learner(1.0)).unwrap();
+        store.put("news", Language::Japanese, "second", &test_learner(2.0)).unwrap();
+        store.put("blog", Language::Korean, "", &test_learner(3.0)).unwrap();
+
+        let models = store.list().unwrap();
+        let keys: Vec<(&str, u32)> = models.iter().map(|m| (m.name.as_str(), m.version)).collect();
+        assert_eq!(keys, vec![("blog", 1), ("news", 1), ("news", 2)]);
+        assert_eq!(models[0].language, Language::Korean);
+        assert_eq!(models[2].description, "second");
+        assert_eq!(models[2].num_features, 1);
+
+        assert_eq!(store.remove("news", Some(2)).unwrap(), 1);
+        let (_, learner) = store.load("news", None).unwrap();
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("UW4:は", 1.0)]);
+
+        assert_eq!(store.remove("news", None).unwrap(), 1);
+        assert_eq!(store.remove("news", None).unwrap(), 0);
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_put_empty_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = ModelStore::open(dir.path().join("models.db")).unwrap();
+        assert!(store.put("empty", Language::Japanese, "", &AdaBoost::new(0.01, 100)).is_err());
+    }
+}
@@ -0,0 +1,262 @@
+//! Text normalization applied before segmentation.
+//!
+//! The pre-trained models were trained on text with full-width and half-width
+//! characters as they occur, so normalization is off by default. When it is turned on,
+//! the same settings must be used for feature extraction and segmentation, which is
+//! what the pipeline configuration of the `pipeline` module records: `litsea train
+//! --config` embeds it in the model, and `litsea segment` normalizes its input the same
+//! way. Offsets of the segmented words refer to the normalized text.
+//!
+//! ```
+//! use litsea::normalize::Normalizer;
+//!
+//! let normalizer = Normalizer {
+//!     fullwidth_ascii: true,
+//!     lowercase: true,
+//!     ..Normalizer::default()
+//! };
+//! assert_eq!(normalizer.normalize("ＬｉｔｓｅａはＲｕｓｔ製"), "litseaはrust製");
+//! ```
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+/// A Unicode normalization form.
+///
+/// NFC composes characters with their combining marks, e.g. `か` followed by U+3099 into
+/// `が`, so text typed on different systems compares equal. NFKC also folds
+/// compatibility characters into their usual forms, such as full-width ASCII, half-width
+/// katakana, circled digits and ligatures, which is the usual choice for search. The
+/// forms other than [`UnicodeForm::None`] need the `normalization` feature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "lowercase"))]
+#[non_exhaustive]
+pub enum UnicodeForm {
+    /// No Unicode normalization.
+    #[default]
+    None,
+    /// Canonical composition.
+    #[cfg(feature = "normalization")]
+    Nfc,
+    /// Compatibility composition.
+    #[cfg(feature = "normalization")]
+    Nfkc,
+}
+
+impl fmt::Display for UnicodeForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UnicodeForm::None => "none",
+            #[cfg(feature = "normalization")]
+            UnicodeForm::Nfc => "nfc",
+            #[cfg(feature = "normalization")]
+            UnicodeForm::Nfkc => "nfkc",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for UnicodeForm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(UnicodeForm::None),
+            #[cfg(feature = "normalization")]
+            "nfc" => Ok(UnicodeForm::Nfc),
+            #[cfg(feature = "normalization")]
+            "nfkc" => Ok(UnicodeForm::Nfkc),
+            #[cfg(not(feature = "normalization"))]
+            "nfc" | "nfkc" => {
+                Err(alloc::format!("Unicode normalization {} needs the `normalization` feature", s))
+            }
+            _ => Err(alloc::format!("Unsupported Unicode normalization form: {}", s)),
+        }
+    }
+}
+
+/// Settings for normalizing text before segmentation.
+///
+/// The Unicode normalization form is applied first, then the other settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(default, deny_unknown_fields))]
+pub struct Normalizer {
+    /// The Unicode normalization form.
+    pub unicode: UnicodeForm,
+    /// Convert full-width ASCII letters, digits and symbols (U+FF01..U+FF5E) and the
+    /// ideographic space to ASCII.
+    pub fullwidth_ascii: bool,
+    /// Convert half-width katakana and punctuation (U+FF61..U+FF9F) to their full-width
+    /// forms, combining the voiced sound marks with the preceding kana, as in `ｶﾞ` to `ガ`.
+    pub halfwidth_katakana: bool,
+    /// Convert Latin letters to lowercase.
+    pub lowercase: bool,
+}
+
+/// Full-width forms of the half-width katakana and punctuation U+FF61..=U+FF9F.
+const HALFWIDTH_KATAKANA: [char; 63] = [
+    '。', '「', '」', '、', '・', 'ヲ', 'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ャ', 'ュ', 'ョ', 'ッ', 'ー',
+    'ア', 'イ', 'ウ', 'エ', 'オ', 'カ', 'キ', 'ク', 'ケ', 'コ', 'サ', 'シ', 'ス', 'セ', 'ソ', 'タ',
+    'チ', 'ツ', 'テ', 'ト', 'ナ', 'ニ', 'ヌ', 'ネ', 'ノ', 'ハ', 'ヒ', 'フ', 'ヘ', 'ホ', 'マ', 'ミ',
+    'ム', 'メ', 'モ', 'ヤ', 'ユ', 'ヨ', 'ラ', 'リ', 'ル', 'レ', 'ロ', 'ワ', 'ン', '゛', '゜',
+];
+
+impl Normalizer {
+    /// Returns `true` if the normalizer leaves text unchanged.
+    #[must_use]
+    pub fn is_identity(&self) -> bool {
+        self.unicode == UnicodeForm::None
+            && !self.fullwidth_ascii
+            && !self.halfwidth_katakana
+            && !self.lowercase
+    }
+
+    /// Normalizes a text.
+    ///
+    /// # Arguments
+    /// * `text` - The text to normalize.
+    ///
+    /// # Returns
+    /// The normalized text, borrowed if nothing had to change.
+    #[must_use]
+    pub fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        let text = match self.unicode {
+            UnicodeForm::None => Cow::Borrowed(text),
+            #[cfg(feature = "normalization")]
+            UnicodeForm::Nfc => {
+                icu_normalizer::ComposingNormalizerBorrowed::new_nfc().normalize(text)
+            }
+            #[cfg(feature = "normalization")]
+            UnicodeForm::Nfkc => {
+                icu_normalizer::ComposingNormalizerBorrowed::new_nfkc().normalize(text)
+            }
+        };
+        if !text.chars().any(|ch| self.normalize_char(ch) != ch) {
+            return text;
+        }
+        let mut normalized = String::with_capacity(text.len());
+        for ch in text.chars() {
+            let step = match ch {
+                '\u{FF9E}' if self.halfwidth_katakana => 1,
+                '\u{FF9F}' if self.halfwidth_katakana => 2,
+                _ => 0,
+            };
+            if let Some(voiced) = normalized.chars().next_back().and_then(|last| voice(last, step))
+            {
+                normalized.pop();
+                normalized.push(voiced);
+            } else {
+                normalized.push(self.normalize_char(ch));
+            }
+        }
+        Cow::Owned(normalized)
+    }
+
+    /// Normalizes a single character.
+    fn normalize_char(&self, ch: char) -> char {
+        let ch = match ch {
+            '\u{FF01}'..='\u{FF5E}' if self.fullwidth_ascii => {
+                char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch)
+            }
+            '\u{3000}' if self.fullwidth_ascii => ' ',
+            '\u{FF61}'..='\u{FF9F}' if self.halfwidth_katakana => {
+                HALFWIDTH_KATAKANA[(ch as u32 - 0xFF61) as usize]
+            }
+            _ => ch,
+        };
+        if self.lowercase && ch.is_ascii_uppercase() {
+            ch.to_ascii_lowercase()
+        } else if self.lowercase && ('\u{FF21}'..='\u{FF3A}').contains(&ch) {
+            char::from_u32(ch as u32 + 0x20).unwrap_or(ch)
+        } else {
+            ch
+        }
+    }
+}
+
+/// Returns the katakana with the voiced sound mark (`step` 1) or the semi-voiced sound
+/// mark (`step` 2), or `None` if it has no such form or `step` is 0.
+fn voice(kana: char, step: u32) -> Option<char> {
+    match (kana, step) {
+        ('カ' | 'キ' | 'ク' | 'ケ' | 'コ' | 'サ' | 'シ' | 'ス' | 'セ' | 'ソ', 1)
+        | ('タ' | 'チ' | 'ツ' | 'テ' | 'ト', 1)
+        | ('ハ' | 'ヒ' | 'フ' | 'ヘ' | 'ホ', 1 | 2) => char::from_u32(kana as u32 + step),
+        ('ウ', 1) => Some('ヴ'),
+        ('ワ', 1) => Some('ヷ'),
+        ('ヲ', 1) => Some('ヺ'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identity() {
+        let normalizer = Normalizer::default();
+        assert!(normalizer.is_identity());
+        assert!(matches!(normalizer.normalize("ＡＢＣ"), Cow::Borrowed("ＡＢＣ")));
+    }
+
+    #[test]
+    fn test_fullwidth_ascii() {
+        let normalizer = Normalizer {
+            fullwidth_ascii: true,
+            ..Normalizer::default()
+        };
+        assert_eq!(normalizer.normalize("ＡＢＣ　１２３！～"), "ABC 123!~");
+        // Katakana and other full-width characters are kept.
+        assert!(matches!(normalizer.normalize("カタカナ。"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_lowercase() {
+        let normalizer = Normalizer {
+            lowercase: true,
+            ..Normalizer::default()
+        };
+        // Full-width letters keep their width but are lowercased.
+        assert_eq!(normalizer.normalize("Rust　ＲＵＳＴ"), "rust　ｒｕｓｔ");
+    }
+
+    #[test]
+    fn test_halfwidth_katakana() {
+        let normalizer = Normalizer {
+            halfwidth_katakana: true,
+            ..Normalizer::default()
+        };
+        assert!(!normalizer.is_identity());
+        assert_eq!(normalizer.normalize("ｶﾞｲﾄﾞﾌﾞｯｸ｡"), "ガイドブック。");
+        assert_eq!(normalizer.normalize("ﾊﾟﾝとｳﾞｧｲｵﾘﾝ"), "パンとヴァイオリン");
+        // Marks that do not combine are kept as full-width marks.
+        assert_eq!(normalizer.normalize("ﾞｱﾞ"), "゛ア゛");
+        assert!(matches!(normalizer.normalize("ガイド ABC"), Cow::Borrowed(_)));
+    }
+
+    #[cfg(feature = "normalization")]
+    #[test]
+    fn test_unicode_form() {
+        let nfc = Normalizer {
+            unicode: UnicodeForm::Nfc,
+            ..Normalizer::default()
+        };
+        assert_eq!(nfc.normalize("か\u{3099}ＡＢ"), "がＡＢ");
+        assert!(matches!(nfc.normalize("がＡＢ"), Cow::Borrowed(_)));
+
+        let nfkc = Normalizer {
+            unicode: UnicodeForm::Nfkc,
+            lowercase: true,
+            ..Normalizer::default()
+        };
+        assert_eq!(nfkc.normalize("ＡＢ①ｶﾞ㍻"), "ab1ガ平成");
+
+        assert_eq!("NFKC".parse::<UnicodeForm>(), Ok(UnicodeForm::Nfkc));
+        assert_eq!(UnicodeForm::Nfc.to_string(), "nfc");
+        assert!("nfd".parse::<UnicodeForm>().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Export of the linear boundary scorer as an ONNX graph.
+//!
+//! The exported graph takes a batch of feature index rows and scores each row as one
+//! boundary candidate:
+//!
+//! ```text
+//! features [batch, n] (int64)
+//!   -> Gather(weights)   [batch, n]
+//!   -> ReduceSum(axis 1) [batch]
+//!   -> Add(bias)         score [batch] (float)
+//!   -> GreaterOrEqual(0) boundary [batch] (bool)
+//! ```
+//!
+//! Feature indices refer to the feature table returned by [`feature_table`]. Features
+//! that are not in the table are dropped, and rows shorter than `n` are padded with
+//! [`padding_index`], whose weight is zero.
+
+use std::io::{self, Write};
+
+use crate::adaboost::AdaBoost;
+
+/// ONNX IR version written to the model; IR version 7 goes with opset 13.
+const IR_VERSION: u64 = 7;
+
+/// Version of the default operator set used by the graph.
+const OPSET_VERSION: u64 = 13;
+
+/// `TensorProto.DataType` values.
+const FLOAT: u64 = 1;
+const INT64: u64 = 7;
+const BOOL: u64 = 9;
+
+/// `AttributeProto.AttributeType.INT`.
+const ATTRIBUTE_INT: u64 = 2;
+
+/// A protobuf message being encoded.
+#[derive(Default)]
+struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    fn raw_varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.buf.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.raw_varint(u64::from(field) << 3 | u64::from(wire_type));
+    }
+
+    fn varint(&mut self, field: u32, v: u64) -> &mut Self {
+        self.key(field, 0);
+        self.raw_varint(v);
+        self
+    }
+
+    fn bytes(&mut self, field: u32, b: &[u8]) -> &mut Self {
+        self.key(field, 2);
+        self.raw_varint(b.len() as u64);
+        self.buf.extend_from_slice(b);
+        self
+    }
+
+    fn string(&mut self, field: u32, s: &str) -> &mut Self {
+        self.bytes(field, s.as_bytes())
+    }
+
+    fn message(&mut self, field: u32, m: &Message) -> &mut Self {
+        self.bytes(field, &m.buf)
+    }
+}
+
+/// Encodes a `TensorProto` initializer.
+fn tensor(name: &str, data_type: u64, dims: &[u64], raw_data: &[u8]) -> Message {
+    let mut m = Message::default();
+    for &d in dims {
+        m.varint(1, d);
+    }
+    m.varint(2, data_type).string(8, name).bytes(9, raw_data);
+    m
+}
+
+/// Encodes a `ValueInfoProto` for a graph input or output with symbolic dimensions.
+fn value_info(name: &str, elem_type: u64, dims: &[&str]) -> Message {
+    let mut shape = Message::default();
+    for dim in dims {
+        let mut d = Message::default();
+        d.string(2, dim);
+        shape.message(1, &d);
+    }
+    let mut tensor_type = Message::default();
+    tensor_type.varint(1, elem_type).message(2, &shape);
+    let mut type_proto = Message::default();
+    type_proto.message(1, &tensor_type);
+    let mut m = Message::default();
+    m.string(1, name).message(2, &type_proto);
+    m
+}
+
+/// Encodes a `NodeProto` in the default domain.
+fn node(op_type: &str, inputs: &[&str], outputs: &[&str], attributes: &[Message]) -> Message {
+    let mut m = Message::default();
+    for input in inputs {
+        m.string(1, input);
+    }
+    for output in outputs {
+        m.string(2, output);
+    }
+    m.string(3, op_type).string(4, op_type);
+    for attribute in attributes {
+        m.message(5, attribute);
+    }
+    m
+}
+
+/// Returns the feature table of a model: the feature at position `i` is looked up by
+/// index `i` in the exported graph.
+///
+/// # Arguments
+/// * `learner` - The model to export.
+///
+/// # Returns
+/// The names of all features with a non-zero weight, in index order.
+#[must_use]
+pub fn feature_table(learner: &AdaBoost) -> Vec<&str> {
+    learner.weights().map(|(feature, _)| feature).collect()
+}
+
+/// Returns the index used to pad feature rows, which has a weight of zero.
+///
+/// # Arguments
+/// * `learner` - The model to export.
+#[must_use]
+pub fn padding_index(learner: &AdaBoost) -> i64 {
+    learner.weights().count() as i64
+}
+
+/// Writes a model as an ONNX graph.
+///
+/// The graph has a single input `features` of shape `[batch, n]` and two outputs,
+/// `score` (the boundary score, including the bias) and `boundary` (whether the score
+/// is non-negative), both of shape `[batch]`. Weights are stored as 32-bit floats.
+///
+/// # Arguments
+/// * `learner` - The model to export.
+/// * `writer` - The destination of the ONNX model.
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn export_model<W: Write>(learner: &AdaBoost, mut writer: W) -> io::Result<()> {
+    let weights: Vec<u8> = learner
+        .weights()
+        .map(|(_, w)| w as f32)
+        .chain(std::iter::once(0.0))
+        .flat_map(f32::to_le_bytes)
+        .collect();
+    let num_weights = (weights.len() / 4) as u64;
+
+    let mut keepdims = Message::default();
+    keepdims.string(1, "keepdims").varint(3, 0).varint(20, ATTRIBUTE_INT);
+
+    let mut graph = Message::default();
+    graph
+        .message(1, &node("Gather", &["weights", "features"], &["gathered"], &[]))
+        .message(1, &node("ReduceSum", &["gathered", "axes"], &["sum"], &[keepdims]))
+        .message(1, &node("Add", &["sum", "bias"], &["score"], &[]))
+        .message(1, &node("GreaterOrEqual", &["score", "zero"], &["boundary"], &[]))
+        .string(2, "litsea")
+        .message(5, &tensor("weights", FLOAT, &[num_weights], &weights))
+        .message(5, &tensor("axes", INT64, &[1], &1i64.to_le_bytes()))
+        .message(5, &tensor("bias", FLOAT, &[], &(learner.get_bias() as f32).to_le_bytes()))
+        .message(5, &tensor("zero", FLOAT, &[], &0f32.to_le_bytes()))
+        .message(11, &value_info("features", INT64, &["batch", "n"]))
+        .message(12, &value_info("score", FLOAT, &["batch"]))
+        .message(12, &value_info("boundary", BOOL, &["batch"]));
+
+    let mut opset = Message::default();
+    opset.string(1, "").varint(2, OPSET_VERSION);
+
+    let mut model = Message::default();
+    model
+        .varint(1, IR_VERSION)
+        .string(2, "litsea")
+        .string(3, crate::version())
+        .message(7, &graph)
+        .message(8, &opset);
+    writer.write_all(&model.buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_learner() -> AdaBoost {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner
+            .set_weights(vec![("UW4:は".to_string(), 0.5), ("BW2:です".to_string(), -1.0)], -0.25);
+        learner
+    }
+
+    #[test]
+    fn test_message_encoding() {
+        let mut m = Message::default();
+        m.varint(1, 300).string(2, "ab");
+        assert_eq!(m.buf, vec![0x08, 0xac, 0x02, 0x12, 0x02, b'a', b'b']);
+    }
+
+    #[test]
+    fn test_feature_table() {
+        let learner = test_learner();
+        let table = feature_table(&learner);
+        assert_eq!(table.len(), 2);
+        assert!(table.contains(&"UW4:は"));
+        assert!(table.contains(&"BW2:です"));
+        assert_eq!(padding_index(&learner), 2);
+    }
+
+    #[test]
+    fn test_export_model() {
+        let learner = test_learner();
+        let mut buf = Vec::new();
+        export_model(&learner, &mut buf).unwrap();
+        // ir_version comes first.
+        assert_eq!(&buf[..2], &[0x08, IR_VERSION as u8]);
+
+        // The weights initializer holds the weights in table order, then the padding.
+        let table = feature_table(&learner);
+        let weights: Vec<u8> = table
+            .iter()
+            .map(|f| if *f == "UW4:は" { 0.5f32 } else { -1.0f32 })
+            .chain(std::iter::once(0.0))
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let mut raw = Message::default();
+        raw.bytes(9, &weights);
+        assert!(buf.windows(raw.buf.len()).any(|w| w == raw.buf));
+    }
+}
@@ -0,0 +1,384 @@
+//! Serializable configuration of the whole segmentation pipeline.
+//!
+//! A [`PipelineConfig`] records everything that affects how text is segmented besides
+//! the model weights: the language (which selects the character classes), the
+//! normalization, the feature templates, the model, the dictionaries, the
+//! post-processing rules and the output format. It is read from and written to TOML or
+//! JSON, and `litsea train --config` embeds it in the model so that `litsea segment`
+//! uses the same settings the model was trained with.
+//!
+//! ```
+//! use litsea::pipeline::PipelineConfig;
+//!
+//! let config = PipelineConfig::from_toml(
+//!     r#"
+//! language = "japanese"
+//! model = "resources/japanese.model"
+//!
+//! [normalizer]
+//! fullwidth_ascii = true
+//!
+//! [post_rules]
+//! drop_stopwords = true
+//! "#,
+//! )
+//! .unwrap();
+//! assert!(config.normalizer.fullwidth_ascii);
+//! assert!(config.post_rules.drop_stopwords);
+//! assert_eq!(PipelineConfig::from_json(&config.to_json().unwrap()).unwrap(), config);
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::adaboost::AdaBoost;
+use crate::cli::OutputFormat;
+use crate::error::LitseaError;
+use crate::grouping::GroupingRules;
+use crate::language::{Language, LatinClasses};
+use crate::model_info;
+use crate::normalize::Normalizer;
+use crate::segmenter::FeatureTemplates;
+
+/// The metadata key under which [`PipelineConfig::embed`] stores the configuration in
+/// a model.
+pub const METADATA_KEY: &str = "pipeline";
+
+/// Dictionaries used when segmenting. Relative paths are resolved against the working
+/// directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Dictionaries {
+    /// A reading dictionary for `tagged` and `mecab` output.
+    pub readings: Option<PathBuf>,
+    /// A lemma dictionary for `tagged` and `mecab` output.
+    pub lemmas: Option<PathBuf>,
+    /// A word list for compound splitting, in addition to the built-in one.
+    pub compound_words: Option<PathBuf>,
+    /// A stopword list, in addition to the built-in one.
+    pub stopwords: Option<PathBuf>,
+    /// A unigram vocabulary for N-best reranking.
+    pub vocab: Option<PathBuf>,
+    /// A word list or MeCab dictionary for lattice segmentation.
+    pub lexicon: Option<PathBuf>,
+}
+
+/// Post-processing rules applied to the segmented words.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostRules {
+    /// Split compound nouns into their parts.
+    pub split_compounds: bool,
+    /// Drop stopwords from the output.
+    pub drop_stopwords: bool,
+    /// Weight of the vocabulary score when reranking.
+    pub vocab_weight: f64,
+    /// Number of candidate segmentations when reranking.
+    pub beam: usize,
+    /// Bonus of lexicon words for every position inside them, in lattice segmentation.
+    pub lexicon_bonus: f64,
+    /// Tokens that are never split, such as numbers and URLs.
+    pub grouping: GroupingRules,
+    /// The shift added to the score of every boundary decision; positive splits more.
+    pub boundary_bias: f64,
+}
+
+impl Default for PostRules {
+    fn default() -> Self {
+        PostRules {
+            split_compounds: false,
+            drop_stopwords: false,
+            vocab_weight: 0.1,
+            beam: 8,
+            lexicon_bonus: 0.5,
+            grouping: GroupingRules::default(),
+            boundary_bias: 0.0,
+        }
+    }
+}
+
+/// The configuration of a segmentation pipeline.
+///
+/// All fields have defaults, so a configuration file only needs the settings that
+/// differ from them. Unknown fields are rejected to catch typos.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    /// The language, which selects the character classes.
+    #[serde(with = "display_from_str")]
+    pub language: Language,
+    /// Normalization applied before feature extraction and segmentation.
+    pub normalizer: Normalizer,
+    /// The feature templates the model was trained with.
+    pub feature_templates: FeatureTemplates,
+    /// How Latin letters are divided into character types.
+    pub latin_classes: LatinClasses,
+    /// The model to segment with (file path or http/https URL).
+    pub model: Option<String>,
+    /// Dictionaries used when segmenting.
+    pub dictionaries: Dictionaries,
+    /// Post-processing rules.
+    pub post_rules: PostRules,
+    /// The output format of `litsea segment`.
+    #[serde(with = "display_from_str")]
+    pub output_format: OutputFormat,
+}
+
+impl PipelineConfig {
+    /// Parses a configuration from TOML.
+    ///
+    /// # Arguments
+    /// * `text` - The TOML document.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if the document is not valid TOML or has
+    /// unknown or invalid fields.
+    pub fn from_toml(text: &str) -> crate::error::Result<Self> {
+        toml::from_str(text).map_err(invalid_config)
+    }
+
+    /// Parses a configuration from JSON.
+    ///
+    /// # Arguments
+    /// * `text` - The JSON document.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if the document is not valid JSON or has
+    /// unknown or invalid fields.
+    pub fn from_json(text: &str) -> crate::error::Result<Self> {
+        serde_json::from_str(text).map_err(invalid_config)
+    }
+
+    /// Serializes the configuration to TOML.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration cannot be serialized.
+    pub fn to_toml(&self) -> crate::error::Result<String> {
+        toml::to_string_pretty(self).map_err(invalid_config)
+    }
+
+    /// Serializes the configuration to pretty-printed JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration cannot be serialized.
+    pub fn to_json(&self) -> crate::error::Result<String> {
+        serde_json::to_string_pretty(self).map_err(invalid_config)
+    }
+
+    /// Loads a configuration file: JSON if its extension is `.json`, TOML otherwise.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the configuration file.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if the file cannot be read, or
+    /// [`LitseaError::InvalidInput`] if it cannot be parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        if is_json(path) { Self::from_json(&text) } else { Self::from_toml(&text) }
+    }
+
+    /// Saves the configuration: as JSON if the extension is `.json`, TOML otherwise.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the configuration file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::error::Result<()> {
+        let path = path.as_ref();
+        let text = if is_json(path) { self.to_json()? } else { self.to_toml()? };
+        Ok(fs::write(path, text)?)
+    }
+
+    /// Returns the configuration as a model metadata entry: [`METADATA_KEY`] and the
+    /// configuration as single-line JSON.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration cannot be serialized.
+    pub fn to_metadata(&self) -> crate::error::Result<(String, String)> {
+        let value = serde_json::to_string(self).map_err(invalid_config)?;
+        Ok((METADATA_KEY.to_string(), value))
+    }
+
+    /// Embeds the configuration in a model, to be written with it.
+    ///
+    /// # Arguments
+    /// * `model` - The model.
+    ///
+    /// # Errors
+    /// Returns an error if the configuration cannot be serialized.
+    pub fn embed(&self, model: &mut AdaBoost) -> crate::error::Result<()> {
+        let (key, value) = self.to_metadata()?;
+        model.set_metadata(&key, &value)
+    }
+
+    /// Reads the configuration embedded in a model.
+    ///
+    /// # Arguments
+    /// * `model` - The model.
+    ///
+    /// # Returns
+    /// The embedded configuration, or `None` if the model has none.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::ModelFormat`] if the embedded configuration cannot be parsed.
+    pub fn from_model(model: &AdaBoost) -> crate::error::Result<Option<Self>> {
+        model
+            .metadata(METADATA_KEY)
+            .map(|json| {
+                serde_json::from_str(json).map_err(|e| {
+                    LitseaError::ModelFormat(format!("Invalid embedded configuration: {}", e))
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns the configuration for a model without an embedded one: the defaults, with
+    /// the feature templates the model records, e.g. a model imported from TinySegmenter.
+    ///
+    /// # Arguments
+    /// * `model` - The model.
+    ///
+    /// # Returns
+    /// The default configuration for the model.
+    #[must_use]
+    pub fn for_model(model: &AdaBoost) -> Self {
+        PipelineConfig {
+            feature_templates: model_info::recorded_templates(model).unwrap_or_default(),
+            ..PipelineConfig::default()
+        }
+    }
+}
+
+/// Returns `true` if the path has the `.json` extension.
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Wraps a parse or serialization error as [`LitseaError::InvalidInput`].
+fn invalid_config<E: ToString>(e: E) -> LitseaError {
+    LitseaError::InvalidInput(e.to_string())
+}
+
+/// Serializes a value through its `Display` and `FromStr` implementations, so it
+/// appears in configuration files as on the command line.
+mod display_from_str {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr<Err = String>,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    fn config() -> PipelineConfig {
+        PipelineConfig {
+            language: Language::Korean,
+            normalizer: Normalizer {
+                fullwidth_ascii: true,
+                halfwidth_katakana: true,
+                ..Normalizer::default()
+            },
+            model: Some("resources/korean.model".to_string()),
+            dictionaries: Dictionaries {
+                stopwords: Some(PathBuf::from("stopwords.txt")),
+                ..Dictionaries::default()
+            },
+            post_rules: PostRules {
+                beam: 4,
+                boundary_bias: 0.5,
+                ..PostRules::default()
+            },
+            output_format: OutputFormat::Tagged,
+            feature_templates: FeatureTemplates::TinySegmenter,
+            latin_classes: LatinClasses::CaseWidth,
+        }
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = PipelineConfig::from_toml("").unwrap();
+        assert_eq!(config, PipelineConfig::default());
+        assert_eq!(config.language, Language::Japanese);
+        assert_eq!(config.output_format, OutputFormat::Plain);
+        assert_eq!(config.post_rules.beam, 8);
+        assert_eq!(PipelineConfig::from_json("{}").unwrap(), config);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let config = config();
+        let toml = config.to_toml().unwrap();
+        assert!(toml.contains("language = \"korean\""));
+        assert!(toml.contains("feature_templates = \"tinysegmenter\""));
+        assert!(toml.contains("latin_classes = \"case-width\""));
+        assert_eq!(PipelineConfig::from_toml(&toml).unwrap(), config);
+        assert_eq!(PipelineConfig::from_json(&config.to_json().unwrap()).unwrap(), config);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let err = PipelineConfig::from_toml("langauge = \"japanese\"").unwrap_err();
+        assert!(matches!(err, LitseaError::InvalidInput(_)), "{}", err);
+        assert!(PipelineConfig::from_toml("language = \"klingon\"").is_err());
+        assert!(PipelineConfig::from_json("{\"output_format\": \"xml\"}").is_err());
+    }
+
+    #[test]
+    fn test_load_save() {
+        let dir = TempDir::new().unwrap();
+        for name in ["pipeline.toml", "pipeline.json"] {
+            let path = dir.path().join(name);
+            config().save(&path).unwrap();
+            assert_eq!(PipelineConfig::load(&path).unwrap(), config());
+        }
+        let json = fs::read_to_string(dir.path().join("pipeline.json")).unwrap();
+        assert!(json.trim_start().starts_with('{'));
+    }
+
+    #[test]
+    fn test_embed() {
+        let mut model = AdaBoost::new(0.01, 10);
+        assert_eq!(PipelineConfig::from_model(&model).unwrap(), None);
+        config().embed(&mut model).unwrap();
+        assert!(!model.metadata(METADATA_KEY).unwrap().contains('\n'));
+        assert_eq!(PipelineConfig::from_model(&model).unwrap(), Some(config()));
+
+        model.set_metadata(METADATA_KEY, "{").unwrap();
+        let err = PipelineConfig::from_model(&model).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)), "{}", err);
+    }
+
+    #[test]
+    fn test_for_model() {
+        let mut model = AdaBoost::new(0.01, 10);
+        assert_eq!(PipelineConfig::for_model(&model), PipelineConfig::default());
+        model.set_metadata(model_info::TEMPLATES_KEY, "tinysegmenter-1").unwrap();
+        let config = PipelineConfig::for_model(&model);
+        assert_eq!(config.feature_templates, FeatureTemplates::TinySegmenter);
+        assert_eq!(config.language, Language::Japanese);
+    }
+}
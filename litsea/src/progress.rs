@@ -0,0 +1,213 @@
+//! Progress callbacks for training.
+//!
+//! The library never writes to standard error itself: events for logging go through
+//! `tracing` (with the `tracing` feature), and applications that show progress, such as
+//! the `litsea` command with its progress bar, register a [`ProgressHandler`] with
+//! [`AdaBoost::set_progress_handler`](crate::adaboost::AdaBoost::set_progress_handler)
+//! or [`Trainer::set_progress_handler`](crate::trainer::Trainer::set_progress_handler).
+//! A handler can record the progress with [`MetricsCsv`], or with
+//! [`EventWriter::add_progress`](crate::tensorboard::EventWriter::add_progress) for
+//! TensorBoard.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//!
+//! use litsea::adaboost::AdaBoost;
+//! use litsea::progress::TrainingProgress;
+//!
+//! let iterations = Arc::new(AtomicUsize::new(0));
+//! let counter = Arc::clone(&iterations);
+//! let mut learner = AdaBoost::new(0.01, 10);
+//! learner.set_progress_handler(Some(Arc::new(move |progress: &TrainingProgress| {
+//!     counter.store(progress.iteration, Ordering::Relaxed);
+//! })));
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::adaboost::TrainingRecord;
+
+/// The state of a training run after a boosting iteration.
+#[derive(Debug, Clone, Copy)]
+pub struct TrainingProgress<'a> {
+    /// The iteration just completed, starting from 1.
+    pub iteration: usize,
+    /// The maximum number of iterations; training may stop earlier.
+    pub num_iterations: usize,
+    /// The feature whose weight the iteration changed, empty for the bias term.
+    pub feature: &'a str,
+    /// The weight the iteration added to the feature.
+    pub alpha: f64,
+    /// The weighted error rate of the weak learner of the iteration on the training
+    /// instances, between 0 and 0.5.
+    pub error_rate: f64,
+    /// The accuracy on the training instances after the iteration in percentage (%),
+    /// with a development set or every
+    /// [accuracy interval](crate::adaboost::AdaBoost::set_accuracy_interval) iterations.
+    pub train_accuracy: Option<f64>,
+    /// The accuracies after the iteration, with a development set.
+    pub record: Option<&'a TrainingRecord>,
+}
+
+/// Receives the progress of training.
+///
+/// Handlers are shared behind an [`Arc`](std::sync::Arc) and called through `&self`, so
+/// they keep their state in atomics or types with interior mutability, like progress
+/// bars. Any `Fn(&TrainingProgress)` closure is a handler.
+pub trait ProgressHandler: Send + Sync {
+    /// Called after every boosting iteration.
+    ///
+    /// # Arguments
+    /// * `progress` - The state of the training run.
+    fn on_iteration(&self, progress: &TrainingProgress<'_>);
+}
+
+impl<F> ProgressHandler for F
+where
+    F: Fn(&TrainingProgress<'_>) + Send + Sync,
+{
+    fn on_iteration(&self, progress: &TrainingProgress<'_>) {
+        self(progress)
+    }
+}
+
+impl fmt::Debug for dyn ProgressHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHandler")
+    }
+}
+
+/// The header of the files written by [`MetricsCsv`].
+const CSV_HEADER: &str = "iteration,feature,alpha,error_rate,train_accuracy,dev_accuracy,dev_f1";
+
+/// Writes the progress of every iteration as a row of CSV, so that long training runs
+/// can be monitored and compared, e.g. in a spreadsheet.
+///
+/// The columns are `iteration`, `feature`, `alpha`, `error_rate`, `train_accuracy`,
+/// `dev_accuracy` and `dev_f1`, as in [`TrainingProgress`] and [`TrainingRecord`]. The
+/// accuracies are empty in the rows of iterations that report none.
+///
+/// ```
+/// use litsea::progress::{MetricsCsv, TrainingProgress};
+///
+/// let mut out = Vec::new();
+/// let mut csv = MetricsCsv::new(&mut out).unwrap();
+/// csv.add_progress(&TrainingProgress {
+///     iteration: 1,
+///     num_iterations: 100,
+///     feature: "UW4:は",
+///     alpha: 0.5,
+///     error_rate: 0.25,
+///     train_accuracy: Some(80.0),
+///     record: None,
+/// })
+/// .unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap().lines().nth(1), Some("1,UW4:は,0.5,0.25,80,,"));
+/// ```
+#[derive(Debug)]
+pub struct MetricsCsv<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> MetricsCsv<W> {
+    /// Creates a CSV writer and writes the header.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination of the rows, e.g. a file.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+        Ok(MetricsCsv { writer })
+    }
+
+    /// Writes the row of an iteration.
+    ///
+    /// # Arguments
+    /// * `progress` - The progress reported after the iteration.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn add_progress(&mut self, progress: &TrainingProgress<'_>) -> io::Result<()> {
+        let optional = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        writeln!(
+            self.writer,
+            "{},{},{},{},{},{},{}",
+            progress.iteration,
+            csv_field(progress.feature),
+            progress.alpha,
+            progress.error_rate,
+            optional(progress.train_accuracy),
+            optional(progress.record.map(|record| record.dev_accuracy)),
+            optional(progress.record.map(|record| record.dev_f1)),
+        )
+    }
+
+    /// Flushes the rows written so far.
+    ///
+    /// # Errors
+    /// Returns an error if flushing fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, a quote or a line break.
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_csv() {
+        let record = TrainingRecord {
+            iteration: 2,
+            train_accuracy: 90.0,
+            dev_accuracy: 85.5,
+            dev_f1: 80.25,
+            overfitting: false,
+        };
+        let mut out = Vec::new();
+        let mut csv = MetricsCsv::new(&mut out).unwrap();
+        csv.add_progress(&TrainingProgress {
+            iteration: 2,
+            num_iterations: 10,
+            feature: "UW4:,",
+            alpha: -0.125,
+            error_rate: 0.375,
+            train_accuracy: Some(90.0),
+            record: Some(&record),
+        })
+        .unwrap();
+        csv.add_progress(&TrainingProgress {
+            iteration: 3,
+            num_iterations: 10,
+            feature: "",
+            alpha: 0.5,
+            error_rate: 0.25,
+            train_accuracy: None,
+            record: None,
+        })
+        .unwrap();
+        csv.flush().unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "2,\"UW4:,\",-0.125,0.375,90,85.5,80.25");
+        assert_eq!(lines[2], "3,,0.5,0.25,,,");
+        assert_eq!(csv_field("UW4:\""), "\"UW4:\"\"\"");
+        assert_eq!(csv_field("UW4:は"), "UW4:は");
+    }
+}
@@ -0,0 +1,535 @@
+//! Project scaffolding and build tasks.
+//!
+//! [`init`] creates a working directory for training a model:
+//!
+//! ```text
+//! litsea.toml        pipeline configuration, with every setting and its default
+//! tasks.toml         build tasks, run with `litsea build`
+//! corpus/train.txt   a small sample training corpus, to be replaced
+//! corpus/gold.txt    a small sample gold corpus, held out from training
+//! features/          extracted features
+//! models/            trained models and training logs
+//! ```
+//!
+//! The tasks file defines Makefile-style tasks: each task runs one `litsea` command
+//! after the tasks it depends on, and is skipped when all its outputs are newer than
+//! its inputs.
+//!
+//! ```toml
+//! default = ["train"]
+//!
+//! [tasks.features]
+//! inputs = ["litsea.toml", "corpus/train.txt"]
+//! outputs = ["features/train.txt"]
+//! run = ["extract", "--config", "litsea.toml", "corpus/train.txt", "features/train.txt"]
+//!
+//! [tasks.train]
+//! deps = ["features"]
+//! inputs = ["features/train.txt"]
+//! outputs = ["models/model.txt"]
+//! run = ["train", "--config", "litsea.toml", "features/train.txt", "models/model.txt"]
+//! ```
+//!
+//! ```
+//! use litsea::project::Tasks;
+//!
+//! let tasks = Tasks::from_toml(
+//!     r#"
+//! [tasks.features]
+//! run = ["extract", "corpus.txt", "features.txt"]
+//!
+//! [tasks.train]
+//! deps = ["features"]
+//! run = ["train", "features.txt", "model.txt"]
+//! "#,
+//! )
+//! .unwrap();
+//! assert_eq!(tasks.plan(&["train"]).unwrap(), ["features", "train"]);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LitseaError;
+use crate::language::Language;
+
+/// Name of the pipeline configuration file of a project.
+pub const CONFIG_FILE: &str = "litsea.toml";
+
+/// Name of the tasks file of a project.
+pub const TASKS_FILE: &str = "tasks.toml";
+
+/// Directories created by [`init`].
+const DIRECTORIES: [&str; 3] = ["corpus", "features", "models"];
+
+/// A build task: a `litsea` command with its dependencies, inputs and outputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Task {
+    /// What the task does, shown by `litsea build --list`.
+    pub description: Option<String>,
+    /// Tasks to run before this one.
+    pub deps: Vec<String>,
+    /// Files the task reads. Relative paths are resolved against the project directory.
+    pub inputs: Vec<PathBuf>,
+    /// Files the task writes. A task without outputs always runs.
+    pub outputs: Vec<PathBuf>,
+    /// The arguments of the `litsea` command, starting with the subcommand.
+    pub run: Vec<String>,
+}
+
+impl Task {
+    /// Returns whether all outputs exist and are newer than all inputs, so the task
+    /// does not need to run. A task without outputs is never up to date.
+    ///
+    /// # Arguments
+    /// * `dir` - The project directory, against which relative paths are resolved.
+    #[must_use]
+    pub fn is_up_to_date(&self, dir: &Path) -> bool {
+        let modified = |path: &PathBuf| fs::metadata(dir.join(path)).and_then(|m| m.modified());
+        let Some(oldest_output) = self
+            .outputs
+            .iter()
+            .map(modified)
+            .collect::<io::Result<Vec<SystemTime>>>()
+            .ok()
+            .and_then(|times| times.into_iter().min())
+        else {
+            return false;
+        };
+        self.inputs
+            .iter()
+            .map(modified)
+            .all(|input| input.is_ok_and(|time| time <= oldest_output))
+    }
+}
+
+/// The tasks of a project, read from its tasks file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tasks {
+    /// The tasks run by `litsea build` without arguments.
+    pub default: Vec<String>,
+    /// The tasks by name.
+    pub tasks: BTreeMap<String, Task>,
+}
+
+impl Tasks {
+    /// Parses tasks from TOML.
+    ///
+    /// # Arguments
+    /// * `text` - The TOML document.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if the document is not valid TOML or has
+    /// unknown or invalid fields, or a task depends on an undefined task.
+    pub fn from_toml(text: &str) -> crate::error::Result<Self> {
+        let tasks: Tasks = toml::from_str(text).map_err(invalid_tasks)?;
+        for (name, task) in &tasks.tasks {
+            if task.run.is_empty() {
+                return Err(invalid_tasks(format!("Task '{}' has no command to run", name)));
+            }
+            if let Some(dep) = task.deps.iter().find(|dep| !tasks.tasks.contains_key(*dep)) {
+                return Err(invalid_tasks(format!(
+                    "Task '{}' depends on undefined task '{}'",
+                    name, dep
+                )));
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Loads a tasks file.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the tasks file.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if the file cannot be read, or
+    /// [`LitseaError::InvalidInput`] if it cannot be parsed.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Orders the given tasks and all the tasks they depend on so that every task comes
+    /// after its dependencies. Each task appears once.
+    ///
+    /// # Arguments
+    /// * `targets` - The tasks to run, or none for the [`default`](Self::default) tasks.
+    ///
+    /// # Returns
+    /// The names of the tasks in the order to run them.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if a task is undefined or the dependencies
+    /// form a cycle.
+    pub fn plan<'a, S: AsRef<str>>(
+        &'a self,
+        targets: &'a [S],
+    ) -> crate::error::Result<Vec<&'a str>> {
+        let targets: Vec<&str> = if targets.is_empty() {
+            self.default.iter().map(String::as_str).collect()
+        } else {
+            targets.iter().map(AsRef::as_ref).collect()
+        };
+        if targets.is_empty() {
+            return Err(invalid_tasks("No tasks given and no default tasks"));
+        }
+        let mut order = Vec::new();
+        let mut visiting = Vec::new();
+        for target in targets {
+            self.visit(target, &mut visiting, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Appends `name` to `order` after its dependencies, in depth-first order.
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        visiting: &mut Vec<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> crate::error::Result<()> {
+        if order.contains(&name) {
+            return Ok(());
+        }
+        if visiting.contains(&name) {
+            visiting.push(name);
+            return Err(invalid_tasks(format!(
+                "Task dependencies form a cycle: {}",
+                visiting.join(" -> ")
+            )));
+        }
+        let task = self
+            .tasks
+            .get(name)
+            .ok_or_else(|| invalid_tasks(format!("Undefined task '{}'", name)))?;
+        visiting.push(name);
+        for dep in &task.deps {
+            self.visit(dep, visiting, order)?;
+        }
+        visiting.pop();
+        order.push(name);
+        Ok(())
+    }
+}
+
+/// The files written by [`init`], relative to the project directory.
+fn template_files(language: Language) -> [(&'static str, String); 4] {
+    let (train, gold) = sample_corpus(language);
+    [
+        (CONFIG_FILE, CONFIG_TEMPLATE.replace("{language}", &language.to_string())),
+        (TASKS_FILE, TASKS_TEMPLATE.replace("{language}", &language.to_string())),
+        ("corpus/train.txt", train.to_string()),
+        ("corpus/gold.txt", gold.to_string()),
+    ]
+}
+
+/// Creates a project for training a model in `dir`, as described in the
+/// [module documentation](self). `dir` is created if it does not exist.
+///
+/// # Arguments
+/// * `dir` - The project directory.
+/// * `language` - The language of the project, written to the configuration and used
+///   for the sample corpora.
+///
+/// # Returns
+/// The paths of the files written.
+///
+/// # Errors
+/// Returns [`LitseaError::Io`] with [`io::ErrorKind::AlreadyExists`] if any of the files
+/// exists, before writing anything, or [`LitseaError::Io`] if a file cannot be written.
+pub fn init(dir: &Path, language: Language) -> crate::error::Result<Vec<PathBuf>> {
+    let files = template_files(language);
+    if let Some((name, _)) = files.iter().find(|(name, _)| dir.join(name).exists()) {
+        return Err(LitseaError::Io(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dir.join(name).display()),
+        )));
+    }
+    for subdir in DIRECTORIES {
+        fs::create_dir_all(dir.join(subdir))?;
+    }
+    let mut written = Vec::with_capacity(files.len());
+    for (name, content) in files {
+        let path = dir.join(name);
+        fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Sample training and gold corpora, one sentence per line with words separated by
+/// spaces.
+fn sample_corpus(language: Language) -> (&'static str, &'static str) {
+    match language {
+        Language::Japanese => (
+            "これ は テスト です 。\n\
+             今日 は いい 天気 です ね 。\n\
+             私 は 毎朝 コーヒー を 飲み ます 。\n\
+             駅 まで 歩い て 十 分 かかり ます 。\n\
+             新しい 本 を 二 冊 買い まし た 。\n\
+             彼女 は 東京 に 住ん で い ます 。\n\
+             明日 の 会議 は 午後 三 時 から です 。\n\
+             この 映画 は とても 面白かっ た 。\n",
+            "私 は 本 を 読み ます 。\n\
+             明日 は 雨 が 降る そう です 。\n\
+             駅 の 近く に 新しい 店 が でき た 。\n",
+        ),
+        Language::Chinese => (
+            "这 是 一 个 测试 。\n\
+             今天 天气 很 好 。\n\
+             我 每天 早上 喝 咖啡 。\n\
+             他 在 北京 工作 。\n\
+             我们 明天 下午 开会 。\n\
+             这 本 书 非常 有意思 。\n",
+            "我 喜欢 看 书 。\n\
+             明天 可能 会 下雨 。\n",
+        ),
+        Language::Korean => (
+            "이것 은 테스트 입니다 .\n\
+             오늘 은 날씨 가 좋 습니다 .\n\
+             저 는 매일 아침 커피 를 마십니다 .\n\
+             그녀 는 서울 에 살 고 있 습니다 .\n\
+             내일 회의 는 오후 세 시 입니다 .\n\
+             이 영화 는 아주 재미있 었 습니다 .\n",
+            "저 는 책 을 읽 습니다 .\n\
+             내일 은 비 가 온다고 합니다 .\n",
+        ),
+    }
+}
+
+const CONFIG_TEMPLATE: &str = r#"# Pipeline configuration of the project, embedded in the model by `litsea train
+# --config` so that `litsea segment` uses the same settings. Every setting is shown
+# with its default; see the "Pipeline Configuration" chapter of the documentation.
+
+# Language, which selects the character classes: japanese, chinese or korean.
+language = "{language}"
+
+# Feature templates: litsea, or tinysegmenter for models trained for TinySegmenter.
+feature_templates = "litsea"
+
+# Latin character classes: single, case or case-width.
+latin_classes = "single"
+
+# Model used by `litsea segment` when no model is given.
+# model = "models/model.txt"
+
+# Output format of `litsea segment`: plain, tagged or mecab.
+output_format = "plain"
+
+[normalizer]
+# Unicode normalization: none, nfc or nfkc.
+unicode = "none"
+# Convert full-width ASCII to half-width and the ideographic space to a space.
+fullwidth_ascii = false
+# Convert half-width katakana to full-width.
+halfwidth_katakana = false
+# Lowercase Latin letters.
+lowercase = false
+
+[dictionaries]
+# readings = "readings.tsv"
+# lemmas = "lemmas.tsv"
+# compound_words = "words.txt"
+# stopwords = "stopwords.txt"
+# vocab = "vocab.tsv"
+# lexicon = "lexicon.txt"
+
+[post_rules]
+# Split compound nouns into their parts.
+split_compounds = false
+# Drop stopwords from the output.
+drop_stopwords = false
+# Weight of the vocabulary score when reranking with a vocabulary.
+vocab_weight = 0.1
+# Number of candidate segmentations when reranking.
+beam = 8
+# Bonus of lexicon words for every position inside them, with a lexicon.
+lexicon_bonus = 0.5
+
+[post_rules.grouping]
+# Never split runs of digits, such as 2024 or 3.14.
+digits = false
+# Never split URLs.
+urls = false
+# Never split email addresses.
+emails = false
+# Never split emoji sequences.
+emoji = false
+# Never split runs of Latin letters.
+latin = false
+"#;
+
+const TASKS_TEMPLATE: &str = r#"# Build tasks, run with `litsea build [TASK...]`. Each task runs a litsea command
+# after the tasks in `deps`, and is skipped when all its `outputs` are newer than its
+# `inputs`. Paths are relative to this directory.
+
+# Tasks run by `litsea build` without arguments.
+default = ["analyze"]
+
+[tasks.features]
+description = "Extract features from the training corpus"
+inputs = ["litsea.toml", "corpus/train.txt"]
+outputs = ["features/train.txt"]
+run = ["extract", "--config", "litsea.toml", "corpus/train.txt", "features/train.txt"]
+
+[tasks.dev-features]
+description = "Extract features from the gold corpus to monitor overfitting"
+inputs = ["litsea.toml", "corpus/gold.txt"]
+outputs = ["features/gold.txt"]
+run = ["extract", "--config", "litsea.toml", "corpus/gold.txt", "features/gold.txt"]
+
+[tasks.train]
+description = "Train the model"
+deps = ["features", "dev-features"]
+inputs = ["litsea.toml", "features/train.txt", "features/gold.txt"]
+outputs = ["models/model.txt"]
+run = [
+    "train", "--config", "litsea.toml", "--num-iterations", "1000",
+    "--dev-features", "features/gold.txt", "--log", "models/train.jsonl",
+    "features/train.txt", "models/model.txt",
+]
+
+[tasks.analyze]
+description = "Show the errors of the model on the gold corpus"
+deps = ["train"]
+run = ["analyze", "--language", "{language}", "corpus/gold.txt", "models/model.txt"]
+"#;
+
+/// Wraps a parse error or an invalid task as [`LitseaError::InvalidInput`].
+fn invalid_tasks<E: ToString>(e: E) -> LitseaError {
+    LitseaError::InvalidInput(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use tempfile::tempdir;
+
+    use crate::pipeline::PipelineConfig;
+
+    fn tasks() -> Tasks {
+        Tasks::from_toml(
+            r#"
+default = ["d"]
+[tasks.a]
+run = ["extract"]
+[tasks.b]
+deps = ["a"]
+run = ["extract"]
+[tasks.c]
+deps = ["a"]
+run = ["extract"]
+[tasks.d]
+deps = ["b", "c"]
+run = ["train"]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_plan() {
+        let tasks = tasks();
+        assert_eq!(tasks.plan::<&str>(&[]).unwrap(), ["a", "b", "c", "d"]);
+        assert_eq!(tasks.plan(&["c", "b"]).unwrap(), ["a", "c", "b"]);
+        assert!(tasks.plan(&["e"]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_tasks() {
+        let err = Tasks::from_toml("[tasks.a]\ndeps = [\"a\"]\nrun = [\"x\"]\n")
+            .unwrap()
+            .plan(&["a"])
+            .unwrap_err();
+        assert!(
+            matches!(&err, LitseaError::InvalidInput(reason) if reason.contains("cycle: a -> a")),
+            "{}",
+            err
+        );
+        assert!(Tasks::from_toml("[tasks.a]\ndeps = [\"b\"]\nrun = [\"x\"]\n").is_err());
+        assert!(Tasks::from_toml("[tasks.a]\n").is_err());
+        assert!(Tasks::from_toml("[tasks.a]\nrun = [\"x\"]\ncommand = \"x\"\n").is_err());
+        assert!(Tasks::default().plan::<&str>(&[]).is_err());
+    }
+
+    #[test]
+    fn test_is_up_to_date() {
+        let dir = tempdir().unwrap();
+        let task = Task {
+            inputs: vec![PathBuf::from("in.txt")],
+            outputs: vec![PathBuf::from("out.txt")],
+            run: vec!["extract".to_string()],
+            ..Task::default()
+        };
+        fs::write(dir.path().join("in.txt"), "").unwrap();
+        assert!(!task.is_up_to_date(dir.path()));
+
+        let output = fs::File::create(dir.path().join("out.txt")).unwrap();
+        let input_time = fs::metadata(dir.path().join("in.txt")).unwrap().modified().unwrap();
+        output.set_modified(input_time + Duration::from_secs(10)).unwrap();
+        assert!(task.is_up_to_date(dir.path()));
+
+        output.set_modified(input_time - Duration::from_secs(10)).unwrap();
+        assert!(!task.is_up_to_date(dir.path()));
+
+        let no_outputs = Task {
+            outputs: Vec::new(),
+            ..task
+        };
+        assert!(!no_outputs.is_up_to_date(dir.path()));
+    }
+
+    #[test]
+    fn test_init() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().join("project");
+        let files = init(&project, Language::Korean).unwrap();
+        assert_eq!(files.len(), 4);
+        for subdir in DIRECTORIES {
+            assert!(project.join(subdir).is_dir());
+        }
+
+        // The configuration documents the defaults.
+        let config = PipelineConfig::load(project.join(CONFIG_FILE)).unwrap();
+        assert_eq!(
+            config,
+            PipelineConfig {
+                language: Language::Korean,
+                ..PipelineConfig::default()
+            }
+        );
+        let tasks = Tasks::load(project.join(TASKS_FILE)).unwrap();
+        assert_eq!(
+            tasks.plan::<&str>(&[]).unwrap(),
+            ["features", "dev-features", "train", "analyze"]
+        );
+        assert!(tasks.tasks["analyze"].run.contains(&"korean".to_string()));
+
+        let err = init(&project, Language::Korean).unwrap_err();
+        assert!(
+            matches!(&err, LitseaError::Io(e) if e.kind() == io::ErrorKind::AlreadyExists),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_sample_corpora() {
+        for language in [Language::Japanese, Language::Chinese, Language::Korean] {
+            let (train, gold) = sample_corpus(language);
+            for line in train.lines().chain(gold.lines()) {
+                assert!(line.contains(' ') && !line.contains("  "), "{line}");
+            }
+        }
+    }
+}
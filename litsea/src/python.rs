@@ -0,0 +1,207 @@
+//! Python bindings, enabled by the `python` feature.
+//!
+//! The module `litsea` exposes a `Segmenter` class that loads a model file and segments
+//! text, and a `Trainer` class that trains a segmenter from corpus files with the
+//! [`Pipeline`](crate::Pipeline):
+//!
+//! ```python
+//! import litsea
+//!
+//! segmenter = litsea.Segmenter("./resources/japanese.model", language="japanese")
+//! segmenter.segment("これはテストです。")  # ['これ', 'は', 'テスト', 'です', '。']
+//!
+//! trainer = litsea.Trainer(language="japanese", iterations=500)
+//! trainer.add_corpus("corpus.txt")
+//! segmenter = trainer.train(model_path="model.txt")
+//! ```
+//!
+//! Build and install the extension module with [maturin](https://www.maturin.rs) from
+//! `bindings/python`:
+//!
+//! ```sh
+//! cd bindings/python && maturin develop --release
+//! ```
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::adaboost::AdaBoost;
+use crate::compression;
+use crate::error::LitseaError;
+use crate::language::Language;
+use crate::segmenter::{self, FeatureTemplates};
+use crate::training::Pipeline;
+
+/// Converts an error of the library into a Python exception: `OSError` for I/O
+/// failures and `ValueError` for invalid content or arguments.
+fn to_py_err(e: LitseaError) -> PyErr {
+    match e {
+        LitseaError::Io(e) => PyIOError::new_err(e.to_string()),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+/// Parses an argument with `FromStr`, raising `ValueError` if it is invalid.
+fn parse<T: std::str::FromStr<Err = String>>(value: &str) -> PyResult<T> {
+    value.parse().map_err(PyValueError::new_err)
+}
+
+/// Loads a model file synchronously and builds a segmenter for it.
+fn open_segmenter(
+    model_path: &Path,
+    language: Language,
+    templates: Option<FeatureTemplates>,
+    latin_classes: &str,
+) -> Result<segmenter::Segmenter, LitseaError> {
+    let mut content = Vec::new();
+    compression::open(model_path)?.read_to_end(&mut content)?;
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model_bytes(&content)?;
+    let mut segmenter = segmenter::Segmenter::new(language, Some(learner));
+    // Without templates, those the model records are kept.
+    if let Some(templates) = templates {
+        segmenter.set_feature_templates(templates);
+    }
+    segmenter.set_latin_classes(latin_classes.parse().map_err(LitseaError::InvalidInput)?);
+    segmenter.check_model()?;
+    Ok(segmenter)
+}
+
+/// A word segmenter with a trained model.
+#[pyclass(frozen, module = "litsea")]
+pub struct Segmenter {
+    inner: segmenter::Segmenter,
+}
+
+#[pymethods]
+impl Segmenter {
+    /// Loads a model file, which may be compressed with gzip (`.gz`) or Zstandard
+    /// (`.zst`) with the `compression` feature. A positive `boundary_bias` splits more,
+    /// a negative one less.
+    #[new]
+    #[pyo3(signature = (model_path, language = "japanese", feature_templates = None, latin_classes = "single", boundary_bias = 0.0))]
+    fn new(
+        model_path: PathBuf,
+        language: &str,
+        feature_templates: Option<&str>,
+        latin_classes: &str,
+        boundary_bias: f64,
+    ) -> PyResult<Self> {
+        let language = parse(language)?;
+        let templates = feature_templates.map(parse).transpose()?;
+        let mut inner =
+            open_segmenter(&model_path, language, templates, latin_classes).map_err(to_py_err)?;
+        inner.set_boundary_bias(boundary_bias);
+        Ok(Segmenter { inner })
+    }
+
+    /// Segments a text into words.
+    fn segment(&self, py: Python<'_>, text: &str) -> Vec<String> {
+        py.detach(|| self.inner.segment(text))
+    }
+
+    /// The language of the segmenter.
+    #[getter]
+    fn language(&self) -> String {
+        self.inner.language.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Segmenter(language='{}')", self.inner.language)
+    }
+}
+
+/// Trains a segmenter from corpus files with one sentence per line and words separated
+/// by spaces.
+#[pyclass(module = "litsea")]
+pub struct Trainer {
+    language: Language,
+    templates: FeatureTemplates,
+    threshold: f64,
+    num_iterations: usize,
+    num_threads: usize,
+    corpora: Vec<PathBuf>,
+}
+
+#[pymethods]
+impl Trainer {
+    #[new]
+    #[pyo3(signature = (language = "japanese", feature_templates = "litsea", threshold = 0.01, iterations = 100, threads = 0))]
+    fn new(
+        language: &str,
+        feature_templates: &str,
+        threshold: f64,
+        iterations: usize,
+        threads: usize,
+    ) -> PyResult<Self> {
+        Ok(Trainer {
+            language: parse(language)?,
+            templates: parse(feature_templates)?,
+            threshold,
+            num_iterations: iterations,
+            num_threads: threads,
+            corpora: Vec::new(),
+        })
+    }
+
+    /// Adds a corpus file to train on.
+    fn add_corpus(&mut self, path: PathBuf) {
+        self.corpora.push(path);
+    }
+
+    /// Trains a segmenter on the corpus files, saving the model if `model_path` is given.
+    #[pyo3(signature = (model_path = None))]
+    fn train(&self, py: Python<'_>, model_path: Option<PathBuf>) -> PyResult<Segmenter> {
+        let mut builder = Pipeline::builder()
+            .language(self.language)
+            .feature_templates(self.templates)
+            .threshold(self.threshold)
+            .iterations(self.num_iterations)
+            .threads(self.num_threads);
+        for corpus in &self.corpora {
+            builder = builder.corpus(corpus);
+        }
+        if let Some(path) = model_path {
+            builder = builder.model_path(path);
+        }
+        let inner = py.detach(|| builder.train()).map_err(to_py_err)?;
+        Ok(Segmenter { inner })
+    }
+}
+
+/// Word segmentation with AdaBoost models.
+#[pymodule]
+fn litsea(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", crate::version())?;
+    m.add_class::<Segmenter>()?;
+    m.add_class::<Trainer>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_segmenter() {
+        let model = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model"));
+        let segmenter =
+            open_segmenter(model, Language::Japanese, Some(FeatureTemplates::Litsea), "single")
+                .unwrap();
+        assert_eq!(
+            segmenter.segment("これはテストです。"),
+            vec!["これ", "は", "テスト", "です", "。"]
+        );
+
+        let missing = Path::new("/nonexistent.model");
+        let result =
+            open_segmenter(missing, Language::Japanese, Some(FeatureTemplates::Litsea), "single");
+        assert!(matches!(result, Err(LitseaError::Io(_))));
+        let result =
+            open_segmenter(model, Language::Japanese, Some(FeatureTemplates::Litsea), "upper");
+        assert!(matches!(result, Err(LitseaError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,257 @@
+//! Quantization of the weights of a model, for smaller model files.
+//!
+//! [`AdaBoost::quantize`](crate::adaboost::AdaBoost::quantize) rounds the weights of a
+//! trained model so that [`save_model`](crate::adaboost::AdaBoost::save_model) writes
+//! them with fewer digits:
+//!
+//! - [`Quantization::F16`] rounds every weight to the nearest half-precision float and
+//!   writes it with the fewest digits that read back as the same value, at most five.
+//! - [`Quantization::I8`] maps the weights linearly onto the integers from -127 to 127
+//!   and writes the integers, with the scale in a metadata entry:
+//!
+//! ```text
+//! #litsea-model sha256=<hex>
+//! #format 2
+//! #quantization i8 0.0123
+//! ...
+//! ```
+//!
+//! Loading multiplies the integers by the scale, and rounds f16 weights again, so the
+//! loaded model has exactly the weights it was saved with; the entry must precede the
+//! weights, as `save_model` writes it. Models with i8 weights have format
+//! version 2, so that earlier versions of Litsea, which would read the integers as
+//! weights, refuse to load them. The bias is written in full precision, and features
+//! whose weight rounds to 0 are dropped like those pruned.
+
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::error::LitseaError;
+
+/// The largest integer of i8 weights; -128 is not used, so the range is symmetric.
+#[cfg(feature = "std")]
+const I8_MAX: f64 = 127.0;
+
+/// The largest finite half-precision float.
+const F16_MAX: f64 = 65504.0;
+
+/// The number of bits of the significand of a double-precision float, without the
+/// implicit one.
+const F64_SIGNIFICAND_BITS: i32 = 52;
+
+/// The number of bits of the significand of a half-precision float, without the
+/// implicit one.
+const F16_SIGNIFICAND_BITS: i32 = 10;
+
+/// The exponent of the smallest normal half-precision float.
+const F16_MIN_EXPONENT: i32 = -14;
+
+/// The smallest positive half-precision float, 2^-24.
+const F16_MIN_POSITIVE: f64 = f64::from_bits(
+    ((1023 + F16_MIN_EXPONENT - F16_SIGNIFICAND_BITS) as u64) << F64_SIGNIFICAND_BITS,
+);
+
+/// How the weights of a model are quantized when it is saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "lowercase"))]
+pub enum Quantization {
+    /// Half-precision floats, with 11 significant bits.
+    F16,
+    /// Integers from -127 to 127 times a scale shared by all weights.
+    I8,
+}
+
+impl fmt::Display for Quantization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Quantization::F16 => write!(f, "f16"),
+            Quantization::I8 => write!(f, "i8"),
+        }
+    }
+}
+
+impl FromStr for Quantization {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "f16" => Ok(Quantization::F16),
+            "i8" => Ok(Quantization::I8),
+            _ => Err(format!("Unsupported quantization: '{}'. Supported: f16, i8", s)),
+        }
+    }
+}
+
+/// How much quantizing changed the weights of a model, returned by
+/// [`AdaBoost::quantize`](crate::adaboost::AdaBoost::quantize).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct QuantizationError {
+    /// The largest change of a weight in absolute value.
+    pub max_error: f64,
+    /// The mean change of the weights in absolute value.
+    pub mean_error: f64,
+    /// The number of features whose weight was rounded to 0 and which are dropped.
+    pub features_zeroed: usize,
+}
+
+/// Rounds a weight to the nearest half-precision float, ties to even. Weights beyond the
+/// range of half-precision floats are clamped to its largest finite value.
+pub(crate) fn round_f16(weight: f64) -> f64 {
+    if weight == 0.0 || !weight.is_finite() {
+        return weight;
+    }
+    let sign = if weight < 0.0 { -1.0 } else { 1.0 };
+    if weight.abs() >= F16_MAX {
+        return sign * F16_MAX;
+    }
+    let bits = weight.abs().to_bits();
+    let exponent = (bits >> F64_SIGNIFICAND_BITS) as i32 - 1023;
+    // The bits of the f64 significand below the precision of an f16 of this magnitude,
+    // which has fewer bits when it is subnormal.
+    let dropped =
+        F64_SIGNIFICAND_BITS - F16_SIGNIFICAND_BITS + (F16_MIN_EXPONENT - exponent).max(0);
+    if dropped > F64_SIGNIFICAND_BITS {
+        // Values above half of the smallest f16 round up to it.
+        return if weight.abs() > F16_MIN_POSITIVE / 2.0 {
+            sign * F16_MIN_POSITIVE
+        } else {
+            0.0
+        };
+    }
+    // Rounding the significand may carry into the exponent, which is still correct.
+    let unit = 1u64 << dropped;
+    let rest = bits & (unit - 1);
+    let mut rounded = bits - rest;
+    if rest > unit / 2 || (rest == unit / 2 && rounded & unit != 0) {
+        rounded += unit;
+    }
+    sign * f64::from_bits(rounded)
+}
+
+/// Formats a half-precision weight with the fewest significant digits that read back as
+/// the same half-precision float.
+#[cfg(feature = "std")]
+pub(crate) fn format_f16(weight: f64) -> String {
+    // Five significant digits distinguish any two half-precision floats.
+    for digits in 1..=5 {
+        let short = format!("{:.*e}", digits - 1, weight);
+        if let Ok(value) = short.parse::<f64>() {
+            if round_f16(value) == weight {
+                return value.to_string();
+            }
+        }
+    }
+    weight.to_string()
+}
+
+/// Returns the scale of i8 weights whose largest absolute value is `max_abs`.
+#[cfg(feature = "std")]
+pub(crate) fn i8_scale(max_abs: f64) -> f64 {
+    if max_abs > 0.0 { max_abs / I8_MAX } else { 1.0 }
+}
+
+/// Returns the i8 weight nearest to `weight`, in units of `scale`.
+#[cfg(feature = "std")]
+pub(crate) fn to_i8(weight: f64, scale: f64) -> i8 {
+    (weight / scale).round().clamp(-I8_MAX, I8_MAX) as i8
+}
+
+/// Rounds a weight to the nearest multiple of `scale`.
+#[cfg(feature = "std")]
+pub(crate) fn round_i8(weight: f64, scale: f64) -> f64 {
+    f64::from(to_i8(weight, scale)) * scale
+}
+
+/// Parses the `quantization` metadata entry of a model.
+///
+/// # Returns
+/// The quantization and the factor to multiply the weights by when the model is loaded:
+/// the scale of i8 weights, and 1 for f16 weights.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the entry names an unknown quantization or
+/// the scale of i8 weights is missing or not a positive number.
+pub(crate) fn parse_entry(entry: &str) -> Result<(Quantization, f64), LitseaError> {
+    let (name, scale) = entry.split_once(' ').unwrap_or((entry, ""));
+    match name.parse().map_err(LitseaError::ModelFormat)? {
+        Quantization::F16 => Ok((Quantization::F16, 1.0)),
+        Quantization::I8 => match scale.trim().parse::<f64>() {
+            Ok(scale) if scale > 0.0 && scale.is_finite() => Ok((Quantization::I8, scale)),
+            _ => Err(LitseaError::ModelFormat(format!(
+                "Invalid scale of i8 weights: '{}'",
+                scale.trim()
+            ))),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        for quantization in [Quantization::F16, Quantization::I8] {
+            assert_eq!(quantization.to_string().parse::<Quantization>().unwrap(), quantization);
+        }
+        assert_eq!("F16".parse::<Quantization>().unwrap(), Quantization::F16);
+        assert!("i4".parse::<Quantization>().is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_round_f16() {
+        // Values of half-precision floats are kept.
+        assert_eq!(F16_MIN_POSITIVE, 2f64.powi(-24));
+        for value in [1.0, -0.5, 0.0999755859375, 65504.0, F16_MIN_POSITIVE, 0.0] {
+            assert_eq!(round_f16(value), value);
+        }
+        // 1 + 2^-11 is halfway between 1 and 1 + 2^-10, and rounds to the even one.
+        assert_eq!(round_f16(1.0 + 2f64.powi(-11)), 1.0);
+        assert_eq!(round_f16(0.1), 0.0999755859375);
+        assert_eq!(round_f16(1e6), 65504.0);
+        assert_eq!(round_f16(-1e-9), 0.0);
+        // Subnormal half-precision floats are multiples of 2^-24.
+        assert_eq!(round_f16(3.2 * F16_MIN_POSITIVE), 3.0 * F16_MIN_POSITIVE);
+        assert_eq!(round_f16(0.6 * F16_MIN_POSITIVE), F16_MIN_POSITIVE);
+        assert_eq!(round_f16(0.5 * F16_MIN_POSITIVE), 0.0);
+        // Rounding up may carry into the exponent.
+        assert_eq!(round_f16(2047.9), 2048.0);
+
+        assert_eq!(format_f16(round_f16(0.1)), "0.1");
+        assert_eq!(format_f16(round_f16(-0.123456)), "-0.1235");
+        assert_eq!(format_f16(2.0), "2");
+        for value in [0.3333, -1234.5, 2f64.powi(-20)] {
+            let rounded = round_f16(value);
+            assert_eq!(round_f16(format_f16(rounded).parse().unwrap()), rounded);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_round_i8() {
+        let scale = i8_scale(31.75);
+        assert_eq!(scale, 0.25);
+        assert_eq!(round_i8(31.75, scale), 31.75);
+        assert_eq!(round_i8(-0.3, scale), -0.25);
+        assert_eq!(round_i8(0.1, scale), 0.0);
+        assert_eq!(round_i8(100.0, scale), 31.75);
+        assert_eq!(to_i8(-100.0, scale), -127);
+        assert_eq!(i8_scale(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_parse_entry() {
+        assert_eq!(parse_entry("f16").unwrap(), (Quantization::F16, 1.0));
+        assert_eq!(parse_entry("i8 0.25").unwrap(), (Quantization::I8, 0.25));
+        assert!(parse_entry("i8").is_err());
+        assert!(parse_entry("i8 -1").is_err());
+        assert!(parse_entry("i4 1").is_err());
+    }
+}
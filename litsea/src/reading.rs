@@ -0,0 +1,241 @@
+//! Kana reading (yomi) estimation for segmented tokens.
+//!
+//! Readings come from a user-provided dictionary of word readings. Words that are not in
+//! the dictionary are read by a character-level fallback: the longest dictionary entry
+//! matching at each position is used, single-character entries (e.g. the usual reading
+//! of each kanji) fill the gaps, hiragana is converted to katakana, and any other
+//! character is kept as is. Readings are written in katakana, as MeCab does.
+//!
+//! The dictionary is a TSV file with one `surface<TAB>reading` entry per line; empty
+//! lines and lines starting with `#` are ignored.
+//!
+//! ```
+//! use litsea::reading::ReadingDictionary;
+//!
+//! let dictionary = ReadingDictionary::from_tsv("東京\tとうきょう\n都\tと\n".as_bytes()).unwrap();
+//! assert_eq!(dictionary.reading("東京都"), "トウキョウト");
+//! assert_eq!(dictionary.reading("ですよ"), "デスヨ");
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::error::LitseaError;
+use crate::token::{Token, TokenFilter};
+
+/// A dictionary of word readings with a character-level fallback.
+#[derive(Debug, Clone, Default)]
+pub struct ReadingDictionary {
+    entries: HashMap<String, String>,
+    /// Length in characters of the longest entry, bounding the longest-match search.
+    max_chars: usize,
+}
+
+impl ReadingDictionary {
+    /// Creates an empty dictionary, which only reads kana.
+    ///
+    /// # Returns
+    /// A new, empty [`ReadingDictionary`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a dictionary from TSV lines of `surface<TAB>reading`.
+    ///
+    /// # Arguments
+    /// * `reader` - The TSV content.
+    ///
+    /// # Returns
+    /// The dictionary. Later entries for the same surface replace earlier ones.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if reading fails, or [`LitseaError::Parse`] if a line
+    /// does not have two columns.
+    pub fn from_tsv<R: BufRead>(reader: R) -> crate::error::Result<Self> {
+        let mut dictionary = Self::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (surface, reading) = line
+                .split_once('\t')
+                .filter(|(surface, reading)| !surface.is_empty() && !reading.is_empty())
+                .ok_or_else(|| LitseaError::Parse {
+                    line: i + 1,
+                    reason: format!("Invalid reading entry: '{}'", line),
+                })?;
+            // Extra columns are ignored, so lexicons with more fields can be used as is.
+            let reading = reading.split('\t').next().unwrap_or(reading);
+            dictionary.insert(surface, reading);
+        }
+        Ok(dictionary)
+    }
+
+    /// Loads a dictionary from a TSV file. See [`from_tsv`](Self::from_tsv).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the TSV file.
+    ///
+    /// # Returns
+    /// The loaded dictionary.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        Self::from_tsv(BufReader::new(File::open(path)?))
+    }
+
+    /// Adds an entry, converting its reading to katakana.
+    ///
+    /// # Arguments
+    /// * `surface` - The written form of the word.
+    /// * `reading` - Its reading in hiragana or katakana.
+    pub fn insert(&mut self, surface: &str, reading: &str) {
+        self.max_chars = self.max_chars.max(surface.chars().count());
+        self.entries.insert(surface.to_string(), to_katakana(reading));
+    }
+
+    /// Returns the number of entries in the dictionary.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the dictionary has no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Estimates the reading of a word.
+    ///
+    /// # Arguments
+    /// * `surface` - The word to read.
+    ///
+    /// # Returns
+    /// The reading in katakana: the dictionary entry for the whole word if there is one,
+    /// and the character-level fallback otherwise.
+    #[must_use]
+    pub fn reading(&self, surface: &str) -> String {
+        if let Some(reading) = self.entries.get(surface) {
+            return reading.clone();
+        }
+        let chars: Vec<(usize, char)> = surface.char_indices().collect();
+        let mut out = String::with_capacity(surface.len());
+        let mut i = 0;
+        while i < chars.len() {
+            // Longest dictionary match starting at this character.
+            let longest = (2..=self.max_chars.min(chars.len() - i)).rev().find_map(|n| {
+                let end = chars.get(i + n).map_or(surface.len(), |&(pos, _)| pos);
+                self.entries.get(&surface[chars[i].0..end]).map(|reading| (n, reading))
+            });
+            match longest {
+                Some((n, reading)) => {
+                    out.push_str(reading);
+                    i += n;
+                }
+                None => {
+                    let ch = chars[i].1;
+                    let mut buf = [0; 4];
+                    match self.entries.get(&*ch.encode_utf8(&mut buf)) {
+                        Some(reading) => out.push_str(reading),
+                        None => out.push(hiragana_to_katakana(ch)),
+                    }
+                    i += 1;
+                }
+            }
+        }
+        out
+    }
+
+    /// Attaches a reading to every token.
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to annotate.
+    pub fn annotate(&self, tokens: &mut [Token]) {
+        for token in tokens {
+            token.reading = Some(self.reading(&token.surface));
+        }
+    }
+}
+
+impl TokenFilter for ReadingDictionary {
+    fn apply(&self, tokens: &mut Vec<Token>) {
+        self.annotate(tokens);
+    }
+}
+
+/// Converts a hiragana character to katakana, leaving other characters unchanged.
+fn hiragana_to_katakana(ch: char) -> char {
+    match ch {
+        // ぁ..ゖ and the iteration marks ゝゞ map to ァ..ヶ and ヽヾ.
+        '\u{3041}'..='\u{3096}' | '\u{309D}'..='\u{309E}' => {
+            char::from_u32(ch as u32 + 0x60).unwrap_or(ch)
+        }
+        _ => ch,
+    }
+}
+
+/// Converts the hiragana in a string to katakana.
+fn to_katakana(s: &str) -> String {
+    s.chars().map(hiragana_to_katakana).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::rwcp_segmenter;
+
+    fn dictionary() -> ReadingDictionary {
+        let tsv =
+            "# surface\treading\n東京\tとうきょう\n東京都\tとうきょうと\n都\tと\n今日\tキョウ\n\n";
+        ReadingDictionary::from_tsv(tsv.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_from_tsv() {
+        let dictionary = dictionary();
+        assert_eq!(dictionary.len(), 4);
+        assert!(!dictionary.is_empty());
+        assert!(ReadingDictionary::new().is_empty());
+    }
+
+    #[test]
+    fn test_from_tsv_invalid() {
+        let err = ReadingDictionary::from_tsv("東京\tとうきょう\n京都\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 2, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_reading_exact_match() {
+        assert_eq!(dictionary().reading("東京都"), "トウキョウト");
+        assert_eq!(dictionary().reading("今日"), "キョウ");
+    }
+
+    #[test]
+    fn test_reading_fallback() {
+        let dictionary = dictionary();
+        // Longest match, then single-character entries and kana.
+        assert_eq!(dictionary.reading("東京都へ"), "トウキョウトヘ");
+        assert_eq!(dictionary.reading("今日は"), "キョウハ");
+        // Unknown kanji and Latin letters are kept as is.
+        assert_eq!(dictionary.reading("京ABC"), "京ABC");
+        assert_eq!(dictionary.reading("ゝカナ"), "ヽカナ");
+    }
+
+    #[test]
+    fn test_annotate() {
+        let segmenter = rwcp_segmenter();
+
+        let mut tokens = segmenter.tokenize("今日はテストです。");
+        dictionary().annotate(&mut tokens);
+        let readings: Vec<&str> = tokens.iter().map(|t| t.reading.as_deref().unwrap()).collect();
+        assert_eq!(readings.concat(), "キョウハテストデス。");
+    }
+}
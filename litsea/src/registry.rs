@@ -0,0 +1,421 @@
+//! Fetching of published pretrained models from a model registry.
+//!
+//! A registry is a directory served over HTTP(S) or on the local file system
+//! (`file://`) that contains an `index.tsv` file. Each non-empty line of the index that
+//! does not start with `#` describes one model version with five tab-separated fields:
+//!
+//! ```text
+//! name  version  language  sha256  url
+//! japanese  0.4.0  japanese  015abb53...  https://example.com/models/japanese.model
+//! ```
+//!
+//! A relative `url` is resolved against the registry URL. Fetched models are verified
+//! against their SHA-256 checksum and stored in a local cache, so later fetches of the
+//! same version do not download the model again.
+
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::LitseaError;
+use crate::language::Language;
+use crate::util::sha256_hex;
+
+/// Registry used when none is configured.
+pub const DEFAULT_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/mosuka/litsea/main/registry";
+
+/// Environment variable overriding the registry URL.
+pub const REGISTRY_ENV: &str = "LITSEA_REGISTRY";
+
+/// Environment variable overriding the cache directory.
+pub const CACHE_DIR_ENV: &str = "LITSEA_CACHE_DIR";
+
+/// Name of the index file in a registry.
+const INDEX_FILE: &str = "index.tsv";
+
+fn invalid_index(line: usize, message: &str) -> LitseaError {
+    LitseaError::Parse {
+        line,
+        reason: format!("Invalid registry index: {}", message),
+    }
+}
+
+/// Returns whether a name or version is safe to use as part of a cache file name.
+fn is_valid_component(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('.')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+/// Compares two versions component by component, numerically where both components are
+/// numbers (so that `0.10.0` is newer than `0.9.0`).
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split(['.', '-']);
+    let mut b_parts = b.split(['.', '-']);
+    loop {
+        match (a_parts.next(), b_parts.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    _ => x.cmp(y),
+                };
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+        }
+    }
+}
+
+/// A model version listed in a registry index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegistryEntry {
+    /// Name of the model.
+    pub name: String,
+    /// Version of the model.
+    pub version: String,
+    /// Language the model was trained for.
+    pub language: Language,
+    /// Expected SHA-256 checksum of the model file, in lowercase hexadecimal.
+    pub sha256: String,
+    /// Absolute URL of the model file.
+    pub url: String,
+}
+
+/// A model fetched into the local cache.
+#[derive(Debug, Clone)]
+pub struct FetchedModel {
+    /// The registry entry of the model.
+    pub entry: RegistryEntry,
+    /// Path of the verified model file in the cache.
+    pub path: PathBuf,
+    /// Whether the model was downloaded, rather than found in the cache.
+    pub downloaded: bool,
+}
+
+/// A model registry together with the local cache that fetched models are stored in.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    url: String,
+    cache_dir: PathBuf,
+}
+
+impl Registry {
+    /// Creates a registry client.
+    ///
+    /// # Arguments
+    /// * `url` - The registry URL (`http://`, `https://` or `file://`).
+    /// * `cache_dir` - The directory that fetched models are stored in.
+    #[must_use]
+    pub fn new(url: &str, cache_dir: PathBuf) -> Self {
+        Registry {
+            url: url.trim_end_matches('/').to_string(),
+            cache_dir,
+        }
+    }
+
+    /// Creates a registry client configured from the environment.
+    ///
+    /// The registry URL is taken from `LITSEA_REGISTRY`, falling back to
+    /// [`DEFAULT_REGISTRY_URL`]. The cache directory is taken from `LITSEA_CACHE_DIR`,
+    /// falling back to `$XDG_CACHE_HOME/litsea` and then `$HOME/.cache/litsea`.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if no cache directory can be determined.
+    pub fn from_env() -> crate::error::Result<Self> {
+        let url = env::var(REGISTRY_ENV).unwrap_or_else(|_| DEFAULT_REGISTRY_URL.to_string());
+        Ok(Registry::new(&url, default_cache_dir()?))
+    }
+
+    /// Returns the registry URL.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the cache directory.
+    #[must_use]
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Resolves a URL relative to the registry.
+    fn resolve(&self, url: &str) -> String {
+        if url.contains("://") {
+            url.to_string()
+        } else {
+            format!("{}/{}", self.url, url)
+        }
+    }
+
+    /// Reads a file from the registry.
+    async fn read(&self, url: &str) -> io::Result<Vec<u8>> {
+        if let Some(path) = url.strip_prefix("file://") {
+            return fs::read(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {}: {}", url, e)));
+        }
+        #[cfg(feature = "remote_model")]
+        {
+            crate::util::download(url).await
+        }
+        #[cfg(not(feature = "remote_model"))]
+        {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "Cannot fetch {}: http:// and https:// are not supported in this build",
+                    url
+                ),
+            ))
+        }
+    }
+
+    /// Downloads and parses the registry index.
+    ///
+    /// # Returns
+    /// All model versions listed in the index, in index order.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] if the index cannot be downloaded or is not UTF-8, or
+    /// [`LitseaError::Parse`] if a line is malformed.
+    pub async fn index(&self) -> crate::error::Result<Vec<RegistryEntry>> {
+        let content = self.read(&self.resolve(INDEX_FILE)).await?;
+        let content = String::from_utf8(content).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "Registry index is not UTF-8")
+        })?;
+
+        let mut entries = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, version, language, sha256, url] = fields[..] else {
+                return Err(invalid_index(i + 1, "expected 5 tab-separated fields"));
+            };
+            if !is_valid_component(name) || !is_valid_component(version) {
+                return Err(invalid_index(i + 1, "invalid name or version"));
+            }
+            let language: Language =
+                language.parse().map_err(|e: String| invalid_index(i + 1, &e))?;
+            if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(invalid_index(i + 1, "invalid SHA-256 checksum"));
+            }
+            entries.push(RegistryEntry {
+                name: name.to_string(),
+                version: version.to_string(),
+                language,
+                sha256: sha256.to_ascii_lowercase(),
+                url: self.resolve(url),
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Finds a model version in the registry index.
+    ///
+    /// # Arguments
+    /// * `spec` - A model name, optionally pinned to a version as `name@version`.
+    ///   Without a version, the newest version is used.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Io`] with [`io::ErrorKind::NotFound`] if the registry has no
+    /// such model, or the error of [`index`](Self::index).
+    pub async fn resolve_spec(&self, spec: &str) -> crate::error::Result<RegistryEntry> {
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version)),
+            None => (spec, None),
+        };
+        self.index()
+            .await?
+            .into_iter()
+            .filter(|e| e.name == name && version.is_none_or(|v| e.version == v))
+            .max_by(|a, b| compare_versions(&a.version, &b.version))
+            .ok_or_else(|| {
+                LitseaError::Io(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("Model not found in registry: {}", spec),
+                ))
+            })
+    }
+
+    /// Fetches a model into the cache, downloading it only if no verified copy is cached.
+    ///
+    /// # Arguments
+    /// * `spec` - A model name, optionally pinned to a version as `name@version`.
+    ///
+    /// # Returns
+    /// The fetched model and its path in the cache.
+    ///
+    /// # Errors
+    /// Returns the error of [`resolve_spec`](Self::resolve_spec) if the model is not in the
+    /// registry, [`LitseaError::Io`] if it cannot be downloaded or cached, or
+    /// [`LitseaError::ModelFormat`] if its checksum does not match the index.
+    pub async fn fetch(&self, spec: &str) -> crate::error::Result<FetchedModel> {
+        let entry = self.resolve_spec(spec).await?;
+        let path = self.cache_dir.join(&entry.name).join(format!("{}.model", entry.version));
+
+        // A cached copy is only used if it still matches the checksum in the index.
+        if fs::read(&path).is_ok_and(|cached| sha256_hex(&cached) == entry.sha256) {
+            return Ok(FetchedModel {
+                entry,
+                path,
+                downloaded: false,
+            });
+        }
+
+        let content = self.read(&entry.url).await?;
+        let actual = sha256_hex(&content);
+        if actual != entry.sha256 {
+            return Err(LitseaError::ModelFormat(format!(
+                "Checksum mismatch for {}@{}: expected {}, got {}",
+                entry.name, entry.version, entry.sha256, actual
+            )));
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first so an interrupted fetch never leaves a partial model.
+        let tmp = path.with_extension("model.tmp");
+        fs::write(&tmp, &content)?;
+        fs::rename(&tmp, &path)?;
+        Ok(FetchedModel {
+            entry,
+            path,
+            downloaded: true,
+        })
+    }
+}
+
+/// Returns the default cache directory for fetched models.
+///
+/// # Errors
+/// Returns [`LitseaError::Io`] with [`io::ErrorKind::NotFound`] if none of
+/// `LITSEA_CACHE_DIR`, `XDG_CACHE_HOME` and `HOME` is set.
+pub fn default_cache_dir() -> crate::error::Result<PathBuf> {
+    if let Some(dir) = env::var_os(CACHE_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CACHE_HOME") {
+        return Ok(PathBuf::from(dir).join("litsea"));
+    }
+    if let Some(home) = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        return Ok(PathBuf::from(home).join(".cache").join("litsea"));
+    }
+    Err(LitseaError::Io(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("Cannot determine the cache directory; set {}", CACHE_DIR_ENV),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a registry with two versions of a model.
+    fn test_registry(dir: &Path) -> Registry {
+        let registry_dir = dir.join("registry");
+        fs::create_dir_all(&registry_dir).unwrap();
+        let v1 = b"UW4:a\t1\n-0.5\n";
+        let v2 = b"UW4:b\t2\n-1\n";
+        fs::write(registry_dir.join("v1.model"), v1).unwrap();
+        fs::write(registry_dir.join("v2.model"), v2).unwrap();
+        let index = format!(
+            "# name\tversion\tlanguage\tsha256\turl\n\
+             test\t0.9.0\tja\t{}\tv1.model\n\
+             test\t0.10.0\tjapanese\t{}\tv2.model\n\
+             broken\t1\tja\t{}\tv2.model\n",
+            sha256_hex(v1),
+            sha256_hex(v2),
+            sha256_hex(v1),
+        );
+        fs::write(registry_dir.join(INDEX_FILE), index).unwrap();
+        Registry::new(&format!("file://{}/", registry_dir.display()), dir.join("cache"))
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        assert_eq!(compare_versions("0.10.0", "0.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("1.0", "1.0.1"), Ordering::Less);
+        assert_eq!(compare_versions("2", "2"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_is_valid_component() {
+        assert!(is_valid_component("japanese-news_v3.1"));
+        assert!(!is_valid_component("../etc"));
+        assert!(!is_valid_component("a/b"));
+        assert!(!is_valid_component(""));
+    }
+
+    #[test]
+    fn test_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = test_registry(dir.path());
+        let entries = tokio_test::block_on(registry.index()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].language, Language::Japanese);
+        assert!(entries[0].url.starts_with("file://"));
+        assert!(entries[0].url.ends_with("/registry/v1.model"));
+
+        fs::write(dir.path().join("registry").join(INDEX_FILE), "test\t1\tja\n").unwrap();
+        let err = tokio_test::block_on(registry.index()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 1, .. }), "{}", err);
+    }
+
+    #[test]
+    fn test_fetch() {
+        let dir = tempfile::tempdir().unwrap();
+        let registry = test_registry(dir.path());
+
+        // The newest version is chosen by default.
+        let fetched = tokio_test::block_on(registry.fetch("test")).unwrap();
+        assert_eq!(fetched.entry.version, "0.10.0");
+        assert!(fetched.downloaded);
+        assert_eq!(fs::read(&fetched.path).unwrap(), b"UW4:b\t2\n-1\n");
+
+        // A second fetch is served from the cache.
+        let fetched = tokio_test::block_on(registry.fetch("test@0.10.0")).unwrap();
+        assert!(!fetched.downloaded);
+
+        let fetched = tokio_test::block_on(registry.fetch("test@0.9.0")).unwrap();
+        assert_eq!(fetched.entry.version, "0.9.0");
+        assert_eq!(fs::read(&fetched.path).unwrap(), b"UW4:a\t1\n-0.5\n");
+
+        // A corrupted cache entry is downloaded again.
+        fs::write(&fetched.path, b"garbage").unwrap();
+        let fetched = tokio_test::block_on(registry.fetch("test@0.9.0")).unwrap();
+        assert!(fetched.downloaded);
+
+        let err = tokio_test::block_on(registry.fetch("test@1.0.0")).unwrap_err();
+        assert!(
+            matches!(&err, LitseaError::Io(e) if e.kind() == io::ErrorKind::NotFound),
+            "{}",
+            err
+        );
+
+        let err = tokio_test::block_on(registry.fetch("broken")).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)), "{}", err);
+        assert!(!dir.path().join("cache").join("broken").join("1.model").exists());
+    }
+
+    #[test]
+    fn test_bundled_registry() {
+        // The registry published with the repository must match the bundled models.
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("file://{}/../registry", env!("CARGO_MANIFEST_DIR"));
+        let registry = Registry::new(&url, dir.path().to_path_buf());
+        for entry in tokio_test::block_on(registry.index()).unwrap() {
+            let spec = format!("{}@{}", entry.name, entry.version);
+            tokio_test::block_on(registry.fetch(&spec)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! Validation and repair of hand-edited model files.
+//!
+//! [`AdaBoost::load_model`](crate::adaboost::AdaBoost::load_model) is strict: it stops at
+//! the first line it cannot parse, and it silently misreads a model whose bias line is
+//! missing or whose features are listed twice. [`repair_model`] reads a model file
+//! leniently instead, fixes what it can and reports every change, so a model that was
+//! edited by hand or by other tools can be checked and rewritten in the form
+//! [`AdaBoost::save_model`](crate::adaboost::AdaBoost::save_model) writes:
+//!
+//! - Windows (`\r\n`) and classic Mac (`\r`) line endings are converted to `\n`.
+//! - Blank lines are removed.
+//! - Weights of a feature listed more than once are added up.
+//! - Weights that are not numbers, or are infinite or NaN, are dropped.
+//! - A missing bias line is recomputed so that the model has no bias of its own, which is
+//!   how the rest of the model would be read without it.
+//! - A stale or misplaced integrity header is replaced by a fresh checksum.
+//!
+//! The repaired model lists its metadata and then its nonzero weights, sorted by name,
+//! followed by the bias and preceded by a checksum header. The weights of a
+//! [quantized](crate::quantize) model stay quantized with the same scale.
+//!
+//! ```
+//! use litsea::repair::repair_model;
+//!
+//! let model = "UW4:は\t0.5\r\nUW4:は\t0.25\nUW4:が\tNaN\n";
+//! let (repaired, report) = repair_model(model.as_bytes()).unwrap();
+//! assert_eq!(report.issues.len(), 4);
+//!
+//! let body = String::from_utf8(repaired).unwrap();
+//! assert!(body.ends_with("UW4:は\t0.75\n-0.375\n"));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::integrity::{self, HEADER_PREFIX};
+use crate::model_info;
+use crate::quantize;
+
+/// A problem found in a model file, and how [`repair_model`] dealt with it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Lines ended with `\r\n` or `\r`; they now end with `\n`.
+    LineEndings { crlf: usize, cr: usize },
+    /// The checksum in the header did not match the content; a new one was written.
+    ChecksumMismatch,
+    /// The header carried a signature, which no longer matches the repaired content.
+    SignatureRemoved,
+    /// An integrity header appeared after the first line and was removed.
+    MisplacedHeader { line: usize },
+    /// A blank line was removed.
+    BlankLine { line: usize },
+    /// A metadata line with an invalid key was removed.
+    InvalidMetadata { line: usize },
+    /// A line whose weight or bias is not a number was removed.
+    InvalidNumber { line: usize, value: String },
+    /// A line with more than two columns; only the feature and its weight were kept.
+    ExtraColumns { line: usize },
+    /// A weight that is infinite or NaN was removed.
+    NonFiniteWeight { line: usize, feature: String },
+    /// A feature already listed earlier; its weight was added to the earlier one.
+    DuplicateFeature { line: usize, feature: String },
+    /// A bias line followed by another; the last one was kept.
+    DuplicateBias { line: usize },
+    /// A bias line that is not the last line; it was moved to the end.
+    BiasNotLast { line: usize },
+    /// A bias that is infinite or NaN was removed.
+    NonFiniteBias { line: usize },
+    /// The model had no bias line; this bias was computed from the weights.
+    MissingBias { bias: f64 },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::LineEndings { crlf, cr } => {
+                write!(f, "converted line endings to LF ({} CRLF, {} CR)", crlf, cr)
+            }
+            Issue::ChecksumMismatch => {
+                write!(f, "checksum in the header does not match; wrote a new checksum")
+            }
+            Issue::SignatureRemoved => {
+                write!(f, "removed the signature, which does not cover the repaired model")
+            }
+            Issue::MisplacedHeader { line } => write!(f, "line {}: removed misplaced header", line),
+            Issue::BlankLine { line } => write!(f, "line {}: removed blank line", line),
+            Issue::InvalidMetadata { line } => {
+                write!(f, "line {}: removed metadata with an invalid key", line)
+            }
+            Issue::InvalidNumber { line, value } => {
+                write!(f, "line {}: removed invalid number '{}'", line, value)
+            }
+            Issue::ExtraColumns { line } => {
+                write!(f, "line {}: ignored columns after the weight", line)
+            }
+            Issue::NonFiniteWeight { line, feature } => {
+                write!(f, "line {}: removed non-finite weight of {}", line, feature)
+            }
+            Issue::DuplicateFeature { line, feature } => {
+                write!(f, "line {}: added duplicate weight of {} to the earlier one", line, feature)
+            }
+            Issue::DuplicateBias { line } => {
+                write!(f, "line {}: replaced by a later bias line", line)
+            }
+            Issue::BiasNotLast { line } => write!(f, "line {}: moved bias to the end", line),
+            Issue::NonFiniteBias { line } => write!(f, "line {}: removed non-finite bias", line),
+            Issue::MissingBias { bias } => write!(f, "added missing bias line: {}", bias),
+        }
+    }
+}
+
+/// The result of checking a model file with [`repair_model`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    /// The problems found, in the order of the lines they were found on.
+    pub issues: Vec<Issue>,
+    /// Number of features in the repaired model.
+    pub features: usize,
+    /// Whether the repaired model differs from the input, including changes that are
+    /// not problems, such as sorting the features or adding a checksum.
+    pub changed: bool,
+}
+
+impl RepairReport {
+    /// Returns whether no problems were found.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+fn invalid_model(message: String) -> LitseaError {
+    LitseaError::ModelFormat(message)
+}
+
+/// Normalizes the line endings of `content` to `\n`.
+///
+/// # Returns
+/// The normalized content and the numbers of `\r\n` and lone `\r` line endings.
+fn normalize_line_endings(content: &str) -> (String, usize, usize) {
+    let mut out = String::with_capacity(content.len());
+    let (mut crlf, mut cr) = (0, 0);
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\r' {
+            if chars.peek() == Some(&'\n') {
+                chars.next();
+                crlf += 1;
+            } else {
+                cr += 1;
+            }
+            out.push('\n');
+        } else {
+            out.push(c);
+        }
+    }
+    (out, crlf, cr)
+}
+
+/// Checks a model file and repairs the problems listed in the [module documentation](self).
+///
+/// A model without problems is returned unchanged if it is already in normalized form,
+/// so its signature, if any, is kept.
+///
+/// # Arguments
+/// * `content` - The bytes of the model file.
+///
+/// # Returns
+/// The repaired model file and a report of the problems found.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the file is not valid UTF-8 or contains no
+/// weights and no bias, or has an invalid quantization entry.
+pub fn repair_model(content: &[u8]) -> crate::error::Result<(Vec<u8>, RepairReport)> {
+    let text = std::str::from_utf8(content)
+        .map_err(|e| invalid_model(format!("Model is not valid UTF-8: {}", e)))?;
+    let mut report = RepairReport::default();
+
+    let (text, crlf, cr) = normalize_line_endings(text);
+    if crlf + cr > 0 {
+        report.issues.push(Issue::LineEndings { crlf, cr });
+    }
+
+    let (header, body) = integrity::split_header(text.as_bytes());
+    // The checksum covers the bytes as they were, before converting the line endings.
+    if header.is_some() && integrity::check_checksum(content).is_err() {
+        report.issues.push(Issue::ChecksumMismatch);
+    }
+    let first_line = if header.is_some() { 2 } else { 1 };
+    // The header is a whole line, so the body is still valid UTF-8.
+    let body = std::str::from_utf8(body).map_err(|e| invalid_model(e.to_string()))?;
+
+    let mut learner = AdaBoost::new(0.01, 100);
+    let mut weights: Vec<(String, f64)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut bias: Option<(usize, f64)> = None;
+    let mut last_line = 0;
+
+    for (i, line) in body.lines().enumerate() {
+        let line_num = first_line + i;
+        if line.starts_with(HEADER_PREFIX) {
+            report.issues.push(Issue::MisplacedHeader { line: line_num });
+            continue;
+        }
+        if let Some(entry) = line.strip_prefix('#') {
+            let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+            if learner.set_metadata(key, value).is_err() {
+                report.issues.push(Issue::InvalidMetadata { line: line_num });
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let Some(h) = parts.next() else {
+            report.issues.push(Issue::BlankLine { line: line_num });
+            continue;
+        };
+        last_line = line_num;
+
+        let Some(v) = parts.next() else {
+            let Ok(b) = h.parse::<f64>() else {
+                report.issues.push(Issue::InvalidNumber {
+                    line: line_num,
+                    value: h.to_string(),
+                });
+                continue;
+            };
+            if !b.is_finite() {
+                report.issues.push(Issue::NonFiniteBias { line: line_num });
+                continue;
+            }
+            if let Some((previous, _)) = bias.replace((line_num, b)) {
+                report.issues.push(Issue::DuplicateBias { line: previous });
+            }
+            continue;
+        };
+        if parts.next().is_some() {
+            report.issues.push(Issue::ExtraColumns { line: line_num });
+        }
+        let Ok(w) = v.parse::<f64>() else {
+            report.issues.push(Issue::InvalidNumber {
+                line: line_num,
+                value: v.to_string(),
+            });
+            continue;
+        };
+        if !w.is_finite() {
+            report.issues.push(Issue::NonFiniteWeight {
+                line: line_num,
+                feature: h.to_string(),
+            });
+            continue;
+        }
+        match positions.get(h) {
+            Some(&index) => {
+                report.issues.push(Issue::DuplicateFeature {
+                    line: line_num,
+                    feature: h.to_string(),
+                });
+                weights[index].1 += w;
+            }
+            None => {
+                positions.insert(h.to_string(), weights.len());
+                weights.push((h.to_string(), w));
+            }
+        }
+    }
+
+    // i8 weights are written in units of the scale.
+    let quantization = learner
+        .metadata(model_info::QUANTIZATION_KEY)
+        .map(quantize::parse_entry)
+        .transpose()?;
+    if let Some((_, scale)) = quantization {
+        weights.iter_mut().for_each(|(_, w)| *w *= scale);
+    }
+
+    let bias = match bias {
+        Some((line, b)) => {
+            if line != last_line {
+                report.issues.push(Issue::BiasNotLast { line });
+            }
+            b
+        }
+        None if weights.is_empty() => {
+            return Err(invalid_model("Model has no weights and no bias".to_string()));
+        }
+        None => {
+            // Without a bias line, the bias bucket of the model is zero.
+            let b = -weights.iter().map(|(_, w)| w).sum::<f64>() / 2.0;
+            report.issues.push(Issue::MissingBias { bias: b });
+            b
+        }
+    };
+    report.features = weights.iter().filter(|(_, w)| *w != 0.0).count();
+    learner.set_weights(weights, bias);
+    if let Some((quantization, scale)) = quantization {
+        learner.set_quantization(quantization, scale);
+    }
+
+    let mut repaired = Vec::new();
+    learner.write_model(&mut repaired)?;
+    let (_, repaired_body) = integrity::split_header(&repaired);
+    let (_, original_body) = integrity::split_header(content);
+    if report.is_clean() && header.is_some() && repaired_body == original_body {
+        return Ok((content.to_vec(), report));
+    }
+    report.changed = repaired != content;
+    if header.is_some_and(|h| h.signature.is_some()) {
+        report.issues.push(Issue::SignatureRemoved);
+    }
+    Ok((repaired, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repair(model: &str) -> (String, RepairReport) {
+        let (repaired, report) = repair_model(model.as_bytes()).unwrap();
+        (String::from_utf8(repaired).unwrap(), report)
+    }
+
+    fn body(model: &str) -> &str {
+        let (_, body) = integrity::split_header(model.as_bytes());
+        std::str::from_utf8(body).unwrap()
+    }
+
+    #[test]
+    fn test_clean_model_unchanged() {
+        let model = String::from_utf8(integrity::add_header(
+            "#format 1\n#note x\nUW4:が\t0.25\n-0.5\n".as_bytes(),
+        ))
+        .unwrap();
+        let (repaired, report) = repair(&model);
+        assert!(report.is_clean());
+        assert!(!report.changed);
+        assert_eq!(report.features, 1);
+        assert_eq!(repaired, model);
+    }
+
+    #[test]
+    fn test_normalizes_without_issues() {
+        let (repaired, report) = repair("UW4:は 0.5\nUW4:が\t0.25\n-0.5\n");
+        assert!(report.is_clean());
+        assert!(report.changed);
+        assert_eq!(body(&repaired), "#format 1\nUW4:が\t0.25\nUW4:は\t0.5\n-0.5\n");
+        assert!(integrity::check_checksum(repaired.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_repairs() {
+        let model = "#note x\r\nUW4:は\t0.5\r\n\nUW4:が\tinf\rUW4:は\t0.25\tfoo\nUW4:の\tabc\n";
+        let (repaired, report) = repair(model);
+        assert_eq!(
+            report.issues,
+            vec![
+                Issue::LineEndings { crlf: 2, cr: 1 },
+                Issue::BlankLine { line: 3 },
+                Issue::NonFiniteWeight {
+                    line: 4,
+                    feature: "UW4:が".to_string()
+                },
+                Issue::ExtraColumns { line: 5 },
+                Issue::DuplicateFeature {
+                    line: 5,
+                    feature: "UW4:は".to_string()
+                },
+                Issue::InvalidNumber {
+                    line: 6,
+                    value: "abc".to_string()
+                },
+                Issue::MissingBias { bias: -0.375 },
+            ]
+        );
+        assert_eq!(body(&repaired), "#format 1\n#note x\nUW4:は\t0.75\n-0.375\n");
+
+        // The repaired model loads with the weights and bias that were meant.
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(&repaired).unwrap();
+        assert_eq!(learner.get_bias(), -0.375);
+        assert_eq!(learner.weights().collect::<Vec<_>>(), vec![("UW4:は", 0.75)]);
+    }
+
+    #[test]
+    fn test_bias_lines() {
+        let (repaired, report) = repair("0.1\nUW4:は\t0.5\n-0.25\nUW4:が\t0.125\n");
+        assert_eq!(
+            report.issues,
+            vec![Issue::DuplicateBias { line: 1 }, Issue::BiasNotLast { line: 3 }]
+        );
+        assert_eq!(body(&repaired), "#format 1\nUW4:が\t0.125\nUW4:は\t0.5\n-0.25\n");
+    }
+
+    #[test]
+    fn test_header_issues() {
+        let model = "#litsea-model sha256=00 ed25519=00\nUW4:は\t0.5\n-0.25\n";
+        let (repaired, report) = repair(model);
+        assert_eq!(report.issues, vec![Issue::ChecksumMismatch, Issue::SignatureRemoved]);
+        assert!(integrity::check_checksum(repaired.as_bytes()).is_ok());
+
+        let (_, report) = repair("UW4:は\t0.5\n#litsea-model sha256=00\n-0.25\n");
+        assert_eq!(report.issues, vec![Issue::MisplacedHeader { line: 2 }]);
+    }
+
+    #[test]
+    fn test_quantized_model() {
+        let model = String::from_utf8(integrity::add_header(
+            "#format 2\n#quantization i8 0.25\nUW4:が\t-3\nUW4:は\t127\n-0.5\n".as_bytes(),
+        ))
+        .unwrap();
+        let (repaired, report) = repair(&model);
+        assert!(report.is_clean());
+        assert_eq!(repaired, model);
+
+        // Duplicate i8 weights are added up in units of the scale.
+        let (repaired, _) = repair("#quantization i8 0.25\nUW4:は\t2\nUW4:は\t2\n-0.5\n");
+        assert_eq!(body(&repaired), "#format 2\n#quantization i8 0.25\nUW4:は\t4\n-0.5\n");
+        assert!(matches!(
+            repair_model(b"#quantization i8\nUW4:a\t2\n-0.5\n"),
+            Err(LitseaError::ModelFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(repair_model(b"\n#note x\n"), Err(LitseaError::ModelFormat(_))));
+        assert!(matches!(repair_model(b"UW4:\xff\t0.5\n"), Err(LitseaError::ModelFormat(_))));
+    }
+}
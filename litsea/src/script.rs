@@ -0,0 +1,518 @@
+//! Script detection and routing for mixed-language text.
+//!
+//! A [`ScriptRouter`] detects the writing system of each sentence, or of each run of one
+//! script within a sentence, and sends it to the segmenter configured for that script.
+//! Scripts without a segmenter are passed through and split on whitespace only, which is
+//! right for Latin text. This lets collections that mix Japanese, Korean, Thai and English
+//! documents be segmented in one pass.
+//!
+//! Routes are configured in code or with a model map, a file with one
+//! `script language model_uri` or `script passthrough` line per script:
+//!
+//! ```text
+//! # script  language  model
+//! kana      japanese  ./resources/japanese.model
+//! han       japanese  ./resources/japanese.model
+//! hangul    korean    ./resources/korean.model
+//! latin     passthrough
+//! ```
+//!
+//! ```
+//! use litsea::script::Script;
+//!
+//! assert_eq!(Script::detect("これはテストです。"), Script::Kana);
+//! assert_eq!(Script::detect("我喜欢学习"), Script::Han);
+//! assert_eq!(Script::detect("한국어 문장입니다"), Script::Hangul);
+//! assert_eq!(Script::detect("This is a pen."), Script::Latin);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::language::Language;
+use crate::segmenter::Segmenter;
+use crate::token::{Token, set_char_offsets};
+
+/// A writing system that text is routed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    /// Japanese: hiragana or katakana, usually mixed with kanji.
+    Kana,
+    /// Han characters without kana, as in Chinese.
+    Han,
+    /// Korean Hangul.
+    Hangul,
+    /// Thai.
+    Thai,
+    /// Latin letters, as in English.
+    Latin,
+    /// Any other letters, or text without letters.
+    Other,
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Script::Kana => write!(f, "kana"),
+            Script::Han => write!(f, "han"),
+            Script::Hangul => write!(f, "hangul"),
+            Script::Thai => write!(f, "thai"),
+            Script::Latin => write!(f, "latin"),
+            Script::Other => write!(f, "other"),
+        }
+    }
+}
+
+impl FromStr for Script {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "kana" | "japanese" | "ja" => Ok(Script::Kana),
+            "han" | "chinese" | "zh" => Ok(Script::Han),
+            "hangul" | "korean" | "ko" => Ok(Script::Hangul),
+            "thai" | "th" => Ok(Script::Thai),
+            "latin" => Ok(Script::Latin),
+            "other" => Ok(Script::Other),
+            _ => Err(format!(
+                "Unsupported script: '{}'. Supported: kana, han, hangul, thai, latin, other",
+                s
+            )),
+        }
+    }
+}
+
+impl Script {
+    /// Returns the script of a character.
+    ///
+    /// # Arguments
+    /// * `ch` - The character.
+    ///
+    /// # Returns
+    /// The script of a letter, or `None` for characters shared by all scripts, such as
+    /// digits, punctuation, symbols and whitespace. The prolonged sound mark `ー` is kana.
+    #[must_use]
+    pub fn of(ch: char) -> Option<Script> {
+        match ch {
+            '\u{3041}'..='\u{309F}' | '\u{30A0}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}' => {
+                Some(Script::Kana)
+            }
+            '\u{FF66}'..='\u{FF9F}' => Some(Script::Kana),
+            '\u{3005}' | '\u{3007}' | '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => {
+                Some(Script::Han)
+            }
+            '\u{F900}'..='\u{FAFF}' | '\u{20000}'..='\u{2FA1F}' => Some(Script::Han),
+            '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+                Some(Script::Hangul)
+            }
+            '\u{0E00}'..='\u{0E7F}' => Some(Script::Thai),
+            'A'..='Z' | 'a'..='z' | '\u{00C0}'..='\u{024F}' | '\u{FF21}'..='\u{FF3A}' => {
+                Some(Script::Latin)
+            }
+            '\u{FF41}'..='\u{FF5A}' => Some(Script::Latin),
+            _ if ch.is_alphabetic() => Some(Script::Other),
+            _ => None,
+        }
+    }
+
+    /// Detects the script of a text from the letters it contains.
+    ///
+    /// Text with any kana is [`Kana`](Script::Kana) unless Hangul, Thai or other letters
+    /// outnumber kana and Han together, since Japanese mixes kana with kanji and often
+    /// with Latin words. Otherwise the script with the most letters wins.
+    ///
+    /// # Arguments
+    /// * `text` - The text to detect the script of.
+    ///
+    /// # Returns
+    /// The detected script, or [`Other`](Script::Other) if the text has no letters.
+    #[must_use]
+    pub fn detect(text: &str) -> Script {
+        const ORDER: [Script; 6] = [
+            Script::Kana,
+            Script::Han,
+            Script::Hangul,
+            Script::Thai,
+            Script::Latin,
+            Script::Other,
+        ];
+        let mut counts = [0usize; 6];
+        for script in text.chars().filter_map(Script::of) {
+            counts[script as usize] += 1;
+        }
+        let japanese = counts[Script::Kana as usize] + counts[Script::Han as usize];
+        let others = [Script::Hangul, Script::Thai, Script::Other];
+        if counts[Script::Kana as usize] > 0
+            && others.iter().all(|&script| japanese >= counts[script as usize])
+        {
+            return Script::Kana;
+        }
+        // Ties go to the script listed first.
+        let (best, &count) = counts
+            .iter()
+            .enumerate()
+            .skip(1)
+            .fold((0, &counts[0]), |best, item| if item.1 > best.1 { item } else { best });
+        if count == 0 { Script::Other } else { ORDER[best] }
+    }
+
+    /// Splits a text into runs of one script. Kana and Han form one run, whose script is
+    /// [`Kana`](Script::Kana) if it has any kana. Characters without a script belong to
+    /// the run before them, or to the first run at the start of the text.
+    ///
+    /// # Arguments
+    /// * `text` - The text to split.
+    ///
+    /// # Returns
+    /// The runs in order with their scripts. They cover the whole text.
+    #[must_use]
+    pub fn spans(text: &str) -> Vec<(Script, &str)> {
+        let group = |script: Script| match script {
+            Script::Kana | Script::Han => Script::Kana,
+            other => other,
+        };
+        // (group, start, has kana)
+        let mut runs: Vec<(Script, usize, bool)> = Vec::new();
+        for (pos, ch) in text.char_indices() {
+            let Some(script) = Script::of(ch) else { continue };
+            match runs.last_mut() {
+                Some(run) if run.0 == group(script) => run.2 |= script == Script::Kana,
+                _ => runs.push((group(script), pos, script == Script::Kana)),
+            }
+        }
+        if runs.is_empty() {
+            return if text.is_empty() { Vec::new() } else { vec![(Script::Other, text)] };
+        }
+        runs[0].1 = 0;
+        let ends: Vec<usize> = runs.iter().skip(1).map(|run| run.1).chain([text.len()]).collect();
+        runs.iter()
+            .zip(ends)
+            .map(|(&(group, start, has_kana), end)| {
+                let script = match group {
+                    Script::Kana if !has_kana => Script::Han,
+                    other => other,
+                };
+                (script, &text[start..end])
+            })
+            .collect()
+    }
+}
+
+/// Where text of a script is sent.
+pub enum Route {
+    /// Segmented with this segmenter.
+    Segment(Box<Segmenter>),
+    /// Split on whitespace only.
+    Passthrough,
+}
+
+/// Routes sentences or spans to per-script segmenters.
+pub struct ScriptRouter {
+    routes: HashMap<Script, Route>,
+    split_spans: bool,
+}
+
+impl Default for ScriptRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScriptRouter {
+    /// Creates a router that passes every script through and routes whole sentences.
+    ///
+    /// # Returns
+    /// A new [`ScriptRouter`] without segmenters.
+    #[must_use]
+    pub fn new() -> Self {
+        ScriptRouter {
+            routes: HashMap::new(),
+            split_spans: false,
+        }
+    }
+
+    /// Sends text of a script to a segmenter.
+    ///
+    /// # Arguments
+    /// * `script` - The script.
+    /// * `segmenter` - The segmenter for it.
+    ///
+    /// # Returns
+    /// The router with the route added.
+    #[must_use]
+    pub fn with_segmenter(mut self, script: Script, segmenter: Segmenter) -> Self {
+        self.routes.insert(script, Route::Segment(Box::new(segmenter)));
+        self
+    }
+
+    /// Passes text of a script through, splitting it on whitespace only. This is the
+    /// default for scripts without a route.
+    ///
+    /// # Arguments
+    /// * `script` - The script.
+    ///
+    /// # Returns
+    /// The router with the route added.
+    #[must_use]
+    pub fn with_passthrough(mut self, script: Script) -> Self {
+        self.routes.insert(script, Route::Passthrough);
+        self
+    }
+
+    /// Sets whether each sentence is split into runs of one script (see [`Script::spans`])
+    /// that are routed separately, instead of routing the whole sentence by its detected
+    /// script.
+    ///
+    /// # Arguments
+    /// * `split_spans` - Whether to route spans.
+    ///
+    /// # Returns
+    /// The router with the new setting.
+    #[must_use]
+    pub fn with_span_splitting(mut self, split_spans: bool) -> Self {
+        self.split_spans = split_spans;
+        self
+    }
+
+    /// Creates a router from a model map: lines of `script language model_uri` or
+    /// `script passthrough`, with columns separated by tabs or spaces. Empty lines and
+    /// lines starting with `#` are ignored.
+    /// Models are loaded with [`AdaBoost::load_model`], so local paths and URLs are
+    /// supported; a model used for several scripts is loaded once per script.
+    ///
+    /// # Arguments
+    /// * `content` - The model map.
+    ///
+    /// # Returns
+    /// The router with a route for every line.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::Parse`] if a line is malformed, or the error of
+    /// [`AdaBoost::load_model`] if a model cannot be loaded.
+    pub async fn from_model_map(content: &str) -> crate::error::Result<Self> {
+        let invalid = |i: usize, message: String| LitseaError::Parse {
+            line: i + 1,
+            reason: format!("Invalid model map entry: {}", message),
+        };
+        let mut router = Self::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let script: Script = columns[0].parse().map_err(|e| invalid(i, e))?;
+            router = match columns[1..] {
+                ["passthrough"] => router.with_passthrough(script),
+                [language, uri] => {
+                    let language: Language = language.parse().map_err(|e| invalid(i, e))?;
+                    let mut learner = AdaBoost::new(0.01, 100);
+                    learner.load_model(uri).await?;
+                    router.with_segmenter(script, Segmenter::new(language, Some(learner)))
+                }
+                _ => return Err(invalid(i, format!("'{}'", line))),
+            };
+        }
+        Ok(router)
+    }
+
+    /// Loads a model map from a file. See [`from_model_map`](Self::from_model_map).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the model map.
+    ///
+    /// # Returns
+    /// The router with a route for every line.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read, a line is malformed or a model
+    /// cannot be loaded.
+    pub async fn load<P: AsRef<Path>>(path: P) -> crate::error::Result<Self> {
+        Self::from_model_map(&fs::read_to_string(path)?).await
+    }
+
+    /// Returns the route of a script.
+    ///
+    /// # Arguments
+    /// * `script` - The script.
+    #[must_use]
+    pub fn route(&self, script: Script) -> &Route {
+        self.routes.get(&script).unwrap_or(&Route::Passthrough)
+    }
+
+    /// Segments a sentence with the segmenter of its script, or of each of its spans.
+    ///
+    /// # Arguments
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The tokens of the sentence with their byte offsets in it.
+    #[must_use]
+    pub fn tokenize(&self, sentence: &str) -> Vec<Token> {
+        let mut tokens = if self.split_spans {
+            let mut tokens = Vec::new();
+            let mut offset = 0;
+            for (script, span) in Script::spans(sentence) {
+                tokens.extend(self.tokenize_span(script, span, offset));
+                offset += span.len();
+            }
+            tokens
+        } else {
+            self.tokenize_span(Script::detect(sentence), sentence, 0)
+        };
+        set_char_offsets(&mut tokens, sentence);
+        tokens
+    }
+
+    /// Segments a sentence, as [`tokenize`](Self::tokenize) does.
+    ///
+    /// # Arguments
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The words of the sentence in order.
+    #[must_use]
+    pub fn segment(&self, sentence: &str) -> Vec<String> {
+        self.tokenize(sentence).into_iter().map(|token| token.surface).collect()
+    }
+
+    /// Tokenizes a span of one script at `offset` in the sentence.
+    fn tokenize_span(&self, script: Script, span: &str, offset: usize) -> Vec<Token> {
+        let start = span.len() - span.trim_start().len();
+        let trimmed = span.trim();
+        let mut tokens = match self.route(script) {
+            Route::Segment(segmenter) => segmenter.tokenize(trimmed),
+            Route::Passthrough => trimmed
+                .split_whitespace()
+                .map(|word| {
+                    let start = word.as_ptr() as usize - trimmed.as_ptr() as usize;
+                    Token {
+                        surface: word.to_string(),
+                        start,
+                        end: start + word.len(),
+                        ..Token::default()
+                    }
+                })
+                .collect(),
+        };
+        for token in &mut tokens {
+            token.start += offset + start;
+            token.end += offset + start;
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::{rwcp_segmenter, segmenter};
+
+    fn router() -> ScriptRouter {
+        ScriptRouter::new()
+            .with_segmenter(Script::Kana, rwcp_segmenter())
+            .with_segmenter(
+                Script::Hangul,
+                segmenter(include_str!("../../resources/korean.model"), Language::Korean),
+            )
+    }
+
+    #[test]
+    fn test_script_of() {
+        assert_eq!(Script::of('あ'), Some(Script::Kana));
+        assert_eq!(Script::of('ー'), Some(Script::Kana));
+        assert_eq!(Script::of('漢'), Some(Script::Han));
+        assert_eq!(Script::of('한'), Some(Script::Hangul));
+        assert_eq!(Script::of('ก'), Some(Script::Thai));
+        assert_eq!(Script::of('é'), Some(Script::Latin));
+        assert_eq!(Script::of('Я'), Some(Script::Other));
+        assert_eq!(Script::of('1'), None);
+        assert_eq!(Script::of('。'), None);
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Script::detect("東京都に住む"), Script::Kana);
+        assert_eq!(Script::detect("LitseaはTinySegmenterを参考に"), Script::Kana);
+        assert_eq!(Script::detect("한국어로 된 すし"), Script::Hangul);
+        assert_eq!(Script::detect("中华人民共和国"), Script::Han);
+        assert_eq!(Script::detect("ภาษาไทย"), Script::Thai);
+        assert_eq!(Script::detect("123 !?"), Script::Other);
+        assert_eq!(Script::detect(""), Script::Other);
+    }
+
+    #[test]
+    fn test_script_from_str() {
+        assert_eq!("Japanese".parse::<Script>(), Ok(Script::Kana));
+        assert_eq!("hangul".parse::<Script>(), Ok(Script::Hangul));
+        assert_eq!(Script::Thai.to_string().parse::<Script>(), Ok(Script::Thai));
+        assert!("cyrillic".parse::<Script>().is_err());
+    }
+
+    #[test]
+    fn test_spans() {
+        let text = "「Rust」で書く。Hello, world! 한국어";
+        let spans = Script::spans(text);
+        assert_eq!(
+            spans,
+            vec![
+                (Script::Latin, "「Rust」"),
+                (Script::Kana, "で書く。"),
+                (Script::Latin, "Hello, world! "),
+                (Script::Hangul, "한국어"),
+            ]
+        );
+        assert_eq!(spans.iter().map(|(_, span)| *span).collect::<String>(), text);
+        assert_eq!(Script::spans("漢字"), vec![(Script::Han, "漢字")]);
+        assert_eq!(Script::spans("123"), vec![(Script::Other, "123")]);
+        assert!(Script::spans("").is_empty());
+    }
+
+    #[test]
+    fn test_route_sentences() {
+        let router = router();
+        assert_eq!(
+            router.segment("これはテストです。"),
+            vec!["これ", "は", "テスト", "です", "。"]
+        );
+        assert_eq!(router.segment("This is a pen."), vec!["This", "is", "a", "pen."]);
+        // Thai has no route, so it is passed through whole.
+        assert_eq!(router.segment("ภาษาไทย"), vec!["ภาษาไทย"]);
+    }
+
+    #[test]
+    fn test_route_spans() {
+        let router = router().with_span_splitting(true);
+        let sentence = "Hello world これはテストです。";
+        let tokens = router.tokenize(sentence);
+        let surfaces: Vec<&str> = tokens.iter().map(|t| t.surface.as_str()).collect();
+        assert_eq!(surfaces, vec!["Hello", "world", "これ", "は", "テスト", "です", "。"]);
+        let chars: Vec<char> = sentence.chars().collect();
+        for token in &tokens {
+            assert_eq!(&sentence[token.start..token.end], token.surface);
+            let surface: String = chars[token.char_start..token.char_end].iter().collect();
+            assert_eq!(surface, token.surface);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_from_model_map() {
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model");
+        let map =
+            format!("# script\tlanguage\tmodel\nkana\tjapanese\t{}\nlatin\tpassthrough\n", model);
+        let router = ScriptRouter::from_model_map(&map).await.unwrap();
+        assert!(matches!(router.route(Script::Kana), Route::Segment(_)));
+        assert!(matches!(router.route(Script::Latin), Route::Passthrough));
+        assert!(matches!(router.route(Script::Hangul), Route::Passthrough));
+
+        let err = ScriptRouter::from_model_map("kana\tjapanese\n").await.err().unwrap();
+        assert!(matches!(err, LitseaError::Parse { line: 1, .. }), "{}", err);
+        assert!(ScriptRouter::from_model_map("cyrillic\tpassthrough\n").await.is_err());
+    }
+}
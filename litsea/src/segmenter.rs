@@ -1,19 +1,260 @@
-use std::collections::HashSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+use std::hash::{DefaultHasher, Hash, Hasher};
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+#[cfg(feature = "std")]
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::adaboost::AdaBoost;
-use crate::language::{CharTypePatterns, Language};
+use crate::error::LitseaError;
+use crate::grouping::GroupingRules;
+use crate::language::{CharType, CharTypePatterns, Language, LatinClasses};
+use crate::model_info;
+use crate::token::{Token, set_char_offsets};
+
+/// Feature templates used to turn the context of a character into features.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "config", serde(rename_all = "lowercase"))]
+pub enum FeatureTemplates {
+    /// The templates of Litsea models: those of TinySegmenter without the word trigrams
+    /// (TW1-TW4), plus word and character type pairs (WC1-WC4) for Japanese and Chinese.
+    #[default]
+    Litsea,
+    /// Exactly the templates of the original TinySegmenter, with its character types and
+    /// its boundary rule (a score above 0), so models published for TinySegmenter and its
+    /// ports segment text identically.
+    TinySegmenter,
+}
+
+impl fmt::Display for FeatureTemplates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureTemplates::Litsea => write!(f, "litsea"),
+            FeatureTemplates::TinySegmenter => write!(f, "tinysegmenter"),
+        }
+    }
+}
+
+impl FromStr for FeatureTemplates {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "litsea" => Ok(FeatureTemplates::Litsea),
+            "tinysegmenter" => Ok(FeatureTemplates::TinySegmenter),
+            _ => Err(format!(
+                "Unsupported feature templates: '{}'. Supported: litsea, tinysegmenter",
+                s
+            )),
+        }
+    }
+}
+
+/// A word produced by [`Segmenter::segment_with_scores`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoredWord {
+    /// The text of the word.
+    pub surface: String,
+    /// Byte offset of the start of the word in the sentence.
+    pub start: usize,
+    /// Byte offset of the end of the word in the sentence.
+    pub end: usize,
+    /// Score of the boundary before the word, or `None` for the first word, which
+    /// always starts at the beginning of the sentence.
+    pub score: Option<f64>,
+}
+
+impl ScoredWord {
+    /// Returns the estimated probability that the boundary before the word is correct,
+    /// derived from the AdaBoost margin as `1 / (1 + exp(-2 * |score|))`.
+    /// The first word has a confidence of 1. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn confidence(&self) -> f64 {
+        self.score.map_or(1.0, |score| 1.0 / (1.0 + (-2.0 * score.abs()).exp()))
+    }
+}
+
+/// A candidate segmentation of a sentence, produced by [`Segmenter::segment_nbest`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segmentation {
+    /// The words of the sentence in order.
+    pub words: Vec<ScoredWord>,
+    /// Total margin of the boundary decisions: the scores of the boundaries that were
+    /// placed minus the scores of those that were not, each plus the
+    /// [boundary bias](Segmenter::set_boundary_bias). Higher is more confident.
+    pub score: f64,
+}
+
+impl Segmentation {
+    /// Returns the surfaces of the words.
+    #[must_use]
+    pub fn surfaces(&self) -> Vec<&str> {
+        self.words.iter().map(|w| w.surface.as_str()).collect()
+    }
+}
+
+/// Number of independently locked parts of a [`ScoreCache`], so that threads segmenting
+/// with the same segmenter rarely wait for each other.
+#[cfg(feature = "std")]
+const CACHE_SHARDS: usize = 16;
+
+/// A cache of decision scores keyed by a fingerprint of the features of the decision.
+///
+/// Log-like and templated text repeats the same contexts constantly, and a cached score
+/// replaces the lookup of every feature of the decision by a single lookup. A shard that
+/// reaches its share of the capacity is cleared.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct ScoreCache {
+    shards: Vec<Mutex<HashMap<u64, f64>>>,
+    shard_capacity: usize,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+/// Usage statistics of the score cache of a [`Segmenter`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Number of decisions whose score was found in the cache.
+    pub hits: usize,
+    /// Number of decisions whose score was computed.
+    pub misses: usize,
+    /// Number of scores currently cached.
+    pub entries: usize,
+}
+
+#[cfg(feature = "std")]
+impl CacheStats {
+    /// Returns the percentage of decisions served from the cache.
+    #[must_use]
+    pub fn hit_rate(&self) -> f64 {
+        self.hits as f64 / (self.hits + self.misses).max(1) as f64 * 100.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl ScoreCache {
+    fn new(capacity: usize) -> Self {
+        ScoreCache {
+            shards: (0..CACHE_SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+            shard_capacity: capacity.div_ceil(CACHE_SHARDS),
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the cached score of the features, or computes and caches it.
+    fn score<F: FnOnce() -> f64>(&self, attributes: &[String], compute: F) -> f64 {
+        let mut hasher = DefaultHasher::new();
+        attributes.hash(&mut hasher);
+        let fingerprint = hasher.finish();
+        let shard = &self.shards[(fingerprint % CACHE_SHARDS as u64) as usize];
+
+        if let Some(&score) = shard.lock().unwrap_or_else(|e| e.into_inner()).get(&fingerprint) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return score;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let score = compute();
+        let mut scores = shard.lock().unwrap_or_else(|e| e.into_inner());
+        if scores.len() >= self.shard_capacity {
+            scores.clear();
+        }
+        scores.insert(fingerprint, score);
+        score
+    }
+
+    /// Drops the cached scores, which are stale once the model changes.
+    fn clear(&mut self) {
+        for shard in &mut self.shards {
+            shard.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self
+                .shards
+                .iter()
+                .map(|s| s.lock().unwrap_or_else(|e| e.into_inner()).len())
+                .sum(),
+        }
+    }
+}
 
 /// Segmenter struct for text segmentation using AdaBoost
 /// It uses predefined patterns to classify characters and segment sentences into words.
+///
+/// A `Segmenter` is `Send + Sync`: segmenting only reads the model, and the score cache
+/// locks its shards, so one loaded segmenter can be shared behind an [`Arc`] by any
+/// number of threads, e.g. the request handlers of a web server. Methods that change the
+/// model or the settings take `&mut self` and must be called before sharing it.
+///
+/// The model itself is held in an [`Arc`], so segmenters with different settings can
+/// share one loaded model: cloning a segmenter, or creating one with
+/// [`from_shared`](Self::from_shared), does not copy the model. Training a segmenter
+/// whose model is shared, e.g. with [`learn_sentence`](Self::learn_sentence), first
+/// gives it its own copy.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use litsea::segmenter::Segmenter;
+///
+/// let segmenter = Arc::new(Segmenter::from_model_str(
+///     litsea::language::Language::Japanese,
+///     include_str!("../../resources/RWCP.model"),
+/// )?);
+/// let handlers: Vec<_> = (0..4)
+///     .map(|_| {
+///         let segmenter = Arc::clone(&segmenter);
+///         std::thread::spawn(move || segmenter.segment("これはテストです。"))
+///     })
+///     .collect();
+/// for handler in handlers {
+///     assert_eq!(handler.join().unwrap(), ["これ", "は", "テスト", "です", "。"]);
+/// }
+/// # Ok::<(), litsea::error::LitseaError>(())
+/// ```
 pub struct Segmenter {
     pub language: Language,
-    char_types: CharTypePatterns,
-    pub learner: AdaBoost,
+    char_types: Arc<CharTypePatterns>,
+    templates: FeatureTemplates,
+    latin_classes: LatinClasses,
+    grouping: GroupingRules,
+    bias: f64,
+    learner: Arc<AdaBoost>,
+    #[cfg(feature = "std")]
+    cache: Option<ScoreCache>,
 }
 
+// Sharing a segmenter across threads is part of its API; keep it that way.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Segmenter>();
+};
+
 impl Segmenter {
     /// Creates a new instance of [`Segmenter`].
     ///
+    /// The segmenter uses the feature templates the model records in its metadata (see
+    /// [`model_info::recorded_templates`]), e.g. those of TinySegmenter for a model
+    /// imported from a TinySegmenter script, and Litsea's templates otherwise.
+    ///
     /// # Arguments
     /// * `language` - The language to use for character type classification.
     /// * `learner` - An optional AdaBoost instance. If None, a default AdaBoost instance is created.
@@ -29,13 +270,316 @@ impl Segmenter {
     /// let segmenter = Segmenter::new(Language::Japanese, None);
     /// ```
     pub fn new(language: Language, learner: Option<AdaBoost>) -> Self {
-        Segmenter {
-            char_types: language.char_type_patterns(),
+        let learner = learner.unwrap_or_else(|| AdaBoost::new(0.01, 100));
+        Segmenter::from_shared(language, Arc::new(learner))
+    }
+
+    /// Creates a segmenter for a model shared with other segmenters, e.g. segmenters of
+    /// the same model with different settings. The model is not copied.
+    ///
+    /// The segmenter uses the feature templates the model records, as with
+    /// [`new`](Self::new).
+    ///
+    /// # Arguments
+    /// * `language` - The language to use for character type classification.
+    /// * `learner` - The shared model.
+    ///
+    /// # Returns
+    /// A new Segmenter for the model.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    ///
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    /// let model = Arc::new(learner);
+    ///
+    /// let display = Segmenter::from_shared(Language::Japanese, Arc::clone(&model));
+    /// let mut search = Segmenter::from_shared(Language::Japanese, Arc::clone(&model));
+    /// search.set_boundary_bias(1.0);
+    /// assert!(Arc::ptr_eq(&display.shared_learner(), &search.shared_learner()));
+    /// ```
+    pub fn from_shared(language: Language, learner: Arc<AdaBoost>) -> Self {
+        let mut segmenter = Segmenter {
+            char_types: Arc::new(language.char_type_patterns()),
+            templates: FeatureTemplates::default(),
+            latin_classes: LatinClasses::default(),
+            grouping: GroupingRules::default(),
+            bias: 0.0,
             language,
-            learner: learner.unwrap_or_else(|| AdaBoost::new(0.01, 100)),
+            learner,
+            #[cfg(feature = "std")]
+            cache: None,
+        };
+        if let Some(templates) = model_info::recorded_templates(&segmenter.learner) {
+            segmenter.set_feature_templates(templates);
+        }
+        segmenter
+    }
+
+    /// Creates a segmenter from a model held in memory, e.g. embedded with
+    /// `include_str!` or fetched by a browser. Needs no file system, so it also works
+    /// without the `std` feature and on `wasm32-unknown-unknown`.
+    ///
+    /// # Arguments
+    /// * `language` - The language the model was trained for.
+    /// * `model` - The content of a model file.
+    ///
+    /// # Returns
+    /// A new Segmenter for the model.
+    ///
+    /// # Errors
+    /// Returns an error if the model cannot be parsed or its checksum does not match.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let model = include_str!("../../resources/RWCP.model");
+    /// let segmenter = Segmenter::from_model_str(Language::Japanese, model).unwrap();
+    /// assert_eq!(segmenter.segment("これはテストです。"), ["これ", "は", "テスト", "です", "。"]);
+    /// ```
+    pub fn from_model_str(language: Language, model: &str) -> crate::error::Result<Self> {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(model)?;
+        Ok(Segmenter::new(language, Some(learner)))
+    }
+
+    /// Returns the model of the segmenter.
+    #[must_use]
+    pub fn learner(&self) -> &AdaBoost {
+        &self.learner
+    }
+
+    /// Returns the model of the segmenter, e.g. to change its weights. A model shared
+    /// with other segmenters is copied first, so they are not affected. Clears the score
+    /// cache, whose scores may no longer match the weights.
+    pub fn learner_mut(&mut self) -> &mut AdaBoost {
+        #[cfg(feature = "std")]
+        if let Some(cache) = &mut self.cache {
+            cache.clear();
+        }
+        Arc::make_mut(&mut self.learner)
+    }
+
+    /// Returns the model of the segmenter for sharing with other segmenters, see
+    /// [`from_shared`](Self::from_shared). The model is not copied.
+    #[must_use]
+    pub fn shared_learner(&self) -> Arc<AdaBoost> {
+        Arc::clone(&self.learner)
+    }
+
+    /// Returns the model of the segmenter, e.g. to train it further. A model shared with
+    /// other segmenters is copied.
+    #[must_use]
+    pub fn into_learner(self) -> AdaBoost {
+        Arc::unwrap_or_clone(self.learner)
+    }
+
+    /// Enables a cache of decision scores holding up to `capacity` entries, or disables
+    /// it if `capacity` is 0. Enabling it again clears the cache. Changing the weights
+    /// through [`learner_mut`](Self::learner_mut) or
+    /// [`learn_sentence`](Self::learn_sentence) clears it too.
+    ///
+    /// Scores are keyed by a 64-bit hash of the features of a decision, so a collision
+    /// between two contexts, while unlikely, returns the score of the other one.
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of cached scores, e.g. 1,000,000.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    ///
+    /// let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+    /// segmenter.set_score_cache(100_000);
+    /// segmenter.segment("これはテストです。");
+    /// segmenter.segment("これはテストです。");
+    /// assert_eq!(segmenter.cache_stats().unwrap().hits, 8);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_score_cache(&mut self, capacity: usize) {
+        self.cache = (capacity > 0).then(|| ScoreCache::new(capacity));
+    }
+
+    /// Returns the usage statistics of the score cache, or `None` if it is disabled.
+    #[cfg(feature = "std")]
+    #[must_use]
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(ScoreCache::stats)
+    }
+
+    /// Returns the score of a boundary decision given its features, from the cache if
+    /// it is enabled.
+    fn score(&self, attributes: &[String]) -> f64 {
+        #[cfg(feature = "std")]
+        if let Some(cache) = &self.cache {
+            return cache.score(attributes, || self.learner.score_attributes(attributes));
+        }
+        self.learner.score_attributes(attributes)
+    }
+
+    /// Sets the feature templates. [`FeatureTemplates::TinySegmenter`] also switches to
+    /// the character types of TinySegmenter, whatever the language.
+    ///
+    /// # Arguments
+    /// * `templates` - The feature templates the model was trained with.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::{FeatureTemplates, Segmenter};
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    ///
+    /// let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+    /// segmenter.set_feature_templates(FeatureTemplates::TinySegmenter);
+    /// assert_eq!(segmenter.get_type("。"), "O");
+    /// ```
+    pub fn set_feature_templates(&mut self, templates: FeatureTemplates) {
+        self.templates = templates;
+        self.char_types = Arc::new(self.char_type_patterns());
+    }
+
+    /// Returns the feature templates of the segmenter.
+    #[must_use]
+    pub fn feature_templates(&self) -> FeatureTemplates {
+        self.templates
+    }
+
+    /// Sets how Latin letters are divided into character types. Features must be
+    /// extracted with the same classes as the model was trained with. The classes are
+    /// ignored with [`FeatureTemplates::TinySegmenter`], whose character types are fixed.
+    ///
+    /// # Arguments
+    /// * `classes` - The Latin classes.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::{Language, LatinClasses};
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut segmenter = Segmenter::new(Language::Japanese, None);
+    /// segmenter.set_latin_classes(LatinClasses::Case);
+    /// assert_eq!(segmenter.get_type("P"), "AU");
+    /// assert_eq!(segmenter.get_type("h"), "AL");
+    /// ```
+    pub fn set_latin_classes(&mut self, classes: LatinClasses) {
+        self.latin_classes = classes;
+        self.char_types = Arc::new(self.char_type_patterns());
+    }
+
+    /// Returns how Latin letters are divided into character types.
+    #[must_use]
+    pub fn latin_classes(&self) -> LatinClasses {
+        self.latin_classes
+    }
+
+    /// Sets the rules that keep tokens such as numbers, URLs and emoji in one word. No
+    /// boundary is placed inside such a token, whatever its score.
+    ///
+    /// # Arguments
+    /// * `rules` - The grouping rules.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::grouping::GroupingRules;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut segmenter = Segmenter::new(Language::Japanese, None);
+    /// segmenter.set_grouping_rules(GroupingRules::all());
+    /// assert_eq!(segmenter.segment("v1.2"), ["v", "1.2"]);
+    /// ```
+    pub fn set_grouping_rules(&mut self, rules: GroupingRules) {
+        self.grouping = rules;
+    }
+
+    /// Returns the rules that keep tokens in one word.
+    #[must_use]
+    pub fn grouping_rules(&self) -> GroupingRules {
+        self.grouping
+    }
+
+    /// Shifts the decision point of every boundary by adding `bias` to its score, 0 by
+    /// default. A positive bias splits more, trading precision for recall on word
+    /// boundaries, e.g. for search indexing, and a negative one less, without retraining.
+    /// Later decisions see the boundaries placed with the bias, so segmenting with it can
+    /// differ from shifting the [`boundary_scores`](Self::boundary_scores) of the default
+    /// segmentation.
+    ///
+    /// # Arguments
+    /// * `bias` - The shift of the decision point. A boundary is placed where the score
+    ///   plus the bias is at least 0, or above 0 with [`FeatureTemplates::TinySegmenter`].
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let model = include_str!("../../resources/RWCP.model");
+    /// let mut segmenter = Segmenter::from_model_str(Language::Japanese, model).unwrap();
+    /// segmenter.set_boundary_bias(f64::NEG_INFINITY);
+    /// assert_eq!(segmenter.segment("これはテストです。"), ["これはテストです。"]);
+    /// ```
+    pub fn set_boundary_bias(&mut self, bias: f64) {
+        self.bias = bias;
+    }
+
+    /// Returns the shift of the decision point, see
+    /// [`set_boundary_bias`](Self::set_boundary_bias).
+    #[must_use]
+    pub fn boundary_bias(&self) -> f64 {
+        self.bias
+    }
+
+    /// Creates the character type patterns for the language, templates and Latin classes.
+    fn char_type_patterns(&self) -> CharTypePatterns {
+        match self.templates {
+            FeatureTemplates::Litsea => {
+                self.language.char_type_patterns().with_latin_classes(self.latin_classes)
+            }
+            FeatureTemplates::TinySegmenter => CharTypePatterns::tinysegmenter(),
         }
     }
 
+    /// Creates a segmenter from the latest version of a model in a [`ModelStore`].
+    ///
+    /// The language is taken from the model's metadata in the store.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the SQLite model store.
+    /// * `name` - The name of the model.
+    ///
+    /// # Returns
+    /// A new Segmenter for the stored model.
+    ///
+    /// # Errors
+    /// Returns an error if the store cannot be opened or has no model named `name`.
+    ///
+    /// [`ModelStore`]: crate::model_store::ModelStore
+    #[cfg(feature = "sqlite")]
+    pub fn from_store<P: AsRef<std::path::Path>>(
+        path: P,
+        name: &str,
+    ) -> crate::error::Result<Self> {
+        let (language, learner) = crate::model_store::ModelStore::open(path)?.load(name, None)?;
+        Ok(Segmenter::new(language, Some(learner)))
+    }
+
     /// Gets the type of a character based on language-specific patterns.
     ///
     /// # Arguments
@@ -59,6 +603,69 @@ impl Segmenter {
         self.char_types.get_type(ch)
     }
 
+    /// Gets the class of a character based on language-specific patterns.
+    ///
+    /// # Arguments
+    /// * `ch` - The character.
+    ///
+    /// # Returns
+    /// The class of the character, or `None` if it has a type code of custom patterns.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::{CharType, Language};
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let segmenter = Segmenter::new(Language::Japanese, None);
+    /// assert_eq!(segmenter.char_type('あ'), Some(CharType::Hiragana));
+    /// ```
+    #[must_use]
+    pub fn char_type(&self, ch: char) -> Option<CharType> {
+        self.char_types.char_type(ch)
+    }
+
+    /// Checks that the model was trained with the character types and feature templates
+    /// of the segmenter, as far as its metadata records them.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::ModelFormat`] if the model records a different character
+    /// type scheme or feature templates.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::{Language, LatinClasses};
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.set_metadata("char_types", "japanese-2-case").unwrap();
+    /// let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+    /// assert!(segmenter.check_model().is_err());
+    /// segmenter.set_latin_classes(LatinClasses::Case);
+    /// assert!(segmenter.check_model().is_ok());
+    /// ```
+    pub fn check_model(&self) -> Result<(), LitseaError> {
+        let expected = [
+            (
+                model_info::CHAR_TYPES_KEY,
+                model_info::char_type_scheme(self.language, self.templates, self.latin_classes),
+            ),
+            (model_info::TEMPLATES_KEY, model_info::templates_id(self.templates)),
+        ];
+        for (key, value) in expected {
+            if let Some(recorded) = self.learner.metadata(key) {
+                if recorded != value {
+                    return Err(LitseaError::ModelFormat(format!(
+                        "The model was trained with {} '{}', but the segmenter uses '{}'",
+                        key, recorded, value
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "train")]
     /// Processes a corpus string by building tags, characters, and types arrays,
     /// then calls the callback for each character position with its attributes and label.
     fn process_corpus<F>(&self, corpus: &str, mut callback: F)
@@ -83,150 +690,522 @@ impl Segmenter {
                 tags.push("O".to_string());
             }
             for ch in word.chars() {
-                let s = ch.to_string();
-                types.push(self.get_type(&s).to_string());
-                chars.push(s);
+                types.push(self.char_types.get_char_type(ch).to_string());
+                chars.push(ch.to_string());
             }
         }
         if tags.len() < 4 {
             return;
         }
-        // Override the first real character's tag to "U" (Unknown) instead of "B",
-        // because there is no preceding word boundary decision to reference at position 0.
-        tags[3] = "U".to_string();
-
-        chars.extend_from_slice(&["E1".into(), "E2".into(), "E3".into()]);
-        types.extend_from_slice(&["O".into(), "O".into(), "O".into()]);
+        // Override the first real character's tag to "U" (Unknown) instead of "B",
+        // because there is no preceding word boundary decision to reference at position 0.
+        tags[3] = "U".to_string();
+
+        chars.extend_from_slice(&["E1".into(), "E2".into(), "E3".into()]);
+        types.extend_from_slice(&["O".into(), "O".into(), "O".into()]);
+
+        for i in 4..(chars.len() - 3) {
+            let label = if tags[i] == "B" { 1 } else { -1 };
+            let attrs = self.get_attributes(i, &tags, &chars, &types);
+            callback(attrs, label);
+        }
+    }
+
+    #[cfg(feature = "train")]
+    /// Adds a corpus to the segmenter with a custom writer function.
+    ///
+    /// # Arguments
+    /// * `corpus` - A string slice representing the corpus to be added.
+    /// * `writer` - A closure that takes a HashSet of attributes and a label (i8) and writes them.
+    ///
+    /// # Note
+    /// The writer function is called for each word in the corpus, allowing for custom handling of the attributes and labels.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let segmenter = Segmenter::new(Language::Japanese, None);
+    /// segmenter.add_corpus_with_writer("テスト です", |attrs, label| {
+    ///    println!("Attributes: {:?}, Label: {}", attrs, label);
+    /// });
+    /// ```
+    ///
+    /// This will process the corpus and call the writer function for each word, passing the attributes and label.
+    pub fn add_corpus_with_writer<F>(&self, corpus: &str, writer: F)
+    where
+        F: FnMut(HashSet<String>, i8),
+    {
+        self.process_corpus(corpus, writer);
+    }
+
+    #[cfg(feature = "train")]
+    /// Adds a corpus to the segmenter.
+    ///
+    /// # Arguments
+    /// * `corpus` - A string slice representing the corpus to be added.
+    ///
+    /// This method processes the corpus, extracts features, and adds instances to the AdaBoost learner.
+    /// If the corpus is empty, it does nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut segmenter = Segmenter::new(Language::Japanese, None);
+    /// segmenter.add_corpus("テスト です");
+    /// ```
+    /// This will process the corpus and add instances to the segmenter.
+    pub fn add_corpus(&mut self, corpus: &str) {
+        let mut instances = Vec::new();
+        self.process_corpus(corpus, |attrs, label| {
+            instances.push((attrs, label));
+        });
+        let learner = Arc::make_mut(&mut self.learner);
+        for (attrs, label) in instances {
+            learner.add_instance(attrs, label);
+        }
+    }
+
+    #[cfg(feature = "train")]
+    /// Alias of [`add_corpus`](Self::add_corpus), the name used by TinySegmenterMaker.
+    ///
+    /// # Arguments
+    /// * `sentence` - A sentence with words separated by spaces.
+    pub fn add_sentence(&mut self, sentence: &str) {
+        self.add_corpus(sentence);
+    }
+
+    #[cfg(feature = "train")]
+    /// Learns from a correctly segmented sentence, updating the model right away with
+    /// [`AdaBoost::partial_fit`], e.g. to feed back a correction of a segmentation
+    /// mistake. Boosting on the boundaries of the sentence, and the other instances of the
+    /// learner, stops once the learner gets all of them right; set a small
+    /// [learning rate](AdaBoost::set_learning_rate) for a loaded model, so that the
+    /// sentence changes it no more than needed.
+    ///
+    /// # Arguments
+    /// * `gold` - The sentence with words separated by spaces.
+    ///
+    /// # Returns
+    /// The number of boundary decisions learned from.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 50);
+    /// learner.set_learning_rate(0.02).unwrap();
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    /// let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+    /// assert_eq!(
+    ///     segmenter.segment("猫である。名前はまだ無い。"),
+    ///     ["猫", "で", "ある", "。名前", "はまだ無い", "。"]
+    /// );
+    ///
+    /// assert_eq!(segmenter.learn_sentence("猫 で ある 。 名前 は まだ 無い 。"), 12);
+    /// assert_eq!(
+    ///     segmenter.segment("猫である。名前はまだ無い。"),
+    ///     ["猫", "で", "ある", "。", "名前", "は", "まだ", "無い", "。"]
+    /// );
+    /// ```
+    pub fn learn_sentence(&mut self, gold: &str) -> usize {
+        let mut instances = Vec::new();
+        self.process_corpus(gold, |attrs, label| {
+            instances.push((attrs, label));
+        });
+        self.learner_mut().partial_fit(instances)
+    }
+
+    /// Segments a sentence into words.
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    ///
+    /// # Returns
+    /// A vector of strings, where each string is a segmented word from the sentence.
+    ///
+    /// # Note
+    /// The method processes the sentence character by character, using the AdaBoost learner to predict whether a character is the beginning of a new word or not.
+    /// It constructs attributes based on the surrounding characters and their types, allowing for accurate segmentation.
+    /// If the sentence is empty, it returns an empty vector.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::adaboost::AdaBoost;
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let mut learner = AdaBoost::new(0.01, 100);
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+    ///
+    /// let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+    /// let result = segmenter.segment("これはテストです。");
+    /// assert_eq!(result, vec!["これ", "は", "テスト", "です", "。"]);
+    /// ```
+    /// This will segment the sentence into words and return them as a vector of strings.
+    #[must_use]
+    pub fn segment(&self, sentence: &str) -> Vec<String> {
+        self.segment_with_scores(sentence).into_iter().map(|w| w.surface).collect()
+    }
+
+    /// Alias of [`segment`](Self::segment), the name used by TinySegmenter.
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    ///
+    /// # Returns
+    /// A vector of strings, where each string is a segmented word from the sentence.
+    #[must_use]
+    pub fn parse(&self, sentence: &str) -> Vec<String> {
+        self.segment(sentence)
+    }
+
+    /// Alias of [`segment_nbest`](Self::segment_nbest), named after [`parse`](Self::parse).
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    /// * `n` - The maximum number of segmentations to return.
+    ///
+    /// # Returns
+    /// Up to `n` distinct segmentations, most confident first.
+    #[must_use]
+    pub fn parse_nbest(&self, sentence: &str, n: usize) -> Vec<Segmentation> {
+        self.segment_nbest(sentence, n)
+    }
+
+    /// Segments a sentence into [`Token`]s, ready to be annotated by post-processing
+    /// stages such as the `reading` module.
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    ///
+    /// # Returns
+    /// The words of the sentence in order, with their byte and character offsets and
+    /// without annotations.
+    #[must_use]
+    pub fn tokenize(&self, sentence: &str) -> Vec<Token> {
+        let mut tokens: Vec<Token> =
+            self.segment_with_scores(sentence).into_iter().map(Token::from).collect();
+        set_char_offsets(&mut tokens, sentence);
+        tokens
+    }
+
+    /// Alias of [`tokenize`](Self::tokenize), named after [`parse`](Self::parse), for
+    /// callers that need to map the words back to the sentence, e.g. to highlight them.
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    ///
+    /// # Returns
+    /// The words of the sentence in order, with their byte offsets in
+    /// [`start`](Token::start) and [`end`](Token::end) and their character offsets in
+    /// [`char_start`](Token::char_start) and [`char_end`](Token::char_end).
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let segmenter = Segmenter::new(Language::Japanese, None);
+    /// let tokens = segmenter.parse_with_spans("テスト");
+    /// assert_eq!((tokens[0].start, tokens[0].char_start), (0, 0));
+    /// assert_eq!(tokens.last().unwrap().char_end, 3);
+    /// ```
+    #[must_use]
+    pub fn parse_with_spans(&self, sentence: &str) -> Vec<Token> {
+        self.tokenize(sentence)
+    }
+
+    /// Segments a sentence into words, keeping the offset of each word and the score of
+    /// the boundary before it.
+    ///
+    /// # Arguments
+    /// * `sentence` - A string slice representing the sentence to be parsed.
+    ///
+    /// # Returns
+    /// The words of the sentence in order, as [`ScoredWord`]s. The words are the same as
+    /// those returned by [`segment`](Self::segment).
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::language::Language;
+    /// use litsea::segmenter::Segmenter;
+    ///
+    /// let segmenter = Segmenter::new(Language::Japanese, None);
+    /// let words = segmenter.segment_with_scores("テスト");
+    /// assert_eq!(words[0].start, 0);
+    /// assert_eq!(words.last().unwrap().end, "テスト".len());
+    /// ```
+    #[must_use]
+    pub fn segment_with_scores(&self, sentence: &str) -> Vec<ScoredWord> {
+        if sentence.is_empty() {
+            return Vec::new();
+        }
+        trace_span!(TRACE, "segment", bytes = sentence.len());
+        let mut tags = Self::initial_tags();
+        let (chars, types) = self.padded_chars(sentence);
+        let joined = self.grouping.joined(sentence);
 
+        let mut result = Vec::new();
+        let mut word = ScoredWord {
+            surface: chars[3].clone(),
+            start: 0,
+            end: chars[3].len(),
+            score: None,
+        };
         for i in 4..(chars.len() - 3) {
-            let label = if tags[i] == "B" { 1 } else { -1 };
-            let attrs = self.get_attributes(i, &tags, &chars, &types);
-            callback(attrs, label);
+            let score = self.score(&self.attributes(i, &tags, &chars, &types));
+            if self.places_boundary(score, &joined, i) {
+                let start = word.end;
+                result.push(core::mem::replace(
+                    &mut word,
+                    ScoredWord {
+                        surface: String::new(),
+                        start,
+                        end: start,
+                        score: Some(score),
+                    },
+                ));
+                tags.push("B".to_string());
+            } else {
+                tags.push("O".to_string());
+            }
+            word.surface += &chars[i];
+            word.end += chars[i].len();
         }
+        result.push(word);
+        result
     }
 
-    /// Adds a corpus to the segmenter with a custom writer function.
+    /// Returns the score of every boundary decision made by [`segment`](Self::segment),
+    /// the raw AdaBoost margin: one for each pair of adjacent characters, at least the
+    /// negated [boundary bias](Self::set_boundary_bias) where a boundary is placed. Callers
+    /// can threshold the scores themselves, e.g. splitting more for search indexing than
+    /// for display, without retraining.
     ///
     /// # Arguments
-    /// * `corpus` - A string slice representing the corpus to be added.
-    /// * `writer` - A closure that takes a HashSet of attributes and a label (i8) and writes them.
+    /// * `sentence` - A string slice representing the sentence to be parsed.
     ///
-    /// # Note
-    /// The writer function is called for each word in the corpus, allowing for custom handling of the attributes and labels.
+    /// # Returns
+    /// The scores in order, one fewer than the characters of the sentence.
     ///
     /// # Example
     /// ```
     /// use litsea::language::Language;
     /// use litsea::segmenter::Segmenter;
     ///
-    /// let segmenter = Segmenter::new(Language::Japanese, None);
-    /// segmenter.add_corpus_with_writer("テスト です", |attrs, label| {
-    ///    println!("Attributes: {:?}, Label: {}", attrs, label);
-    /// });
+    /// let model = include_str!("../../resources/RWCP.model");
+    /// let segmenter = Segmenter::from_model_str(Language::Japanese, model).unwrap();
+    /// let scores = segmenter.boundary_scores("これはテストです。");
+    /// assert_eq!(scores.len(), "これはテストです。".chars().count() - 1);
+    /// // Non-negative scores are the boundaries placed by `segment`.
+    /// let words = segmenter.segment("これはテストです。");
+    /// assert_eq!(scores.iter().filter(|s| **s >= 0.0).count() + 1, words.len());
     /// ```
-    ///
-    /// This will process the corpus and call the writer function for each word, passing the attributes and label.
-    pub fn add_corpus_with_writer<F>(&self, corpus: &str, writer: F)
-    where
-        F: FnMut(HashSet<String>, i8),
-    {
-        self.process_corpus(corpus, writer);
+    #[must_use]
+    pub fn boundary_scores(&self, sentence: &str) -> Vec<f64> {
+        if sentence.is_empty() {
+            return Vec::new();
+        }
+        let mut tags = Self::initial_tags();
+        let (chars, types) = self.padded_chars(sentence);
+
+        let joined = self.grouping.joined(sentence);
+
+        let mut scores = Vec::with_capacity(chars.len() - 7);
+        for i in 4..(chars.len() - 3) {
+            let score = self.score(&self.attributes(i, &tags, &chars, &types));
+            tags.push(if self.places_boundary(score, &joined, i) { "B" } else { "O" }.to_string());
+            scores.push(score);
+        }
+        scores
     }
 
-    /// Adds a corpus to the segmenter.
+    /// Returns the score of every boundary decision made by [`segment`](Self::segment).
+    #[deprecated(since = "0.4.0", note = "renamed to `boundary_scores`")]
+    #[must_use]
+    pub fn decision_scores(&self, sentence: &str) -> Vec<f64> {
+        self.boundary_scores(sentence)
+    }
+
+    /// Returns the features of every boundary decision made by [`segment`](Self::segment),
+    /// in the order of [`boundary_scores`](Self::boundary_scores).
     ///
     /// # Arguments
-    /// * `corpus` - A string slice representing the corpus to be added.
+    /// * `sentence` - A string slice representing the sentence to be parsed.
     ///
-    /// This method processes the corpus, extracts features, and adds instances to the AdaBoost learner.
-    /// If the corpus is empty, it does nothing.
+    /// # Returns
+    /// The features of each decision, one entry fewer than the characters of the sentence.
     ///
     /// # Example
     /// ```
     /// use litsea::language::Language;
     /// use litsea::segmenter::Segmenter;
     ///
-    /// let mut segmenter = Segmenter::new(Language::Japanese, None);
-    /// segmenter.add_corpus("テスト です");
+    /// let segmenter = Segmenter::new(Language::Japanese, None);
+    /// let features = segmenter.decision_features("テスト");
+    /// assert_eq!(features.len(), 2);
+    /// assert!(features[0].contains(&"UW4:ス".to_string()));
     /// ```
-    /// This will process the corpus and add instances to the segmenter.
-    pub fn add_corpus(&mut self, corpus: &str) {
-        let mut instances = Vec::new();
-        self.process_corpus(corpus, |attrs, label| {
-            instances.push((attrs, label));
-        });
-        for (attrs, label) in instances {
-            self.learner.add_instance(attrs, label);
+    #[must_use]
+    pub fn decision_features(&self, sentence: &str) -> Vec<Vec<String>> {
+        if sentence.is_empty() {
+            return Vec::new();
+        }
+        let mut tags = Self::initial_tags();
+        let (chars, types) = self.padded_chars(sentence);
+
+        let joined = self.grouping.joined(sentence);
+
+        let mut features = Vec::with_capacity(chars.len() - 7);
+        for i in 4..(chars.len() - 3) {
+            let attributes = self.attributes(i, &tags, &chars, &types);
+            let score = self.score(&attributes);
+            tags.push(if self.places_boundary(score, &joined, i) { "B" } else { "O" }.to_string());
+            features.push(attributes);
         }
+        features
     }
 
-    /// Segments a sentence into words.
+    /// Segments a sentence into the `n` most confident segmentations, exploring
+    /// alternative decisions for boundaries that are close to the decision threshold.
+    ///
+    /// The search keeps the `n` best partial segmentations (a beam) at every character,
+    /// ranked by their total margin, so later decisions see the boundaries placed by each
+    /// alternative. With `n = 1` the result is the segmentation of [`segment`](Self::segment).
     ///
     /// # Arguments
     /// * `sentence` - A string slice representing the sentence to be parsed.
+    /// * `n` - The maximum number of segmentations to return.
     ///
     /// # Returns
-    /// A vector of strings, where each string is a segmented word from the sentence.
-    ///
-    /// # Note
-    /// The method processes the sentence character by character, using the AdaBoost learner to predict whether a character is the beginning of a new word or not.
-    /// It constructs attributes based on the surrounding characters and their types, allowing for accurate segmentation.
-    /// If the sentence is empty, it returns an empty vector.
+    /// Up to `n` distinct segmentations, most confident first. Empty if the sentence is
+    /// empty or `n` is 0.
     ///
     /// # Example
     /// ```
-    /// use std::path::PathBuf;
-    ///
     /// use litsea::adaboost::AdaBoost;
     /// use litsea::language::Language;
     /// use litsea::segmenter::Segmenter;
     ///
-    /// # tokio_test::block_on(async {
-    /// let model_file =
-    ///     PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../resources").join("RWCP.model");
     /// let mut learner = AdaBoost::new(0.01, 100);
-    /// learner.load_model(model_file.to_str().unwrap()).await.unwrap();
+    /// learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
     ///
     /// let segmenter = Segmenter::new(Language::Japanese, Some(learner));
-    /// let result = segmenter.segment("これはテストです。");
-    /// assert_eq!(result, vec!["これ", "は", "テスト", "です", "。"]);
-    /// # });
+    /// let candidates = segmenter.segment_nbest("これはテストです。", 3);
+    /// assert_eq!(candidates.len(), 3);
+    /// assert_eq!(candidates[0].surfaces(), vec!["これ", "は", "テスト", "です", "。"]);
     /// ```
-    /// This will segment the sentence into words and return them as a vector of strings.
     #[must_use]
-    pub fn segment(&self, sentence: &str) -> Vec<String> {
-        if sentence.is_empty() {
+    pub fn segment_nbest(&self, sentence: &str, n: usize) -> Vec<Segmentation> {
+        if sentence.is_empty() || n == 0 {
             return Vec::new();
         }
-        let learner = &self.learner;
-        // Padding for lookback: tags[0..3] are fixed "U" (Unknown) for get_attributes(),
-        // and tags[3] is also "U" since there is no boundary decision before the first character.
-        let mut tags = vec!["U".to_string(); 4];
+        trace_span!(TRACE, "segment_nbest", bytes = sentence.len(), n = n);
+        let (chars, types) = self.padded_chars(sentence);
+        let joined = self.grouping.joined(sentence);
+
+        // Each candidate keeps its tags, the score of every boundary decision and its
+        // total margin.
+        let mut beam = vec![(Self::initial_tags(), Vec::new(), 0.0)];
+        for i in 4..(chars.len() - 3) {
+            let mut next = Vec::with_capacity(beam.len() * 2);
+            for (tags, scores, total) in beam {
+                let score = self.score(&self.attributes(i, &tags, &chars, &types));
+                let margin = score + self.bias;
+                if !is_joined(&joined, i) {
+                    let mut boundary_tags = tags.clone();
+                    boundary_tags.push("B".to_string());
+                    let mut boundary_scores = scores.clone();
+                    boundary_scores.push(score);
+                    next.push((boundary_tags, boundary_scores, total + margin));
+                }
+
+                let (mut tags, mut scores) = (tags, scores);
+                tags.push("O".to_string());
+                scores.push(score);
+                next.push((tags, scores, total - margin));
+            }
+            // The sort is stable, so ties keep the boundary first, as `segment` does.
+            next.sort_by(|a, b| b.2.total_cmp(&a.2));
+            next.truncate(n);
+            beam = next;
+        }
+
+        beam.into_iter()
+            .map(|(tags, scores, total)| {
+                let mut words = Vec::new();
+                let mut word = ScoredWord {
+                    surface: chars[3].clone(),
+                    start: 0,
+                    end: chars[3].len(),
+                    score: None,
+                };
+                for (j, i) in (4..(chars.len() - 3)).enumerate() {
+                    if tags[i] == "B" {
+                        let start = word.end;
+                        words.push(core::mem::replace(
+                            &mut word,
+                            ScoredWord {
+                                surface: String::new(),
+                                start,
+                                end: start,
+                                score: Some(scores[j]),
+                            },
+                        ));
+                    }
+                    word.surface += &chars[i];
+                    word.end += chars[i].len();
+                }
+                words.push(word);
+                Segmentation {
+                    words,
+                    score: total,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns `true` if a score places a boundary. TinySegmenter requires a score above
+    /// the negated bias, Litsea one at least equal to it.
+    fn is_boundary(&self, score: f64) -> bool {
+        match self.templates {
+            FeatureTemplates::Litsea => score >= -self.bias,
+            FeatureTemplates::TinySegmenter => score > -self.bias,
+        }
+    }
+
+    /// Returns `true` if a boundary is placed before the character at `i` of the padded
+    /// characters: if the score places one and the grouping rules allow it.
+    fn places_boundary(&self, score: f64, joined: &[bool], i: usize) -> bool {
+        self.is_boundary(score) && !is_joined(joined, i)
+    }
+
+    /// Returns the tags before the first boundary decision. tags[0..3] are fixed "U"
+    /// (Unknown) padding for lookback in get_attributes(), and tags[3] is also "U" since
+    /// there is no boundary decision before the first character.
+    fn initial_tags() -> Vec<String> {
+        vec!["U".to_string(); 4]
+    }
+
+    /// Splits a sentence into characters and their types, padded with three markers on
+    /// each side for the attribute window.
+    fn padded_chars(&self, sentence: &str) -> (Vec<String>, Vec<String>) {
         let mut chars = vec!["B3".to_string(), "B2".to_string(), "B1".to_string()];
         let mut types = vec!["O".to_string(); 3];
 
         for ch in sentence.chars() {
-            let s = ch.to_string();
-            types.push(self.get_type(&s).to_string());
-            chars.push(s);
+            types.push(self.char_types.get_char_type(ch).to_string());
+            chars.push(ch.to_string());
         }
         chars.extend_from_slice(&["E1".into(), "E2".into(), "E3".into()]);
         types.extend_from_slice(&["O".into(), "O".into(), "O".into()]);
-
-        let mut result = Vec::new();
-        let mut word = chars[3].clone();
-        for i in 4..(chars.len() - 3) {
-            let label = learner.predict(self.get_attributes(i, &tags, &chars, &types));
-            if label >= 0 {
-                result.push(std::mem::take(&mut word));
-                tags.push("B".to_string());
-            } else {
-                tags.push("O".to_string());
-            }
-            word += &chars[i];
-        }
-        result.push(word);
-        result
+        (chars, types)
     }
 
     /// Gets the attributes for a specific index in the character and type arrays.
@@ -247,6 +1226,7 @@ impl Segmenter {
     /// # Note
     /// The attributes are constructed based on the surrounding characters and their types, allowing for rich feature extraction.
     /// This method is used internally by the segmenter to create features for each character in the sentence.
+    #[cfg(feature = "std")]
     #[must_use]
     pub fn get_attributes(
         &self,
@@ -255,6 +1235,18 @@ impl Segmenter {
         chars: &[String],
         types: &[String],
     ) -> HashSet<String> {
+        self.attributes(i, tags, chars, types).into_iter().collect()
+    }
+
+    /// Builds the attributes for a specific index, as [`get_attributes`](Self::get_attributes)
+    /// does, without the `std` feature. The attributes are all distinct.
+    fn attributes(
+        &self,
+        i: usize,
+        tags: &[String],
+        chars: &[String],
+        types: &[String],
+    ) -> Vec<String> {
         let w1 = &chars[i - 3];
         let w2 = &chars[i - 2];
         let w3 = &chars[i - 1];
@@ -271,7 +1263,7 @@ impl Segmenter {
         let p2 = &tags[i - 2];
         let p3 = &tags[i - 1];
 
-        let mut attrs: HashSet<String> = [
+        let mut attrs = vec![
             format!("UP1:{}", p1),
             format!("UP2:{}", p2),
             format!("UP3:{}", p3),
@@ -286,6 +1278,16 @@ impl Segmenter {
             format!("BW1:{}{}", w2, w3),
             format!("BW2:{}{}", w3, w4),
             format!("BW3:{}{}", w4, w5),
+        ];
+        if self.templates == FeatureTemplates::TinySegmenter {
+            attrs.extend([
+                format!("TW1:{}{}{}", w1, w2, w3),
+                format!("TW2:{}{}{}", w2, w3, w4),
+                format!("TW3:{}{}{}", w3, w4, w5),
+                format!("TW4:{}{}{}", w4, w5, w6),
+            ]);
+        }
+        attrs.extend([
             format!("UC1:{}", c1),
             format!("UC2:{}", c2),
             format!("UC3:{}", c3),
@@ -310,32 +1312,83 @@ impl Segmenter {
             format!("TQ2:{}{}{}{}", p2, c2, c3, c4),
             format!("TQ3:{}{}{}{}", p3, c1, c2, c3),
             format!("TQ4:{}{}{}{}", p3, c2, c3, c4),
-        ]
-        .into_iter()
-        .collect();
+        ]);
 
         // Language-specific features: char + char-type mixed features for Japanese and Chinese.
         // Korean is excluded because its uniform character types (SN/SF only) make these features noise.
-        match self.language {
-            Language::Japanese | Language::Chinese => {
-                attrs.insert(format!("WC1:{}{}", w3, c4));
-                attrs.insert(format!("WC2:{}{}", c3, w4));
-                attrs.insert(format!("WC3:{}{}", w3, c3));
-                attrs.insert(format!("WC4:{}{}", w4, c4));
-            }
-            _ => {}
+        // TinySegmenter has none.
+        if self.templates == FeatureTemplates::Litsea
+            && matches!(self.language, Language::Japanese | Language::Chinese)
+        {
+            attrs.push(format!("WC1:{}{}", w3, c4));
+            attrs.push(format!("WC2:{}{}", c3, w4));
+            attrs.push(format!("WC3:{}{}", w3, c3));
+            attrs.push(format!("WC4:{}{}", w4, c4));
         }
 
         attrs
     }
 }
 
+/// Clones the settings and shares the model; the clone gets an empty score cache of the
+/// same capacity.
+impl Clone for Segmenter {
+    fn clone(&self) -> Self {
+        Segmenter {
+            language: self.language,
+            char_types: Arc::clone(&self.char_types),
+            templates: self.templates,
+            latin_classes: self.latin_classes,
+            grouping: self.grouping,
+            bias: self.bias,
+            learner: Arc::clone(&self.learner),
+            #[cfg(feature = "std")]
+            cache: self
+                .cache
+                .as_ref()
+                .map(|cache| ScoreCache::new(cache.shard_capacity * CACHE_SHARDS)),
+        }
+    }
+}
+
+/// Returns `true` if the grouping rules join the character at `i` of the padded
+/// characters to the one before it. `joined` is empty when no rule is enabled.
+fn is_joined(joined: &[bool], i: usize) -> bool {
+    joined.get(i - 3).copied().unwrap_or(false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[cfg(feature = "std")]
     use std::path::PathBuf;
 
+    #[test]
+    fn test_from_model_str() {
+        let segmenter =
+            Segmenter::from_model_str(Language::Japanese, "UW4:は\t2.0\nUW3:は\t2.0\n-1.0\n")
+                .unwrap();
+        assert_eq!(segmenter.segment("これはペン"), ["これ", "は", "ペン"]);
+
+        let result = Segmenter::from_model_str(Language::Japanese, "UW4:は\tx\n");
+        assert!(matches!(result, Err(crate::error::LitseaError::Parse { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_parse_with_spans() {
+        let segmenter =
+            Segmenter::from_model_str(Language::Japanese, "UW4:は\t2.0\nUW3:は\t2.0\n-1.0\n")
+                .unwrap();
+        let tokens = segmenter.parse_with_spans("これはペン");
+        let spans: Vec<(&str, usize, usize, usize, usize)> = tokens
+            .iter()
+            .map(|t| (t.surface.as_str(), t.start, t.end, t.char_start, t.char_end))
+            .collect();
+        assert_eq!(spans, [("これ", 0, 6, 0, 2), ("は", 6, 9, 2, 3), ("ペン", 9, 15, 3, 5)]);
+        assert!(segmenter.parse_with_spans("").is_empty());
+    }
+
     #[test]
     fn test_get_type_japanese() {
         let segmenter = Segmenter::new(Language::Japanese, None);
@@ -375,6 +1428,7 @@ mod tests {
         assert_eq!(segmenter.get_type("@"), "O"); // Other
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_add_corpus_with_writer() {
         let segmenter = Segmenter::new(Language::Japanese, None);
@@ -401,6 +1455,7 @@ mod tests {
         assert!(attrs.iter().any(|a| a.starts_with("UC")));
     }
 
+    #[cfg(feature = "train")]
     #[test]
     fn test_add_corpus() {
         let mut segmenter = Segmenter::new(Language::Japanese, None);
@@ -409,6 +1464,29 @@ mod tests {
         // Should not panic or add anything, just a smoke test
     }
 
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_learn_sentence() {
+        let mut learner = AdaBoost::new(0.01, 50);
+        learner.set_learning_rate(0.02).unwrap();
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        segmenter.set_score_cache(1000);
+        let others = ["これはテストです。", "私の名前は中野です。"];
+        let before: Vec<_> = others.iter().map(|s| segmenter.segment(s)).collect();
+        assert_ne!(segmenter.segment("吾輩は猫である。").join(" "), "吾輩 は 猫 で ある 。");
+
+        assert_eq!(segmenter.learn_sentence("吾輩 は 猫 で ある 。"), 7);
+        // Scores cached before learning are dropped.
+        assert_eq!(segmenter.cache_stats().unwrap().entries, 0);
+        assert_eq!(segmenter.segment("吾輩は猫である。").join(" "), "吾輩 は 猫 で ある 。");
+        for (sentence, expected) in others.iter().zip(before) {
+            assert_eq!(segmenter.segment(sentence), expected);
+        }
+        assert_eq!(segmenter.learn_sentence(""), 0);
+    }
+
+    #[cfg(feature = "std")]
     #[tokio::test]
     async fn test_segment() {
         let sentence = "これはテストです。";
@@ -434,6 +1512,31 @@ mod tests {
         assert_eq!(result[4], "。");
     }
 
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn test_segment_with_scores() {
+        let sentence = "これはテストです。";
+
+        let model_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources")
+            .join("RWCP.model");
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model(model_file.to_str().unwrap()).await.unwrap();
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+
+        let words = segmenter.segment_with_scores(sentence);
+        let surfaces: Vec<&str> = words.iter().map(|w| w.surface.as_str()).collect();
+        assert_eq!(surfaces, segmenter.segment(sentence));
+        for word in &words {
+            assert_eq!(&sentence[word.start..word.end], word.surface);
+            assert!((0.5..=1.0).contains(&word.confidence()));
+        }
+        assert_eq!(words[0].score, None);
+        assert_eq!(words[0].confidence(), 1.0);
+        assert!(words[1..].iter().all(|w| w.score.is_some_and(|s| s >= 0.0)));
+    }
+
+    #[cfg(feature = "train")]
     #[test]
     fn test_add_sentence_empty() {
         let mut segmenter = Segmenter::new(Language::Japanese, None);
@@ -441,6 +1544,258 @@ mod tests {
         // Should not panic or add anything
     }
 
+    #[test]
+    fn test_segment_with_model_str() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        assert_eq!(
+            segmenter.segment("これはテストです。"),
+            vec!["これ", "は", "テスト", "です", "。"]
+        );
+    }
+
+    #[test]
+    fn test_parse_alias() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        assert_eq!(segmenter.parse("これはテストです。"), segmenter.segment("これはテストです。"));
+    }
+
+    #[test]
+    fn test_segment_nbest() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentence = "東京都に住んでいます。";
+
+        let best = segmenter.segment_nbest(sentence, 1);
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].words, segmenter.segment_with_scores(sentence));
+
+        let candidates = segmenter.segment_nbest(sentence, 5);
+        assert_eq!(candidates.len(), 5);
+        assert!(candidates.windows(2).all(|w| w[0].score >= w[1].score));
+        assert_eq!(segmenter.parse_nbest(sentence, 5), candidates);
+        for (i, candidate) in candidates.iter().enumerate() {
+            assert_eq!(candidate.surfaces().concat(), sentence);
+            for word in &candidate.words {
+                assert_eq!(&sentence[word.start..word.end], word.surface);
+            }
+            assert!(candidates[..i].iter().all(|c| c.surfaces() != candidate.surfaces()));
+        }
+
+        assert!(segmenter.segment_nbest("", 5).is_empty());
+        assert!(segmenter.segment_nbest(sentence, 0).is_empty());
+        // A single character has only one segmentation.
+        assert_eq!(segmenter.segment_nbest("あ", 5).len(), 1);
+    }
+
+    #[test]
+    fn test_grouping_rules() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentence = "2024年に1,000円払った";
+        assert!(segmenter.segment(sentence).contains(&"0".to_string()));
+
+        segmenter.set_grouping_rules(GroupingRules::all());
+        let words = segmenter.segment(sentence);
+        assert!(words[0].starts_with("2024"));
+        assert!(words.contains(&"1,000".to_string()));
+        assert_eq!(segmenter.segment("Caféで読む")[0], "Café");
+        assert_eq!(
+            segmenter.segment("https://example.com/pathを見て")[0],
+            "https://example.com/path"
+        );
+
+        // The other methods make the same decisions.
+        let scores = segmenter.boundary_scores(sentence);
+        assert_eq!(scores.len(), sentence.chars().count() - 1);
+        for candidate in segmenter.segment_nbest(sentence, 5) {
+            assert!(candidate.surfaces()[0].starts_with("2024"));
+        }
+        assert_eq!(
+            segmenter.segment_nbest(sentence, 1)[0].words,
+            segmenter.segment_with_scores(sentence)
+        );
+    }
+
+    #[test]
+    fn test_boundary_scores() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentence = "東京都に住んでいます。";
+
+        let scores = segmenter.boundary_scores(sentence);
+        assert_eq!(scores.len(), sentence.chars().count() - 1);
+        let boundaries: Vec<f64> =
+            segmenter.segment_with_scores(sentence).iter().filter_map(|w| w.score).collect();
+        let placed: Vec<f64> = scores.iter().copied().filter(|s| *s >= 0.0).collect();
+        assert_eq!(placed, boundaries);
+        assert!(segmenter.boundary_scores("").is_empty());
+        assert!(segmenter.boundary_scores("あ").is_empty());
+    }
+
+    #[test]
+    fn test_boundary_bias() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentence = "東京都に住んでいます。";
+        let default = segmenter.segment(sentence);
+        assert_eq!(segmenter.boundary_bias(), 0.0);
+
+        segmenter.set_boundary_bias(0.5);
+        assert_eq!(segmenter.boundary_bias(), 0.5);
+        let aggressive = segmenter.segment(sentence);
+        assert!(aggressive.len() > default.len(), "{:?}", aggressive);
+        // Every method follows the bias.
+        let placed = segmenter.boundary_scores(sentence).iter().filter(|s| **s >= -0.5).count();
+        assert_eq!(placed + 1, aggressive.len());
+        assert_eq!(segmenter.segment_nbest(sentence, 1)[0].surfaces(), aggressive);
+
+        segmenter.set_boundary_bias(-0.5);
+        assert!(segmenter.segment(sentence).len() < default.len());
+
+        segmenter.set_boundary_bias(f64::NEG_INFINITY);
+        assert_eq!(segmenter.segment(sentence), [sentence]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_concurrent_segment() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentences = ["東京都に住んでいます。", "これはテストです。", "今日は晴れ。"];
+        let expected: Vec<Vec<String>> = sentences.iter().map(|s| segmenter.segment(s)).collect();
+
+        // Threads share the segmenter, and its cache, without copying the model.
+        segmenter.set_score_cache(1000);
+        let segmenter = std::sync::Arc::new(segmenter);
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let segmenter = std::sync::Arc::clone(&segmenter);
+                std::thread::spawn(move || {
+                    (0..50)
+                        .map(|j| segmenter.segment(sentences[(i + j) % sentences.len()]))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            for (j, words) in handle.join().unwrap().into_iter().enumerate() {
+                assert_eq!(words, expected[(i + j) % sentences.len()]);
+            }
+        }
+        // Every decision went through the cache once.
+        let num_decisions: usize = (0..8)
+            .flat_map(|i| (0..50).map(move |j| sentences[(i + j) % sentences.len()]))
+            .map(|s| s.chars().count() - 1)
+            .sum();
+        let stats = segmenter.cache_stats().unwrap();
+        assert_eq!(stats.hits + stats.misses, num_decisions);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_score_cache() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        let sentence = "東京都に住んでいます。";
+        let expected = segmenter.segment_with_scores(sentence);
+        assert!(segmenter.cache_stats().is_none());
+
+        segmenter.set_score_cache(1000);
+        assert_eq!(segmenter.segment_with_scores(sentence), expected);
+        let stats = segmenter.cache_stats().unwrap();
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 10);
+        assert_eq!(stats.entries, 10);
+        assert_eq!(segmenter.segment_with_scores(sentence), expected);
+        assert_eq!(segmenter.segment_nbest(sentence, 1)[0].words, expected);
+        let stats = segmenter.cache_stats().unwrap();
+        assert_eq!(stats.hits, 20);
+        assert_eq!(stats.hit_rate(), 20.0 / 30.0 * 100.0);
+
+        // A full shard is cleared rather than growing past the capacity.
+        segmenter.set_score_cache(1);
+        assert_eq!(segmenter.segment_with_scores(sentence), expected);
+        assert!(segmenter.cache_stats().unwrap().entries <= CACHE_SHARDS);
+
+        segmenter.set_score_cache(0);
+        assert!(segmenter.cache_stats().is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_shared_model() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let model = Arc::new(learner);
+        let segmenter = Segmenter::from_shared(Language::Japanese, Arc::clone(&model));
+        let mut biased = segmenter.clone();
+        biased.set_boundary_bias(f64::NEG_INFINITY);
+        assert!(Arc::ptr_eq(&biased.shared_learner(), &model));
+
+        let sentence = "東京都に住んでいます。";
+        let expected = segmenter.segment(sentence);
+        let segmenters: Vec<Segmenter> = (0..4).map(|_| segmenter.clone()).collect();
+        std::thread::scope(|s| {
+            let handles: Vec<_> = segmenters
+                .iter()
+                .map(|segmenter| s.spawn(|| segmenter.segment(sentence)))
+                .collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), expected);
+            }
+            let biased = s.spawn(|| biased.segment(sentence)).join().unwrap();
+            assert_eq!(biased, [sentence]);
+        });
+        assert_eq!(Arc::strong_count(&model), 7);
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_learn_shared_model() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let model = Arc::new(learner);
+        let mut segmenter = Segmenter::from_shared(Language::Japanese, Arc::clone(&model));
+
+        // Training a segmenter gives it its own copy rather than changing the shared model.
+        segmenter.learn_sentence("東京 都 に 住ん で い ます 。");
+        assert!(!Arc::ptr_eq(&segmenter.shared_learner(), &model));
+        assert_eq!(Arc::strong_count(&model), 1);
+
+        // Changing the weights drops the cached scores.
+        segmenter.set_score_cache(1000);
+        let _ = segmenter.segment("東京都に住んでいます。");
+        assert!(segmenter.cache_stats().unwrap().entries > 0);
+        segmenter.learner_mut().prune(1.0);
+        assert_eq!(segmenter.cache_stats().unwrap().entries, 0);
+        assert!(segmenter.into_learner().weights().count() < model.weights().count());
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_add_sentence_alias() {
+        let mut by_corpus = Segmenter::new(Language::Japanese, None);
+        by_corpus.add_corpus("テスト です");
+        let mut by_sentence = Segmenter::new(Language::Japanese, None);
+        by_sentence.add_sentence("テスト です");
+
+        assert_eq!(
+            by_sentence.learner().get_metrics().num_instances,
+            by_corpus.learner().get_metrics().num_instances
+        );
+        assert_eq!(by_sentence.learner().get_metrics().num_instances, 4);
+    }
+
     #[test]
     fn test_segment_empty_sentence() {
         let segmenter = Segmenter::new(Language::Japanese, None);
@@ -448,6 +1803,7 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_get_attributes() {
         let segmenter = Segmenter::new(Language::Japanese, None);
@@ -487,6 +1843,7 @@ mod tests {
         assert_eq!(attrs.len(), 42);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     #[should_panic]
     fn test_get_attributes_panics_index_too_low() {
@@ -506,6 +1863,7 @@ mod tests {
         let _ = segmenter.get_attributes(2, &tags, &chars, &types);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     #[should_panic]
     fn test_get_attributes_panics_index_too_high() {
@@ -525,6 +1883,7 @@ mod tests {
         let _ = segmenter.get_attributes(5, &tags, &chars, &types);
     }
 
+    #[cfg(feature = "std")]
     #[test]
     fn test_get_attributes_korean() {
         let segmenter = Segmenter::new(Language::Korean, None);
@@ -560,4 +1919,75 @@ mod tests {
         // 38 base features only (Korean does not include WC word-character features)
         assert_eq!(attrs.len(), 38);
     }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_get_attributes_tinysegmenter() {
+        let mut segmenter = Segmenter::new(Language::Japanese, None);
+        segmenter.set_feature_templates(FeatureTemplates::TinySegmenter);
+        assert_eq!(segmenter.feature_templates(), FeatureTemplates::TinySegmenter);
+
+        let tags = vec!["U".to_string(); 7];
+        let chars: Vec<String> = ["B3", "B2", "B1", "あ", "い", "う", "E1"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let types: Vec<String> =
+            ["O", "O", "O", "I", "I", "I", "O"].iter().map(|t| t.to_string()).collect();
+
+        let attrs = segmenter.get_attributes(4, &tags, &chars, &types);
+        assert!(attrs.contains("TW1:B2B1あ"));
+        assert!(attrs.contains("TW4:いうE1"));
+        assert!(!attrs.iter().any(|attr| attr.starts_with("WC")));
+        // 38 base features + 4 word trigrams, as in the original TinySegmenter
+        assert_eq!(attrs.len(), 42);
+    }
+
+    #[test]
+    fn test_feature_templates_tinysegmenter() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model_str(include_str!("../../resources/RWCP.model")).unwrap();
+        let mut segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        segmenter.set_feature_templates(FeatureTemplates::TinySegmenter);
+        // TinySegmenter has no punctuation class.
+        assert_eq!(segmenter.get_type("。"), "O");
+        assert_eq!(segmenter.get_type("ｱ"), "K");
+        assert_eq!(
+            segmenter.segment("私の名前は中野です。"),
+            vec!["私", "の", "名前", "は", "中野", "です", "。"]
+        );
+
+        segmenter.set_feature_templates(FeatureTemplates::Litsea);
+        assert_eq!(segmenter.get_type("。"), "P");
+    }
+
+    #[test]
+    fn test_parse_feature_templates() {
+        for templates in [FeatureTemplates::Litsea, FeatureTemplates::TinySegmenter] {
+            assert_eq!(templates.to_string().parse::<FeatureTemplates>(), Ok(templates));
+        }
+        assert_eq!("TinySegmenter".parse(), Ok(FeatureTemplates::TinySegmenter));
+        assert!("kytea".parse::<FeatureTemplates>().is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_from_store() {
+        let model = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/RWCP.model");
+        let mut learner = AdaBoost::new(0.01, 100);
+        tokio_test::block_on(learner.load_model(model)).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("models.db");
+        let mut store = crate::model_store::ModelStore::open(&path).unwrap();
+        store.put("rwcp", Language::Japanese, "RWCP", &learner).unwrap();
+
+        let segmenter = Segmenter::from_store(&path, "rwcp").unwrap();
+        assert_eq!(segmenter.language, Language::Japanese);
+        assert_eq!(
+            segmenter.segment("これはテストです。"),
+            vec!["これ", "は", "テスト", "です", "。"]
+        );
+        assert!(Segmenter::from_store(&path, "missing").is_err());
+    }
 }
@@ -0,0 +1,350 @@
+//! Semi-supervised self-training on unlabeled text.
+//!
+//! Labeled corpora are small, but unlabeled text is plentiful. A [`SelfTrainer`] grows a
+//! model from unlabeled text in rounds: it segments every sentence with the current
+//! model, keeps the sentences whose least certain boundary decision is still above a
+//! confidence threshold, extracts features from them (together with the labeled corpus,
+//! if any) and trains a new model, which is used in the next round.
+//!
+//! Each round writes its pseudo-labeled corpus, features and model to a working
+//! directory as `round-<n>.corpus`, `round-<n>.features` and `round-<n>.model`.
+
+use crate::adaboost::{AdaBoost, Boosting, Metrics};
+use crate::compression;
+use crate::control::TrainControl;
+use crate::error::LitseaError;
+use crate::extractor::Extractor;
+use crate::language::Language;
+use crate::normalize::Normalizer;
+use crate::segmenter::Segmenter;
+use crate::trainer::Trainer;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// The result of one round of self-training.
+#[derive(Debug, Clone)]
+pub struct RoundReport {
+    /// The round, starting at 1.
+    pub round: usize,
+    /// The number of non-empty sentences in the unlabeled corpus.
+    pub sentences: usize,
+    /// The number of sentences segmented confidently enough to train on.
+    pub selected: usize,
+    /// The model trained in this round.
+    pub model_file: PathBuf,
+    /// The metrics of the model on its training data.
+    pub metrics: Metrics,
+}
+
+/// Self-training of a segmentation model on unlabeled text.
+#[derive(Debug, Clone)]
+pub struct SelfTrainer {
+    language: Language,
+    normalizer: Normalizer,
+    confidence: f64,
+    rounds: usize,
+    threshold: f64,
+    num_iterations: usize,
+    boosting: Boosting,
+    labeled_corpus: Option<PathBuf>,
+}
+
+impl SelfTrainer {
+    /// Creates a self-trainer with 3 rounds, a confidence threshold of 0.55 and the
+    /// training defaults of `litsea train`.
+    ///
+    /// # Arguments
+    /// * `language` - The language of the corpora.
+    ///
+    /// # Returns
+    /// A new [`SelfTrainer`].
+    #[must_use]
+    pub fn new(language: Language) -> Self {
+        SelfTrainer {
+            language,
+            normalizer: Normalizer::default(),
+            confidence: 0.55,
+            rounds: 3,
+            threshold: 0.01,
+            num_iterations: 100,
+            boosting: Boosting::AdaBoost,
+            labeled_corpus: None,
+        }
+    }
+
+    /// Sets the minimum confidence of a sentence, between 0.5 and 1. The confidence of a
+    /// sentence is that of its least certain boundary decision, `1 / (1 + exp(-2 * |score|))`
+    /// as in [`ScoredWord::confidence`](crate::segmenter::ScoredWord::confidence).
+    ///
+    /// # Arguments
+    /// * `confidence` - The minimum confidence.
+    ///
+    /// # Returns
+    /// The self-trainer with the threshold set.
+    #[must_use]
+    pub fn with_confidence(mut self, confidence: f64) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Sets the number of rounds of segmenting and retraining.
+    ///
+    /// # Arguments
+    /// * `rounds` - The number of rounds.
+    ///
+    /// # Returns
+    /// The self-trainer with the number of rounds set.
+    #[must_use]
+    pub fn with_rounds(mut self, rounds: usize) -> Self {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Sets the training parameters of each round, as `litsea train --threshold` and
+    /// `--num-iterations` do.
+    ///
+    /// # Arguments
+    /// * `threshold` - The threshold for stopping the training.
+    /// * `num_iterations` - The maximum number of iterations.
+    ///
+    /// # Returns
+    /// The self-trainer with the training parameters set.
+    #[must_use]
+    pub fn with_training(mut self, threshold: f64, num_iterations: usize) -> Self {
+        self.threshold = threshold;
+        self.num_iterations = num_iterations;
+        self
+    }
+
+    /// Sets how instances are reweighted during training. Pseudo-labeled sentences contain
+    /// boundary errors, which [`Boosting::Capped`] keeps from dominating the training.
+    ///
+    /// # Arguments
+    /// * `boosting` - The boosting variant.
+    ///
+    /// # Returns
+    /// The self-trainer with the boosting variant set.
+    #[must_use]
+    pub fn with_boosting(mut self, boosting: Boosting) -> Self {
+        self.boosting = boosting;
+        self
+    }
+
+    /// Sets a labeled corpus that is added to the pseudo-labeled sentences of every round,
+    /// so the model does not drift away from the gold segmentation.
+    ///
+    /// # Arguments
+    /// * `path` - The corpus, one sentence per line with words separated by spaces.
+    ///
+    /// # Returns
+    /// The self-trainer with the labeled corpus set.
+    #[must_use]
+    pub fn with_labeled_corpus<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.labeled_corpus = Some(path.into());
+        self
+    }
+
+    /// Sets the normalization applied to the unlabeled text before segmenting it.
+    ///
+    /// # Arguments
+    /// * `normalizer` - The normalization settings.
+    ///
+    /// # Returns
+    /// The self-trainer with the normalizer set.
+    #[must_use]
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Returns the confidence of a segmentation from its boundary decision scores: that of
+    /// the least certain decision, or 1 if there is no decision to make.
+    ///
+    /// # Arguments
+    /// * `scores` - The scores returned by [`Segmenter::boundary_scores`].
+    #[must_use]
+    pub fn sentence_confidence(scores: &[f64]) -> f64 {
+        scores
+            .iter()
+            .map(|s| s.abs())
+            .reduce(f64::min)
+            .map_or(1.0, |margin| 1.0 / (1.0 + (-2.0 * margin).exp()))
+    }
+
+    /// Writes the confidently segmented sentences of an unlabeled corpus, and the labeled
+    /// corpus if set, as a segmented corpus.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter with the current model.
+    /// * `unlabeled` - The unlabeled corpus, one sentence per line.
+    /// * `writer` - The destination of the segmented corpus.
+    ///
+    /// # Returns
+    /// The number of non-empty unlabeled sentences and the number of those selected.
+    ///
+    /// # Errors
+    /// Returns an error if a corpus cannot be read or the output cannot be written.
+    pub fn select<R: BufRead, W: Write>(
+        &self,
+        segmenter: &Segmenter,
+        unlabeled: R,
+        writer: &mut W,
+    ) -> io::Result<(usize, usize)> {
+        let (mut sentences, mut selected) = (0, 0);
+        for line in unlabeled.lines() {
+            let line = line?;
+            let line = self.normalizer.normalize(line.trim());
+            if line.is_empty() {
+                continue;
+            }
+            sentences += 1;
+            if Self::sentence_confidence(&segmenter.boundary_scores(&line)) >= self.confidence {
+                selected += 1;
+                writeln!(writer, "{}", segmenter.segment(&line).join(" "))?;
+            }
+        }
+        if let Some(path) = &self.labeled_corpus {
+            for line in compression::open(path)?.lines() {
+                writeln!(writer, "{}", line?)?;
+            }
+        }
+        Ok((sentences, selected))
+    }
+
+    /// Runs the configured number of rounds, starting from a model.
+    ///
+    /// # Arguments
+    /// * `model` - The initial model, e.g. one trained on the labeled corpus.
+    /// * `unlabeled` - The unlabeled corpus, one sentence per line.
+    /// * `work_dir` - The directory for the files of each round; created if missing.
+    /// * `control` - Stops, pauses or resumes the training of the rounds. A stop ends the
+    ///   run after the current round, which keeps the model trained until then.
+    ///
+    /// # Returns
+    /// The report of every completed round. The model of the last round is the result.
+    ///
+    /// # Errors
+    /// Returns an error if a corpus cannot be read, a round selects no sentences and there
+    /// is no labeled corpus, or a file of a round cannot be written.
+    pub fn run(
+        &self,
+        model: AdaBoost,
+        unlabeled: &Path,
+        work_dir: &Path,
+        control: impl Into<TrainControl>,
+    ) -> crate::error::Result<Vec<RoundReport>> {
+        let control = control.into();
+        fs::create_dir_all(work_dir)?;
+        let mut segmenter = Segmenter::new(self.language, Some(model));
+        let mut reports = Vec::with_capacity(self.rounds);
+        for round in 1..=self.rounds {
+            if control.is_stopped() {
+                break;
+            }
+            trace_span!(INFO, "self_training_round", round = round);
+            let corpus_file = work_dir.join(format!("round-{}.corpus", round));
+            let features_file = work_dir.join(format!("round-{}.features", round));
+            let model_file = work_dir.join(format!("round-{}.model", round));
+
+            let mut writer = BufWriter::new(File::create(&corpus_file)?);
+            let unlabeled_reader = compression::open(unlabeled)?;
+            let (sentences, selected) = self.select(&segmenter, unlabeled_reader, &mut writer)?;
+            writer.flush()?;
+            if selected == 0 && self.labeled_corpus.is_none() {
+                return Err(LitseaError::InvalidInput(format!(
+                    "Round {}: no sentence reached the confidence threshold {}",
+                    round, self.confidence
+                )));
+            }
+            trace_event!(info, round, sentences, selected, "selected sentences");
+
+            let mut extractor = Extractor::new(self.language);
+            extractor.extract(&corpus_file, &features_file)?;
+            let mut trainer = Trainer::new(self.threshold, self.num_iterations, &features_file)?;
+            trainer.set_boosting(self.boosting)?;
+            let metrics = trainer.train(control.clone(), &model_file)?;
+
+            let mut learner = AdaBoost::new(self.threshold, self.num_iterations);
+            learner.load_model_str(&fs::read_to_string(&model_file)?)?;
+            segmenter = Segmenter::new(self.language, Some(learner));
+            reports.push(RoundReport {
+                round,
+                sentences,
+                selected,
+                model_file,
+                metrics,
+            });
+        }
+        Ok(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::rwcp_segmenter;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sentence_confidence() {
+        assert_eq!(SelfTrainer::sentence_confidence(&[]), 1.0);
+        assert_eq!(SelfTrainer::sentence_confidence(&[0.0, 3.0]), 0.5);
+        let confidence = SelfTrainer::sentence_confidence(&[2.0, -1.0]);
+        assert!((confidence - 1.0 / (1.0 + (-2.0f64).exp())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_select() {
+        let segmenter = rwcp_segmenter();
+        let input = "これはテストです。\n\n東京都に住んでいます。\n";
+        let mut out = Vec::new();
+        let (sentences, selected) = SelfTrainer::new(Language::Japanese)
+            .with_confidence(0.5)
+            .select(&segmenter, input.as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!((sentences, selected), (2, 2));
+        let corpus = String::from_utf8(out).unwrap();
+        assert_eq!(corpus.lines().next(), Some("これ は テスト です 。"));
+
+        let mut out = Vec::new();
+        let (_, selected) = SelfTrainer::new(Language::Japanese)
+            .with_confidence(1.0)
+            .select(&segmenter, input.as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(selected, 0);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = TempDir::new().unwrap();
+        let unlabeled = dir.path().join("unlabeled.txt");
+        fs::write(&unlabeled, "これはテストです。\n東京都に住んでいます。\n今日は晴れです。\n")
+            .unwrap();
+        let labeled = dir.path().join("labeled.txt");
+        fs::write(&labeled, "これ は ペン です 。\n").unwrap();
+
+        let reports = SelfTrainer::new(Language::Japanese)
+            .with_rounds(2)
+            .with_confidence(0.5)
+            .with_training(0.01, 10)
+            .with_boosting(Boosting::Capped(4.0))
+            .with_labeled_corpus(&labeled)
+            .run(rwcp_segmenter().into_learner(), &unlabeled, dir.path(), TrainControl::new())
+            .unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].sentences, 3);
+        assert!(reports.iter().all(|r| r.model_file.exists()));
+        assert!(dir.path().join("round-2.corpus").exists());
+
+        // A stopped run trains nothing.
+        let control = TrainControl::new();
+        control.stop();
+        let reports = SelfTrainer::new(Language::Japanese)
+            .run(rwcp_segmenter().into_learner(), &unlabeled, dir.path(), control)
+            .unwrap();
+        assert!(reports.is_empty());
+    }
+}
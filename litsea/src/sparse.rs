@@ -0,0 +1,103 @@
+//! Compressed sparse row (CSR) storage of the feature indices of training instances.
+//!
+//! Each instance is a row of the feature indices it has, in ascending order. A
+//! [`SparseMatrix`] holds the indices of all rows back to back in one `u32` array, and
+//! the start of each row in another, so that a row takes 4 bytes per feature and 8
+//! bytes of offset on 64-bit targets, and the rows of a range of instances are one
+//! contiguous slice that the training loop reads from start to end.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Feature indices of instances in compressed sparse row format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SparseMatrix {
+    /// The start of each row in `indices`, followed by the end of the last row.
+    indptr: Vec<usize>,
+    /// The feature indices of all rows, back to back.
+    indices: Vec<u32>,
+}
+
+impl Default for SparseMatrix {
+    fn default() -> Self {
+        SparseMatrix {
+            indptr: vec![0],
+            indices: Vec::new(),
+        }
+    }
+}
+
+impl SparseMatrix {
+    /// Returns the number of rows.
+    pub(crate) fn num_rows(&self) -> usize {
+        self.indptr.len() - 1
+    }
+
+    /// Returns the number of feature indices of all rows.
+    #[cfg(test)]
+    pub(crate) fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Returns the feature indices of row `i`.
+    ///
+    /// # Panics
+    /// Panics if `i` is not less than the number of rows.
+    pub(crate) fn row(&self, i: usize) -> &[u32] {
+        &self.indices[self.indptr[i]..self.indptr[i + 1]]
+    }
+
+    /// Appends a row and returns its indices, in the order given, e.g. to sort them.
+    pub(crate) fn push_row<I: IntoIterator<Item = u32>>(&mut self, indices: I) -> &mut [u32] {
+        let start = self.indices.len();
+        self.indices.extend(indices);
+        self.indptr.push(self.indices.len());
+        &mut self.indices[start..]
+    }
+
+    /// Reserves capacity for `rows` more rows with `nnz` indices in total.
+    pub(crate) fn reserve(&mut self, rows: usize, nnz: usize) {
+        self.indptr.reserve(rows);
+        self.indices.reserve(nnz);
+    }
+
+    /// Replaces every index `h` with `remap[h]`, and sorts the indices of each row again.
+    pub(crate) fn remap(&mut self, remap: &[u32]) {
+        for h in &mut self.indices {
+            *h = remap[*h as usize];
+        }
+        for bounds in self.indptr.windows(2) {
+            self.indices[bounds[0]..bounds[1]].sort_unstable();
+        }
+    }
+
+    /// Returns the heap memory held by the matrix, in bytes.
+    pub(crate) fn heap_size(&self) -> usize {
+        self.indptr.capacity() * core::mem::size_of::<usize>()
+            + self.indices.capacity() * core::mem::size_of::<u32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        let mut matrix = SparseMatrix::default();
+        assert_eq!(matrix.num_rows(), 0);
+        matrix.push_row([3, 1]).sort_unstable();
+        matrix.push_row([]);
+        matrix.push_row([2]);
+        assert_eq!(matrix.num_rows(), 3);
+        assert_eq!(matrix.nnz(), 3);
+        assert_eq!(matrix.row(0), [1, 3]);
+        assert!(matrix.row(1).is_empty());
+        assert_eq!(matrix.row(2), [2]);
+
+        // 1 -> 2, 2 -> 0, 3 -> 1: the first row becomes [2, 1] and is sorted again.
+        matrix.remap(&[0, 2, 0, 1]);
+        assert_eq!(matrix.row(0), [1, 2]);
+        assert_eq!(matrix.row(2), [0]);
+    }
+}
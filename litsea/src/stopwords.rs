@@ -0,0 +1,218 @@
+//! Stopword sets for dropping very common function words, e.g. before indexing.
+//!
+//! [`StopWords::japanese`] provides a bundled list of Japanese particles, auxiliary
+//! verbs, formal nouns and pronouns. Sets can be extended with custom words or files of
+//! one word per line, and are applied to tokens as a [`TokenFilter`].
+//!
+//! ```
+//! use litsea::stopwords::StopWords;
+//!
+//! let mut stopwords = StopWords::japanese();
+//! stopwords.insert("テスト");
+//! assert!(stopwords.contains("は"));
+//! assert!(stopwords.contains("テスト"));
+//! assert!(!stopwords.contains("東京"));
+//! ```
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::token::{Token, TokenFilter};
+
+/// The bundled Japanese stopwords, separated by whitespace: particles, auxiliary verbs
+/// and copulas, formal nouns, pronouns and demonstratives, and conjunctions.
+const JAPANESE: &str = "\
+    が の を に へ と で や から より まで は も か な ね \
+    よ わ ぞ さ て ば し だけ ほど くらい ぐらい など なり やら こそ でも \
+    しか さえ ばかり のみ って けど けれど けれども ので のに ながら つつ たり だり \
+    だ です ます た ない ぬ ん う よう らしい れる られる せる させる たい でし \
+    まし ませ だっ なかっ ある いる する なる なっ いう おり おる \
+    こと もの ため とき ところ はず わけ まま うち ほう \
+    これ それ あれ どれ この その あの どの ここ そこ あそこ どこ こちら そちら あちら どちら \
+    こう そう ああ どう 私 僕 俺 あなた 彼 彼女 我々 自分 \
+    また および 及び かつ しかし そして それで ただし なお または 又は ならびに 並びに つまり";
+
+/// A set of words to drop from segmentation output.
+#[derive(Debug, Clone, Default)]
+pub struct StopWords {
+    words: HashSet<String>,
+}
+
+impl StopWords {
+    /// Creates an empty set.
+    ///
+    /// # Returns
+    /// A new, empty [`StopWords`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a set with the bundled Japanese stopwords.
+    ///
+    /// # Returns
+    /// A new [`StopWords`] with the Japanese list.
+    #[must_use]
+    pub fn japanese() -> Self {
+        let mut stopwords = Self::new();
+        stopwords.extend(JAPANESE.split_whitespace());
+        stopwords
+    }
+
+    /// Adds a word to the set.
+    ///
+    /// # Arguments
+    /// * `word` - The word to add.
+    pub fn insert(&mut self, word: &str) {
+        self.words.insert(word.to_string());
+    }
+
+    /// Adds words to the set.
+    ///
+    /// # Arguments
+    /// * `words` - The words to add.
+    pub fn extend<I>(&mut self, words: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.words.extend(words.into_iter().map(|w| w.as_ref().to_string()));
+    }
+
+    /// Removes a word from the set.
+    ///
+    /// # Arguments
+    /// * `word` - The word to remove.
+    ///
+    /// # Returns
+    /// `true` if the word was in the set.
+    pub fn remove(&mut self, word: &str) -> bool {
+        self.words.remove(word)
+    }
+
+    /// Adds the words of a file with one word per line. Empty lines and lines starting
+    /// with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the word list.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            let word = line.trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                self.insert(word);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if the word is a stopword.
+    ///
+    /// # Arguments
+    /// * `word` - The word to check.
+    #[must_use]
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// Returns the number of words in the set.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns `true` if the set has no words.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns `true` if the token is a stopword, by its surface or its lemma.
+    ///
+    /// # Arguments
+    /// * `token` - The token to check.
+    #[must_use]
+    pub fn is_stopword(&self, token: &Token) -> bool {
+        self.contains(&token.surface) || token.lemma.as_deref().is_some_and(|l| self.contains(l))
+    }
+}
+
+impl TokenFilter for StopWords {
+    fn apply(&self, tokens: &mut Vec<Token>) {
+        tokens.retain(|token| !self.is_stopword(token));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    fn token(surface: &str) -> Token {
+        Token {
+            surface: surface.to_string(),
+            ..Token::default()
+        }
+    }
+
+    #[test]
+    fn test_japanese() {
+        let stopwords = StopWords::japanese();
+        assert!(stopwords.contains("の"));
+        assert!(stopwords.contains("です"));
+        assert!(stopwords.contains("こと"));
+        assert!(!stopwords.contains("テスト"));
+        assert!(StopWords::new().is_empty());
+    }
+
+    #[test]
+    fn test_insert_remove() {
+        let mut stopwords = StopWords::new();
+        stopwords.extend(["えー", "あの"]);
+        stopwords.insert("まあ");
+        assert_eq!(stopwords.len(), 3);
+        assert!(stopwords.remove("あの"));
+        assert!(!stopwords.remove("あの"));
+        assert!(!stopwords.contains("あの"));
+    }
+
+    #[test]
+    fn test_load() {
+        let mut file = NamedTempFile::new().unwrap();
+        writeln!(file, "# fillers\nえー\n\n  まあ  ").unwrap();
+        let mut stopwords = StopWords::new();
+        stopwords.load(file.path()).unwrap();
+        assert_eq!(stopwords.len(), 2);
+        assert!(stopwords.contains("まあ"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut tokens =
+            vec![token("これ"), token("は"), token("テスト"), token("です"), token("。")];
+        StopWords::japanese().apply(&mut tokens);
+        let surfaces: Vec<&str> = tokens.iter().map(|t| t.surface.as_str()).collect();
+        assert_eq!(surfaces, vec!["テスト", "。"]);
+    }
+
+    #[test]
+    fn test_apply_by_lemma() {
+        let mut tokens = vec![Token {
+            surface: "でし".to_string(),
+            lemma: Some("です".to_string()),
+            ..Token::default()
+        }];
+        let mut stopwords = StopWords::new();
+        stopwords.insert("です");
+        stopwords.apply(&mut tokens);
+        assert!(tokens.is_empty());
+    }
+}
@@ -0,0 +1,366 @@
+//! Part-of-speech tagging of segmented words.
+//!
+//! A [`Tagger`] assigns one tag, such as a part of speech, to each word of a segmented
+//! sentence, so that a segmentation model and a tagger model together make a small
+//! morphological analyzer.
+//!
+//! The tagger is a [`MultiClassAdaBoost`] over the tags: each feature of the context of
+//! the word is conjoined with a candidate tag as `TAG|feature`, and the candidate with
+//! the highest score wins. Words are tagged from left to right, so the tag given to the
+//! previous word is a feature as well. Tagger
+//! models are ordinary model files that list their tags in the `tags` metadata entry, so
+//! they are checked, signed and stored like segmentation models.
+//!
+//! Training corpora have one sentence per line, with words written as `surface/TAG` and
+//! separated by spaces:
+//!
+//! ```text
+//! 猫/名詞 が/助詞 好き/形容動詞 だ/助動詞
+//! ```
+
+#[cfg(feature = "train")]
+use std::collections::HashSet;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::adaboost::{AdaBoost, MultiClassAdaBoost};
+#[cfg(feature = "train")]
+use crate::control::TrainControl;
+use crate::error::LitseaError;
+use crate::language::{CharTypePatterns, Language};
+use crate::token::{Token, TokenFilter};
+
+/// Metadata key of the space-separated tags of a tagger model.
+pub const TAGS_KEY: &str = "tags";
+
+/// Stands for the word or tag before the first and after the last word of a sentence.
+const BOUNDARY: &str = "<S>";
+
+/// A sentence of a tagged corpus, as pairs of a word and its tag.
+pub type TaggedSentence = Vec<(String, String)>;
+
+/// Assigns tags to the words of segmented sentences.
+#[derive(Debug)]
+pub struct Tagger {
+    classifier: MultiClassAdaBoost<String>,
+    char_types: CharTypePatterns,
+}
+
+impl Tagger {
+    /// Creates a tagger from a loaded tagger model.
+    ///
+    /// # Arguments
+    /// * `language` - The language of the model, whose character types are features.
+    /// * `learner` - The tagger model.
+    ///
+    /// # Returns
+    /// A new [`Tagger`].
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidData`] error carrying
+    /// [`LitseaError::ModelFormat`] if the model has no tags, i.e. it is not a tagger model.
+    pub fn new(language: Language, learner: AdaBoost) -> crate::error::Result<Self> {
+        if learner.metadata(TAGS_KEY).is_none_or(|tags| tags.trim().is_empty()) {
+            return Err(LitseaError::ModelFormat(
+                "The model has no tags; it is not a tagger model".to_string(),
+            ));
+        }
+        Ok(Tagger {
+            classifier: MultiClassAdaBoost::from_metadata(learner, TAGS_KEY)?,
+            char_types: language.char_type_patterns(),
+        })
+    }
+
+    /// Trains a tagger on a tagged corpus.
+    ///
+    /// Every word is an instance for each tag of the corpus, positive for its own tag and
+    /// negative for the others, so training takes about as many times longer as there are
+    /// tags.
+    ///
+    /// # Arguments
+    /// * `language` - The language of the corpus.
+    /// * `sentences` - The tagged sentences, e.g. from [`read_corpus`].
+    /// * `learner` - A new learner with the threshold, number of iterations and other
+    ///   training settings.
+    /// * `control` - Stops, pauses or resumes training.
+    ///
+    /// # Returns
+    /// The trained [`Tagger`].
+    ///
+    /// # Errors
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the corpus has no words or a
+    /// tag contains `|`.
+    #[cfg(feature = "train")]
+    pub fn train(
+        language: Language,
+        sentences: &[TaggedSentence],
+        learner: AdaBoost,
+        control: impl Into<TrainControl>,
+    ) -> crate::error::Result<Self> {
+        let tags: HashSet<String> =
+            sentences.iter().flatten().map(|(_, tag)| tag.clone()).collect();
+        if tags.is_empty() {
+            return Err(LitseaError::InvalidInput("The corpus has no words".to_string()));
+        }
+        let mut classifier = MultiClassAdaBoost::new(learner, tags)?;
+        classifier.store_labels(TAGS_KEY)?;
+
+        let char_types = language.char_type_patterns();
+        for sentence in sentences {
+            let words: Vec<&str> = sentence.iter().map(|(word, _)| word.as_str()).collect();
+            for (i, (_, gold)) in sentence.iter().enumerate() {
+                let previous = if i == 0 { BOUNDARY } else { sentence[i - 1].1.as_str() };
+                let context = context_features(&char_types, &words, i, previous);
+                classifier.add_instance(&context, gold)?;
+            }
+        }
+        classifier.train(control);
+
+        Ok(Tagger {
+            classifier,
+            char_types,
+        })
+    }
+
+    /// Returns the tags the model chooses from, in sorted order.
+    #[must_use]
+    pub fn tags(&self) -> &[String] {
+        self.classifier.labels()
+    }
+
+    /// Returns the tagger model.
+    #[must_use]
+    pub fn learner(&self) -> &AdaBoost {
+        self.classifier.learner()
+    }
+
+    /// Tags the words of a sentence.
+    ///
+    /// # Arguments
+    /// * `words` - The words of the sentence in order.
+    ///
+    /// # Returns
+    /// The tag of each word.
+    #[must_use]
+    pub fn tag<S: AsRef<str>>(&self, words: &[S]) -> Vec<&str> {
+        let words: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+        let mut out: Vec<&str> = Vec::with_capacity(words.len());
+        for i in 0..words.len() {
+            let previous = out.last().copied().unwrap_or(BOUNDARY);
+            let context = context_features(&self.char_types, &words, i, previous);
+            out.push(self.classifier.predict(&context).as_str());
+        }
+        out
+    }
+
+    /// Computes the share of the words of a tagged corpus that get their gold tag.
+    ///
+    /// # Arguments
+    /// * `sentences` - The tagged sentences.
+    ///
+    /// # Returns
+    /// The accuracy in percentage (%), or 0 without words.
+    #[must_use]
+    pub fn accuracy(&self, sentences: &[TaggedSentence]) -> f64 {
+        let mut correct = 0;
+        let mut total = 0;
+        for sentence in sentences {
+            let words: Vec<&str> = sentence.iter().map(|(word, _)| word.as_str()).collect();
+            for (tag, (_, gold)) in self.tag(&words).into_iter().zip(sentence) {
+                correct += usize::from(tag == gold);
+                total += 1;
+            }
+        }
+        correct as f64 / total.max(1) as f64 * 100.0
+    }
+
+    /// Saves the tagger model, with the tags in its metadata.
+    ///
+    /// # Arguments
+    /// * `path` - The destination of the model file.
+    ///
+    /// # Errors
+    /// Returns an error if the model is empty or the file cannot be written.
+    pub fn save(&self, path: &Path) -> crate::error::Result<()> {
+        self.classifier.learner().save_model(path)
+    }
+}
+
+impl TokenFilter for Tagger {
+    /// Tags the tokens that have no tags yet, so tags from a lexicon are kept.
+    fn apply(&self, tokens: &mut Vec<Token>) {
+        let words: Vec<&str> = tokens.iter().map(|t| t.surface.as_str()).collect();
+        let tags: Vec<String> = self.tag(&words).into_iter().map(str::to_string).collect();
+        for (token, tag) in tokens.iter_mut().zip(tags) {
+            if token.tags.is_empty() {
+                token.tags.push(tag);
+            }
+        }
+    }
+}
+
+/// Returns the features of the word at `i` in its context, before they are conjoined
+/// with a candidate tag.
+fn context_features(
+    char_types: &CharTypePatterns,
+    words: &[&str],
+    i: usize,
+    previous_tag: &str,
+) -> Vec<String> {
+    let word = words[i];
+    let chars: Vec<char> = word.chars().collect();
+    let types: String = chars.iter().map(|&c| char_types.get_char_type(c)).collect();
+    let mut features = vec![
+        // The prior of the tag.
+        "B".to_string(),
+        format!("W0:{}", word),
+        format!("W-1:{}", if i == 0 { BOUNDARY } else { words[i - 1] }),
+        format!("W+1:{}", words.get(i + 1).copied().unwrap_or(BOUNDARY)),
+        format!("T-1:{}", previous_tag),
+        format!("C0:{}", types),
+    ];
+    if let (Some(first), Some(last)) = (chars.first(), chars.last()) {
+        features.push(format!("P1:{}", first));
+        features.push(format!("S1:{}", last));
+    }
+    if chars.len() >= 2 {
+        features.push(format!("S2:{}", chars[chars.len() - 2..].iter().collect::<String>()));
+    }
+    features
+}
+
+/// Splits a word of a tagged corpus into its surface and tag at the last `/`.
+///
+/// # Arguments
+/// * `word` - A word written as `surface/TAG`.
+///
+/// # Returns
+/// The surface and the tag, or `None` if either is empty or there is no `/`.
+#[must_use]
+pub fn split_tagged(word: &str) -> Option<(&str, &str)> {
+    word.rsplit_once('/')
+        .filter(|(surface, tag)| !surface.is_empty() && !tag.is_empty())
+}
+
+/// Reads a tagged corpus, one sentence per line with words written as `surface/TAG` and
+/// separated by spaces. Empty lines are skipped.
+///
+/// # Arguments
+/// * `reader` - The corpus.
+///
+/// # Returns
+/// The tagged sentences.
+///
+/// # Errors
+/// Returns an error if reading fails, or an [`io::ErrorKind::InvalidData`] error carrying
+/// [`LitseaError::Parse`] if a word has no tag.
+pub fn read_corpus<R: BufRead>(reader: R) -> crate::error::Result<Vec<TaggedSentence>> {
+    let mut sentences = Vec::new();
+    for (line_num, line) in reader.lines().enumerate() {
+        let line = line?;
+        let sentence = line
+            .split_whitespace()
+            .map(|word| {
+                split_tagged(word)
+                    .map(|(surface, tag)| (surface.to_string(), tag.to_string()))
+                    .ok_or_else(|| LitseaError::Parse {
+                        line: line_num + 1,
+                        reason: format!("Word '{}' is not written as surface/TAG", word),
+                    })
+            })
+            .collect::<Result<TaggedSentence, _>>()?;
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+    }
+    Ok(sentences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORPUS: &str = "\
+猫/名詞 が/助詞 好き/形容動詞 だ/助動詞
+犬/名詞 が/助詞 走る/動詞
+猫/名詞 を/助詞 見る/動詞
+
+犬/名詞 は/助詞 好き/形容動詞 だ/助動詞
+";
+
+    fn corpus() -> Vec<TaggedSentence> {
+        read_corpus(CORPUS.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_read_corpus() {
+        let sentences = corpus();
+        assert_eq!(sentences.len(), 4);
+        assert_eq!(sentences[1][2], ("走る".to_string(), "動詞".to_string()));
+
+        assert_eq!(split_tagged("1/2/名詞"), Some(("1/2", "名詞")));
+        assert_eq!(split_tagged("/名詞"), None);
+
+        let err = read_corpus("猫/名詞\n猫 が/助詞\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::Parse { line: 2, .. }));
+    }
+
+    #[cfg(feature = "train")]
+    fn train() -> Tagger {
+        let learner = AdaBoost::new(0.001, 100);
+        Tagger::train(Language::Japanese, &corpus(), learner, TrainControl::new()).unwrap()
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_and_tag() {
+        let tagger = train();
+        assert_eq!(tagger.tags(), ["助動詞", "助詞", "動詞", "名詞", "形容動詞"]);
+        assert_eq!(tagger.accuracy(&corpus()), 100.0);
+        assert_eq!(tagger.tag(&["犬", "を", "見る"]), vec!["名詞", "助詞", "動詞"]);
+        assert!(tagger.tag::<&str>(&[]).is_empty());
+
+        let mut tokens: Vec<Token> = ["猫", "が", "走る"]
+            .iter()
+            .map(|w| Token {
+                surface: w.to_string(),
+                ..Token::default()
+            })
+            .collect();
+        tokens[1].tags = vec!["格助詞".to_string()];
+        tagger.apply(&mut tokens);
+        assert_eq!(tokens[0].tags, vec!["名詞"]);
+        assert_eq!(tokens[1].tags, vec!["格助詞"]);
+        assert_eq!(tokens[2].tags, vec!["動詞"]);
+    }
+
+    #[cfg(feature = "train")]
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let tagger = train();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tagger.model");
+        tagger.save(&path).unwrap();
+
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model(path.to_str().unwrap()).await.unwrap();
+        let loaded = Tagger::new(Language::Japanese, learner).unwrap();
+        assert_eq!(loaded.tags(), tagger.tags());
+        assert_eq!(loaded.tag(&["猫", "は", "走る"]), tagger.tag(&["猫", "は", "走る"]));
+    }
+
+    #[test]
+    fn test_new_without_tags() {
+        let err = Tagger::new(Language::Japanese, AdaBoost::new(0.01, 100)).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+    }
+
+    #[cfg(feature = "train")]
+    #[test]
+    fn test_train_invalid_tag() {
+        let sentences = vec![vec![("猫".to_string(), "名詞|一般".to_string())]];
+        let learner = AdaBoost::new(0.01, 10);
+        let result = Tagger::train(Language::Japanese, &sentences, learner, TrainControl::new());
+        assert!(matches!(result, Err(LitseaError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,321 @@
+//! TensorBoard event files for training metrics.
+//!
+//! [`EventWriter`] writes scalar summaries in the event file format TensorBoard reads,
+//! so boosting runs can be monitored alongside other experiments in one dashboard:
+//!
+//! ```sh
+//! litsea train --dev-features dev.txt --tensorboard runs/litsea features.txt model.txt
+//! tensorboard --logdir runs
+//! ```
+//!
+//! An event file is a sequence of TFRecords, each holding an `Event` protocol buffer.
+//! Only the few fields needed for scalars are written, so no protobuf or TensorFlow
+//! dependency is needed:
+//!
+//! ```text
+//! record  length u64, masked CRC-32C of length u32, data, masked CRC-32C of data u32
+//! Event   1: wall_time double, 2: step int64, 3: file_version string, 5: summary Summary
+//! Summary 1: value repeated Value
+//! Value   1: tag string, 2: simple_value float
+//! ```
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::adaboost::TrainingRecord;
+use crate::progress::TrainingProgress;
+
+/// The version written in the first event of a file.
+const FILE_VERSION: &str = "brain.Event:2";
+
+/// Lookup table of CRC-32C (Castagnoli), the checksum of TFRecords.
+const CRC32C_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0x82F6_3B78 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32c(data: &[u8]) -> u32 {
+    !data
+        .iter()
+        .fold(!0u32, |crc, &b| CRC32C_TABLE[((crc ^ b as u32) & 0xFF) as usize] ^ (crc >> 8))
+}
+
+/// Masks a CRC as TFRecord does, since checksums of data containing checksums are weak.
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    (crc.rotate_right(15)).wrapping_add(0xA282_EAD8)
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Appends a length-delimited field.
+fn put_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_varint(buf, u64::from(field << 3 | 2));
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+/// Encodes an `Event` with the current wall time.
+fn encode_event(step: i64, file_version: Option<&str>, scalars: &[(&str, f32)]) -> Vec<u8> {
+    let wall_time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |d| d.as_secs_f64());
+    let mut event = Vec::new();
+    event.push(1 << 3 | 1);
+    event.extend_from_slice(&wall_time.to_le_bytes());
+    event.push(2 << 3);
+    put_varint(&mut event, step as u64);
+    if let Some(version) = file_version {
+        put_bytes(&mut event, 3, version.as_bytes());
+    }
+    if !scalars.is_empty() {
+        let mut summary = Vec::new();
+        for (tag, value) in scalars {
+            let mut entry = Vec::new();
+            put_bytes(&mut entry, 1, tag.as_bytes());
+            entry.push(2 << 3 | 5);
+            entry.extend_from_slice(&value.to_le_bytes());
+            put_bytes(&mut summary, 1, &entry);
+        }
+        put_bytes(&mut event, 5, &summary);
+    }
+    event
+}
+
+/// Writes training metrics as a TensorBoard event file.
+#[derive(Debug)]
+pub struct EventWriter<W: Write> {
+    writer: W,
+}
+
+impl EventWriter<BufWriter<File>> {
+    /// Creates a new event file in a log directory, creating the directory if needed.
+    ///
+    /// The file is named `events.out.tfevents.<seconds>.litsea.<pid>`, so every run adds
+    /// a file that TensorBoard shows as part of the run of the directory.
+    ///
+    /// # Arguments
+    /// * `log_dir` - The directory TensorBoard is pointed at, or a subdirectory of it.
+    ///
+    /// # Errors
+    /// Returns an error if the directory or the file cannot be created.
+    pub fn create(log_dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(log_dir)?;
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let name = format!("events.out.tfevents.{}.litsea.{}", seconds, std::process::id());
+        EventWriter::new(BufWriter::new(File::create(log_dir.join(name))?))
+    }
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Creates an event writer and writes the file version event that starts every
+    /// event file.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination of the events.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn new(writer: W) -> io::Result<Self> {
+        let mut writer = EventWriter { writer };
+        writer.write_record(&encode_event(0, Some(FILE_VERSION), &[]))?;
+        Ok(writer)
+    }
+
+    fn write_record(&mut self, data: &[u8]) -> io::Result<()> {
+        let len = (data.len() as u64).to_le_bytes();
+        self.writer.write_all(&len)?;
+        self.writer.write_all(&masked_crc32c(&len).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&masked_crc32c(data).to_le_bytes())
+    }
+
+    /// Writes scalar values of one step.
+    ///
+    /// # Arguments
+    /// * `step` - The step, e.g. the boosting iteration.
+    /// * `scalars` - Pairs of a tag, such as `dev/accuracy`, and its value.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn add_scalars(&mut self, step: i64, scalars: &[(&str, f64)]) -> io::Result<()> {
+        // TensorBoard stores scalars as 32-bit floats.
+        let scalars: Vec<(&str, f32)> = scalars.iter().map(|&(tag, v)| (tag, v as f32)).collect();
+        self.write_record(&encode_event(step, None, &scalars))
+    }
+
+    /// Writes the training and development accuracy, their gap and the development F1
+    /// score for every iteration, as `train/accuracy`, `dev/accuracy`, `gap` and `dev/f1`.
+    ///
+    /// # Arguments
+    /// * `log` - The records of [`AdaBoost::training_log`](crate::adaboost::AdaBoost::training_log).
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn add_training_log(&mut self, log: &[TrainingRecord]) -> io::Result<()> {
+        for record in log {
+            self.add_scalars(
+                record.iteration as i64,
+                &[
+                    ("train/accuracy", record.train_accuracy),
+                    ("dev/accuracy", record.dev_accuracy),
+                    ("gap", record.gap()),
+                    ("dev/f1", record.dev_f1),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the progress of an iteration while training: the `alpha` and `error_rate`
+    /// of its weak learner, the training accuracy as `train/accuracy` when it is
+    /// reported, and with a development set the scalars of
+    /// [`add_training_log`](Self::add_training_log).
+    ///
+    /// # Arguments
+    /// * `progress` - The progress reported after the iteration.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn add_progress(&mut self, progress: &TrainingProgress<'_>) -> io::Result<()> {
+        let mut scalars = vec![("alpha", progress.alpha), ("error_rate", progress.error_rate)];
+        match progress.record {
+            Some(record) => scalars.extend([
+                ("train/accuracy", record.train_accuracy),
+                ("dev/accuracy", record.dev_accuracy),
+                ("gap", record.gap()),
+                ("dev/f1", record.dev_f1),
+            ]),
+            None => scalars.extend(progress.train_accuracy.map(|a| ("train/accuracy", a))),
+        }
+        self.add_scalars(progress.iteration as i64, &scalars)
+    }
+
+    /// Flushes the events written so far, so TensorBoard can read them.
+    ///
+    /// # Errors
+    /// Returns an error if flushing fails.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splits an event file into its records, checking their checksums.
+    fn read_records(mut bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut records = Vec::new();
+        while !bytes.is_empty() {
+            let (len, rest) = bytes.split_at(8);
+            let (len_crc, rest) = rest.split_at(4);
+            assert_eq!(masked_crc32c(len).to_le_bytes(), len_crc);
+            let (data, rest) = rest.split_at(u64::from_le_bytes(len.try_into().unwrap()) as usize);
+            let (data_crc, rest) = rest.split_at(4);
+            assert_eq!(masked_crc32c(data).to_le_bytes(), data_crc);
+            records.push(data.to_vec());
+            bytes = rest;
+        }
+        records
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(b""), 0);
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut buf = Vec::new();
+        put_varint(&mut buf, 1);
+        put_varint(&mut buf, 300);
+        assert_eq!(buf, [0x01, 0xAC, 0x02]);
+    }
+
+    #[test]
+    fn test_training_log() {
+        let log: Vec<TrainingRecord> = (1..=3)
+            .map(|iteration| TrainingRecord {
+                iteration,
+                train_accuracy: 90.0 + iteration as f64,
+                dev_accuracy: 90.0,
+                dev_f1: 85.0,
+                overfitting: false,
+            })
+            .collect();
+        let mut writer = EventWriter::new(Vec::new()).unwrap();
+        writer.add_training_log(&log).unwrap();
+        let records = read_records(&writer.writer);
+
+        assert_eq!(records.len(), 4);
+        assert!(contains(&records[0], FILE_VERSION.as_bytes()));
+        // Step 3 and the training accuracy of 93 %.
+        assert!(contains(&records[3], &[2 << 3, 3]));
+        assert!(contains(&records[3], b"train/accuracy"));
+        assert!(contains(&records[3], &93.0f32.to_le_bytes()));
+        assert!(contains(&records[3], b"dev/accuracy"));
+        assert!(contains(&records[3], &3.0f32.to_le_bytes()));
+        assert!(contains(&records[3], b"dev/f1"));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut writer = EventWriter::new(Vec::new()).unwrap();
+        writer
+            .add_progress(&TrainingProgress {
+                iteration: 7,
+                num_iterations: 10,
+                feature: "UW4:は",
+                alpha: 0.5,
+                error_rate: 0.25,
+                train_accuracy: Some(93.0),
+                record: None,
+            })
+            .unwrap();
+        let records = read_records(&writer.writer);
+
+        assert_eq!(records.len(), 2);
+        assert!(contains(&records[1], &[2 << 3, 7]));
+        assert!(contains(&records[1], b"alpha"));
+        assert!(contains(&records[1], &0.25f32.to_le_bytes()));
+        assert!(contains(&records[1], b"train/accuracy"));
+        assert!(!contains(&records[1], b"dev/accuracy"));
+    }
+
+    #[test]
+    fn test_create() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("runs").join("a");
+        let mut writer = EventWriter::create(&log_dir).unwrap();
+        writer.add_scalars(1, &[("loss", 0.5)]).unwrap();
+        writer.flush().unwrap();
+
+        let entries: Vec<_> = fs::read_dir(&log_dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].file_name().unwrap().to_str().unwrap().contains("tfevents"));
+        assert_eq!(read_records(&fs::read(&entries[0]).unwrap()).len(), 2);
+    }
+}
@@ -0,0 +1,30 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use crate::adaboost::AdaBoost;
+use crate::language::Language;
+use crate::segmenter::Segmenter;
+
+/// Returns a segmenter for `language` with the model in `model`, in the text model format.
+pub(crate) fn segmenter(model: &str, language: Language) -> Segmenter {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.load_model_str(model).unwrap();
+    Segmenter::new(language, Some(learner))
+}
+
+/// Returns a Japanese segmenter with the bundled RWCP model.
+pub(crate) fn rwcp_segmenter() -> Segmenter {
+    segmenter(include_str!("../../resources/RWCP.model"), Language::Japanese)
+}
+
+/// Returns a Japanese segmenter with the bundled `japanese.model`.
+pub(crate) fn japanese_segmenter() -> Segmenter {
+    segmenter(include_str!("../../resources/japanese.model"), Language::Japanese)
+}
+
+/// Returns a Japanese segmenter whose model only splits before and after "は", so that
+/// tests can tell exactly where it places boundaries.
+pub(crate) fn ha_segmenter() -> Segmenter {
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.set_weights(vec![("UW4:は".to_string(), 2.0), ("UW3:は".to_string(), 2.0)], -1.0);
+    Segmenter::new(Language::Japanese, Some(learner))
+}
@@ -0,0 +1,806 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::language::{CharTypePatterns, Language, LatinClasses};
+use crate::model_info;
+use crate::segmenter::FeatureTemplates;
+
+/// Feature templates shared by TinySegmenter and Litsea, in the order they are scored.
+const BASE_TEMPLATES: &[&str] = &[
+    "UP1", "UP2", "UP3", "BP1", "BP2", "UW1", "UW2", "UW3", "UW4", "UW5", "UW6", "BW1", "BW2",
+    "BW3", "UC1", "UC2", "UC3", "UC4", "UC5", "UC6", "BC1", "BC2", "BC3", "TC1", "TC2", "TC3",
+    "TC4", "UQ1", "UQ2", "UQ3", "BQ1", "BQ2", "BQ3", "BQ4", "TQ1", "TQ2", "TQ3", "TQ4",
+];
+
+/// Litsea-specific word + character-type templates used for Japanese and Chinese.
+const WC_TEMPLATES: &[&str] = &["WC1", "WC2", "WC3", "WC4"];
+
+/// Word trigram templates of the original TinySegmenter, which Litsea models do not use.
+const TW_TEMPLATES: &[&str] = &["TW1", "TW2", "TW3", "TW4"];
+
+/// The flavor of script emitted by [`export_js`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScriptFlavor {
+    /// Plain JavaScript using the original TinySegmenter constructor/prototype layout.
+    #[default]
+    JavaScript,
+    /// TypeScript with type annotations on the generated tables.
+    TypeScript,
+}
+
+impl ScriptFlavor {
+    /// Chooses a flavor from an output file name: `.ts` selects TypeScript,
+    /// anything else JavaScript.
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ts") => ScriptFlavor::TypeScript,
+            _ => ScriptFlavor::JavaScript,
+        }
+    }
+}
+
+/// Returns the feature templates scored by the segmenter for a given language and set of
+/// templates.
+fn templates(language: Language, feature_templates: FeatureTemplates) -> Vec<&'static str> {
+    let mut templates = BASE_TEMPLATES.to_vec();
+    match feature_templates {
+        FeatureTemplates::Litsea => {
+            if matches!(language, Language::Japanese | Language::Chinese) {
+                templates.extend_from_slice(WC_TEMPLATES);
+            }
+        }
+        FeatureTemplates::TinySegmenter => templates.extend_from_slice(TW_TEMPLATES),
+    }
+    templates
+}
+
+/// Quotes a string as a JavaScript string literal.
+fn js_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{2028}' | '\u{2029}' => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Exports a model as a self-contained TinySegmenter-compatible script.
+///
+/// The generated script defines a `TinySegmenter` constructor whose instances expose
+/// `segment(input)` and keep one score table per feature template (`this.UW1__`,
+/// `this.BC2__`, ...) like the original TinySegmenter, so models trained with Litsea
+/// can run client-side in the browser. Character types are classified with the same
+/// language-specific rules as [`Segmenter`](crate::segmenter::Segmenter). A model
+/// trained with [`FeatureTemplates::TinySegmenter`] is exported with the word trigram
+/// tables, character types and boundary rule of the original TinySegmenter, so the
+/// script can replace `tiny_segmenter.js` in existing deployments.
+///
+/// # Arguments
+/// * `learner` - The model to export.
+/// * `language` - The language the model was trained for.
+/// * `feature_templates` - The feature templates the model was trained with.
+/// * `latin_classes` - The Latin classes the model was trained with, ignored with
+///   [`FeatureTemplates::TinySegmenter`].
+/// * `flavor` - Whether to emit JavaScript or TypeScript.
+/// * `writer` - The destination for the generated script.
+///
+/// # Returns
+/// Returns a Result indicating success or failure.
+///
+/// # Errors
+/// Returns an error if writing to `writer` fails.
+pub fn export_js<W: Write>(
+    learner: &AdaBoost,
+    language: Language,
+    feature_templates: FeatureTemplates,
+    latin_classes: LatinClasses,
+    flavor: ScriptFlavor,
+    writer: &mut W,
+) -> io::Result<()> {
+    let templates = templates(language, feature_templates);
+    let (char_types, boundary) = match feature_templates {
+        FeatureTemplates::Litsea => {
+            (language.char_type_patterns().with_latin_classes(latin_classes), ">=")
+        }
+        FeatureTemplates::TinySegmenter => (CharTypePatterns::tinysegmenter(), ">"),
+    };
+    let ts = flavor == ScriptFlavor::TypeScript;
+
+    // Group weights by template name ("UW4:い" -> table "UW4", key "い").
+    let mut tables: BTreeMap<&str, Vec<(&str, f64)>> =
+        templates.iter().map(|t| (*t, Vec::new())).collect();
+    for (feature, weight) in learner.weights() {
+        if let Some((template, key)) = feature.split_once(':') {
+            tables.entry(template).or_default().push((key, weight));
+        }
+    }
+
+    writeln!(
+        writer,
+        "// Generated by Litsea {} for {}. Do not edit by hand.",
+        crate::version(),
+        language
+    )?;
+    if ts {
+        writeln!(writer, "type ScoreTable = {{ [key: string]: number }};")?;
+        writeln!(writer)?;
+        writeln!(writer, "export class TinySegmenter {{")?;
+        writeln!(writer, "  chartype_: Array<[(c: string) => boolean, string]>;")?;
+        writeln!(writer, "  BIAS__: number;")?;
+        for template in tables.keys() {
+            writeln!(writer, "  {}__: ScoreTable;", template)?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "  constructor() {{")?;
+    } else {
+        writeln!(writer, "function TinySegmenter() {{")?;
+    }
+
+    let indent = if ts { "    " } else { "  " };
+    writeln!(writer, "{indent}this.chartype_ = [")?;
+    for (predicate, label) in char_types.to_js() {
+        writeln!(writer, "{indent}  [{}, {}],", predicate, js_string(label))?;
+    }
+    writeln!(writer, "{indent}];")?;
+    writeln!(writer, "{indent}this.BIAS__ = {};", learner.get_bias())?;
+    for (template, entries) in &tables {
+        let body: Vec<String> =
+            entries.iter().map(|(k, w)| format!("{}:{}", js_string(k), w)).collect();
+        writeln!(writer, "{indent}this.{}__ = {{{}}};", template, body.join(","))?;
+    }
+
+    if ts {
+        writeln!(writer, "  }}")?;
+        writeln!(writer)?;
+        write!(writer, "{}", TS_METHODS_HEAD)?;
+    } else {
+        writeln!(writer, "}}")?;
+        writeln!(writer)?;
+        write!(writer, "{}", JS_METHODS_HEAD)?;
+    }
+
+    for template in &templates {
+        writeln!(
+            writer,
+            "{indent}  score += this.ts_(this.{}__[{}]);",
+            template,
+            template_key_js(template)
+        )?;
+    }
+
+    writeln!(writer, "{indent}  {} p = \"O\";", if ts { "let" } else { "var" })?;
+    writeln!(writer, "{indent}  if (score {} 0) {{", boundary)?;
+    if ts {
+        write!(writer, "{}", TS_METHODS_TAIL)?;
+    } else {
+        write!(writer, "{}", JS_METHODS_TAIL)?;
+    }
+    Ok(())
+}
+
+/// Returns the JavaScript expression building the lookup key for a template,
+/// mirroring [`Segmenter::get_attributes`](crate::segmenter::Segmenter::get_attributes).
+fn template_key_js(template: &str) -> &'static str {
+    match template {
+        "UP1" => "p1",
+        "UP2" => "p2",
+        "UP3" => "p3",
+        "BP1" => "p1 + p2",
+        "BP2" => "p2 + p3",
+        "UW1" => "w1",
+        "UW2" => "w2",
+        "UW3" => "w3",
+        "UW4" => "w4",
+        "UW5" => "w5",
+        "UW6" => "w6",
+        "BW1" => "w2 + w3",
+        "BW2" => "w3 + w4",
+        "BW3" => "w4 + w5",
+        "TW1" => "w1 + w2 + w3",
+        "TW2" => "w2 + w3 + w4",
+        "TW3" => "w3 + w4 + w5",
+        "TW4" => "w4 + w5 + w6",
+        "UC1" => "c1",
+        "UC2" => "c2",
+        "UC3" => "c3",
+        "UC4" => "c4",
+        "UC5" => "c5",
+        "UC6" => "c6",
+        "BC1" => "c2 + c3",
+        "BC2" => "c3 + c4",
+        "BC3" => "c4 + c5",
+        "TC1" => "c1 + c2 + c3",
+        "TC2" => "c2 + c3 + c4",
+        "TC3" => "c3 + c4 + c5",
+        "TC4" => "c4 + c5 + c6",
+        "UQ1" => "p1 + c1",
+        "UQ2" => "p2 + c2",
+        "UQ3" => "p3 + c3",
+        "BQ1" => "p2 + c2 + c3",
+        "BQ2" => "p2 + c3 + c4",
+        "BQ3" => "p3 + c2 + c3",
+        "BQ4" => "p3 + c3 + c4",
+        "TQ1" => "p2 + c1 + c2 + c3",
+        "TQ2" => "p2 + c2 + c3 + c4",
+        "TQ3" => "p3 + c1 + c2 + c3",
+        "TQ4" => "p3 + c2 + c3 + c4",
+        "WC1" => "w3 + c4",
+        "WC2" => "c3 + w4",
+        "WC3" => "w3 + c3",
+        "WC4" => "w4 + c4",
+        _ => unreachable!("unknown template: {template}"),
+    }
+}
+
+const JS_METHODS_HEAD: &str = r#"TinySegmenter.prototype.ctype_ = function (str) {
+  for (var i = 0; i < this.chartype_.length; i++) {
+    if (this.chartype_[i][0](str)) {
+      return this.chartype_[i][1];
+    }
+  }
+  return "O";
+};
+
+TinySegmenter.prototype.ts_ = function (v) {
+  if (v) {
+    return v;
+  }
+  return 0;
+};
+
+TinySegmenter.prototype.segment = function (input) {
+  if (input == null || input == undefined || input == "") {
+    return [];
+  }
+  var result = [];
+  var seg = ["B3", "B2", "B1"];
+  var ctype = ["O", "O", "O"];
+  var o = Array.from(input);
+  for (var i = 0; i < o.length; ++i) {
+    seg.push(o[i]);
+    ctype.push(this.ctype_(o[i]));
+  }
+  seg.push("E1", "E2", "E3");
+  ctype.push("O", "O", "O");
+  var word = seg[3];
+  var p1 = "U";
+  var p2 = "U";
+  var p3 = "U";
+  for (var i = 4; i < seg.length - 3; ++i) {
+    var score = this.BIAS__;
+    var w1 = seg[i - 3];
+    var w2 = seg[i - 2];
+    var w3 = seg[i - 1];
+    var w4 = seg[i];
+    var w5 = seg[i + 1];
+    var w6 = seg[i + 2];
+    var c1 = ctype[i - 3];
+    var c2 = ctype[i - 2];
+    var c3 = ctype[i - 1];
+    var c4 = ctype[i];
+    var c5 = ctype[i + 1];
+    var c6 = ctype[i + 2];
+"#;
+
+const JS_METHODS_TAIL: &str = r#"      result.push(word);
+      word = "";
+      p = "B";
+    }
+    p1 = p2;
+    p2 = p3;
+    p3 = p;
+    word += seg[i];
+  }
+  result.push(word);
+  return result;
+};
+
+if (typeof module !== "undefined" && module.exports) {
+  module.exports = TinySegmenter;
+}
+"#;
+
+const TS_METHODS_HEAD: &str = r#"  ctype_(str: string): string {
+    for (const [predicate, label] of this.chartype_) {
+      if (predicate(str)) {
+        return label;
+      }
+    }
+    return "O";
+  }
+
+  ts_(v: number | undefined): number {
+    if (v) {
+      return v;
+    }
+    return 0;
+  }
+
+  segment(input: string): string[] {
+    if (input == null || input == undefined || input == "") {
+      return [];
+    }
+    const result: string[] = [];
+    const seg: string[] = ["B3", "B2", "B1"];
+    const ctype: string[] = ["O", "O", "O"];
+    for (const ch of Array.from(input)) {
+      seg.push(ch);
+      ctype.push(this.ctype_(ch));
+    }
+    seg.push("E1", "E2", "E3");
+    ctype.push("O", "O", "O");
+    let word = seg[3];
+    let p1 = "U";
+    let p2 = "U";
+    let p3 = "U";
+    for (let i = 4; i < seg.length - 3; ++i) {
+      let score = this.BIAS__;
+      const w1 = seg[i - 3];
+      const w2 = seg[i - 2];
+      const w3 = seg[i - 1];
+      const w4 = seg[i];
+      const w5 = seg[i + 1];
+      const w6 = seg[i + 2];
+      const c1 = ctype[i - 3];
+      const c2 = ctype[i - 2];
+      const c3 = ctype[i - 1];
+      const c4 = ctype[i];
+      const c5 = ctype[i + 1];
+      const c6 = ctype[i + 2];
+"#;
+
+const TS_METHODS_TAIL: &str = r#"        result.push(word);
+        word = "";
+        p = "B";
+      }
+      p1 = p2;
+      p2 = p3;
+      p3 = p;
+      word += seg[i];
+    }
+    result.push(word);
+    return result;
+  }
+}
+"#;
+
+/// Returns true if `name` looks like a TinySegmenter feature template (e.g. `UW1`, `TQ4`).
+fn is_template_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 3
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2].is_ascii_digit()
+}
+
+/// Creates a [`LitseaError::ModelFormat`] error for malformed TinySegmenter scripts.
+fn invalid_js(msg: impl Into<String>) -> LitseaError {
+    LitseaError::ModelFormat(msg.into())
+}
+
+/// A minimal scanner over the JavaScript object literals used for TinySegmenter score tables.
+struct JsScanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsScanner<'a> {
+    fn new(src: &'a str) -> Self {
+        JsScanner { src, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(ch) {
+            self.pos += ch.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Parses a single- or double-quoted string literal, resolving escapes.
+    fn string(&mut self) -> crate::error::Result<String> {
+        self.skip_ws();
+        let mut chars = self.rest().char_indices();
+        let quote = match chars.next() {
+            Some((_, q @ ('"' | '\''))) => q,
+            _ => return Err(invalid_js("Expected a string key in score table")),
+        };
+        let mut out = String::new();
+        let mut pending_high: Option<u32> = None;
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                c if c == quote => {
+                    self.pos += i + c.len_utf8();
+                    return Ok(out);
+                }
+                '\\' => {
+                    let (_, esc) = chars.next().ok_or_else(|| invalid_js("Unterminated string"))?;
+                    let unit = match esc {
+                        'n' => Some('\n' as u32),
+                        't' => Some('\t' as u32),
+                        'r' => Some('\r' as u32),
+                        'u' => {
+                            let hex: String = chars.by_ref().take(4).map(|(_, c)| c).collect();
+                            Some(u32::from_str_radix(&hex, 16).map_err(|_| {
+                                invalid_js(format!("Invalid unicode escape: \\u{}", hex))
+                            })?)
+                        }
+                        other => {
+                            out.push(other);
+                            None
+                        }
+                    };
+                    if let Some(unit) = unit {
+                        if (0xD800..0xDC00).contains(&unit) {
+                            pending_high = Some(unit);
+                            continue;
+                        }
+                        let code = match pending_high.take() {
+                            Some(high) if (0xDC00..0xE000).contains(&unit) => {
+                                0x10000 + ((high - 0xD800) << 10) + (unit - 0xDC00)
+                            }
+                            _ => unit,
+                        };
+                        out.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        Err(invalid_js("Unterminated string"))
+    }
+
+    /// Parses a numeric literal.
+    fn number(&mut self) -> crate::error::Result<f64> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let value = rest[..len]
+            .parse()
+            .map_err(|e| invalid_js(format!("Invalid number '{}': {}", &rest[..len], e)))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Parses an object literal of `"key": number` pairs.
+    fn score_table(&mut self) -> crate::error::Result<Vec<(String, f64)>> {
+        let mut entries = Vec::new();
+        if !self.eat('{') {
+            return Err(invalid_js("Expected '{' at the start of a score table"));
+        }
+        if self.eat('}') {
+            return Ok(entries);
+        }
+        loop {
+            let key = self.string()?;
+            if !self.eat(':') {
+                return Err(invalid_js(format!("Expected ':' after key \"{}\"", key)));
+            }
+            entries.push((key, self.number()?));
+            if self.eat(',') {
+                // Allow a trailing comma before the closing brace.
+                if self.eat('}') {
+                    return Ok(entries);
+                }
+                continue;
+            }
+            if self.eat('}') {
+                return Ok(entries);
+            }
+            return Err(invalid_js("Expected ',' or '}' in score table"));
+        }
+    }
+}
+
+/// Imports a model from a TinySegmenter JavaScript file.
+///
+/// The score tables assigned as `this.<TEMPLATE>__ = {...}` (e.g. `this.UW4__`) are
+/// converted into Litsea features named `<TEMPLATE>:<key>`, and `this.BIAS__` becomes
+/// the model bias. Both the original TinySegmenter script and scripts produced by
+/// [`export_js`] are accepted. Weights keep their original scale, which does not change
+/// the segmentation result since only the sign of the score matters.
+///
+/// A script that scores word trigrams (`this.ts_(this.TW1__[...])` and so on) uses the
+/// templates of TinySegmenter, which Litsea's own templates lack. Its model records
+/// [`FeatureTemplates::TinySegmenter`] and the TinySegmenter character types in its
+/// metadata, so a [`Segmenter`](crate::segmenter::Segmenter) created for it scores text
+/// with them and segments it as TinySegmenter does. Other scripts, such as those
+/// exported with Litsea's templates, get no such entries.
+///
+/// # Arguments
+/// * `reader` - A reader over the JavaScript source.
+///
+/// # Returns
+/// Returns the imported model.
+///
+/// # Errors
+/// Returns an error if the source cannot be read, a score table is malformed,
+/// or no `BIAS__` or score tables are found.
+pub fn import_js<R: Read>(mut reader: R) -> crate::error::Result<AdaBoost> {
+    let mut source = String::new();
+    reader.read_to_string(&mut source)?;
+
+    let mut weights = Vec::new();
+    let mut bias = None;
+    let mut scanner = JsScanner::new(&source);
+
+    while let Some(offset) = scanner.rest().find("this.") {
+        scanner.pos += offset + "this.".len();
+        let rest = scanner.rest();
+        let Some(name) =
+            rest.split("__").next().filter(|n| n.chars().all(|c| c.is_ascii_alphanumeric()))
+        else {
+            continue;
+        };
+        if !(name == "BIAS" || is_template_name(name)) {
+            continue;
+        }
+        scanner.pos += name.len() + "__".len();
+        if !scanner.eat('=') {
+            continue;
+        }
+        if name == "BIAS" {
+            bias = Some(scanner.number()?);
+        } else {
+            for (key, weight) in scanner.score_table()? {
+                weights.push((format!("{}:{}", name, key), weight));
+            }
+        }
+    }
+
+    let bias = bias.ok_or_else(|| invalid_js("No BIAS__ found in TinySegmenter script"))?;
+    if weights.is_empty() {
+        return Err(invalid_js("No score tables found in TinySegmenter script"));
+    }
+
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.set_weights(weights, bias);
+    // Scripts exported with Litsea's templates may have TW tables, but never score them.
+    if source.contains("this.ts_(this.TW") {
+        let templates = FeatureTemplates::TinySegmenter;
+        let char_types =
+            model_info::char_type_scheme(Language::Japanese, templates, LatinClasses::Single);
+        learner.set_metadata(model_info::CHAR_TYPES_KEY, &char_types)?;
+        learner.set_metadata(model_info::TEMPLATES_KEY, &model_info::templates_id(templates))?;
+    }
+    Ok(learner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::{Path, PathBuf};
+
+    use crate::segmenter::Segmenter;
+
+    async fn load_rwcp() -> AdaBoost {
+        let model_file = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../resources")
+            .join("RWCP.model");
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.load_model(model_file.to_str().unwrap()).await.unwrap();
+        learner
+    }
+
+    #[test]
+    fn test_flavor_from_path() {
+        assert_eq!(ScriptFlavor::from_path(Path::new("out.ts")), ScriptFlavor::TypeScript);
+        assert_eq!(ScriptFlavor::from_path(Path::new("out.js")), ScriptFlavor::JavaScript);
+        assert_eq!(ScriptFlavor::from_path(Path::new("out")), ScriptFlavor::JavaScript);
+    }
+
+    #[test]
+    fn test_js_string_escapes() {
+        assert_eq!(js_string("あ"), "\"あ\"");
+        assert_eq!(js_string("a\"b"), "\"a\\\"b\"");
+        assert_eq!(js_string("\\"), "\"\\\\\"");
+        assert_eq!(js_string("\u{2028}"), "\"\\u2028\"");
+    }
+
+    #[tokio::test]
+    async fn test_export_js() {
+        let learner = load_rwcp().await;
+        let mut out = Vec::new();
+        export_js(
+            &learner,
+            Language::Japanese,
+            FeatureTemplates::Litsea,
+            LatinClasses::Single,
+            ScriptFlavor::JavaScript,
+            &mut out,
+        )
+        .unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(script.contains("function TinySegmenter() {"));
+        assert!(script.contains(&format!("this.BIAS__ = {};", learner.get_bias())));
+        // Every template, including the Japanese WC templates, gets a table and a score line.
+        for template in templates(Language::Japanese, FeatureTemplates::Litsea) {
+            assert!(script.contains(&format!("this.{}__ = {{", template)), "{template}");
+            assert!(script.contains(&format!("this.ts_(this.{}__[", template)), "{template}");
+        }
+        assert!(script.contains("TinySegmenter.prototype.segment = function (input) {"));
+        assert!(script.contains("    if (score >= 0) {\n      result.push(word);"));
+        assert!(!script.contains("this.ts_(this.TW1__["));
+    }
+
+    #[tokio::test]
+    async fn test_export_js_tinysegmenter_templates() {
+        let learner = load_rwcp().await;
+        let mut out = Vec::new();
+        export_js(
+            &learner,
+            Language::Japanese,
+            FeatureTemplates::TinySegmenter,
+            LatinClasses::Case,
+            ScriptFlavor::JavaScript,
+            &mut out,
+        )
+        .unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        // The word trigrams replace the WC templates, as in the original TinySegmenter.
+        assert!(script.contains("score += this.ts_(this.TW4__[w4 + w5 + w6]);"));
+        assert!(!script.contains("this.ts_(this.WC1__["));
+        // Its character types have no punctuation class and ignore the Latin classes.
+        assert!(script.contains(", \"A\"],"));
+        assert!(!script.contains(", \"P\"],"));
+        assert!(!script.contains(", \"AU\"],"));
+        // Its boundary rule requires a positive score.
+        assert!(script.contains("    if (score > 0) {\n      result.push(word);"));
+    }
+
+    #[tokio::test]
+    async fn test_export_ts_korean() {
+        let learner = load_rwcp().await;
+        let mut out = Vec::new();
+        export_js(
+            &learner,
+            Language::Korean,
+            FeatureTemplates::Litsea,
+            LatinClasses::Case,
+            ScriptFlavor::TypeScript,
+            &mut out,
+        )
+        .unwrap();
+        let script = String::from_utf8(out).unwrap();
+
+        assert!(script.contains("export class TinySegmenter {"));
+        assert!(script.contains("segment(input: string): string[] {"));
+        // Hangul syllable matchers are rendered as codepoint checks.
+        assert!(script.contains("codePointAt(0)"));
+        // Latin letters are classified by case.
+        assert!(script.contains(", \"AU\"],"));
+        assert!(script.contains(", \"AL\"],"));
+        assert!(!script.contains(", \"A\"],"));
+        // Korean does not use the WC templates.
+        assert!(!script.contains("this.ts_(this.WC1__["));
+    }
+
+    #[test]
+    fn test_import_js() {
+        let source = r#"
+            function TinySegmenter() {
+              this.BIAS__ = -332
+              this.BC1__ = {"HH":6,"II":2461,"KH":406,"OH":-1378};
+              this.UW4__ = {",":3930,"\u3042":-1.5, 'x':7,};
+              this.UP1__ = {};
+            }
+        "#;
+        let learner = import_js(source.as_bytes()).unwrap();
+        let weights: BTreeMap<&str, f64> = learner.weights().collect();
+
+        assert_eq!(weights.len(), 7);
+        assert_eq!(weights["BC1:HH"], 6.0);
+        assert_eq!(weights["BC1:OH"], -1378.0);
+        assert_eq!(weights["UW4:,"], 3930.0);
+        assert_eq!(weights["UW4:あ"], -1.5);
+        assert_eq!(weights["UW4:x"], 7.0);
+        assert!((learner.get_bias() + 332.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_import_js_tinysegmenter_templates() {
+        // A boundary before "は" when followed by "テス", which only the word trigram TW4
+        // sees, and a score of exactly 0 before "ト", where TinySegmenter places none.
+        let source = r#"
+            this.BIAS__ = -1;
+            this.TW4__ = {"はテス":2};
+            this.UW4__ = {"ト":1};
+            score += this.ts_(this.TW4__[w4 + w5 + w6]);
+        "#;
+        let learner = import_js(source.as_bytes()).unwrap();
+        assert_eq!(learner.metadata(model_info::TEMPLATES_KEY), Some("tinysegmenter-1"));
+        assert_eq!(learner.metadata(model_info::CHAR_TYPES_KEY), Some("tinysegmenter-1"));
+
+        let segmenter = Segmenter::new(Language::Japanese, Some(learner));
+        assert_eq!(segmenter.feature_templates(), FeatureTemplates::TinySegmenter);
+        assert_eq!(segmenter.get_type("。"), "O");
+        assert!(segmenter.check_model().is_ok());
+        assert_eq!(segmenter.segment("これはテスト"), ["これ", "はテスト"]);
+    }
+
+    #[tokio::test]
+    async fn test_import_js_reproduces_tinysegmenter() {
+        let learner = load_rwcp().await;
+        let mut out = Vec::new();
+        export_js(
+            &learner,
+            Language::Japanese,
+            FeatureTemplates::TinySegmenter,
+            LatinClasses::Single,
+            ScriptFlavor::JavaScript,
+            &mut out,
+        )
+        .unwrap();
+        let mut expected = Segmenter::new(Language::Japanese, Some(learner));
+        expected.set_feature_templates(FeatureTemplates::TinySegmenter);
+
+        // No templates are set: the imported model brings them.
+        let imported = Segmenter::new(Language::Japanese, Some(import_js(out.as_slice()).unwrap()));
+        for sentence in ["私の名前は中野です。", "ＡＢＣ１２３、ｱｲｳ「テスト」です"]
+        {
+            assert_eq!(imported.segment(sentence), expected.segment(sentence), "{sentence}");
+        }
+    }
+
+    #[test]
+    fn test_import_js_errors() {
+        assert!(import_js("this.UW1__ = {\"a\":1};".as_bytes()).is_err());
+        assert!(import_js("this.BIAS__ = 1;".as_bytes()).is_err());
+        assert!(import_js("this.BIAS__ = 1; this.UW1__ = {\"a\" 1};".as_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        let learner = load_rwcp().await;
+        let mut out = Vec::new();
+        export_js(
+            &learner,
+            Language::Japanese,
+            FeatureTemplates::Litsea,
+            LatinClasses::Single,
+            ScriptFlavor::JavaScript,
+            &mut out,
+        )
+        .unwrap();
+        let imported = import_js(out.as_slice()).unwrap();
+        assert_eq!(imported.metadata(model_info::TEMPLATES_KEY), None);
+
+        let original: BTreeMap<&str, f64> = learner.weights().collect();
+        let roundtrip: BTreeMap<&str, f64> = imported.weights().collect();
+        assert_eq!(original, roundtrip);
+        assert!((learner.get_bias() - imported.get_bias()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,109 @@
+//! Tokens produced by segmentation and the [`TokenFilter`] trait of the post-processing
+//! stages that enrich or remove them, such as the `reading`, `lemma`, `compound` and
+//! `stopwords` modules.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::segmenter::ScoredWord;
+
+/// A word of a segmented sentence, with the annotations attached to it after segmentation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Token {
+    /// The text of the word.
+    pub surface: String,
+    /// Byte offset of the start of the word in the sentence.
+    pub start: usize,
+    /// Byte offset of the end of the word in the sentence.
+    pub end: usize,
+    /// Offset of the start of the word in the sentence, counted in characters.
+    pub char_start: usize,
+    /// Offset of the end of the word in the sentence, counted in characters.
+    pub char_end: usize,
+    /// Score of the boundary before the word, or `None` for the first word of a sentence
+    /// and for words the segmenter did not place, such as the inner parts of a split
+    /// compound.
+    pub score: Option<f64>,
+    /// Reading of the word in katakana, if one was attached.
+    pub reading: Option<String>,
+    /// Normalized form of the word, if it was found in a lexicon.
+    pub lemma: Option<String>,
+    /// Tags of the word from a lexicon, such as its part of speech.
+    pub tags: Vec<String>,
+}
+
+/// A post-processing stage applied to the tokens of a sentence, e.g. attaching readings
+/// or dropping stopwords. Filters are applied in the order the caller chooses.
+pub trait TokenFilter {
+    /// Applies the filter to the tokens of one sentence.
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to annotate, replace or remove, in sentence order.
+    fn apply(&self, tokens: &mut Vec<Token>);
+}
+
+impl From<ScoredWord> for Token {
+    fn from(word: ScoredWord) -> Self {
+        Token {
+            surface: word.surface,
+            start: word.start,
+            end: word.end,
+            score: word.score,
+            ..Token::default()
+        }
+    }
+}
+
+/// Sets the character offsets of tokens from their byte offsets in `sentence`.
+///
+/// The sentence is scanned once when the tokens are in order, as segmentation produces
+/// them.
+///
+/// # Arguments
+/// * `tokens` - The tokens, whose byte offsets must be at character boundaries of
+///   `sentence`.
+/// * `sentence` - The sentence the tokens were taken from.
+pub fn set_char_offsets(tokens: &mut [Token], sentence: &str) {
+    // Byte and character offsets of the position reached so far.
+    let mut byte = 0;
+    let mut chars = 0;
+    let mut advance = |to: usize| {
+        if to < byte {
+            byte = 0;
+            chars = 0;
+        }
+        chars += sentence[byte..to].chars().count();
+        byte = to;
+        chars
+    };
+    for token in tokens {
+        token.char_start = advance(token.start);
+        token.char_end = advance(token.end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_set_char_offsets() {
+        let sentence = "東京 is big";
+        let mut tokens: Vec<Token> =
+            [("東京", 0, 6), ("is", 7, 9), ("big", 10, 13), ("東京", 0, 6)]
+                .iter()
+                .map(|&(surface, start, end)| Token {
+                    surface: surface.to_string(),
+                    start,
+                    end,
+                    ..Token::default()
+                })
+                .collect();
+        set_char_offsets(&mut tokens, sentence);
+        let spans: Vec<(usize, usize)> =
+            tokens.iter().map(|t| (t.char_start, t.char_end)).collect();
+        assert_eq!(spans, [(0, 2), (3, 5), (6, 9), (0, 2)]);
+    }
+}
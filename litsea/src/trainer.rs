@@ -1,8 +1,117 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::sync::atomic::AtomicBool;
 
-use crate::adaboost::{AdaBoost, Metrics};
+use crate::adaboost::{AdaBoost, Algorithm, Boosting, Dataset, Metrics, TrainingRecord};
+use crate::control::TrainControl;
+use crate::ensemble::Ensemble;
+use crate::error::{self, LitseaError};
+use crate::evaluation::f1;
+use crate::extractor::Extractor;
+use crate::model_info;
+use crate::progress::ProgressHandler;
+use crate::quantize::{Quantization, QuantizationError};
+
+/// Mean and standard deviation of a metric across the folds of a cross-validation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct Statistic {
+    /// The mean across the folds.
+    pub mean: f64,
+    /// The population standard deviation across the folds.
+    pub stddev: f64,
+}
+
+impl Statistic {
+    /// Computes the mean and standard deviation of values, or zeros if there are none.
+    fn from_values<I: IntoIterator<Item = f64>>(values: I) -> Self {
+        let values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return Statistic::default();
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        Statistic {
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+}
+
+/// The result of [`Trainer::cross_validate`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct CrossValidation {
+    /// The metrics of each fold on its held-out instances, in fold order. Fewer than the
+    /// requested number of folds if the cross-validation was interrupted.
+    pub folds: Vec<Metrics>,
+    /// Precision on the held-out instances in percentage (%).
+    pub precision: Statistic,
+    /// Recall on the held-out instances in percentage (%).
+    pub recall: Statistic,
+    /// Harmonic mean of the precision and recall of each fold in percentage (%).
+    pub f1: Statistic,
+}
+
+impl CrossValidation {
+    fn new(folds: Vec<Metrics>) -> Self {
+        CrossValidation {
+            precision: Statistic::from_values(folds.iter().map(|m| m.precision)),
+            recall: Statistic::from_values(folds.iter().map(|m| m.recall)),
+            f1: Statistic::from_values(folds.iter().map(|m| f1(m.precision, m.recall))),
+            folds,
+        }
+    }
+}
+
+/// The result of pruning a trained model, returned by [`Trainer::prune_report`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct PruneReport {
+    /// The number of features with a non-zero weight before pruning.
+    pub features_before: usize,
+    /// The number of features kept.
+    pub features_after: usize,
+    /// The metrics on the training data before pruning.
+    pub metrics_before: Metrics,
+    /// The metrics on the training data after pruning.
+    pub metrics_after: Metrics,
+    /// The metrics on the development set before pruning, if one was set.
+    pub dev_metrics_before: Option<Metrics>,
+    /// The metrics on the development set after pruning, if one was set.
+    pub dev_metrics_after: Option<Metrics>,
+}
+
+/// The result of quantizing a trained model, returned by [`Trainer::quantization_report`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct QuantizationReport {
+    /// How the weights were quantized.
+    pub quantization: Quantization,
+    /// How much the weights changed.
+    pub error: QuantizationError,
+    /// The metrics on the training data before quantization.
+    pub metrics_before: Metrics,
+    /// The metrics on the training data after quantization.
+    pub metrics_after: Metrics,
+    /// The metrics on the development set before quantization, if one was set.
+    pub dev_metrics_before: Option<Metrics>,
+    /// The metrics on the development set after quantization, if one was set.
+    pub dev_metrics_after: Option<Metrics>,
+}
+
+/// The result of training an ensemble, returned by [`Trainer::ensemble_report`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "config", derive(serde::Serialize))]
+pub struct EnsembleReport {
+    /// The metrics of each model on the instances left out of its bootstrap sample, in
+    /// training order. Fewer than the requested number of models if the training was
+    /// interrupted.
+    pub out_of_bag: Vec<Metrics>,
+    /// Harmonic mean of the out-of-bag precision and recall of each model in
+    /// percentage (%).
+    pub f1: Statistic,
+}
 
 /// Trainer struct for managing the AdaBoost training process.
 /// It initializes the AdaBoost learner with the specified parameters,
@@ -10,6 +119,19 @@ use crate::adaboost::{AdaBoost, Metrics};
 /// and save the trained model.
 pub struct Trainer {
     learner: AdaBoost,
+    /// The instances the learner was initialized from, shared with the models of a
+    /// cross-validation or an ensemble; `None` for instances added to a learner.
+    dataset: Option<Dataset>,
+    /// The features file, `None` for instances given in memory.
+    features_path: Option<PathBuf>,
+    prune_below: Option<f64>,
+    max_features: Option<usize>,
+    prune_report: Option<PruneReport>,
+    quantization: Option<Quantization>,
+    quantization_report: Option<QuantizationReport>,
+    /// The number of bootstrap models to average and the seed of their sampling.
+    ensemble: Option<(usize, u64)>,
+    ensemble_report: Option<EnsembleReport>,
 }
 
 impl Trainer {
@@ -29,13 +151,208 @@ impl Trainer {
         threshold: f64,
         num_iterations: usize,
         features_path: &Path,
-    ) -> std::io::Result<Self> {
+    ) -> crate::error::Result<Self> {
+        let dataset = Dataset::load(features_path)?;
+        let mut learner = AdaBoost::new(threshold, num_iterations);
+        learner.set_dataset(&dataset);
+
+        Ok(Trainer {
+            learner,
+            dataset: Some(dataset),
+            features_path: Some(features_path.to_path_buf()),
+            prune_below: None,
+            max_features: None,
+            prune_report: None,
+            quantization: None,
+            quantization_report: None,
+            ensemble: None,
+            ensemble_report: None,
+        })
+    }
+
+    /// Creates a new instance of [`Trainer`] for a features file with a weight column
+    /// after the label, such as the count column of a file deduplicated by
+    /// [`sort_features`](crate::feature_file::sort_features). See
+    /// [`AdaBoost::set_weighted`].
+    ///
+    /// # Arguments
+    /// * `threshold` - The threshold for the AdaBoost algorithm.
+    /// * `num_iterations` - The number of iterations for the training.
+    /// * `features_path` - The path to the weighted features file.
+    ///
+    /// # Returns
+    /// Returns a new instance of `Trainer`.
+    ///
+    /// # Errors
+    /// Returns an error if the features or instances cannot be initialized or a weight is
+    /// missing or not a positive number.
+    pub fn with_weights(
+        threshold: f64,
+        num_iterations: usize,
+        features_path: &Path,
+    ) -> crate::error::Result<Self> {
+        let dataset = Dataset::load_weighted(features_path)?;
+        let mut learner = AdaBoost::new(threshold, num_iterations);
+        // Weighted development sets are read with their weight column.
+        learner.set_weighted(true);
+        learner.set_dataset(&dataset);
+
+        Ok(Trainer {
+            learner,
+            dataset: Some(dataset),
+            features_path: Some(features_path.to_path_buf()),
+            prune_below: None,
+            max_features: None,
+            prune_report: None,
+            quantization: None,
+            quantization_report: None,
+            ensemble: None,
+            ensemble_report: None,
+        })
+    }
+
+    /// Creates a new instance of [`Trainer`] from sentences in memory, without writing a
+    /// features file.
+    ///
+    /// # Arguments
+    /// * `threshold` - The threshold for the AdaBoost algorithm.
+    /// * `num_iterations` - The number of iterations for the training.
+    /// * `extractor` - The extractor with the language, normalization and feature
+    ///   templates to extract the features with.
+    /// * `lines` - Sentences with words separated by spaces.
+    ///
+    /// # Returns
+    /// Returns a new instance of `Trainer`.
+    ///
+    /// # Example
+    /// ```
+    /// use litsea::extractor::Extractor;
+    /// use litsea::language::Language;
+    /// use litsea::trainer::Trainer;
+    ///
+    /// let extractor = Extractor::new(Language::Japanese);
+    /// let lines = ["これ は ペン です 。", "あれ は 本 です 。"];
+    /// let trainer = Trainer::from_corpus_lines(0.01, 10, &extractor, lines);
+    /// assert_eq!(trainer.num_instances(), 13);
+    /// ```
+    pub fn from_corpus_lines<I, S>(
+        threshold: f64,
+        num_iterations: usize,
+        extractor: &Extractor,
+        lines: I,
+    ) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut learner = AdaBoost::new(threshold, num_iterations);
+        for (attributes, label) in extractor.extract_instances(lines) {
+            learner.add_instance(attributes, label);
+        }
+        Trainer::from_learner(learner)
+    }
+
+    /// Creates a new instance of [`Trainer`] for the instances already added to a
+    /// learner, e.g. with [`Segmenter::add_sentence`](crate::segmenter::Segmenter::add_sentence)
+    /// or [`AdaBoost::add_instance`].
+    ///
+    /// # Arguments
+    /// * `learner` - The learner with the training instances and settings.
+    ///
+    /// # Returns
+    /// Returns a new instance of `Trainer`.
+    #[must_use]
+    pub fn from_learner(learner: AdaBoost) -> Self {
+        Trainer {
+            learner,
+            dataset: None,
+            features_path: None,
+            prune_below: None,
+            max_features: None,
+            prune_report: None,
+            quantization: None,
+            quantization_report: None,
+            ensemble: None,
+            ensemble_report: None,
+        }
+    }
+
+    /// Creates a new instance of [`Trainer`] for a dataset read before, e.g. to train
+    /// with several settings without reading the features file again. The trainer shares
+    /// the instances of the dataset.
+    ///
+    /// # Arguments
+    /// * `threshold` - The threshold for the AdaBoost algorithm.
+    /// * `num_iterations` - The number of iterations for the training.
+    /// * `dataset` - The training instances.
+    ///
+    /// # Returns
+    /// Returns a new instance of `Trainer`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use std::path::Path;
+    ///
+    /// use litsea::adaboost::Dataset;
+    /// use litsea::control::TrainControl;
+    /// use litsea::trainer::Trainer;
+    ///
+    /// let dataset = Dataset::load(Path::new("features.txt"))?;
+    /// for num_iterations in [100, 1000] {
+    ///     let mut trainer = Trainer::from_dataset(0.01, num_iterations, &dataset);
+    ///     let metrics = trainer.fit(TrainControl::new())?;
+    ///     println!("{}: {:.2}%", num_iterations, metrics.accuracy);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn from_dataset(threshold: f64, num_iterations: usize, dataset: &Dataset) -> Self {
+        let mut learner = AdaBoost::new(threshold, num_iterations);
+        learner.set_dataset(dataset);
+        let mut trainer = Trainer::from_learner(learner);
+        trainer.dataset = Some(dataset.clone());
+        trainer
+    }
+
+    /// Creates a new instance of [`Trainer`] that keeps the training instances in a
+    /// memory-mapped instance file instead of in memory, for features files larger than
+    /// memory.
+    ///
+    /// # Arguments
+    /// * `threshold` - The threshold for the AdaBoost algorithm.
+    /// * `num_iterations` - The number of iterations for the training.
+    /// * `features_path` - The path to the features file.
+    /// * `instance_path` - The path of the instance file, which is overwritten.
+    ///
+    /// # Returns
+    /// Returns a new instance of `Trainer`.
+    ///
+    /// # Errors
+    /// Returns an error if the features cannot be initialized or the instance file cannot
+    /// be written or mapped.
+    #[cfg(feature = "mmap")]
+    pub fn with_instance_file(
+        threshold: f64,
+        num_iterations: usize,
+        features_path: &Path,
+        instance_path: &Path,
+    ) -> crate::error::Result<Self> {
         let mut learner = AdaBoost::new(threshold, num_iterations);
 
-        learner.initialize_features(features_path)?;
-        learner.initialize_instances(features_path)?;
+        learner.initialize_mapped(features_path, instance_path)?;
 
-        Ok(Trainer { learner })
+        Ok(Trainer {
+            dataset: Some(learner.dataset()),
+            learner,
+            features_path: Some(features_path.to_path_buf()),
+            prune_below: None,
+            max_features: None,
+            prune_report: None,
+            quantization: None,
+            quantization_report: None,
+            ensemble: None,
+            ensemble_report: None,
+        })
     }
 
     /// Load Model from a URI.
@@ -48,33 +365,437 @@ impl Trainer {
     ///
     /// # Errors
     /// Returns an error if the model cannot be loaded.
-    pub async fn load_model(&mut self, model_uri: &str) -> std::io::Result<()> {
+    pub async fn load_model(&mut self, model_uri: &str) -> crate::error::Result<()> {
         self.learner.load_model(model_uri).await
     }
 
+    /// Set a metadata entry to write to the trained model.
+    ///
+    /// # Arguments
+    /// * `key` - The metadata key.
+    /// * `value` - The metadata value.
+    ///
+    /// # Errors
+    /// Returns an error if the key is empty or contains whitespace, or the value contains a
+    /// line break.
+    pub fn set_metadata(&mut self, key: &str, value: &str) -> crate::error::Result<()> {
+        self.learner.set_metadata(key, value)
+    }
+
+    /// Set the weak learners to combine: discrete AdaBoost, the default, or Real AdaBoost,
+    /// which usually needs far fewer iterations.
+    ///
+    /// # Arguments
+    /// * `algorithm` - The boosting algorithm.
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.learner.set_algorithm(algorithm);
+    }
+
+    /// Set how instances are reweighted during training, e.g. to tolerate label noise in
+    /// automatically segmented corpora.
+    ///
+    /// # Arguments
+    /// * `boosting` - The boosting variant.
+    ///
+    /// # Errors
+    /// Returns an error if the cap of [`Boosting::Capped`] is less than 1.
+    pub fn set_boosting(&mut self, boosting: Boosting) -> crate::error::Result<()> {
+        self.learner.set_boosting(boosting)
+    }
+
+    /// Set a development features file, held out from training, to monitor the gap
+    /// between the training and development accuracy during training.
+    ///
+    /// Call it after [`load_model`](Self::load_model) when resuming from a model.
+    ///
+    /// # Arguments
+    /// * `dev_features_path` - The path to the development features file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read.
+    pub fn set_dev_set(&mut self, dev_features_path: &Path) -> crate::error::Result<()> {
+        self.learner.initialize_dev_instances(dev_features_path)
+    }
+
+    /// Report overfitting when the gap between the training and development accuracy
+    /// has grown `patience` times without shrinking, and optionally stop training.
+    ///
+    /// # Arguments
+    /// * `patience` - The number of growths of the gap that signal overfitting.
+    /// * `stop` - Whether to stop training when overfitting is detected.
+    ///
+    /// # Errors
+    /// Returns an error if `patience` is 0.
+    pub fn set_overfitting_patience(
+        &mut self,
+        patience: usize,
+        stop: bool,
+    ) -> crate::error::Result<()> {
+        self.learner.set_overfitting_patience(patience, stop)
+    }
+
+    /// Stop training when the F1 score on the development set has not improved for
+    /// `patience` iterations, and keep the model of the best iteration.
+    ///
+    /// # Arguments
+    /// * `patience` - The number of iterations without improvement before stopping.
+    ///
+    /// # Errors
+    /// Returns an error if `patience` is 0.
+    pub fn set_early_stopping(&mut self, patience: usize) -> crate::error::Result<()> {
+        self.learner.set_early_stopping(patience)
+    }
+
+    /// Choose the hypothesis of each iteration from a random batch of instances, with an
+    /// iteration on all instances every `correction_interval` iterations, to train on
+    /// large features files faster.
+    ///
+    /// # Arguments
+    /// * `batch_size` - The expected number of instances per batch.
+    /// * `correction_interval` - The number of iterations between full iterations.
+    ///
+    /// # Errors
+    /// Returns an error if `batch_size` or `correction_interval` is 0.
+    pub fn set_mini_batch(
+        &mut self,
+        batch_size: usize,
+        correction_interval: usize,
+    ) -> crate::error::Result<()> {
+        self.learner.set_mini_batch(batch_size, correction_interval)
+    }
+
+    /// Set the maximum number of threads to train on, 0 for one per available core.
+    ///
+    /// # Arguments
+    /// * `num_threads` - The maximum number of threads.
+    pub fn set_num_threads(&mut self, num_threads: usize) {
+        self.learner.set_num_threads(num_threads);
+    }
+
+    /// Set the handler the training reports its progress to after every iteration, see
+    /// [`AdaBoost::set_progress_handler`]. The folds of [`cross_validate`](Self::cross_validate)
+    /// report nothing.
+    ///
+    /// # Arguments
+    /// * `handler` - The handler, or `None` to report nothing.
+    pub fn set_progress_handler(&mut self, handler: Option<Arc<dyn ProgressHandler>>) {
+        self.learner.set_progress_handler(handler);
+    }
+
+    /// Set how often the accuracy on the training instances is reported to the progress
+    /// handler, see [`AdaBoost::set_accuracy_interval`].
+    ///
+    /// # Arguments
+    /// * `interval` - The number of iterations between reports, or 0 for none.
+    pub fn set_accuracy_interval(&mut self, interval: usize) {
+        self.learner.set_accuracy_interval(interval);
+    }
+
+    /// Drop the features whose weight is smaller than `threshold` in absolute value from
+    /// the trained model before it is saved, see [`AdaBoost::prune`].
+    ///
+    /// # Arguments
+    /// * `threshold` - The smallest absolute weight to keep.
+    ///
+    /// # Errors
+    /// Returns an error if `threshold` is negative or not finite.
+    pub fn set_prune_below(&mut self, threshold: f64) -> crate::error::Result<()> {
+        if !(threshold.is_finite() && threshold >= 0.0) {
+            return Err(LitseaError::InvalidInput(format!(
+                "Invalid pruning threshold: {}. It must be a non-negative number.",
+                threshold
+            )));
+        }
+        self.prune_below = Some(threshold);
+        Ok(())
+    }
+
+    /// Keep only the `max_features` features with the largest weights in the trained model
+    /// before it is saved, see [`AdaBoost::prune_to`].
+    ///
+    /// # Arguments
+    /// * `max_features` - The number of features to keep.
+    pub fn set_max_features(&mut self, max_features: usize) {
+        self.max_features = Some(max_features);
+    }
+
+    /// Returns the number of features and the metrics before and after pruning in the
+    /// last training, if pruning was set.
+    #[must_use]
+    pub fn prune_report(&self) -> Option<&PruneReport> {
+        self.prune_report.as_ref()
+    }
+
+    /// Quantize the weights of the trained model before it is saved, after pruning, see
+    /// [`AdaBoost::quantize`].
+    ///
+    /// # Arguments
+    /// * `quantization` - How to quantize the weights, `None` to keep them.
+    pub fn set_quantization(&mut self, quantization: Option<Quantization>) {
+        self.quantization = quantization;
+    }
+
+    /// Returns the error of the weights and the metrics before and after quantization in
+    /// the last training, if quantization was set.
+    #[must_use]
+    pub fn quantization_report(&self) -> Option<&QuantizationReport> {
+        self.quantization_report.as_ref()
+    }
+
+    /// Train an ensemble of `size` models on bootstrap samples of the features file in
+    /// place of a single model, and save the model that averages their scores, see the
+    /// [`ensemble`](crate::ensemble) module. The models are trained from scratch, so a
+    /// model loaded to resume from is replaced, and the training log stays empty.
+    ///
+    /// # Arguments
+    /// * `size` - The number of models, at least 2.
+    /// * `seed` - The seed of the bootstrap sampling.
+    ///
+    /// # Errors
+    /// Returns an error if `size` is less than 2 or the trainer has no features file or
+    /// dataset.
+    pub fn set_ensemble(&mut self, size: usize, seed: u64) -> crate::error::Result<()> {
+        if size < 2 {
+            return Err(LitseaError::InvalidInput(format!(
+                "Invalid ensemble size {}: an ensemble needs at least 2 models",
+                size
+            )));
+        }
+        if self.dataset.is_none() {
+            return Err(LitseaError::InvalidInput(
+                "An ensemble needs the instances of a features file or a dataset".to_string(),
+            ));
+        }
+        self.ensemble = Some((size, seed));
+        Ok(())
+    }
+
+    /// Returns the out-of-bag metrics of the models of the last training, if an ensemble
+    /// was set.
+    #[must_use]
+    pub fn ensemble_report(&self) -> Option<&EnsembleReport> {
+        self.ensemble_report.as_ref()
+    }
+
+    /// Returns the training and development accuracy after each iteration of the last
+    /// training, empty without a development set.
+    #[must_use]
+    pub fn training_log(&self) -> &[TrainingRecord] {
+        self.learner.training_log()
+    }
+
+    /// Returns the metrics of the trained model on the development set, if one was set.
+    #[must_use]
+    pub fn dev_metrics(&self) -> Option<Metrics> {
+        self.learner.dev_metrics()
+    }
+
+    /// Returns the iteration of the model kept by early stopping in the last training.
+    #[must_use]
+    pub fn best_iteration(&self) -> Option<usize> {
+        self.learner.best_iteration()
+    }
+
+    /// Estimates how well a model trained on the features file generalizes with k-fold
+    /// cross-validation: the instances are split into `num_folds` contiguous blocks, and
+    /// for each block a model is trained on the others and scored on it.
+    ///
+    /// The models are trained from scratch with the settings of this trainer, such as the
+    /// threshold, the number of iterations and the boosting variant, and are discarded.
+    /// The folds share the instances the trainer read, without reading the features file
+    /// again. For a trainer created with [`with_instance_file`](Self::with_instance_file),
+    /// the instances of each fold are copied into memory.
+    ///
+    /// # Arguments
+    /// * `num_folds` - The number of folds, at least 2.
+    /// * `control` - Stops, pauses or resumes the cross-validation. A stop discards the
+    ///   fold in progress.
+    ///
+    /// # Returns
+    /// The metrics of each fold on its held-out instances, with their mean and standard
+    /// deviation.
+    ///
+    /// # Errors
+    /// Returns an error if `num_folds` is less than 2 or the trainer has no features file
+    /// or dataset.
+    pub fn cross_validate(
+        &self,
+        num_folds: usize,
+        control: impl Into<TrainControl>,
+    ) -> crate::error::Result<CrossValidation> {
+        let control = control.into();
+        let Some(dataset) = &self.dataset else {
+            return Err(LitseaError::InvalidInput(
+                "Cross-validation needs the instances of a features file or a dataset".to_string(),
+            ));
+        };
+        let mut folds = Vec::with_capacity(num_folds);
+        for fold in 0..num_folds {
+            let mut learner = self.learner.untrained_copy();
+            learner.set_dataset_fold(dataset, num_folds, fold)?;
+            if control.is_stopped() {
+                break;
+            }
+            learner.train(control.clone());
+            if control.is_stopped() {
+                break;
+            }
+            if let Some(metrics) = learner.dev_metrics() {
+                trace_event!(
+                    info,
+                    fold,
+                    precision = metrics.precision,
+                    recall = metrics.recall,
+                    "cross-validation fold"
+                );
+                folds.push(metrics);
+            }
+        }
+        Ok(CrossValidation::new(folds))
+    }
+
     /// Train the AdaBoost model.
     ///
     /// # Arguments
-    /// * `running` - An `Arc<AtomicBool>` to control the running state of the training process.
+    /// * `control` - Stops, pauses or resumes the training, see [`AdaBoost::train`]. The
+    ///   model trained until a stop is saved.
     /// * `model_path` - The path to save the trained model.
     ///
     /// # Returns
     /// Returns a Result indicating success or failure.
     ///
     /// # Errors
-    /// Returns an error if the training fails or if the model cannot be saved.
+    /// Returns [`LitseaError::Io`] if the model cannot be saved.
     pub fn train(
         &mut self,
-        running: Arc<AtomicBool>,
+        control: impl Into<TrainControl>,
         model_path: &Path,
-    ) -> Result<Metrics, Box<dyn std::error::Error>> {
-        self.learner.train(running);
+    ) -> error::Result<Metrics> {
+        let metrics = self.fit(control)?;
 
         // Save the trained model to the specified file
         self.learner.save_model(model_path)?;
+        trace_event!(info, path = %model_path.display(), "saved model");
+
+        Ok(metrics)
+    }
 
+    /// Train the AdaBoost model without saving it, e.g. to segment with it right away
+    /// after [`into_learner`](Self::into_learner).
+    ///
+    /// # Arguments
+    /// * `control` - Stops, pauses or resumes the training, see [`AdaBoost::train`].
+    ///
+    /// # Returns
+    /// The metrics of the model on the training instances.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if the name of the features file cannot be
+    /// recorded in the metadata.
+    pub fn fit(&mut self, control: impl Into<TrainControl>) -> error::Result<Metrics> {
+        // Name the training data after the features file unless it was set.
+        if self.learner.metadata(model_info::CORPUS_KEY).is_none() {
+            let name = self
+                .features_path
+                .as_deref()
+                .and_then(Path::file_name)
+                .map(|n| n.to_string_lossy().into_owned());
+            if let Some(name) = name {
+                self.set_metadata(model_info::CORPUS_KEY, &name)?;
+            }
+        }
+        match (self.ensemble, &self.dataset) {
+            (Some((size, seed)), Some(dataset)) => {
+                let ensemble = Ensemble::train(&self.learner, dataset, size, seed, control)?;
+                ensemble.average_into(&mut self.learner);
+                let out_of_bag = ensemble.out_of_bag_metrics();
+                self.ensemble_report = Some(EnsembleReport {
+                    f1: Statistic::from_values(
+                        out_of_bag.iter().map(|m| f1(m.precision, m.recall)),
+                    ),
+                    out_of_bag,
+                });
+            }
+            _ => self.learner.train(control),
+        }
+        self.prune();
+        self.quantize();
         Ok(self.learner.get_metrics())
     }
+
+    /// Returns the number of training instances.
+    #[must_use]
+    pub fn num_instances(&self) -> usize {
+        self.learner.num_instances()
+    }
+
+    /// Returns the learner, e.g. to segment with the trained model.
+    #[must_use]
+    pub fn into_learner(self) -> AdaBoost {
+        self.learner
+    }
+
+    /// Prunes the trained model as set, and records the metrics before and after.
+    fn prune(&mut self) {
+        if self.prune_below.is_none() && self.max_features.is_none() {
+            return;
+        }
+        let features_before = self.learner.weights().count();
+        let metrics_before = self.learner.get_metrics();
+        let dev_metrics_before = self.learner.dev_metrics();
+
+        if let Some(threshold) = self.prune_below {
+            self.learner.prune(threshold);
+        }
+        if let Some(max_features) = self.max_features {
+            self.learner.prune_to(max_features);
+        }
+
+        let report = PruneReport {
+            features_before,
+            features_after: self.learner.weights().count(),
+            metrics_before,
+            metrics_after: self.learner.get_metrics(),
+            dev_metrics_before,
+            dev_metrics_after: self.learner.dev_metrics(),
+        };
+        trace_event!(
+            info,
+            features_before = report.features_before,
+            features_after = report.features_after,
+            accuracy_before = report.metrics_before.accuracy,
+            accuracy_after = report.metrics_after.accuracy,
+            "pruned model"
+        );
+        self.prune_report = Some(report);
+    }
+
+    fn quantize(&mut self) {
+        let Some(quantization) = self.quantization else {
+            return;
+        };
+        let metrics_before = self.learner.get_metrics();
+        let dev_metrics_before = self.learner.dev_metrics();
+        let error = self.learner.quantize(quantization);
+
+        let report = QuantizationReport {
+            quantization,
+            error,
+            metrics_before,
+            metrics_after: self.learner.get_metrics(),
+            dev_metrics_before,
+            dev_metrics_after: self.learner.dev_metrics(),
+        };
+        trace_event!(
+            info,
+            quantization = %quantization,
+            max_error = report.error.max_error,
+            accuracy_before = report.metrics_before.accuracy,
+            accuracy_after = report.metrics_after.accuracy,
+            "quantized model"
+        );
+        self.quantization_report = Some(report);
+    }
 }
 
 #[cfg(test)]
@@ -111,6 +832,30 @@ mod tests {
         file
     }
 
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_with_instance_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut features_file = NamedTempFile::new()?;
+        writeln!(features_file, "1 UW4:は BW1:はテ")?;
+        writeln!(features_file, "-1 UW4:テ BW1:テス")?;
+        writeln!(features_file, "-1 UW4:ス BW1:スト")?;
+        writeln!(features_file, "1 UW4:ト")?;
+        let instance_file = NamedTempFile::new()?;
+        let running = Arc::new(AtomicBool::new(true));
+
+        let mut in_memory = Trainer::new(0.001, 10, features_file.path())?;
+        let mut mapped =
+            Trainer::with_instance_file(0.001, 10, features_file.path(), instance_file.path())?;
+        let memory_model = NamedTempFile::new()?;
+        let mapped_model = NamedTempFile::new()?;
+        let memory_metrics = in_memory.train(running.clone(), memory_model.path())?;
+        let mapped_metrics = mapped.train(running, mapped_model.path())?;
+
+        assert_eq!(mapped_metrics.accuracy, memory_metrics.accuracy);
+        assert_eq!(std::fs::read(mapped_model.path())?, std::fs::read(memory_model.path())?);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_load_model() -> Result<(), Box<dyn std::error::Error>> {
         // Prepare a dummy features file
@@ -130,10 +875,135 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_cross_validate() -> Result<(), Box<dyn std::error::Error>> {
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..10 {
+            writeln!(features_file, "1 UW4:は\n-1 UW4:テ")?;
+        }
+        let mut trainer = Trainer::new(0.001, 10, features_file.path())?;
+        trainer.set_num_threads(1);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let cv = trainer.cross_validate(4, running.clone())?;
+        assert_eq!(cv.folds.len(), 4);
+        assert!(cv.folds.iter().all(|m| m.num_instances == 5));
+        // The boundary feature separates the labels of every fold.
+        assert_eq!(
+            cv.f1,
+            Statistic {
+                mean: 100.0,
+                stddev: 0.0
+            }
+        );
+        assert_eq!(cv.precision.mean, 100.0);
+
+        assert!(trainer.cross_validate(1, running).is_err());
+        let stopped = trainer.cross_validate(4, Arc::new(AtomicBool::new(false)))?;
+        assert!(stopped.folds.is_empty());
+        assert_eq!(stopped.f1, Statistic::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ensemble() -> Result<(), Box<dyn std::error::Error>> {
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..10 {
+            writeln!(features_file, "1 UW4:は\n-1 UW4:テ")?;
+        }
+        let mut trainer = Trainer::new(0.001, 10, features_file.path())?;
+        assert!(trainer.set_ensemble(1, 0).is_err());
+        trainer.set_ensemble(3, 0)?;
+        let model_file = NamedTempFile::new()?;
+
+        let metrics = trainer.train(Arc::new(AtomicBool::new(true)), model_file.path())?;
+        assert_eq!(metrics.accuracy, 100.0);
+        let report = trainer.ensemble_report().unwrap();
+        assert_eq!(report.out_of_bag.len(), 3);
+        assert_eq!(report.f1.mean, 100.0);
+
+        let mut learner = AdaBoost::new(0.001, 10);
+        learner.load_model_str(&std::fs::read_to_string(model_file.path())?)?;
+        assert_eq!(learner.metadata(model_info::ENSEMBLE_KEY), Some("3"));
+
+        let mut in_memory = Trainer::from_learner(AdaBoost::new(0.001, 10));
+        assert!(in_memory.set_ensemble(3, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune() -> Result<(), Box<dyn std::error::Error>> {
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..10 {
+            writeln!(features_file, "1 UW4:は BW1:はテ\n-1 UW4:テ\n-1 UW4:ス")?;
+        }
+        writeln!(features_file, "1 UW4:ス")?;
+        let mut trainer = Trainer::new(0.001, 10, features_file.path())?;
+        assert!(trainer.set_prune_below(-1.0).is_err());
+        assert!(trainer.set_prune_below(f64::NAN).is_err());
+        trainer.set_max_features(1);
+        let model_file = NamedTempFile::new()?;
+
+        let metrics = trainer.train(Arc::new(AtomicBool::new(true)), model_file.path())?;
+        let report = trainer.prune_report().unwrap();
+        assert!(report.features_before > 1);
+        assert_eq!(report.features_after, 1);
+        assert_eq!(report.metrics_after.accuracy, metrics.accuracy);
+        assert!(report.dev_metrics_before.is_none());
+
+        let mut learner = AdaBoost::new(0.001, 10);
+        learner.load_model_str(&std::fs::read_to_string(model_file.path())?)?;
+        assert_eq!(learner.weights().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quantize() -> Result<(), Box<dyn std::error::Error>> {
+        let mut features_file = NamedTempFile::new()?;
+        for _ in 0..10 {
+            writeln!(features_file, "1 UW4:は BW1:はテ\n-1 UW4:テ\n-1 UW4:ス")?;
+        }
+        writeln!(features_file, "1 UW4:ス")?;
+        let mut trainer = Trainer::new(0.001, 10, features_file.path())?;
+        trainer.set_quantization(Some(Quantization::I8));
+        let model_file = NamedTempFile::new()?;
+
+        let metrics = trainer.train(TrainControl::new(), model_file.path())?;
+        let report = trainer.quantization_report().unwrap();
+        assert_eq!(report.quantization, Quantization::I8);
+        assert!(report.error.max_error > 0.0);
+        assert_eq!(report.metrics_after.accuracy, metrics.accuracy);
+        assert!(report.dev_metrics_after.is_none());
+
+        let content = std::fs::read_to_string(model_file.path())?;
+        assert!(content.contains("\n#quantization i8 "), "{content}");
+        let mut loaded = AdaBoost::new(0.001, 10);
+        loaded.load_model_str(&content)?;
+        let trained = trainer.into_learner();
+        assert_eq!(loaded.weights().count(), trained.weights().count());
+        for attribute in ["UW4:は", "UW4:テ", "UW4:ス"] {
+            let score = trained.score_attributes([attribute]);
+            assert!((loaded.score_attributes([attribute]) - score).abs() < 1e-9);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_statistic() {
+        let s = Statistic::from_values([1.0, 3.0]);
+        assert_eq!(
+            s,
+            Statistic {
+                mean: 2.0,
+                stddev: 1.0
+            }
+        );
+    }
+
     #[test]
     fn test_new_empty_features_file() {
         // A features file with no actual features (only labels) should return an error
-        // because initialize_features() requires at least one feature beyond the bias term.
+        // because initialize() requires at least one feature beyond the bias term.
         let mut file = NamedTempFile::new().expect("Failed to create temp file");
         // Write a line with only a label and no feature names.
         writeln!(file, "1").expect("Failed to write");
@@ -165,4 +1035,33 @@ mod tests {
         assert!(metrics.recall >= 0.0);
         Ok(())
     }
+
+    #[test]
+    fn test_in_memory() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::language::Language;
+        use crate::segmenter::Segmenter;
+
+        let lines: Vec<String> =
+            ["これ は ペン です 。", "", "あれ は 本 です 。"].map(String::from).to_vec();
+        let extractor = Extractor::new(Language::Japanese);
+        let mut trainer = Trainer::from_corpus_lines(0.01, 20, &extractor, lines.iter());
+        assert_eq!(trainer.num_instances(), 13);
+        let metrics = trainer.fit(Arc::new(AtomicBool::new(true)))?;
+        assert_eq!(metrics.accuracy, 100.0);
+        // Without a features file, there is no corpus name and no cross-validation.
+        assert!(trainer.cross_validate(2, Arc::new(AtomicBool::new(true))).is_err());
+
+        // The same instances added with Segmenter::add_sentence.
+        let mut segmenter = Segmenter::new(Language::Japanese, None);
+        for line in &lines {
+            segmenter.add_sentence(line);
+        }
+        let mut from_learner = Trainer::from_learner(segmenter.into_learner());
+        assert_eq!(from_learner.num_instances(), 13);
+        from_learner.fit(Arc::new(AtomicBool::new(true)))?;
+        let learner = from_learner.into_learner();
+        assert_eq!(learner.metadata("corpus"), None);
+        assert!(learner.metadata("iterations").is_some());
+        Ok(())
+    }
 }
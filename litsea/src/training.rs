@@ -0,0 +1,306 @@
+//! Training a segmenter from a corpus in one call.
+//!
+//! [`Pipeline`] extracts the features of a segmented corpus, trains a model on them and
+//! returns a [`Segmenter`] ready to use, without the features file that `litsea extract`
+//! and `litsea train` pass between them:
+//!
+//! ```no_run
+//! use litsea::Pipeline;
+//!
+//! let segmenter = Pipeline::builder()
+//!     .corpus("corpus.txt")
+//!     .iterations(500)
+//!     .threads(8)
+//!     .train()?;
+//! println!("{:?}", segmenter.segment("これはテストです。"));
+//! # Ok::<(), litsea::error::LitseaError>(())
+//! ```
+//!
+//! The instances are kept in memory; for corpora whose features do not fit, extract
+//! them to a file and train with [`Trainer::with_instance_file`](crate::trainer::Trainer).
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::adaboost::Algorithm;
+use crate::compression;
+use crate::control::TrainControl;
+use crate::error::{self, LitseaError};
+use crate::extractor::Extractor;
+use crate::language::{Language, LatinClasses};
+use crate::model_info;
+use crate::normalize::Normalizer;
+use crate::progress::ProgressHandler;
+use crate::segmenter::{FeatureTemplates, Segmenter};
+use crate::trainer::Trainer;
+
+/// The settings of training a segmenter from corpus files, made with
+/// [`Pipeline::builder`].
+#[derive(Clone)]
+pub struct Pipeline {
+    language: Language,
+    normalizer: Normalizer,
+    templates: FeatureTemplates,
+    latin_classes: LatinClasses,
+    corpora: Vec<PathBuf>,
+    threshold: f64,
+    num_iterations: usize,
+    num_threads: usize,
+    algorithm: Algorithm,
+    progress: Option<Arc<dyn ProgressHandler>>,
+    model_path: Option<PathBuf>,
+}
+
+/// Builds a [`Pipeline`]. The defaults are those of `litsea extract` and `litsea
+/// train`: Japanese, no normalization, the Litsea templates, a threshold of 0.01 and 100
+/// iterations of discrete AdaBoost on all cores.
+#[derive(Clone)]
+pub struct PipelineBuilder {
+    pipeline: Pipeline,
+}
+
+impl Pipeline {
+    /// Starts building a pipeline with the default settings.
+    #[must_use]
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder {
+            pipeline: Pipeline {
+                language: Language::default(),
+                normalizer: Normalizer::default(),
+                templates: FeatureTemplates::default(),
+                latin_classes: LatinClasses::default(),
+                corpora: Vec::new(),
+                threshold: 0.01,
+                num_iterations: 100,
+                num_threads: 0,
+                algorithm: Algorithm::default(),
+                progress: None,
+                model_path: None,
+            },
+        }
+    }
+
+    /// Extracts the features of the corpora, trains a model on them and saves it if a
+    /// model path was set.
+    ///
+    /// The model records the name of the corpora, the character type scheme and the
+    /// feature templates in its [metadata](crate::model_info), and with the `config`
+    /// feature the [pipeline configuration](crate::pipeline::PipelineConfig), so that
+    /// `litsea segment` picks up the settings.
+    ///
+    /// # Returns
+    /// A segmenter with the trained model. Text must be normalized with the normalizer
+    /// of the pipeline before it is segmented.
+    ///
+    /// # Errors
+    /// Returns [`LitseaError::InvalidInput`] if no corpus was given or the corpora have
+    /// no sentences, and [`LitseaError::Io`] if a corpus cannot be read or the model
+    /// cannot be saved.
+    pub fn train(&self) -> error::Result<Segmenter> {
+        if self.corpora.is_empty() {
+            return Err(LitseaError::InvalidInput("No corpus to train on".to_string()));
+        }
+        trace_span!(INFO, "pipeline", corpora = self.corpora.len());
+        let mut extractor = Extractor::new(self.language);
+        extractor.set_normalizer(self.normalizer);
+        extractor.set_feature_templates(self.templates);
+        extractor.set_latin_classes(self.latin_classes);
+        extractor.set_num_threads(self.num_threads);
+
+        let mut lines = Vec::new();
+        for corpus in &self.corpora {
+            for line in compression::open(corpus)?.lines() {
+                lines.push(line?);
+            }
+        }
+        let mut trainer =
+            Trainer::from_corpus_lines(self.threshold, self.num_iterations, &extractor, lines);
+        if trainer.num_instances() == 0 {
+            return Err(LitseaError::InvalidInput("The corpus has no sentences".to_string()));
+        }
+
+        self.record_metadata(&mut trainer)?;
+        trainer.set_algorithm(self.algorithm);
+        trainer.set_num_threads(self.num_threads);
+        trainer.set_progress_handler(self.progress.clone());
+        let control = TrainControl::new();
+        match &self.model_path {
+            Some(path) => trainer.train(control, path)?,
+            None => trainer.fit(control)?,
+        };
+
+        let mut segmenter = Segmenter::new(self.language, Some(trainer.into_learner()));
+        segmenter.set_feature_templates(self.templates);
+        segmenter.set_latin_classes(self.latin_classes);
+        Ok(segmenter)
+    }
+
+    /// Records the corpora and the settings in the metadata of the model.
+    fn record_metadata(&self, trainer: &mut Trainer) -> error::Result<()> {
+        #[cfg(feature = "config")]
+        {
+            let (key, value) = crate::pipeline::PipelineConfig {
+                language: self.language,
+                normalizer: self.normalizer,
+                feature_templates: self.templates,
+                latin_classes: self.latin_classes,
+                ..Default::default()
+            }
+            .to_metadata()?;
+            trainer.set_metadata(&key, &value)?;
+        }
+
+        let names: Vec<String> = self
+            .corpora
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        trainer.set_metadata(model_info::CORPUS_KEY, &names.join(","))?;
+        trainer.set_metadata(
+            model_info::CHAR_TYPES_KEY,
+            &model_info::char_type_scheme(self.language, self.templates, self.latin_classes),
+        )?;
+        trainer
+            .set_metadata(model_info::TEMPLATES_KEY, &model_info::templates_id(self.templates))?;
+        Ok(())
+    }
+}
+
+impl PipelineBuilder {
+    /// Sets the language, which selects the character types.
+    #[must_use]
+    pub fn language(mut self, language: Language) -> Self {
+        self.pipeline.language = language;
+        self
+    }
+
+    /// Sets the normalization applied to each sentence of the corpora.
+    #[must_use]
+    pub fn normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.pipeline.normalizer = normalizer;
+        self
+    }
+
+    /// Sets the feature templates.
+    #[must_use]
+    pub fn feature_templates(mut self, templates: FeatureTemplates) -> Self {
+        self.pipeline.templates = templates;
+        self
+    }
+
+    /// Sets how Latin letters are divided into character types.
+    #[must_use]
+    pub fn latin_classes(mut self, classes: LatinClasses) -> Self {
+        self.pipeline.latin_classes = classes;
+        self
+    }
+
+    /// Adds a corpus file with one sentence per line and words separated by spaces.
+    /// Files whose names end in `.gz` or `.zst` are decompressed, see
+    /// [`compression`](crate::compression).
+    #[must_use]
+    pub fn corpus<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.pipeline.corpora.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets the error rate below which training stops.
+    #[must_use]
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.pipeline.threshold = threshold;
+        self
+    }
+
+    /// Sets the maximum number of boosting iterations.
+    #[must_use]
+    pub fn iterations(mut self, num_iterations: usize) -> Self {
+        self.pipeline.num_iterations = num_iterations;
+        self
+    }
+
+    /// Sets the maximum number of threads for extraction and training, or 0 for one per
+    /// available core.
+    #[must_use]
+    pub fn threads(mut self, num_threads: usize) -> Self {
+        self.pipeline.num_threads = num_threads;
+        self
+    }
+
+    /// Sets the boosting algorithm.
+    #[must_use]
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.pipeline.algorithm = algorithm;
+        self
+    }
+
+    /// Sets the handler training reports its progress to after every iteration.
+    #[must_use]
+    pub fn progress_handler(mut self, handler: Arc<dyn ProgressHandler>) -> Self {
+        self.pipeline.progress = Some(handler);
+        self
+    }
+
+    /// Saves the trained model to a file as well.
+    #[must_use]
+    pub fn model_path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.pipeline.model_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Finishes building the pipeline.
+    #[must_use]
+    pub fn build(self) -> Pipeline {
+        self.pipeline
+    }
+
+    /// Builds the pipeline and trains with it, see [`Pipeline::train`].
+    ///
+    /// # Errors
+    /// Returns an error if training fails.
+    pub fn train(self) -> error::Result<Segmenter> {
+        self.pipeline.train()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    #[test]
+    fn test_train() -> error::Result<()> {
+        let mut corpus = NamedTempFile::new()?;
+        for _ in 0..20 {
+            writeln!(corpus, "これ は ペン です 。\nあれ は 本 です 。")?;
+        }
+        let model = NamedTempFile::new()?;
+        let segmenter = Pipeline::builder()
+            .corpus(corpus.path())
+            .iterations(50)
+            .threads(2)
+            .model_path(model.path())
+            .train()?;
+        assert_eq!(segmenter.segment("これはペンです。"), ["これ", "は", "ペン", "です", "。"]);
+        assert_eq!(segmenter.learner().metadata("char_types"), Some("japanese-2"));
+        assert_eq!(segmenter.learner().metadata("templates"), Some("litsea-1"));
+        assert!(segmenter.check_model().is_ok());
+
+        let mut learner = crate::adaboost::AdaBoost::new(0.01, 100);
+        learner.load_model_str(&std::fs::read_to_string(model.path())?)?;
+        assert_eq!(learner.weights().count(), segmenter.learner().weights().count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_corpus() {
+        assert!(matches!(Pipeline::builder().train(), Err(LitseaError::InvalidInput(_))));
+        let empty = NamedTempFile::new().unwrap();
+        let result = Pipeline::builder().corpus(empty.path()).train();
+        assert!(matches!(result, Err(LitseaError::InvalidInput(_))));
+    }
+}
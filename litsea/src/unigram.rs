@@ -0,0 +1,441 @@
+//! Unigram word-frequency model for reranking N-best segmentations.
+//!
+//! The boundary classifier decides each boundary from a window of six characters, so it
+//! can be locally confident about a segmentation that is implausible as a whole. A
+//! [`UnigramModel`] counts how often each word occurs in a segmented corpus (it is built
+//! by `litsea vocab`), and a [`UnigramReranker`] rescores the N-best segmentations of
+//! [`Segmenter::segment_nbest`] by adding the weighted log likelihood of their words to
+//! the classifier margin.
+//!
+//! Known words have the add-one smoothed probability `(count + 1) / (total + vocabulary)`.
+//! An unknown word of `k` characters is as likely as `k` unseen words in a row, so the
+//! model does not prefer splitting unknown words either way and leaves them to the
+//! classifier.
+//!
+//! ```
+//! use litsea::unigram::UnigramModel;
+//!
+//! let model = UnigramModel::from_corpus("外国 人 参政 権\n外国 人\n".as_bytes(), None).unwrap();
+//! assert_eq!(model.count("外国"), 2);
+//! assert!(model.log_prob("外国") > model.log_prob("参政"));
+//! assert!(model.log_prob("参政") > model.log_prob("人参"));
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::segmenter::{ScoredWord, Segmentation, Segmenter};
+use crate::stopwords::StopWords;
+use crate::token::{Token, set_char_offsets};
+
+/// Word counts of a segmented corpus.
+#[derive(Debug, Clone, Default)]
+pub struct UnigramModel {
+    counts: HashMap<String, u64>,
+    total: u64,
+}
+
+impl UnigramModel {
+    /// Creates an empty model.
+    ///
+    /// # Returns
+    /// A new [`UnigramModel`] without words.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the words of a segmented corpus, one sentence per line with words
+    /// separated by spaces, as used by `litsea extract`.
+    ///
+    /// # Arguments
+    /// * `reader` - The corpus.
+    /// * `stopwords` - Words that are not counted, if any.
+    ///
+    /// # Returns
+    /// The model with the counts of the corpus.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails.
+    pub fn from_corpus<R: BufRead>(reader: R, stopwords: Option<&StopWords>) -> io::Result<Self> {
+        let mut model = Self::new();
+        for line in reader.lines() {
+            for word in line?.split_whitespace() {
+                if stopwords.is_none_or(|s| !s.contains(word)) {
+                    model.add(word, 1);
+                }
+            }
+        }
+        Ok(model)
+    }
+
+    /// Reads a model from TSV lines of `word<TAB>count`, as written by
+    /// [`write_tsv`](Self::write_tsv). Empty lines and lines starting with `#` are ignored.
+    ///
+    /// # Arguments
+    /// * `reader` - The TSV content.
+    ///
+    /// # Returns
+    /// The model. Counts of repeated words are added up.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails or a line has no valid count.
+    pub fn from_tsv<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut model = Self::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = line.split_once('\t').and_then(|(word, count)| {
+                let count = count.split('\t').next().unwrap_or(count).trim();
+                Some((word, count.parse::<u64>().ok()?)).filter(|(word, _)| !word.is_empty())
+            });
+            let Some((word, count)) = entry else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid vocabulary entry at line {}: '{}'", i + 1, line),
+                ));
+            };
+            model.add(word, count);
+        }
+        Ok(model)
+    }
+
+    /// Loads a model from a TSV file. See [`from_tsv`](Self::from_tsv).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the TSV file.
+    ///
+    /// # Returns
+    /// The loaded model.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is malformed.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::from_tsv(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes the model as TSV lines of `word<TAB>count`, most frequent first.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination of the TSV content.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_tsv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut entries: Vec<(&String, &u64)> = self.counts.iter().collect();
+        entries.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        for (word, count) in entries {
+            writeln!(writer, "{}\t{}", word, count)?;
+        }
+        writer.flush()
+    }
+
+    /// Saves the model to a TSV file. See [`write_tsv`](Self::write_tsv).
+    ///
+    /// # Arguments
+    /// * `path` - The path to the TSV file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_tsv(&mut BufWriter::new(File::create(path)?))
+    }
+
+    /// Adds occurrences of a word.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    /// * `count` - The number of occurrences to add.
+    pub fn add(&mut self, word: &str, count: u64) {
+        *self.counts.entry(word.to_string()).or_insert(0) += count;
+        self.total += count;
+    }
+
+    /// Removes the words that occur fewer than `min_count` times, e.g. to drop typos
+    /// and keep the vocabulary small.
+    ///
+    /// # Arguments
+    /// * `min_count` - The minimum number of occurrences of a word to keep.
+    pub fn retain_min_count(&mut self, min_count: u64) {
+        self.counts.retain(|_, &mut count| count >= min_count);
+        self.total = self.counts.values().sum();
+    }
+
+    /// Returns the number of occurrences of a word.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    #[must_use]
+    pub fn count(&self, word: &str) -> u64 {
+        self.counts.get(word).copied().unwrap_or(0)
+    }
+
+    /// Returns the total number of word occurrences.
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns the number of distinct words.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns `true` if the model has no words.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the natural logarithm of the probability of a word.
+    ///
+    /// # Arguments
+    /// * `word` - The word.
+    ///
+    /// # Returns
+    /// The smoothed log probability of a known word, or that of an unseen word times the
+    /// number of characters for an unknown word.
+    #[must_use]
+    pub fn log_prob(&self, word: &str) -> f64 {
+        let denominator = (self.total + self.counts.len() as u64).max(1) as f64;
+        match self.counts.get(word) {
+            Some(&count) => ((count + 1) as f64 / denominator).ln(),
+            None => -denominator.ln() * word.chars().count() as f64,
+        }
+    }
+
+    /// Returns the log likelihood of a sequence of words: the sum of their log probabilities.
+    ///
+    /// # Arguments
+    /// * `words` - The words.
+    #[must_use]
+    pub fn log_likelihood<I>(&self, words: I) -> f64
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        words.into_iter().map(|word| self.log_prob(word.as_ref())).sum()
+    }
+}
+
+/// Rescores the N-best segmentations of a [`Segmenter`] with a [`UnigramModel`].
+#[derive(Debug, Clone)]
+pub struct UnigramReranker {
+    model: UnigramModel,
+    weight: f64,
+    beam: usize,
+}
+
+impl UnigramReranker {
+    /// Creates a reranker that weighs the log likelihood of the words by 0.1 against the
+    /// classifier margin and considers the 8 best segmentations.
+    ///
+    /// # Arguments
+    /// * `model` - The word frequencies.
+    ///
+    /// # Returns
+    /// A new [`UnigramReranker`].
+    #[must_use]
+    pub fn new(model: UnigramModel) -> Self {
+        UnigramReranker {
+            model,
+            weight: 0.1,
+            beam: 8,
+        }
+    }
+
+    /// Sets the weight of the word log likelihood. Boundary margins are usually below 1,
+    /// so weights well below 1 let the model break close calls only; 0 keeps the
+    /// classifier ranking.
+    ///
+    /// # Arguments
+    /// * `weight` - The weight of the log likelihood.
+    ///
+    /// # Returns
+    /// The reranker with the new weight.
+    #[must_use]
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets the number of segmentations that are rescored.
+    ///
+    /// # Arguments
+    /// * `beam` - The number of segmentations, at least 1.
+    ///
+    /// # Returns
+    /// The reranker with the new beam width.
+    #[must_use]
+    pub fn with_beam(mut self, beam: usize) -> Self {
+        self.beam = beam.max(1);
+        self
+    }
+
+    /// Returns the word frequencies.
+    #[must_use]
+    pub fn model(&self) -> &UnigramModel {
+        &self.model
+    }
+
+    /// Returns the combined score of a segmentation: the classifier margin plus the
+    /// weighted log likelihood of its words.
+    ///
+    /// # Arguments
+    /// * `segmentation` - The segmentation to score.
+    #[must_use]
+    pub fn score(&self, segmentation: &Segmentation) -> f64 {
+        let words = segmentation.words.iter().map(|w| w.surface.as_str());
+        segmentation.score + self.weight * self.model.log_likelihood(words)
+    }
+
+    /// Sorts segmentations by their combined [`score`](Self::score), best first.
+    ///
+    /// # Arguments
+    /// * `candidates` - The segmentations, e.g. from [`Segmenter::segment_nbest`].
+    ///
+    /// # Returns
+    /// The segmentations in their new order.
+    #[must_use]
+    pub fn rerank(&self, candidates: Vec<Segmentation>) -> Vec<Segmentation> {
+        let mut scored: Vec<(f64, Segmentation)> =
+            candidates.into_iter().map(|c| (self.score(&c), c)).collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// Segments a sentence, keeping the best of its N-best segmentations after rescoring.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter that proposes the segmentations.
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The words of the best segmentation, as [`ScoredWord`]s.
+    #[must_use]
+    pub fn segment(&self, segmenter: &Segmenter, sentence: &str) -> Vec<ScoredWord> {
+        self.rerank(segmenter.segment_nbest(sentence, self.beam))
+            .into_iter()
+            .next()
+            .map(|best| best.words)
+            .unwrap_or_default()
+    }
+
+    /// Segments a sentence into [`Token`]s, as [`segment`](Self::segment) does.
+    ///
+    /// # Arguments
+    /// * `segmenter` - The segmenter that proposes the segmentations.
+    /// * `sentence` - The sentence to segment.
+    ///
+    /// # Returns
+    /// The tokens of the best segmentation, with their byte and character offsets and
+    /// without annotations.
+    #[must_use]
+    pub fn tokenize(&self, segmenter: &Segmenter, sentence: &str) -> Vec<Token> {
+        let mut tokens: Vec<Token> =
+            self.segment(segmenter, sentence).into_iter().map(Token::from).collect();
+        set_char_offsets(&mut tokens, sentence);
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::NamedTempFile;
+
+    use crate::test_util::rwcp_segmenter;
+
+    #[test]
+    fn test_from_corpus() {
+        let corpus = "これ は テスト です 。\nテスト です\n\n";
+        let model = UnigramModel::from_corpus(corpus.as_bytes(), None).unwrap();
+        assert_eq!(model.len(), 5);
+        assert_eq!(model.total(), 7);
+        assert_eq!(model.count("テスト"), 2);
+        assert_eq!(model.count("テス"), 0);
+
+        let stopwords = StopWords::japanese();
+        let model = UnigramModel::from_corpus(corpus.as_bytes(), Some(&stopwords)).unwrap();
+        assert_eq!(model.count("は"), 0);
+        assert_eq!(model.count("です"), 0);
+        assert_eq!(model.total(), 3);
+    }
+
+    #[test]
+    fn test_tsv_roundtrip() {
+        let model = UnigramModel::from_corpus("a b b c c c\n".as_bytes(), None).unwrap();
+        let mut out = Vec::new();
+        model.write_tsv(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "c\t3\nb\t2\na\t1\n");
+
+        let file = NamedTempFile::new().unwrap();
+        model.save(file.path()).unwrap();
+        let loaded = UnigramModel::load(file.path()).unwrap();
+        assert_eq!(loaded.count("c"), 3);
+        assert_eq!(loaded.total(), 6);
+    }
+
+    #[test]
+    fn test_from_tsv_invalid() {
+        let err = UnigramModel::from_tsv("# word\tcount\na\t1\nb\tmany\n".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 3"));
+    }
+
+    #[test]
+    fn test_retain_min_count() {
+        let mut model = UnigramModel::from_corpus("a b b c c c\n".as_bytes(), None).unwrap();
+        model.retain_min_count(2);
+        assert_eq!(model.len(), 2);
+        assert_eq!(model.total(), 5);
+        assert_eq!(model.count("a"), 0);
+    }
+
+    #[test]
+    fn test_log_prob() {
+        let model = UnigramModel::from_corpus("a b b\n".as_bytes(), None).unwrap();
+        // (count + 1) / (total + vocabulary) = (2 + 1) / (3 + 2)
+        assert!((model.log_prob("b") - (3.0f64 / 5.0).ln()).abs() < 1e-12);
+        // Unknown words are as likely as one unseen word per character.
+        assert!((model.log_prob("xyz") - 3.0 * (1.0f64 / 5.0).ln()).abs() < 1e-12);
+        assert!((model.log_likelihood(["x", "y", "z"]) - model.log_prob("xyz")).abs() < 1e-12);
+        assert!(UnigramModel::new().log_prob("a").is_finite());
+    }
+
+    #[test]
+    fn test_rerank() {
+        let segmenter = rwcp_segmenter();
+        let sentence = "外国人参政権";
+        let candidates = segmenter.segment_nbest(sentence, 8);
+        assert_eq!(candidates[0].surfaces(), vec!["外国", "人参", "政権"]);
+
+        let corpus = "外国人 参政権 を 考える\n外国人 の 参政権\n";
+        let model = UnigramModel::from_corpus(corpus.as_bytes(), None).unwrap();
+        let reranker = UnigramReranker::new(model);
+        let best = reranker.segment(&segmenter, sentence);
+        let surfaces: Vec<&str> = best.iter().map(|w| w.surface.as_str()).collect();
+        assert_eq!(surfaces, vec!["外国人", "参政権"]);
+        assert_eq!(best[1].start, "外国人".len());
+
+        // Without weight, the classifier ranking is kept.
+        let reranked = reranker.clone().with_weight(0.0).rerank(candidates.clone());
+        assert_eq!(reranked, candidates);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let segmenter = rwcp_segmenter();
+        let reranker = UnigramReranker::new(UnigramModel::new()).with_beam(0);
+        let sentence = "これはテストです。";
+        assert_eq!(reranker.tokenize(&segmenter, sentence), segmenter.tokenize(sentence));
+        assert!(reranker.tokenize(&segmenter, "").is_empty());
+    }
+}
@@ -1,5 +1,9 @@
-use std::fmt;
-use std::str::FromStr;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+use sha2::{Digest, Sha256};
 
 /// URI scheme for loading models.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -41,6 +45,171 @@ impl FromStr for ModelScheme {
     }
 }
 
+/// Computes the SHA-256 digest of data as a lowercase hexadecimal string.
+///
+/// # Arguments
+/// * `data` - The data to hash.
+#[must_use]
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Returns the number of threads to split `len` items across: one per chunk of at least
+/// `min_chunk_len` items, and at most `max_threads`, or the available parallelism if
+/// `max_threads` is 0.
+#[cfg(feature = "std")]
+fn thread_count(len: usize, min_chunk_len: usize, max_threads: usize) -> usize {
+    let max_threads = match max_threads {
+        0 => std::thread::available_parallelism().map_or(1, core::num::NonZeroUsize::get),
+        n => n,
+    };
+    max_threads.min(len.div_ceil(min_chunk_len.max(1)))
+}
+
+/// Splits `0..len` into contiguous ranges of at least `min_chunk_len` items, one per
+/// available thread at most, applies `f` to each range on its own thread and returns the
+/// results in order. Runs on the calling thread if there is a single range.
+#[cfg(feature = "std")]
+pub(crate) fn map_chunks<T, F>(len: usize, min_chunk_len: usize, f: F) -> alloc::vec::Vec<T>
+where
+    T: Send,
+    F: Fn(core::ops::Range<usize>) -> T + Sync,
+{
+    map_chunks_limited(len, min_chunk_len, 0, f)
+}
+
+/// Like [`map_chunks`], but uses at most `max_threads` threads, or the available
+/// parallelism if `max_threads` is 0.
+#[cfg(feature = "std")]
+pub(crate) fn map_chunks_limited<T, F>(
+    len: usize,
+    min_chunk_len: usize,
+    max_threads: usize,
+    f: F,
+) -> alloc::vec::Vec<T>
+where
+    T: Send,
+    F: Fn(core::ops::Range<usize>) -> T + Sync,
+{
+    let threads = thread_count(len, min_chunk_len, max_threads);
+    if threads <= 1 {
+        return alloc::vec![f(0..len)];
+    }
+    let chunk_len = len.div_ceil(threads);
+    std::thread::scope(|scope| {
+        let handles: alloc::vec::Vec<_> = (0..len)
+            .step_by(chunk_len)
+            .map(|start| {
+                let f = &f;
+                scope.spawn(move || f(start..(start + chunk_len).min(len)))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    })
+}
+
+/// Splits `items` into contiguous chunks as [`map_chunks_limited`] does and applies `f` to
+/// the offset of each chunk in `items` and the chunk, each on its own thread.
+#[cfg(feature = "train")]
+pub(crate) fn for_each_chunk_mut<T, F>(
+    items: &mut [T],
+    min_chunk_len: usize,
+    max_threads: usize,
+    f: F,
+) where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    let threads = thread_count(items.len(), min_chunk_len, max_threads);
+    if threads <= 1 {
+        f(0, items);
+        return;
+    }
+    let chunk_len = items.len().div_ceil(threads);
+    std::thread::scope(|scope| {
+        for (n, chunk) in items.chunks_mut(chunk_len).enumerate() {
+            let f = &f;
+            scope.spawn(move || f(n * chunk_len, chunk));
+        }
+    });
+}
+
+/// SplitMix64, a small seeded generator for reproducible sampling.
+#[cfg(feature = "std")]
+pub(crate) struct SplitMix64(pub(crate) u64);
+
+#[cfg(feature = "std")]
+impl SplitMix64 {
+    const GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(Self::GAMMA);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`. The modulo bias is negligible for corpus sizes.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Returns the number that the `index`-th call of [`next_u64`](Self::next_u64) on a
+    /// generator seeded with `seed` returns, without generating the ones before, so
+    /// that threads can draw from one sequence independently.
+    #[cfg(feature = "train")]
+    pub(crate) fn nth(seed: u64, index: u64) -> u64 {
+        SplitMix64(seed.wrapping_add(index.wrapping_mul(Self::GAMMA))).next_u64()
+    }
+}
+
+/// Downloads the body of a URL over HTTP or HTTPS.
+///
+/// # Arguments
+/// * `url` - The URL to download.
+///
+/// # Returns
+/// The response body.
+///
+/// # Errors
+/// Returns an error if the request fails or the server does not respond with a success status.
+#[cfg(feature = "remote_model")]
+pub(crate) async fn download(url: &str) -> std::io::Result<Vec<u8>> {
+    use reqwest::Client;
+
+    // Create HTTP client with a custom user agent
+    let client = Client::builder()
+        .user_agent(format!("Litsea/{}", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| std::io::Error::other(format!("Failed to create HTTP client: {}", e)))?;
+
+    // Send GET request to the URL
+    let resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| std::io::Error::other(format!("Failed to download model: {}", e)))?;
+
+    // Check if the response status is successful
+    if !resp.status().is_success() {
+        return Err(std::io::Error::other(format!(
+            "Failed to download model: HTTP {}",
+            resp.status()
+        )));
+    }
+
+    // Read the response body
+    let content = resp
+        .bytes()
+        .await
+        .map_err(|e| std::io::Error::other(format!("Failed to read model content: {}", e)))?;
+    Ok(content.to_vec())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -66,6 +235,25 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_map_chunks() {
+        let sums = map_chunks(10_000, 100, |range| range.sum::<usize>());
+        assert!(!sums.is_empty());
+        assert_eq!(sums.iter().sum::<usize>(), (0..10_000).sum::<usize>());
+        // Too few items for a second chunk.
+        assert_eq!(map_chunks(5, 100, |range| range.len()), alloc::vec![5]);
+        assert_eq!(map_chunks(0, 100, |range| range.len()), alloc::vec![0]);
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn test_display() {
         assert_eq!(format!("{}", ModelScheme::Http), "http");
@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use crate::adaboost::AdaBoost;
+use crate::error::LitseaError;
+use crate::kytea::{expand_types, template};
+
+/// Header written before the encoded model by Vaporetto 0.6.
+const MODEL_MAGIC: &[u8] = b"VaporettoTokenizer 0.6.0\n";
+
+/// Window size of Litsea's character and type n-gram features.
+const WINDOW_SIZE: u8 = 3;
+
+/// Largest absolute weight after quantizing a model for export.
+const QUANTIZE_MAX: f64 = (1 << 20) as f64;
+
+/// Creates a [`LitseaError::ModelFormat`] error for malformed Vaporetto models.
+fn invalid_model(message: impl Into<String>) -> LitseaError {
+    LitseaError::ModelFormat(message.into())
+}
+
+/// Maps a Litsea Japanese character type to Vaporetto's type code.
+///
+/// Kanji numerals (`M`) and punctuation (`P`) have no Vaporetto type of their own,
+/// so n-grams containing them are not exported.
+fn vaporetto_type(litsea: char) -> Option<u8> {
+    match litsea {
+        'H' => Some(b'K'),
+        'I' => Some(b'H'),
+        'K' => Some(b'T'),
+        'A' => Some(b'R'),
+        'N' => Some(b'D'),
+        'O' => Some(b'O'),
+        _ => None,
+    }
+}
+
+/// Decoder for the bincode 2 "standard" encoding used by Vaporetto model files.
+struct Decoder<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, n: usize) -> crate::error::Result<&'a [u8]> {
+        if self.data.len() < n {
+            return Err(invalid_model("Unexpected end of Vaporetto model"));
+        }
+        let (head, tail) = self.data.split_at(n);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> crate::error::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> crate::error::Result<u64> {
+        let value = match self.u8()? {
+            b @ 0..=250 => b as u64,
+            251 => u16::from_le_bytes(self.take(2)?.try_into().unwrap_or_default()) as u64,
+            252 => u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()) as u64,
+            253 => u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default()),
+            _ => return Err(invalid_model("Unsupported integer encoding in Vaporetto model")),
+        };
+        Ok(value)
+    }
+
+    fn len(&mut self) -> crate::error::Result<usize> {
+        let len = self.varint()? as usize;
+        // Every element takes at least one byte, which bounds allocations on corrupt input.
+        if len > self.data.len() {
+            return Err(invalid_model("Invalid length in Vaporetto model"));
+        }
+        Ok(len)
+    }
+
+    fn i32(&mut self) -> crate::error::Result<i32> {
+        let v = self.varint()?;
+        let v = u32::try_from(v).map_err(|_| invalid_model("Weight out of range"))?;
+        Ok(((v >> 1) as i32) ^ -((v & 1) as i32))
+    }
+
+    fn bytes(&mut self) -> crate::error::Result<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn string(&mut self) -> crate::error::Result<&'a str> {
+        std::str::from_utf8(self.bytes()?).map_err(|e| invalid_model(e.to_string()))
+    }
+
+    fn weights(&mut self) -> crate::error::Result<Vec<i32>> {
+        (0..self.len()?).map(|_| self.i32()).collect()
+    }
+}
+
+/// Encoder for the bincode 2 "standard" encoding used by Vaporetto model files.
+struct Encoder<W> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    fn varint(&mut self, v: u64) -> io::Result<()> {
+        if v < 251 {
+            self.writer.write_all(&[v as u8])
+        } else if let Ok(v) = u16::try_from(v) {
+            self.writer.write_all(&[251])?;
+            self.writer.write_all(&v.to_le_bytes())
+        } else if let Ok(v) = u32::try_from(v) {
+            self.writer.write_all(&[252])?;
+            self.writer.write_all(&v.to_le_bytes())
+        } else {
+            self.writer.write_all(&[253])?;
+            self.writer.write_all(&v.to_le_bytes())
+        }
+    }
+
+    fn i32(&mut self, v: i32) -> io::Result<()> {
+        self.varint(((v << 1) ^ (v >> 31)) as u32 as u64)
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> io::Result<()> {
+        self.varint(b.len() as u64)?;
+        self.writer.write_all(b)
+    }
+
+    fn weights(&mut self, weights: &[i32]) -> io::Result<()> {
+        self.varint(weights.len() as u64)?;
+        weights.iter().try_for_each(|&w| self.i32(w))
+    }
+}
+
+/// Converts the weights of one Vaporetto n-gram into Litsea features.
+///
+/// `weights[j]` applies when the n-gram starts `j - window_size` characters from the
+/// character right after the boundary.
+fn convert_ngram(
+    kind: char,
+    ngram: &str,
+    weights: &[i32],
+    window_size: u8,
+    out: &mut Vec<(String, f64)>,
+) -> usize {
+    let len = ngram.chars().count();
+    let keys = if kind == 'T' { expand_types(ngram) } else { Some(vec![ngram.to_string()]) };
+    let mut skipped = 0;
+    for (j, &w) in weights.iter().enumerate() {
+        if w == 0 {
+            continue;
+        }
+        let offset = j as i32 - window_size as i32;
+        match (template(kind, offset, len), &keys) {
+            (Some(template), Some(keys)) => {
+                out.extend(keys.iter().map(|k| (format!("{}:{}", template, k), w as f64)))
+            }
+            _ => skipped += 1,
+        }
+    }
+    skipped
+}
+
+/// Imports the word segmentation weights of a Vaporetto model.
+///
+/// The reader expects an uncompressed Vaporetto 0.6 model, as produced by decompressing
+/// a published `.model.zst` file with `zstd -d`. Character n-gram weights become `UW`
+/// and `BW` features and character type n-gram weights become `UC`, `BC` and `TC`
+/// features, wherever the n-gram position falls within Litsea's six-character window.
+/// Dictionary weights, character trigrams, and positions outside the window are
+/// skipped. Type codes are mapped to the Japanese character types, so the result should
+/// be used with [`Language::Japanese`](crate::language::Language::Japanese).
+///
+/// # Arguments
+/// * `reader` - A reader over the uncompressed model.
+///
+/// # Returns
+/// Returns the imported model and the number of skipped weights.
+///
+/// # Errors
+/// Returns [`LitseaError::ModelFormat`] if the model cannot be decoded or has no
+/// convertible weights, or an error if it cannot be read.
+pub fn import_model<R: Read>(mut reader: R) -> crate::error::Result<(AdaBoost, usize)> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    let data = data.strip_prefix(MODEL_MAGIC).ok_or_else(|| {
+        invalid_model("Not a Vaporetto 0.6 model (decompress .zst files with `zstd -d` first)")
+    })?;
+    let mut decoder = Decoder { data };
+
+    let mut char_ngrams = Vec::new();
+    for _ in 0..decoder.len()? {
+        char_ngrams.push((decoder.string()?, decoder.weights()?));
+    }
+    let mut type_ngrams = Vec::new();
+    for _ in 0..decoder.len()? {
+        let ngram =
+            std::str::from_utf8(decoder.bytes()?).map_err(|e| invalid_model(e.to_string()))?;
+        type_ngrams.push((ngram, decoder.weights()?));
+    }
+    let mut skipped = 0;
+    for _ in 0..decoder.len()? {
+        // word, weights, comment
+        decoder.string()?;
+        skipped += decoder.weights()?.len();
+        decoder.string()?;
+    }
+    let bias = decoder.i32()?;
+    let char_window_size = decoder.u8()?;
+    let type_window_size = decoder.u8()?;
+    // Tag models follow; they are not used for word segmentation.
+
+    let mut weights = Vec::new();
+    for (ngram, w) in &char_ngrams {
+        skipped += convert_ngram('X', ngram, w, char_window_size, &mut weights);
+    }
+    for (ngram, w) in &type_ngrams {
+        skipped += convert_ngram('T', ngram, w, type_window_size, &mut weights);
+    }
+    if weights.is_empty() {
+        return Err(invalid_model("No convertible weights found in Vaporetto model"));
+    }
+
+    let mut learner = AdaBoost::new(0.01, 100);
+    learner.set_weights(weights, bias as f64);
+    Ok((learner, skipped))
+}
+
+/// Splits a Litsea feature into its Vaporetto n-gram kind, start offset and n-gram.
+fn parse_feature(feature: &str) -> Option<(char, i32, &str)> {
+    let (name, ngram) = feature.split_once(':')?;
+    let (kind, first) = match &name[..2.min(name.len())] {
+        "UW" => ('X', -3),
+        "BW" => ('X', -2),
+        "UC" => ('T', -3),
+        "BC" => ('T', -2),
+        "TC" => ('T', -3),
+        _ => return None,
+    };
+    let index: i32 = name[2..].parse().ok()?;
+    Some((kind, first + index - 1, ngram))
+}
+
+/// Exports a model in Vaporetto's model format.
+///
+/// Weights are quantized to integers, since Vaporetto stores integer weights; this
+/// preserves the segmentation result up to rounding. Only `UW`, `BW`, `UC`, `BC` and
+/// `TC` features have a Vaporetto counterpart. Type n-grams containing kanji numerals
+/// (`M`) or punctuation (`P`) are skipped, since Vaporetto classifies those characters
+/// as kanji and other. The output is uncompressed; compress it with `zstd` to use it
+/// where Vaporetto expects a `.model.zst` file.
+///
+/// # Arguments
+/// * `learner` - The model to export.
+/// * `writer` - The destination of the model.
+///
+/// # Returns
+/// Returns the number of skipped features.
+///
+/// # Errors
+/// Returns an error if writing fails.
+pub fn export_model<W: Write>(learner: &AdaBoost, writer: W) -> crate::error::Result<usize> {
+    let max_abs = learner
+        .weights()
+        .map(|(_, w)| w.abs())
+        .chain(std::iter::once(learner.get_bias().abs()))
+        .fold(0.0, f64::max);
+    let scale = if max_abs > 0.0 { QUANTIZE_MAX / max_abs } else { 1.0 };
+    let quantize = |w: f64| (w * scale).round() as i32;
+
+    let mut char_ngrams: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    let mut type_ngrams: BTreeMap<Vec<u8>, Vec<i32>> = BTreeMap::new();
+    let mut skipped = 0;
+
+    for (feature, w) in learner.weights() {
+        let Some((kind, offset, ngram)) = parse_feature(feature) else {
+            skipped += 1;
+            continue;
+        };
+        let len = ngram.chars().count();
+        if template(kind, offset, len).is_none() {
+            skipped += 1;
+            continue;
+        }
+        let slots = 2 * WINDOW_SIZE as usize + 1 - len;
+        let j = (offset + WINDOW_SIZE as i32) as usize;
+        if kind == 'X' {
+            char_ngrams.entry(ngram.to_string()).or_insert_with(|| vec![0; slots])[j] +=
+                quantize(w);
+        } else {
+            let Some(types) = ngram.chars().map(vaporetto_type).collect::<Option<Vec<u8>>>() else {
+                skipped += 1;
+                continue;
+            };
+            type_ngrams.entry(types).or_insert_with(|| vec![0; slots])[j] += quantize(w);
+        }
+    }
+
+    let mut writer = writer;
+    writer.write_all(MODEL_MAGIC)?;
+    let mut encoder = Encoder { writer };
+    encoder.varint(char_ngrams.len() as u64)?;
+    for (ngram, weights) in &char_ngrams {
+        encoder.bytes(ngram.as_bytes())?;
+        encoder.weights(weights)?;
+    }
+    encoder.varint(type_ngrams.len() as u64)?;
+    for (ngram, weights) in &type_ngrams {
+        encoder.bytes(ngram)?;
+        encoder.weights(weights)?;
+    }
+    encoder.varint(0)?; // dictionary
+    encoder.i32(quantize(learner.get_bias()))?;
+    encoder.writer.write_all(&[WINDOW_SIZE, WINDOW_SIZE])?;
+    encoder.varint(0)?; // tag models
+    Ok(skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_roundtrip() {
+        let mut buf = Vec::new();
+        let mut encoder = Encoder { writer: &mut buf };
+        for v in [0, 250, -1, 1000, -70000, i32::MAX, i32::MIN] {
+            encoder.i32(v).unwrap();
+        }
+        encoder.varint(300).unwrap();
+
+        let mut decoder = Decoder { data: &buf };
+        for v in [0, 250, -1, 1000, -70000, i32::MAX, i32::MIN] {
+            assert_eq!(decoder.i32().unwrap(), v);
+        }
+        assert_eq!(decoder.varint().unwrap(), 300);
+        assert!(decoder.data.is_empty());
+    }
+
+    #[test]
+    fn test_parse_feature() {
+        assert_eq!(parse_feature("UW1:a"), Some(('X', -3, "a")));
+        assert_eq!(parse_feature("BW3:ab"), Some(('X', 0, "ab")));
+        assert_eq!(parse_feature("TC4:IIH"), Some(('T', 0, "IIH")));
+        assert_eq!(parse_feature("UP1:B"), None);
+        assert_eq!(parse_feature("WC1:aI"), None);
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() {
+        let mut learner = AdaBoost::new(0.01, 100);
+        learner.set_weights(
+            vec![
+                ("UW4:は".to_string(), 0.5),
+                ("UW3:は".to_string(), 0.25),
+                ("BW2:です".to_string(), -1.0),
+                ("TC1:HII".to_string(), 0.125),
+                ("BC2:MI".to_string(), 0.75),
+                ("UP1:B".to_string(), 0.5),
+            ],
+            -0.5,
+        );
+        let mut buf = Vec::new();
+        let skipped = export_model(&learner, &mut buf).unwrap();
+        // BC2:MI has no Vaporetto type and UP1 no Vaporetto template.
+        assert_eq!(skipped, 2);
+        assert!(buf.starts_with(MODEL_MAGIC));
+
+        let (imported, skipped) = import_model(buf.as_slice()).unwrap();
+        assert_eq!(skipped, 0);
+        let weights: BTreeMap<&str, f64> = imported.weights().collect();
+        let scale = QUANTIZE_MAX;
+        assert_eq!(weights["UW4:は"], 0.5 * scale);
+        assert_eq!(weights["UW3:は"], 0.25 * scale);
+        assert_eq!(weights["BW2:です"], -scale);
+        // Vaporetto's kanji type covers both Litsea kanji and kanji numerals.
+        assert_eq!(weights["TC1:HII"], 0.125 * scale);
+        assert_eq!(weights["TC1:MII"], 0.125 * scale);
+        assert_eq!(weights.len(), 5);
+        assert!((imported.get_bias() + 0.5 * scale).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_import_model_invalid() {
+        let err = import_model("not a model".as_bytes()).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+        let mut truncated = MODEL_MAGIC.to_vec();
+        truncated.push(5);
+        let err = import_model(truncated.as_slice()).unwrap_err();
+        assert!(matches!(err, LitseaError::ModelFormat(_)));
+    }
+}
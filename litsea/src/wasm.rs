@@ -0,0 +1,64 @@
+//! WebAssembly bindings for JavaScript, enabled by the `wasm` feature.
+//!
+//! The bindings segment text in the browser or in Node.js with a model passed as a
+//! string, e.g. fetched by the page, so they need no file system. The feature does not
+//! imply `std`, and the smallest build leaves out everything else:
+//!
+//! ```sh
+//! cargo rustc -p litsea --lib --release --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/litsea.wasm
+//! ```
+//!
+//! ```js
+//! import init, { Segmenter } from "./pkg/litsea.js";
+//!
+//! await init();
+//! const model = await (await fetch("japanese.model")).text();
+//! const segmenter = new Segmenter("japanese", model);
+//! console.log(segmenter.segment("これはテストです。"));
+//! segmenter.free();
+//! ```
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::language::Language;
+
+/// A segmenter for JavaScript, exported as the class `Segmenter`.
+#[wasm_bindgen(js_name = Segmenter)]
+pub struct WasmSegmenter {
+    inner: crate::segmenter::Segmenter,
+}
+
+#[wasm_bindgen(js_class = Segmenter)]
+impl WasmSegmenter {
+    /// `new Segmenter(language, model)`
+    ///
+    /// # Arguments
+    /// * `language` - The language of the model, e.g. `"japanese"`.
+    /// * `model` - The content of a model file.
+    ///
+    /// # Errors
+    /// Throws if the language is unknown or the model cannot be loaded.
+    #[wasm_bindgen(constructor)]
+    pub fn new(language: &str, model: &str) -> Result<WasmSegmenter, JsError> {
+        let language: Language = language.parse().map_err(|e: String| JsError::new(&e))?;
+        let inner = crate::segmenter::Segmenter::from_model_str(language, model)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(WasmSegmenter { inner })
+    }
+
+    /// `segment(text): string[]`
+    ///
+    /// # Arguments
+    /// * `text` - The text to segment.
+    ///
+    /// # Returns
+    /// The words of the text.
+    pub fn segment(&self, text: &str) -> Vec<String> {
+        self.inner.segment(text)
+    }
+}